@@ -8,6 +8,8 @@ apt-get update && apt-get install --assume-yes \
 	libgtk-4-dev \
 	libinput-dev \
 	libpulse-dev \
+	libpipewire-0.3-dev \
+	libclang-dev \
 	libluajit-5.1-dev \
 	libgtk4-layer-shell-dev
 
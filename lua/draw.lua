@@ -1,4 +1,4 @@
-function(draw_function, ptr, width, height)
+function(draw_function, ptr, width, height, style)
     local cr = __lgi_core.record.new(cairo.Context, ptr)
-    draw_function(cr, width, height)
+    draw_function(cr, width, height, style)
 end
\ No newline at end of file
@@ -0,0 +1,154 @@
+use glib::ControlFlow;
+use gtk::prelude::*;
+use gtk::{Revealer, RevealerTransitionType, TickCallbackId, Widget};
+use std::cell::{Cell, RefCell};
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+use std::time::Duration;
+
+/// CSS class set on a module's widgets when it has `disable_animations` set.
+/// Animations are skipped for any widget inside one with this class.
+pub const DISABLED_CLASS: &str = "no-animations";
+
+/// The default length of value animations, such as progress bars filling.
+pub const VALUE_DURATION: Duration = Duration::from_millis(200);
+
+/// Checks whether a widget should be animated.
+///
+/// This is false if animations are disabled globally,
+/// or the widget is inside a module with animations disabled.
+pub fn enabled(widget: &impl IsA<Widget>) -> bool {
+    let widget = widget.as_ref();
+
+    widget.settings().is_gtk_enable_animations()
+        && !std::iter::successors(Some(widget.clone()), Widget::parent)
+            .any(|widget| widget.has_css_class(DISABLED_CLASS))
+}
+
+/// Eases an animation's progress from `0.0` to `1.0`,
+/// starting quickly and slowing towards the end.
+fn ease_out_cubic(t: f64) -> f64 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+/// A numeric widget property which smoothly animates
+/// towards each new value it is set to,
+/// driven by the widget's frame clock.
+///
+/// Where animations are disabled or the widget is not mapped,
+/// the value is set immediately.
+#[derive(Clone)]
+pub struct AnimatedValue {
+    widget: Widget,
+    duration: Duration,
+    get: Rc<dyn Fn() -> f64>,
+    set: Rc<dyn Fn(f64)>,
+    tick_id: Rc<RefCell<Option<TickCallbackId>>>,
+}
+
+impl Debug for AnimatedValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AnimatedValue")
+            .field("widget", &self.widget)
+            .field("duration", &self.duration)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AnimatedValue {
+    /// Creates a new animated value,
+    /// read and written using `get` and `set`.
+    pub fn new(
+        widget: &impl IsA<Widget>,
+        duration: Duration,
+        get: impl Fn() -> f64 + 'static,
+        set: impl Fn(f64) + 'static,
+    ) -> Self {
+        Self {
+            widget: widget.as_ref().clone(),
+            duration,
+            get: Rc::new(get),
+            set: Rc::new(set),
+            tick_id: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    /// Animates from the current value to `target`,
+    /// replacing any animation already running.
+    pub fn set(&self, target: f64) {
+        self.cancel();
+
+        let from = (self.get)();
+
+        if self.duration.is_zero()
+            || !self.widget.is_mapped()
+            || !enabled(&self.widget)
+            || (target - from).abs() < f64::EPSILON
+        {
+            (self.set)(target);
+            return;
+        }
+
+        let set = self.set.clone();
+        let tick_id = self.tick_id.clone();
+        let duration = self.duration.as_micros() as f64;
+        let start = Cell::new(None);
+
+        let id = self.widget.add_tick_callback(move |_, clock| {
+            let now = clock.frame_time();
+            let start = start.get().unwrap_or_else(|| {
+                start.set(Some(now));
+                now
+            });
+
+            let t = ((now - start) as f64 / duration).min(1.0);
+            set(from + (target - from) * ease_out_cubic(t));
+
+            if t < 1.0 {
+                ControlFlow::Continue
+            } else {
+                // callback is removed once complete, so must not be removed again
+                tick_id.take();
+                ControlFlow::Break
+            }
+        });
+
+        self.tick_id.replace(Some(id));
+    }
+
+    /// Stops any running animation, leaving the value where it is.
+    pub fn cancel(&self) {
+        if let Some(id) = self.tick_id.take() {
+            id.remove();
+        }
+    }
+}
+
+/// Hides the child of a revealer using its transition,
+/// then calls `f` once it is fully hidden.
+///
+/// Where animations are disabled, the revealer is not mapped
+/// or the child is already hidden, `f` is called immediately.
+pub fn conceal_then(revealer: &Revealer, f: impl FnOnce() + 'static) {
+    if !revealer.is_child_revealed()
+        || !revealer.is_mapped()
+        || revealer.transition_type() == RevealerTransitionType::None
+        || revealer.transition_duration() == 0
+        || !enabled(revealer)
+    {
+        revealer.set_reveal_child(false);
+        f();
+        return;
+    }
+
+    let f = Cell::new(Some(f));
+    revealer.connect_child_revealed_notify(move |revealer| {
+        if !revealer.is_child_revealed()
+            && let Some(f) = f.take()
+        {
+            f();
+        }
+    });
+
+    revealer.set_reveal_child(false);
+}
@@ -0,0 +1,243 @@
+use crate::popup::Popup;
+use glib::SourceId;
+use gtk::prelude::*;
+use gtk::{ApplicationWindow, EventControllerMotion, GestureDrag, Revealer, Window};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LockState {
+    /// No lock held on bar visibility
+    Unlocked,
+    /// Lock held on bar visibility
+    Locked,
+    /// Lock held on bar visibility
+    /// with request to hide bar as soon as lock is cleared
+    LockedPendingClose,
+}
+
+/// Hides the bar once the pointer leaves it,
+/// and reveals it again when the pointer reaches the screen edge.
+///
+/// While hidden, a near-invisible hotspot window is placed along the edge
+/// to detect the pointer.
+///
+/// In intellihide mode, the bar is only hidden while a window overlaps it.
+#[derive(Debug)]
+pub struct Autohide {
+    window: ApplicationWindow,
+    revealer: Revealer,
+    hotspot_window: Window,
+    popup: Rc<Popup>,
+
+    /// Time to wait after the pointer leaves before hiding.
+    delay: Duration,
+    /// Whether to only hide while a window overlaps the bar.
+    intellihide: bool,
+
+    hovered: Cell<bool>,
+    overlapped: Cell<bool>,
+    lock_state: Cell<LockState>,
+    timeout_id: RefCell<Option<SourceId>>,
+}
+
+impl Autohide {
+    pub fn new(
+        window: ApplicationWindow,
+        revealer: Revealer,
+        hotspot_window: Window,
+        popup: Rc<Popup>,
+        delay: u64,
+        intellihide: bool,
+        start_hidden: bool,
+    ) -> Rc<Self> {
+        let autohide = Rc::new(Self {
+            window,
+            revealer,
+            hotspot_window,
+            popup,
+            delay: Duration::from_millis(delay),
+            intellihide,
+            hovered: Cell::new(false),
+            // so a bar starting hidden is revealed by the first update
+            overlapped: Cell::new(start_hidden),
+            lock_state: Cell::new(LockState::Unlocked),
+            timeout_id: RefCell::new(None),
+        });
+
+        autohide.install_events();
+
+        if start_hidden {
+            autohide.revealer.set_reveal_child(false);
+            autohide.hotspot_window.set_visible(true);
+        }
+
+        autohide
+    }
+
+    fn install_events(self: &Rc<Self>) {
+        let event_controller = EventControllerMotion::new();
+
+        let autohide = Rc::downgrade(self);
+        event_controller.connect_enter(move |_, _, _| {
+            if let Some(autohide) = autohide.upgrade() {
+                autohide.hovered.set(true);
+                autohide.cancel_hide();
+            }
+        });
+
+        let autohide = Rc::downgrade(self);
+        event_controller.connect_leave(move |_| {
+            if let Some(autohide) = autohide.upgrade() {
+                autohide.hovered.set(false);
+                autohide.schedule_hide();
+            }
+        });
+
+        self.window.add_controller(event_controller);
+
+        let autohide = Rc::downgrade(self);
+        self.popup.popover.connect_hide(move |_| {
+            if let Some(autohide) = autohide.upgrade() {
+                autohide.schedule_hide();
+            }
+        });
+
+        let event_controller = EventControllerMotion::new();
+
+        let autohide = Rc::downgrade(self);
+        event_controller.connect_enter(move |_, _, _| {
+            if let Some(autohide) = autohide.upgrade() {
+                autohide.reveal();
+            }
+        });
+
+        self.hotspot_window.add_controller(event_controller);
+
+        // swiping in from the screen edge on a touchscreen
+        let gesture = GestureDrag::builder().touch_only(true).build();
+
+        let autohide = Rc::downgrade(self);
+        gesture.connect_drag_begin(move |_, _, _| {
+            if let Some(autohide) = autohide.upgrade() {
+                autohide.reveal();
+            }
+        });
+
+        self.hotspot_window.add_controller(gesture);
+
+        // only unmap the window once the hide animation has finished
+        let autohide = Rc::downgrade(self);
+        self.revealer
+            .connect_child_revealed_notify(move |revealer| {
+                if !revealer.reveals_child()
+                    && !revealer.is_child_revealed()
+                    && let Some(autohide) = autohide.upgrade()
+                {
+                    autohide.hide_window();
+                }
+            });
+    }
+
+    /// Shows the bar, hiding the hotspot.
+    pub fn reveal(&self) {
+        self.cancel_hide();
+
+        self.hotspot_window.set_visible(false);
+        self.window.set_visible(true);
+        self.revealer.set_reveal_child(true);
+    }
+
+    /// Hides the bar, showing the hotspot.
+    ///
+    /// If a lock is held on the bar's visibility,
+    /// it is hidden once the lock is released.
+    pub fn conceal(&self) {
+        self.cancel_hide();
+
+        if self.lock_state.get() != LockState::Unlocked {
+            self.lock_state.set(LockState::LockedPendingClose);
+            return;
+        }
+
+        self.revealer.set_reveal_child(false);
+
+        // the window is unmapped once the animation completes,
+        // unless there is no animation to wait for.
+        if !self.revealer.is_child_revealed() {
+            self.hide_window();
+        }
+    }
+
+    fn hide_window(&self) {
+        self.window.set_visible(false);
+        self.hotspot_window.set_visible(true);
+    }
+
+    /// Hides the bar after the configured delay,
+    /// so long as the pointer has not returned and no popup is open.
+    fn schedule_hide(self: &Rc<Self>) {
+        self.cancel_hide();
+
+        let autohide = Rc::downgrade(self);
+        let timeout_id = glib::timeout_add_local_once(self.delay, move || {
+            let Some(autohide) = autohide.upgrade() else {
+                return;
+            };
+
+            // source is removed once complete, so must not be removed again
+            autohide.timeout_id.take();
+
+            if autohide.can_hide() {
+                autohide.conceal();
+            }
+        });
+
+        *self.timeout_id.borrow_mut() = Some(timeout_id);
+    }
+
+    /// Whether nothing is keeping the bar visible,
+    /// such as the pointer or an open popup.
+    fn can_hide(&self) -> bool {
+        !self.hovered.get() && !self.popup.visible() && (!self.intellihide || self.overlapped.get())
+    }
+
+    /// Sets whether a window overlaps the bar.
+    ///
+    /// In intellihide mode, this hides the bar after the delay
+    /// when a window starts overlapping it,
+    /// and reveals it when no window overlaps it.
+    pub fn set_overlapped(self: &Rc<Self>, overlapped: bool) {
+        if !self.intellihide || overlapped == self.overlapped.replace(overlapped) {
+            return;
+        }
+
+        if overlapped {
+            self.schedule_hide();
+        } else {
+            self.reveal();
+        }
+    }
+
+    fn cancel_hide(&self) {
+        if let Some(timeout_id) = self.timeout_id.take() {
+            timeout_id.remove();
+        }
+    }
+
+    /// Sets whether the bar should be kept visible,
+    /// hiding it if a request to do so was made while locked.
+    pub fn set_locked(&self, locked: bool) {
+        if locked {
+            self.lock_state.set(LockState::Locked);
+        } else {
+            let pending_close = self.lock_state.get() == LockState::LockedPendingClose;
+            self.lock_state.set(LockState::Unlocked);
+
+            if pending_close {
+                self.conceal();
+            }
+        }
+    }
+}
@@ -0,0 +1,1110 @@
+mod autohide;
+
+use self::autohide::Autohide;
+use crate::Ironbar;
+use crate::animation;
+use crate::config::{
+    BarConfig, BarPosition, CenterMode, ExclusiveZone, MarginConfig, ModuleConfig,
+};
+#[cfg(feature = "toplevel")]
+use crate::dynamic_value::subscribe_fullscreen;
+use crate::modules::{
+    BarModuleFactory, ModuleInfo, ModuleLocation, ModuleRef, create_error_widget,
+};
+#[cfg(feature = "osd")]
+use crate::osd::Osd;
+use crate::popup::Popup;
+use crate::{panic_guard, poll, startup};
+use color_eyre::{Report, Result};
+use glib::SourceId;
+use gtk::gdk::{Monitor, Rectangle};
+use gtk::prelude::*;
+use gtk::{
+    Align, Application, ApplicationWindow, CenterBox, Orientation, Revealer,
+    RevealerTransitionType, TextDirection, Widget, Window,
+};
+use gtk_layer_shell::LayerShell;
+use std::cell::{Cell, OnceCell, RefCell};
+use std::panic::{self, AssertUnwindSafe};
+use std::rc::Rc;
+use std::time::Duration;
+use tracing::{debug, error, info};
+
+#[derive(Debug, Clone)]
+enum Inner {
+    New {
+        config: Option<BarConfig>,
+    },
+    Loaded {
+        module_refs: Rc<RefCell<Vec<ModuleRef>>>,
+        popup: Rc<Popup>,
+        /// Required to create modules at runtime.
+        monitor: Monitor,
+        instance: Rc<Bar>,
+        center_mode: CenterMode,
+    },
+}
+
+/// Window state saved while the bar is peeked,
+/// restored once the peek ends.
+#[derive(Debug)]
+struct PeekState {
+    visible: bool,
+    layer: gtk_layer_shell::Layer,
+    auto_exclusive_zone: bool,
+    exclusive_zone: i32,
+    timeout_id: Option<SourceId>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Bar {
+    name: String,
+    monitor_name: String,
+    position: BarPosition,
+
+    ironbar: Rc<Ironbar>,
+    /// Whether modules which fail are replaced with an error widget.
+    strict: bool,
+
+    window: ApplicationWindow,
+
+    revealer: Revealer,
+    overlay: gtk::Overlay,
+    content: CenterBox,
+
+    start: gtk::Box,
+    center: gtk::Box,
+    end: gtk::Box,
+
+    autohide: Rc<OnceCell<Rc<Autohide>>>,
+    /// Whether the bar is currently hidden
+    /// because of a fullscreen window.
+    fullscreen_hidden: Rc<Cell<bool>>,
+    /// Whether the bar is currently hidden
+    /// because its `visible_when` rule is not met.
+    rule_hidden: Rc<Cell<bool>>,
+    peek: Rc<RefCell<Option<PeekState>>>,
+    #[cfg(feature = "osd")]
+    osd: Rc<OnceCell<Osd>>,
+
+    inner: Inner,
+}
+
+impl Bar {
+    pub fn new(
+        app: &Application,
+        monitor_name: String,
+        config: BarConfig,
+        ironbar: Rc<Ironbar>,
+    ) -> Self {
+        let window = ApplicationWindow::builder().application(app).build();
+
+        let name = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("bar-{}", Ironbar::unique_id()));
+
+        window.set_widget_name(&name);
+
+        window.add_css_class(&format!("bar-{monitor_name}"));
+        window.add_css_class(&format!("bar-{}", config.position.name()));
+
+        if let Some(class) = &config.class {
+            // gtk counts classes with spaces as the same class
+            for part in class.split(' ') {
+                window.add_css_class(part);
+            }
+        }
+
+        let position = config.position;
+        let orientation = position.orientation();
+
+        let content = CenterBox::builder().orientation(orientation).name("bar");
+
+        let content = if orientation == Orientation::Horizontal {
+            content.height_request(config.height)
+        } else {
+            content.width_request(config.height)
+        }
+        .build();
+
+        content.add_css_class("container");
+        content.add_css_class(match orientation {
+            Orientation::Vertical => "vertical",
+            _ => "horizontal",
+        });
+
+        let start = create_container("start", orientation);
+        let center = create_container("center", orientation);
+        let end = create_container("end", orientation);
+
+        // positioned module groups are drawn over the main content,
+        // as is the center group in `bar` center mode
+        let overlay = gtk::Overlay::new();
+        overlay.set_child(Some(&content));
+
+        // used to animate autohide
+        let revealer = Revealer::builder()
+            .transition_type(RevealerTransitionType::None)
+            .reveal_child(true)
+            .child(&overlay)
+            .build();
+
+        window.set_child(Some(&revealer));
+
+        // polling slows down while every bar is hidden
+        window.connect_map(|_| poll::set_bar_shown(true));
+        window.connect_unmap(|_| poll::set_bar_shown(false));
+
+        Self {
+            name,
+            monitor_name,
+            position,
+            ironbar,
+            strict: config.strict,
+            window,
+            revealer,
+            overlay,
+            content,
+            start,
+            center,
+            end,
+            autohide: Rc::new(OnceCell::new()),
+            fullscreen_hidden: Rc::new(Cell::new(false)),
+            rule_hidden: Rc::new(Cell::new(false)),
+            peek: Rc::new(RefCell::new(None)),
+            #[cfg(feature = "osd")]
+            osd: Rc::new(OnceCell::new()),
+            inner: Inner::New {
+                config: Some(config),
+            },
+        }
+    }
+
+    pub fn init(mut self, monitor: &Monitor) -> Self {
+        let Inner::New { ref mut config } = self.inner else {
+            return self;
+        };
+
+        let Some(config) = config.take() else {
+            return self;
+        };
+
+        info!(
+            "Initializing bar '{}' on '{}'",
+            self.name, self.monitor_name
+        );
+
+        let intellihide = config.autohide.is_some() && config.intellihide;
+        let start_hidden = config
+            .start_hidden
+            .unwrap_or_else(|| config.autohide.is_some() && !intellihide);
+
+        self.setup_layer_shell(
+            &self.window,
+            config
+                .exclusive_zone
+                .unwrap_or(ExclusiveZone::Auto(!start_hidden && !intellihide)),
+            config.anchor_to_edges,
+            config.margin,
+            config.layer,
+            monitor,
+        );
+
+        let autohide = config.autohide;
+        let hide_on_fullscreen = config.hide_on_fullscreen;
+        let visible_when = config.visible_when.clone();
+        let hotspot_size = config.autohide_hotspot_size;
+        let anchor_to_edges = config.anchor_to_edges;
+        let margin = config.margin;
+        #[cfg(feature = "workspaces")]
+        let height = config.height;
+
+        #[cfg(feature = "osd")]
+        if let Some(osd) = config.osd.clone() {
+            self.osd
+                .set(Osd::new(monitor, osd, &self.ironbar))
+                .expect("osd should only be set up once");
+        }
+
+        self.revealer.set_transition_type(
+            config
+                .autohide_transition_type
+                .to_revealer_transition_type(self.position),
+        );
+        self.revealer
+            .set_transition_duration(config.autohide_transition_duration);
+
+        let center_mode = config.center_mode;
+
+        let instance = Rc::new(self.clone());
+        let load_result = self.load_modules(instance.clone(), config, monitor);
+
+        if let Some(delay) = autohide {
+            let hotspot_window = Window::new();
+            self.setup_hotspot(&hotspot_window, hotspot_size);
+            self.setup_layer_shell(
+                &hotspot_window,
+                ExclusiveZone::Auto(false),
+                anchor_to_edges,
+                margin,
+                gtk_layer_shell::Layer::Top,
+                monitor,
+            );
+
+            let autohide = Autohide::new(
+                self.window.clone(),
+                self.revealer.clone(),
+                hotspot_window,
+                load_result.popup.clone(),
+                delay,
+                intellihide,
+                start_hidden,
+            );
+
+            self.autohide
+                .set(autohide.clone())
+                .expect("autohide should only be set up once");
+
+            if intellihide {
+                #[cfg(feature = "workspaces")]
+                self.setup_intellihide(autohide, monitor, height, margin);
+
+                #[cfg(not(feature = "workspaces"))]
+                tracing::warn!("`intellihide` requires the `workspaces` feature");
+            }
+        }
+
+        self.show(!start_hidden);
+
+        if hide_on_fullscreen {
+            #[cfg(feature = "toplevel")]
+            self.setup_fullscreen_hide();
+
+            #[cfg(not(feature = "toplevel"))]
+            tracing::warn!("`hide_on_fullscreen` requires the `toplevel` feature");
+        }
+
+        if let Some(visible_when) = visible_when {
+            let bar = self.clone();
+            visible_when.subscribe(&self.ironbar, &self.monitor_name, move |visible| {
+                bar.set_hidden_for(&bar.rule_hidden, !visible, "`visible_when` rule");
+            });
+        }
+
+        self.inner = Inner::Loaded {
+            popup: load_result.popup,
+            module_refs: Rc::new(RefCell::new(load_result.module_refs)),
+            monitor: monitor.clone(),
+            instance,
+            center_mode,
+        };
+
+        self
+    }
+
+    /// Closes the bar, consuming it.
+    pub fn close(self) {
+        #[cfg(feature = "osd")]
+        if let Some(osd) = self.osd.get() {
+            osd.close();
+        }
+
+        self.window.close();
+        self.window.destroy();
+    }
+
+    /// Sets up GTK layer shell for a provided application window.
+    fn setup_layer_shell(
+        &self,
+        win: &impl IsA<Window>,
+        exclusive_zone: ExclusiveZone,
+        anchor_to_edges: bool,
+        margin: MarginConfig,
+        layer: gtk_layer_shell::Layer,
+        monitor: &Monitor,
+    ) {
+        use gtk_layer_shell::Edge;
+
+        let position = self.position;
+
+        win.init_layer_shell();
+        win.set_monitor(Some(monitor));
+        win.set_layer(layer);
+        win.set_namespace(Some(env!("CARGO_PKG_NAME")));
+
+        match exclusive_zone {
+            ExclusiveZone::Auto(true) => win.auto_exclusive_zone_enable(),
+            ExclusiveZone::Auto(false) => {}
+            ExclusiveZone::Pixels(size) => win.set_exclusive_zone(size),
+        }
+
+        win.set_margin(Edge::Top, margin.top);
+        win.set_margin(Edge::Bottom, margin.bottom);
+        win.set_margin(Edge::Left, margin.left);
+        win.set_margin(Edge::Right, margin.right);
+
+        let bar_orientation = position.orientation();
+
+        win.set_anchor(
+            Edge::Top,
+            position == BarPosition::Top
+                || (bar_orientation == Orientation::Vertical && anchor_to_edges),
+        );
+        win.set_anchor(
+            Edge::Bottom,
+            position == BarPosition::Bottom
+                || (bar_orientation == Orientation::Vertical && anchor_to_edges),
+        );
+        win.set_anchor(
+            Edge::Left,
+            position == BarPosition::Left
+                || (bar_orientation == Orientation::Horizontal && anchor_to_edges),
+        );
+        win.set_anchor(
+            Edge::Right,
+            position == BarPosition::Right
+                || (bar_orientation == Orientation::Horizontal && anchor_to_edges),
+        );
+    }
+
+    /// Sets up the hotspot window used to reveal the bar
+    /// when the pointer reaches the screen edge.
+    fn setup_hotspot(&self, hotspot_window: &Window, size: i32) {
+        hotspot_window.set_visible(false);
+
+        hotspot_window.set_opacity(0.01);
+        hotspot_window.set_decorated(false);
+
+        let (w, h) = match self.position {
+            BarPosition::Top | BarPosition::Bottom => (0, size),
+            BarPosition::Left | BarPosition::Right => (size, 0),
+        };
+        hotspot_window.set_default_size(w, h);
+    }
+
+    /// Hides the bar while the focused window on its monitor is fullscreen,
+    /// showing it again once the window leaves fullscreen or loses focus.
+    #[cfg(feature = "toplevel")]
+    fn setup_fullscreen_hide(&self) {
+        let bar = self.clone();
+        subscribe_fullscreen(&self.ironbar, &self.monitor_name, move |fullscreen| {
+            bar.set_hidden_for(&bar.fullscreen_hidden, fullscreen, "fullscreen window");
+        });
+    }
+
+    /// Hides the bar while a window on the active workspace overlaps it,
+    /// using the strip along the monitor edge which the bar occupies.
+    #[cfg(feature = "workspaces")]
+    fn setup_intellihide(
+        &self,
+        autohide: Rc<Autohide>,
+        monitor: &Monitor,
+        height: i32,
+        margin: MarginConfig,
+    ) {
+        use crate::channels::BroadcastReceiverExt;
+        use crate::clients::compositor::WindowRect;
+
+        let rx = self
+            .ironbar
+            .clients
+            .borrow_mut()
+            .workspaces()
+            .and_then(|client| Ok(client.subscribe_visible_windows()?));
+
+        let rx = match rx {
+            Ok(rx) => rx,
+            Err(err) => {
+                tracing::warn!("Failed to set up intellihide: {err}");
+                return;
+            }
+        };
+
+        let monitor = monitor.geometry();
+
+        rx.recv_glib(self, move |bar, windows| {
+            let Some(windows) = windows.0.get(&bar.monitor_name) else {
+                autohide.set_overlapped(false);
+                return;
+            };
+
+            let rect = match bar.position {
+                BarPosition::Top => WindowRect {
+                    x: monitor.x(),
+                    y: monitor.y(),
+                    width: monitor.width(),
+                    height: margin.top + bar.window.height().max(height),
+                },
+                BarPosition::Bottom => {
+                    let thickness = margin.bottom + bar.window.height().max(height);
+                    WindowRect {
+                        x: monitor.x(),
+                        y: monitor.y() + monitor.height() - thickness,
+                        width: monitor.width(),
+                        height: thickness,
+                    }
+                }
+                BarPosition::Left => WindowRect {
+                    x: monitor.x(),
+                    y: monitor.y(),
+                    width: margin.left + bar.window.width().max(height),
+                    height: monitor.height(),
+                },
+                BarPosition::Right => {
+                    let thickness = margin.right + bar.window.width().max(height);
+                    WindowRect {
+                        x: monitor.x() + monitor.width() - thickness,
+                        y: monitor.y(),
+                        width: thickness,
+                        height: monitor.height(),
+                    }
+                }
+            };
+
+            autohide.set_overlapped(windows.iter().any(|window| window.intersects(&rect)));
+        });
+    }
+
+    /// Hides or re-shows the bar for `reason`,
+    /// tracking whether it is hidden for that reason in `flag`.
+    ///
+    /// The bar is only re-shown if it was hidden by this,
+    /// so visibility set by other means is left alone.
+    fn set_hidden_for(&self, flag: &Cell<bool>, hidden: bool, reason: &str) {
+        if hidden == flag.get() {
+            return;
+        }
+
+        if hidden {
+            if self.visible() {
+                debug!("Hiding bar '{}' for {reason}", self.name);
+                flag.set(true);
+                self.set_visible(false);
+            }
+        } else {
+            debug!("Re-showing bar '{}' after {reason}", self.name);
+            flag.set(false);
+            self.set_visible(true);
+        }
+    }
+
+    /// Loads the configured modules onto a bar.
+    fn load_modules(
+        &self,
+        instance: Rc<Bar>,
+        config: BarConfig,
+        monitor: &Monitor,
+    ) -> BarLoadResult {
+        let app = &self.window.application().expect("to exist");
+
+        macro_rules! info {
+            ($location:expr) => {
+                ModuleInfo {
+                    app,
+                    bar_position: config.position,
+                    monitor,
+                    output_name: &self.monitor_name,
+                    location: $location,
+                }
+            };
+        }
+
+        // popup ignores module location so can bodge this for now
+        let popup = Popup::new(
+            &info!(ModuleLocation::Left),
+            config.popup_gap,
+            config.popup_autohide,
+            config.popup_keyboard_mode,
+            &self.content,
+        );
+        let popup = Rc::new(popup);
+
+        let strict = config.strict;
+        let mut refs = vec![];
+
+        let orientation = config.position.orientation();
+        let center_mode = config.center_mode;
+
+        let groups = [&self.start, &self.center, &self.end];
+        let sizes = config
+            .spacing
+            .per_group()
+            .into_iter()
+            .zip(config.padding.per_group());
+
+        for (container, (spacing, padding)) in groups.into_iter().zip(sizes) {
+            container.set_spacing(spacing);
+
+            if orientation == Orientation::Horizontal {
+                container.set_margin_start(padding);
+                container.set_margin_end(padding);
+            } else {
+                container.set_margin_top(padding);
+                container.set_margin_bottom(padding);
+            }
+        }
+
+        if center_mode == CenterMode::Space {
+            // lay the groups out in a row,
+            // with the center group filling the space between the others
+            let row = gtk::Box::new(orientation, 0);
+            row.set_hexpand(true);
+            row.set_vexpand(true);
+
+            if orientation == Orientation::Horizontal {
+                self.center.set_hexpand(true);
+                self.center.set_halign(Align::Center);
+            } else {
+                self.center.set_vexpand(true);
+                self.center.set_valign(Align::Center);
+            }
+
+            row.append(&self.start);
+            row.append(&self.center);
+            row.append(&self.end);
+
+            self.content.set_center_widget(Some(&row));
+        }
+
+        if let Some(modules) = config.start {
+            self.attach_group(&ModuleLocation::Left, center_mode);
+
+            let info = info!(ModuleLocation::Left);
+            refs.extend(add_modules(
+                &self.start,
+                modules,
+                &info,
+                &self.ironbar,
+                &instance,
+                &popup,
+                strict,
+            ));
+        }
+
+        if let Some(modules) = config.center {
+            self.attach_group(&ModuleLocation::Center, center_mode);
+
+            let info = info!(ModuleLocation::Center);
+            refs.extend(add_modules(
+                &self.center,
+                modules,
+                &info,
+                &self.ironbar,
+                &instance,
+                &popup,
+                strict,
+            ));
+        }
+
+        if let Some(modules) = config.end {
+            self.attach_group(&ModuleLocation::Right, center_mode);
+
+            let info = info!(ModuleLocation::Right);
+            refs.extend(add_modules(
+                &self.end,
+                modules,
+                &info,
+                &self.ironbar,
+                &instance,
+                &popup,
+                strict,
+            ));
+        }
+
+        if let Some(groups) = config.positioned {
+            let info = info!(ModuleLocation::Center);
+
+            let mut containers = vec![];
+            for group in groups {
+                let container =
+                    create_container(group.name.as_deref().unwrap_or("positioned"), orientation);
+                container.add_css_class("positioned");
+                container.set_spacing(group.spacing);
+                self.overlay.add_overlay(&container);
+
+                refs.extend(add_modules(
+                    &container,
+                    group.modules,
+                    &info,
+                    &self.ironbar,
+                    &instance,
+                    &popup,
+                    strict,
+                ));
+
+                containers.push((container, group.offset, group.align));
+            }
+
+            self.overlay
+                .connect_get_child_position(move |overlay, widget| {
+                    let (_, offset, align) = containers
+                        .iter()
+                        .find(|(container, ..)| container.upcast_ref::<gtk::Widget>() == widget)?;
+
+                    let (_, natural) = widget.preferred_size();
+                    let (width, height) = (overlay.width(), overlay.height());
+
+                    let rect = if orientation == Orientation::Horizontal {
+                        let size = natural.width().min(width);
+                        let mut x = align.position(*offset, size, width);
+
+                        // offsets are measured from the end in right-to-left layouts
+                        if overlay.direction() == TextDirection::Rtl {
+                            x = width - size - x;
+                        }
+
+                        Rectangle::new(x, 0, size, height)
+                    } else {
+                        let size = natural.height().min(height);
+                        Rectangle::new(0, align.position(*offset, size, height), width, size)
+                    };
+
+                    Some(rect)
+                });
+        }
+
+        BarLoadResult {
+            popup,
+            module_refs: refs,
+        }
+    }
+
+    /// Places a group's container onto the bar.
+    ///
+    /// In `space` center mode, the containers are always placed.
+    fn attach_group(&self, location: &ModuleLocation, center_mode: CenterMode) {
+        if center_mode == CenterMode::Space {
+            return;
+        }
+
+        match location {
+            ModuleLocation::Left => self.content.set_start_widget(Some(&self.start)),
+            ModuleLocation::Center if center_mode == CenterMode::Auto => {
+                self.content.set_center_widget(Some(&self.center));
+            }
+            ModuleLocation::Center => {
+                // drawn over the other groups so that it is never pushed off-center
+                if self.position.orientation() == Orientation::Horizontal {
+                    self.center.set_halign(Align::Center);
+                } else {
+                    self.center.set_valign(Align::Center);
+                }
+
+                self.overlay.add_overlay(&self.center);
+                self.overlay.set_measure_overlay(&self.center, true);
+            }
+            ModuleLocation::Right => self.content.set_end_widget(Some(&self.end)),
+        }
+    }
+
+    fn container(&self, location: &ModuleLocation) -> &gtk::Box {
+        match location {
+            ModuleLocation::Left => &self.start,
+            ModuleLocation::Center => &self.center,
+            ModuleLocation::Right => &self.end,
+        }
+    }
+
+    fn show(&self, include_window: bool) {
+        debug!("Showing bar: {}", self.name);
+
+        // show each box but do not use `show_all`.
+        // this ensures `show_if` option works as intended.
+        self.start.set_visible(true);
+        self.center.set_visible(true);
+        self.end.set_visible(true);
+        self.content.set_visible(true);
+
+        if include_window {
+            self.window.set_visible(true);
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether modules which fail are replaced with an error widget.
+    pub fn strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Marks the bar as having a module which failed.
+    pub fn set_degraded(&self) {
+        self.content.add_css_class("degraded");
+    }
+
+    /// The top-level layer shell window for the bar.
+    pub fn window(&self) -> &ApplicationWindow {
+        &self.window
+    }
+
+    /// The name of the output the bar is displayed on.
+    pub fn monitor_name(&self) -> &str {
+        &self.monitor_name
+    }
+
+    /// Whether any of the bar's modules failed to load.
+    pub fn degraded(&self) -> bool {
+        self.content.has_css_class("degraded")
+    }
+
+    pub fn popup(&self) -> Rc<Popup> {
+        match &self.inner {
+            Inner::New { .. } => {
+                panic!("Attempted to get popup of uninitialized bar. This is a serious bug!")
+            }
+            Inner::Loaded { popup, .. } => popup.clone(),
+        }
+    }
+
+    pub fn visible(&self) -> bool {
+        self.window.is_visible()
+    }
+
+    /// Sets the window visibility status.
+    ///
+    /// Where autohide is enabled, this reveals or hides the bar
+    /// in the same way as the pointer would.
+    pub fn set_visible(&self, visible: bool) {
+        match self.autohide.get() {
+            Some(autohide) if visible => autohide.reveal(),
+            Some(autohide) => autohide.conceal(),
+            None => self.window.set_visible(visible),
+        }
+    }
+
+    pub fn set_exclusive(&self, exclusive: bool) {
+        if exclusive {
+            self.window.auto_exclusive_zone_enable();
+        } else {
+            self.window.set_exclusive_zone(0);
+        }
+    }
+
+    /// Starts peeking the bar, or ends the current peek.
+    ///
+    /// While peeking, the bar is shown on the overlay layer
+    /// so that it is drawn above fullscreen windows,
+    /// without reserving an exclusive zone.
+    /// If a duration is given, the peek automatically ends once it elapses.
+    pub fn toggle_peek(&self, duration: Option<Duration>) {
+        if self.peek.borrow().is_some() {
+            self.end_peek();
+            return;
+        }
+
+        debug!("Peeking bar: {}", self.name);
+
+        let timeout_id = duration.map(|duration| {
+            let bar = self.clone();
+            glib::timeout_add_local_once(duration, move || {
+                // source is removed once complete, so must not be removed again
+                if let Some(peek) = bar.peek.borrow_mut().as_mut() {
+                    peek.timeout_id.take();
+                }
+
+                bar.end_peek();
+            })
+        });
+
+        self.peek.replace(Some(PeekState {
+            visible: self.visible(),
+            layer: self.window.layer(),
+            auto_exclusive_zone: self.window.auto_exclusive_zone_is_enabled(),
+            exclusive_zone: self.window.exclusive_zone(),
+            timeout_id,
+        }));
+
+        self.window.set_layer(gtk_layer_shell::Layer::Overlay);
+        self.window.set_exclusive_zone(0);
+        self.set_visible(true);
+    }
+
+    /// Ends the current peek, if any,
+    /// restoring the bar to its previous state.
+    fn end_peek(&self) {
+        let Some(peek) = self.peek.take() else {
+            return;
+        };
+
+        debug!("Ending peek for bar: {}", self.name);
+
+        if let Some(timeout_id) = peek.timeout_id {
+            timeout_id.remove();
+        }
+
+        self.window.set_layer(peek.layer);
+
+        if peek.auto_exclusive_zone {
+            self.window.auto_exclusive_zone_enable();
+        } else {
+            self.window.set_exclusive_zone(peek.exclusive_zone);
+        }
+
+        self.set_visible(peek.visible);
+    }
+
+    /// Sets whether the bar should be kept visible when autohide is enabled.
+    pub fn set_locked(&self, locked: bool) {
+        if let Some(autohide) = self.autohide.get() {
+            autohide.set_locked(locked);
+        }
+    }
+
+    pub fn modules(&self) -> Vec<ModuleRef> {
+        match &self.inner {
+            Inner::New { .. } => {
+                panic!("Attempted to get modules of uninitialized bar. This is a serious bug!")
+            }
+            Inner::Loaded { module_refs, .. } => module_refs.borrow().clone(),
+        }
+    }
+
+    /// Gets a module by its ID or name.
+    pub fn find_module(&self, module: &str) -> Option<ModuleRef> {
+        self.modules()
+            .into_iter()
+            .find(|m| m.id.to_string() == module || m.name == module)
+    }
+
+    /// Creates a module and adds it to a group,
+    /// at `index` or the end of the group.
+    ///
+    /// Returns the ID of the new module.
+    pub fn add_module(
+        &self,
+        config: ModuleConfig,
+        location: ModuleLocation,
+        index: Option<usize>,
+    ) -> Result<usize> {
+        let Inner::Loaded {
+            module_refs,
+            popup,
+            monitor,
+            instance,
+            center_mode,
+        } = &self.inner
+        else {
+            return Err(Report::msg("Bar is not loaded"));
+        };
+
+        let container = self.container(&location);
+
+        // groups with no configured modules are not placed on the bar
+        if container.parent().is_none() {
+            self.attach_group(&location, *center_mode);
+        }
+
+        let app = self.window.application().expect("to exist");
+        let info = ModuleInfo {
+            app: &app,
+            bar_position: self.position,
+            monitor,
+            output_name: &self.monitor_name,
+            location: location.clone(),
+        };
+
+        let module = add_modules(
+            container,
+            vec![config],
+            &info,
+            &self.ironbar,
+            instance,
+            popup,
+            false,
+        )
+        .pop()
+        .ok_or_else(|| Report::msg("Failed to create module, check the logs for details"))?;
+
+        if let Some(index) = index {
+            self.move_module(&module, Some(location), index);
+        }
+
+        let id = module.id;
+        module_refs.borrow_mut().push(module);
+
+        Ok(id)
+    }
+
+    /// Removes a module from the bar,
+    /// along with its popup.
+    ///
+    /// The module's tasks are stopped,
+    /// and its buttons can no longer open popups.
+    pub fn remove_module(&self, module: &ModuleRef) {
+        let Inner::Loaded {
+            module_refs, popup, ..
+        } = &self.inner
+        else {
+            return;
+        };
+
+        if let Some((container, slot)) = self.module_slot(module) {
+            // modules are wrapped in a revealer,
+            // so can transition out before being removed
+            match slot.downcast_ref::<Revealer>() {
+                Some(revealer) => {
+                    let slot = slot.clone();
+                    animation::conceal_then(revealer, move || container.remove(&slot));
+                }
+                None => container.remove(&slot),
+            }
+        }
+
+        module.guard.abort();
+
+        popup.unregister_content(module.id);
+        popup.unregister_buttons_within(&module.root_widget);
+        if let Some(parts) = &module.popup {
+            for button in &parts.buttons {
+                popup.unregister_button(button);
+            }
+        }
+
+        module_refs.borrow_mut().retain(|m| m.id != module.id);
+    }
+
+    /// Moves a module to `index` within a group,
+    /// or within its current group if `location` is not set.
+    pub fn move_module(&self, module: &ModuleRef, location: Option<ModuleLocation>, index: usize) {
+        let Some((mut container, slot)) = self.module_slot(module) else {
+            return;
+        };
+
+        if let Some(location) = location {
+            let target = self.container(&location);
+
+            if target != &container {
+                if let Inner::Loaded { center_mode, .. } = &self.inner
+                    && target.parent().is_none()
+                {
+                    self.attach_group(&location, *center_mode);
+                }
+
+                container.remove(&slot);
+                target.append(&slot);
+                container = target.clone();
+            }
+        }
+
+        let mut siblings = vec![];
+        let mut child = container.first_child();
+        while let Some(widget) = child {
+            child = widget.next_sibling();
+            if widget != slot {
+                siblings.push(widget);
+            }
+        }
+
+        let sibling = index
+            .checked_sub(1)
+            .and_then(|i| siblings.get(i).or(siblings.last()));
+
+        container.reorder_child_after(&slot, sibling);
+    }
+
+    /// Gets the group container a module is in,
+    /// and the widget wrapping the module which is its direct child.
+    fn module_slot(&self, module: &ModuleRef) -> Option<(gtk::Box, Widget)> {
+        let mut child = module.root_widget.clone();
+
+        while let Some(parent) = child.parent() {
+            if let Some(container) = parent.downcast_ref::<gtk::Box>()
+                && (container == &self.start
+                    || container == &self.center
+                    || container == &self.end
+                    || container.has_css_class("positioned"))
+            {
+                return Some((container.clone(), child));
+            }
+
+            child = parent;
+        }
+
+        None
+    }
+}
+
+/// Creates a `gtk::Box` container to place widgets inside.
+fn create_container(name: &str, orientation: Orientation) -> gtk::Box {
+    let container = gtk::Box::builder()
+        .orientation(orientation)
+        .spacing(0)
+        .name(name)
+        .build();
+
+    container.add_css_class("container");
+    container
+}
+
+#[derive(Debug)]
+struct BarLoadResult {
+    popup: Rc<Popup>,
+    module_refs: Vec<ModuleRef>,
+}
+
+/// Adds modules into a provided GTK box,
+/// which should be one of its left, center or right containers.
+///
+/// In `strict` mode, modules which fail to be created
+/// are replaced with an error widget, and the bar is marked as degraded.
+/// Modules which panic are always replaced.
+fn add_modules(
+    content: &gtk::Box,
+    modules: Vec<ModuleConfig>,
+    info: &ModuleInfo,
+    ironbar: &Rc<Ironbar>,
+    slf: &Rc<Bar>,
+    popup: &Rc<Popup>,
+    strict: bool,
+) -> Vec<ModuleRef> {
+    let module_factory = BarModuleFactory::new(ironbar.clone(), slf.clone(), popup.clone()).into();
+
+    let mut results = vec![];
+    for config in modules {
+        let name = config.name();
+
+        // panics during creation are caught so only this module is lost
+        let res = panic::catch_unwind(AssertUnwindSafe(|| {
+            startup::time("module", &name, || {
+                config.create(&module_factory, content, info)
+            })
+        }));
+
+        match res {
+            Ok(Ok(res)) => results.push(res),
+            Ok(Err(err)) => {
+                error!("failed to create module {name}: {:?}", err);
+
+                if strict {
+                    let reason = format!("Failed to create module {name}: {err:#}");
+                    content.append(&create_error_widget(&name, &reason));
+                    slf.set_degraded();
+                }
+            }
+            Err(payload) => {
+                let message = panic_guard::panic_message(&*payload);
+                error!("module {name} panicked during creation: {message}");
+
+                let reason = format!("Module {name} panicked: {message}");
+                content.append(&create_error_widget(&name, &reason));
+                slf.set_degraded();
+            }
+        }
+    }
+
+    results
+}
+
+pub fn create_bar(
+    app: &Application,
+    monitor: &Monitor,
+    monitor_name: String,
+    config: BarConfig,
+    ironbar: Rc<Ironbar>,
+) -> Bar {
+    let bar = Bar::new(app, monitor_name, config, ironbar);
+    bar.init(monitor)
+}
@@ -1,8 +1,51 @@
 use crate::modules::ModuleUpdateEvent;
-use crate::spawn;
+use crate::panic_guard::{self, PanicGuard};
+use crate::{lock, spawn};
+use indexmap::IndexMap;
 use smithay_client_toolkit::reexports::calloop;
+use std::collections::BTreeMap;
 use std::fmt::Debug;
+use std::hash::Hash;
+use std::panic::Location;
+use std::sync::Mutex;
+use std::time::Duration;
 use tokio::sync::{broadcast, mpsc};
+use tokio::time::{Instant, sleep_until};
+
+/// The default capacity of each module's update channels.
+pub const DEFAULT_CAPACITY: usize = 64;
+
+/// Lag recorded against each broadcast receiver, keyed by receiver name.
+static LAG_STATS: Mutex<BTreeMap<String, LagStats>> = Mutex::new(BTreeMap::new());
+
+/// How often a broadcast receiver has fallen behind its sender.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LagStats {
+    /// The number of times the receiver lagged.
+    pub events: u64,
+    /// The total number of messages dropped.
+    pub dropped: u64,
+}
+
+/// Records that a broadcast receiver fell behind and dropped `count` messages.
+pub fn record_lag(name: &str, count: u64) {
+    tracing::warn!("{name} lagged behind by {count} messages");
+
+    let mut stats = lock!(LAG_STATS);
+    let stats = stats.entry(name.to_string()).or_default();
+    stats.events += 1;
+    stats.dropped += count;
+}
+
+/// Gets the lag recorded against each broadcast receiver so far.
+pub fn lag_stats() -> BTreeMap<String, LagStats> {
+    lock!(LAG_STATS).clone()
+}
+
+/// Names a receiver by where it was subscribed from.
+fn receiver_name(location: &Location) -> String {
+    format!("{}:{}", location.file(), location.line())
+}
 
 pub trait SyncSenderExt<T> {
     /// Asynchronously sends a message on the channel,
@@ -91,6 +134,67 @@ impl<T: Send> AsyncSenderExt<T> for mpsc::Sender<T> {
     }
 }
 
+/// Coalesces messages from `rx` which share a key,
+/// so that only the latest message for each key is sent on
+/// once `window` has passed since the first of them was received.
+///
+/// Messages without a key are sent on immediately,
+/// after any pending messages so that ordering is kept.
+pub fn coalesce<T, K, F>(mut rx: mpsc::Receiver<T>, window: Duration, key: F) -> mpsc::Receiver<T>
+where
+    T: Send + 'static,
+    K: Hash + Eq + Send + 'static,
+    F: Fn(&T) -> Option<K> + Send + 'static,
+{
+    let (tx, out_rx) = mpsc::channel(rx.max_capacity());
+
+    spawn(async move {
+        let mut pending = IndexMap::new();
+        let mut deadline = None;
+
+        loop {
+            let message = match deadline {
+                Some(deadline_at) => tokio::select! {
+                    message = rx.recv() => message,
+                    () = sleep_until(deadline_at) => {
+                        for (_, message) in pending.drain(..) {
+                            tx.send_expect(message).await;
+                        }
+                        deadline = None;
+                        continue;
+                    }
+                },
+                None => rx.recv().await,
+            };
+
+            let Some(message) = message else {
+                break;
+            };
+
+            match key(&message) {
+                Some(key) => {
+                    pending.insert(key, message);
+                    deadline.get_or_insert_with(|| Instant::now() + window);
+                }
+                None => {
+                    for (_, message) in pending.drain(..) {
+                        tx.send_expect(message).await;
+                    }
+                    deadline = None;
+
+                    tx.send_expect(message).await;
+                }
+            }
+        }
+
+        for (_, message) in pending.drain(..) {
+            tx.send_expect(message).await;
+        }
+    });
+
+    out_rx
+}
+
 pub trait MpscReceiverExt<T> {
     /// Spawns a `GLib` future on the local thread, and calls `rx.recv()`
     /// in a loop, passing the message to `f`.
@@ -115,9 +219,12 @@ impl<T: 'static> MpscReceiverExt<T> for mpsc::Receiver<T> {
         Fn: FnMut(&D::Target, T) + 'static,
     {
         let deps = deps.clone_content();
+        let guard = PanicGuard::current();
         glib::spawn_future_local(async move {
             while let Some(val) = self.recv().await {
-                f(&deps, val);
+                if panic_guard::catch(guard.as_ref(), || f(&deps, val)).is_none() {
+                    break;
+                }
             }
         });
     }
@@ -148,12 +255,23 @@ where
         D::Target: 'static,
         Fn: FnMut(&D::Target, T) -> F + 'static,
         F: Future;
+
+    /// Receives the next message, like `recv`,
+    /// recording any lag against `name`.
+    ///
+    /// When this returns [`broadcast::error::RecvError::Lagged`],
+    /// messages were dropped, so the caller should resync its full state.
+    fn recv_tracked(
+        &mut self,
+        name: &str,
+    ) -> impl Future<Output = Result<T, broadcast::error::RecvError>>;
 }
 
 impl<T> BroadcastReceiverExt<T> for broadcast::Receiver<T>
 where
     T: Debug + Clone + 'static,
 {
+    #[track_caller]
     fn recv_glib<D, Fn>(mut self, deps: D, mut f: Fn)
     where
         D: Dependency,
@@ -161,15 +279,17 @@ where
         Fn: FnMut(&D::Target, T) + 'static,
     {
         let deps = deps.clone_content();
+        let guard = PanicGuard::current();
+        let name = receiver_name(Location::caller());
         glib::spawn_future_local(async move {
             loop {
                 match self.recv().await {
-                    Ok(val) => f(&deps, val),
-                    Err(broadcast::error::RecvError::Lagged(count)) => {
-                        tracing::warn!(
-                            "Channel lagged behind by {count}, this may result in unexpected or broken behaviour"
-                        );
+                    Ok(val) => {
+                        if panic_guard::catch(guard.as_ref(), || f(&deps, val)).is_none() {
+                            break;
+                        }
                     }
+                    Err(broadcast::error::RecvError::Lagged(count)) => record_lag(&name, count),
                     Err(err) => {
                         tracing::error!("{err:?}");
                         break;
@@ -179,6 +299,7 @@ where
         });
     }
 
+    #[track_caller]
     fn recv_glib_async<D, Fn, F>(mut self, deps: D, mut f: Fn)
     where
         D: Dependency,
@@ -187,17 +308,21 @@ where
         F: Future,
     {
         let deps = deps.clone_content();
+        let guard = PanicGuard::current();
+        let name = receiver_name(Location::caller());
         glib::spawn_future_local(async move {
             loop {
                 match self.recv().await {
                     Ok(val) => {
-                        f(&deps, val).await;
-                    }
-                    Err(broadcast::error::RecvError::Lagged(count)) => {
-                        tracing::warn!(
-                            "Channel lagged behind by {count}, this may result in unexpected or broken behaviour"
-                        );
+                        let fut = async { f(&deps, val).await };
+                        if panic_guard::catch_future(guard.as_ref(), fut)
+                            .await
+                            .is_none()
+                        {
+                            break;
+                        }
                     }
+                    Err(broadcast::error::RecvError::Lagged(count)) => record_lag(&name, count),
                     Err(err) => {
                         tracing::error!("{err:?}");
                         break;
@@ -206,6 +331,16 @@ where
             }
         });
     }
+
+    async fn recv_tracked(&mut self, name: &str) -> Result<T, broadcast::error::RecvError> {
+        let res = self.recv().await;
+
+        if let Err(broadcast::error::RecvError::Lagged(count)) = res {
+            record_lag(name, count);
+        }
+
+        res
+    }
 }
 
 /// `recv_glib` callback dependency
@@ -259,3 +394,33 @@ impl_dependency!(0 T1, 1 T2, 2 T3, 3 T4, 4 T5, 5 T6, 6 T7, 7 T8, 8 T9);
 impl_dependency!(0 T1, 1 T2, 2 T3, 3 T4, 4 T5, 5 T6, 6 T7, 7 T8, 8 T9, 9 T10);
 impl_dependency!(0 T1, 1 T2, 2 T3, 3 T4, 4 T5, 5 T6, 6 T7, 7 T8, 8 T9, 9 T10, 10 T11);
 impl_dependency!(0 T1, 1 T2, 2 T3, 3 T4, 4 T5, 5 T6, 6 T7, 7 T8, 8 T9, 9 T10, 10 T11, 11 T12);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ironbar;
+
+    #[test]
+    fn coalesce_keeps_latest() {
+        Ironbar::runtime().block_on(async {
+            let (tx, rx) = mpsc::channel(8);
+            let mut rx = coalesce(
+                rx,
+                Duration::from_millis(10),
+                |(key, _): &(Option<u8>, u8)| *key,
+            );
+
+            for message in [(Some(0), 1), (Some(1), 1), (Some(0), 2), (None, 3)] {
+                tx.send_expect(message).await;
+            }
+            drop(tx);
+
+            let mut received = vec![];
+            while let Some(message) = rx.recv().await {
+                received.push(message);
+            }
+
+            assert_eq!(received, vec![(Some(0), 2), (Some(1), 1), (None, 3)]);
+        });
+    }
+}
@@ -1,15 +1,19 @@
 use crate::config::ConfigLocation;
 use crate::error::ExitCode;
 use crate::ipc::{Command, Response};
-use clap::{Parser, ValueEnum};
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::process::exit;
 
 #[derive(Parser, Debug, Serialize, Deserialize)]
-#[command(version)]
+#[command(
+    version,
+    group(ArgGroup::new("remote_token").args(["token", "token_file"]).multiple(true))
+)]
 pub struct Args {
     #[command(subcommand)]
-    pub command: Option<Command>,
+    pub command: Option<CliCommand>,
 
     /// Print the config JSON schema to `stdout`
     /// and exit.
@@ -27,6 +31,17 @@ pub struct Args {
     #[arg(long)]
     pub debug: bool,
 
+    /// Print how long each module and client took to initialize
+    /// to stderr once the bars have loaded.
+    #[arg(long)]
+    pub profile_startup: bool,
+
+    /// Run the bar in a child process,
+    /// restarting it if the Wayland connection is lost.
+    /// Ironvars are kept across restarts.
+    #[arg(long)]
+    pub supervise: bool,
+
     /// Specify the path to the config file to use.
     #[arg(short('c'), long, env = "IRONBAR_CONFIG")]
     pub config: Option<ConfigLocation>,
@@ -46,12 +61,111 @@ pub struct Args {
     #[arg(short, long)]
     pub format: Option<Format>,
 
+    /// Send IPC commands to a remote instance at this address,
+    /// such as `192.168.1.20:7420`, instead of the local instance.
+    #[arg(long, env = "IRONBAR_REMOTE", requires = "remote_token")]
+    pub remote: Option<String>,
+
+    /// The token to authenticate with the remote instance.
+    ///
+    /// Arguments can be read by other users through `/proc/<pid>/cmdline`,
+    /// so prefer `--token-file` or the `IRONBAR_TOKEN` variable on shared machines.
+    #[arg(long, env = "IRONBAR_TOKEN", hide_env_values = true)]
+    pub token: Option<String>,
+
+    /// A file to read the token to authenticate with the remote instance from,
+    /// used instead of `--token` if both are set.
+    /// Trailing newlines are ignored.
+    #[arg(long)]
+    pub token_file: Option<PathBuf>,
+
     /// `bar_id` argument passed by `swaybar_command`.
     /// Not used.
     #[arg(short('b'), hide(true))]
     sway_bar_id: Option<String>,
 }
 
+/// Top-level CLI commands.
+///
+/// Most commands are sent to the running instance over IPC,
+/// but some are handled locally by the CLI.
+#[derive(Subcommand, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CliCommand {
+    /// Convert a config from another bar into an Ironbar config.
+    Migrate(MigrateArgs),
+
+    /// Write a starter config and stylesheet,
+    /// with modules matching the detected system.
+    Init(InitArgs),
+
+    /// Work with the config file.
+    #[cfg(feature = "config")]
+    #[command(subcommand)]
+    Config(ConfigCommand),
+
+    /// Print the config JSON schema to `stdout`.
+    #[cfg(feature = "extras")]
+    Schema,
+
+    #[command(flatten)]
+    Ipc(Command),
+}
+
+#[derive(clap::Args, Debug, Serialize, Deserialize)]
+pub struct MigrateArgs {
+    /// Path to the Waybar config file to convert.
+    #[arg(long("from-waybar"))]
+    pub from_waybar: PathBuf,
+
+    /// Path to the Waybar stylesheet to convert.
+    /// Defaults to `style.css` next to the config file.
+    #[arg(long)]
+    pub style: Option<PathBuf>,
+
+    /// Directory to write the converted `config.json` and `style.css` into.
+    /// Existing files are never overwritten.
+    /// If not set, the config is printed to `stdout`.
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[cfg(feature = "config")]
+#[derive(Subcommand, Debug, Serialize, Deserialize)]
+pub enum ConfigCommand {
+    /// Validate the loaded config and CSS path, and exit immediately.
+    /// Exits with code `0` if config is valid, or `1` if invalid.
+    ///
+    /// Unknown and deprecated options are reported as warnings.
+    Validate {
+        /// Fail on warnings as well as errors.
+        #[arg(long)]
+        strict: bool,
+    },
+}
+
+#[derive(clap::Args, Debug, Serialize, Deserialize)]
+pub struct InitArgs {
+    /// Directory to write `config.json` and `style.css` into.
+    /// Defaults to the Ironbar config directory.
+    /// Existing files are not overwritten unless `--force` is passed.
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Print the generated config to `stdout` instead of writing any files.
+    #[arg(long)]
+    pub print: bool,
+
+    /// Overwrite existing `config.json` and `style.css` files.
+    #[arg(short, long)]
+    pub force: bool,
+
+    /// Use everything detected without asking.
+    /// This is implied when `stdin` is not a terminal.
+    #[arg(short, long)]
+    pub yes: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize, Default, ValueEnum, Clone, Copy, Eq, PartialEq)]
 pub enum Format {
     #[default]
@@ -91,6 +205,7 @@ pub fn handle_response(response: Response, format: Format) {
             Response::OkValue { value } => println!("{value}"),
             Response::Multi { values } => println!("{}", values.join("\n")),
             Response::Err { message } => eprintln!("error\n{}", message.unwrap_or_default()),
+            Response::Event(event) => println!("{event}"),
         },
         Format::Json => println!(
             "{}",
@@ -0,0 +1,388 @@
+use crate::channels::SyncSenderExt;
+use crate::{lock, poll, spawn};
+#[cfg(feature = "brightness")]
+use color_eyre::{Result, eyre::eyre};
+use std::path::Path;
+#[cfg(feature = "brightness")]
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+#[cfg(feature = "brightness")]
+use tokio::process::Command;
+use tokio::sync::broadcast;
+use tracing::{debug, trace};
+
+const BACKLIGHT_DIR: &str = "/sys/class/backlight";
+
+/// The kernel does not notify watchers when brightness changes,
+/// so devices are polled instead.
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Querying monitors over DDC takes up to a second per monitor,
+/// so they are polled much less often.
+#[cfg(feature = "brightness")]
+const DDC_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The VCP feature code for brightness.
+#[cfg(feature = "brightness")]
+const VCP_BRIGHTNESS: &str = "10";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceKind {
+    /// A screen backlight in `/sys/class/backlight`, such as a laptop panel.
+    Backlight,
+    /// An external monitor controlled over DDC/CI using `ddcutil`.
+    Ddc,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    /// The sysfs device name, or the `ddcutil` display number.
+    pub name: String,
+    /// The monitor model for DDC devices, otherwise the device name.
+    pub description: String,
+    pub kind: DeviceKind,
+    pub brightness: u32,
+    pub max_brightness: u32,
+}
+
+impl Device {
+    pub fn percent(&self) -> f64 {
+        if self.max_brightness == 0 {
+            0.0
+        } else {
+            (f64::from(self.brightness) / f64::from(self.max_brightness) * 100.0).round()
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Devices {
+    backlights: Vec<Device>,
+    displays: Vec<Device>,
+}
+
+impl Devices {
+    fn all(&self) -> Vec<Device> {
+        self.backlights
+            .iter()
+            .chain(&self.displays)
+            .cloned()
+            .collect()
+    }
+
+    fn backlights_mut(&mut self) -> &mut Vec<Device> {
+        &mut self.backlights
+    }
+
+    #[cfg(feature = "brightness")]
+    fn displays_mut(&mut self) -> &mut Vec<Device> {
+        &mut self.displays
+    }
+}
+
+/// Client for reading and setting the brightness of screens,
+/// from their backlight devices or over DDC.
+///
+/// DDC monitors are only included once `watch_ddc` has been called.
+#[derive(Debug)]
+pub struct Client {
+    devices: Arc<Mutex<Devices>>,
+    #[cfg(feature = "brightness")]
+    ddc_started: AtomicBool,
+    tx: broadcast::Sender<Vec<Device>>,
+    _rx: broadcast::Receiver<Vec<Device>>,
+}
+
+impl Client {
+    pub(crate) fn new() -> Self {
+        trace!("Initializing backlight client");
+
+        let (tx, rx) = broadcast::channel(8);
+        let devices = Arc::new(Mutex::new(Devices::default()));
+
+        {
+            let tx = tx.clone();
+            let devices = devices.clone();
+
+            spawn(async move {
+                loop {
+                    let backlights = read_backlights().await;
+                    update(&devices, &tx, Devices::backlights_mut, backlights);
+
+                    poll::sleep(POLL_INTERVAL).await;
+                }
+            });
+        }
+
+        Self {
+            devices,
+            #[cfg(feature = "brightness")]
+            ddc_started: AtomicBool::new(false),
+            tx,
+            _rx: rx,
+        }
+    }
+
+    /// Gets all devices, with backlight devices first.
+    pub fn devices(&self) -> Vec<Device> {
+        lock!(self.devices).all()
+    }
+
+    /// Gets the brightness percentage of the first backlight device,
+    /// if one is available.
+    pub fn brightness(&self) -> Option<f64> {
+        lock!(self.devices).backlights.first().map(Device::percent)
+    }
+
+    /// Gets a receiver for the full list of devices,
+    /// which is sent each time any device changes.
+    pub fn subscribe(&self) -> broadcast::Receiver<Vec<Device>> {
+        self.tx.subscribe()
+    }
+
+    /// Starts polling external monitors over DDC using `ddcutil`.
+    /// Only the first call has any effect.
+    #[cfg(feature = "brightness")]
+    pub fn watch_ddc(&self) {
+        if self.ddc_started.swap(true, Ordering::Relaxed) {
+            return;
+        }
+
+        let tx = self.tx.clone();
+        let devices = self.devices.clone();
+
+        spawn(async move {
+            loop {
+                let displays = read_displays().await;
+                update(&devices, &tx, Devices::displays_mut, displays);
+
+                poll::sleep(DDC_POLL_INTERVAL).await;
+            }
+        });
+    }
+
+    /// Sets the brightness of the named device.
+    ///
+    /// Backlight devices are set through logind,
+    /// which allows this without root.
+    #[cfg(feature = "brightness")]
+    pub async fn set_percent(&self, name: &str, percent: f64) -> Result<()> {
+        let device = self
+            .devices()
+            .into_iter()
+            .find(|device| device.name == name)
+            .ok_or_else(|| eyre!("Unknown brightness device '{name}'"))?;
+
+        let brightness =
+            (percent.clamp(0.0, 100.0) / 100.0 * f64::from(device.max_brightness)).round() as u32;
+
+        match device.kind {
+            DeviceKind::Backlight => {
+                let connection = crate::clients::dbus::system().await?;
+                connection
+                    .call_method(
+                        Some("org.freedesktop.login1"),
+                        "/org/freedesktop/login1/session/auto",
+                        Some("org.freedesktop.login1.Session"),
+                        "SetBrightness",
+                        &("backlight", name, brightness),
+                    )
+                    .await?;
+            }
+            DeviceKind::Ddc => {
+                let output = Command::new("ddcutil")
+                    .args(["setvcp", VCP_BRIGHTNESS, &brightness.to_string()])
+                    .args(["--display", name])
+                    .output()
+                    .await?;
+
+                if !output.status.success() {
+                    return Err(eyre!(
+                        "ddcutil failed: {}",
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    ));
+                }
+            }
+        }
+
+        // show the change straight away rather than waiting for the next poll
+        let list: fn(&mut Devices) -> &mut Vec<Device> = match device.kind {
+            DeviceKind::Backlight => Devices::backlights_mut,
+            DeviceKind::Ddc => Devices::displays_mut,
+        };
+
+        let mut new_devices = list(&mut lock!(self.devices)).clone();
+        if let Some(device) = new_devices.iter_mut().find(|device| device.name == name) {
+            device.brightness = brightness;
+        }
+        update(&self.devices, &self.tx, list, new_devices);
+
+        Ok(())
+    }
+}
+
+/// Replaces one list of devices, sending the full list if it changed.
+fn update(
+    devices: &Mutex<Devices>,
+    tx: &broadcast::Sender<Vec<Device>>,
+    list: fn(&mut Devices) -> &mut Vec<Device>,
+    new_devices: Vec<Device>,
+) {
+    let all = {
+        let mut devices = lock!(devices);
+        let list = list(&mut devices);
+
+        if *list == new_devices {
+            return;
+        }
+
+        *list = new_devices;
+        devices.all()
+    };
+
+    debug!("Brightness devices changed: {all:?}");
+    tx.send_expect(all);
+}
+
+/// Reads all backlight devices, sorted by name.
+async fn read_backlights() -> Vec<Device> {
+    let Ok(mut entries) = tokio::fs::read_dir(BACKLIGHT_DIR).await else {
+        return vec![];
+    };
+
+    let mut devices = vec![];
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        if let Some(device) = read_backlight(&entry.path()).await {
+            devices.push(device);
+        }
+    }
+
+    devices.sort_by(|a, b| a.name.cmp(&b.name));
+    devices
+}
+
+async fn read_backlight(path: &Path) -> Option<Device> {
+    let read = |file: &'static str| async move {
+        tokio::fs::read_to_string(path.join(file))
+            .await
+            .ok()?
+            .trim()
+            .parse::<u32>()
+            .ok()
+    };
+
+    let name = path.file_name()?.to_string_lossy().to_string();
+
+    Some(Device {
+        description: name.clone(),
+        name,
+        kind: DeviceKind::Backlight,
+        brightness: read("brightness").await?,
+        max_brightness: read("max_brightness").await?,
+    })
+}
+
+/// Reads all monitors which support DDC using `ddcutil`.
+/// If `ddcutil` is not installed, no monitors are returned.
+#[cfg(feature = "brightness")]
+async fn read_displays() -> Vec<Device> {
+    let Some(detected) = ddcutil(&["detect", "--brief"]).await else {
+        return vec![];
+    };
+
+    let mut devices = vec![];
+    for (name, description) in parse_detect(&detected) {
+        let Some(brightness) = ddcutil(&["getvcp", VCP_BRIGHTNESS, "--brief", "--display", &name])
+            .await
+            .and_then(|output| parse_getvcp(&output))
+        else {
+            continue;
+        };
+
+        devices.push(Device {
+            name,
+            description,
+            kind: DeviceKind::Ddc,
+            brightness: brightness.0,
+            max_brightness: brightness.1,
+        });
+    }
+
+    devices
+}
+
+/// Runs `ddcutil` with the arguments, returning its output on success.
+#[cfg(feature = "brightness")]
+async fn ddcutil(args: &[&str]) -> Option<String> {
+    let output = Command::new("ddcutil").args(args).output().await.ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Parses the display numbers and monitor models
+/// from the output of `ddcutil detect --brief`.
+///
+/// Displays are listed as a `Display <n>` line,
+/// followed by indented details including `Monitor: <mfg>:<model>:<serial>`.
+#[cfg(feature = "brightness")]
+fn parse_detect(output: &str) -> Vec<(String, String)> {
+    let mut displays = vec![];
+    let mut display = None;
+
+    for line in output.lines() {
+        if let Some(number) = line.strip_prefix("Display ") {
+            display = Some(number.trim().to_string());
+        } else if let Some(monitor) = line.trim().strip_prefix("Monitor:")
+            && let Some(number) = display.take()
+        {
+            let model = monitor.trim().split(':').nth(1).unwrap_or_default();
+            let description = if model.is_empty() {
+                format!("Display {number}")
+            } else {
+                model.to_string()
+            };
+
+            displays.push((number, description));
+        }
+    }
+
+    displays
+}
+
+/// Parses the current and maximum values
+/// from the output of `ddcutil getvcp --brief`,
+/// which is in the format `VCP 10 C <current> <max>`.
+#[cfg(feature = "brightness")]
+fn parse_getvcp(output: &str) -> Option<(u32, u32)> {
+    let mut parts = output.split_whitespace().skip(3);
+    let current = parts.next()?.parse().ok()?;
+    let max = parts.next()?.parse().ok()?;
+    Some((current, max))
+}
+
+#[cfg(all(test, feature = "brightness"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_detect() {
+        let output = "Display 1\n   I2C bus:  /dev/i2c-4\n   Monitor:             DEL:DELL U2415:CFV9N\n\nInvalid display\n   I2C bus:  /dev/i2c-5\n\nDisplay 2\n   I2C bus:  /dev/i2c-6\n   Monitor:             GSM::\n";
+
+        assert_eq!(
+            parse_detect(output),
+            vec![
+                ("1".to_string(), "DELL U2415".to_string()),
+                ("2".to_string(), "Display 2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_getvcp() {
+        assert_eq!(parse_getvcp("VCP 10 C 50 100\n"), Some((50, 100)));
+        assert_eq!(parse_getvcp("VCP 10 ERR\n"), None);
+    }
+}
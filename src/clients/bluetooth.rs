@@ -1,6 +1,6 @@
 use crate::{Ironbar, error, register_fallible_client, spawn};
 use bluer::Result;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::{sync::watch, task::JoinSet};
 use tracing::debug;
 
@@ -10,6 +10,8 @@ pub enum BluetoothState {
     Enabled {
         /// Sorted by `address`
         devices: Vec<BluetoothDevice>,
+        /// Whether the adapter is scanning for new devices.
+        discovering: bool,
     },
     Disabled,
     Disabling,
@@ -24,6 +26,7 @@ impl BluetoothState {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BluetoothDeviceStatus {
+    Pairing,
     Connecting,
     Connected,
     Disconnecting,
@@ -47,6 +50,10 @@ pub struct BluetoothDevice {
 
     /// The battery percentage.
     pub battery_percent: Option<u8>,
+
+    /// Whether the device has been paired with the adapter.
+    /// Unpaired devices are only listed after being found by a scan.
+    pub paired: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -76,22 +83,10 @@ impl Client {
                 // This does not affect responsiveness, as user actions force
                 // an immediate state update.
                 loop {
-                    match Self::get_state(&session).await {
-                        Ok(mut new_state) => {
-                            debug!("New bluetooth state: {:?}", new_state);
-
-                            if 0 == tx.receiver_count() {
-                                break;
-                            }
+                    Self::refresh(&session, &tx).await;
 
-                            tx.send_modify(|old_state| {
-                                Self::patch_new_state(&mut new_state, old_state);
-                                *old_state = new_state;
-                            });
-                        }
-                        Err(err) => {
-                            error!("Bluetooth error: {}", err);
-                        }
+                    if 0 == tx.receiver_count() {
+                        break;
                     }
 
                     tokio::time::sleep(Duration::from_secs(5)).await;
@@ -137,6 +132,7 @@ impl Client {
                 debug!("Enabled");
                 BluetoothState::Enabled {
                     devices: Vec::new(),
+                    discovering: false,
                 }
             };
 
@@ -173,6 +169,7 @@ impl Client {
                 error!("Failed to disable: {}", err);
                 BluetoothState::Enabled {
                     devices: Vec::new(),
+                    discovering: false,
                 }
             } else {
                 debug!("Disabled");
@@ -262,6 +259,104 @@ impl Client {
         });
     }
 
+    pub(crate) fn pair(&self, address: bluer::Address) {
+        debug!("Pair {}", address);
+
+        let session = self.session.clone();
+        let tx = self.tx.clone();
+        spawn(async move {
+            if !tx.send_if_modified(|state| {
+                Self::try_replace_device_status(
+                    state,
+                    address,
+                    BluetoothDeviceStatus::Disconnected,
+                    BluetoothDeviceStatus::Pairing,
+                )
+            }) {
+                return;
+            }
+
+            let new_status = if let Err(err) = Self::pair_device(&session, address).await {
+                error!("Pairing error: {}", err);
+                BluetoothDeviceStatus::Disconnected
+            } else {
+                debug!("Pair finished {}", address);
+                BluetoothDeviceStatus::Connected
+            };
+
+            tx.send_if_modified(|state| {
+                Self::try_replace_device_status(
+                    state,
+                    address,
+                    BluetoothDeviceStatus::Pairing,
+                    new_status,
+                )
+            });
+
+            // picks up the new paired state
+            Self::refresh(&session, &tx).await;
+        });
+    }
+
+    /// Scans for new devices for `duration`,
+    /// listing each device as it is found.
+    pub(crate) fn scan(&self, duration: Duration) {
+        debug!("Scanning for {:?}", duration);
+
+        let session = self.session.clone();
+        let tx = self.tx.clone();
+        spawn(async move {
+            if let Err(err) = Self::discover(&session, &tx, duration).await {
+                error!("Failed to scan for devices: {}", err);
+            }
+
+            Self::refresh(&session, &tx).await;
+            debug!("Scan finished");
+        });
+    }
+
+    async fn discover(
+        session: &bluer::Session,
+        tx: &watch::Sender<BluetoothState>,
+        duration: Duration,
+    ) -> Result<()> {
+        let adapter = session.default_adapter().await?;
+
+        // discovery stops once the stream is dropped
+        let _discovery = adapter.discover_devices().await?;
+
+        let end = Instant::now() + duration;
+        while Instant::now() < end {
+            Self::refresh(session, tx).await;
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+
+        Ok(())
+    }
+
+    /// Gets the full state, sending it if it has changed.
+    async fn refresh(session: &bluer::Session, tx: &watch::Sender<BluetoothState>) {
+        match Self::get_state(session).await {
+            Ok(mut new_state) => {
+                debug!("New bluetooth state: {:?}", new_state);
+
+                tx.send_if_modified(|old_state| {
+                    Self::patch_new_state(&mut new_state, old_state);
+
+                    if *old_state == new_state {
+                        false
+                    } else {
+                        *old_state = new_state;
+                        true
+                    }
+                });
+            }
+            Err(err) => {
+                error!("Bluetooth error: {}", err);
+            }
+        }
+    }
+
     async fn set_powered(session: bluer::Session, val: bool) -> Result<()> {
         let adapter = session.default_adapter().await?;
         adapter.set_powered(val).await?;
@@ -276,6 +371,17 @@ impl Client {
         Ok(())
     }
 
+    async fn pair_device(session: &bluer::Session, address: bluer::Address) -> Result<()> {
+        let adapter = session.default_adapter().await?;
+        let device = adapter.device(address)?;
+        device.pair().await?;
+        // allows the device to reconnect by itself in future
+        device.set_trusted(true).await?;
+        device.connect().await?;
+
+        Ok(())
+    }
+
     async fn disconnect_device(session: bluer::Session, address: bluer::Address) -> Result<()> {
         let adapter = session.default_adapter().await?;
         let device = adapter.device(address)?;
@@ -303,6 +409,7 @@ impl Client {
             alias: device.alias().await?,
             icon: device.icon().await?,
             battery_percent: device.battery_percentage().await?,
+            paired: device.is_paired().await?,
         })
     }
 
@@ -330,7 +437,10 @@ impl Client {
 
                     devices.sort_by_key(|d| d.address);
 
-                    BluetoothState::Enabled { devices }
+                    BluetoothState::Enabled {
+                        devices,
+                        discovering: adapter.is_discovering().await?,
+                    }
                 } else {
                     BluetoothState::Disabled
                 }
@@ -358,9 +468,11 @@ impl Client {
             (
                 BluetoothState::Enabled {
                     devices: new_devices,
+                    ..
                 },
                 BluetoothState::Enabled {
                     devices: old_devices,
+                    ..
                 },
             ) => {
                 for new_device in new_devices {
@@ -382,6 +494,12 @@ impl Client {
                             ) => {
                                 new_device.status = BluetoothDeviceStatus::Connecting;
                             }
+                            (
+                                BluetoothDeviceStatus::Disconnected,
+                                BluetoothDeviceStatus::Pairing,
+                            ) => {
+                                new_device.status = BluetoothDeviceStatus::Pairing;
+                            }
                             _ => (),
                         }
                     }
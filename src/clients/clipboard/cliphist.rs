@@ -0,0 +1,99 @@
+//! Adapter for the [cliphist](https://github.com/sentriz/cliphist) database,
+//! using its command line interface.
+
+use super::storage::Entry;
+use std::io;
+use std::process::Stdio;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Loads up to `max_entries` of the newest items, oldest first.
+///
+/// cliphist does not save mime types,
+/// so these are detected from the value.
+pub async fn load(max_entries: usize) -> io::Result<Vec<Entry>> {
+    let list = run(&["list"], None).await?;
+    let list = String::from_utf8_lossy(&list);
+
+    let mut entries = vec![];
+
+    for key in list.lines().filter_map(parse_key).take(max_entries) {
+        let bytes = run(&["decode"], Some(format!("{key}\t").as_bytes())).await?;
+        entries.push((key.to_string(), mime_type(&bytes).to_string(), bytes));
+    }
+
+    entries.reverse();
+    Ok(entries)
+}
+
+/// Saves the value, returning its key.
+pub async fn store(bytes: &[u8]) -> io::Result<String> {
+    run(&["store"], Some(bytes)).await?;
+
+    // stored items move to the top of the list
+    let list = run(&["list"], None).await?;
+    String::from_utf8_lossy(&list)
+        .lines()
+        .find_map(parse_key)
+        .map(ToString::to_string)
+        .ok_or_else(|| io::Error::other("Stored item missing from cliphist"))
+}
+
+pub async fn remove(key: &str) -> io::Result<()> {
+    run(&["delete"], Some(format!("{key}\t").as_bytes()))
+        .await
+        .map(|_| ())
+}
+
+/// Gets the key from a line of `cliphist list` output,
+/// which is in the format `<key>\t<preview>`.
+fn parse_key(line: &str) -> Option<&str> {
+    line.split_once('\t').map(|(key, _)| key)
+}
+
+/// Detects supported image types from their magic bytes,
+/// otherwise treating the value as text.
+fn mime_type(bytes: &[u8]) -> &'static str {
+    if bytes.starts_with(b"\x89PNG") {
+        "image/png"
+    } else if bytes.starts_with(b"\xFF\xD8\xFF") {
+        "image/jpeg"
+    } else if bytes.starts_with(b"BM") {
+        "image/bmp"
+    } else if bytes.starts_with(b"II*\0") || bytes.starts_with(b"MM\0*") {
+        "image/tiff"
+    } else {
+        "text/plain;charset=utf-8"
+    }
+}
+
+/// Runs `cliphist` with the arguments,
+/// writing `input` to its stdin and returning its stdout.
+async fn run(args: &[&str], input: Option<&[u8]>) -> io::Result<Vec<u8>> {
+    let mut child = Command::new("cliphist")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        if let Some(input) = input {
+            stdin.write_all(input).await?;
+        }
+        // closing stdin signals the end of the input
+        drop(stdin);
+    }
+
+    let output = child.wait_with_output().await?;
+
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(io::Error::other(format!(
+            "cliphist {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
@@ -1,20 +1,29 @@
+mod cliphist;
+mod storage;
+
+use self::storage::Storage;
 use super::wayland::{self, ClipboardItem};
 use crate::channels::AsyncSenderExt;
-use crate::{arc_mut, lock, register_client, spawn};
+use crate::{arc_mut, lock, spawn};
 use indexmap::IndexMap;
 use indexmap::map::Iter;
 use std::sync::{Arc, Mutex};
 use tokio::sync::mpsc;
 use tracing::{debug, trace};
 
+pub use self::storage::StorageConfig;
+
 #[derive(Debug)]
 pub enum ClipboardEvent {
     Add(ClipboardItem),
     Remove(usize),
     Activate(usize),
+    /// The clipboard was set to an item which is not kept in the history.
+    Deactivate,
 }
 
 type EventSender = mpsc::Sender<ClipboardEvent>;
+type Senders = Arc<Mutex<Vec<(EventSender, usize)>>>;
 
 /// Clipboard client singleton,
 /// to ensure bars don't duplicate requests to the compositor.
@@ -22,73 +31,41 @@ type EventSender = mpsc::Sender<ClipboardEvent>;
 pub struct Client {
     wayland: Arc<wayland::Client>,
 
-    senders: Arc<Mutex<Vec<(EventSender, usize)>>>,
+    senders: Senders,
     cache: Arc<Mutex<ClipboardCache>>,
+    storage: Arc<Storage>,
 }
 
 impl Client {
-    pub(crate) fn new(wl: Arc<wayland::Client>) -> Self {
+    pub(crate) fn new(wl: Arc<wayland::Client>, storage_config: StorageConfig) -> Self {
         trace!("Initializing clipboard client");
 
         let senders = arc_mut!(Vec::<(EventSender, usize)>::new());
 
         let cache = arc_mut!(ClipboardCache::new());
+        let storage = Arc::new(Storage::new(storage_config));
 
         {
             let senders = senders.clone();
             let cache = cache.clone();
+            let storage = storage.clone();
             let wl = wl.clone();
 
             spawn(async move {
                 let item = wl.clipboard_item();
                 let mut rx = wl.subscribe_clipboard();
 
-                if let Some(item) = item {
-                    let senders = lock!(senders);
-                    let iter = senders.iter();
-                    for (tx, _) in iter {
-                        tx.send_spawn(ClipboardEvent::Add(item.clone()));
-                    }
+                for item in storage.load().await {
+                    add_item(&item, &senders, &cache);
+                }
 
-                    lock!(cache).insert(item, senders.len());
+                if let Some(item) = item {
+                    handle_item(item, &senders, &cache, &storage);
                 }
 
                 while let Ok(item) = rx.recv().await {
                     debug!("Received clipboard item (ID: {})", item.id);
-
-                    let (existing_id, cache_size) = {
-                        let cache = lock!(cache);
-                        (cache.contains(&item), cache.len())
-                    };
-
-                    existing_id.map_or_else(
-                        || {
-                            {
-                                let mut cache = lock!(cache);
-                                let senders = lock!(senders);
-                                cache.insert(item.clone(), senders.len());
-                            }
-                            let senders = lock!(senders);
-                            let iter = senders.iter();
-                            for (tx, sender_cache_size) in iter {
-                                if cache_size == *sender_cache_size {
-                                    let removed_id = lock!(cache)
-                                        .remove_ref_first()
-                                        .expect("Clipboard cache unexpectedly empty");
-
-                                    tx.send_spawn(ClipboardEvent::Remove(removed_id));
-                                }
-                                tx.send_spawn(ClipboardEvent::Add(item.clone()));
-                            }
-                        },
-                        |existing_id| {
-                            let senders = lock!(senders);
-                            let iter = senders.iter();
-                            for (tx, _) in iter {
-                                tx.send_spawn(ClipboardEvent::Activate(existing_id));
-                            }
-                        },
-                    );
+                    handle_item(item, &senders, &cache, &storage);
                 }
             });
         }
@@ -97,16 +74,19 @@ impl Client {
             wayland: wl,
             senders,
             cache,
+            storage,
         }
     }
 
+    /// Subscribes to the clipboard history,
+    /// initially receiving up to `cache_size` of the newest items.
     pub fn subscribe(&self, cache_size: usize) -> mpsc::Receiver<ClipboardEvent> {
         let (tx, rx) = mpsc::channel(16);
 
         {
             let cache = lock!(self.cache);
 
-            let iter = cache.iter();
+            let iter = cache.iter().skip(cache.len().saturating_sub(cache_size));
             for (_, (item, _)) in iter {
                 tx.send_spawn(ClipboardEvent::Add(item.clone()));
             }
@@ -138,6 +118,7 @@ impl Client {
 
     pub fn remove(&self, id: usize) {
         lock!(self.cache).remove(id);
+        self.storage.remove(id);
 
         let senders = lock!(self.senders);
         let iter = senders.iter();
@@ -147,6 +128,67 @@ impl Client {
     }
 }
 
+/// Adds a newly copied item to the history and saves it,
+/// unless it is excluded.
+///
+/// If an identical item already exists, that is activated instead.
+fn handle_item(
+    item: ClipboardItem,
+    senders: &Senders,
+    cache: &Arc<Mutex<ClipboardCache>>,
+    storage: &Storage,
+) {
+    if storage.is_excluded(&item) {
+        debug!("Ignoring excluded clipboard item (ID: {})", item.id);
+
+        let senders = lock!(senders);
+        let iter = senders.iter();
+        for (tx, _) in iter {
+            tx.send_spawn(ClipboardEvent::Deactivate);
+        }
+
+        return;
+    }
+
+    let existing_id = lock!(cache).contains(&item);
+
+    if let Some(existing_id) = existing_id {
+        let senders = lock!(senders);
+        let iter = senders.iter();
+        for (tx, _) in iter {
+            tx.send_spawn(ClipboardEvent::Activate(existing_id));
+        }
+    } else {
+        add_item(&item, senders, cache);
+        storage.store(&item);
+    }
+}
+
+/// Inserts the item into the cache and sends it to each subscriber,
+/// removing the oldest item for subscribers whose history is full.
+fn add_item(item: &ClipboardItem, senders: &Senders, cache: &Arc<Mutex<ClipboardCache>>) {
+    let cache_size = lock!(cache).len();
+
+    {
+        let mut cache = lock!(cache);
+        let senders = lock!(senders);
+        cache.insert(item.clone(), senders.len());
+    }
+
+    let senders = lock!(senders);
+    let iter = senders.iter();
+    for (tx, sender_cache_size) in iter {
+        if cache_size == *sender_cache_size {
+            let removed_id = lock!(cache)
+                .remove_ref_first()
+                .expect("Clipboard cache unexpectedly empty");
+
+            tx.send_spawn(ClipboardEvent::Remove(removed_id));
+        }
+        tx.send_spawn(ClipboardEvent::Add(item.clone()));
+    }
+}
+
 /// Shared clipboard item cache.
 ///
 /// Items are stored with a number of references,
@@ -232,5 +274,3 @@ impl ClipboardCache {
         self.cache.iter()
     }
 }
-
-register_client!(Client, clipboard);
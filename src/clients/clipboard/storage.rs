@@ -0,0 +1,314 @@
+use super::cliphist;
+use crate::clients::wayland::ClipboardItem;
+use crate::{lock, spawn};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::fs;
+use tracing::{debug, error, warn};
+
+/// Where to save the clipboard history between restarts.
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum StorageBackend {
+    /// The history is lost on restart.
+    #[default]
+    None,
+    /// Items are saved as files in Ironbar's data directory.
+    File,
+    /// Items are read from and saved to [cliphist](https://github.com/sentriz/cliphist).
+    Cliphist,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct StorageConfig {
+    /// Where to save the clipboard history between restarts.
+    ///
+    /// **Valid options**: `none`, `file`, `cliphist`
+    /// <br>
+    /// **Default**: `none`
+    pub backend: StorageBackend,
+
+    /// The maximum number of items to save.
+    /// The oldest items are deleted once this is exceeded.
+    ///
+    /// cliphist limits its own history,
+    /// so this only limits the number of items loaded from it.
+    ///
+    /// **Default**: `100`
+    pub max_entries: usize,
+
+    /// The maximum size of an item to save, in bytes.
+    /// Larger items are still shown until restart.
+    ///
+    /// **Default**: `5000000`
+    pub max_entry_size: usize,
+
+    /// Mime types which cause an item to be ignored entirely
+    /// if the copying application offers them.
+    /// Use a trailing `*` to match any mime type with the prefix, such as `image/*`.
+    ///
+    /// The default ignores passwords copied from password managers.
+    ///
+    /// **Default**: `["x-kde-passwordManagerHint"]`
+    pub exclude_mime_types: Vec<String>,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: StorageBackend::default(),
+            max_entries: 100,
+            max_entry_size: 5_000_000,
+            exclude_mime_types: vec!["x-kde-passwordManagerHint".to_string()],
+        }
+    }
+}
+
+/// Persists clipboard items using the configured backend.
+///
+/// Each backend identifies saved items by its own key,
+/// which is tracked against the ID of the loaded or stored item.
+#[derive(Debug)]
+pub struct Storage {
+    config: StorageConfig,
+    directory: Option<PathBuf>,
+    keys: Arc<Mutex<HashMap<usize, String>>>,
+}
+
+impl Storage {
+    pub fn new(config: StorageConfig) -> Self {
+        let directory = if config.backend == StorageBackend::File {
+            let directory = dirs::data_dir().map(|dir| dir.join("ironbar").join("clipboard"));
+            if directory.is_none() {
+                warn!("Missing XDG data dir, clipboard history will not be saved");
+            }
+            directory
+        } else {
+            None
+        };
+
+        Self {
+            config,
+            directory,
+            keys: Arc::default(),
+        }
+    }
+
+    /// Checks whether the item offers any of the excluded mime types.
+    pub fn is_excluded(&self, item: &ClipboardItem) -> bool {
+        item.offered_mime_types.iter().any(|mime_type| {
+            self.config
+                .exclude_mime_types
+                .iter()
+                .any(|pattern| mime_type_matches(pattern, mime_type))
+        })
+    }
+
+    /// Loads the saved items, oldest first.
+    pub async fn load(&self) -> Vec<ClipboardItem> {
+        let entries = match self.config.backend {
+            StorageBackend::None => return vec![],
+            StorageBackend::File => match &self.directory {
+                Some(directory) => load_files(directory).await,
+                None => return vec![],
+            },
+            StorageBackend::Cliphist => cliphist::load(self.config.max_entries).await,
+        };
+
+        let entries = match entries {
+            Ok(entries) => entries,
+            Err(err) => {
+                error!("Failed to load clipboard history: {err:?}");
+                return vec![];
+            }
+        };
+
+        let skip = entries.len().saturating_sub(self.config.max_entries);
+        let mut keys = lock!(self.keys);
+
+        entries
+            .into_iter()
+            .skip(skip)
+            .filter_map(|(key, mime_type, bytes)| {
+                let item = ClipboardItem::from_bytes(&mime_type, &bytes)?;
+                keys.insert(item.id, key);
+                Some(item)
+            })
+            .collect()
+    }
+
+    /// Saves the item in the background,
+    /// unless it is too large.
+    pub fn store(&self, item: &ClipboardItem) {
+        let Some(bytes) = item.bytes() else {
+            return;
+        };
+
+        if bytes.len() > self.config.max_entry_size {
+            debug!(
+                "Not saving clipboard item {} ({} bytes)",
+                item.id,
+                bytes.len()
+            );
+            return;
+        }
+
+        let id = item.id;
+        let bytes = bytes.to_vec();
+        let mime_type = item.mime_type.clone();
+        let keys = self.keys.clone();
+
+        match self.config.backend {
+            StorageBackend::None => {}
+            StorageBackend::File => {
+                let Some(directory) = self.directory.clone() else {
+                    return;
+                };
+                let max_entries = self.config.max_entries;
+
+                spawn(async move {
+                    match store_file(&directory, &mime_type, &bytes, max_entries).await {
+                        Ok(key) => {
+                            lock!(keys).insert(id, key);
+                        }
+                        Err(err) => error!("Failed to save clipboard item: {err:?}"),
+                    }
+                });
+            }
+            StorageBackend::Cliphist => {
+                spawn(async move {
+                    match cliphist::store(&bytes).await {
+                        Ok(key) => {
+                            lock!(keys).insert(id, key);
+                        }
+                        Err(err) => error!("Failed to save clipboard item: {err:?}"),
+                    }
+                });
+            }
+        }
+    }
+
+    /// Deletes the saved copy of the item with the ID in the background,
+    /// if there is one.
+    pub fn remove(&self, id: usize) {
+        let Some(key) = lock!(self.keys).remove(&id) else {
+            return;
+        };
+
+        let directory = self.directory.clone();
+        let backend = self.config.backend;
+
+        spawn(async move {
+            let res = match (backend, directory) {
+                (StorageBackend::File, Some(directory)) => {
+                    fs::remove_file(directory.join(key)).await
+                }
+                (StorageBackend::Cliphist, _) => cliphist::remove(&key).await,
+                _ => Ok(()),
+            };
+
+            if let Err(err) = res {
+                error!("Failed to delete saved clipboard item: {err:?}");
+            }
+        });
+    }
+}
+
+/// Checks whether the mime type matches the pattern,
+/// which may end in a `*` wildcard.
+fn mime_type_matches(pattern: &str, mime_type: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => mime_type
+            .get(..prefix.len())
+            .is_some_and(|start| start.eq_ignore_ascii_case(prefix)),
+        None => mime_type.eq_ignore_ascii_case(pattern),
+    }
+}
+
+/// A saved item's key, mime type and raw value.
+pub type Entry = (String, String, Vec<u8>);
+
+/// Loads every item saved in the directory, oldest first.
+///
+/// Each item is saved in a file named with the time it was copied,
+/// which contains its mime type on the first line followed by the raw value.
+async fn load_files(directory: &Path) -> io::Result<Vec<Entry>> {
+    let mut keys = saved_keys(directory).await?;
+    keys.sort_unstable_by_key(|(time, _)| *time);
+
+    let mut entries = Vec::with_capacity(keys.len());
+
+    for (_, key) in keys {
+        let contents = fs::read(directory.join(&key)).await?;
+
+        if let Some(index) = contents.iter().position(|&b| b == b'\n') {
+            let mime_type = String::from_utf8_lossy(&contents[..index]).to_string();
+            entries.push((key, mime_type, contents[index + 1..].to_vec()));
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Saves the item to a new file in the directory,
+/// deleting the oldest files beyond `max_entries`.
+async fn store_file(
+    directory: &Path,
+    mime_type: &str,
+    bytes: &[u8],
+    max_entries: usize,
+) -> io::Result<String> {
+    fs::create_dir_all(directory).await?;
+
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let key = time.to_string();
+
+    let mut contents = Vec::with_capacity(mime_type.len() + 1 + bytes.len());
+    contents.extend_from_slice(mime_type.as_bytes());
+    contents.push(b'\n');
+    contents.extend_from_slice(bytes);
+
+    fs::write(directory.join(&key), contents).await?;
+
+    let mut keys = saved_keys(directory).await?;
+    if keys.len() > max_entries {
+        keys.sort_unstable_by_key(|(time, _)| *time);
+
+        for (_, key) in &keys[..keys.len() - max_entries] {
+            fs::remove_file(directory.join(key)).await?;
+        }
+    }
+
+    Ok(key)
+}
+
+/// Gets the names of the saved item files,
+/// alongside the times they are named with.
+async fn saved_keys(directory: &Path) -> io::Result<Vec<(u128, String)>> {
+    let mut keys = vec![];
+
+    let mut dir = match fs::read_dir(directory).await {
+        Ok(dir) => dir,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(keys),
+        Err(err) => return Err(err),
+    };
+
+    while let Some(entry) = dir.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if let Ok(time) = name.parse() {
+            keys.push((time, name));
+        }
+    }
+
+    Ok(keys)
+}
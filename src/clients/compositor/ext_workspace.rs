@@ -0,0 +1,41 @@
+//! Implements the workspace client on top of the Wayland client,
+//! using the `ext-workspace` protocol.
+//! This is used as a fallback for compositors without their own client.
+
+use super::{
+    Error, Result, VisibleWindows, WindowMatch, Workspace, WorkspaceClient, WorkspaceUpdate,
+    WorkspaceWindows,
+};
+use crate::clients::wayland;
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+
+impl WorkspaceClient for wayland::Client {
+    fn focus(&self, id: i64) {
+        debug!("activating workspace with id: {id}");
+        self.workspace_activate(id);
+    }
+
+    fn move_window(&self, _window: &WindowMatch, _workspace_id: i64) {
+        warn!("Moving windows between workspaces is not supported by the ext-workspace protocol");
+    }
+
+    fn subscribe(&self) -> broadcast::Receiver<WorkspaceUpdate> {
+        self.subscribe_workspaces()
+    }
+
+    fn workspaces(&self) -> Vec<Workspace> {
+        self.workspace_info_all()
+    }
+
+    fn subscribe_windows(&self) -> Result<broadcast::Receiver<WorkspaceWindows>> {
+        Err(Error::Unsupported(
+            "workspace windows",
+            &["sway", "hyprland", "niri"],
+        ))
+    }
+
+    fn subscribe_visible_windows(&self) -> Result<broadcast::Receiver<VisibleWindows>> {
+        Err(Error::Unsupported("visible windows", &["sway", "hyprland"]))
+    }
+}
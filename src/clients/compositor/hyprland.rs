@@ -3,20 +3,33 @@ use super::{BindModeClient, BindModeUpdate};
 #[cfg(feature = "keyboard+hyprland")]
 use super::{KeyboardLayoutClient, KeyboardLayoutUpdate};
 use super::{Visibility, Workspace};
+#[cfg(feature = "focused+hyprland")]
+use super::{XWaylandClient, XWaylandUpdate};
 use crate::channels::SyncSenderExt;
+use crate::clients::reconnect;
 use crate::{arc_mut, lock, spawn_blocking};
 use hyprland::Result;
 use hyprland::ctl::switch_xkb_layout;
+#[cfg(feature = "keyboard+hyprland")]
+use hyprland::data::Keyboard;
 use hyprland::data::{Devices, Workspace as HWorkspace, Workspaces};
-use hyprland::dispatch::{Dispatch, DispatchType, WorkspaceIdentifierWithSpecial};
+use hyprland::dispatch::{
+    Dispatch, DispatchType, WindowIdentifier, WorkspaceIdentifierWithSpecial,
+};
 use hyprland::event_listener::EventListener;
 use hyprland::prelude::*;
+#[cfg(feature = "keyboard+hyprland")]
+use hyprland::shared::Address;
 use hyprland::shared::{HyprDataVec, WorkspaceType};
+#[cfg(feature = "keyboard+hyprland")]
+use std::collections::HashMap;
+#[cfg(feature = "keyboard+hyprland")]
+use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast::{Receiver, Sender, channel};
 use tracing::{debug, error, info, warn};
 
 #[cfg(feature = "workspaces")]
-use super::WorkspaceUpdate;
+use super::{VisibleWindows, WindowMatch, WindowRect, WorkspaceUpdate, WorkspaceWindows};
 
 #[derive(Debug)]
 struct TxRx<T> {
@@ -30,16 +43,41 @@ impl<T: Clone> TxRx<T> {
     }
 }
 
+/// Tracks the layout used in each window
+/// for per-window keyboard layouts.
+#[cfg(feature = "keyboard+hyprland")]
+#[derive(Debug, Default)]
+struct WindowLayouts {
+    enabled: bool,
+    focused: Option<Address>,
+    /// The index of the active layout.
+    current: Option<usize>,
+    /// The index of the layout last used in each window.
+    windows: HashMap<Address, usize>,
+}
+
 #[derive(Debug)]
 pub struct Client {
     #[cfg(feature = "workspaces+hyprland")]
     workspace: TxRx<WorkspaceUpdate>,
 
+    #[cfg(feature = "workspaces+hyprland")]
+    workspace_windows: TxRx<WorkspaceWindows>,
+
+    #[cfg(feature = "workspaces+hyprland")]
+    visible_windows: TxRx<VisibleWindows>,
+
     #[cfg(feature = "keyboard+hyprland")]
     keyboard_layout: TxRx<KeyboardLayoutUpdate>,
 
+    #[cfg(feature = "keyboard+hyprland")]
+    window_layouts: Arc<Mutex<WindowLayouts>>,
+
     #[cfg(feature = "bindmode+hyprland")]
     bindmode: TxRx<BindModeUpdate>,
+
+    #[cfg(feature = "focused+hyprland")]
+    xwayland: TxRx<XWaylandUpdate>,
 }
 
 impl Client {
@@ -47,10 +85,18 @@ impl Client {
         let instance = Self {
             #[cfg(feature = "workspaces+hyprland")]
             workspace: TxRx::new(),
+            #[cfg(feature = "workspaces+hyprland")]
+            workspace_windows: TxRx::new(),
+            #[cfg(feature = "workspaces+hyprland")]
+            visible_windows: TxRx::new(),
             #[cfg(feature = "keyboard+hyprland")]
             keyboard_layout: TxRx::new(),
+            #[cfg(feature = "keyboard+hyprland")]
+            window_layouts: arc_mut!(WindowLayouts::default()),
             #[cfg(feature = "bindmode+hyprland")]
             bindmode: TxRx::new(),
+            #[cfg(feature = "focused+hyprland")]
+            xwayland: TxRx::new(),
         };
 
         instance.listen_events();
@@ -63,31 +109,68 @@ impl Client {
         #[cfg(feature = "workspaces+hyprland")]
         let workspace_tx = self.workspace.tx.clone();
 
+        #[cfg(feature = "workspaces+hyprland")]
+        let workspace_windows_tx = self.workspace_windows.tx.clone();
+
+        #[cfg(feature = "workspaces+hyprland")]
+        let visible_windows_tx = self.visible_windows.tx.clone();
+
         #[cfg(feature = "keyboard+hyprland")]
         let keyboard_layout_tx = self.keyboard_layout.tx.clone();
 
+        #[cfg(feature = "keyboard+hyprland")]
+        let window_layouts = self.window_layouts.clone();
+
         #[cfg(feature = "bindmode+hyprland")]
         let bindmode_tx = self.bindmode.tx.clone();
 
+        #[cfg(feature = "focused+hyprland")]
+        let xwayland_tx = self.xwayland.tx.clone();
+
         spawn_blocking(move || {
-            let mut event_listener = EventListener::new();
+            reconnect::run_blocking("hyprland", |session| {
+                // re-send the full state, which also checks the socket is available
+                #[cfg(feature = "workspaces+hyprland")]
+                workspace_tx.send_expect(WorkspaceUpdate::Init(Self::get_workspaces()?));
+                #[cfg(feature = "workspaces+hyprland")]
+                workspace_windows_tx.send_expect(Self::get_workspace_windows()?);
+                #[cfg(feature = "workspaces+hyprland")]
+                visible_windows_tx.send_expect(Self::get_visible_windows()?);
+                #[cfg(not(feature = "workspaces+hyprland"))]
+                Workspaces::get()?;
+
+                let mut event_listener = EventListener::new();
+
+                // we need a lock to ensure events don't run at the same time
+                let lock = arc_mut!(());
+
+                // cache the active workspace since Hyprland doesn't give us the prev active
+                #[cfg(feature = "workspaces+hyprland")]
+                Self::listen_workspace_events(&workspace_tx, &mut event_listener, &lock);
+
+                #[cfg(feature = "workspaces+hyprland")]
+                Self::listen_window_events(&workspace_windows_tx, &mut event_listener, &lock);
+
+                #[cfg(feature = "workspaces+hyprland")]
+                Self::listen_visible_window_events(&visible_windows_tx, &mut event_listener, &lock);
+
+                #[cfg(feature = "keyboard+hyprland")]
+                Self::listen_keyboard_events(
+                    &keyboard_layout_tx,
+                    &window_layouts,
+                    &mut event_listener,
+                    &lock,
+                );
 
-            // we need a lock to ensure events don't run at the same time
-            let lock = arc_mut!(());
+                #[cfg(feature = "bindmode+hyprland")]
+                Self::listen_bindmode_events(&bindmode_tx, &mut event_listener, &lock);
 
-            // cache the active workspace since Hyprland doesn't give us the prev active
-            #[cfg(feature = "workspaces+hyprland")]
-            Self::listen_workspace_events(&workspace_tx, &mut event_listener, &lock);
+                #[cfg(feature = "focused+hyprland")]
+                Self::listen_xwayland_events(&xwayland_tx, &mut event_listener, &lock);
 
-            #[cfg(feature = "keyboard+hyprland")]
-            Self::listen_keyboard_events(&keyboard_layout_tx, &mut event_listener, &lock);
-
-            #[cfg(feature = "bindmode+hyprland")]
-            Self::listen_bindmode_events(&bindmode_tx, &mut event_listener, &lock);
-
-            if let Err(err) = event_listener.start_listener() {
-                error!("Failed to start listener: {err:#}");
-            }
+                session.connected();
+                event_listener.start_listener()
+            });
         });
     }
 
@@ -284,13 +367,151 @@ impl Client {
         }
     }
 
+    #[cfg(feature = "workspaces+hyprland")]
+    fn listen_window_events(
+        tx: &Sender<WorkspaceWindows>,
+        event_listener: &mut EventListener,
+        lock: &std::sync::Arc<std::sync::Mutex<()>>,
+    ) {
+        // events only include the window address,
+        // so the full list of clients is re-fetched for each
+        let send_windows = {
+            let tx = tx.clone();
+            let lock = lock.clone();
+
+            move || {
+                let _lock = lock!(lock);
+
+                match Self::get_workspace_windows() {
+                    Ok(windows) => tx.send_expect(windows),
+                    Err(err) => error!("Failed to get windows: {err:#?}"),
+                }
+            }
+        };
+
+        {
+            let send_windows = send_windows.clone();
+            event_listener.add_window_opened_handler(move |event| {
+                debug!("Window opened: {:?}", event.window_address);
+                send_windows();
+            });
+        }
+
+        {
+            let send_windows = send_windows.clone();
+            event_listener.add_window_closed_handler(move |address| {
+                debug!("Window closed: {address:?}");
+                send_windows();
+            });
+        }
+
+        event_listener.add_window_moved_handler(move |event| {
+            debug!("Window moved: {:?}", event.window_address);
+            send_windows();
+        });
+    }
+
+    #[cfg(feature = "workspaces+hyprland")]
+    fn listen_visible_window_events(
+        tx: &Sender<VisibleWindows>,
+        event_listener: &mut EventListener,
+        lock: &std::sync::Arc<std::sync::Mutex<()>>,
+    ) {
+        // there is no event for a window being resized,
+        // so the windows are also re-fetched whenever focus changes
+        let send_windows = {
+            let tx = tx.clone();
+            let lock = lock.clone();
+
+            move || {
+                let _lock = lock!(lock);
+
+                match Self::get_visible_windows() {
+                    Ok(windows) => tx.send_expect(windows),
+                    Err(err) => error!("Failed to get visible windows: {err:#?}"),
+                }
+            }
+        };
+
+        {
+            let send_windows = send_windows.clone();
+            event_listener.add_window_opened_handler(move |_| send_windows());
+        }
+
+        {
+            let send_windows = send_windows.clone();
+            event_listener.add_window_closed_handler(move |_| send_windows());
+        }
+
+        {
+            let send_windows = send_windows.clone();
+            event_listener.add_window_moved_handler(move |_| send_windows());
+        }
+
+        {
+            let send_windows = send_windows.clone();
+            event_listener.add_float_state_changed_handler(move |_| send_windows());
+        }
+
+        {
+            let send_windows = send_windows.clone();
+            event_listener.add_workspace_changed_handler(move |_| send_windows());
+        }
+
+        event_listener.add_active_window_changed_handler(move |_| send_windows());
+    }
+
     #[cfg(feature = "keyboard+hyprland")]
     fn listen_keyboard_events(
         keyboard_layout_tx: &Sender<KeyboardLayoutUpdate>,
+        window_layouts: &Arc<Mutex<WindowLayouts>>,
         event_listener: &mut EventListener,
         lock: &std::sync::Arc<std::sync::Mutex<()>>,
     ) {
         let tx = keyboard_layout_tx.clone();
+
+        {
+            let window_layouts = window_layouts.clone();
+            let lock = lock.clone();
+
+            event_listener.add_active_window_changed_handler(move |window| {
+                let _lock = lock!(lock);
+                let mut state = lock!(window_layouts);
+
+                state.focused = window.map(|window| window.address);
+
+                let Some(address) = state.focused.clone().filter(|_| state.enabled) else {
+                    return;
+                };
+
+                match state.windows.get(&address).copied() {
+                    Some(index) if state.current != Some(index) => {
+                        drop(state);
+                        debug!("Restoring layout {index} for window {address}");
+                        Self::switch_layout_index(index);
+                    }
+                    Some(_) => {}
+                    // newly focused windows keep the current layout
+                    None => {
+                        if let Some(current) = state.current {
+                            state.windows.insert(address, current);
+                        }
+                    }
+                }
+            });
+        }
+
+        {
+            let window_layouts = window_layouts.clone();
+            let lock = lock.clone();
+
+            event_listener.add_window_closed_handler(move |address| {
+                let _lock = lock!(lock);
+                lock!(window_layouts).windows.remove(&address);
+            });
+        }
+
+        let window_layouts = window_layouts.clone();
         let lock = lock.clone();
 
         event_listener.add_layout_changed_handler(move |layout_event| {
@@ -333,7 +554,26 @@ impl Client {
             };
 
             debug!("Received layout: {layout:?}");
-            tx.send_expect(KeyboardLayoutUpdate(layout));
+
+            let update = KeyboardLayoutUpdate {
+                name: layout,
+                layouts: Self::get_main_keyboard()
+                    .map(|keyboard| layout_names(&keyboard))
+                    .unwrap_or_default(),
+            };
+
+            {
+                let mut state = lock!(window_layouts);
+                state.current = update.index();
+
+                if state.enabled
+                    && let (Some(address), Some(index)) = (state.focused.clone(), state.current)
+                {
+                    state.windows.insert(address, index);
+                }
+            }
+
+            tx.send_expect(update);
         });
     }
 
@@ -357,6 +597,38 @@ impl Client {
         });
     }
 
+    #[cfg(feature = "focused+hyprland")]
+    fn listen_xwayland_events(
+        xwayland_tx: &Sender<XWaylandUpdate>,
+        event_listener: &mut EventListener,
+        lock: &std::sync::Arc<std::sync::Mutex<()>>,
+    ) {
+        let tx = xwayland_tx.clone();
+        let lock = lock.clone();
+
+        event_listener.add_active_window_changed_handler(move |window| {
+            let _lock = lock!(lock);
+
+            // the event does not include whether the window is an XWayland client
+            let xwayland = window.is_some() && Self::is_active_xwayland();
+
+            debug!("Received active window change (xwayland: {xwayland})");
+            tx.send_expect(XWaylandUpdate(xwayland));
+        });
+    }
+
+    /// Gets whether the active window is an XWayland client.
+    #[cfg(feature = "focused+hyprland")]
+    fn is_active_xwayland() -> bool {
+        match hyprland::data::Client::get_active() {
+            Ok(client) => client.is_some_and(|client| client.xwayland),
+            Err(err) => {
+                error!("Failed to get active window: {err:#?}");
+                false
+            }
+        }
+    }
+
     /// Sends a `WorkspaceUpdate::Focus` event
     /// and updates the active workspace cache.
     #[cfg(feature = "workspaces+hyprland")]
@@ -401,6 +673,63 @@ impl Client {
         let w = HWorkspace::get_active().map(|w| Workspace::from((Visibility::focused(), w)))?;
         Ok(w)
     }
+
+    /// Gets all workspaces from the server.
+    #[cfg(feature = "workspaces+hyprland")]
+    fn get_workspaces() -> Result<Vec<Workspace>> {
+        let active_id = HWorkspace::get_active().ok().map(|active| active.name);
+        let is_visible = create_is_visible();
+
+        let workspaces = Workspaces::get()?
+            .into_iter()
+            .map(|w| {
+                let vis = Visibility::from((&w, active_id.as_deref(), &is_visible));
+                Workspace::from((vis, w))
+            })
+            .collect();
+
+        Ok(workspaces)
+    }
+
+    /// Gets the windows on each workspace from the server.
+    #[cfg(feature = "workspaces+hyprland")]
+    fn get_workspace_windows() -> Result<WorkspaceWindows> {
+        let windows = hyprland::data::Clients::get()?
+            .into_iter()
+            .filter(|client| client.mapped)
+            .map(|client| (client.workspace.id as i64, client.class))
+            .collect();
+
+        Ok(windows)
+    }
+
+    /// Gets the windows on the visible workspaces of each monitor from the server.
+    #[cfg(feature = "workspaces+hyprland")]
+    fn get_visible_windows() -> Result<VisibleWindows> {
+        let clients = hyprland::data::Clients::get()?;
+
+        let windows = hyprland::data::Monitors::get()?
+            .into_iter()
+            .map(|monitor| {
+                let workspaces = [monitor.active_workspace.id, monitor.special_workspace.id];
+
+                let windows = clients
+                    .iter()
+                    .filter(|client| client.mapped && workspaces.contains(&client.workspace.id))
+                    .map(|client| WindowRect {
+                        x: client.at.0.into(),
+                        y: client.at.1.into(),
+                        width: client.size.0.into(),
+                        height: client.size.1.into(),
+                    })
+                    .collect();
+
+                (monitor.name, windows)
+            })
+            .collect();
+
+        Ok(VisibleWindows(windows))
+    }
 }
 
 #[cfg(feature = "workspaces+hyprland")]
@@ -413,22 +742,36 @@ impl super::WorkspaceClient for Client {
         }
     }
 
+    fn move_window(&self, window: &WindowMatch, workspace_id: i64) {
+        let clients = match hyprland::data::Clients::get() {
+            Ok(clients) => clients,
+            Err(e) => {
+                error!("Failed to get windows: {e:#}");
+                return;
+            }
+        };
+
+        for client in clients
+            .into_iter()
+            .filter(|client| window.matches(&client.class, &client.title))
+        {
+            let identifier = WorkspaceIdentifierWithSpecial::Id(workspace_id as i32);
+            let window = WindowIdentifier::Address(client.address);
+
+            if let Err(e) = Dispatch::call(DispatchType::MoveToWorkspaceSilent(
+                identifier,
+                Some(window),
+            )) {
+                error!("Couldn't move window to workspace '{workspace_id}': {e:#}");
+            }
+        }
+    }
+
     fn subscribe(&self) -> Receiver<WorkspaceUpdate> {
         let rx = self.workspace.tx.subscribe();
 
-        let active_id = HWorkspace::get_active().ok().map(|active| active.name);
-        let is_visible = create_is_visible();
-
-        match Workspaces::get() {
+        match Self::get_workspaces() {
             Ok(workspaces) => {
-                let workspaces = workspaces
-                    .into_iter()
-                    .map(|w| {
-                        let vis = Visibility::from((&w, active_id.as_deref(), &is_visible));
-                        Workspace::from((vis, w))
-                    })
-                    .collect();
-
                 self.workspace
                     .tx
                     .send_expect(WorkspaceUpdate::Init(workspaces));
@@ -440,56 +783,168 @@ impl super::WorkspaceClient for Client {
 
         rx
     }
+
+    fn workspaces(&self) -> Vec<Workspace> {
+        Self::get_workspaces().unwrap_or_else(|e| {
+            error!("Failed to get workspaces: {e:#}");
+            vec![]
+        })
+    }
+
+    fn subscribe_windows(&self) -> super::Result<Receiver<WorkspaceWindows>> {
+        let rx = self.workspace_windows.tx.subscribe();
+
+        match Self::get_workspace_windows() {
+            Ok(windows) => self.workspace_windows.tx.send_expect(windows),
+            Err(e) => error!("Failed to get windows: {e:#}"),
+        }
+
+        Ok(rx)
+    }
+
+    fn subscribe_visible_windows(&self) -> super::Result<Receiver<VisibleWindows>> {
+        let rx = self.visible_windows.tx.subscribe();
+
+        match Self::get_visible_windows() {
+            Ok(windows) => self.visible_windows.tx.send_expect(windows),
+            Err(e) => error!("Failed to get visible windows: {e:#}"),
+        }
+
+        Ok(rx)
+    }
 }
 
 #[cfg(feature = "keyboard+hyprland")]
-impl KeyboardLayoutClient for Client {
-    fn set_next_active(&self) {
-        let Ok(devices) = Devices::get() else {
-            error!("Failed to get devices");
-            return;
-        };
+impl Client {
+    /// Gets the main keyboard device.
+    fn get_main_keyboard() -> Option<Keyboard> {
+        match Devices::get() {
+            Ok(devices) => devices.keyboards.into_iter().find(|k| k.main),
+            Err(err) => {
+                error!("Failed to get devices: {err:#?}");
+                None
+            }
+        }
+    }
 
-        let device = devices
-            .keyboards
-            .iter()
-            .find(|k| k.main)
-            .map(|k| k.name.clone());
+    fn switch_layout_index(index: usize) {
+        match u8::try_from(index) {
+            Ok(index) => Self::switch_layout(switch_xkb_layout::SwitchXKBLayoutCmdTypes::Id(index)),
+            Err(_) => error!("Invalid keyboard layout index: {index}"),
+        }
+    }
 
-        if let Some(device) = device {
-            if let Err(e) =
-                switch_xkb_layout::call(device, switch_xkb_layout::SwitchXKBLayoutCmdTypes::Next)
-            {
+    fn switch_layout(cmd: switch_xkb_layout::SwitchXKBLayoutCmdTypes) {
+        if let Some(device) = Self::get_main_keyboard() {
+            if let Err(e) = switch_xkb_layout::call(device.name, cmd) {
                 error!("Failed to switch keyboard layout due to Hyprland error: {e}");
             }
         } else {
             error!("Failed to get keyboard device from hyprland");
         }
     }
+}
+
+#[cfg(feature = "keyboard+hyprland")]
+impl KeyboardLayoutClient for Client {
+    fn set_next_active(&self) {
+        Self::switch_layout(switch_xkb_layout::SwitchXKBLayoutCmdTypes::Next);
+    }
+
+    fn set_active(&self, index: usize) {
+        Self::switch_layout_index(index);
+    }
+
+    fn enable_per_window(&self) -> super::Result<()> {
+        let mut state = lock!(self.window_layouts);
+        state.enabled = true;
+
+        if state.focused.is_none() {
+            state.focused = hyprland::data::Client::get_active()
+                .ok()
+                .flatten()
+                .map(|client| client.address);
+        }
+
+        Ok(())
+    }
 
     fn subscribe(&self) -> Receiver<KeyboardLayoutUpdate> {
         let rx = self.keyboard_layout.tx.subscribe();
 
-        match Devices::get().map(|devices| {
-            devices
-                .keyboards
-                .iter()
-                .find(|k| k.main)
-                .map(|k| k.active_keymap.clone())
-        }) {
-            Ok(Some(layout)) => {
-                self.keyboard_layout
-                    .tx
-                    .send_expect(KeyboardLayoutUpdate(layout));
+        match Self::get_main_keyboard() {
+            Some(keyboard) => {
+                let update = KeyboardLayoutUpdate {
+                    layouts: layout_names(&keyboard),
+                    name: keyboard.active_keymap,
+                };
+
+                lock!(self.window_layouts).current = update.index();
+                self.keyboard_layout.tx.send_expect(update);
             }
-            Ok(None) => error!("Failed to get current keyboard layout hyprland"),
-            Err(err) => error!("Failed to get devices: {err:#?}"),
+            None => error!("Failed to get current keyboard layout hyprland"),
         }
 
         rx
     }
 }
 
+/// Gets the names of the keyboard's configured layouts.
+///
+/// Hyprland only provides the name of the active layout,
+/// and codes for the full list,
+/// so names are looked up from the XKB rules.
+/// Codes are used where a name cannot be found.
+#[cfg(feature = "keyboard+hyprland")]
+fn layout_names(keyboard: &Keyboard) -> Vec<String> {
+    let root =
+        std::env::var("XKB_CONFIG_ROOT").unwrap_or_else(|_| "/usr/share/X11/xkb".to_string());
+    let rules = std::fs::read_to_string(format!("{root}/rules/evdev.lst")).unwrap_or_default();
+
+    // keyed by layout and variant codes
+    let mut names = HashMap::<(&str, &str), &str>::new();
+    let mut section = "";
+
+    for line in rules.lines() {
+        if let Some(header) = line.strip_prefix('!') {
+            section = header.trim();
+            continue;
+        }
+
+        let Some((code, description)) = line.trim().split_once(char::is_whitespace) else {
+            continue;
+        };
+
+        match (section, description.trim()) {
+            ("layout", description) => {
+                names.insert((code, ""), description);
+            }
+            // variants are listed as `code layout: description`
+            ("variant", description) => {
+                if let Some((layout, description)) = description.split_once(": ") {
+                    names.insert((layout, code), description);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut variants = keyboard.variant.split(',');
+
+    keyboard
+        .layout
+        .split(',')
+        .map(|layout| {
+            let layout = layout.trim();
+            let variant = variants.next().unwrap_or_default().trim();
+
+            names
+                .get(&(layout, variant))
+                .map_or_else(|| layout.to_string(), |name| (*name).to_string())
+        })
+        .collect()
+}
+
 #[cfg(feature = "bindmode+hyprland")]
 impl BindModeClient for Client {
     fn subscribe(&self) -> super::Result<Receiver<BindModeUpdate>> {
@@ -497,6 +952,19 @@ impl BindModeClient for Client {
     }
 }
 
+#[cfg(feature = "focused+hyprland")]
+impl XWaylandClient for Client {
+    fn subscribe(&self) -> super::Result<Receiver<XWaylandUpdate>> {
+        let rx = self.xwayland.tx.subscribe();
+
+        self.xwayland
+            .tx
+            .send_expect(XWaylandUpdate(Self::is_active_xwayland()));
+
+        Ok(rx)
+    }
+}
+
 fn get_workspace_name(name: WorkspaceType) -> String {
     match name {
         WorkspaceType::Regular(name) => name,
@@ -1,14 +1,18 @@
 use crate::register_fallible_client;
 use cfg_if::cfg_if;
+#[cfg(feature = "workspaces")]
+use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
 use std::sync::Arc;
 use thiserror::Error;
 use tokio::sync::broadcast;
 use tracing::debug;
 
+#[cfg(feature = "workspaces+ext")]
+mod ext_workspace;
 #[cfg(feature = "hyprland")]
 pub mod hyprland;
-#[cfg(feature = "niri")]
+#[cfg(feature = "workspaces+niri")]
 pub mod niri;
 #[cfg(feature = "sway")]
 pub mod sway;
@@ -45,7 +49,7 @@ impl Display for Compositor {
                 Self::Sway => "Sway",
                 #[cfg(any(feature = "hyprland"))]
                 Self::Hyprland => "Hyprland",
-                #[cfg(feature = "workspaces+niri")]
+                #[cfg(feature = "niri")]
                 Self::Niri => "Niri",
                 Self::Unsupported => "Unsupported",
             }
@@ -56,7 +60,7 @@ impl Display for Compositor {
 impl Compositor {
     /// Attempts to get the current compositor.
     /// This is done by checking system env vars.
-    fn get_current() -> Self {
+    pub(crate) fn get_current() -> Self {
         if std::env::var("SWAYSOCK").is_ok() {
             cfg_if! {
                 if #[cfg(feature = "sway")] { Self::Sway }
@@ -115,6 +119,25 @@ impl Compositor {
         }
     }
 
+    #[cfg(feature = "focused")]
+    pub fn create_xwayland_client(
+        clients: &mut super::Clients,
+    ) -> Result<Arc<dyn XWaylandClient + Send + Sync>> {
+        let current = Self::get_current();
+        debug!("Getting xwayland client for: {current}");
+        match current {
+            #[cfg(feature = "focused+sway")]
+            Self::Sway => Ok(clients.sway().map_err(|err| Error::Other(err.into()))?),
+            #[cfg(feature = "focused+hyprland")]
+            Self::Hyprland => Ok(clients.hyprland()),
+            #[cfg(feature = "niri")]
+            Self::Niri => Err(Error::Unsupported("xwayland", &["sway", "hyprland"])),
+            Self::Unsupported => Err(Error::Unsupported("xwayland", &["sway", "hyprland"])),
+            #[allow(unreachable_patterns)]
+            _ => Err(Error::Disabled("xwayland")),
+        }
+    }
+
     /// Creates a new instance of
     /// the workspace client for the current compositor.
     #[cfg(feature = "workspaces")]
@@ -130,6 +153,9 @@ impl Compositor {
             Self::Hyprland => Ok(clients.hyprland()),
             #[cfg(feature = "workspaces+niri")]
             Self::Niri => Ok(Arc::new(niri::Client::new())),
+            #[cfg(feature = "workspaces+ext")]
+            Self::Unsupported => Ok(clients.wayland()),
+            #[cfg(not(feature = "workspaces+ext"))]
             Self::Unsupported => Err(Error::Unsupported(
                 "workspaces",
                 &["sway", "hyprland", "niri"],
@@ -141,6 +167,7 @@ impl Compositor {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "ipc", derive(serde::Serialize))]
 pub struct Workspace {
     /// Unique identifier
     pub id: i64,
@@ -157,6 +184,8 @@ pub struct Workspace {
 /// Indicates workspace visibility.
 /// Visible workspaces have a boolean flag to indicate if they are also focused.
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "ipc", derive(serde::Serialize))]
+#[cfg_attr(feature = "ipc", serde(rename_all = "snake_case"))]
 pub enum Visibility {
     Visible { focused: bool },
     Hidden,
@@ -186,7 +215,21 @@ impl Visibility {
 
 #[derive(Debug, Clone)]
 #[cfg(feature = "keyboard")]
-pub struct KeyboardLayoutUpdate(pub String);
+pub struct KeyboardLayoutUpdate {
+    /// The name of the active layout.
+    pub name: String,
+    /// The names of all configured layouts, in order.
+    pub layouts: Vec<String>,
+}
+
+#[cfg(feature = "keyboard")]
+impl KeyboardLayoutUpdate {
+    /// Gets the index of the active layout
+    /// in the list of configured layouts.
+    pub fn index(&self) -> Option<usize> {
+        self.layouts.iter().position(|layout| layout == &self.name)
+    }
+}
 
 #[derive(Debug, Clone)]
 #[cfg(feature = "workspaces")]
@@ -221,6 +264,84 @@ pub enum WorkspaceUpdate {
     Unknown,
 }
 
+/// The app IDs of the windows open on each workspace,
+/// keyed by workspace ID.
+#[derive(Debug, Clone, Default)]
+#[cfg(feature = "workspaces")]
+pub struct WorkspaceWindows(pub HashMap<i64, Vec<String>>);
+
+#[cfg(feature = "workspaces")]
+impl FromIterator<(i64, String)> for WorkspaceWindows {
+    fn from_iter<T: IntoIterator<Item = (i64, String)>>(iter: T) -> Self {
+        let mut windows = HashMap::<i64, Vec<String>>::new();
+
+        for (workspace_id, app_id) in iter {
+            windows.entry(workspace_id).or_default().push(app_id);
+        }
+
+        Self(windows)
+    }
+}
+
+/// The position and size of a window
+/// in the compositor's global space, in logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg(feature = "workspaces")]
+pub struct WindowRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+#[cfg(feature = "workspaces")]
+impl WindowRect {
+    /// Checks whether the two rectangles overlap.
+    /// Rectangles which only share an edge do not overlap.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+/// The windows on the visible workspace of each monitor,
+/// keyed by monitor name.
+#[derive(Debug, Clone, Default)]
+#[cfg(feature = "workspaces")]
+pub struct VisibleWindows(pub HashMap<String, Vec<WindowRect>>);
+
+/// Identifies windows by the details
+/// available from the Wayland foreign toplevel protocol,
+/// such as when dragging a window onto a workspace.
+#[derive(Debug, Clone)]
+#[cfg(any(feature = "launcher", feature = "taskbar", feature = "workspaces"))]
+pub struct WindowMatch {
+    pub app_id: String,
+    /// The window title,
+    /// or `None` to match all windows of the application.
+    pub title: Option<String>,
+}
+
+#[cfg(any(feature = "launcher", feature = "taskbar", feature = "workspaces"))]
+impl WindowMatch {
+    /// The key of the widget tag holding the window
+    /// on buttons which can be dragged onto workspaces.
+    pub const TAG: &'static str = "window-match";
+
+    /// Gets whether a window with the app ID and title matches.
+    #[cfg(feature = "workspaces")]
+    pub fn matches(&self, app_id: &str, title: &str) -> bool {
+        self.app_id == app_id && self.title.as_ref().is_none_or(|t| t == title)
+    }
+}
+
+/// Whether the focused window is an XWayland client.
+#[derive(Debug, Clone, Copy)]
+#[cfg(feature = "focused")]
+pub struct XWaylandUpdate(pub bool);
+
 #[derive(Clone, Debug)]
 #[cfg(feature = "bindmode")]
 pub struct BindModeUpdate {
@@ -235,8 +356,27 @@ pub trait WorkspaceClient: Debug + Send + Sync {
     /// Requests the workspace with this id is focused.
     fn focus(&self, id: i64);
 
+    /// Requests the matching windows are moved
+    /// to the workspace with this id.
+    fn move_window(&self, window: &WindowMatch, workspace_id: i64);
+
     /// Creates a new to workspace event receiver.
     fn subscribe(&self) -> broadcast::Receiver<WorkspaceUpdate>;
+
+    /// Gets the current workspaces,
+    /// without notifying other subscribers.
+    fn workspaces(&self) -> Vec<Workspace>;
+
+    /// Creates a new receiver for the windows open on each workspace.
+    /// The current state is sent immediately,
+    /// and again whenever a window opens, closes or moves.
+    fn subscribe_windows(&self) -> Result<broadcast::Receiver<WorkspaceWindows>>;
+
+    /// Creates a new receiver for the windows on the visible workspace of each monitor.
+    /// The current state is sent immediately,
+    /// and again whenever a window opens, closes or moves,
+    /// or the visible workspaces change.
+    fn subscribe_visible_windows(&self) -> Result<broadcast::Receiver<VisibleWindows>>;
 }
 
 #[cfg(feature = "workspaces")]
@@ -247,6 +387,14 @@ pub trait KeyboardLayoutClient: Debug + Send + Sync {
     /// Switches to the next layout.
     fn set_next_active(&self);
 
+    /// Switches to the layout at this index
+    /// in the list of configured layouts.
+    fn set_active(&self, index: usize);
+
+    /// Remembers the layout used in each window,
+    /// switching back to it whenever the window is focused.
+    fn enable_per_window(&self) -> Result<()>;
+
     /// Creates a new to keyboard layout event receiver.
     fn subscribe(&self) -> broadcast::Receiver<KeyboardLayoutUpdate>;
 }
@@ -262,3 +410,13 @@ pub trait BindModeClient: Debug + Send + Sync {
 
 #[cfg(feature = "bindmode")]
 register_fallible_client!(dyn BindModeClient, bindmode);
+
+#[cfg(feature = "focused")]
+pub trait XWaylandClient: Debug + Send + Sync {
+    /// Creates a new receiver for XWayland updates to the focused window.
+    /// The current state is sent immediately.
+    fn subscribe(&self) -> Result<broadcast::Receiver<XWaylandUpdate>>;
+}
+
+#[cfg(feature = "focused")]
+register_fallible_client!(dyn XWaylandClient, xwayland);
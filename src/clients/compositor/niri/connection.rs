@@ -28,7 +28,14 @@ pub enum Response {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum Action {
-    FocusWorkspace { reference: WorkspaceReferenceArg },
+    FocusWorkspace {
+        reference: WorkspaceReferenceArg,
+    },
+    MoveWindowToWorkspace {
+        window_id: Option<u64>,
+        reference: WorkspaceReferenceArg,
+        focus: bool,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -68,11 +75,22 @@ impl From<&Workspace> for IronWorkspace {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Window {
+    pub id: u64,
+    pub title: Option<String>,
+    pub app_id: Option<String>,
+    pub workspace_id: Option<u64>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum Event {
     WorkspacesChanged { workspaces: Vec<Workspace> },
     WorkspaceActivated { id: u64, focused: bool },
     WorkspaceUrgencyChanged { id: u64, urgent: bool },
+    WindowsChanged { windows: Vec<Window> },
+    WindowOpenedOrChanged { window: Window },
+    WindowClosed { id: u64 },
     Other,
 }
 
@@ -110,9 +128,13 @@ impl Connection {
 
         let events = move || {
             buf.clear();
-            await_sync(async {
-                reader.read_line(&mut buf).await.unwrap_or(0);
-            });
+            let read = await_sync(async { reader.read_line(&mut buf).await })?;
+
+            // niri closed the socket
+            if read == 0 {
+                return Err(std::io::ErrorKind::UnexpectedEof.into());
+            }
+
             let event: Event = serde_json::from_str(&buf).unwrap_or(Event::Other);
             Ok(event)
         };
@@ -1,8 +1,13 @@
-use super::{Workspace as IronWorkspace, WorkspaceClient, WorkspaceUpdate};
+use super::{
+    VisibleWindows, WindowMatch, Workspace as IronWorkspace, WorkspaceClient, WorkspaceUpdate,
+    WorkspaceWindows,
+};
 use crate::channels::SyncSenderExt;
 use crate::clients::compositor::Visibility;
+use crate::clients::reconnect;
 use crate::{arc_rw, read_lock, spawn, write_lock};
-use connection::{Action, Connection, Event, Request, WorkspaceReferenceArg};
+use connection::{Action, Connection, Event, Request, Window, WorkspaceReferenceArg};
+use std::collections::BTreeMap;
 use std::sync::{Arc, RwLock};
 use tokio::sync::broadcast;
 use tracing::{debug, error, warn};
@@ -15,6 +20,12 @@ pub struct Client {
     _rx: broadcast::Receiver<WorkspaceUpdate>,
 
     workspaces: Arc<RwLock<Vec<IronWorkspace>>>,
+
+    windows_tx: broadcast::Sender<WorkspaceWindows>,
+    _windows_rx: broadcast::Receiver<WorkspaceWindows>,
+
+    /// Open windows, keyed by ID.
+    windows: Arc<RwLock<BTreeMap<u64, Window>>>,
 }
 
 impl Client {
@@ -25,176 +36,238 @@ impl Client {
         let workspace_state = arc_rw!(vec![]);
         let workspace_state2 = workspace_state.clone();
 
-        spawn(async move {
-            let mut conn = Connection::connect().await?;
-            let (_, mut event_listener) = conn.send(Request::EventStream).await?;
-
-            let mut first_event = true;
-
-            loop {
-                let events = match event_listener() {
-                    Ok(Event::WorkspacesChanged { workspaces }) => {
-                        debug!("WorkspacesChanged: {:?}", workspaces);
-
-                        // Niri only has a WorkspacesChanged Event and Ironbar has 4 events which have to be handled: Add, Remove, Rename and Move.
-                        // This is handled by keeping a previous state of workspaces and comparing with the new state for changes.
-                        let new_workspaces: Vec<IronWorkspace> = workspaces
-                            .into_iter()
-                            .map(|w| IronWorkspace::from(&w))
-                            .collect();
-
-                        let mut updates: Vec<WorkspaceUpdate> = vec![];
-
-                        if first_event {
-                            // Niri's WorkspacesChanged event does not initially sort workspaces by ID when first output,
-                            // which makes sort = added meaningless. Therefore, new_workspaces are sorted by ID here to ensure a consistent addition order.
-                            let mut new_workspaces = new_workspaces.clone();
-                            new_workspaces.sort_by_key(|w| w.id);
-                            updates.push(WorkspaceUpdate::Init(new_workspaces));
-                            first_event = false;
-                        } else {
-                            // first pass - add/update
-                            for workspace in &new_workspaces {
-                                let workspace_state = read_lock!(workspace_state);
-                                let old_workspace = workspace_state
-                                    .iter()
-                                    .find(|&w: &&IronWorkspace| w.id == workspace.id);
-
-                                match old_workspace {
-                                    None => updates.push(WorkspaceUpdate::Add(workspace.clone())),
-                                    Some(old_workspace) => {
-                                        if workspace.name != old_workspace.name {
-                                            updates.push(WorkspaceUpdate::Rename {
-                                                id: workspace.id,
-                                                name: workspace.name.clone(),
-                                            });
+        let (windows_tx, windows_rx) = broadcast::channel(32);
+        let windows_tx2 = windows_tx.clone();
+
+        let window_state = arc_rw!(BTreeMap::new());
+        let window_state2 = window_state.clone();
+
+        spawn(reconnect::run("niri", move |session| {
+            let tx = tx.clone();
+            let workspace_state = workspace_state.clone();
+            let windows_tx = windows_tx.clone();
+            let window_state = window_state.clone();
+
+            async move {
+                let mut conn = Connection::connect().await?;
+                let (_, mut event_listener) = conn.send(Request::EventStream).await?;
+                session.connected();
+
+                // the first event on each connection contains all workspaces
+                let mut first_event = true;
+
+                let err = loop {
+                    let events = match event_listener() {
+                        Ok(Event::WorkspacesChanged { workspaces }) => {
+                            debug!("WorkspacesChanged: {:?}", workspaces);
+
+                            // Niri only has a WorkspacesChanged Event and Ironbar has 4 events which have to be handled: Add, Remove, Rename and Move.
+                            // This is handled by keeping a previous state of workspaces and comparing with the new state for changes.
+                            let new_workspaces: Vec<IronWorkspace> = workspaces
+                                .into_iter()
+                                .map(|w| IronWorkspace::from(&w))
+                                .collect();
+
+                            let mut updates: Vec<WorkspaceUpdate> = vec![];
+
+                            if first_event {
+                                // Niri's WorkspacesChanged event does not initially sort workspaces by ID when first output,
+                                // which makes sort = added meaningless. Therefore, new_workspaces are sorted by ID here to ensure a consistent addition order.
+                                let mut new_workspaces = new_workspaces.clone();
+                                new_workspaces.sort_by_key(|w| w.id);
+                                updates.push(WorkspaceUpdate::Init(new_workspaces));
+                                first_event = false;
+                            } else {
+                                // first pass - add/update
+                                for workspace in &new_workspaces {
+                                    let workspace_state = read_lock!(workspace_state);
+                                    let old_workspace = workspace_state
+                                        .iter()
+                                        .find(|&w: &&IronWorkspace| w.id == workspace.id);
+
+                                    match old_workspace {
+                                        None => {
+                                            updates.push(WorkspaceUpdate::Add(workspace.clone()))
                                         }
-
-                                        if workspace.monitor != old_workspace.monitor
-                                            || workspace.index != old_workspace.index
-                                        {
-                                            updates.push(WorkspaceUpdate::Move(workspace.clone()));
+                                        Some(old_workspace) => {
+                                            if workspace.name != old_workspace.name {
+                                                updates.push(WorkspaceUpdate::Rename {
+                                                    id: workspace.id,
+                                                    name: workspace.name.clone(),
+                                                });
+                                            }
+
+                                            if workspace.monitor != old_workspace.monitor
+                                                || workspace.index != old_workspace.index
+                                            {
+                                                updates
+                                                    .push(WorkspaceUpdate::Move(workspace.clone()));
+                                            }
                                         }
                                     }
                                 }
-                            }
 
-                            // second pass - delete
-                            for workspace in read_lock!(workspace_state).iter() {
-                                let exists = new_workspaces.iter().any(|w| w.id == workspace.id);
+                                // second pass - delete
+                                for workspace in read_lock!(workspace_state).iter() {
+                                    let exists =
+                                        new_workspaces.iter().any(|w| w.id == workspace.id);
 
-                                if !exists {
-                                    updates.push(WorkspaceUpdate::Remove(workspace.id));
+                                    if !exists {
+                                        updates.push(WorkspaceUpdate::Remove(workspace.id));
+                                    }
                                 }
                             }
+
+                            *write_lock!(workspace_state) = new_workspaces;
+                            updates
                         }
 
-                        *write_lock!(workspace_state) = new_workspaces;
-                        updates
-                    }
+                        Ok(Event::WorkspaceActivated { id, focused }) => {
+                            debug!("WorkspaceActivated: id: {}, focused: {}", id, focused);
 
-                    Ok(Event::WorkspaceActivated { id, focused }) => {
-                        debug!("WorkspaceActivated: id: {}, focused: {}", id, focused);
+                            // workspace with id is activated, if focus is true then it is also focused
+                            // if focused is true then focus has changed => find old focused workspace. set it to inactive and set current
+                            //
+                            // we use indexes here as both new/old need to be mutable
 
-                        // workspace with id is activated, if focus is true then it is also focused
-                        // if focused is true then focus has changed => find old focused workspace. set it to inactive and set current
-                        //
-                        // we use indexes here as both new/old need to be mutable
+                            let new_index = read_lock!(workspace_state)
+                                .iter()
+                                .position(|w| w.id == id as i64);
 
-                        let new_index = read_lock!(workspace_state)
-                            .iter()
-                            .position(|w| w.id == id as i64);
+                            if let Some(new_index) = new_index {
+                                if focused {
+                                    let old_index = read_lock!(workspace_state)
+                                        .iter()
+                                        .position(|w| w.visibility.is_focused());
 
-                        if let Some(new_index) = new_index {
-                            if focused {
-                                let old_index = read_lock!(workspace_state)
-                                    .iter()
-                                    .position(|w| w.visibility.is_focused());
+                                    if let Some(old_index) = old_index {
+                                        write_lock!(workspace_state)[new_index].visibility =
+                                            Visibility::focused();
 
-                                if let Some(old_index) = old_index {
-                                    write_lock!(workspace_state)[new_index].visibility =
-                                        Visibility::focused();
+                                        if read_lock!(workspace_state)[old_index].monitor
+                                            == read_lock!(workspace_state)[new_index].monitor
+                                        {
+                                            write_lock!(workspace_state)[old_index].visibility =
+                                                Visibility::Hidden;
+                                        } else {
+                                            write_lock!(workspace_state)[old_index].visibility =
+                                                Visibility::visible();
+                                        }
 
-                                    if read_lock!(workspace_state)[old_index].monitor
-                                        == read_lock!(workspace_state)[new_index].monitor
-                                    {
-                                        write_lock!(workspace_state)[old_index].visibility =
-                                            Visibility::Hidden;
+                                        vec![WorkspaceUpdate::Focus {
+                                            old: Some(
+                                                read_lock!(workspace_state)[old_index].clone(),
+                                            ),
+                                            new: read_lock!(workspace_state)[new_index].clone(),
+                                        }]
                                     } else {
-                                        write_lock!(workspace_state)[old_index].visibility =
-                                            Visibility::visible();
-                                    }
+                                        write_lock!(workspace_state)[new_index].visibility =
+                                            Visibility::focused();
 
-                                    vec![WorkspaceUpdate::Focus {
-                                        old: Some(read_lock!(workspace_state)[old_index].clone()),
-                                        new: read_lock!(workspace_state)[new_index].clone(),
-                                    }]
+                                        vec![WorkspaceUpdate::Focus {
+                                            old: None,
+                                            new: read_lock!(workspace_state)[new_index].clone(),
+                                        }]
+                                    }
                                 } else {
+                                    // if focused is false means active workspace on a particular monitor has changed =>
+                                    // change all workspaces on monitor to inactive and change current workspace as active
                                     write_lock!(workspace_state)[new_index].visibility =
-                                        Visibility::focused();
+                                        Visibility::visible();
 
-                                    vec![WorkspaceUpdate::Focus {
-                                        old: None,
-                                        new: read_lock!(workspace_state)[new_index].clone(),
-                                    }]
-                                }
-                            } else {
-                                // if focused is false means active workspace on a particular monitor has changed =>
-                                // change all workspaces on monitor to inactive and change current workspace as active
-                                write_lock!(workspace_state)[new_index].visibility =
-                                    Visibility::visible();
-
-                                let old_index = read_lock!(workspace_state).iter().position(|w| {
-                                    (w.visibility.is_focused() || w.visibility.is_visible())
-                                        && w.monitor
-                                            == read_lock!(workspace_state)[new_index].monitor
-                                });
+                                    let old_index =
+                                        read_lock!(workspace_state).iter().position(|w| {
+                                            (w.visibility.is_focused() || w.visibility.is_visible())
+                                                && w.monitor
+                                                    == read_lock!(workspace_state)[new_index]
+                                                        .monitor
+                                        });
 
-                                if let Some(old_index) = old_index {
-                                    write_lock!(workspace_state)[old_index].visibility =
-                                        Visibility::Hidden;
+                                    if let Some(old_index) = old_index {
+                                        write_lock!(workspace_state)[old_index].visibility =
+                                            Visibility::Hidden;
 
-                                    vec![]
-                                } else {
-                                    vec![]
+                                        vec![]
+                                    } else {
+                                        vec![]
+                                    }
                                 }
+                            } else {
+                                warn!("No workspace with id for new focus/visible workspace found");
+                                vec![]
                             }
-                        } else {
-                            warn!("No workspace with id for new focus/visible workspace found");
+                        }
+                        Ok(Event::WorkspaceUrgencyChanged { id, urgent }) => {
+                            vec![WorkspaceUpdate::Urgent {
+                                id: id as i64,
+                                urgent,
+                            }]
+                        }
+                        Ok(Event::WindowsChanged { windows }) => {
+                            *write_lock!(window_state) =
+                                windows.into_iter().map(|w| (w.id, w)).collect();
+                            windows_tx.send_expect(workspace_windows(&read_lock!(window_state)));
                             vec![]
                         }
-                    }
-                    Ok(Event::WorkspaceUrgencyChanged { id, urgent }) => {
-                        vec![WorkspaceUpdate::Urgent {
-                            id: id as i64,
-                            urgent,
-                        }]
-                    }
-                    Ok(Event::Other) => {
-                        vec![]
-                    }
-                    Err(err) => {
-                        error!("{err:?}");
-                        break;
+                        Ok(Event::WindowOpenedOrChanged { window }) => {
+                            let old = write_lock!(window_state).insert(window.id, window.clone());
+
+                            // title changes are also sent, which do not need an update
+                            let changed = old.is_none_or(|old| {
+                                old.app_id != window.app_id
+                                    || old.workspace_id != window.workspace_id
+                            });
+
+                            if changed {
+                                windows_tx
+                                    .send_expect(workspace_windows(&read_lock!(window_state)));
+                            }
+
+                            vec![]
+                        }
+                        Ok(Event::WindowClosed { id }) => {
+                            write_lock!(window_state).remove(&id);
+                            windows_tx.send_expect(workspace_windows(&read_lock!(window_state)));
+                            vec![]
+                        }
+                        Ok(Event::Other) => {
+                            vec![]
+                        }
+                        Err(err) => break err,
+                    };
+
+                    for event in events {
+                        tx.send_expect(event);
                     }
                 };
 
-                for event in events {
-                    tx.send_expect(event);
-                }
+                Err::<(), std::io::Error>(err)
             }
-
-            Ok::<(), std::io::Error>(())
-        });
+        }));
 
         Self {
             tx: tx2,
             _rx: rx,
             workspaces: workspace_state2,
+            windows_tx: windows_tx2,
+            _windows_rx: windows_rx,
+            windows: window_state2,
         }
     }
 }
 
+/// Groups the app IDs of windows by their workspace.
+fn workspace_windows(windows: &BTreeMap<u64, Window>) -> WorkspaceWindows {
+    windows
+        .values()
+        .filter_map(|window| {
+            let workspace_id = window.workspace_id?;
+            let app_id = window.app_id.clone()?;
+            Some((workspace_id as i64, app_id))
+        })
+        .collect()
+}
+
 impl WorkspaceClient for Client {
     fn focus(&self, id: i64) {
         debug!("focusing workspace with id: {}", id);
@@ -216,6 +289,37 @@ impl WorkspaceClient for Client {
         });
     }
 
+    fn move_window(&self, window: &WindowMatch, workspace_id: i64) {
+        let ids = read_lock!(self.windows)
+            .values()
+            .filter(|w| {
+                window.matches(
+                    w.app_id.as_deref().unwrap_or_default(),
+                    w.title.as_deref().unwrap_or_default(),
+                )
+            })
+            .map(|w| w.id)
+            .collect::<Vec<_>>();
+
+        spawn(async move {
+            for id in ids {
+                let mut conn = Connection::connect().await?;
+
+                let command = Request::Action(Action::MoveWindowToWorkspace {
+                    window_id: Some(id),
+                    reference: WorkspaceReferenceArg::Id(workspace_id as u64),
+                    focus: false,
+                });
+
+                if let Err(err) = conn.send(command).await {
+                    error!("failed to send command: {err:?}");
+                }
+            }
+
+            Ok::<(), std::io::Error>(())
+        });
+    }
+
     fn subscribe(&self) -> broadcast::Receiver<WorkspaceUpdate> {
         let rx = self.tx.subscribe();
 
@@ -227,4 +331,25 @@ impl WorkspaceClient for Client {
 
         rx
     }
+
+    fn workspaces(&self) -> Vec<IronWorkspace> {
+        read_lock!(self.workspaces).clone()
+    }
+
+    fn subscribe_windows(&self) -> super::Result<broadcast::Receiver<WorkspaceWindows>> {
+        let rx = self.windows_tx.subscribe();
+
+        self.windows_tx
+            .send_expect(workspace_windows(&read_lock!(self.windows)));
+
+        Ok(rx)
+    }
+
+    fn subscribe_visible_windows(&self) -> super::Result<broadcast::Receiver<VisibleWindows>> {
+        // niri does not report window positions
+        Err(super::Error::Unsupported(
+            "visible windows",
+            &["sway", "hyprland"],
+        ))
+    }
 }
@@ -3,11 +3,11 @@ use crate::channels::SyncSenderExt;
 use crate::clients::sway::Client;
 use crate::{await_sync, error, spawn};
 use color_eyre::Report;
-use swayipc_async::{InputChange, InputEvent, Node, WorkspaceChange, WorkspaceEvent};
+use swayipc_async::{Input, InputChange, InputEvent, Node, WorkspaceChange, WorkspaceEvent};
 use tokio::sync::broadcast::{Receiver, channel};
 
 #[cfg(feature = "workspaces")]
-use super::WorkspaceUpdate;
+use super::{VisibleWindows, WindowMatch, WindowRect, WorkspaceUpdate, WorkspaceWindows};
 
 #[cfg(feature = "workspaces+sway")]
 impl super::WorkspaceClient for Client {
@@ -37,6 +37,51 @@ impl super::WorkspaceClient for Client {
         });
     }
 
+    fn move_window(&self, window: &WindowMatch, workspace_id: i64) {
+        let client = self.connection().clone();
+        let window = window.clone();
+
+        spawn(async move {
+            let mut client = client.lock().await;
+
+            let name = client
+                .get_workspaces()
+                .await?
+                .into_iter()
+                .find(|w| w.id == workspace_id)
+                .map(|w| w.name);
+
+            let Some(name) = name else {
+                return Err(Report::msg(format!(
+                    "couldn't find workspace with id {workspace_id}"
+                )));
+            };
+
+            let tree = client.get_tree().await?;
+            let ids = windows(&tree)
+                .into_iter()
+                .filter(|node| {
+                    app_id(node).is_some_and(|app_id| {
+                        window.matches(app_id, node.name.as_deref().unwrap_or_default())
+                    })
+                })
+                .map(|node| node.id)
+                .collect::<Vec<_>>();
+
+            for id in ids {
+                let command = format!("[con_id={id}] move container to workspace \"{name}\"");
+
+                if let Err(e) = client.run_command(command).await {
+                    return Err(Report::msg(format!(
+                        "Couldn't move window to workspace '{workspace_id}': {e:#}"
+                    )));
+                }
+            }
+
+            Ok(())
+        });
+    }
+
     fn subscribe(&self) -> Receiver<WorkspaceUpdate> {
         let (tx, rx) = channel(16);
 
@@ -64,6 +109,182 @@ impl super::WorkspaceClient for Client {
 
         rx
     }
+
+    fn workspaces(&self) -> Vec<Workspace> {
+        let client = self.connection().clone();
+
+        await_sync(async {
+            let mut client = client.lock().await;
+            client.get_workspaces().await
+        })
+        .map(|workspaces| workspaces.into_iter().map(Workspace::from).collect())
+        .unwrap_or_else(|err| {
+            error!("Failed to get workspaces: {err:?}");
+            vec![]
+        })
+    }
+
+    fn subscribe_windows(&self) -> super::Result<Receiver<WorkspaceWindows>> {
+        let (tx, rx) = channel(16);
+
+        let client = self.connection().clone();
+
+        await_sync(async {
+            let tree = client.lock().await.get_tree().await?;
+            tx.send_expect(WorkspaceWindows::from(&tree));
+
+            self.add_listener::<swayipc_async::WindowEvent>(move |event| {
+                use swayipc_async::WindowChange;
+
+                if !matches!(
+                    event.change,
+                    WindowChange::New | WindowChange::Close | WindowChange::Move
+                ) {
+                    return;
+                }
+
+                // the event only includes the window,
+                // so the tree is needed to find its workspace
+                let client = client.clone();
+                let tx = tx.clone();
+                spawn(async move {
+                    match client.lock().await.get_tree().await {
+                        Ok(tree) => tx.send_expect(WorkspaceWindows::from(&tree)),
+                        Err(err) => error!("Failed to get tree: {err:#}"),
+                    }
+                });
+            })
+            .await
+        })
+        .map_err(|err| super::Error::Other(err.into()))?;
+
+        Ok(rx)
+    }
+
+    fn subscribe_visible_windows(&self) -> super::Result<Receiver<VisibleWindows>> {
+        let (tx, rx) = channel(16);
+
+        let client = self.connection().clone();
+
+        // events do not include the full tree,
+        // so it is re-fetched for each
+        let send_windows = move || {
+            let client = client.clone();
+            let tx = tx.clone();
+            spawn(async move {
+                match client.lock().await.get_tree().await {
+                    Ok(tree) => tx.send_expect(VisibleWindows::from(&tree)),
+                    Err(err) => error!("Failed to get tree: {err:#}"),
+                }
+            });
+        };
+
+        send_windows();
+
+        await_sync(async {
+            {
+                let send_windows = send_windows.clone();
+                self.add_listener::<swayipc_async::WindowEvent>(move |event| {
+                    use swayipc_async::WindowChange;
+
+                    if !matches!(
+                        event.change,
+                        WindowChange::Title | WindowChange::Mark | WindowChange::Urgent
+                    ) {
+                        send_windows();
+                    }
+                })
+                .await?;
+            }
+
+            self.add_listener::<WorkspaceEvent>(move |event| {
+                if matches!(event.change, WorkspaceChange::Focus | WorkspaceChange::Move) {
+                    send_windows();
+                }
+            })
+            .await
+        })
+        .map_err(|err| super::Error::Other(err.into()))?;
+
+        Ok(rx)
+    }
+}
+
+#[cfg(feature = "workspaces")]
+impl From<&Node> for VisibleWindows {
+    fn from(tree: &Node) -> Self {
+        let windows = tree
+            .nodes
+            .iter()
+            .filter(|node| node.node_type == swayipc_async::NodeType::Output)
+            .map(|output| {
+                let windows = windows(output)
+                    .into_iter()
+                    .filter(|node| node.visible.unwrap_or_default())
+                    .map(|node| WindowRect {
+                        x: node.rect.x,
+                        y: node.rect.y,
+                        width: node.rect.width,
+                        height: node.rect.height,
+                    })
+                    .collect();
+
+                (output.name.clone().unwrap_or_default(), windows)
+            })
+            .collect();
+
+        Self(windows)
+    }
+}
+
+#[cfg(feature = "workspaces")]
+impl From<&Node> for WorkspaceWindows {
+    fn from(tree: &Node) -> Self {
+        tree.iter()
+            .filter(|node| node.node_type == swayipc_async::NodeType::Workspace)
+            .flat_map(|workspace| {
+                windows(workspace)
+                    .into_iter()
+                    .filter_map(app_id)
+                    .map(move |app_id| (workspace.id, app_id.to_string()))
+            })
+            .collect()
+    }
+}
+
+/// Gets the windows below the node in the tree,
+/// in tree order.
+#[cfg(feature = "workspaces")]
+fn windows(node: &Node) -> Vec<&Node> {
+    fn collect<'a>(node: &'a Node, windows: &mut Vec<&'a Node>) {
+        let is_window = matches!(
+            node.node_type,
+            swayipc_async::NodeType::Con | swayipc_async::NodeType::FloatingCon
+        ) && node.nodes.is_empty();
+
+        if is_window {
+            windows.push(node);
+        }
+
+        for child in node.nodes.iter().chain(&node.floating_nodes) {
+            collect(child, windows);
+        }
+    }
+
+    let mut windows = vec![];
+    collect(node, &mut windows);
+    windows
+}
+
+/// Gets the app ID of a window.
+/// XWayland windows have a class instead.
+#[cfg(feature = "workspaces")]
+fn app_id(node: &Node) -> Option<&str> {
+    node.app_id.as_deref().or_else(|| {
+        node.window_properties
+            .as_ref()
+            .and_then(|properties| properties.class.as_deref())
+    })
 }
 
 impl From<Node> for Workspace {
@@ -164,8 +385,10 @@ impl From<WorkspaceEvent> for WorkspaceUpdate {
 use super::{KeyboardLayoutClient, KeyboardLayoutUpdate};
 
 #[cfg(feature = "keyboard+sway")]
-impl KeyboardLayoutClient for Client {
-    fn set_next_active(&self) {
+impl Client {
+    /// Runs `xkb_switch_layout` on the first keyboard with a layout,
+    /// using the argument as either `next` or the layout index.
+    fn switch_layout(&self, arg: String) {
         let client = self.connection().clone();
         spawn(async move {
             let mut client = client.lock().await;
@@ -178,7 +401,7 @@ impl KeyboardLayoutClient for Client {
             {
                 if let Err(e) = client
                     .run_command(format!(
-                        "input {} xkb_switch_layout next",
+                        "input {} xkb_switch_layout {arg}",
                         keyboard.identifier
                     ))
                     .await
@@ -190,6 +413,24 @@ impl KeyboardLayoutClient for Client {
             }
         });
     }
+}
+
+#[cfg(feature = "keyboard+sway")]
+impl KeyboardLayoutClient for Client {
+    fn set_next_active(&self) {
+        self.switch_layout("next".to_string());
+    }
+
+    fn set_active(&self, index: usize) {
+        self.switch_layout(index.to_string());
+    }
+
+    fn enable_per_window(&self) -> super::Result<()> {
+        Err(super::Error::Unsupported(
+            "per-window keyboard layouts",
+            &["hyprland"],
+        ))
+    }
 
     fn subscribe(&self) -> Receiver<KeyboardLayoutUpdate> {
         let (tx, rx) = channel(16);
@@ -200,8 +441,11 @@ impl KeyboardLayoutClient for Client {
             let mut client = client.lock().await;
             let inputs = client.get_inputs().await.expect("to get inputs");
 
-            if let Some(layout) = inputs.into_iter().find_map(|i| i.xkb_active_layout_name) {
-                tx.send_expect(KeyboardLayoutUpdate(layout));
+            if let Some(layout) = inputs
+                .into_iter()
+                .find_map(|i| KeyboardLayoutUpdate::try_from(i).ok())
+            {
+                tx.send_expect(layout);
             } else {
                 error!("Failed to get keyboard layout from Sway!");
             }
@@ -209,7 +453,11 @@ impl KeyboardLayoutClient for Client {
             drop(client);
 
             self.add_listener::<InputEvent>(move |event| {
-                if let Ok(layout) = KeyboardLayoutUpdate::try_from(event.clone()) {
+                if matches!(
+                    event.change,
+                    InputChange::XkbLayout | InputChange::XkbKeymap
+                ) && let Ok(layout) = KeyboardLayoutUpdate::try_from(event.input.clone())
+                {
                     tx.send_expect(layout);
                 }
             })
@@ -222,19 +470,16 @@ impl KeyboardLayoutClient for Client {
 }
 
 #[cfg(feature = "keyboard+sway")]
-impl TryFrom<InputEvent> for KeyboardLayoutUpdate {
+impl TryFrom<Input> for KeyboardLayoutUpdate {
     type Error = ();
 
-    fn try_from(value: InputEvent) -> Result<Self, Self::Error> {
-        match value.change {
-            InputChange::XkbLayout | InputChange::XkbKeymap => {
-                if let Some(layout) = value.input.xkb_active_layout_name {
-                    Ok(KeyboardLayoutUpdate(layout))
-                } else {
-                    Err(())
-                }
-            }
-            _ => Err(()),
+    fn try_from(value: Input) -> Result<Self, Self::Error> {
+        match value.xkb_active_layout_name {
+            Some(name) => Ok(KeyboardLayoutUpdate {
+                name,
+                layouts: value.xkb_layout_names,
+            }),
+            None => Err(()),
         }
     }
 }
@@ -271,3 +516,40 @@ impl BindModeClient for Client {
         Ok(rx)
     }
 }
+
+#[cfg(feature = "focused+sway")]
+use super::{XWaylandClient, XWaylandUpdate};
+
+#[cfg(feature = "focused+sway")]
+impl XWaylandClient for Client {
+    fn subscribe(&self) -> super::Result<Receiver<XWaylandUpdate>> {
+        let (tx, rx) = channel(16);
+
+        let client = self.connection().clone();
+
+        await_sync(async {
+            let mut client = client.lock().await;
+            let tree = client.get_tree().await?;
+
+            let focused = tree.find_focused_as_ref(|node| node.focused);
+            tx.send_expect(XWaylandUpdate(focused.is_some_and(is_xwayland)));
+
+            drop(client);
+
+            self.add_listener::<swayipc_async::WindowEvent>(move |event| {
+                if event.change == swayipc_async::WindowChange::Focus {
+                    tx.send_expect(XWaylandUpdate(is_xwayland(&event.container)));
+                }
+            })
+            .await
+        })
+        .map_err(|err| super::Error::Other(err.into()))?;
+
+        Ok(rx)
+    }
+}
+
+#[cfg(feature = "focused+sway")]
+fn is_xwayland(node: &Node) -> bool {
+    node.shell == Some(swayipc_async::ShellType::Xwayland)
+}
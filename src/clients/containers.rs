@@ -0,0 +1,194 @@
+use crate::channels::SyncSenderExt;
+use crate::clients::reconnect::{self, Session};
+use crate::clients::unix_http;
+use crate::{arc_mut, lock, spawn};
+use color_eyre::Result;
+use serde::Deserialize;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::time::timeout;
+
+/// A container, as listed by the Docker API.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Container {
+    pub id: String,
+    #[serde(default)]
+    names: Vec<String>,
+    pub image: String,
+    /// The container state, such as `running` or `exited`.
+    pub state: String,
+    /// A human-readable status, such as `Up 2 hours`.
+    pub status: String,
+}
+
+impl Container {
+    /// Gets the container name, without the leading slash.
+    pub fn name(&self) -> &str {
+        self.names
+            .first()
+            .map_or(self.id.as_str(), |name| name.trim_start_matches('/'))
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state == "running"
+    }
+
+    /// Whether the container's health check is failing.
+    /// The list API only includes this in the status text.
+    pub fn is_unhealthy(&self) -> bool {
+        self.status.contains("(unhealthy)")
+    }
+}
+
+/// An action to perform on a container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Start,
+    Stop,
+    Restart,
+}
+
+impl Action {
+    fn endpoint(self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Stop => "stop",
+            Self::Restart => "restart",
+        }
+    }
+}
+
+/// HTTP/1.1 requires a host header, but Docker ignores its value.
+const HOST: &str = "localhost";
+
+/// The events endpoint, filtered to container events.
+/// The filter is `{"type":["container"]}`, URL-encoded.
+const EVENTS_PATH: &str = "/events?filters=%7B%22type%22%3A%5B%22container%22%5D%7D";
+
+/// How long to wait for more events before refreshing the container list,
+/// as a single action such as starting a container sends several.
+const EVENT_BATCH_DELAY: Duration = Duration::from_millis(100);
+
+/// Client for the Docker Engine API over a unix socket.
+/// Podman exposes a compatible API, so is also supported.
+///
+/// The container list is refreshed whenever the engine reports a container event.
+#[derive(Debug)]
+pub struct Client {
+    socket: PathBuf,
+    containers: Arc<Mutex<Vec<Container>>>,
+    tx: broadcast::Sender<Vec<Container>>,
+    _rx: broadcast::Receiver<Vec<Container>>,
+}
+
+impl Client {
+    pub fn new(socket: PathBuf) -> Self {
+        let containers = arc_mut!(vec![]);
+        let (tx, rx) = broadcast::channel(8);
+
+        {
+            let socket = socket.clone();
+            let containers = containers.clone();
+            let tx = tx.clone();
+
+            spawn(reconnect::run("containers", move |session| {
+                let socket = socket.clone();
+                let containers = containers.clone();
+                let tx = tx.clone();
+
+                async move { watch(&socket, &containers, &tx, &session).await }
+            }));
+        }
+
+        Self {
+            socket,
+            containers,
+            tx,
+            _rx: rx,
+        }
+    }
+
+    /// Gets every container, running or not, sorted by name.
+    pub fn containers(&self) -> Vec<Container> {
+        lock!(self.containers).clone()
+    }
+
+    /// Subscribes to the container list, sent each time it changes.
+    pub fn subscribe(&self) -> broadcast::Receiver<Vec<Container>> {
+        self.tx.subscribe()
+    }
+
+    /// Starts, stops or restarts the container.
+    pub async fn perform(&self, id: &str, action: Action) -> Result<()> {
+        let path = format!("/containers/{id}/{}", action.endpoint());
+        unix_http::request(&self.socket, HOST, "POST", &path, None).await?;
+        Ok(())
+    }
+}
+
+/// Lists the containers, then refreshes the list
+/// each time a batch of container events is received.
+///
+/// This runs until the engine closes the event stream.
+async fn watch(
+    socket: &Path,
+    containers: &Mutex<Vec<Container>>,
+    tx: &broadcast::Sender<Vec<Container>>,
+    session: &Session,
+) -> Result<()> {
+    // subscribe first, so no changes are missed between listing and streaming
+    let mut events = unix_http::stream(socket, HOST, EVENTS_PATH).await?;
+    session.connected();
+
+    loop {
+        let mut new_containers: Vec<Container> = serde_json::from_slice(
+            &unix_http::request(socket, HOST, "GET", "/containers/json?all=true", None).await?,
+        )?;
+        new_containers.sort_by(|a, b| a.name().cmp(b.name()));
+
+        let changed = {
+            let mut containers = lock!(containers);
+            let changed = *containers != new_containers;
+            *containers = new_containers.clone();
+            changed
+        };
+
+        if changed {
+            tx.send_expect(new_containers);
+        }
+
+        if events.next_line().await?.is_none() {
+            return Ok(());
+        }
+
+        while let Ok(line) = timeout(EVENT_BATCH_DELAY, events.next_line()).await {
+            if line?.is_none() {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Gets the path of the Docker socket,
+/// falling back to the Podman user socket if Docker is not installed.
+pub fn default_socket() -> PathBuf {
+    if let Some(path) = env::var("DOCKER_HOST")
+        .ok()
+        .and_then(|host| host.strip_prefix("unix://").map(PathBuf::from))
+    {
+        return path;
+    }
+
+    let docker = Path::new("/var/run/docker.sock");
+    if docker.exists() {
+        return docker.to_path_buf();
+    }
+
+    env::var_os("XDG_RUNTIME_DIR")
+        .map(|dir| PathBuf::from(dir).join("podman/podman.sock"))
+        .unwrap_or_else(|| docker.to_path_buf())
+}
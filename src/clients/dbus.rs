@@ -0,0 +1,36 @@
+use tokio::sync::OnceCell;
+use tracing::debug;
+use zbus::{Connection, Result};
+
+static SESSION: OnceCell<Connection> = OnceCell::const_new();
+static SYSTEM: OnceCell<Connection> = OnceCell::const_new();
+
+/// Gets the connection to the session bus shared by all clients,
+/// connecting on first use.
+///
+/// Connections are reference-counted, so the returned value can be cloned freely.
+/// If connecting fails, the next call tries again.
+pub async fn session() -> Result<Connection> {
+    SESSION
+        .get_or_try_init(|| async {
+            debug!("Connecting to session bus");
+            Box::pin(Connection::session()).await
+        })
+        .await
+        .cloned()
+}
+
+/// Gets the connection to the system bus shared by all clients,
+/// connecting on first use.
+///
+/// Connections are reference-counted, so the returned value can be cloned freely.
+/// If connecting fails, the next call tries again.
+pub async fn system() -> Result<Connection> {
+    SYSTEM
+        .get_or_try_init(|| async {
+            debug!("Connecting to system bus");
+            Box::pin(Connection::system()).await
+        })
+        .await
+        .cloned()
+}
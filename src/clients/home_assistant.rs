@@ -0,0 +1,304 @@
+use crate::channels::SyncSenderExt;
+use crate::clients::reconnect::{self, Session};
+use crate::{arc_rw, read_lock, spawn, write_lock};
+use color_eyre::{Report, Result};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tokio::sync::{broadcast, mpsc};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, error};
+
+/// Message ID of the `get_states` request sent on connect.
+/// Requests sent afterwards use increasing IDs.
+const GET_STATES_ID: u64 = 2;
+
+/// The state of a Home Assistant entity.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Entity {
+    pub entity_id: String,
+    pub state: String,
+    #[serde(default)]
+    pub attributes: Map<String, Value>,
+}
+
+impl Entity {
+    /// Gets the entity's display name,
+    /// falling back to its ID if it has none.
+    pub fn friendly_name(&self) -> &str {
+        self.attributes
+            .get("friendly_name")
+            .and_then(Value::as_str)
+            .unwrap_or(&self.entity_id)
+    }
+
+    /// Gets the unit the state is measured in, if any.
+    pub fn unit(&self) -> Option<&str> {
+        self.attributes
+            .get("unit_of_measurement")
+            .and_then(Value::as_str)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Update {
+    /// Every entity's state has been fetched, after (re)connecting.
+    /// The new states can be read using [`Client::entity`].
+    All,
+    /// A single entity's state has changed.
+    Changed(Box<Entity>),
+}
+
+/// A message received over the websocket.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Incoming {
+    AuthRequired,
+    AuthOk,
+    AuthInvalid {
+        #[serde(default)]
+        message: String,
+    },
+    Result {
+        id: u64,
+        success: bool,
+        #[serde(default)]
+        result: Value,
+        error: Option<ResultError>,
+    },
+    Event {
+        event: Event,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResultError {
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Event {
+    data: StateChanged,
+}
+
+#[derive(Debug, Deserialize)]
+struct StateChanged {
+    entity_id: String,
+    new_state: Option<Entity>,
+}
+
+/// State shared between the client and its connection.
+#[derive(Debug, Clone)]
+struct Shared {
+    entities: Arc<RwLock<HashMap<String, Entity>>>,
+    /// Sends requests over the current connection,
+    /// or `None` while disconnected.
+    requests: Arc<RwLock<Option<mpsc::Sender<Value>>>>,
+    tx: broadcast::Sender<Update>,
+}
+
+impl Shared {
+    /// Connects to the websocket API,
+    /// handling messages until the connection is lost.
+    async fn run_session(&self, url: &str, token: &str, session: &Session) -> Result<()> {
+        let (socket, _) = connect_async(url).await?;
+        let (mut sink, mut stream) = socket.split();
+
+        let (requests_tx, mut requests_rx) = mpsc::channel(16);
+        let mut id = GET_STATES_ID;
+
+        loop {
+            tokio::select! {
+                message = stream.next() => {
+                    let text = match message {
+                        Some(Ok(Message::Text(text))) => text,
+                        Some(Ok(Message::Close(_))) | None => break Ok(()),
+                        Some(Ok(_)) => continue,
+                        Some(Err(err)) => break Err(err.into()),
+                    };
+
+                    let message = match serde_json::from_str(&text) {
+                        Ok(Incoming::AuthOk) => {
+                            session.connected();
+                            write_lock!(self.requests).replace(requests_tx.clone());
+                            Incoming::AuthOk
+                        }
+                        Ok(message) => message,
+                        Err(err) => {
+                            debug!("Failed to parse Home Assistant message: {err}");
+                            continue;
+                        }
+                    };
+
+                    for reply in self.handle_message(message, token)? {
+                        sink.send(reply).await?;
+                    }
+                }
+                Some(payload) = requests_rx.recv() => {
+                    id += 1;
+                    sink.send(request(id, payload)).await?;
+                }
+            }
+        }
+    }
+
+    /// Handles a message received over the websocket,
+    /// returning any messages to send in reply.
+    fn handle_message(&self, message: Incoming, token: &str) -> Result<Vec<Message>> {
+        let replies = match message {
+            Incoming::AuthRequired => vec![Message::text(
+                json!({ "type": "auth", "access_token": token }).to_string(),
+            )],
+            Incoming::AuthOk => vec![
+                request(
+                    1,
+                    json!({ "type": "subscribe_events", "event_type": "state_changed" }),
+                ),
+                request(GET_STATES_ID, json!({ "type": "get_states" })),
+            ],
+            Incoming::AuthInvalid { message } => {
+                return Err(Report::msg(format!("Failed to authenticate: {message}")));
+            }
+            Incoming::Result {
+                id,
+                success,
+                result,
+                error,
+            } => {
+                if !success {
+                    error!(
+                        "Home Assistant request failed: {}",
+                        error.map(|err| err.message).unwrap_or_default()
+                    );
+                } else if id == GET_STATES_ID {
+                    let states: Vec<Entity> = serde_json::from_value(result)?;
+
+                    *write_lock!(self.entities) = states
+                        .into_iter()
+                        .map(|entity| (entity.entity_id.clone(), entity))
+                        .collect();
+
+                    self.tx.send_expect(Update::All);
+                }
+
+                vec![]
+            }
+            Incoming::Event { event } => {
+                let data = event.data;
+                if let Some(entity) = data.new_state {
+                    write_lock!(self.entities).insert(data.entity_id, entity.clone());
+                    self.tx.send_expect(Update::Changed(Box::new(entity)));
+                } else {
+                    write_lock!(self.entities).remove(&data.entity_id);
+                }
+
+                vec![]
+            }
+            Incoming::Other => vec![],
+        };
+
+        Ok(replies)
+    }
+}
+
+#[derive(Debug)]
+pub struct Client {
+    shared: Shared,
+    _rx: broadcast::Receiver<Update>,
+}
+
+impl Client {
+    /// Creates a client connected to the Home Assistant instance at `url`,
+    /// authenticating with the long-lived access `token`.
+    pub fn new(url: &str, token: String) -> Self {
+        let url = websocket_url(url);
+        let (tx, rx) = broadcast::channel(32);
+
+        let shared = Shared {
+            entities: arc_rw!(HashMap::new()),
+            requests: arc_rw!(None),
+            tx,
+        };
+
+        {
+            let shared = shared.clone();
+
+            spawn(reconnect::run("home_assistant", move |session| {
+                let url = url.clone();
+                let token = token.clone();
+                let shared = shared.clone();
+
+                async move {
+                    let res = shared.run_session(&url, &token, &session).await;
+                    write_lock!(shared.requests).take();
+                    res
+                }
+            }));
+        }
+
+        Self { shared, _rx: rx }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Update> {
+        self.shared.tx.subscribe()
+    }
+
+    /// Gets the current state of the entity with the given ID,
+    /// if it exists.
+    pub fn entity(&self, entity_id: &str) -> Option<Entity> {
+        read_lock!(self.shared.entities).get(entity_id).cloned()
+    }
+
+    /// Calls a service on the entity,
+    /// for example `light.turn_on`.
+    pub async fn call_service(&self, domain: &str, service: &str, entity_id: &str) -> Result<()> {
+        let requests = read_lock!(self.shared.requests)
+            .clone()
+            .ok_or_else(|| Report::msg("Not connected to Home Assistant"))?;
+
+        requests
+            .send(json!({
+                "type": "call_service",
+                "domain": domain,
+                "service": service,
+                "target": { "entity_id": entity_id },
+            }))
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Gets the domain of an entity ID, such as `light` for `light.desk`.
+pub fn domain(entity_id: &str) -> &str {
+    entity_id
+        .split_once('.')
+        .map_or(entity_id, |(domain, _)| domain)
+}
+
+/// Serializes `payload` as a websocket message with the given ID.
+fn request(id: u64, mut payload: Value) -> Message {
+    payload["id"] = id.into();
+    Message::text(payload.to_string())
+}
+
+/// Gets the websocket API URL
+/// for the Home Assistant instance at `url`.
+fn websocket_url(url: &str) -> String {
+    let url = url.trim_end_matches('/');
+
+    let url = if let Some(host) = url.strip_prefix("https://") {
+        format!("wss://{host}")
+    } else if let Some(host) = url.strip_prefix("http://") {
+        format!("ws://{host}")
+    } else {
+        url.to_string()
+    };
+
+    format!("{url}/api/websocket")
+}
@@ -0,0 +1,111 @@
+//! D-Bus interface proxies for `kdeconnectd`.
+//!
+//! KDE Connect uses camel-case member names,
+//! so each member is named explicitly.
+//!
+//! Each device is at `/modules/kdeconnect/devices/<id>`,
+//! with its plugins at child paths named after the plugin.
+
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.kde.kdeconnect.daemon",
+    default_service = "org.kde.kdeconnect",
+    default_path = "/modules/kdeconnect"
+)]
+pub trait Daemon {
+    /// Gets the IDs of known devices.
+    #[zbus(name = "devices")]
+    fn devices(&self, only_reachable: bool, only_paired: bool) -> zbus::Result<Vec<String>>;
+}
+
+#[proxy(
+    interface = "org.kde.kdeconnect.device",
+    default_service = "org.kde.kdeconnect",
+    assume_defaults = false
+)]
+pub trait Device {
+    #[zbus(property, name = "name")]
+    fn name(&self) -> zbus::Result<String>;
+
+    #[zbus(property, name = "isReachable")]
+    fn is_reachable(&self) -> zbus::Result<bool>;
+}
+
+#[proxy(
+    interface = "org.kde.kdeconnect.device.battery",
+    default_service = "org.kde.kdeconnect",
+    assume_defaults = false
+)]
+pub trait Battery {
+    #[zbus(property, name = "charge")]
+    fn charge(&self) -> zbus::Result<i32>;
+
+    #[zbus(property, name = "isCharging")]
+    fn is_charging(&self) -> zbus::Result<bool>;
+}
+
+#[proxy(
+    interface = "org.kde.kdeconnect.device.connectivity_report",
+    default_service = "org.kde.kdeconnect",
+    assume_defaults = false
+)]
+pub trait ConnectivityReport {
+    #[zbus(property, name = "cellularNetworkType")]
+    fn cellular_network_type(&self) -> zbus::Result<String>;
+
+    /// Signal strength from `0` to `4`, or `-1` if unknown.
+    #[zbus(property, name = "cellularNetworkStrength")]
+    fn cellular_network_strength(&self) -> zbus::Result<i32>;
+}
+
+#[proxy(
+    interface = "org.kde.kdeconnect.device.notifications",
+    default_service = "org.kde.kdeconnect",
+    assume_defaults = false
+)]
+pub trait Notifications {
+    /// Gets the IDs of the notifications currently shown on the device.
+    #[zbus(name = "activeNotifications")]
+    fn active_notifications(&self) -> zbus::Result<Vec<String>>;
+}
+
+#[proxy(
+    interface = "org.kde.kdeconnect.device.notifications.notification",
+    default_service = "org.kde.kdeconnect",
+    assume_defaults = false
+)]
+pub trait Notification {
+    #[zbus(property, name = "appName")]
+    fn app_name(&self) -> zbus::Result<String>;
+}
+
+#[proxy(
+    interface = "org.kde.kdeconnect.device.findmyphone",
+    default_service = "org.kde.kdeconnect",
+    assume_defaults = false
+)]
+pub trait FindMyPhone {
+    #[zbus(name = "ring")]
+    fn ring(&self) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.kde.kdeconnect.device.ping",
+    default_service = "org.kde.kdeconnect",
+    assume_defaults = false
+)]
+pub trait Ping {
+    #[zbus(name = "sendPing")]
+    fn send_ping(&self) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.kde.kdeconnect.device.clipboard",
+    default_service = "org.kde.kdeconnect",
+    assume_defaults = false
+)]
+pub trait Clipboard {
+    #[zbus(name = "sendClipboard")]
+    fn send_clipboard(&self) -> zbus::Result<()>;
+}
@@ -0,0 +1,231 @@
+mod dbus;
+
+use crate::channels::SyncSenderExt;
+use crate::clients::reconnect;
+use crate::{register_fallible_client, spawn};
+use color_eyre::Result;
+use dbus::{
+    BatteryProxy, ClipboardProxy, ConnectivityReportProxy, DaemonProxy, DeviceProxy,
+    FindMyPhoneProxy, NotificationProxy, NotificationsProxy, PingProxy,
+};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::time::timeout;
+use tracing::{debug, error};
+use zbus::export::ordered_stream::OrderedStreamExt;
+use zbus::message::Type;
+use zbus::proxy::{CacheProperties, ProxyImpl};
+use zbus::{Connection, MatchRule, MessageStream};
+
+const SERVICE: &str = "org.kde.kdeconnect";
+
+/// How long to wait for further signals before refreshing,
+/// as the daemon tends to send several at once.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// A paired KDE Connect device.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Device {
+    pub id: String,
+    pub name: String,
+    pub reachable: bool,
+    /// Charge percentage and whether charging,
+    /// if the battery plugin is enabled.
+    pub battery: Option<(i32, bool)>,
+    /// Cellular network type and strength from `0` to `4`,
+    /// if the connectivity report plugin is enabled.
+    pub network: Option<(String, i32)>,
+    /// The app name of each notification shown on the device,
+    /// if the notifications plugin is enabled.
+    pub notifications: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Client {
+    connection: Connection,
+    tx: broadcast::Sender<Vec<Device>>,
+    _rx: broadcast::Receiver<Vec<Device>>,
+}
+
+impl Client {
+    pub async fn new() -> Result<Self> {
+        let connection = crate::clients::dbus::session().await?;
+        let (tx, rx) = broadcast::channel(8);
+
+        {
+            let connection = connection.clone();
+            let tx = tx.clone();
+
+            spawn(reconnect::run("kdeconnect", move |session| {
+                let connection = connection.clone();
+                let tx = tx.clone();
+
+                async move {
+                    let daemon = DaemonProxy::new(&connection).await?;
+                    let mut owner_stream = daemon.inner().receive_owner_changed().await?;
+
+                    let rule = MatchRule::builder()
+                        .msg_type(Type::Signal)
+                        .sender(SERVICE)?
+                        .build();
+                    let mut signals =
+                        MessageStream::for_match_rule(rule, &connection, None).await?;
+
+                    // fails if kdeconnectd is not running,
+                    // and re-sends the full state after a restart.
+                    tx.send_expect(devices(&connection, &daemon).await?);
+                    session.connected();
+
+                    loop {
+                        tokio::select! {
+                            Some(_) = signals.next() => {
+                                while let Ok(Some(_)) = timeout(DEBOUNCE, signals.next()).await {}
+
+                                debug!("Refreshing KDE Connect devices");
+                                tx.send_expect(devices(&connection, &daemon).await?);
+                            }
+                            Some(owner) = owner_stream.next() => {
+                                if owner.is_none() {
+                                    break;
+                                }
+                            }
+                            else => break,
+                        }
+                    }
+
+                    Ok::<(), zbus::Error>(())
+                }
+            }));
+        }
+
+        Ok(Self {
+            connection,
+            tx,
+            _rx: rx,
+        })
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Vec<Device>> {
+        self.tx.subscribe()
+    }
+
+    /// Gets the current state of each paired device.
+    pub async fn devices(&self) -> Result<Vec<Device>> {
+        let daemon = DaemonProxy::new(&self.connection).await?;
+        Ok(devices(&self.connection, &daemon).await?)
+    }
+
+    /// Makes the device ring, even if it is on silent.
+    pub async fn ring(&self, id: &str) {
+        let res = async {
+            plugin::<FindMyPhoneProxy>(&self.connection, id, "findmyphone")
+                .await?
+                .ring()
+                .await
+        };
+
+        if let Err(err) = res.await {
+            error!("Failed to ring device: {err:?}");
+        }
+    }
+
+    /// Sends a ping notification to the device.
+    pub async fn ping(&self, id: &str) {
+        let res = async {
+            plugin::<PingProxy>(&self.connection, id, "ping")
+                .await?
+                .send_ping()
+                .await
+        };
+
+        if let Err(err) = res.await {
+            error!("Failed to ping device: {err:?}");
+        }
+    }
+
+    /// Sends the contents of the local clipboard to the device.
+    pub async fn send_clipboard(&self, id: &str) {
+        let res = async {
+            plugin::<ClipboardProxy>(&self.connection, id, "clipboard")
+                .await?
+                .send_clipboard()
+                .await
+        };
+
+        if let Err(err) = res.await {
+            error!("Failed to send clipboard to device: {err:?}");
+        }
+    }
+}
+
+/// Gets the state of each paired device.
+async fn devices(connection: &Connection, daemon: &DaemonProxy<'_>) -> zbus::Result<Vec<Device>> {
+    let mut devices = vec![];
+
+    for id in daemon.devices(false, true).await? {
+        let device = plugin::<DeviceProxy>(connection, &id, "").await?;
+
+        // plugins can be disabled per-device,
+        // in which case their objects do not exist.
+        let battery = async {
+            let battery = plugin::<BatteryProxy>(connection, &id, "battery").await?;
+            Ok::<_, zbus::Error>((battery.charge().await?, battery.is_charging().await?))
+        };
+
+        let network = async {
+            let report =
+                plugin::<ConnectivityReportProxy>(connection, &id, "connectivity_report").await?;
+
+            Ok::<_, zbus::Error>((
+                report.cellular_network_type().await?,
+                report.cellular_network_strength().await?,
+            ))
+        };
+
+        devices.push(Device {
+            name: device.name().await?,
+            reachable: device.is_reachable().await?,
+            battery: battery.await.ok(),
+            network: network.await.ok(),
+            notifications: notifications(connection, &id).await.unwrap_or_default(),
+            id,
+        });
+    }
+
+    Ok(devices)
+}
+
+/// Gets the app name of each notification shown on the device.
+async fn notifications(connection: &Connection, id: &str) -> zbus::Result<Vec<String>> {
+    let proxy = plugin::<NotificationsProxy>(connection, id, "notifications").await?;
+
+    let mut apps = vec![];
+    for notification in proxy.active_notifications().await? {
+        let path = format!("notifications/{notification}");
+        let notification = plugin::<NotificationProxy>(connection, id, &path).await?;
+        apps.push(notification.app_name().await?);
+    }
+
+    Ok(apps)
+}
+
+/// Creates a proxy for the device's plugin object,
+/// or the device itself if `plugin` is empty.
+async fn plugin<'a, P>(connection: &Connection, id: &str, plugin: &str) -> zbus::Result<P>
+where
+    P: ProxyImpl<'a> + From<zbus::Proxy<'a>>,
+{
+    let path = if plugin.is_empty() {
+        format!("/modules/kdeconnect/devices/{id}")
+    } else {
+        format!("/modules/kdeconnect/devices/{id}/{plugin}")
+    };
+
+    P::builder(connection)
+        .path(path)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await
+}
+
+register_fallible_client!(Client, kdeconnect);
@@ -5,7 +5,7 @@ use std::{ops::Deref, sync::Arc};
 use tracing::{debug, error, info, warn};
 
 use crate::Ironbar;
-use crate::ironvar::{Namespace, VariableManager};
+use crate::ironvar::{Namespace, VariableManager, WritableNamespace};
 
 /// Wrapper around Lua instance
 /// to create a singleton and handle initialization.
@@ -107,6 +107,23 @@ impl IronbarUserData {
         Ok(Value::Table(table))
     }
 
+    fn var_set(&self, key: &str, value: &Value) -> Result<(), Error> {
+        let value = match value {
+            Value::String(value) => value.to_str()?.to_string(),
+            Value::Integer(_) | Value::Number(_) | Value::Boolean(_) => value.to_string()?,
+            _ => {
+                return Err(Error::RuntimeError(format!(
+                    "Variable value must be a string, number or boolean, got {}",
+                    value.type_name()
+                )));
+            }
+        };
+
+        self.variable_manager
+            .set(key, value)
+            .map_err(|err| Error::RuntimeError(format!("Failed to set variable {key}: {err}")))
+    }
+
     fn to_value(lua: &Lua, value: String) -> Result<Value, Error> {
         if let Ok(i) = value.parse::<i64>() {
             i.into_lua(lua)
@@ -160,5 +177,8 @@ impl UserData for IronbarUserData {
         methods.add_method("var_list", |lua, this, namespace| {
             this.var_list(lua, namespace)
         });
+        methods.add_method("var_set", |_, this, (key, value): (String, Value)| {
+            this.var_set(&key, &value)
+        });
     }
 }
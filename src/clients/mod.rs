@@ -1,40 +1,99 @@
-use crate::{Ironbar, await_sync};
+use crate::{Ironbar, await_sync, lock, startup};
 use color_eyre::Result;
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
+#[cfg(any(feature = "brightness", feature = "osd"))]
+pub mod backlight;
 #[cfg(feature = "bluetooth")]
 pub mod bluetooth;
 #[cfg(feature = "clipboard")]
 pub mod clipboard;
 #[cfg(any(
     feature = "bindmode",
+    feature = "focused",
     feature = "hyprland",
     feature = "keyboard",
     feature = "workspaces",
 ))]
 pub mod compositor;
+#[cfg(feature = "containers")]
+pub mod containers;
+#[cfg(any(
+    feature = "battery",
+    feature = "brightness",
+    feature = "capture",
+    feature = "color_scheme",
+    feature = "kdeconnect",
+    feature = "network_manager",
+    feature = "notifications",
+    feature = "power_menu",
+    feature = "screencast",
+    feature = "systemd",
+    feature = "tablet",
+    feature = "tray",
+))]
+pub mod dbus;
+#[cfg(feature = "home_assistant")]
+pub mod home_assistant;
 #[cfg(feature = "inhibit")]
 pub mod inhibit;
+#[cfg(feature = "kdeconnect")]
+pub mod kdeconnect;
 #[cfg(feature = "keyboard")]
 pub mod libinput;
 #[cfg(feature = "cairo")]
 pub mod lua;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
 #[cfg(feature = "music")]
 pub mod music;
 #[cfg(feature = "network_manager")]
+pub mod netlink;
+#[cfg(feature = "network_manager")]
 pub mod networkmanager;
+#[cfg(feature = "notifications")]
+pub mod notifications;
 pub mod outputs;
+#[cfg(feature = "battery")]
+pub mod power_profiles;
+#[cfg(any(
+    feature = "battery",
+    feature = "containers",
+    feature = "home_assistant",
+    feature = "hyprland",
+    feature = "ipc",
+    feature = "kdeconnect",
+    feature = "mail",
+    feature = "mqtt",
+    feature = "music+mpd",
+    feature = "niri",
+    feature = "notifications",
+    feature = "screencast",
+    feature = "sway",
+    feature = "syncthing",
+    feature = "tray",
+    feature = "volume",
+))]
+pub mod reconnect;
+#[cfg(feature = "screencast")]
+pub mod screencast;
 #[cfg(feature = "sway")]
 pub mod sway;
 #[cfg(feature = "notifications")]
 pub mod swaync;
+#[cfg(feature = "syncthing")]
+pub mod syncthing;
 #[cfg(feature = "sys_info")]
 pub mod sysinfo;
+#[cfg(feature = "systemd")]
+pub mod systemd;
 #[cfg(feature = "tray")]
 pub mod tray;
+#[cfg(any(feature = "containers", feature = "vpn"))]
+pub mod unix_http;
 #[cfg(feature = "battery")]
 pub mod upower;
 #[cfg(feature = "volume")]
@@ -47,6 +106,8 @@ pub mod wayland;
 pub struct Clients {
     wayland: Option<Arc<wayland::Client>>,
     outputs: Option<Arc<outputs::Client>>,
+    #[cfg(any(feature = "brightness", feature = "osd"))]
+    backlight: Option<Arc<backlight::Client>>,
     #[cfg(feature = "workspaces")]
     workspaces: Option<Arc<dyn compositor::WorkspaceClient>>,
     #[cfg(feature = "sway")]
@@ -55,36 +116,73 @@ pub struct Clients {
     hyprland: Option<Arc<compositor::hyprland::Client>>,
     #[cfg(feature = "bindmode")]
     bindmode: Option<Arc<dyn compositor::BindModeClient>>,
+    #[cfg(feature = "focused")]
+    xwayland: Option<Arc<dyn compositor::XWaylandClient>>,
     #[cfg(feature = "clipboard")]
     clipboard: Option<Arc<clipboard::Client>>,
+    #[cfg(feature = "home_assistant")]
+    home_assistant: HashMap<Box<str>, Arc<home_assistant::Client>>,
+    #[cfg(feature = "containers")]
+    containers: HashMap<PathBuf, Arc<containers::Client>>,
     #[cfg(feature = "inhibit")]
     inhibit: Option<Arc<inhibit::Client>>,
+    #[cfg(feature = "kdeconnect")]
+    kdeconnect: Option<Arc<kdeconnect::Client>>,
     #[cfg(feature = "keyboard")]
     libinput: HashMap<Box<str>, Arc<libinput::Client>>,
     #[cfg(feature = "keyboard")]
     keyboard_layout: Option<Arc<dyn compositor::KeyboardLayoutClient>>,
     #[cfg(feature = "cairo")]
     lua: Option<Rc<lua::LuaEngine>>,
+    #[cfg(feature = "mqtt")]
+    mqtt: HashMap<(Box<str>, u16), Arc<mqtt::Client>>,
     #[cfg(feature = "music")]
     music: HashMap<music::ClientType, Arc<dyn music::MusicClient>>,
     #[cfg(feature = "network_manager")]
     network_manager: Option<Arc<networkmanager::Client>>,
     #[cfg(feature = "notifications")]
-    notifications: Option<Arc<swaync::Client>>,
+    notifications: HashMap<notifications::ClientType, Arc<dyn notifications::NotificationClient>>,
+    #[cfg(feature = "notifications")]
+    notification_daemon: Option<Arc<notifications::daemon::Client>>,
+    #[cfg(feature = "screencast")]
+    screencast: Option<Arc<screencast::Client>>,
+    #[cfg(feature = "syncthing")]
+    syncthing: HashMap<Box<str>, Arc<syncthing::Client>>,
     #[cfg(feature = "sys_info")]
     sys_info: Option<Arc<sysinfo::Client>>,
+    #[cfg(feature = "systemd")]
+    systemd: HashMap<systemd::Bus, Arc<systemd::Client>>,
     #[cfg(feature = "tray")]
     tray: Option<Arc<tray::Client>>,
     #[cfg(feature = "battery")]
     upower: Option<Arc<upower::Client>>,
+    #[cfg(feature = "battery")]
+    power_profiles: Option<Arc<power_profiles::Client>>,
     #[cfg(feature = "volume")]
-    volume: Option<Arc<volume::Client>>,
+    volume: HashMap<volume::ClientType, Arc<dyn volume::VolumeClient>>,
     #[cfg(feature = "bluetooth")]
     bluetooth: Option<Arc<bluetooth::Client>>,
 }
 
 pub type ClientResult<T> = Result<Arc<T>>;
 
+/// Names of the clients which have been started.
+static STARTED: Mutex<BTreeSet<&str>> = Mutex::new(BTreeSet::new());
+
+/// Runs `f` to create the named client,
+/// recording how long it took when profiling startup.
+fn time<R>(name: &'static str, f: impl FnOnce() -> R) -> R {
+    let res = startup::time("client", name, f);
+    lock!(STARTED).insert(name);
+    res
+}
+
+/// Gets the names of the clients which have been started, in alphabetical order.
+/// This includes clients which failed to connect.
+pub fn started() -> Vec<&'static str> {
+    lock!(STARTED).iter().copied().collect()
+}
+
 impl Clients {
     pub(crate) fn new() -> Self {
         Self::default()
@@ -92,29 +190,46 @@ impl Clients {
 
     pub fn wayland(&mut self) -> Arc<wayland::Client> {
         self.wayland
-            .get_or_insert_with(|| Arc::new(wayland::Client::new()))
+            .get_or_insert_with(|| time("wayland", || Arc::new(wayland::Client::new())))
             .clone()
     }
 
     pub fn outputs(&mut self) -> Arc<outputs::Client> {
         self.outputs
-            .get_or_insert_with(|| Arc::new(outputs::Client::new()))
+            .get_or_insert_with(|| time("outputs", || Arc::new(outputs::Client::new())))
             .clone()
     }
 
+    #[cfg(any(feature = "brightness", feature = "osd"))]
+    pub fn backlight(&mut self) -> Arc<backlight::Client> {
+        self.backlight
+            .get_or_insert_with(|| time("backlight", || Arc::new(backlight::Client::new())))
+            .clone()
+    }
+
+    /// Gets the clipboard client, starting it if necessary.
+    ///
+    /// The storage options are taken from the first caller.
     #[cfg(feature = "clipboard")]
-    pub fn clipboard(&mut self) -> Arc<clipboard::Client> {
+    pub fn clipboard(
+        &mut self,
+        storage_config: &clipboard::StorageConfig,
+    ) -> Arc<clipboard::Client> {
         let wayland = self.wayland();
 
         self.clipboard
-            .get_or_insert_with(|| Arc::new(clipboard::Client::new(wayland)))
+            .get_or_insert_with(|| {
+                time("clipboard", || {
+                    Arc::new(clipboard::Client::new(wayland, storage_config.clone()))
+                })
+            })
             .clone()
     }
 
     #[cfg(feature = "inhibit")]
     pub fn inhibit(&mut self) -> Arc<inhibit::Client> {
         self.inhibit
-            .get_or_insert_with(|| Arc::new(inhibit::Client::new()))
+            .get_or_insert_with(|| time("inhibit", || Arc::new(inhibit::Client::new())))
             .clone()
     }
 
@@ -123,7 +238,9 @@ impl Clients {
         let client = if let Some(workspaces) = &self.workspaces {
             workspaces.clone()
         } else {
-            let client = compositor::Compositor::create_workspace_client(self)?;
+            let client = time("workspaces", || {
+                compositor::Compositor::create_workspace_client(self)
+            })?;
             self.workspaces.replace(client.clone());
             client
         };
@@ -136,7 +253,9 @@ impl Clients {
         let client = if let Some(keyboard_layout) = &self.keyboard_layout {
             keyboard_layout.clone()
         } else {
-            let client = compositor::Compositor::create_keyboard_layout_client(self)?;
+            let client = time("keyboard_layout", || {
+                compositor::Compositor::create_keyboard_layout_client(self)
+            })?;
             self.keyboard_layout.replace(client.clone());
             client
         };
@@ -149,7 +268,9 @@ impl Clients {
         let client = if let Some(client) = &self.bindmode {
             client.clone()
         } else {
-            let client = compositor::Compositor::create_bindmode_client(self)?;
+            let client = time("bindmode", || {
+                compositor::Compositor::create_bindmode_client(self)
+            })?;
             self.bindmode.replace(client.clone());
             client
         };
@@ -157,12 +278,27 @@ impl Clients {
         Ok(client)
     }
 
+    #[cfg(feature = "focused")]
+    pub fn xwayland(&mut self) -> ClientResult<dyn compositor::XWaylandClient> {
+        let client = if let Some(client) = &self.xwayland {
+            client.clone()
+        } else {
+            let client = time("xwayland", || {
+                compositor::Compositor::create_xwayland_client(self)
+            })?;
+            self.xwayland.replace(client.clone());
+            client
+        };
+
+        Ok(client)
+    }
+
     #[cfg(feature = "sway")]
     pub fn sway(&mut self) -> ClientResult<sway::Client> {
         let client = if let Some(client) = &self.sway {
             client.clone()
         } else {
-            let client = await_sync(async { sway::Client::new().await })?;
+            let client = time("sway", || await_sync(async { sway::Client::new().await }))?;
             let client = Arc::new(client);
             self.sway.replace(client.clone());
             client
@@ -176,7 +312,7 @@ impl Clients {
         if let Some(client) = &self.hyprland {
             client.clone()
         } else {
-            let client = Arc::new(compositor::hyprland::Client::new());
+            let client = time("hyprland", || Arc::new(compositor::hyprland::Client::new()));
             self.hyprland.replace(client.clone());
             client
         }
@@ -185,26 +321,64 @@ impl Clients {
     #[cfg(feature = "cairo")]
     pub fn lua(&mut self, config_dir: &Path) -> Rc<lua::LuaEngine> {
         self.lua
-            .get_or_insert_with(|| Rc::new(lua::LuaEngine::new(config_dir)))
+            .get_or_insert_with(|| time("lua", || Rc::new(lua::LuaEngine::new(config_dir))))
             .clone()
     }
 
+    #[cfg(feature = "containers")]
+    pub fn containers(&mut self, socket: PathBuf) -> Arc<containers::Client> {
+        self.containers
+            .entry(socket.clone())
+            .or_insert_with(|| time("containers", || Arc::new(containers::Client::new(socket))))
+            .clone()
+    }
+
+    #[cfg(feature = "home_assistant")]
+    pub fn home_assistant(&mut self, url: &str, token: &str) -> Arc<home_assistant::Client> {
+        if let Some(client) = self.home_assistant.get(url) {
+            client.clone()
+        } else {
+            let client = time("home_assistant", || {
+                Arc::new(home_assistant::Client::new(url, token.to_string()))
+            });
+            self.home_assistant.insert(url.into(), client.clone());
+            client
+        }
+    }
+
     #[cfg(feature = "keyboard")]
     pub fn libinput(&mut self, seat: &str) -> Arc<libinput::Client> {
         if let Some(client) = self.libinput.get(seat) {
             client.clone()
         } else {
-            let client = libinput::Client::init(seat.to_string());
+            let client = time("libinput", || libinput::Client::init(seat.to_string()));
             self.libinput.insert(seat.into(), client.clone());
             client
         }
     }
 
+    #[cfg(feature = "mqtt")]
+    pub fn mqtt(
+        &mut self,
+        host: &str,
+        port: u16,
+        credentials: Option<(String, String)>,
+    ) -> Arc<mqtt::Client> {
+        self.mqtt
+            .entry((host.into(), port))
+            .or_insert_with(|| {
+                time("mqtt", || {
+                    Arc::new(mqtt::Client::new(host, port, credentials))
+                })
+            })
+            .clone()
+    }
+
     #[cfg(feature = "music")]
     pub fn music(&mut self, client_type: music::ClientType) -> Arc<dyn music::MusicClient> {
         self.music
             .entry(client_type.clone())
-            .or_insert_with(|| music::create_client(client_type))
+            .or_insert_with(|| time("music", || music::create_client(client_type)))
             .clone()
     }
 
@@ -213,20 +387,97 @@ impl Clients {
         if let Some(client) = &self.network_manager {
             Ok(client.clone())
         } else {
-            let client = await_sync(async move { networkmanager::create_client().await })?;
+            let client = time("network_manager", || {
+                await_sync(async move { networkmanager::create_client().await })
+            })?;
             self.network_manager = Some(client.clone());
             Ok(client)
         }
     }
 
+    #[cfg(feature = "kdeconnect")]
+    pub fn kdeconnect(&mut self) -> ClientResult<kdeconnect::Client> {
+        let client = if let Some(client) = &self.kdeconnect {
+            client.clone()
+        } else {
+            let client = time("kdeconnect", || {
+                await_sync(async { kdeconnect::Client::new().await })
+            })?;
+            let client = Arc::new(client);
+            self.kdeconnect.replace(client.clone());
+            client
+        };
+
+        Ok(client)
+    }
+
+    #[cfg(feature = "notifications")]
+    pub fn notifications(
+        &mut self,
+        client_type: notifications::ClientType,
+    ) -> ClientResult<dyn notifications::NotificationClient> {
+        let client = if let Some(client) = self.notifications.get(&client_type) {
+            client.clone()
+        } else {
+            let client = time("notifications", || {
+                await_sync(notifications::create_client(client_type))
+            })?;
+            self.notifications.insert(client_type, client.clone());
+            client
+        };
+
+        Ok(client)
+    }
+
+    /// Gets the built-in notification daemon, starting it if necessary.
+    ///
+    /// The history size and denied applications are taken from the first caller.
     #[cfg(feature = "notifications")]
-    pub fn notifications(&mut self) -> ClientResult<swaync::Client> {
-        let client = if let Some(client) = &self.notifications {
+    pub fn notification_daemon(
+        &mut self,
+        history_size: usize,
+        denied_apps: &[String],
+    ) -> ClientResult<notifications::daemon::Client> {
+        let client = if let Some(client) = &self.notification_daemon {
+            client.clone()
+        } else {
+            let client = time("notification_daemon", || {
+                await_sync(async {
+                    notifications::daemon::Client::new(history_size, denied_apps.to_vec()).await
+                })
+            })?;
+            let client = Arc::new(client);
+            self.notification_daemon.replace(client.clone());
+            client
+        };
+
+        Ok(client)
+    }
+
+    #[cfg(feature = "screencast")]
+    pub fn screencast(&mut self) -> ClientResult<screencast::Client> {
+        let client = if let Some(client) = &self.screencast {
+            client.clone()
+        } else {
+            let client = time("screencast", || {
+                await_sync(async { screencast::Client::new().await })
+            })?;
+            let client = Arc::new(client);
+            self.screencast.replace(client.clone());
+            client
+        };
+
+        Ok(client)
+    }
+
+    #[cfg(feature = "syncthing")]
+    pub fn syncthing(&mut self, url: &str, api_key: &str) -> ClientResult<syncthing::Client> {
+        let client = if let Some(client) = self.syncthing.get(url) {
             client.clone()
         } else {
-            let client = await_sync(async { swaync::Client::new().await })?;
+            let client = time("syncthing", || syncthing::Client::new(url, api_key))?;
             let client = Arc::new(client);
-            self.notifications.replace(client.clone());
+            self.syncthing.insert(url.into(), client.clone());
             client
         };
 
@@ -237,7 +488,7 @@ impl Clients {
     pub fn sys_info(&mut self) -> Arc<sysinfo::Client> {
         self.sys_info
             .get_or_insert_with(|| {
-                let client = Arc::new(sysinfo::Client::new());
+                let client = time("sys_info", || Arc::new(sysinfo::Client::new()));
 
                 #[cfg(any(feature = "ipc", feature = "cairo"))]
                 Ironbar::variable_manager().register_namespace("sysinfo", client.clone());
@@ -247,12 +498,28 @@ impl Clients {
             .clone()
     }
 
+    #[cfg(feature = "systemd")]
+    pub fn systemd(&mut self, bus: systemd::Bus) -> ClientResult<systemd::Client> {
+        let client = if let Some(client) = self.systemd.get(&bus) {
+            client.clone()
+        } else {
+            let client = time("systemd", || {
+                await_sync(async { systemd::Client::new(bus).await })
+            })?;
+            let client = Arc::new(client);
+            self.systemd.insert(bus, client.clone());
+            client
+        };
+
+        Ok(client)
+    }
+
     #[cfg(feature = "tray")]
     pub fn tray(&mut self) -> ClientResult<tray::Client> {
         let client = if let Some(client) = &self.tray {
             client.clone()
         } else {
-            let client = await_sync(async { tray::Client::new().await })?;
+            let client = time("tray", || await_sync(async { tray::Client::new().await }))?;
             self.tray.replace(client.clone());
             client
         };
@@ -265,7 +532,9 @@ impl Clients {
         let client = if let Some(client) = &self.upower {
             client.clone()
         } else {
-            let client = await_sync(async { upower::Client::new().await })?;
+            let client = time("upower", || {
+                await_sync(async { upower::Client::new().await })
+            })?;
 
             #[cfg(any(feature = "ipc", feature = "cairo"))]
             Ironbar::variable_manager().register_namespace("upower", client.clone());
@@ -277,19 +546,52 @@ impl Clients {
         Ok(client)
     }
 
+    #[cfg(feature = "battery")]
+    pub fn power_profiles(&mut self) -> ClientResult<power_profiles::Client> {
+        let client = if let Some(client) = &self.power_profiles {
+            client.clone()
+        } else {
+            let client = time("power_profiles", || {
+                await_sync(async { power_profiles::Client::new().await })
+            })?;
+            let client = Arc::new(client);
+
+            #[cfg(any(feature = "ipc", feature = "cairo"))]
+            Ironbar::variable_manager().register_namespace("power_profiles", client.clone());
+
+            self.power_profiles.replace(client.clone());
+            client
+        };
+
+        Ok(client)
+    }
+
     #[cfg(feature = "volume")]
-    pub fn volume(&mut self) -> Arc<volume::Client> {
+    pub fn volume(&mut self, client_type: volume::ClientType) -> Arc<dyn volume::VolumeClient> {
         self.volume
-            .get_or_insert_with(volume::create_client)
+            .entry(client_type)
+            .or_insert_with(|| time("volume", || volume::create_client(client_type)))
             .clone()
     }
 
+    /// Gets the first started volume client,
+    /// or starts the default client if there are none.
+    #[cfg(all(feature = "volume", feature = "ipc"))]
+    pub fn any_volume(&mut self) -> Arc<dyn volume::VolumeClient> {
+        match self.volume.values().next() {
+            Some(client) => client.clone(),
+            None => self.volume(volume::ClientType::default()),
+        }
+    }
+
     #[cfg(feature = "bluetooth")]
     pub fn bluetooth(&mut self) -> ClientResult<bluetooth::Client> {
         let client = if let Some(client) = &self.bluetooth {
             client.clone()
         } else {
-            let client = await_sync(async { bluetooth::Client::new().await })?;
+            let client = time("bluetooth", || {
+                await_sync(async { bluetooth::Client::new().await })
+            })?;
             let client = Arc::new(client);
             self.bluetooth.replace(client.clone());
             client
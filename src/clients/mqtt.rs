@@ -0,0 +1,137 @@
+use crate::channels::SyncSenderExt;
+use crate::clients::reconnect;
+use crate::{arc_mut, lock, spawn};
+use color_eyre::Result;
+use rumqttc::{AsyncClient, Event, EventLoop, MqttOptions, Packet, QoS};
+use std::collections::HashSet;
+use std::process;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{debug, error};
+
+/// The number of requests which can be queued
+/// while the connection is busy.
+const REQUEST_CAPACITY: usize = 16;
+
+/// A message received on a subscribed topic.
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub topic: String,
+    pub payload: String,
+}
+
+#[derive(Debug)]
+pub struct Client {
+    client: AsyncClient,
+    /// Topic filters to resubscribe to after reconnecting.
+    topics: Arc<Mutex<HashSet<String>>>,
+    tx: broadcast::Sender<Message>,
+    _rx: broadcast::Receiver<Message>,
+}
+
+impl Client {
+    /// Creates a client connected to the MQTT broker at `host:port`,
+    /// optionally authenticating with a username and password.
+    pub fn new(host: &str, port: u16, credentials: Option<(String, String)>) -> Self {
+        let mut options = MqttOptions::new(format!("ironbar-{}", process::id()), host, port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if let Some((username, password)) = credentials {
+            options.set_credentials(username, password);
+        }
+
+        let (client, event_loop) = AsyncClient::new(options, REQUEST_CAPACITY);
+        let event_loop = Arc::new(tokio::sync::Mutex::new(event_loop));
+        let topics = arc_mut!(HashSet::new());
+        let (tx, rx) = broadcast::channel(32);
+
+        {
+            let client = client.clone();
+            let topics = topics.clone();
+            let tx = tx.clone();
+
+            // the event loop reconnects itself when polled after an error,
+            // so each session only needs to poll until the next error.
+            spawn(reconnect::run("mqtt", move |session| {
+                let client = client.clone();
+                let topics = topics.clone();
+                let tx = tx.clone();
+                let event_loop = event_loop.clone();
+
+                async move {
+                    let mut event_loop = event_loop.lock().await;
+                    poll(&mut event_loop, &client, &topics, &tx, &session).await
+                }
+            }));
+        }
+
+        Self {
+            client,
+            topics,
+            tx,
+            _rx: rx,
+        }
+    }
+
+    /// Subscribes to messages received on every topic added using [`Client::add_topic`].
+    pub fn subscribe(&self) -> broadcast::Receiver<Message> {
+        self.tx.subscribe()
+    }
+
+    /// Starts receiving messages on topics matching the filter.
+    /// Filters may contain `+` and `#` wildcards.
+    pub fn add_topic(&self, filter: &str) {
+        if lock!(self.topics).insert(filter.to_string())
+            && let Err(err) = self.client.try_subscribe(filter, QoS::AtMostOnce)
+        {
+            error!("Failed to subscribe to {filter}: {err:?}");
+        }
+    }
+
+    /// Publishes the payload to the topic.
+    pub async fn publish(&self, topic: &str, payload: &str, retain: bool) -> Result<()> {
+        self.client
+            .publish(topic, QoS::AtLeastOnce, retain, payload.as_bytes())
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// Polls the event loop until the connection fails,
+/// forwarding received messages.
+async fn poll(
+    event_loop: &mut EventLoop,
+    client: &AsyncClient,
+    topics: &Mutex<HashSet<String>>,
+    tx: &broadcast::Sender<Message>,
+    session: &reconnect::Session,
+) -> Result<()> {
+    loop {
+        match event_loop.poll().await? {
+            Event::Incoming(Packet::ConnAck(_)) => {
+                session.connected();
+
+                // subscriptions do not persist with a clean session
+                for filter in lock!(topics).iter() {
+                    client.try_subscribe(filter, QoS::AtMostOnce)?;
+                }
+            }
+            Event::Incoming(Packet::Publish(publish)) => {
+                debug!("Received MQTT message on {}", publish.topic);
+
+                tx.send_expect(Message {
+                    topic: publish.topic,
+                    payload: String::from_utf8_lossy(&publish.payload).into_owned(),
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Checks whether the topic matches the filter,
+/// following MQTT wildcard rules.
+pub fn matches(topic: &str, filter: &str) -> bool {
+    rumqttc::matches(topic, filter)
+}
@@ -19,6 +19,12 @@ pub enum PlayerUpdate {
     /// Triggered at regular intervals while a track is playing.
     /// Used to keep track of the progress through the current track.
     ProgressTick(ProgressTick),
+    /// Triggered when the tracks in the queue change,
+    /// or a different track in the queue starts playing.
+    Queue(Queue),
+    /// Triggered when a player appears or shuts down,
+    /// or a different player becomes active.
+    Players(Players),
 }
 
 #[derive(Clone, Debug)]
@@ -49,6 +55,35 @@ pub struct Status {
     pub playlist_length: u32,
 }
 
+/// The upcoming and previous tracks of the player.
+#[derive(Clone, Debug, Default)]
+pub struct Queue {
+    pub tracks: Vec<QueueTrack>,
+    /// The index of the current track.
+    pub current: Option<usize>,
+    /// Whether tracks can be removed from the queue.
+    pub can_remove: bool,
+    /// Whether tracks can be moved within the queue.
+    pub can_reorder: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct QueueTrack {
+    /// The player's ID for the track,
+    /// used to jump to or edit it.
+    pub id: String,
+    pub track: Track,
+}
+
+/// The players which can be switched between.
+#[derive(Clone, Debug, Default)]
+pub struct Players {
+    /// The name of each player, in priority order.
+    pub names: Vec<String>,
+    /// The name of the active player.
+    pub current: Option<String>,
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct ProgressTick {
     pub duration: Option<Duration>,
@@ -66,6 +101,17 @@ pub trait MusicClient: Debug + Send + Sync {
     fn set_volume_percent(&self, vol: u8) -> Result<()>;
     fn seek(&self, duration: Duration) -> Result<()>;
 
+    /// Plays the track in the queue with the ID.
+    fn play_track(&self, id: &str) -> Result<()>;
+    /// Removes the track with the ID from the queue.
+    fn remove_track(&self, id: &str) -> Result<()>;
+    /// Moves the track with the ID to the index in the queue.
+    fn move_track(&self, id: &str, index: usize) -> Result<()>;
+
+    /// Switches to the player with the name,
+    /// keeping it active until it shuts down.
+    fn set_player(&self, name: &str) -> Result<()>;
+
     fn subscribe_change(&self) -> broadcast::Receiver<PlayerUpdate>;
 }
 
@@ -74,7 +120,10 @@ pub enum ClientType {
     #[cfg(feature = "music+mpd")]
     Mpd { host: String, music_dir: PathBuf },
     #[cfg(feature = "music+mpris")]
-    Mpris,
+    Mpris {
+        player_priority: Vec<String>,
+        ignored_players: Vec<String>,
+    },
 }
 
 pub fn create_client(client_type: ClientType) -> Arc<dyn MusicClient> {
@@ -82,6 +131,9 @@ pub fn create_client(client_type: ClientType) -> Arc<dyn MusicClient> {
         #[cfg(feature = "music+mpd")]
         ClientType::Mpd { host, music_dir } => Arc::new(mpd::Client::new(host, music_dir)),
         #[cfg(feature = "music+mpris")]
-        ClientType::Mpris => Arc::new(mpris::Client::new()),
+        ClientType::Mpris {
+            player_priority,
+            ignored_players,
+        } => Arc::new(mpris::Client::new(player_priority, ignored_players)),
     }
 }
@@ -1,29 +1,37 @@
 use super::{
-    MusicClient, PlayerState, PlayerUpdate, ProgressTick, Result, Status, TICK_INTERVAL_MS, Track,
+    MusicClient, PlayerState, PlayerUpdate, ProgressTick, Queue, QueueTrack, Result, Status,
+    TICK_INTERVAL_MS, Track,
 };
 use crate::channels::SyncSenderExt;
-use crate::{Ironbar, await_sync, spawn};
-use mpd_client::client::{ConnectionEvent, Subsystem};
-use mpd_client::commands::{self, SeekMode};
+use crate::clients::reconnect;
+use crate::{arc_rw, await_sync, read_lock, spawn, write_lock};
+use mpd_client::client::{Connection, ConnectionEvent, Subsystem};
+use mpd_client::commands::{self, SeekMode, SongId, SongPosition};
+use mpd_client::protocol::MpdProtocolError;
 use mpd_client::responses::{PlayState, Song};
 use mpd_client::tag::Tag;
-use mpd_utils::{PersistentClient, mpd_client};
+use mpd_utils::mpd_client;
+use std::os::unix::fs::FileTypeExt;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use std::time::Duration;
+use tokio::net::{TcpStream, UnixStream};
 use tokio::sync::broadcast;
 use tokio::time::sleep;
-use tracing::debug;
 
 macro_rules! command {
     ($self:ident, $command:expr) => {
-        await_sync(async move { Ok($self.client.command($command).await?) })
+        await_sync(async move { Ok($self.client()?.command($command).await?) })
     };
 }
 
+type MpdClient = mpd_client::Client;
+
 #[derive(Debug)]
 pub struct Client {
-    client: Arc<PersistentClient>,
+    /// The client for the current connection,
+    /// or `None` while disconnected.
+    client: Arc<RwLock<Option<MpdClient>>>,
     music_dir: PathBuf,
     tx: broadcast::Sender<PlayerUpdate>,
     _rx: broadcast::Receiver<PlayerUpdate>,
@@ -31,36 +39,49 @@ pub struct Client {
 
 impl Client {
     pub fn new(host: String, music_dir: PathBuf) -> Self {
-        let client = Arc::new(PersistentClient::new(host, Duration::from_secs(5)));
-        let mut client_rx = client.subscribe();
-
+        let client = arc_rw!(None);
         let (tx, rx) = broadcast::channel(32);
 
-        let _guard = Ironbar::runtime().enter();
-        client.init();
-
         {
-            let tx = tx.clone();
             let client = client.clone();
+            let tx = tx.clone();
             let music_dir = music_dir.clone();
 
-            spawn(async move {
-                Self::send_update(&client, &tx, &music_dir)
-                    .await
-                    .expect("Failed to send update");
-
-                while let Ok(change) = client_rx.recv().await {
-                    debug!("Received state change: {change:?}");
-                    if let ConnectionEvent::SubsystemChange(
-                        Subsystem::Player | Subsystem::Queue | Subsystem::Mixer,
-                    ) = *change
-                    {
-                        Self::send_update(&client, &tx, &music_dir)
-                            .await
-                            .expect("Failed to send update");
-                    }
+            spawn(reconnect::run("mpd", move |session| {
+                let host = host.clone();
+                let client = client.clone();
+                let tx = tx.clone();
+                let music_dir = music_dir.clone();
+
+                async move {
+                    let (mpd, mut events) = connect(&host).await?;
+                    write_lock!(client).replace(mpd.clone());
+                    session.connected();
+
+                    Self::send_update(&mpd, &tx, &music_dir).await;
+                    Self::send_queue(&mpd, &tx, &music_dir).await;
+
+                    let res: color_eyre::Result<()> = loop {
+                        match events.next().await {
+                            Some(ConnectionEvent::SubsystemChange(
+                                Subsystem::Player | Subsystem::Queue,
+                            )) => {
+                                Self::send_update(&mpd, &tx, &music_dir).await;
+                                Self::send_queue(&mpd, &tx, &music_dir).await;
+                            }
+                            Some(ConnectionEvent::SubsystemChange(Subsystem::Mixer)) => {
+                                Self::send_update(&mpd, &tx, &music_dir).await;
+                            }
+                            Some(ConnectionEvent::SubsystemChange(_)) => {}
+                            Some(ConnectionEvent::ConnectionClosed(err)) => break Err(err.into()),
+                            None => break Ok(()),
+                        }
+                    };
+
+                    write_lock!(client).take();
+                    res
                 }
-            });
+            }));
         }
 
         {
@@ -69,7 +90,11 @@ impl Client {
 
             spawn(async move {
                 loop {
-                    Self::send_tick_update(&client, &tx).await;
+                    let mpd = read_lock!(client).clone();
+                    if let Some(mpd) = mpd {
+                        Self::send_tick_update(&mpd, &tx).await;
+                    }
+
                     sleep(Duration::from_millis(TICK_INTERVAL_MS)).await;
                 }
             });
@@ -83,11 +108,18 @@ impl Client {
         }
     }
 
+    /// Gets the client for the current connection.
+    fn client(&self) -> Result<MpdClient> {
+        read_lock!(self.client)
+            .clone()
+            .ok_or_else(|| "Not connected to MPD".into())
+    }
+
     async fn send_update(
-        client: &PersistentClient,
+        client: &MpdClient,
         tx: &broadcast::Sender<PlayerUpdate>,
         music_dir: &Path,
-    ) -> std::result::Result<(), broadcast::error::SendError<PlayerUpdate>> {
+    ) {
         let current_song = client.command(commands::CurrentSong).await;
         let status = client.command(commands::Status).await;
 
@@ -98,11 +130,35 @@ impl Client {
             let update = PlayerUpdate::Update(Box::new(track), status);
             tx.send_expect(update);
         }
+    }
+
+    async fn send_queue(
+        client: &MpdClient,
+        tx: &broadcast::Sender<PlayerUpdate>,
+        music_dir: &Path,
+    ) {
+        let queue = client.command(commands::Queue).await;
+        let status = client.command(commands::Status).await;
+
+        if let (Ok(queue), Ok(status)) = (queue, status) {
+            let queue = Queue {
+                tracks: queue
+                    .iter()
+                    .map(|s| QueueTrack {
+                        id: s.id.0.to_string(),
+                        track: convert_song(&s.song, music_dir),
+                    })
+                    .collect(),
+                current: status.current_song.map(|(pos, _)| pos.0),
+                can_remove: true,
+                can_reorder: true,
+            };
 
-        Ok(())
+            tx.send_expect(PlayerUpdate::Queue(queue));
+        }
     }
 
-    async fn send_tick_update(client: &PersistentClient, tx: &broadcast::Sender<PlayerUpdate>) {
+    async fn send_tick_update(client: &MpdClient, tx: &broadcast::Sender<PlayerUpdate>) {
         let status = client.command(commands::Status).await;
 
         if let Ok(status) = status
@@ -143,17 +199,59 @@ impl MusicClient for Client {
         command!(self, commands::Seek(SeekMode::Absolute(duration)))
     }
 
+    fn play_track(&self, id: &str) -> Result<()> {
+        let id = parse_id(id)?;
+        command!(self, commands::Play::song(id))
+    }
+
+    fn remove_track(&self, id: &str) -> Result<()> {
+        let id = parse_id(id)?;
+        command!(self, commands::Delete::id(id))
+    }
+
+    fn move_track(&self, id: &str, index: usize) -> Result<()> {
+        let id = parse_id(id)?;
+        command!(
+            self,
+            commands::Move::id(id).to_position(SongPosition(index))
+        )
+    }
+
+    fn set_player(&self, _name: &str) -> Result<()> {
+        Err("MPD does not support multiple players".into())
+    }
+
     fn subscribe_change(&self) -> broadcast::Receiver<PlayerUpdate> {
         let rx = self.tx.subscribe();
-        await_sync(async move {
-            Self::send_update(&self.client, &self.tx, &self.music_dir)
-                .await
-                .expect("to be able to send update");
-        });
+        if let Ok(client) = self.client() {
+            await_sync(async move {
+                Self::send_update(&client, &self.tx, &self.music_dir).await;
+                Self::send_queue(&client, &self.tx, &self.music_dir).await;
+            });
+        }
         rx
     }
 }
 
+/// Connects to the MPD server at `host`,
+/// which is either a Unix socket path or a TCP address.
+async fn connect(host: &str) -> std::result::Result<Connection, MpdProtocolError> {
+    let path = Path::new(host);
+    let is_socket = path
+        .metadata()
+        .is_ok_and(|metadata| metadata.file_type().is_socket());
+
+    if is_socket {
+        MpdClient::connect(UnixStream::connect(path).await?).await
+    } else {
+        MpdClient::connect(TcpStream::connect(host).await?).await
+    }
+}
+
+fn parse_id(id: &str) -> Result<SongId> {
+    Ok(SongId(id.parse()?))
+}
+
 fn convert_song(song: &Song, music_dir: &Path) -> Track {
     let (track, disc) = song.number();
 
@@ -1,11 +1,15 @@
-use super::{MusicClient, PlayerState, PlayerUpdate, Result, Status, TICK_INTERVAL_MS, Track};
+use super::{
+    MusicClient, PlayerState, PlayerUpdate, Players, Queue, QueueTrack, Result, Status,
+    TICK_INTERVAL_MS, Track,
+};
 use crate::channels::SyncSenderExt;
 use crate::clients::music::ProgressTick;
 use crate::{arc_mut, lock, spawn_blocking};
-use mpris::{DBusError, Event, Metadata, PlaybackStatus, Player, PlayerFinder};
+use mpris::{DBusError, Event, Metadata, PlaybackStatus, Player, PlayerFinder, TrackID, TrackList};
 use std::cmp;
-use std::collections::HashSet;
-use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::thread::sleep;
 use std::time::Duration;
 use tokio::sync::broadcast;
@@ -14,24 +18,116 @@ use tracing::{debug, error, trace};
 #[derive(Debug)]
 pub struct Client {
     current_player: Arc<Mutex<Option<String>>>,
+    players: Arc<Mutex<HashMap<String, PlayerInfo>>>,
+    /// Whether the current player was chosen by the user,
+    /// in which case it is kept until it shuts down.
+    pinned: Arc<AtomicBool>,
     tx: broadcast::Sender<PlayerUpdate>,
     _rx: broadcast::Receiver<PlayerUpdate>,
 }
 
+#[derive(Debug, Clone, Copy)]
+struct PlayerInfo {
+    /// The player's position in the priority list.
+    /// Lower is preferred.
+    rank: usize,
+    playing: bool,
+}
+
+/// Rules for which players to track,
+/// and which to prefer showing.
+#[derive(Debug)]
+struct PlayerRules {
+    priority: Vec<String>,
+    ignored: Vec<String>,
+}
+
+impl PlayerRules {
+    fn is_ignored(&self, player: &Player) -> bool {
+        self.ignored
+            .iter()
+            .any(|pattern| player_matches(pattern, player))
+    }
+
+    /// Gets the position of the first entry matching the player by name,
+    /// falling back to the position of `*`.
+    /// Players matching neither are ranked last.
+    fn rank(&self, player: &Player) -> usize {
+        self.priority
+            .iter()
+            .position(|pattern| pattern != "*" && player_matches(pattern, player))
+            .or_else(|| self.priority.iter().position(|pattern| pattern == "*"))
+            .unwrap_or(self.priority.len())
+    }
+}
+
+/// Checks whether the pattern is `*`,
+/// or matches the player's identity or bus name, ignoring case.
+///
+/// The bus name is the part after `org.mpris.MediaPlayer2.`,
+/// without any instance suffix.
+fn player_matches(pattern: &str, player: &Player) -> bool {
+    pattern == "*"
+        || player.identity().eq_ignore_ascii_case(pattern)
+        || player
+            .bus_name_player_name_part()
+            .eq_ignore_ascii_case(pattern)
+}
+
+/// Chooses the player to show when there is no current player,
+/// preferring playing players, followed by the highest priority.
+fn best_player(players: &HashMap<String, PlayerInfo>) -> Option<String> {
+    players
+        .iter()
+        .min_by_key(|(name, info)| (!info.playing, info.rank, name.to_lowercase()))
+        .map(|(name, _)| name.clone())
+}
+
+/// Checks whether a player which has started playing
+/// should replace the current player.
+///
+/// The current player is kept only while it is playing
+/// and has a higher priority.
+fn should_switch(
+    players: &HashMap<String, PlayerInfo>,
+    current: Option<&str>,
+    candidate: &str,
+) -> bool {
+    if current == Some(candidate) {
+        return false;
+    }
+
+    match (
+        current.and_then(|name| players.get(name)),
+        players.get(candidate),
+    ) {
+        (Some(current), Some(candidate)) => !current.playing || current.rank >= candidate.rank,
+        _ => true,
+    }
+}
+
 const NO_ACTIVE_PLAYER: &str = "com.github.altdesktop.playerctld.NoActivePlayer";
 const NO_REPLY: &str = "org.freedesktop.DBus.Error.NoReply";
 const NO_SERVICE: &str = "org.freedesktop.DBus.Error.ServiceUnknown";
 const NO_METHOD: &str = "org.freedesktop.DBus.Error.UnknownMethod";
 
 impl Client {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(player_priority: Vec<String>, ignored_players: Vec<String>) -> Self {
         let (tx, rx) = broadcast::channel(32);
 
         let current_player = arc_mut!(None);
+        let players = arc_mut!(HashMap::new());
+        let pinned = Arc::new(AtomicBool::new(false));
+
+        let rules = PlayerRules {
+            priority: player_priority,
+            ignored: ignored_players,
+        };
 
         {
-            let players_list = arc_mut!(HashSet::new());
+            let players = players.clone();
             let current_player = current_player.clone();
+            let pinned = pinned.clone();
             let tx = tx.clone();
 
             spawn_blocking(move || {
@@ -41,7 +137,7 @@ impl Client {
                 // so we have to keep polling the player list
                 loop {
                     // mpris-rs does not filter NoActivePlayer errors, so we have to do it ourselves
-                    let players = player_finder.find_all().unwrap_or_else(|e| match e {
+                    let found = player_finder.find_all().unwrap_or_else(|e| match e {
                         mpris::FindingError::DBusError(DBusError::TransportError(
                             transport_error,
                         )) if transport_error.name() == Some(NO_ACTIVE_PLAYER)
@@ -60,30 +156,58 @@ impl Client {
                     // This is because we almost never need to lock on players without locking on current_player.
                     {
                         let mut current_player_lock = lock!(current_player);
+                        let mut players_lock = lock!(players);
 
-                        let mut players_list_val = lock!(players_list);
-                        for player in players {
-                            let identity = player.identity();
+                        let mut changed = false;
 
-                            if current_player_lock.is_none() {
-                                debug!("Setting active player to '{identity}'");
-                                current_player_lock.replace(identity.to_string());
+                        for player in &found {
+                            let identity = player.identity();
 
-                                if let Err(err) = Self::send_update(&player, &tx) {
-                                    error!("{err:?}");
-                                }
+                            if players_lock.contains_key(identity) || rules.is_ignored(player) {
+                                continue;
                             }
-                            if !players_list_val.contains(identity) {
-                                debug!("Adding MPRIS player '{identity}'");
-                                players_list_val.insert(identity.to_string());
-
-                                Self::listen_player_events(
-                                    identity.to_string(),
-                                    players_list.clone(),
-                                    current_player.clone(),
-                                    tx.clone(),
-                                );
+
+                            debug!("Adding MPRIS player '{identity}'");
+
+                            let playing =
+                                matches!(player.get_playback_status(), Ok(PlaybackStatus::Playing));
+
+                            players_lock.insert(
+                                identity.to_string(),
+                                PlayerInfo {
+                                    rank: rules.rank(player),
+                                    playing,
+                                },
+                            );
+
+                            Self::listen_player_events(
+                                identity.to_string(),
+                                players.clone(),
+                                current_player.clone(),
+                                pinned.clone(),
+                                tx.clone(),
+                            );
+
+                            changed = true;
+                        }
+
+                        if current_player_lock.is_none()
+                            && let Some(identity) = best_player(&players_lock)
+                        {
+                            debug!("Setting active player to '{identity}'");
+
+                            if let Some(player) = found.iter().find(|p| p.identity() == identity)
+                                && let Err(err) = Self::send_update(player, &tx)
+                            {
+                                error!("{err:?}");
                             }
+
+                            current_player_lock.replace(identity);
+                            changed = true;
+                        }
+
+                        if changed {
+                            Self::send_players(current_player_lock.as_deref(), &players_lock, &tx);
                         }
                     }
                     // wait 1 second before re-checking players
@@ -108,6 +232,8 @@ impl Client {
 
         Self {
             current_player,
+            players,
+            pinned,
             tx,
             _rx: rx,
         }
@@ -115,8 +241,9 @@ impl Client {
 
     fn listen_player_events(
         player_id: String,
-        players: Arc<Mutex<HashSet<String>>>,
+        players: Arc<Mutex<HashMap<String, PlayerInfo>>>,
         current_player: Arc<Mutex<Option<String>>>,
+        pinned: Arc<AtomicBool>,
         tx: broadcast::Sender<PlayerUpdate>,
     ) {
         spawn_blocking(move || {
@@ -124,22 +251,40 @@ impl Client {
 
             if let Ok(player) = player_finder.find_by_name(&player_id) {
                 let identity = player.identity();
-                let handle_shutdown = |current_player_lock_option: Option<
-                    std::sync::MutexGuard<'_, Option<String>>,
-                >| {
-                    debug!("Player '{identity}' shutting down");
-                    // Lock of player before players (see new() to make sure order is consistent)
-                    if let Some(mut guard) = current_player_lock_option {
-                        guard.take();
-                    } else {
-                        lock!(current_player).take();
-                    }
-                    let mut players_locked = lock!(players);
-                    players_locked.remove(identity);
-                    if players_locked.is_empty() {
-                        tx.send_expect(PlayerUpdate::Update(Box::new(None), Status::default()));
-                    }
-                };
+                let handle_shutdown =
+                    |current_player_lock: Option<MutexGuard<'_, Option<String>>>| {
+                        debug!("Player '{identity}' shutting down");
+                        // Lock of player before players (see new() to make sure order is consistent)
+                        let mut current_player_lock =
+                            current_player_lock.unwrap_or_else(|| lock!(current_player));
+                        let mut players_lock = lock!(players);
+                        players_lock.remove(identity);
+
+                        if current_player_lock.as_deref() == Some(identity) {
+                            pinned.store(false, Ordering::Relaxed);
+                            *current_player_lock = best_player(&players_lock);
+
+                            match current_player_lock
+                                .as_ref()
+                                .and_then(|name| player_finder.find_by_name(name).ok())
+                            {
+                                Some(player) => {
+                                    if let Err(err) = Self::send_update(&player, &tx) {
+                                        error!("{err:?}");
+                                    }
+                                }
+                                None => {
+                                    tx.send_expect(PlayerUpdate::Update(
+                                        Box::new(None),
+                                        Status::default(),
+                                    ));
+                                    tx.send_expect(PlayerUpdate::Queue(Queue::default()));
+                                }
+                            }
+                        }
+
+                        Self::send_players(current_player_lock.as_deref(), &players_lock, &tx);
+                    };
 
                 for event in player.events()? {
                     trace!("Received player event from '{identity}': {event:?}");
@@ -160,9 +305,34 @@ impl Client {
                         }
                         Ok(_) => {
                             let mut current_player_lock = lock!(current_player);
-                            if matches!(event, Ok(Event::Playing)) {
-                                current_player_lock.replace(identity.to_string());
+
+                            if let Ok(Event::Playing | Event::Paused | Event::Stopped) = event {
+                                let playing = matches!(event, Ok(Event::Playing));
+                                let mut players_lock = lock!(players);
+
+                                if let Some(info) = players_lock.get_mut(identity) {
+                                    info.playing = playing;
+                                }
+
+                                if playing
+                                    && !pinned.load(Ordering::Relaxed)
+                                    && should_switch(
+                                        &players_lock,
+                                        current_player_lock.as_deref(),
+                                        identity,
+                                    )
+                                {
+                                    debug!("Setting active player to '{identity}'");
+                                    current_player_lock.replace(identity.to_string());
+
+                                    Self::send_players(
+                                        current_player_lock.as_deref(),
+                                        &players_lock,
+                                        &tx,
+                                    );
+                                }
                             }
+
                             if let Some(current_identity) = current_player_lock.as_ref()
                                 && current_identity == identity
                                 && let Err(err) = Self::send_update(&player, &tx)
@@ -186,15 +356,34 @@ impl Client {
         });
     }
 
+    /// Sends the tracked players, sorted by priority then name.
+    fn send_players(
+        current: Option<&str>,
+        players: &HashMap<String, PlayerInfo>,
+        tx: &broadcast::Sender<PlayerUpdate>,
+    ) {
+        let mut names = players
+            .iter()
+            .map(|(name, info)| (info.rank, name.to_lowercase(), name.clone()))
+            .collect::<Vec<_>>();
+        names.sort_unstable();
+
+        tx.send_expect(PlayerUpdate::Players(Players {
+            names: names.into_iter().map(|(_, _, name)| name).collect(),
+            current: current.map(ToString::to_string),
+        }));
+    }
+
     fn send_update(player: &Player, tx: &broadcast::Sender<PlayerUpdate>) -> Result<()> {
         debug!("Sending update using '{}'", player.identity());
 
         let metadata = player.get_metadata()?;
+        let current_id = metadata.track_id();
         let playback_status = player
             .get_playback_status()
             .unwrap_or(PlaybackStatus::Stopped);
 
-        let track_list = player.get_track_list();
+        let track_list = player.checked_get_track_list().ok().flatten();
 
         let volume_percent = player.get_volume().map(|vol| (vol * 100.0) as u8).ok();
 
@@ -202,7 +391,9 @@ impl Client {
             // MRPIS doesn't seem to provide playlist info reliably,
             // so we can just assume next/prev will work by bodging the numbers
             playlist_position: 1,
-            playlist_length: track_list.map(|list| list.len() as u32).unwrap_or(u32::MAX),
+            playlist_length: track_list
+                .as_ref()
+                .map_or(u32::MAX, |list| list.len() as u32),
             state: PlayerState::from(playback_status),
             volume_percent,
         };
@@ -212,9 +403,51 @@ impl Client {
         let player_update = PlayerUpdate::Update(Box::new(Some(track)), status);
         tx.send_expect(player_update);
 
+        let queue = match track_list {
+            Some(track_list) => Self::get_queue(player, &track_list, current_id.as_ref())
+                .unwrap_or_else(|err| {
+                    error!("Failed to get MPRIS track list: {err:?}");
+                    Queue::default()
+                }),
+            None => Queue::default(),
+        };
+        tx.send_expect(PlayerUpdate::Queue(queue));
+
         Ok(())
     }
 
+    /// Gets the metadata for each track in the track list.
+    ///
+    /// MPRIS has no method to move tracks,
+    /// so the queue can never be reordered.
+    fn get_queue(
+        player: &Player,
+        track_list: &TrackList,
+        current_id: Option<&TrackID>,
+    ) -> Result<Queue> {
+        let ids = track_list.ids();
+        let metadata = player.get_tracks_metadata(ids)?;
+
+        let tracks = ids
+            .iter()
+            .zip(metadata)
+            .map(|(id, metadata)| QueueTrack {
+                id: id.as_str().to_string(),
+                track: Track::from(metadata),
+            })
+            .collect();
+
+        let current = current_id
+            .and_then(|current_id| ids.iter().position(|id| id.as_str() == current_id.as_str()));
+
+        Ok(Queue {
+            tracks,
+            current,
+            can_remove: player.checked_can_edit_tracks(),
+            can_reorder: false,
+        })
+    }
+
     fn get_player(&self) -> Option<Player> {
         let player_name = lock!(self.current_player);
         let player_name = player_name.as_ref();
@@ -309,6 +542,56 @@ impl MusicClient for Client {
         Ok(())
     }
 
+    fn play_track(&self, id: &str) -> Result<()> {
+        if let Some(player) = Self::get_player(self) {
+            player.go_to(&TrackID::new(id)?)?;
+        } else {
+            error!("Could not find player");
+        }
+        Ok(())
+    }
+
+    fn remove_track(&self, id: &str) -> Result<()> {
+        if let Some(player) = Self::get_player(self) {
+            if !player.checked_can_edit_tracks() {
+                return Err("Player does not support removing tracks".into());
+            }
+
+            player.remove_track(&TrackID::new(id)?)?;
+        } else {
+            error!("Could not find player");
+        }
+        Ok(())
+    }
+
+    fn move_track(&self, _id: &str, _index: usize) -> Result<()> {
+        Err("MPRIS does not support moving tracks".into())
+    }
+
+    fn set_player(&self, name: &str) -> Result<()> {
+        {
+            let mut current_player = lock!(self.current_player);
+            let players = lock!(self.players);
+
+            if !players.contains_key(name) {
+                return Err(format!("Player '{name}' not found").into());
+            }
+
+            debug!("Setting active player to '{name}'");
+
+            self.pinned.store(true, Ordering::Relaxed);
+            current_player.replace(name.to_string());
+
+            Self::send_players(current_player.as_deref(), &players, &self.tx);
+        }
+
+        if let Some(player) = self.get_player() {
+            Self::send_update(&player, &self.tx)?;
+        }
+
+        Ok(())
+    }
+
     fn subscribe_change(&self) -> broadcast::Receiver<PlayerUpdate> {
         debug!("Creating new subscription");
         let rx = self.tx.subscribe();
@@ -329,6 +612,12 @@ impl MusicClient for Client {
                 .send_expect(PlayerUpdate::Update(Box::new(None), status));
         }
 
+        {
+            let current_player = lock!(self.current_player);
+            let players = lock!(self.players);
+            Self::send_players(current_player.as_deref(), &players, &self.tx);
+        }
+
         rx
     }
 }
@@ -0,0 +1,240 @@
+use rustix::io::Errno;
+use rustix::net::netlink::SocketAddrNetlink;
+use rustix::net::{AddressFamily, RecvFlags, SocketFlags, SocketType, bind, recv, socket_with};
+use std::fs;
+use std::io;
+use std::os::fd::OwnedFd;
+use std::path::Path;
+use tokio::io::unix::AsyncFd;
+
+const SYS_CLASS_NET: &str = "/sys/class/net";
+
+/// The multicast group for link changes.
+const RTMGRP_LINK: u32 = 0x1;
+/// The multicast group for IPv4 route changes.
+const RTMGRP_IPV4_ROUTE: u32 = 0x40;
+/// The multicast group for IPv6 route changes.
+const RTMGRP_IPV6_ROUTE: u32 = 0x400;
+
+/// The route flag set on routes which are in use.
+const RTF_UP: u32 = 0x1;
+/// The route flag set on unreachable routes.
+const RTF_REJECT: u32 = 0x200;
+
+/// The interface flag set on interfaces which are up.
+const IFF_UP: u32 = 0x1;
+
+/// The ARP hardware type of Ethernet interfaces.
+const ARPHRD_ETHER: &str = "1";
+
+/// The kind of network a link connects to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinkKind {
+    Wired,
+    Wifi,
+    Cellular,
+    Vpn,
+    Other,
+}
+
+/// Waits for the kernel to announce link or route changes,
+/// calling `on_change` once for each batch of changes.
+///
+/// Messages are not parsed, as callers re-read the state they need.
+pub async fn watch(mut on_change: impl FnMut()) -> io::Result<()> {
+    let socket = socket_with(
+        AddressFamily::NETLINK,
+        SocketType::RAW,
+        SocketFlags::NONBLOCK | SocketFlags::CLOEXEC,
+        None, // NETLINK_ROUTE
+    )?;
+
+    bind(
+        &socket,
+        &SocketAddrNetlink::new(0, RTMGRP_LINK | RTMGRP_IPV4_ROUTE | RTMGRP_IPV6_ROUTE),
+    )?;
+
+    let socket = AsyncFd::new(socket)?;
+    let mut buf = [0; 8192];
+
+    loop {
+        let mut guard = socket.readable().await?;
+
+        // drain every pending message before reading the new state
+        while let Ok(res) = guard.try_io(|socket| recv_message(socket.get_ref(), &mut buf)) {
+            res?;
+        }
+
+        on_change();
+    }
+}
+
+/// Receives a single message from the socket, discarding its contents.
+fn recv_message(socket: &OwnedFd, buf: &mut [u8]) -> io::Result<()> {
+    match recv(socket, buf, RecvFlags::empty()) {
+        // messages were dropped, but the state is re-read after either way
+        Ok(_) | Err(Errno::NOBUFS) => Ok(()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Gets the name of the interface used by the default route,
+/// preferring IPv4 routes.
+pub fn default_interface() -> Option<String> {
+    let ipv4 = fs::read_to_string("/proc/net/route").unwrap_or_default();
+    if let Some(interface) = ipv4_default_interface(&ipv4) {
+        return Some(interface.to_string());
+    }
+
+    let ipv6 = fs::read_to_string("/proc/net/ipv6_route").unwrap_or_default();
+    ipv6_default_interface(&ipv6).map(ToString::to_string)
+}
+
+/// Gets the kind of network the interface connects to.
+pub fn link_kind(interface: &str) -> LinkKind {
+    let path = Path::new(SYS_CLASS_NET).join(interface);
+
+    let uevent = fs::read_to_string(path.join("uevent")).unwrap_or_default();
+    let arp_type = fs::read_to_string(path.join("type")).unwrap_or_default();
+
+    parse_link_kind(
+        &uevent,
+        path.join("wireless").exists(),
+        path.join("tun_flags").exists(),
+        arp_type.trim(),
+    )
+}
+
+/// Checks whether any Wi-Fi interface is up.
+pub fn wireless_up() -> bool {
+    let Ok(entries) = fs::read_dir(SYS_CLASS_NET) else {
+        return false;
+    };
+
+    entries.flatten().any(|entry| {
+        let interface = entry.file_name();
+
+        link_kind(&interface.to_string_lossy()) == LinkKind::Wifi
+            && fs::read_to_string(entry.path().join("flags"))
+                .ok()
+                .and_then(|flags| parse_hex(flags.trim()))
+                .is_some_and(|flags| flags & IFF_UP != 0)
+    })
+}
+
+/// Gets the interface of the default route with the lowest metric
+/// from the contents of `/proc/net/route`.
+fn ipv4_default_interface(routes: &str) -> Option<&str> {
+    routes
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let columns: Vec<_> = line.split_whitespace().collect();
+
+            let &[interface, destination, _, flags, _, _, metric, mask, ..] = columns.as_slice()
+            else {
+                return None;
+            };
+
+            let flags = parse_hex(flags)?;
+            let default = destination == "00000000" && mask == "00000000";
+
+            (default && flags & RTF_UP != 0)
+                .then(|| Some((metric.parse::<u32>().ok()?, interface)))
+                .flatten()
+        })
+        .min_by_key(|&(metric, _)| metric)
+        .map(|(_, interface)| interface)
+}
+
+/// Gets the interface of the default route with the lowest metric
+/// from the contents of `/proc/net/ipv6_route`.
+fn ipv6_default_interface(routes: &str) -> Option<&str> {
+    routes
+        .lines()
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+
+            let destination = columns.next()?;
+            let prefix_len = columns.next()?;
+            // skip the source and next hop
+            let metric = columns.nth(3)?;
+            // skip the reference and use counts
+            let flags = columns.nth(2)?;
+            let interface = columns.next()?;
+
+            let flags = parse_hex(flags)?;
+            let default = destination.bytes().all(|b| b == b'0') && prefix_len == "00";
+
+            (default && flags & RTF_UP != 0 && flags & RTF_REJECT == 0)
+                .then(|| Some((parse_hex(metric)?, interface)))
+                .flatten()
+        })
+        .min_by_key(|&(metric, _)| metric)
+        .map(|(_, interface)| interface)
+}
+
+/// Gets the kind of network an interface connects to
+/// from its `uevent` file, whether it has `wireless` and `tun_flags` entries,
+/// and its ARP hardware type.
+fn parse_link_kind(uevent: &str, wireless: bool, tun: bool, arp_type: &str) -> LinkKind {
+    let dev_type = uevent
+        .lines()
+        .find_map(|line| line.strip_prefix("DEVTYPE="));
+
+    match dev_type {
+        Some("wlan") => LinkKind::Wifi,
+        Some("wwan") => LinkKind::Cellular,
+        Some("wireguard") => LinkKind::Vpn,
+        _ if wireless => LinkKind::Wifi,
+        _ if tun => LinkKind::Vpn,
+        _ if arp_type == ARPHRD_ETHER => LinkKind::Wired,
+        _ => LinkKind::Other,
+    }
+}
+
+fn parse_hex(value: &str) -> Option<u32> {
+    u32::from_str_radix(value, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ipv4_default_route() {
+        let routes = "\
+Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+wlan0\t00000000\t0102A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0
+eth0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0
+eth0\t0001A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0";
+
+        assert_eq!(ipv4_default_interface(routes), Some("eth0"));
+        assert_eq!(ipv4_default_interface(""), None);
+    }
+
+    #[test]
+    fn ipv6_default_route() {
+        let routes = "\
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 fe800000000000000000000000000001 00000400 00000001 00000000 00000003 wlan0
+fe800000000000000000000000000000 40 00000000000000000000000000000000 00 00000000000000000000000000000000 00000100 00000001 00000000 00000001 wlan0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 00000000000000000000000000000000 ffffffff 00000001 00000000 00200200 lo";
+
+        assert_eq!(ipv6_default_interface(routes), Some("wlan0"));
+    }
+
+    #[test]
+    fn link_kinds() {
+        assert_eq!(
+            parse_link_kind("DEVTYPE=wlan\nINTERFACE=wlan0", false, false, "1"),
+            LinkKind::Wifi
+        );
+        assert_eq!(
+            parse_link_kind("DEVTYPE=wireguard", false, false, "65534"),
+            LinkKind::Vpn
+        );
+        assert_eq!(parse_link_kind("", false, true, "65534"), LinkKind::Vpn);
+        assert_eq!(parse_link_kind("", false, false, "1"), LinkKind::Wired);
+        assert_eq!(parse_link_kind("", false, false, "772"), LinkKind::Other);
+    }
+}
@@ -1,24 +1,43 @@
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
+use crate::clients::netlink::{self, LinkKind};
 use crate::{register_fallible_client, spawn};
+use futures_lite::StreamExt;
 use futures_signals::signal::{Mutable, MutableSignalCloned};
-use tracing::error;
-use zbus::export::ordered_stream::OrderedStreamExt;
-use zbus::fdo::PropertiesProxy;
+use tokio::time::timeout;
+use tracing::{debug, error};
+use zbus::fdo::{DBusProxy, PropertiesProxy};
 use zbus::{
     Connection, Result,
-    names::InterfaceName,
+    names::{BusName, InterfaceName},
     proxy,
-    zvariant::{ObjectPath, Str},
+    zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Str, Value},
 };
 
 const DBUS_BUS: &str = "org.freedesktop.NetworkManager";
 const DBUS_PATH: &str = "/org/freedesktop/NetworkManager";
 const DBUS_INTERFACE: &str = "org.freedesktop.NetworkManager";
 
+/// The `DeviceType` of Wi-Fi devices.
+const DEVICE_TYPE_WIFI: u32 = 2;
+
+/// The `Flags` bit set on access points which require a password.
+const AP_FLAGS_PRIVACY: u32 = 0x1;
+/// The `RsnFlags` bit set on access points which support WPA3 personal.
+const AP_SEC_KEY_MGMT_SAE: u32 = 0x400;
+
+/// The longest to wait for a requested scan to finish.
+const SCAN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The `State` of active connections which are fully connected.
+const ACTIVE_CONNECTION_STATE_ACTIVATED: u32 = 2;
+
 #[derive(Debug)]
 pub struct Client {
     client_state: Mutable<ClientState>,
+    vpn_connections: Mutable<Vec<VpnConnection>>,
     interface_name: InterfaceName<'static>,
     dbus_connection: Connection,
     props_proxy: PropertiesProxy<'static>,
@@ -58,12 +77,166 @@ trait NetworkManagerDbus {
 
     #[zbus(property)]
     fn wireless_enabled(&self) -> Result<bool>;
+
+    fn activate_connection(
+        &self,
+        connection: &ObjectPath<'_>,
+        device: &ObjectPath<'_>,
+        specific_object: &ObjectPath<'_>,
+    ) -> Result<OwnedObjectPath>;
+
+    fn add_and_activate_connection(
+        &self,
+        connection: HashMap<&str, HashMap<&str, Value<'_>>>,
+        device: &ObjectPath<'_>,
+        specific_object: &ObjectPath<'_>,
+    ) -> Result<(OwnedObjectPath, OwnedObjectPath)>;
+
+    fn deactivate_connection(&self, active_connection: &ObjectPath<'_>) -> Result<()>;
+}
+
+#[proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.Settings",
+    default_path = "/org/freedesktop/NetworkManager/Settings"
+)]
+trait SettingsDbus {
+    fn list_connections(&self) -> Result<Vec<OwnedObjectPath>>;
+}
+
+#[proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.Connection.Active",
+    assume_defaults = false
+)]
+trait ActiveConnectionDbus {
+    #[zbus(property)]
+    fn connection(&self) -> Result<OwnedObjectPath>;
+
+    #[zbus(property)]
+    fn state(&self) -> Result<u32>;
+}
+
+#[proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.Device",
+    assume_defaults = false
+)]
+trait DeviceDbus {
+    #[zbus(property)]
+    fn device_type(&self) -> Result<u32>;
+
+    #[zbus(property)]
+    fn available_connections(&self) -> Result<Vec<OwnedObjectPath>>;
+}
+
+#[proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.Device.Wireless",
+    assume_defaults = false
+)]
+trait WirelessDbus {
+    #[zbus(property)]
+    fn access_points(&self) -> Result<Vec<OwnedObjectPath>>;
+
+    #[zbus(property)]
+    fn active_access_point(&self) -> Result<OwnedObjectPath>;
+
+    #[zbus(property)]
+    fn last_scan(&self) -> Result<i64>;
+
+    fn request_scan(&self, options: HashMap<&str, Value<'_>>) -> Result<()>;
+}
+
+#[proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.AccessPoint",
+    assume_defaults = false
+)]
+trait AccessPointDbus {
+    #[zbus(property)]
+    fn ssid(&self) -> Result<Vec<u8>>;
+
+    #[zbus(property)]
+    fn strength(&self) -> Result<u8>;
+
+    #[zbus(property)]
+    fn flags(&self) -> Result<u32>;
+
+    #[zbus(property)]
+    fn wpa_flags(&self) -> Result<u32>;
+
+    #[zbus(property)]
+    fn rsn_flags(&self) -> Result<u32>;
+}
+
+#[proxy(
+    default_service = "org.freedesktop.NetworkManager",
+    interface = "org.freedesktop.NetworkManager.Settings.Connection",
+    assume_defaults = false
+)]
+trait ConnectionDbus {
+    fn get_settings(&self) -> Result<HashMap<String, HashMap<String, OwnedValue>>>;
+}
+
+/// A Wi-Fi network visible to the wireless device.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AccessPoint {
+    /// The D-Bus path of the strongest access point for the network.
+    pub path: String,
+    pub ssid: String,
+    /// Signal strength, from `0` to `100`.
+    pub strength: u8,
+    /// Whether a password is required to connect.
+    pub secured: bool,
+    /// Whether WPA3 is supported.
+    pub sae: bool,
+    /// Whether the device is connected to the network.
+    pub active: bool,
+    /// Whether a connection has been saved for the network,
+    /// so it can be connected to without a password.
+    pub known: bool,
+}
+
+/// The state of a VPN connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VpnState {
+    Inactive,
+    Activating,
+    Active,
+}
+
+impl VpnState {
+    /// Gets the name of the state for use as a CSS class.
+    pub fn css_class(self) -> &'static str {
+        match self {
+            Self::Inactive => "inactive",
+            Self::Activating => "activating",
+            Self::Active => "active",
+        }
+    }
+}
+
+/// A saved VPN or WireGuard connection.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VpnConnection {
+    /// The D-Bus path of the saved connection.
+    pub path: String,
+    /// The D-Bus path of the active connection,
+    /// if the connection is not inactive.
+    pub active_path: Option<String>,
+    /// The user-facing connection name.
+    pub id: String,
+    /// `wireguard`, or the name of the VPN plugin such as `openvpn`.
+    pub kind: String,
+    pub state: VpnState,
 }
 
 impl Client {
     async fn new() -> Result<Self> {
         let client_state = Mutable::new(ClientState::Unknown);
-        let dbus_connection = Connection::system().await?;
+        let vpn_connections = Mutable::new(vec![]);
+        let dbus_connection = crate::clients::dbus::system().await?;
         let interface_name = InterfaceName::from_static_str(DBUS_INTERFACE)?;
         let props_proxy = PropertiesProxy::builder(&dbus_connection)
             .destination(DBUS_BUS)?
@@ -73,6 +246,7 @@ impl Client {
 
         Ok(Self {
             client_state,
+            vpn_connections,
             interface_name,
             dbus_connection,
             props_proxy,
@@ -82,8 +256,8 @@ impl Client {
     async fn run(&self) -> Result<()> {
         let proxy = NetworkManagerDbusProxy::new(&self.dbus_connection).await?;
 
-        let mut primary_connection = proxy.primary_connection().await?;
-        let mut primary_connection_type = proxy.primary_connection_type().await?;
+        let mut primary_connection = proxy.primary_connection().await?.to_string();
+        let mut primary_connection_type = proxy.primary_connection_type().await?.to_string();
         let mut wireless_enabled = proxy.wireless_enabled().await?;
 
         self.client_state.set(determine_state(
@@ -92,6 +266,8 @@ impl Client {
             wireless_enabled,
         ));
 
+        self.refresh_vpn_connections().await;
+
         let mut stream = self.props_proxy.receive_properties_changed().await?;
         while let Some(change) = stream.next().await {
             let args = change.args()?;
@@ -99,19 +275,27 @@ impl Client {
                 continue;
             }
 
-            let changed_props = args.changed_properties;
+            // new values are read from the signal itself,
+            // rather than making another round trip to fetch them.
             let mut relevant_prop_changed = false;
+            let mut vpn_changed = false;
+
+            for (key, value) in args.changed_properties {
+                match key {
+                    "ActiveConnections" | "ActivatingConnection" => {
+                        vpn_changed = true;
+                        continue;
+                    }
+                    "PrimaryConnection" => {
+                        primary_connection = value.downcast_ref::<ObjectPath>()?.to_string();
+                    }
+                    "PrimaryConnectionType" => {
+                        primary_connection_type = value.downcast_ref::<&str>()?.to_string();
+                    }
+                    "WirelessEnabled" => wireless_enabled = value.downcast_ref::<bool>()?,
+                    _ => continue,
+                }
 
-            if changed_props.contains_key("PrimaryConnection") {
-                primary_connection = proxy.primary_connection().await?;
-                relevant_prop_changed = true;
-            }
-            if changed_props.contains_key("PrimaryConnectionType") {
-                primary_connection_type = proxy.primary_connection_type().await?;
-                relevant_prop_changed = true;
-            }
-            if changed_props.contains_key("WirelessEnabled") {
-                wireless_enabled = proxy.wireless_enabled().await?;
                 relevant_prop_changed = true;
             }
 
@@ -122,14 +306,350 @@ impl Client {
                     wireless_enabled,
                 ));
             }
+
+            if vpn_changed || relevant_prop_changed {
+                self.refresh_vpn_connections().await;
+            }
         }
 
         Ok(())
     }
 
+    /// Follows the connection state using the kernel's links and routes,
+    /// for systems which do not run NetworkManager.
+    ///
+    /// The state is taken from the interface used by the default route.
+    async fn run_netlink(&self) -> std::io::Result<()> {
+        self.client_state.set(kernel_state());
+        netlink::watch(|| self.client_state.set(kernel_state())).await
+    }
+
+    /// Checks whether NetworkManager is running.
+    async fn is_running(&self) -> Result<bool> {
+        let dbus = DBusProxy::new(&self.dbus_connection).await?;
+        Ok(dbus.name_has_owner(BusName::try_from(DBUS_BUS)?).await?)
+    }
+
     pub fn subscribe(&self) -> MutableSignalCloned<ClientState> {
         self.client_state.signal_cloned()
     }
+
+    pub fn subscribe_vpn(&self) -> MutableSignalCloned<Vec<VpnConnection>> {
+        self.vpn_connections.signal_cloned()
+    }
+
+    async fn refresh_vpn_connections(&self) {
+        match self.fetch_vpn_connections().await {
+            Ok(connections) => {
+                if *self.vpn_connections.lock_ref() != connections {
+                    self.vpn_connections.set(connections);
+                }
+            }
+            Err(err) => error!("Failed to get VPN connections: {err:?}"),
+        }
+    }
+
+    /// Gets each saved VPN and WireGuard connection,
+    /// sorted by name.
+    async fn fetch_vpn_connections(&self) -> Result<Vec<VpnConnection>> {
+        let proxy = NetworkManagerDbusProxy::new(&self.dbus_connection).await?;
+        let settings = SettingsDbusProxy::new(&self.dbus_connection).await?;
+
+        // saved connection path to active connection path and state
+        let mut active = HashMap::new();
+
+        for path in proxy.active_connections().await? {
+            let active_connection = ActiveConnectionDbusProxy::builder(&self.dbus_connection)
+                .path(path.clone())?
+                .build()
+                .await?;
+
+            let state = if active_connection.state().await? == ACTIVE_CONNECTION_STATE_ACTIVATED {
+                VpnState::Active
+            } else {
+                VpnState::Activating
+            };
+
+            active.insert(
+                active_connection.connection().await?.to_string(),
+                (path.to_string(), state),
+            );
+        }
+
+        let mut connections = vec![];
+
+        for path in settings.list_connections().await? {
+            let connection = ConnectionDbusProxy::builder(&self.dbus_connection)
+                .path(path.clone())?
+                .build()
+                .await?;
+
+            let settings = connection.get_settings().await?;
+
+            let Some(kind) = settings.get("connection").and_then(|connection| {
+                match connection.get("type")?.downcast_ref::<&str>().ok()? {
+                    "wireguard" => Some("wireguard".to_string()),
+                    "vpn" => Some(
+                        settings
+                            .get("vpn")
+                            .and_then(|vpn| vpn.get("service-type"))
+                            .and_then(|service| service.downcast_ref::<&str>().ok())
+                            .and_then(|service| service.rsplit('.').next())
+                            .unwrap_or("vpn")
+                            .to_string(),
+                    ),
+                    _ => None,
+                }
+            }) else {
+                continue;
+            };
+
+            let id = settings
+                .get("connection")
+                .and_then(|connection| connection.get("id"))
+                .and_then(|id| id.downcast_ref::<&str>().ok())
+                .unwrap_or_default()
+                .to_string();
+
+            let path = path.to_string();
+            let (active_path, state) = match active.remove(&path) {
+                Some((active_path, state)) => (Some(active_path), state),
+                None => (None, VpnState::Inactive),
+            };
+
+            connections.push(VpnConnection {
+                path,
+                active_path,
+                id,
+                kind,
+                state,
+            });
+        }
+
+        connections.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(connections)
+    }
+
+    /// Brings the VPN connection up or down.
+    pub async fn set_vpn_active(&self, connection: &VpnConnection, active: bool) -> Result<()> {
+        let proxy = NetworkManagerDbusProxy::new(&self.dbus_connection).await?;
+
+        match (&connection.active_path, active) {
+            (None, true) => {
+                debug!("Activating VPN connection '{}'", connection.id);
+
+                // NetworkManager picks the device for VPN connections
+                let none = ObjectPath::from_str_unchecked("/");
+                proxy
+                    .activate_connection(
+                        &ObjectPath::try_from(connection.path.as_str())?,
+                        &none,
+                        &none,
+                    )
+                    .await?;
+            }
+            (Some(active_path), false) => {
+                debug!("Deactivating VPN connection '{}'", connection.id);
+
+                proxy
+                    .deactivate_connection(&ObjectPath::try_from(active_path.as_str())?)
+                    .await?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Gets the path of the first Wi-Fi device, if there is one.
+    async fn wireless_device(&self) -> Result<Option<OwnedObjectPath>> {
+        let proxy = NetworkManagerDbusProxy::new(&self.dbus_connection).await?;
+
+        for path in proxy.devices().await? {
+            let device = DeviceDbusProxy::builder(&self.dbus_connection)
+                .path(path.clone())?
+                .build()
+                .await?;
+
+            if device.device_type().await? == DEVICE_TYPE_WIFI {
+                return Ok(Some(path.into()));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Gets the SSID of each saved connection the device can use,
+    /// and the connection's path.
+    async fn known_networks(&self, device: &OwnedObjectPath) -> Result<HashMap<String, String>> {
+        let device = DeviceDbusProxy::builder(&self.dbus_connection)
+            .path(device.clone())?
+            .build()
+            .await?;
+
+        let mut networks = HashMap::new();
+
+        for path in device.available_connections().await? {
+            let connection = ConnectionDbusProxy::builder(&self.dbus_connection)
+                .path(path.clone())?
+                .build()
+                .await?;
+
+            let mut settings = connection.get_settings().await?;
+            let Some(ssid) = settings
+                .get_mut("802-11-wireless")
+                .and_then(|wireless| wireless.remove("ssid"))
+            else {
+                continue;
+            };
+
+            let ssid = Vec::<u8>::try_from(ssid)?;
+            networks.insert(String::from_utf8_lossy(&ssid).to_string(), path.to_string());
+        }
+
+        Ok(networks)
+    }
+
+    /// Gets each Wi-Fi network visible to the wireless device,
+    /// with the connected network first, followed by the strongest.
+    ///
+    /// Networks with several access points are only included once,
+    /// and hidden networks are not included.
+    pub async fn access_points(&self) -> Result<Vec<AccessPoint>> {
+        let Some(device) = self.wireless_device().await? else {
+            return Ok(vec![]);
+        };
+
+        let wireless = WirelessDbusProxy::builder(&self.dbus_connection)
+            .path(device.clone())?
+            .build()
+            .await?;
+
+        let active_path = wireless.active_access_point().await?;
+        let known = self.known_networks(&device).await?;
+
+        let mut networks: Vec<AccessPoint> = vec![];
+
+        for path in wireless.access_points().await? {
+            let proxy = AccessPointDbusProxy::builder(&self.dbus_connection)
+                .path(path.clone())?
+                .build()
+                .await?;
+
+            let ssid = String::from_utf8_lossy(&proxy.ssid().await?).to_string();
+            if ssid.is_empty() {
+                continue;
+            }
+
+            let (wpa_flags, rsn_flags) = (proxy.wpa_flags().await?, proxy.rsn_flags().await?);
+
+            let access_point = AccessPoint {
+                path: path.to_string(),
+                strength: proxy.strength().await?,
+                secured: proxy.flags().await? & AP_FLAGS_PRIVACY != 0
+                    || wpa_flags != 0
+                    || rsn_flags != 0,
+                sae: rsn_flags & AP_SEC_KEY_MGMT_SAE != 0,
+                active: path == active_path,
+                known: known.contains_key(&ssid),
+                ssid,
+            };
+
+            match networks
+                .iter_mut()
+                .find(|network| network.ssid == access_point.ssid)
+            {
+                Some(network) => {
+                    let active = network.active || access_point.active;
+                    if access_point.strength > network.strength {
+                        *network = access_point;
+                    }
+                    network.active = active;
+                }
+                None => networks.push(access_point),
+            }
+        }
+
+        networks.sort_by(|a, b| b.active.cmp(&a.active).then(b.strength.cmp(&a.strength)));
+
+        Ok(networks)
+    }
+
+    /// Asks the wireless device to scan for networks,
+    /// waiting until the scan has finished.
+    pub async fn scan(&self) -> Result<()> {
+        let Some(device) = self.wireless_device().await? else {
+            return Ok(());
+        };
+
+        let wireless = WirelessDbusProxy::builder(&self.dbus_connection)
+            .path(device)?
+            .build()
+            .await?;
+
+        // the stream starts with the time of the previous scan
+        let mut last_scan = wireless.receive_last_scan_changed().await;
+        last_scan.next().await;
+
+        debug!("Requesting Wi-Fi scan");
+        wireless.request_scan(HashMap::new()).await?;
+
+        if timeout(SCAN_TIMEOUT, last_scan.next()).await.is_err() {
+            debug!("Timed out waiting for Wi-Fi scan");
+        }
+
+        Ok(())
+    }
+
+    /// Connects to the network,
+    /// using its saved connection if there is one.
+    ///
+    /// A new connection is saved otherwise,
+    /// using the password for secured networks.
+    pub async fn connect(&self, access_point: &AccessPoint, password: Option<&str>) -> Result<()> {
+        let Some(device) = self.wireless_device().await? else {
+            return Ok(());
+        };
+
+        let proxy = NetworkManagerDbusProxy::new(&self.dbus_connection).await?;
+        let specific_object = ObjectPath::try_from(access_point.path.as_str())?;
+
+        if let Some(connection) = self.known_networks(&device).await?.get(&access_point.ssid) {
+            debug!("Activating saved connection for '{}'", access_point.ssid);
+
+            proxy
+                .activate_connection(
+                    &ObjectPath::try_from(connection.as_str())?,
+                    &device,
+                    &specific_object,
+                )
+                .await?;
+        } else {
+            debug!("Adding connection for '{}'", access_point.ssid);
+
+            // the rest of the connection is filled in from the access point
+            let mut connection = HashMap::new();
+
+            if let Some(password) = password {
+                let key_mgmt = if access_point.sae { "sae" } else { "wpa-psk" };
+
+                connection.insert(
+                    "802-11-wireless-security",
+                    HashMap::from([
+                        ("key-mgmt", Value::from(key_mgmt)),
+                        ("psk", Value::from(password)),
+                    ]),
+                );
+            }
+
+            proxy
+                .add_and_activate_connection(connection, &device, &specific_object)
+                .await?;
+        }
+
+        Ok(())
+    }
 }
 
 pub async fn create_client() -> Result<Arc<Client>> {
@@ -137,8 +657,19 @@ pub async fn create_client() -> Result<Arc<Client>> {
     {
         let client = client.clone();
         spawn(async move {
-            if let Err(error) = client.run().await {
-                error!("{}", error);
+            match client.is_running().await {
+                Ok(true) => {
+                    if let Err(error) = client.run().await {
+                        error!("{}", error);
+                    }
+                }
+                Ok(false) => {
+                    debug!("NetworkManager is not running, following links and routes instead");
+                    if let Err(error) = client.run_netlink().await {
+                        error!("Failed to monitor network links: {error}");
+                    }
+                }
+                Err(error) => error!("{}", error),
             }
         });
     }
@@ -167,4 +698,19 @@ fn determine_state(
     }
 }
 
+/// Determines the connection state from the kernel's links and routes.
+fn kernel_state() -> ClientState {
+    match netlink::default_interface() {
+        Some(interface) => match netlink::link_kind(&interface) {
+            LinkKind::Wired => ClientState::WiredConnected,
+            LinkKind::Wifi => ClientState::WifiConnected,
+            LinkKind::Cellular => ClientState::CellularConnected,
+            LinkKind::Vpn => ClientState::VpnConnected,
+            LinkKind::Other => ClientState::Unknown,
+        },
+        None if netlink::wireless_up() => ClientState::WifiDisconnected,
+        None => ClientState::Offline,
+    }
+}
+
 register_fallible_client!(Client, network_manager);
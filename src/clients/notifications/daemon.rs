@@ -0,0 +1,355 @@
+//! A notification server implementing the
+//! [desktop notifications specification](https://specifications.freedesktop.org/notification-spec/latest/),
+//! allowing notifications to be shown without a separate daemon.
+
+use super::Notification;
+use crate::channels::SyncSenderExt;
+use crate::lock;
+use color_eyre::Result;
+use color_eyre::eyre::WrapErr;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::{debug, error};
+use zbus::fdo::RequestNameFlags;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::OwnedValue;
+use zbus::{connection, interface};
+
+const NAME: &str = "org.freedesktop.Notifications";
+const PATH: &str = "/org/freedesktop/Notifications";
+
+/// The reasons a notification can be closed,
+/// as sent in the `NotificationClosed` signal.
+#[derive(Debug, Clone, Copy)]
+enum CloseReason {
+    Dismissed = 2,
+    Closed = 3,
+}
+
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// A notification was added, or replaced an existing one.
+    Notify(Notification),
+    /// The notification with the ID was removed from the history.
+    Close(u32),
+    /// Do not disturb mode was toggled.
+    Dnd(bool),
+}
+
+#[derive(Debug)]
+struct State {
+    last_id: u32,
+    /// All open notifications, oldest first.
+    history: VecDeque<Notification>,
+    history_size: usize,
+    /// Applications whose notifications are ignored.
+    denied_apps: Vec<String>,
+    dnd: bool,
+}
+
+impl State {
+    /// Removes the notification with the ID,
+    /// returning whether it existed.
+    fn remove(&mut self, id: u32) -> bool {
+        let len = self.history.len();
+        self.history.retain(|notification| notification.id != id);
+        self.history.len() != len
+    }
+}
+
+/// The D-Bus interface served to applications.
+struct Server {
+    state: Arc<Mutex<State>>,
+    tx: broadcast::Sender<Event>,
+}
+
+#[interface(name = "org.freedesktop.Notifications")]
+impl Server {
+    fn get_capabilities(&self) -> Vec<&str> {
+        vec![
+            "actions",
+            "body",
+            "icon-static",
+            "persistence",
+            "inline-reply",
+        ]
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn notify(
+        &self,
+        app_name: String,
+        replaces_id: u32,
+        app_icon: String,
+        summary: String,
+        body: String,
+        actions: Vec<String>,
+        hints: HashMap<String, OwnedValue>,
+        expire_timeout: i32,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> u32 {
+        let (notification, removed) = {
+            let mut state = lock!(self.state);
+
+            let replaces = replaces_id != 0
+                && state
+                    .history
+                    .iter()
+                    .any(|notification| notification.id == replaces_id);
+
+            let id = if replaces {
+                replaces_id
+            } else {
+                // ids must be non-zero
+                state.last_id = state.last_id.checked_add(1).unwrap_or(1);
+                state.last_id
+            };
+
+            let notification = Notification::new(
+                id,
+                app_name,
+                app_icon,
+                summary,
+                body,
+                &actions,
+                &hints,
+                expire_timeout,
+            );
+
+            debug!("Received notification: {notification:?}");
+
+            // the application is not told, so behaves as normal
+            if notification.is_from(&state.denied_apps) {
+                debug!("Ignoring notification from '{}'", notification.app_name);
+                return id;
+            }
+
+            let mut removed = vec![];
+
+            if let Some(existing) = state
+                .history
+                .iter_mut()
+                .find(|notification| notification.id == id)
+            {
+                *existing = notification.clone();
+            } else {
+                state.history.push_back(notification.clone());
+
+                while state.history.len() > state.history_size {
+                    if let Some(oldest) = state.history.pop_front() {
+                        removed.push(oldest.id);
+                    }
+                }
+            }
+
+            (notification, removed)
+        };
+
+        let id = notification.id;
+        self.tx.send_expect(Event::Notify(notification));
+
+        for id in removed {
+            self.tx.send_expect(Event::Close(id));
+            if let Err(err) =
+                Self::notification_closed(&emitter, id, CloseReason::Dismissed as u32).await
+            {
+                error!("{err:?}");
+            }
+        }
+
+        id
+    }
+
+    async fn close_notification(
+        &self,
+        id: u32,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) {
+        let removed = lock!(self.state).remove(id);
+
+        if removed {
+            self.tx.send_expect(Event::Close(id));
+            if let Err(err) =
+                Self::notification_closed(&emitter, id, CloseReason::Closed as u32).await
+            {
+                error!("{err:?}");
+            }
+        }
+    }
+
+    #[zbus(out_args("name", "vendor", "version", "spec_version"))]
+    fn get_server_information(&self) -> (&str, &str, &str, &str) {
+        (
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+            "1.2",
+        )
+    }
+
+    #[zbus(signal)]
+    async fn notification_closed(
+        emitter: &SignalEmitter<'_>,
+        id: u32,
+        reason: u32,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn action_invoked(
+        emitter: &SignalEmitter<'_>,
+        id: u32,
+        action_key: &str,
+    ) -> zbus::Result<()>;
+
+    /// Sent when the user replies to a notification inline.
+    /// This is a KDE extension to the specification.
+    #[zbus(signal)]
+    async fn notification_replied(
+        emitter: &SignalEmitter<'_>,
+        id: u32,
+        text: &str,
+    ) -> zbus::Result<()>;
+}
+
+#[derive(Debug)]
+pub struct Client {
+    connection: zbus::Connection,
+    state: Arc<Mutex<State>>,
+    tx: broadcast::Sender<Event>,
+    _rx: broadcast::Receiver<Event>,
+}
+
+impl Client {
+    /// Starts serving the notifications interface on the session bus.
+    ///
+    /// This fails if another notification daemon is already running.
+    pub async fn new(history_size: usize, denied_apps: Vec<String>) -> Result<Self> {
+        let state = Arc::new(Mutex::new(State {
+            last_id: 0,
+            history: VecDeque::new(),
+            history_size,
+            denied_apps,
+            dnd: false,
+        }));
+
+        let (tx, rx) = broadcast::channel(32);
+
+        let server = Server {
+            state: state.clone(),
+            tx: tx.clone(),
+        };
+
+        let connection = Box::pin(
+            connection::Builder::session()?
+                .serve_at(PATH, server)?
+                .build(),
+        )
+        .await?;
+
+        // fail rather than queue behind the running daemon
+        connection
+            .request_name_with_flags(NAME, RequestNameFlags::DoNotQueue.into())
+            .await
+            .wrap_err("Failed to start notification daemon. Is another daemon already running?")?;
+
+        Ok(Self {
+            connection,
+            state,
+            tx,
+            _rx: rx,
+        })
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.tx.subscribe()
+    }
+
+    /// Gets all open notifications, newest first.
+    pub fn notifications(&self) -> Vec<Notification> {
+        lock!(self.state).history.iter().rev().cloned().collect()
+    }
+
+    pub fn dnd(&self) -> bool {
+        lock!(self.state).dnd
+    }
+
+    pub fn toggle_dnd(&self) {
+        let dnd = !self.dnd();
+        self.set_dnd(dnd);
+    }
+
+    pub fn set_dnd(&self, dnd: bool) {
+        debug!("Setting do not disturb: {dnd}");
+        lock!(self.state).dnd = dnd;
+        self.tx.send_expect(Event::Dnd(dnd));
+    }
+
+    /// Dismisses the notification with the ID.
+    pub async fn close(&self, id: u32) {
+        let removed = lock!(self.state).remove(id);
+
+        if removed {
+            self.tx.send_expect(Event::Close(id));
+            self.emit_closed(id, CloseReason::Dismissed).await;
+        }
+    }
+
+    /// Dismisses all notifications.
+    pub async fn clear(&self) {
+        let ids = lock!(self.state)
+            .history
+            .drain(..)
+            .map(|notification| notification.id)
+            .collect::<Vec<_>>();
+
+        for id in ids {
+            self.tx.send_expect(Event::Close(id));
+            self.emit_closed(id, CloseReason::Dismissed).await;
+        }
+    }
+
+    /// Invokes the action on the notification with the ID,
+    /// then dismisses it unless it is resident.
+    pub async fn invoke_action(&self, id: u32, key: &str) {
+        debug!("Invoking action '{key}' on notification {id}");
+
+        let resident = lock!(self.state)
+            .history
+            .iter()
+            .any(|notification| notification.id == id && notification.resident);
+
+        let emitter = self.emitter();
+        if let Err(err) = Server::action_invoked(&emitter, id, key).await {
+            error!("{err:?}");
+        }
+
+        if !resident {
+            self.close(id).await;
+        }
+    }
+
+    /// Sends an inline reply to the notification with the ID,
+    /// then dismisses it.
+    pub async fn reply(&self, id: u32, text: &str) {
+        debug!("Replying to notification {id}");
+
+        let emitter = self.emitter();
+        if let Err(err) = Server::notification_replied(&emitter, id, text).await {
+            error!("{err:?}");
+        }
+
+        self.close(id).await;
+    }
+
+    async fn emit_closed(&self, id: u32, reason: CloseReason) {
+        let emitter = self.emitter();
+        if let Err(err) = Server::notification_closed(&emitter, id, reason as u32).await {
+            error!("{err:?}");
+        }
+    }
+
+    fn emitter(&self) -> SignalEmitter<'static> {
+        SignalEmitter::new(&self.connection, PATH).expect("to be valid object path")
+    }
+}
@@ -0,0 +1,156 @@
+//! Client for the [mako](https://github.com/emersion/mako) notification daemon.
+
+use super::{NotificationClient, State};
+use crate::channels::SyncSenderExt;
+use crate::clients::reconnect::{self, Session};
+use crate::{lock, poll, spawn};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{debug, error};
+use zbus::proxy;
+use zbus::zvariant::OwnedValue;
+
+/// The mode conventionally used to hide notifications,
+/// which must be configured in mako.
+const DND_MODE: &str = "do-not-disturb";
+
+/// mako does not signal changes, so its state is polled.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[proxy(
+    interface = "fr.emersion.Mako",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/fr/emersion/Mako"
+)]
+trait Mako {
+    fn list_notifications(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+
+    /// Restores the most recently dismissed notification from the history.
+    fn restore_notification(&self) -> zbus::Result<()>;
+
+    fn list_modes(&self) -> zbus::Result<Vec<String>>;
+
+    fn set_modes(&self, modes: &[&str]) -> zbus::Result<()>;
+}
+
+#[derive(Debug)]
+pub struct Client {
+    proxy: MakoProxy<'static>,
+    state: Arc<Mutex<Option<State>>>,
+    tx: broadcast::Sender<State>,
+    _rx: broadcast::Receiver<State>,
+}
+
+impl Client {
+    pub async fn new() -> zbus::Result<Self> {
+        let dbus = crate::clients::dbus::session().await?;
+
+        let proxy = MakoProxy::new(&dbus).await?;
+        let (tx, rx) = broadcast::channel(8);
+        let state = Arc::new(Mutex::new(None));
+
+        {
+            let proxy = proxy.clone();
+            let state = state.clone();
+            let tx = tx.clone();
+
+            spawn(reconnect::run("mako", move |session| {
+                poll(proxy.clone(), state.clone(), tx.clone(), session)
+            }));
+        }
+
+        Ok(Self {
+            proxy,
+            state,
+            tx,
+            _rx: rx,
+        })
+    }
+}
+
+/// Sends the state each time it changes,
+/// until the connection to mako is lost.
+async fn poll(
+    proxy: MakoProxy<'static>,
+    state: Arc<Mutex<Option<State>>>,
+    tx: broadcast::Sender<State>,
+    session: Session,
+) -> zbus::Result<()> {
+    // fails if mako is not running
+    let mut last = get_state(&proxy).await?;
+    session.connected();
+
+    lock!(state).replace(last);
+    tx.send_expect(last);
+
+    loop {
+        poll::sleep(POLL_INTERVAL).await;
+
+        let new_state = get_state(&proxy).await?;
+        if new_state != last {
+            debug!("Received state: {new_state:?}");
+
+            last = new_state;
+            lock!(state).replace(new_state);
+            tx.send_expect(new_state);
+        }
+    }
+}
+
+async fn get_state(proxy: &MakoProxy<'_>) -> zbus::Result<State> {
+    let count = proxy.list_notifications().await?.len();
+    let modes = proxy.list_modes().await?;
+
+    Ok(State {
+        count: u32::try_from(count).unwrap_or(u32::MAX),
+        dnd: modes.iter().any(|mode| mode == DND_MODE),
+        panel_open: false,
+    })
+}
+
+impl NotificationClient for Client {
+    fn subscribe(&self) -> broadcast::Receiver<State> {
+        self.tx.subscribe()
+    }
+
+    fn state(&self) -> Option<State> {
+        *lock!(self.state)
+    }
+
+    fn toggle_visibility(&self) {
+        debug!("Restoring last notification");
+
+        let proxy = self.proxy.clone();
+        spawn(async move {
+            if let Err(err) = proxy.restore_notification().await {
+                error!("{err:?}");
+            }
+        });
+    }
+
+    fn set_dnd(&self, dnd: bool) {
+        debug!("Setting do not disturb: {dnd}");
+
+        let proxy = self.proxy.clone();
+        spawn(async move {
+            let result = async {
+                // other active modes are kept
+                let mut modes = proxy.list_modes().await?;
+                modes.retain(|mode| mode != DND_MODE);
+
+                if dnd {
+                    modes.push(DND_MODE.to_string());
+                }
+
+                let modes = modes.iter().map(String::as_str).collect::<Vec<_>>();
+                proxy.set_modes(&modes).await
+            };
+
+            if let Err(err) = result.await {
+                error!("{err:?}");
+            }
+        });
+    }
+}
@@ -0,0 +1,201 @@
+pub mod daemon;
+mod mako;
+
+use super::swaync;
+use color_eyre::Result;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use zbus::zvariant::OwnedValue;
+
+/// The action key used by applications to request an inline reply.
+const INLINE_REPLY: &str = "inline-reply";
+
+/// The state of a notification daemon, as shown on the bar.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct State {
+    /// The number of notifications.
+    pub count: u32,
+    pub dnd: bool,
+    /// Whether the daemon's own panel is open.
+    pub panel_open: bool,
+}
+
+/// A client for an external notification daemon.
+pub trait NotificationClient: Debug + Send + Sync {
+    /// Gets an event receiver,
+    /// which is sent the new state each time it changes.
+    fn subscribe(&self) -> broadcast::Receiver<State>;
+
+    /// Gets the current state, if connected.
+    fn state(&self) -> Option<State>;
+
+    /// Toggles the daemon's panel.
+    /// Where the daemon has no panel,
+    /// the most recently dismissed notification is restored instead.
+    fn toggle_visibility(&self);
+
+    fn set_dnd(&self, dnd: bool);
+}
+
+/// The external notification daemon to connect to.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum ClientType {
+    /// [SwayNC](https://github.com/ErikReider/SwayNotificationCenter).
+    #[default]
+    Swaync,
+    /// [mako](https://github.com/emersion/mako), version 1.8 or later.
+    Mako,
+}
+
+pub async fn create_client(client_type: ClientType) -> Result<Arc<dyn NotificationClient>> {
+    let client: Arc<dyn NotificationClient> = match client_type {
+        ClientType::Swaync => Arc::new(swaync::Client::new().await?),
+        ClientType::Mako => Arc::new(mako::Client::new().await?),
+    };
+
+    Ok(client)
+}
+
+/// A notification sent by an application.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub id: u32,
+    pub app_name: String,
+    /// An icon name or path provided by the application.
+    pub app_icon: String,
+    pub summary: String,
+    pub body: String,
+    pub actions: Vec<Action>,
+    pub urgency: Urgency,
+    /// The number of milliseconds before the notification expires.
+    /// `-1` leaves this to the server, and `0` never expires.
+    pub expire_timeout: i32,
+    /// Placeholder text for the reply entry,
+    /// if the notification accepts inline replies.
+    pub reply_placeholder: Option<String>,
+    /// Whether the notification should stay open after an action is invoked.
+    pub resident: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Action {
+    pub key: String,
+    pub label: String,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+impl From<u8> for Urgency {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Low,
+            2 => Self::Critical,
+            _ => Self::Normal,
+        }
+    }
+}
+
+impl Urgency {
+    /// Gets the CSS class name for the urgency.
+    pub const fn class(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Normal => "normal",
+            Self::Critical => "critical",
+        }
+    }
+}
+
+impl Notification {
+    /// Checks whether the notification was sent by one of the applications,
+    /// ignoring case.
+    pub fn is_from(&self, apps: &[String]) -> bool {
+        apps.iter()
+            .any(|app| app.eq_ignore_ascii_case(&self.app_name))
+    }
+
+    /// Creates a notification from the arguments of a `Notify` call.
+    ///
+    /// Actions are sent as a flat list of alternating keys and labels.
+    /// The `inline-reply` action is taken from the list
+    /// and used to enable the reply entry instead.
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        id: u32,
+        app_name: String,
+        app_icon: String,
+        summary: String,
+        body: String,
+        actions: &[String],
+        hints: &HashMap<String, OwnedValue>,
+        expire_timeout: i32,
+    ) -> Self {
+        let hint_str = |key: &str| {
+            hints
+                .get(key)
+                .and_then(|value| value.downcast_ref::<&str>().ok())
+                .map(ToString::to_string)
+        };
+
+        let mut reply_placeholder = None;
+
+        let actions = actions
+            .chunks_exact(2)
+            .filter_map(|pair| {
+                if pair[0] == INLINE_REPLY {
+                    reply_placeholder = Some(
+                        hint_str("x-kde-reply-placeholder-text").unwrap_or_else(|| pair[1].clone()),
+                    );
+                    None
+                } else {
+                    Some(Action {
+                        key: pair[0].clone(),
+                        label: pair[1].clone(),
+                    })
+                }
+            })
+            .collect();
+
+        let urgency = hints
+            .get("urgency")
+            .and_then(|value| value.downcast_ref::<u8>().ok())
+            .map(Urgency::from)
+            .unwrap_or_default();
+
+        let resident = hints
+            .get("resident")
+            .and_then(|value| value.downcast_ref::<bool>().ok())
+            .unwrap_or_default();
+
+        // the icon may instead be sent as a hint
+        let app_icon = if app_icon.is_empty() {
+            hint_str("image-path").unwrap_or_default()
+        } else {
+            app_icon
+        };
+
+        Self {
+            id,
+            app_name,
+            app_icon,
+            summary,
+            body,
+            actions,
+            urgency,
+            expire_timeout,
+            reply_placeholder,
+            resident,
+        }
+    }
+}
@@ -48,7 +48,8 @@ impl MonitorProxy {
 
     fn connect_wayland(&mut self, wl_monitor: &OutputInfo) -> &mut Self {
         self.state = match &self.state {
-            InternalMonitorState::GdkConnected(gdk_monitor) => {
+            InternalMonitorState::GdkConnected(gdk_monitor)
+            | InternalMonitorState::BothConnected(_, gdk_monitor) => {
                 InternalMonitorState::BothConnected(wl_monitor.clone(), gdk_monitor.clone())
             }
             _ => InternalMonitorState::WaylandConnected(wl_monitor.clone()),
@@ -59,7 +60,8 @@ impl MonitorProxy {
 
     fn connect_gdk(&mut self, gdk_monitor: glib::SendWeakRef<Monitor>) -> &mut Self {
         self.state = match &self.state {
-            InternalMonitorState::WaylandConnected(wl_monitor) => {
+            InternalMonitorState::WaylandConnected(wl_monitor)
+            | InternalMonitorState::BothConnected(wl_monitor, _) => {
                 InternalMonitorState::BothConnected(wl_monitor.clone(), gdk_monitor)
             }
             _ => InternalMonitorState::GdkConnected(gdk_monitor),
@@ -68,6 +70,26 @@ impl MonitorProxy {
         self
     }
 
+    /// Updates the stored wayland output info.
+    ///
+    /// Returns whether the output is fully connected
+    /// and its description has changed,
+    /// in which case its bars need to be reloaded to match the new config.
+    fn update_wayland(&mut self, wl_monitor: &OutputInfo) -> bool {
+        match &mut self.state {
+            InternalMonitorState::WaylandConnected(info) => {
+                *info = wl_monitor.clone();
+                false
+            }
+            InternalMonitorState::BothConnected(info, _) => {
+                let changed = info.description != wl_monitor.description;
+                *info = wl_monitor.clone();
+                changed
+            }
+            _ => false,
+        }
+    }
+
     fn maybe_send(&self, tx: &Sender<MonitorEvent>) {
         match &self.state {
             InternalMonitorState::Disconnected => {
@@ -138,7 +160,11 @@ impl Client {
                             wayland::OutputEventType::Destroyed => {
                                 entry.disconnect().maybe_send(&output_tx)
                             }
-                            wayland::OutputEventType::Update => {}
+                            wayland::OutputEventType::Update => {
+                                if entry.update_wayland(&event.output) {
+                                    entry.maybe_send(&output_tx);
+                                }
+                            }
                         };
                     }
                 }
@@ -160,13 +186,7 @@ impl Client {
                             let output_tx = output_tx.clone();
                             let monitors = monitors.clone();
 
-                            /*
-                             * At this point, we have a `gdk::Monitor`. However, the object initially
-                             * has all its fields `None`, including `connector`.
-                             *
-                             * We have to listen for the notify event for the `connector` being set.
-                             */
-                            monitor.connect_notify(Some("connector"), move |m, _| {
+                            let connect = move |m: &Monitor| {
                                 if let Some(connector) = m.connector() {
                                     lock!(monitors)
                                         .entry(connector.to_string())
@@ -179,7 +199,20 @@ impl Client {
                                         )))
                                         .maybe_send(&output_tx);
                                 }
-                            });
+                            };
+
+                            /*
+                             * At this point, we usually have a `gdk::Monitor` with all its fields `None`,
+                             * including `connector`.
+                             *
+                             * We have to listen for the notify event for the `connector` being set.
+                             * On reconnect, the connector can already be set, so it is checked up front too.
+                             */
+                            if monitor.connector().is_some() {
+                                connect(&monitor);
+                            }
+
+                            monitor.connect_notify(Some("connector"), move |m, _| connect(m));
                         }
                     }
                 });
@@ -0,0 +1,172 @@
+use crate::channels::SyncSenderExt;
+use crate::clients::reconnect;
+use crate::{lock, register_fallible_client, spawn};
+use color_eyre::Result;
+use futures_lite::StreamExt;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::debug;
+use zbus::proxy;
+use zbus::zvariant::OwnedValue;
+
+#[proxy(
+    default_service = "net.hadess.PowerProfiles",
+    interface = "net.hadess.PowerProfiles",
+    default_path = "/net/hadess/PowerProfiles"
+)]
+trait PowerProfilesDbus {
+    #[zbus(property)]
+    fn active_profile(&self) -> zbus::Result<String>;
+
+    #[zbus(property)]
+    fn set_active_profile(&self, profile: &str) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn profiles(&self) -> zbus::Result<Vec<HashMap<String, OwnedValue>>>;
+}
+
+/// The power profiles provided by power-profiles-daemon.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct State {
+    /// The name of the active profile,
+    /// such as `performance`, `balanced` or `power-saver`.
+    pub active: String,
+    /// The name of each available profile.
+    pub profiles: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct Client {
+    proxy: PowerProfilesDbusProxy<'static>,
+    state: Arc<Mutex<State>>,
+    tx: broadcast::Sender<State>,
+    _rx: broadcast::Receiver<State>,
+}
+
+impl Client {
+    pub async fn new() -> Result<Self> {
+        let dbus = crate::clients::dbus::system().await?;
+
+        let proxy = PowerProfilesDbusProxy::new(&dbus).await?;
+        let (tx, rx) = broadcast::channel(8);
+        let state = Arc::new(Mutex::new(State::default()));
+
+        {
+            let proxy = proxy.clone();
+            let state = state.clone();
+            let tx = tx.clone();
+
+            spawn(reconnect::run("power_profiles", move |session| {
+                let proxy = proxy.clone();
+                let state = state.clone();
+                let tx = tx.clone();
+
+                async move {
+                    let mut active_stream = proxy.receive_active_profile_changed().await;
+                    let mut profiles_stream = proxy.receive_profiles_changed().await;
+                    let mut owner_stream = proxy.inner().receive_owner_changed().await?;
+
+                    // fails if the daemon is not running,
+                    // and re-sends the full state after a restart.
+                    let new_state = self::state(&proxy).await?;
+                    session.connected();
+
+                    *lock!(state) = new_state.clone();
+                    tx.send_expect(new_state);
+
+                    loop {
+                        tokio::select! {
+                            Some(_) = active_stream.next() => {}
+                            Some(_) = profiles_stream.next() => {}
+                            Some(owner) = owner_stream.next() => {
+                                if owner.is_none() {
+                                    break;
+                                }
+                                continue;
+                            }
+                            else => break,
+                        }
+
+                        let new_state = self::state(&proxy).await?;
+                        debug!("Power profiles changed: {new_state:?}");
+
+                        *lock!(state) = new_state.clone();
+                        tx.send_expect(new_state);
+                    }
+
+                    Ok::<(), zbus::Error>(())
+                }
+            }));
+        }
+
+        Ok(Self {
+            proxy,
+            state,
+            tx,
+            _rx: rx,
+        })
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<State> {
+        self.tx.subscribe()
+    }
+
+    pub fn state(&self) -> State {
+        lock!(self.state).clone()
+    }
+
+    /// Switches to the named profile.
+    pub async fn set_profile(&self, profile: &str) -> Result<()> {
+        debug!("Setting power profile: {profile}");
+        self.proxy.set_active_profile(profile).await?;
+        Ok(())
+    }
+}
+
+async fn state(proxy: &PowerProfilesDbusProxy<'_>) -> zbus::Result<State> {
+    let profiles = proxy
+        .profiles()
+        .await?
+        .iter()
+        .filter_map(|profile| {
+            profile
+                .get("Profile")?
+                .downcast_ref::<&str>()
+                .ok()
+                .map(ToString::to_string)
+        })
+        .collect();
+
+    Ok(State {
+        active: proxy.active_profile().await?,
+        profiles,
+    })
+}
+
+#[cfg(any(feature = "ipc", feature = "cairo"))]
+impl crate::ironvar::Namespace for Client {
+    fn get(&self, key: &str) -> Option<String> {
+        let state = lock!(self.state);
+
+        match key {
+            "active" => Some(state.active.clone()),
+            "profiles" => Some(state.profiles.join(",")),
+            _ => None,
+        }
+    }
+
+    fn list(&self) -> Vec<String> {
+        vec!["active".to_string(), "profiles".to_string()]
+    }
+
+    fn namespaces(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn get_namespace(&self, _key: &str) -> Option<crate::ironvar::NamespaceTrait> {
+        None
+    }
+}
+
+register_fallible_client!(Client, power_profiles);
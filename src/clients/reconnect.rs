@@ -0,0 +1,231 @@
+use crate::lock;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::sleep;
+use tracing::{debug, error, info};
+
+/// The delay before the first reconnect attempt.
+const MIN_DELAY: Duration = Duration::from_secs(1);
+/// The longest delay between reconnect attempts.
+const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// The state of a client's connection to its backing service.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionStatus {
+    Connecting,
+    Connected,
+    Disconnected,
+}
+
+impl Display for ConnectionStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Connecting => "connecting",
+                Self::Connected => "connected",
+                Self::Disconnected => "disconnected",
+            }
+        )
+    }
+}
+
+/// Connection status of each reconnecting client, keyed by client name.
+static STATUSES: Mutex<BTreeMap<&str, ConnectionStatus>> = Mutex::new(BTreeMap::new());
+
+fn set_status(name: &'static str, status: ConnectionStatus) {
+    debug!("{name} connection status: {status}");
+
+    #[cfg(feature = "ipc")]
+    {
+        use std::sync::Once;
+
+        static REGISTER: Once = Once::new();
+        REGISTER.call_once(|| {
+            crate::Ironbar::variable_manager()
+                .register_namespace("connections", Arc::new(Connections));
+        });
+    }
+
+    lock!(STATUSES).insert(name, status);
+}
+
+/// Removes the connection status of a client which has been stopped.
+pub fn remove_status(name: &str) {
+    lock!(STATUSES).remove(name);
+}
+
+/// Gets the connection status of each reconnecting client,
+/// in alphabetical order.
+pub fn statuses() -> Vec<(&'static str, ConnectionStatus)> {
+    lock!(STATUSES)
+        .iter()
+        .map(|(name, status)| (*name, *status))
+        .collect()
+}
+
+/// Exponential backoff between reconnect attempts.
+#[derive(Debug, Clone, Copy)]
+struct Backoff {
+    current: Duration,
+}
+
+impl Backoff {
+    const fn new() -> Self {
+        Self { current: MIN_DELAY }
+    }
+
+    /// Gets the delay before the next attempt,
+    /// doubling it for the attempt after.
+    fn next(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = (self.current * 2).min(MAX_DELAY);
+        delay
+    }
+}
+
+/// Handle passed to each connection attempt,
+/// used to report once the connection is established.
+#[derive(Debug, Clone)]
+pub struct Session {
+    name: &'static str,
+    connected: Arc<AtomicBool>,
+}
+
+impl Session {
+    fn new(name: &'static str) -> Self {
+        set_status(name, ConnectionStatus::Connecting);
+
+        Self {
+            name,
+            connected: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Marks the connection as established.
+    /// This resets the reconnect backoff.
+    pub fn connected(&self) {
+        info!("{} connected", self.name);
+        self.connected.store(true, Ordering::Relaxed);
+        set_status(self.name, ConnectionStatus::Connected);
+    }
+
+    /// Marks the session as ended,
+    /// getting the delay before the next attempt.
+    fn end<E: Debug>(self, result: Result<(), E>, backoff: &mut Backoff) -> Duration {
+        set_status(self.name, ConnectionStatus::Disconnected);
+
+        if self.connected.load(Ordering::Relaxed) {
+            *backoff = Backoff::new();
+        }
+
+        let delay = backoff.next();
+
+        match result {
+            Ok(()) => info!(
+                "{} connection closed, reconnecting in {}s",
+                self.name,
+                delay.as_secs()
+            ),
+            Err(err) => error!(
+                "{} connection failed, reconnecting in {}s: {err:?}",
+                self.name,
+                delay.as_secs()
+            ),
+        }
+
+        delay
+    }
+}
+
+/// Runs a connection session in a loop,
+/// reconnecting with exponential backoff each time it ends.
+///
+/// The session should connect to the service,
+/// call [`Session::connected`] once the connection is established,
+/// and run until the connection is lost.
+/// Clients should re-send their full state after reconnecting,
+/// as anything may have changed while disconnected.
+pub async fn run<F, Fut, E>(name: &'static str, mut session: F)
+where
+    F: FnMut(Session) -> Fut,
+    Fut: Future<Output = Result<(), E>>,
+    E: Debug,
+{
+    let mut backoff = Backoff::new();
+
+    loop {
+        let handle = Session::new(name);
+        let result = session(handle.clone()).await;
+        let delay = handle.end(result, &mut backoff);
+
+        sleep(delay).await;
+    }
+}
+
+/// Like [`run`], for sessions which block the thread.
+///
+/// This must be called from a blocking thread.
+pub fn run_blocking<F, E>(name: &'static str, mut session: F)
+where
+    F: FnMut(Session) -> Result<(), E>,
+    E: Debug,
+{
+    let mut backoff = Backoff::new();
+
+    loop {
+        let handle = Session::new(name);
+        let result = session(handle.clone());
+        let delay = handle.end(result, &mut backoff);
+
+        std::thread::sleep(delay);
+    }
+}
+
+/// `ironvar` namespace exposing the connection status of each client.
+#[cfg(feature = "ipc")]
+struct Connections;
+
+#[cfg(feature = "ipc")]
+impl crate::ironvar::Namespace for Connections {
+    fn get(&self, key: &str) -> Option<String> {
+        lock!(STATUSES).get(key).map(ToString::to_string)
+    }
+
+    fn list(&self) -> Vec<String> {
+        lock!(STATUSES).keys().map(ToString::to_string).collect()
+    }
+
+    fn namespaces(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn get_namespace(&self, _key: &str) -> Option<crate::ironvar::NamespaceTrait> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_max() {
+        let mut backoff = Backoff::new();
+
+        assert_eq!(backoff.next(), Duration::from_secs(1));
+        assert_eq!(backoff.next(), Duration::from_secs(2));
+        assert_eq!(backoff.next(), Duration::from_secs(4));
+
+        for _ in 0..10 {
+            backoff.next();
+        }
+
+        assert_eq!(backoff.next(), MAX_DELAY);
+    }
+}
@@ -0,0 +1,293 @@
+use crate::channels::SyncSenderExt;
+use crate::clients::reconnect::{self, Session};
+use crate::{arc_mut, lock, spawn};
+use color_eyre::Result;
+use std::collections::HashMap;
+use std::fs;
+use std::num::NonZeroU32;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::debug;
+use zbus::export::ordered_stream::OrderedStreamExt;
+use zbus::fdo::{DBusProxy, MonitoringProxy};
+use zbus::message::Type;
+use zbus::names::BusName;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue};
+use zbus::{Connection, MatchRule, Message, MessageStream};
+
+const PORTAL: &str = "org.freedesktop.portal.Desktop";
+const IMPL_SCREENCAST: &str = "org.freedesktop.impl.portal.ScreenCast";
+const IMPL_SESSION: &str = "org.freedesktop.impl.portal.Session";
+const SESSION: &str = "org.freedesktop.portal.Session";
+
+/// An active screencast session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screencast {
+    /// The object path of the session.
+    pub handle: String,
+    /// The ID of the app sharing the screen,
+    /// or its process name if it is not sandboxed.
+    pub app: String,
+    /// The bus name of the portal backend implementing the session.
+    backend: String,
+}
+
+/// Tracks screencast sessions from the messages
+/// exchanged between the portal and its backend.
+#[derive(Debug, Default)]
+struct Tracker {
+    /// Sessions which have been created but not started.
+    created: HashMap<String, Screencast>,
+    /// Sessions waiting for the backend to reply to their start request,
+    /// keyed by the serial number of the request.
+    starting: HashMap<NonZeroU32, String>,
+    active: Vec<Screencast>,
+}
+
+impl Tracker {
+    /// Updates the sessions from the message,
+    /// returning the session if it has just started.
+    fn handle(&mut self, message: &Message) -> Option<Screencast> {
+        let header = message.header();
+        let interface = header.interface().map(|name| name.as_str());
+        let member = header.member().map(|name| name.as_str());
+
+        match (message.message_type(), interface, member) {
+            (Type::MethodCall, Some(IMPL_SCREENCAST), Some("CreateSession")) => {
+                let (_, handle, app_id, _): (
+                    OwnedObjectPath,
+                    OwnedObjectPath,
+                    String,
+                    HashMap<String, OwnedValue>,
+                ) = message.body().deserialize().ok()?;
+
+                let backend = header.destination()?.to_string();
+                let handle = handle.to_string();
+
+                self.created.insert(
+                    handle.clone(),
+                    Screencast {
+                        handle,
+                        app: app_id,
+                        backend,
+                    },
+                );
+            }
+            (Type::MethodCall, Some(IMPL_SCREENCAST), Some("Start")) => {
+                let (_, handle, _, _, _): (
+                    OwnedObjectPath,
+                    OwnedObjectPath,
+                    String,
+                    String,
+                    HashMap<String, OwnedValue>,
+                ) = message.body().deserialize().ok()?;
+
+                self.starting
+                    .insert(header.primary().serial_num(), handle.to_string());
+            }
+            (Type::MethodReturn, _, _) => {
+                let handle = self.starting.remove(&header.reply_serial()?)?;
+                let (response, _): (u32, HashMap<String, OwnedValue>) =
+                    message.body().deserialize().ok()?;
+
+                // any other response means the user cancelled or the request failed
+                let screencast = self.created.remove(&handle)?;
+                if response == 0 {
+                    return Some(screencast);
+                }
+            }
+            (Type::MethodCall, Some(IMPL_SESSION), Some("Close"))
+            | (Type::Signal, Some(IMPL_SESSION | SESSION), Some("Closed")) => {
+                let handle = header.path()?.as_str();
+                self.created.remove(handle);
+                self.active.retain(|screencast| screencast.handle != handle);
+            }
+            _ => {}
+        }
+
+        None
+    }
+}
+
+#[derive(Debug)]
+pub struct Client {
+    connection: Connection,
+    active: Arc<Mutex<Vec<Screencast>>>,
+    tx: broadcast::Sender<Vec<Screencast>>,
+    _rx: broadcast::Receiver<Vec<Screencast>>,
+}
+
+impl Client {
+    pub async fn new() -> Result<Self> {
+        let connection = crate::clients::dbus::session().await?;
+        let active = arc_mut!(vec![]);
+        let (tx, rx) = broadcast::channel(8);
+
+        {
+            let connection = connection.clone();
+            let active = active.clone();
+            let tx = tx.clone();
+
+            spawn(reconnect::run("screencast", move |session| {
+                let connection = connection.clone();
+                let active = active.clone();
+                let tx = tx.clone();
+
+                async move {
+                    let res = monitor(&connection, &active, &tx, &session).await;
+
+                    // sessions cannot be tracked while disconnected
+                    lock!(active).clear();
+                    tx.send_expect(vec![]);
+
+                    res
+                }
+            }));
+        }
+
+        Ok(Self {
+            connection,
+            active,
+            tx,
+            _rx: rx,
+        })
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Vec<Screencast>> {
+        self.tx.subscribe()
+    }
+
+    /// Gets every active screencast session.
+    pub fn screencasts(&self) -> Vec<Screencast> {
+        lock!(self.active).clone()
+    }
+
+    /// Stops the screencast session with the handle.
+    ///
+    /// The portal only allows the app which started a session to close it,
+    /// so the session is closed through the portal backend instead.
+    pub async fn stop(&self, handle: &str) -> Result<()> {
+        let backend = lock!(self.active)
+            .iter()
+            .find(|screencast| screencast.handle == handle)
+            .map(|screencast| screencast.backend.clone());
+
+        if let Some(backend) = backend {
+            self.connection
+                .call_method(Some(backend), handle, Some(IMPL_SESSION), "Close", &())
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Monitors messages to and from the portal,
+/// until the portal exits.
+async fn monitor(
+    connection: &Connection,
+    active: &Mutex<Vec<Screencast>>,
+    tx: &broadcast::Sender<Vec<Screencast>>,
+    session: &Session,
+) -> Result<()> {
+    let dbus = DBusProxy::new(connection).await?;
+    let portal = dbus.get_name_owner(BusName::try_from(PORTAL)?).await?;
+
+    let rules = [
+        MatchRule::builder()
+            .msg_type(Type::MethodCall)
+            .interface(IMPL_SCREENCAST)?
+            .build(),
+        MatchRule::builder()
+            .msg_type(Type::MethodCall)
+            .interface(IMPL_SESSION)?
+            .member("Close")?
+            .build(),
+        MatchRule::builder()
+            .msg_type(Type::Signal)
+            .interface(IMPL_SESSION)?
+            .member("Closed")?
+            .build(),
+        MatchRule::builder()
+            .msg_type(Type::Signal)
+            .interface(SESSION)?
+            .member("Closed")?
+            .build(),
+        // replies from the backend to the portal
+        MatchRule::builder()
+            .msg_type(Type::MethodReturn)
+            .destination(portal.as_str())?
+            .build(),
+        MatchRule::builder()
+            .msg_type(Type::Signal)
+            .interface("org.freedesktop.DBus")?
+            .member("NameOwnerChanged")?
+            .arg(0, PORTAL)?
+            .build(),
+    ];
+
+    // a monitor connection cannot send messages,
+    // so a separate connection is used.
+    let monitor = Box::pin(Connection::session()).await?;
+    MonitoringProxy::new(&monitor)
+        .await?
+        .become_monitor(&rules, 0)
+        .await?;
+
+    let mut messages = MessageStream::from(monitor);
+    let mut tracker = Tracker::default();
+    session.connected();
+
+    while let Some(message) = messages.next().await {
+        let message = message?;
+
+        if message
+            .header()
+            .member()
+            .is_some_and(|m| m == "NameOwnerChanged")
+        {
+            debug!("Portal owner changed");
+            break;
+        }
+
+        let started = tracker.handle(&message);
+
+        if let Some(mut screencast) = started {
+            if screencast.app.is_empty() {
+                screencast.app = process_name(&dbus, &screencast.handle)
+                    .await
+                    .unwrap_or_else(|| "Unknown".to_string());
+            }
+
+            debug!("Screencast started by {}", screencast.app);
+            tracker.active.push(screencast);
+        }
+
+        let mut active = lock!(active);
+        if *active != tracker.active {
+            active.clone_from(&tracker.active);
+            tx.send_expect(active.clone());
+        }
+    }
+
+    Ok(())
+}
+
+/// Gets the name of the process which owns the session.
+///
+/// Session handles are of the form
+/// `/org/freedesktop/portal/desktop/session/<sender>/<token>`,
+/// where the sender is the app's unique bus name with `.` replaced by `_`.
+async fn process_name(dbus: &DBusProxy<'_>, handle: &str) -> Option<String> {
+    let sender = handle.split('/').nth_back(1)?;
+    let sender = format!(":{}", sender.replace('_', "."));
+
+    let pid = dbus
+        .get_connection_unix_process_id(BusName::try_from(sender).ok()?)
+        .await
+        .ok()?;
+
+    fs::read_to_string(format!("/proc/{pid}/comm"))
+        .ok()
+        .map(|name| name.trim().to_string())
+}
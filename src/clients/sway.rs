@@ -1,3 +1,4 @@
+use crate::clients::reconnect;
 use crate::spawn;
 use futures_lite::StreamExt;
 use std::sync::Arc;
@@ -9,7 +10,7 @@ type Result<T> = std::result::Result<T, swayipc_async::Error>;
 type SyncFn<T> = dyn Fn(&T) + Sync + Send;
 
 struct TaskState {
-    join_handle: Option<tokio::task::JoinHandle<Result<()>>>,
+    join_handle: Option<tokio::task::JoinHandle<()>>,
     // could have been a `HashMap<EventType, Vec<Box<dyn Fn(&Event) + Sync + Send>>>`, but we don't
     // expect enough listeners to justify the constant overhead of a hashmap.
     listeners: Arc<Vec<(EventType, Box<SyncFn<Event>>)>>,
@@ -85,28 +86,33 @@ impl Client {
 
         listeners_mut.push((event_type, f));
 
-        // create new client as subscription takes ownership
-        let client = Connection::new().await?;
-
         let event_types = listeners.iter().map(|(t, _)| *t).collect::<Vec<_>>();
         let listeners = listeners.clone();
 
-        let handle = spawn(async move {
-            let mut events = client.subscribe(&event_types).await?;
-
-            while let Some(event) = events.next().await {
-                trace!("event: {:?}", event);
-                let event = event?;
-                let ty = sway_event_to_event_type(&event);
-                for (t, f) in listeners.iter() {
-                    if *t == ty {
-                        f(&event);
+        let handle = spawn(reconnect::run("sway", move |session| {
+            let event_types = event_types.clone();
+            let listeners = listeners.clone();
+
+            async move {
+                // create new client as subscription takes ownership
+                let client = Connection::new().await?;
+                let mut events = client.subscribe(&event_types).await?;
+                session.connected();
+
+                while let Some(event) = events.next().await {
+                    trace!("event: {:?}", event);
+                    let event = event?;
+                    let ty = sway_event_to_event_type(&event);
+                    for (t, f) in listeners.iter() {
+                        if *t == ty {
+                            f(&event);
+                        }
                     }
                 }
-            }
 
-            Ok::<(), _>(())
-        });
+                Ok::<(), swayipc_async::Error>(())
+            }
+        }));
 
         *join_handle = Some(handle);
 
@@ -1,9 +1,12 @@
 mod dbus;
 
 use crate::channels::SyncSenderExt;
-use crate::{register_fallible_client, spawn};
+use crate::clients::notifications::{NotificationClient, State};
+use crate::clients::reconnect;
+use crate::{lock, spawn};
 use dbus::SwayNcProxy;
 use serde::Deserialize;
+use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast;
 use tracing::{debug, error};
 use zbus::Result;
@@ -34,59 +37,119 @@ impl From<GetSubscribeData> for Event {
     }
 }
 
+impl From<Event> for State {
+    fn from(event: Event) -> Self {
+        Self {
+            count: event.count,
+            dnd: event.dnd,
+            panel_open: event.cc_open,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Client {
     proxy: SwayNcProxy<'static>,
-    tx: broadcast::Sender<Event>,
-    _rx: broadcast::Receiver<Event>,
+    state: Arc<Mutex<Option<State>>>,
+    tx: broadcast::Sender<State>,
+    _rx: broadcast::Receiver<State>,
 }
 
 impl Client {
     pub async fn new() -> Result<Self> {
-        let dbus = Box::pin(zbus::Connection::session()).await?;
+        let dbus = crate::clients::dbus::session().await?;
 
         let proxy = SwayNcProxy::new(&dbus).await?;
         let (tx, rx) = broadcast::channel(8);
-
-        let mut stream = proxy.receive_subscribe_v2().await?;
+        let state = Arc::new(Mutex::new(None));
 
         {
+            let proxy = proxy.clone();
+            let state = state.clone();
             let tx = tx.clone();
 
-            spawn(async move {
-                while let Some(ev) = stream.next().await {
-                    let ev = ev
-                        .message()
-                        .body()
-                        .deserialize::<Event>()
-                        .expect("to deserialize");
-                    debug!("Received event: {ev:?}");
-                    tx.send_expect(ev);
+            spawn(reconnect::run("swaync", move |session| {
+                let proxy = proxy.clone();
+                let state = state.clone();
+                let tx = tx.clone();
+
+                let send = move |new_state: State| {
+                    lock!(state).replace(new_state);
+                    tx.send_expect(new_state);
+                };
+
+                async move {
+                    let mut stream = proxy.receive_subscribe_v2().await?;
+                    let mut owner_stream = proxy.inner().receive_owner_changed().await?;
+
+                    // fails if swaync is not running,
+                    // and re-sends the full state after a restart.
+                    let data = proxy.get_subscribe_data().await?;
+                    session.connected();
+                    send(Event::from(data).into());
+
+                    loop {
+                        tokio::select! {
+                            Some(ev) = stream.next() => {
+                                let ev = ev
+                                    .message()
+                                    .body()
+                                    .deserialize::<Event>()
+                                    .expect("to deserialize");
+                                debug!("Received event: {ev:?}");
+                                send(ev.into());
+                            }
+                            Some(owner) = owner_stream.next() => {
+                                if owner.is_none() {
+                                    break;
+                                }
+                            }
+                            else => break,
+                        }
+                    }
+
+                    Ok::<(), zbus::Error>(())
                 }
-            });
+            }));
         }
 
-        Ok(Self { proxy, tx, _rx: rx })
+        Ok(Self {
+            proxy,
+            state,
+            tx,
+            _rx: rx,
+        })
     }
+}
 
-    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+impl NotificationClient for Client {
+    fn subscribe(&self) -> broadcast::Receiver<State> {
         self.tx.subscribe()
     }
 
-    pub async fn state(&self) -> Result<Event> {
-        debug!("Getting subscribe data (current state)");
-        match self.proxy.get_subscribe_data().await {
-            Ok(data) => Ok(data.into()),
-            Err(err) => Err(err),
-        }
+    fn state(&self) -> Option<State> {
+        *lock!(self.state)
     }
 
-    pub async fn toggle_visibility(&self) {
+    fn toggle_visibility(&self) {
         debug!("Toggling visibility");
-        if let Err(err) = self.proxy.toggle_visibility().await {
-            error!("{err:?}");
-        }
+
+        let proxy = self.proxy.clone();
+        spawn(async move {
+            if let Err(err) = proxy.toggle_visibility().await {
+                error!("{err:?}");
+            }
+        });
     }
-}
 
-register_fallible_client!(Client, notifications);
+    fn set_dnd(&self, dnd: bool) {
+        debug!("Setting do not disturb: {dnd}");
+
+        let proxy = self.proxy.clone();
+        spawn(async move {
+            if let Err(err) = proxy.set_dnd(dnd).await {
+                error!("{err:?}");
+            }
+        });
+    }
+}
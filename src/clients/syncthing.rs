@@ -0,0 +1,302 @@
+use crate::channels::SyncSenderExt;
+use crate::clients::reconnect::{self, Session};
+use crate::{arc_rw, read_lock, spawn, write_lock};
+use color_eyre::Result;
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use tokio::sync::broadcast;
+use tracing::debug;
+
+/// Events which can change the state of a folder.
+const EVENTS: &str = "StateChanged,FolderSummary,FolderCompletion,FolderErrors,\
+                      FolderPaused,FolderResumed,ConfigSaved";
+
+/// The number of seconds to wait for new events before polling again.
+const EVENT_TIMEOUT: u64 = 60;
+
+/// A synced folder and its current state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Folder {
+    pub id: String,
+    pub label: String,
+    pub paused: bool,
+    /// The folder state, such as `idle`, `scanning`, `syncing` or `error`.
+    pub state: String,
+    /// The total size of the folder across the cluster.
+    pub global_bytes: u64,
+    /// The size of the folder which is up to date locally.
+    pub in_sync_bytes: u64,
+    /// The number of files which failed to sync.
+    pub errors: u64,
+}
+
+impl Folder {
+    /// Gets the folder label, falling back to its ID.
+    pub fn name(&self) -> &str {
+        if self.label.is_empty() {
+            &self.id
+        } else {
+            &self.label
+        }
+    }
+
+    pub fn is_syncing(&self) -> bool {
+        matches!(
+            self.state.as_str(),
+            "syncing" | "sync-preparing" | "sync-waiting"
+        )
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.state == "error" || self.errors > 0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Update {
+    /// The state of every folder, sent whenever any of them changes.
+    Folders(Vec<Folder>),
+    /// The connection to Syncthing was lost.
+    Disconnected,
+}
+
+#[derive(Debug, Deserialize)]
+struct FolderConfig {
+    id: String,
+    #[serde(default)]
+    label: String,
+    #[serde(default)]
+    paused: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FolderStatus {
+    #[serde(default)]
+    state: String,
+    #[serde(default)]
+    global_bytes: u64,
+    #[serde(default)]
+    in_sync_bytes: u64,
+    #[serde(default)]
+    pull_errors: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct Event {
+    id: u64,
+}
+
+/// Requests to the Syncthing REST API.
+#[derive(Debug, Clone)]
+struct Api {
+    http: reqwest::Client,
+    url: String,
+}
+
+impl Api {
+    fn new(url: &str, api_key: &str) -> Result<Self> {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-API-Key", HeaderValue::from_str(api_key)?);
+
+        Ok(Self {
+            http: reqwest::Client::builder()
+                .default_headers(headers)
+                .build()?,
+            url: url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        let body = self
+            .http
+            .get(format!("{}{path}", self.url))
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    /// Gets the configuration and status of every folder.
+    async fn folders(&self) -> Result<Vec<Folder>> {
+        let configs: Vec<FolderConfig> = self.get("/rest/config/folders").await?;
+        let mut folders = Vec::with_capacity(configs.len());
+
+        for config in configs {
+            let status: FolderStatus = self
+                .get(&format!("/rest/db/status?folder={}", config.id))
+                .await?;
+
+            folders.push(Folder {
+                id: config.id,
+                label: config.label,
+                paused: config.paused,
+                state: status.state,
+                global_bytes: status.global_bytes,
+                in_sync_bytes: status.in_sync_bytes,
+                errors: status.pull_errors,
+            });
+        }
+
+        folders.sort_by(|a, b| a.name().cmp(b.name()));
+        Ok(folders)
+    }
+
+    /// Waits for events after the one with ID `since`,
+    /// returning the ID of the latest event.
+    async fn wait_for_events(&self, since: u64) -> Result<u64> {
+        let events: Vec<Event> = self
+            .get(&format!(
+                "/rest/events?since={since}&timeout={EVENT_TIMEOUT}&events={EVENTS}"
+            ))
+            .await?;
+
+        Ok(events.last().map_or(since, |event| event.id))
+    }
+
+    /// Gets the ID of the latest event.
+    async fn latest_event(&self) -> Result<u64> {
+        let events: Vec<Event> = self.get("/rest/events?limit=1&timeout=0").await?;
+        Ok(events.last().map_or(0, |event| event.id))
+    }
+
+    async fn set_paused(&self, id: &str, paused: bool) -> Result<()> {
+        self.http
+            .patch(format!("{}/rest/config/folders/{id}", self.url))
+            .header("Content-Type", "application/json")
+            .body(json!({ "paused": paused }).to_string())
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct Client {
+    api: Api,
+    folders: Arc<RwLock<Option<Vec<Folder>>>>,
+    tx: broadcast::Sender<Update>,
+    _rx: broadcast::Receiver<Update>,
+}
+
+impl Client {
+    pub fn new(url: &str, api_key: &str) -> Result<Self> {
+        let api = Api::new(url, api_key)?;
+        let folders = arc_rw!(None);
+        let (tx, rx) = broadcast::channel(16);
+
+        {
+            let api = api.clone();
+            let folders = folders.clone();
+            let tx = tx.clone();
+
+            spawn(reconnect::run("syncthing", move |session| {
+                let api = api.clone();
+                let folders = folders.clone();
+                let tx = tx.clone();
+
+                async move {
+                    let res = run_session(&api, &folders, &tx, &session).await;
+
+                    write_lock!(folders).take();
+                    tx.send_expect(Update::Disconnected);
+
+                    res
+                }
+            }));
+        }
+
+        Ok(Self {
+            api,
+            folders,
+            tx,
+            _rx: rx,
+        })
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Update> {
+        self.tx.subscribe()
+    }
+
+    /// Gets the latest state of every folder,
+    /// or `None` while disconnected.
+    pub fn folders(&self) -> Option<Vec<Folder>> {
+        read_lock!(self.folders).clone()
+    }
+
+    /// Pauses or resumes every folder.
+    pub async fn set_paused(&self, paused: bool) -> Result<()> {
+        let ids = read_lock!(self.folders)
+            .iter()
+            .flatten()
+            .map(|folder| folder.id.clone())
+            .collect::<Vec<_>>();
+
+        for id in ids {
+            self.api.set_paused(&id, paused).await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Fetches the folders each time an event is received,
+/// until the connection is lost.
+async fn run_session(
+    api: &Api,
+    folders: &RwLock<Option<Vec<Folder>>>,
+    tx: &broadcast::Sender<Update>,
+    session: &Session,
+) -> Result<()> {
+    let mut since = api.latest_event().await?;
+    session.connected();
+
+    loop {
+        let new_folders = api.folders().await?;
+
+        let changed = read_lock!(folders).as_ref() != Some(&new_folders);
+        if changed {
+            write_lock!(folders).replace(new_folders.clone());
+            tx.send_expect(Update::Folders(new_folders));
+        }
+
+        // long-polls until an event occurs or the timeout is reached
+        let latest = api.wait_for_events(since).await?;
+        if latest != since {
+            debug!("Received Syncthing events up to {latest}");
+        }
+
+        since = latest;
+    }
+}
+
+/// Reads the API key from Syncthing's config file.
+pub fn find_api_key() -> Option<String> {
+    let state_dir = env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".local/state")));
+
+    let paths = [
+        state_dir.map(|dir| dir.join("syncthing/config.xml")),
+        dirs::config_dir().map(|dir| dir.join("syncthing/config.xml")),
+    ];
+
+    paths.into_iter().flatten().find_map(|path| {
+        let config = fs::read_to_string(path).ok()?;
+        let start = config.find("<apikey>")? + "<apikey>".len();
+        let end = config[start..].find("</apikey>")? + start;
+
+        Some(config[start..end].trim().to_string())
+    })
+}
@@ -0,0 +1,152 @@
+#[cfg(feature = "sys_info+nvidia")]
+use std::cell::OnceCell;
+use std::fmt::{Debug, Formatter};
+use std::fs;
+use std::path::Path;
+#[cfg(feature = "sys_info+nvidia")]
+use tracing::debug;
+
+const DRM_PATH: &str = "/sys/class/drm";
+
+/// A single sample of a GPU's statistics.
+/// Values are `None` where the driver does not expose them.
+#[derive(Debug, Clone, Default)]
+pub struct GpuStats {
+    /// The DRM card name, such as `card0`,
+    /// or `nvidia0` for GPUs read through NVML.
+    pub name: Box<str>,
+    pub percent: Option<f64>,
+    /// Bytes of dedicated memory in use.
+    pub memory_used: Option<f64>,
+    /// Bytes of dedicated memory.
+    pub memory_total: Option<f64>,
+    pub temp_c: Option<f64>,
+}
+
+/// Reads GPU statistics from DRM and hwmon in sysfs,
+/// and from NVML for NVIDIA GPUs.
+#[derive(Default)]
+pub struct Gpus {
+    /// Only loaded once GPU data is first needed.
+    #[cfg(feature = "sys_info+nvidia")]
+    nvml: OnceCell<Option<nvml_wrapper::Nvml>>,
+    stats: Option<Vec<GpuStats>>,
+}
+
+impl Debug for Gpus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Gpus")
+            .field("stats", &self.stats)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Gpus {
+    pub fn refresh(&mut self) {
+        #[cfg_attr(not(feature = "sys_info+nvidia"), allow(unused_mut))]
+        let mut stats = drm_stats();
+
+        #[cfg(feature = "sys_info+nvidia")]
+        if let Some(nvml) = self.nvml.get_or_init(|| {
+            nvml_wrapper::Nvml::init()
+                .inspect_err(|err| debug!("NVML not available: {err}"))
+                .ok()
+        }) {
+            // NVIDIA cards also appear in DRM, but without any stats
+            stats.retain(|gpu| gpu.percent.is_some() || gpu.temp_c.is_some());
+            stats.extend(nvml_stats(nvml));
+        }
+
+        self.stats = Some(stats);
+    }
+
+    /// Gets the latest sample,
+    /// taking the first one if it has not been yet.
+    pub fn stats(&mut self) -> &[GpuStats] {
+        if self.stats.is_none() {
+            self.refresh();
+        }
+
+        self.stats.as_deref().unwrap_or_default()
+    }
+}
+
+/// Reads each DRM card's statistics.
+///
+/// Utilisation and memory are only exposed by `amdgpu`.
+/// Temperature is read from the card's hwmon device,
+/// which is exposed by most drivers for discrete GPUs.
+fn drm_stats() -> Vec<GpuStats> {
+    let Ok(entries) = fs::read_dir(DRM_PATH) else {
+        return vec![];
+    };
+
+    let mut stats = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+
+            // connectors such as `card0-DP-1` also appear here
+            let is_card = name
+                .strip_prefix("card")
+                .is_some_and(|index| index.chars().all(|c| c.is_ascii_digit()));
+
+            is_card.then(|| {
+                let device = entry.path().join("device");
+
+                GpuStats {
+                    name: name.into(),
+                    percent: read_number(&device.join("gpu_busy_percent")),
+                    memory_used: read_number(&device.join("mem_info_vram_used")),
+                    memory_total: read_number(&device.join("mem_info_vram_total")),
+                    temp_c: hwmon_temp(&device),
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    stats.sort_by(|a, b| a.name.cmp(&b.name));
+    stats
+}
+
+/// Reads the first temperature sensor of the device's hwmon,
+/// converting from millidegrees.
+fn hwmon_temp(device: &Path) -> Option<f64> {
+    fs::read_dir(device.join("hwmon"))
+        .ok()?
+        .flatten()
+        .find_map(|entry| read_number(&entry.path().join("temp1_input")))
+        .map(|temp| temp / 1000.0)
+}
+
+fn read_number(path: &Path) -> Option<f64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(feature = "sys_info+nvidia")]
+fn nvml_stats(nvml: &nvml_wrapper::Nvml) -> Vec<GpuStats> {
+    use nvml_wrapper::enum_wrappers::device::TemperatureSensor;
+
+    let count = nvml.device_count().unwrap_or_default();
+
+    (0..count)
+        .filter_map(|index| {
+            let device = nvml.device_by_index(index).ok()?;
+            let memory = device.memory_info().ok();
+
+            Some(GpuStats {
+                name: format!("nvidia{index}").into(),
+                percent: device
+                    .utilization_rates()
+                    .ok()
+                    .map(|rates| f64::from(rates.gpu)),
+                memory_used: memory.as_ref().map(|memory| memory.used as f64),
+                memory_total: memory.as_ref().map(|memory| memory.total as f64),
+                temp_c: device
+                    .temperature(TemperatureSensor::Gpu)
+                    .ok()
+                    .map(f64::from),
+            })
+        })
+        .collect()
+}
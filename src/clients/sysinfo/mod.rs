@@ -1,12 +1,17 @@
-use crate::modules::sysinfo::Interval;
-use crate::{lock, register_client};
+mod gpu;
+
+use self::gpu::{GpuStats, Gpus};
+use crate::{lock, poll, register_client, spawn};
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use sysinfo::{Components, Disks, LoadAvg, Networks, RefreshKind, System};
 use thiserror::Error;
+use tokio::sync::broadcast;
+use tracing::debug;
 
 #[repr(u64)]
 #[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
@@ -81,6 +86,13 @@ impl ValueSet {
             .filter(|v| !v.is_nan())
     }
 
+    /// Keeps only the values whose names match the predicate.
+    #[must_use]
+    pub fn filter(mut self, predicate: impl Fn(&str) -> bool) -> Self {
+        self.values.retain(|name, _| predicate(name));
+        self
+    }
+
     pub fn apply(&self, function: &Function, prefix: Prefix) -> f64 {
         match function {
             Function::None => 0.0,
@@ -113,7 +125,12 @@ impl ValueSet {
     }
 
     fn mean(&self, prefix: Prefix) -> f64 {
-        self.sum(prefix) / self.values.len() as f64
+        let count = self.values(prefix).count();
+        if count == 0 {
+            return 0.0;
+        }
+
+        self.sum(prefix) / count as f64
     }
 }
 
@@ -142,6 +159,42 @@ impl Value {
     }
 }
 
+/// A set of data which is sampled together.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum RefreshType {
+    Memory,
+    Cpu,
+    Temps,
+    Disks,
+    Network,
+    System,
+    Gpu,
+}
+
+impl RefreshType {
+    pub const ALL: [Self; 7] = [
+        Self::Memory,
+        Self::Cpu,
+        Self::Temps,
+        Self::Disks,
+        Self::Network,
+        Self::System,
+        Self::Gpu,
+    ];
+}
+
+/// The state of the shared sampler for a single refresh type.
+#[derive(Debug, Default)]
+struct Sampler {
+    /// The interval requested by each subscriber.
+    intervals: Vec<Duration>,
+    /// When the data was last refreshed.
+    refreshed_at: Option<Instant>,
+    /// The time between the last two refreshes,
+    /// used to calculate rates.
+    elapsed: Option<Duration>,
+}
+
 #[derive(Debug)]
 pub struct Client {
     system: Mutex<System>,
@@ -149,6 +202,10 @@ pub struct Client {
     components: Mutex<Components>,
     networks: Mutex<Networks>,
     load_average: Mutex<LoadAvg>,
+    gpus: Mutex<Gpus>,
+
+    samplers: Mutex<HashMap<RefreshType, Sampler>>,
+    tx: broadcast::Sender<RefreshType>,
 }
 
 impl Client {
@@ -161,39 +218,143 @@ impl Client {
         let networks = Networks::new_with_refreshed_list();
         let load_average = System::load_average();
 
+        let now = Instant::now();
+        let samplers = RefreshType::ALL
+            .into_iter()
+            .map(|refresh_type| {
+                let sampler = Sampler {
+                    refreshed_at: Some(now),
+                    ..Sampler::default()
+                };
+                (refresh_type, sampler)
+            })
+            .collect();
+
+        let (tx, _) = broadcast::channel(32);
+
         Self {
             system: Mutex::new(system),
             disks: Mutex::new(disks),
             components: Mutex::new(components),
             networks: Mutex::new(networks),
             load_average: Mutex::new(load_average),
+            gpus: Mutex::new(Gpus::default()),
+            samplers: Mutex::new(samplers),
+            tx,
+        }
+    }
+
+    /// Subscribes to refreshes of each of the given data types,
+    /// requesting they are sampled at least as often as the given interval.
+    ///
+    /// Data is sampled once for all subscribers,
+    /// at the shortest interval requested for each type.
+    /// The receiver gets the type each time it is refreshed.
+    pub fn subscribe(
+        self: &Arc<Self>,
+        intervals: &[(RefreshType, Duration)],
+    ) -> broadcast::Receiver<RefreshType> {
+        let rx = self.tx.subscribe();
+
+        let mut samplers = lock!(self.samplers);
+        for &(refresh_type, interval) in intervals {
+            let sampler = samplers.entry(refresh_type).or_default();
+            let start = sampler.intervals.is_empty();
+            sampler.intervals.push(interval);
+
+            if start {
+                self.spawn_sampler(refresh_type);
+            }
+        }
+
+        rx
+    }
+
+    /// Starts the task refreshing the data for `refresh_type`
+    /// and notifying subscribers.
+    fn spawn_sampler(self: &Arc<Self>, refresh_type: RefreshType) {
+        debug!("starting {refresh_type:?} sampler");
+
+        let client = self.clone();
+        spawn(async move {
+            loop {
+                poll::sleep(client.interval(refresh_type)).await;
+
+                client.refresh(refresh_type);
+
+                // no receivers is fine - subscribers may not be listening yet
+                let _ = client.tx.send(refresh_type);
+            }
+        });
+    }
+
+    /// Gets the shortest interval requested for `refresh_type`.
+    fn interval(&self, refresh_type: RefreshType) -> Duration {
+        lock!(self.samplers)
+            .get(&refresh_type)
+            .and_then(|sampler| sampler.intervals.iter().min().copied())
+            .unwrap_or(Duration::from_secs(5))
+            .max(Duration::from_millis(100))
+    }
+
+    fn refresh(&self, refresh_type: RefreshType) {
+        match refresh_type {
+            RefreshType::Memory => self.refresh_memory(),
+            RefreshType::Cpu => self.refresh_cpu(),
+            RefreshType::Temps => self.refresh_temps(),
+            RefreshType::Disks => self.refresh_disks(),
+            RefreshType::Network => self.refresh_network(),
+            RefreshType::System => self.refresh_load_average(),
+            RefreshType::Gpu => self.refresh_gpus(),
         }
+
+        let now = Instant::now();
+        let mut samplers = lock!(self.samplers);
+        let sampler = samplers.entry(refresh_type).or_default();
+
+        sampler.elapsed = sampler.refreshed_at.map(|at| now - at);
+        sampler.refreshed_at = Some(now);
+    }
+
+    /// Gets the number of seconds between the last two refreshes of `refresh_type`,
+    /// for converting totals since the last refresh into per-second rates.
+    fn elapsed_secs(&self, refresh_type: RefreshType) -> f64 {
+        lock!(self.samplers)
+            .get(&refresh_type)
+            .and_then(|sampler| sampler.elapsed)
+            .map(|elapsed| elapsed.as_secs_f64())
+            .filter(|&secs| secs > 0.0)
+            .unwrap_or(1.0)
     }
 
-    pub fn refresh_cpu(&self) {
+    fn refresh_cpu(&self) {
         lock!(self.system).refresh_cpu_all();
     }
 
-    pub fn refresh_memory(&self) {
+    fn refresh_memory(&self) {
         lock!(self.system).refresh_memory();
     }
 
-    pub fn refresh_network(&self) {
+    fn refresh_network(&self) {
         lock!(self.networks).refresh(true);
     }
 
-    pub fn refresh_temps(&self) {
+    fn refresh_temps(&self) {
         lock!(self.components).refresh(true);
     }
 
-    pub fn refresh_disks(&self) {
+    fn refresh_disks(&self) {
         lock!(self.disks).refresh(true);
     }
 
-    pub fn refresh_load_average(&self) {
+    fn refresh_load_average(&self) {
         *lock!(self.load_average) = System::load_average();
     }
 
+    fn refresh_gpus(&self) {
+        lock!(self.gpus).refresh();
+    }
+
     pub fn cpu_frequency(&self) -> ValueSet {
         lock!(self.system)
             .cpus()
@@ -332,54 +493,91 @@ impl Client {
             .collect()
     }
 
-    pub fn disk_read(&self, interval: Interval) -> ValueSet {
+    pub fn disk_read(&self) -> ValueSet {
+        let elapsed = self.elapsed_secs(RefreshType::Disks);
         lock!(self.disks)
             .iter()
             .map(|disk| {
                 (
                     disk.mount_point().to_string_lossy().into(),
-                    Value::new(disk.usage().read_bytes as f64 / interval.disks() as f64),
+                    Value::new(disk.usage().read_bytes as f64 / elapsed),
                 )
             })
             .collect()
     }
 
-    pub fn disk_write(&self, interval: Interval) -> ValueSet {
+    pub fn disk_write(&self) -> ValueSet {
+        let elapsed = self.elapsed_secs(RefreshType::Disks);
         lock!(self.disks)
             .iter()
             .map(|disk| {
                 (
                     disk.mount_point().to_string_lossy().into(),
-                    Value::new(disk.usage().written_bytes as f64 / interval.disks() as f64),
+                    Value::new(disk.usage().written_bytes as f64 / elapsed),
                 )
             })
             .collect()
     }
 
-    pub fn net_down(&self, interval: Interval) -> ValueSet {
+    pub fn net_down(&self) -> ValueSet {
+        let elapsed = self.elapsed_secs(RefreshType::Network);
         lock!(self.networks)
             .iter()
             .map(|(name, net)| {
                 (
                     name.as_str().into(),
-                    Value::new(net.received() as f64 / interval.networks() as f64),
+                    Value::new(net.received() as f64 / elapsed),
                 )
             })
             .collect()
     }
 
-    pub fn net_up(&self, interval: Interval) -> ValueSet {
+    pub fn net_up(&self) -> ValueSet {
+        let elapsed = self.elapsed_secs(RefreshType::Network);
         lock!(self.networks)
             .iter()
             .map(|(name, net)| {
                 (
                     name.as_str().into(),
-                    Value::new(net.transmitted() as f64 / interval.networks() as f64),
+                    Value::new(net.transmitted() as f64 / elapsed),
                 )
             })
             .collect()
     }
 
+    /// Gets a value for each GPU which exposes it.
+    fn gpu_values(&self, f: impl Fn(&GpuStats) -> Option<f64>) -> ValueSet {
+        lock!(self.gpus)
+            .stats()
+            .iter()
+            .filter_map(|gpu| Some((gpu.name.clone(), Value::new(f(gpu)?))))
+            .collect()
+    }
+
+    pub fn gpu_percent(&self) -> ValueSet {
+        self.gpu_values(|gpu| gpu.percent)
+    }
+
+    pub fn gpu_memory_used(&self) -> ValueSet {
+        self.gpu_values(|gpu| gpu.memory_used)
+    }
+
+    pub fn gpu_memory_total(&self) -> ValueSet {
+        self.gpu_values(|gpu| gpu.memory_total)
+    }
+
+    pub fn gpu_memory_percent(&self) -> ValueSet {
+        self.gpu_values(|gpu| Some(gpu.memory_used? / gpu.memory_total? * 100.0))
+    }
+
+    pub fn gpu_temp_c(&self) -> ValueSet {
+        self.gpu_values(|gpu| gpu.temp_c)
+    }
+
+    pub fn gpu_temp_f(&self) -> ValueSet {
+        self.gpu_values(|gpu| gpu.temp_c.map(c_to_f))
+    }
+
     pub fn load_average_1(&self) -> Value {
         Value::new(lock!(self.load_average).one)
     }
@@ -431,9 +629,20 @@ pub enum TokenType {
     DiskPercent,
     DiskRead,
     DiskWrite,
+    DiskReadAuto,
+    DiskWriteAuto,
 
     NetDown,
     NetUp,
+    NetDownAuto,
+    NetUpAuto,
+
+    GpuPercent,
+    GpuMemoryUsed,
+    GpuMemoryTotal,
+    GpuMemoryPercent,
+    GpuTempC,
+    GpuTempF,
 
     LoadAverage1,
     LoadAverage5,
@@ -475,9 +684,20 @@ impl FromStr for TokenType {
             "disk_percent" => Ok(Self::DiskPercent),
             "disk_read" => Ok(Self::DiskRead),
             "disk_write" => Ok(Self::DiskWrite),
+            "disk_read_auto" => Ok(Self::DiskReadAuto),
+            "disk_write_auto" => Ok(Self::DiskWriteAuto),
 
             "net_down" => Ok(Self::NetDown),
             "net_up" => Ok(Self::NetUp),
+            "net_down_auto" => Ok(Self::NetDownAuto),
+            "net_up_auto" => Ok(Self::NetUpAuto),
+
+            "gpu_percent" => Ok(Self::GpuPercent),
+            "gpu_memory_used" => Ok(Self::GpuMemoryUsed),
+            "gpu_memory_total" => Ok(Self::GpuMemoryTotal),
+            "gpu_memory_percent" => Ok(Self::GpuMemoryPercent),
+            "gpu_temp_c" => Ok(Self::GpuTempC),
+            "gpu_temp_f" => Ok(Self::GpuTempF),
 
             "load_average_1" => Ok(Self::LoadAverage1),
             "load_average_5" => Ok(Self::LoadAverage5),
@@ -519,8 +739,18 @@ impl Namespace for Client {
             TokenType::DiskPercent => None,
             TokenType::DiskRead => None,
             TokenType::DiskWrite => None,
+            TokenType::DiskReadAuto => None,
+            TokenType::DiskWriteAuto => None,
             TokenType::NetDown => None,
             TokenType::NetUp => None,
+            TokenType::NetDownAuto => None,
+            TokenType::NetUpAuto => None,
+            TokenType::GpuPercent => None,
+            TokenType::GpuMemoryUsed => None,
+            TokenType::GpuMemoryTotal => None,
+            TokenType::GpuMemoryPercent => None,
+            TokenType::GpuTempC => None,
+            TokenType::GpuTempF => None,
             TokenType::LoadAverage1 => get(self.load_average_1()),
             TokenType::LoadAverage5 => get(self.load_average_5()),
             TokenType::LoadAverage15 => get(self.load_average_15()),
@@ -563,6 +793,12 @@ impl Namespace for Client {
             "disk_write",
             "net_down",
             "net_up",
+            "gpu_percent",
+            "gpu_memory_used",
+            "gpu_memory_total",
+            "gpu_memory_percent",
+            "gpu_temp_c",
+            "gpu_temp_f",
         ]
         .into_iter()
         .map(ToString::to_string)
@@ -590,10 +826,21 @@ impl Namespace for Client {
             TokenType::DiskTotal => Some(Arc::new(self.disk_total())),
             TokenType::DiskUsed => Some(Arc::new(self.disk_used())),
             TokenType::DiskPercent => Some(Arc::new(self.disk_percent())),
-            TokenType::DiskRead => Some(Arc::new(self.disk_read(Interval::All(1)))),
-            TokenType::DiskWrite => Some(Arc::new(self.disk_write(Interval::All(1)))),
-            TokenType::NetDown => Some(Arc::new(self.net_down(Interval::All(1)))),
-            TokenType::NetUp => Some(Arc::new(self.net_up(Interval::All(1)))),
+            TokenType::DiskRead => Some(Arc::new(self.disk_read())),
+            TokenType::DiskWrite => Some(Arc::new(self.disk_write())),
+            TokenType::NetDown => Some(Arc::new(self.net_down())),
+            TokenType::NetUp => Some(Arc::new(self.net_up())),
+            // these only differ in how they are displayed
+            TokenType::DiskReadAuto => None,
+            TokenType::DiskWriteAuto => None,
+            TokenType::NetDownAuto => None,
+            TokenType::NetUpAuto => None,
+            TokenType::GpuPercent => Some(Arc::new(self.gpu_percent())),
+            TokenType::GpuMemoryUsed => Some(Arc::new(self.gpu_memory_used())),
+            TokenType::GpuMemoryTotal => Some(Arc::new(self.gpu_memory_total())),
+            TokenType::GpuMemoryPercent => Some(Arc::new(self.gpu_memory_percent())),
+            TokenType::GpuTempC => Some(Arc::new(self.gpu_temp_c())),
+            TokenType::GpuTempF => Some(Arc::new(self.gpu_temp_f())),
             TokenType::LoadAverage1 => None,
             TokenType::LoadAverage5 => None,
             TokenType::LoadAverage15 => None,
@@ -0,0 +1,269 @@
+mod dbus;
+
+use self::dbus::ManagerProxy;
+use crate::channels::SyncSenderExt;
+use crate::{arc_mut, lock, spawn};
+use color_eyre::Result;
+use futures_lite::StreamExt;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::error;
+use zbus::Connection;
+use zbus::fdo::PropertiesProxy;
+use zbus::names::InterfaceName;
+use zbus::zvariant::OwnedValue;
+
+const SERVICE: &str = "org.freedesktop.systemd1";
+const UNIT_INTERFACE: &str = "org.freedesktop.systemd1.Unit";
+
+/// The service manager a unit belongs to.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum Bus {
+    /// The system service manager.
+    System,
+    /// The service manager for the current user.
+    User,
+}
+
+/// Whether a unit is running.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ActiveState {
+    Active,
+    Reloading,
+    Inactive,
+    Failed,
+    Activating,
+    Deactivating,
+    Maintenance,
+    Refreshing,
+    #[default]
+    Unknown,
+}
+
+impl ActiveState {
+    /// The state as named by systemd, also used as a CSS class.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Reloading => "reloading",
+            Self::Inactive => "inactive",
+            Self::Failed => "failed",
+            Self::Activating => "activating",
+            Self::Deactivating => "deactivating",
+            Self::Maintenance => "maintenance",
+            Self::Refreshing => "refreshing",
+            Self::Unknown => "unknown",
+        }
+    }
+}
+
+impl From<&str> for ActiveState {
+    fn from(value: &str) -> Self {
+        match value {
+            "active" => Self::Active,
+            "reloading" => Self::Reloading,
+            "inactive" => Self::Inactive,
+            "failed" => Self::Failed,
+            "activating" => Self::Activating,
+            "deactivating" => Self::Deactivating,
+            "maintenance" => Self::Maintenance,
+            "refreshing" => Self::Refreshing,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unit {
+    pub bus: Bus,
+    /// The unit name, such as `nginx.service`.
+    pub name: String,
+    pub description: String,
+    /// Whether the unit file was found, such as `loaded` or `not-found`.
+    pub load_state: String,
+    pub active_state: ActiveState,
+    /// The unit type specific state, such as `running` or `exited`.
+    pub sub_state: String,
+}
+
+impl Unit {
+    fn new(bus: Bus, name: &str, properties: &HashMap<String, OwnedValue>) -> Self {
+        let property = |key: &str| {
+            properties
+                .get(key)
+                .and_then(|value| value.downcast_ref::<&str>().ok())
+                .unwrap_or_default()
+                .to_string()
+        };
+
+        Self {
+            bus,
+            name: name.to_string(),
+            description: property("Description"),
+            load_state: property("LoadState"),
+            active_state: ActiveState::from(property("ActiveState").as_str()),
+            sub_state: property("SubState"),
+        }
+    }
+}
+
+/// An action which can be run on a unit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitAction {
+    Start,
+    Stop,
+    Restart,
+}
+
+impl UnitAction {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Stop => "stop",
+            Self::Restart => "restart",
+        }
+    }
+}
+
+/// Watches units on a single service manager.
+///
+/// Units are only watched once requested with [`Client::watch`],
+/// after which each change to one of their properties is sent to subscribers.
+#[derive(Debug)]
+pub struct Client {
+    bus: Bus,
+    connection: Connection,
+    manager: ManagerProxy<'static>,
+
+    units: Arc<Mutex<HashMap<String, Unit>>>,
+    watched: Mutex<HashSet<String>>,
+
+    tx: broadcast::Sender<Unit>,
+    _rx: broadcast::Receiver<Unit>,
+}
+
+impl Client {
+    pub async fn new(bus: Bus) -> Result<Self> {
+        let connection = match bus {
+            Bus::System => crate::clients::dbus::system().await?,
+            Bus::User => crate::clients::dbus::session().await?,
+        };
+
+        let manager = ManagerProxy::new(&connection).await?;
+
+        // systemd only sends unit signals while at least one client is subscribed
+        manager.subscribe().await?;
+
+        let (tx, rx) = broadcast::channel(32);
+
+        Ok(Self {
+            bus,
+            connection,
+            manager,
+            units: arc_mut!(HashMap::new()),
+            watched: Mutex::new(HashSet::new()),
+            tx,
+            _rx: rx,
+        })
+    }
+
+    /// Starts watching the named unit, if it is not already being watched.
+    pub fn watch(&self, name: &str) {
+        if !lock!(self.watched).insert(name.to_string()) {
+            return;
+        }
+
+        let bus = self.bus;
+        let connection = self.connection.clone();
+        let manager = self.manager.clone();
+        let units = self.units.clone();
+        let tx = self.tx.clone();
+        let name = name.to_string();
+
+        spawn(async move {
+            if let Err(err) = watch_unit(bus, &connection, &manager, &units, &tx, &name).await {
+                error!("Failed to watch systemd unit '{name}': {err:?}");
+            }
+        });
+    }
+
+    /// Gets the current state of the named units,
+    /// for those which have been loaded.
+    pub fn units(&self, names: &[String]) -> Vec<Unit> {
+        let units = lock!(self.units);
+        names
+            .iter()
+            .filter_map(|name| units.get(name).cloned())
+            .collect()
+    }
+
+    /// Subscribes to changes to any watched unit.
+    pub fn subscribe(&self) -> broadcast::Receiver<Unit> {
+        self.tx.subscribe()
+    }
+
+    /// Asks systemd to start, stop or restart the unit.
+    /// The action is queued, so this returns before it has finished.
+    pub async fn run(&self, name: &str, action: UnitAction) -> Result<()> {
+        // replaces any conflicting queued jobs, as `systemctl` does
+        const MODE: &str = "replace";
+
+        match action {
+            UnitAction::Start => self.manager.start_unit(name, MODE).await?,
+            UnitAction::Stop => self.manager.stop_unit(name, MODE).await?,
+            UnitAction::Restart => self.manager.restart_unit(name, MODE).await?,
+        };
+
+        Ok(())
+    }
+}
+
+/// Loads the unit and sends its state whenever one of its properties changes.
+async fn watch_unit(
+    bus: Bus,
+    connection: &Connection,
+    manager: &ManagerProxy<'static>,
+    units: &Mutex<HashMap<String, Unit>>,
+    tx: &broadcast::Sender<Unit>,
+    name: &str,
+) -> Result<()> {
+    let path = manager.load_unit(name).await?;
+
+    let properties = PropertiesProxy::builder(connection)
+        .destination(SERVICE)?
+        .path(path)?
+        .build()
+        .await?;
+
+    let interface = InterfaceName::from_static_str(UNIT_INTERFACE)?;
+
+    let mut changes = properties.receive_properties_changed().await?;
+
+    loop {
+        // systemd does not include every changed value in the signal,
+        // so the full set is fetched each time
+        let unit = Unit::new(bus, name, &properties.get_all(interface.clone()).await?);
+
+        let changed = lock!(units).insert(name.to_string(), unit.clone()).as_ref() != Some(&unit);
+        if changed {
+            tx.send_expect(unit);
+        }
+
+        loop {
+            let Some(signal) = changes.next().await else {
+                return Ok(());
+            };
+
+            if signal
+                .args()
+                .is_ok_and(|args| args.interface_name == interface)
+            {
+                break;
+            }
+        }
+    }
+}
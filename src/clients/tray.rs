@@ -1,8 +1,9 @@
-use crate::channels::SyncSenderExt;
-use crate::clients::ClientResult;
-use crate::{arc_mut, lock, register_fallible_client, spawn};
+use crate::channels::{BroadcastReceiverExt, SyncSenderExt};
+use crate::clients::{ClientResult, reconnect};
+use crate::{arc_mut, arc_rw, lock, read_lock, register_fallible_client, spawn, write_lock};
+use color_eyre::{Report, Result};
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, RwLock};
 use system_tray::client::{ActivateRequest, Client as TrayClient, Event, UpdateEvent};
 use system_tray::data::BaseMap;
 use system_tray::menu::TrayMenu;
@@ -16,7 +17,9 @@ struct MenuCache {
 
 #[derive(Debug)]
 pub struct Client {
-    client: TrayClient,
+    client: Arc<RwLock<Arc<TrayClient>>>,
+    /// Session bus connection for calls not supported by the tray client.
+    connection: zbus::Connection,
     tx: broadcast::Sender<Event>,
     _rx: broadcast::Receiver<Event>,
 
@@ -25,61 +28,119 @@ pub struct Client {
 
 impl Client {
     pub async fn new() -> ClientResult<Self> {
-        let client = TrayClient::new().await?;
+        let client = Arc::new(TrayClient::new().await?);
+        let connection = crate::clients::dbus::session().await?;
 
         let (tx, rx) = broadcast::channel(16);
         let menus = arc_mut!(HashMap::new());
 
+        let client = arc_rw!(client);
+
         {
+            let client = client.clone();
             let tx = tx.clone();
-            let mut client_rx = client.subscribe();
             let menus = menus.clone();
+            let mut first_session = true;
 
-            // The client will send the Menu & MenuConnect events
-            // to the first module that connects to it,
-            // which means subsequent modules do not receive this information.
-            //
-            // Some info is re-fetched when they request the *items*
-            // but this is not enough to fully hydrate the menus
-            // To work around this, we cache these events to re-send to any future modules.
-            spawn(async move {
-                while let Ok(event) = client_rx.recv().await {
-                    match &event {
-                        Event::Update(address, UpdateEvent::MenuConnect(path)) => {
-                            lock!(menus).insert(
-                                address.clone().into_boxed_str(),
-                                MenuCache {
-                                    path: path.to_string(),
-                                    menu: None,
-                                },
-                            );
-                        }
-                        Event::Update(address, UpdateEvent::Menu(menu)) => {
-                            if let Some(entry) =
-                                lock!(menus).get_mut(&address.clone().into_boxed_str())
-                            {
-                                entry.menu = Some(menu.clone());
+            spawn(reconnect::run("tray", move |session| {
+                let client = client.clone();
+                let tx = tx.clone();
+                let menus = menus.clone();
+                let reconnecting = !std::mem::take(&mut first_session);
+
+                async move {
+                    if reconnecting {
+                        Self::reconnect(&client, &tx, &menus).await?;
+                    }
+
+                    let mut client_rx = read_lock!(client).subscribe();
+                    session.connected();
+
+                    loop {
+                        match client_rx.recv_tracked("tray").await {
+                            Ok(event) => Self::on_event(event, &tx, &menus),
+                            // items may have been missed, so reconnect to resync them all
+                            Err(broadcast::error::RecvError::Lagged(_)) => {
+                                return Err(Report::msg("tray event channel lagged"));
                             }
+                            Err(broadcast::error::RecvError::Closed) => break,
                         }
-                        Event::Remove(address) => {
-                            lock!(menus).remove(address.as_str());
-                        }
-                        _ => {}
                     }
 
-                    tx.send_expect(event);
+                    Err::<(), _>(Report::msg("tray event channel closed"))
                 }
-            });
+            }));
         }
 
         Ok(Arc::new(Self {
             client,
+            connection,
             tx,
             _rx: rx,
             menus,
         }))
     }
 
+    /// Replaces the tray client with a new connection,
+    /// removing all items from the previous one.
+    /// The new client re-sends all current items.
+    async fn reconnect(
+        client: &RwLock<Arc<TrayClient>>,
+        tx: &broadcast::Sender<Event>,
+        menus: &Mutex<HashMap<Box<str>, MenuCache>>,
+    ) -> Result<()> {
+        let new_client = Arc::new(TrayClient::new().await?);
+        let old_client = std::mem::replace(&mut *write_lock!(client), new_client);
+
+        let addresses = lock!(old_client.items())
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        lock!(menus).clear();
+
+        for address in addresses {
+            tx.send_expect(Event::Remove(address));
+        }
+
+        Ok(())
+    }
+
+    fn on_event(
+        event: Event,
+        tx: &broadcast::Sender<Event>,
+        menus: &Mutex<HashMap<Box<str>, MenuCache>>,
+    ) {
+        // The client will send the Menu & MenuConnect events
+        // to the first module that connects to it,
+        // which means subsequent modules do not receive this information.
+        //
+        // Some info is re-fetched when they request the *items*
+        // but this is not enough to fully hydrate the menus
+        // To work around this, we cache these events to re-send to any future modules.
+        match &event {
+            Event::Update(address, UpdateEvent::MenuConnect(path)) => {
+                lock!(menus).insert(
+                    address.clone().into_boxed_str(),
+                    MenuCache {
+                        path: path.to_string(),
+                        menu: None,
+                    },
+                );
+            }
+            Event::Update(address, UpdateEvent::Menu(menu)) => {
+                if let Some(entry) = lock!(menus).get_mut(&address.clone().into_boxed_str()) {
+                    entry.menu = Some(menu.clone());
+                }
+            }
+            Event::Remove(address) => {
+                lock!(menus).remove(address.as_str());
+            }
+            _ => {}
+        }
+
+        tx.send_expect(event);
+    }
+
     pub fn subscribe(&self) -> broadcast::Receiver<Event> {
         let rx = self.tx.subscribe();
 
@@ -101,11 +162,34 @@ impl Client {
     }
 
     pub fn items(&self) -> Arc<Mutex<BaseMap>> {
-        self.client.items()
+        read_lock!(self.client).items()
     }
 
     pub async fn activate(&self, req: ActivateRequest) -> system_tray::error::Result<()> {
-        self.client.activate(req).await
+        let client = read_lock!(self.client).clone();
+        client.activate(req).await
+    }
+
+    /// Forwards a scroll of `delta` steps to the item at the address.
+    ///
+    /// `orientation` is either `vertical` or `horizontal`.
+    pub async fn scroll(&self, address: &str, delta: i32, orientation: &str) -> Result<()> {
+        let (destination, path) = address.split_once('/').map_or(
+            (address, "/StatusNotifierItem".to_string()),
+            |(destination, path)| (destination, format!("/{path}")),
+        );
+
+        self.connection
+            .call_method(
+                Some(destination),
+                path.as_str(),
+                Some("org.kde.StatusNotifierItem"),
+                "Scroll",
+                &(delta, orientation),
+            )
+            .await?;
+
+        Ok(())
     }
 }
 
@@ -0,0 +1,241 @@
+//! Minimal HTTP/1.1 client for APIs served over unix sockets,
+//! such as the Docker and Tailscale local APIs.
+
+use color_eyre::{Report, Result};
+use std::path::Path;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::UnixStream;
+use tracing::debug;
+
+/// Sends an HTTP request over the socket,
+/// returning the response body if it succeeded.
+///
+/// If a body is provided, it is sent as JSON.
+pub async fn request(
+    socket: &Path,
+    host: &str,
+    method: &str,
+    path: &str,
+    body: Option<&str>,
+) -> Result<Vec<u8>> {
+    let mut stream = connect(socket, host, method, path, body).await?;
+
+    let mut response = vec![];
+    stream.read_to_end(&mut response).await?;
+
+    let (status, body) = parse_response(&response)?;
+    if (200..300).contains(&status) {
+        Ok(body)
+    } else {
+        Err(Report::msg(format!(
+            "Request failed with status {status}: {}",
+            String::from_utf8_lossy(&body).trim()
+        )))
+    }
+}
+
+/// Sends a GET request over the socket to an endpoint which streams its response,
+/// such as the Docker events API.
+/// The body can then be read a line at a time as it arrives.
+pub async fn stream(socket: &Path, host: &str, path: &str) -> Result<LineStream> {
+    let stream = connect(socket, host, "GET", path, None).await?;
+    let mut reader = BufReader::new(stream);
+
+    let mut head = String::new();
+    loop {
+        let bytes = reader.read_line(&mut head).await?;
+        if bytes == 0 {
+            return Err(Report::msg("Incomplete HTTP response"));
+        }
+
+        if head.ends_with("\r\n\r\n") {
+            break;
+        }
+    }
+
+    let (status, chunked) = parse_head(&head)?;
+    if !(200..300).contains(&status) {
+        return Err(Report::msg(format!("Request failed with status {status}")));
+    }
+
+    Ok(LineStream {
+        reader,
+        chunked,
+        buffer: vec![],
+    })
+}
+
+/// A response body which is read a line at a time.
+#[derive(Debug)]
+pub struct LineStream {
+    reader: BufReader<UnixStream>,
+    chunked: bool,
+    /// Bytes read from chunks which do not yet make up a full line.
+    buffer: Vec<u8>,
+}
+
+impl LineStream {
+    /// Waits for the next line of the body, without its trailing newline.
+    /// Returns `None` once the server closes the stream.
+    pub async fn next_line(&mut self) -> Result<Option<Vec<u8>>> {
+        if !self.chunked {
+            let mut line = vec![];
+            let bytes = self.reader.read_until(b'\n', &mut line).await?;
+
+            if line.ends_with(b"\n") {
+                line.pop();
+            }
+
+            return Ok((bytes > 0).then_some(line));
+        }
+
+        loop {
+            if let Some(end) = self.buffer.iter().position(|&byte| byte == b'\n') {
+                let mut line = self.buffer.drain(..=end).collect::<Vec<_>>();
+                line.pop();
+                return Ok(Some(line));
+            }
+
+            let mut size = String::new();
+            self.reader.read_line(&mut size).await?;
+
+            let size = size.split(';').next().unwrap_or_default().trim();
+            let size = usize::from_str_radix(size, 16)?;
+
+            if size == 0 {
+                let rest = std::mem::take(&mut self.buffer);
+                return Ok((!rest.is_empty()).then_some(rest));
+            }
+
+            let start = self.buffer.len();
+            self.buffer.resize(start + size, 0);
+            self.reader.read_exact(&mut self.buffer[start..]).await?;
+
+            // skip the trailing CRLF
+            let mut crlf = [0; 2];
+            self.reader.read_exact(&mut crlf).await?;
+        }
+    }
+}
+
+/// Connects to the socket and writes the request.
+async fn connect(
+    socket: &Path,
+    host: &str,
+    method: &str,
+    path: &str,
+    body: Option<&str>,
+) -> Result<UnixStream> {
+    debug!("{method} {path} via {}", socket.display());
+
+    let body = body.unwrap_or_default();
+    let request = format!(
+        "{method} {path} HTTP/1.1\r\n\
+         Host: {host}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n\
+         {body}",
+        body.len()
+    );
+
+    let mut stream = UnixStream::connect(socket).await?;
+    stream.write_all(request.as_bytes()).await?;
+
+    Ok(stream)
+}
+
+/// Parses the status line and headers of an HTTP response,
+/// getting the status code and whether the body uses chunked transfer encoding.
+fn parse_head(head: &str) -> Result<(u16, bool)> {
+    let mut lines = head.lines();
+
+    let status = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| Report::msg("Invalid HTTP status line"))?;
+
+    let chunked = lines.any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.eq_ignore_ascii_case("transfer-encoding") && value.trim() == "chunked"
+        })
+    });
+
+    Ok((status, chunked))
+}
+
+/// Parses an HTTP response into its status code and body,
+/// decoding the body if it uses chunked transfer encoding.
+fn parse_response(response: &[u8]) -> Result<(u16, Vec<u8>)> {
+    let header_end = response
+        .windows(4)
+        .position(|window| window == b"\r\n\r\n")
+        .ok_or_else(|| Report::msg("Incomplete HTTP response"))?;
+
+    let head = std::str::from_utf8(&response[..header_end])?;
+    let (status, chunked) = parse_head(head)?;
+
+    let body = &response[header_end + 4..];
+    let body = if chunked {
+        decode_chunked(body)?
+    } else {
+        body.to_vec()
+    };
+
+    Ok((status, body))
+}
+
+/// Decodes a body sent with chunked transfer encoding.
+fn decode_chunked(mut body: &[u8]) -> Result<Vec<u8>> {
+    let mut decoded = vec![];
+
+    loop {
+        let line_end = body
+            .windows(2)
+            .position(|window| window == b"\r\n")
+            .ok_or_else(|| Report::msg("Incomplete chunk"))?;
+
+        let size = std::str::from_utf8(&body[..line_end])?;
+        let size = size.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16)?;
+
+        if size == 0 {
+            break;
+        }
+
+        let start = line_end + 2;
+        let chunk = body
+            .get(start..start + size)
+            .ok_or_else(|| Report::msg("Incomplete chunk"))?;
+        decoded.extend_from_slice(chunk);
+
+        // skip the chunk and its trailing CRLF
+        body = body.get(start + size + 2..).unwrap_or_default();
+    }
+
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chunked_response() {
+        let response = b"HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\n\r\n4\r\n[{}]\r\n3\r\n[1]\r\n0\r\n\r\n";
+        let (status, body) = parse_response(response).unwrap();
+
+        assert_eq!(status, 200);
+        assert_eq!(body, b"[{}][1]");
+    }
+
+    #[test]
+    fn test_parse_plain_response() {
+        let response = b"HTTP/1.1 304 Not Modified\r\nContent-Length: 0\r\n\r\n";
+        let (status, body) = parse_response(response).unwrap();
+
+        assert_eq!(status, 304);
+        assert!(body.is_empty());
+    }
+}
@@ -1,43 +1,49 @@
 use super::dbus::UPowerProxy;
-use super::{BatteryState, State};
+use super::{BatteryState, Device, State};
 use crate::channels::SyncSenderExt;
 use crate::clients::ClientResult;
 use crate::{await_sync, spawn};
 use futures_lite::StreamExt;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::broadcast;
-use zbus::Result;
+use tokio::time::timeout;
+use tracing::{debug, error};
 use zbus::fdo::PropertiesProxy;
+use zbus::message::Type;
 use zbus::names::InterfaceName;
 use zbus::proxy::CacheProperties;
+use zbus::zvariant::ObjectPath;
+use zbus::{Connection, MatchRule, MessageStream, Result};
+
+const SERVICE: &str = "org.freedesktop.UPower";
+
+/// How long to wait for further signals before refreshing devices,
+/// as each device can change several properties at once.
+const DEBOUNCE: Duration = Duration::from_millis(200);
 
 #[derive(Debug)]
 pub struct Client {
+    connection: Connection,
     proxy: PropertiesProxy<'static>,
     interface_name: InterfaceName<'static>,
 
     tx: broadcast::Sender<State>,
+    devices_tx: broadcast::Sender<Vec<Device>>,
 }
 
 impl Client {
     pub async fn new() -> ClientResult<Self> {
-        let dbus = Box::pin(zbus::Connection::system()).await?;
+        let dbus = crate::clients::dbus::system().await?;
 
         let device_proxy = UPowerProxy::new(&dbus).await?;
 
         let display_device = device_proxy.get_display_device().await?;
 
-        let path = display_device.inner().path();
+        let path = display_device.inner().path().to_owned();
 
-        let proxy = PropertiesProxy::builder(&dbus)
-            .destination("org.freedesktop.UPower")
-            .expect("failed to set proxy destination address")
-            .path(path)
-            .expect("failed to set proxy path")
-            .cache_properties(CacheProperties::No)
-            .build()
-            .await?;
+        let proxy = properties_proxy(&dbus, path).await?;
 
         let interface_name = InterfaceName::from_static_str("org.freedesktop.UPower.Device")
             .expect("failed to create zbus InterfaceName");
@@ -45,6 +51,20 @@ impl Client {
         let (tx, rx) = broadcast::channel(16);
         std::mem::forget(rx);
 
+        let (devices_tx, rx) = broadcast::channel(16);
+        std::mem::forget(rx);
+
+        {
+            let dbus = dbus.clone();
+            let devices_tx = devices_tx.clone();
+
+            spawn(async move {
+                if let Err(err) = watch_devices(&dbus, &devices_tx).await {
+                    error!("Failed to watch UPower devices: {err:?}");
+                }
+            });
+        }
+
         spawn({
             let tx = tx.clone();
             let proxy = proxy.clone();
@@ -91,9 +111,11 @@ impl Client {
         });
 
         Ok(Arc::new(Self {
+            connection: dbus,
             proxy,
             interface_name,
             tx,
+            devices_tx,
         }))
     }
 
@@ -108,6 +130,65 @@ impl Client {
     pub fn subscribe(&self) -> broadcast::Receiver<State> {
         self.tx.subscribe()
     }
+
+    /// Gets every power device,
+    /// including peripherals such as Bluetooth headsets and mice.
+    pub async fn devices(&self) -> Result<Vec<Device>> {
+        devices(&self.connection).await
+    }
+
+    pub fn subscribe_devices(&self) -> broadcast::Receiver<Vec<Device>> {
+        self.devices_tx.subscribe()
+    }
+}
+
+async fn properties_proxy(
+    connection: &Connection,
+    path: ObjectPath<'static>,
+) -> Result<PropertiesProxy<'static>> {
+    PropertiesProxy::builder(connection)
+        .destination(SERVICE)?
+        .path(path)?
+        .cache_properties(CacheProperties::No)
+        .build()
+        .await
+}
+
+async fn devices(connection: &Connection) -> Result<Vec<Device>> {
+    let upower = UPowerProxy::new(connection).await?;
+    let interface_name = InterfaceName::from_static_str("org.freedesktop.UPower.Device")?;
+
+    let mut devices = vec![];
+
+    for path in upower.enumerate_devices().await? {
+        let proxy = properties_proxy(connection, path.clone().into()).await?;
+        let properties = proxy.get_all(interface_name.clone()).await?;
+
+        devices.push(Device::try_from_properties(path.to_string(), properties)?);
+    }
+
+    Ok(devices)
+}
+
+/// Sends the full list of devices
+/// each time a device is added, removed or changes.
+async fn watch_devices(connection: &Connection, tx: &broadcast::Sender<Vec<Device>>) -> Result<()> {
+    let rule = MatchRule::builder()
+        .msg_type(Type::Signal)
+        .sender(SERVICE)?
+        .build();
+    let mut signals = MessageStream::for_match_rule(rule, connection, None).await?;
+
+    tx.send_expect(devices(connection).await?);
+
+    while signals.next().await.is_some() {
+        while let Ok(Some(_)) = timeout(DEBOUNCE, signals.next()).await {}
+
+        debug!("Refreshing UPower devices");
+        tx.send_expect(devices(connection).await?);
+    }
+
+    Ok(())
 }
 
 #[cfg(any(feature = "ipc", feature = "cairo"))]
@@ -41,9 +41,10 @@ impl TryFrom<&zbus::zvariant::Value<'_>> for BatteryState {
     }
 }
 
-#[derive(Debug, Copy, Clone, OwnedValue)]
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, OwnedValue)]
 #[repr(u32)]
 pub enum BatteryType {
+    #[default]
     Unknown = 0,
     LinePower = 1,
     Battery = 2,
@@ -53,6 +54,62 @@ pub enum BatteryType {
     Keyboard = 6,
     Pda = 7,
     Phone = 8,
+    MediaPlayer = 9,
+    Tablet = 10,
+    Computer = 11,
+    GamingInput = 12,
+    Pen = 13,
+    Touchpad = 14,
+    Modem = 15,
+    Network = 16,
+    Headset = 17,
+    Speakers = 18,
+    Headphones = 19,
+    Video = 20,
+    OtherAudio = 21,
+    RemoteControl = 22,
+    Printer = 23,
+    Scanner = 24,
+    Camera = 25,
+    Wearable = 26,
+    Toy = 27,
+    BluetoothGeneric = 28,
+}
+
+impl From<u32> for BatteryType {
+    fn from(number: u32) -> Self {
+        match number {
+            1 => Self::LinePower,
+            2 => Self::Battery,
+            3 => Self::Ups,
+            4 => Self::Monitor,
+            5 => Self::Mouse,
+            6 => Self::Keyboard,
+            7 => Self::Pda,
+            8 => Self::Phone,
+            9 => Self::MediaPlayer,
+            10 => Self::Tablet,
+            11 => Self::Computer,
+            12 => Self::GamingInput,
+            13 => Self::Pen,
+            14 => Self::Touchpad,
+            15 => Self::Modem,
+            16 => Self::Network,
+            17 => Self::Headset,
+            18 => Self::Speakers,
+            19 => Self::Headphones,
+            20 => Self::Video,
+            21 => Self::OtherAudio,
+            22 => Self::RemoteControl,
+            23 => Self::Printer,
+            24 => Self::Scanner,
+            25 => Self::Camera,
+            26 => Self::Wearable,
+            27 => Self::Toy,
+            28 => Self::BluetoothGeneric,
+            _ => Self::Unknown,
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone, OwnedValue)]
@@ -5,11 +5,12 @@ mod dbus;
 mod test_client;
 
 use crate::register_fallible_client;
+use futures_lite::StreamExt;
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use zbus::zvariant::OwnedValue;
 
-pub use dbus::BatteryState;
+pub use dbus::{BatteryState, BatteryType};
 
 #[cfg(not(feature = "battery.test"))]
 pub use client::Client;
@@ -45,6 +46,71 @@ impl TryFrom<HashMap<String, OwnedValue>> for State {
     }
 }
 
+/// A power device, such as the system battery or a peripheral.
+#[derive(Clone, Debug)]
+pub struct Device {
+    /// The device's D-Bus object path, which uniquely identifies it.
+    pub path: String,
+    pub kind: BatteryType,
+    /// The model name reported by the device, which may be empty.
+    pub model: String,
+    /// Whether the device powers the system, such as a laptop battery.
+    /// This is `false` for peripherals.
+    pub power_supply: bool,
+    pub is_present: bool,
+    pub state: State,
+}
+
+impl Device {
+    fn try_from_properties(
+        path: String,
+        properties: HashMap<String, OwnedValue>,
+    ) -> Result<Self, zbus::zvariant::Error> {
+        Ok(Self {
+            path,
+            kind: properties["Type"].downcast_ref::<u32>()?.into(),
+            model: properties["Model"].downcast_ref::<&str>()?.to_string(),
+            power_supply: properties["PowerSupply"].downcast_ref::<bool>()?,
+            is_present: properties["IsPresent"].downcast_ref::<bool>()?,
+            state: properties.try_into()?,
+        })
+    }
+
+    /// Whether the device is a battery-powered peripheral,
+    /// such as a Bluetooth headset, mouse or controller.
+    pub fn is_peripheral(&self) -> bool {
+        !self.power_supply
+            && self.is_present
+            && !matches!(self.kind, BatteryType::Unknown | BatteryType::LinePower)
+    }
+
+    /// Gets the device's model name,
+    /// or the name of its type if it does not have one.
+    pub fn name(&self) -> String {
+        if self.model.is_empty() {
+            self.kind.to_string()
+        } else {
+            self.model.clone()
+        }
+    }
+}
+
+/// Calls `f` with whether the system is running on battery,
+/// initially and each time this changes.
+pub async fn watch_on_battery(f: impl Fn(bool)) -> zbus::Result<()> {
+    let connection = crate::clients::dbus::system().await?;
+    let proxy = dbus::UPowerProxy::new(&connection).await?;
+
+    let mut changes = proxy.receive_on_battery_changed().await;
+    f(proxy.on_battery().await?);
+
+    while let Some(change) = changes.next().await {
+        f(change.get().await?);
+    }
+
+    Ok(())
+}
+
 impl Display for BatteryState {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -63,4 +129,52 @@ impl Display for BatteryState {
     }
 }
 
+impl BatteryType {
+    /// Gets the name of the type for use as a CSS class,
+    /// such as `gaming-input`.
+    pub fn css_class(self) -> String {
+        self.to_string().to_lowercase().replace(' ', "-")
+    }
+}
+
+impl Display for BatteryType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                BatteryType::Unknown => "Unknown",
+                BatteryType::LinePower => "Line power",
+                BatteryType::Battery => "Battery",
+                BatteryType::Ups => "UPS",
+                BatteryType::Monitor => "Monitor",
+                BatteryType::Mouse => "Mouse",
+                BatteryType::Keyboard => "Keyboard",
+                BatteryType::Pda => "PDA",
+                BatteryType::Phone => "Phone",
+                BatteryType::MediaPlayer => "Media player",
+                BatteryType::Tablet => "Tablet",
+                BatteryType::Computer => "Computer",
+                BatteryType::GamingInput => "Gaming input",
+                BatteryType::Pen => "Pen",
+                BatteryType::Touchpad => "Touchpad",
+                BatteryType::Modem => "Modem",
+                BatteryType::Network => "Network",
+                BatteryType::Headset => "Headset",
+                BatteryType::Speakers => "Speakers",
+                BatteryType::Headphones => "Headphones",
+                BatteryType::Video => "Video",
+                BatteryType::OtherAudio => "Other audio",
+                BatteryType::RemoteControl => "Remote control",
+                BatteryType::Printer => "Printer",
+                BatteryType::Scanner => "Scanner",
+                BatteryType::Camera => "Camera",
+                BatteryType::Wearable => "Wearable",
+                BatteryType::Toy => "Toy",
+                BatteryType::BluetoothGeneric => "Bluetooth device",
+            }
+        )
+    }
+}
+
 register_fallible_client!(Client, upower);
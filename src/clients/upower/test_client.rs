@@ -1,4 +1,4 @@
-use super::{BatteryState, State};
+use super::{BatteryState, BatteryType, Device, State};
 use crate::channels::SyncSenderExt;
 use crate::clients::ClientResult;
 use crate::ironvar::NamespaceTrait;
@@ -18,9 +18,37 @@ fn base_state() -> State {
     }
 }
 
+fn devices() -> Vec<Device> {
+    let peripheral = |kind, model: &str, percentage, state| Device {
+        path: format!("/org/freedesktop/UPower/devices/{}", kind as u32),
+        kind,
+        model: model.to_string(),
+        power_supply: false,
+        is_present: true,
+        state: State {
+            percentage,
+            icon_name: "battery-good-symbolic".to_string(),
+            state,
+            time_to_full: 0,
+            time_to_empty: 0,
+        },
+    };
+
+    vec![
+        peripheral(
+            BatteryType::Headset,
+            "Test Headset",
+            60.0,
+            BatteryState::Discharging,
+        ),
+        peripheral(BatteryType::Mouse, "", 25.0, BatteryState::Charging),
+    ]
+}
+
 #[derive(Debug)]
 pub struct Client {
     tx: broadcast::Sender<State>,
+    devices_tx: broadcast::Sender<Vec<Device>>,
 }
 
 impl Client {
@@ -78,7 +106,10 @@ impl Client {
             }
         });
 
-        Ok(Arc::new(Self { tx }))
+        let (devices_tx, rx) = broadcast::channel(16);
+        std::mem::forget(rx);
+
+        Ok(Arc::new(Self { tx, devices_tx }))
     }
 
     pub async fn state(&self) -> Result<State> {
@@ -88,6 +119,14 @@ impl Client {
     pub fn subscribe(&self) -> broadcast::Receiver<State> {
         self.tx.subscribe()
     }
+
+    pub async fn devices(&self) -> Result<Vec<Device>> {
+        Ok(devices())
+    }
+
+    pub fn subscribe_devices(&self) -> broadcast::Receiver<Vec<Device>> {
+        self.devices_tx.subscribe()
+    }
 }
 
 #[cfg(any(feature = "ipc", feature = "cairo"))]
@@ -1,22 +1,14 @@
-mod sink;
-mod sink_input;
-
-use crate::channels::SyncSenderExt;
-use crate::{APP_ID, arc_mut, lock, register_client, spawn_blocking};
-use libpulse_binding::callbacks::ListResult;
-use libpulse_binding::context::introspect::{Introspector, ServerInfo};
-use libpulse_binding::context::subscribe::{Facility, InterestMaskSet, Operation};
-use libpulse_binding::context::{Context, FlagSet, State};
-use libpulse_binding::mainloop::standard::{IterateResult, Mainloop};
-use libpulse_binding::proplist::Proplist;
+#[cfg(feature = "volume+pipewire")]
+mod pipewire;
+mod pulse;
+
+use libpulse_binding::channelmap::Position;
 use libpulse_binding::volume::{ChannelVolumes, Volume};
-pub use sink::Sink;
-pub use sink_input::SinkInput;
-use std::fmt::{Debug, Formatter};
+use serde::Deserialize;
+use std::fmt::Debug;
 use std::ops::{Deref, DerefMut};
 use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast;
-use tracing::{debug, error, info, trace, warn};
 
 type ArcMutVec<T> = Arc<Mutex<Vec<T>>>;
 
@@ -29,262 +21,223 @@ pub enum Event {
     AddInput(SinkInput),
     UpdateInput(SinkInput),
     RemoveInput(u32),
-}
 
-#[derive(Debug)]
-pub struct Client {
-    connection: Arc<Mutex<ConnectionState>>,
+    AddSource(Source),
+    UpdateSource(Source),
+    RemoveSource(String),
 
-    data: Data,
+    AddOutput(SourceOutput),
+    UpdateOutput(SourceOutput),
+    RemoveOutput(u32),
 
-    tx: broadcast::Sender<Event>,
-    _rx: broadcast::Receiver<Event>,
+    AddCard(Card),
+    UpdateCard(Card),
+    RemoveCard(String),
 }
 
-#[derive(Debug, Default, Clone)]
-struct Data {
-    sinks: ArcMutVec<Sink>,
-    sink_inputs: ArcMutVec<SinkInput>,
-
-    default_sink_name: Arc<Mutex<Option<String>>>,
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "ipc", derive(serde::Serialize))]
+pub struct Sink {
+    #[cfg_attr(feature = "ipc", serde(skip))]
+    index: u32,
+    pub name: String,
+    pub description: String,
+    pub volume: VolumeLevels,
+    /// The position of each channel in `volume`.
+    pub channels: Vec<Channel>,
+    pub muted: bool,
+    pub active: bool,
 }
 
-pub enum ConnectionState {
-    Disconnected,
-    Connected {
-        context: Arc<Mutex<Context>>,
-        introspector: Introspector,
-    },
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "ipc", derive(serde::Serialize))]
+pub struct SinkInput {
+    pub index: u32,
+    pub name: String,
+    pub volume: VolumeLevels,
+    pub muted: bool,
+
+    pub can_set_volume: bool,
+
+    /// The latency requested by the stream, such as `1024/48000`.
+    /// Only available using the PipeWire backend.
+    pub latency: Option<String>,
 }
 
-impl Debug for ConnectionState {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{}",
-            match self {
-                Self::Disconnected => "Disconnected",
-                Self::Connected { .. } => "Connected",
-            }
-        )
-    }
+/// An input device, such as a microphone.
+/// Monitors of output devices are not included.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "ipc", derive(serde::Serialize))]
+pub struct Source {
+    #[cfg_attr(feature = "ipc", serde(skip))]
+    index: u32,
+    pub name: String,
+    pub description: String,
+    pub volume: VolumeLevels,
+    pub muted: bool,
+    pub active: bool,
 }
 
-impl Client {
-    pub fn new() -> Self {
-        let (tx, rx) = broadcast::channel(32);
-
-        Self {
-            connection: arc_mut!(ConnectionState::Disconnected),
-            data: Data::default(),
-            tx,
-            _rx: rx,
-        }
-    }
-
-    /// Starts the client.
-    fn run(&self) {
-        let Some(mut proplist) = Proplist::new() else {
-            error!("Failed to create PA proplist");
-            return;
-        };
-
-        if proplist.set_str("APPLICATION_NAME", APP_ID).is_err() {
-            error!("Failed to update PA proplist");
-        }
-
-        let Some(mut mainloop) = Mainloop::new() else {
-            error!("Failed to create PA mainloop");
-            return;
-        };
-
-        let Some(context) = Context::new_with_proplist(&mainloop, "Ironbar Context", &proplist)
-        else {
-            error!("Failed to create PA context");
-            return;
-        };
-
-        let context = arc_mut!(context);
-
-        let state_callback = Box::new({
-            let context = context.clone();
-            let data = self.data.clone();
-            let tx = self.tx.clone();
-
-            move || on_state_change(&context, &data, &tx)
-        });
-
-        lock!(context).set_state_callback(Some(state_callback));
-
-        if let Err(err) = lock!(context).connect(None, FlagSet::NOAUTOSPAWN, None) {
-            error!("{err:?}");
-        }
-
-        let introspector = lock!(context).introspect();
+/// A stream recording from a source.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "ipc", derive(serde::Serialize))]
+pub struct SourceOutput {
+    pub index: u32,
+    pub name: String,
+    pub muted: bool,
+}
 
-        {
-            let mut inner = lock!(self.connection);
-            *inner = ConnectionState::Connected {
-                context,
-                introspector,
-            };
-        }
+/// A sound card, which can switch between profiles
+/// to change the sinks and sources it provides.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "ipc", derive(serde::Serialize))]
+pub struct Card {
+    #[cfg_attr(feature = "ipc", serde(skip))]
+    index: u32,
+    pub name: String,
+    pub description: String,
+    pub profiles: Vec<CardProfile>,
+    pub active_profile: Option<String>,
+}
 
-        loop {
-            match mainloop.iterate(true) {
-                IterateResult::Success(_) => {}
-                IterateResult::Err(err) => error!("{err:?}"),
-                IterateResult::Quit(_) => break,
-            }
-        }
-    }
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "ipc", derive(serde::Serialize))]
+pub struct CardProfile {
+    pub name: String,
+    pub description: String,
+    /// Whether the profile can currently be used,
+    /// for example if a cable is plugged in.
+    pub available: bool,
+}
 
+pub trait VolumeClient: Debug + Send + Sync {
     /// Gets an event receiver.
-    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
-        self.tx.subscribe()
-    }
+    fn subscribe(&self) -> broadcast::Receiver<Event>;
+
+    fn sinks(&self) -> Vec<Sink>;
+    fn set_default_sink(&self, name: &str);
+    fn set_sink_volume(&self, name: &str, volume_percent: f64);
+    fn set_sink_muted(&self, name: &str, muted: bool);
+    /// Sets the balance between the left and right channels,
+    /// from `-1.0` (left only) to `1.0` (right only).
+    fn set_sink_balance(&self, name: &str, balance: f64);
+    /// Sets the volume of each channel at the position.
+    fn set_sink_channel_volume(&self, name: &str, channel: Channel, volume_percent: f64);
+
+    fn sink_inputs(&self) -> Vec<SinkInput>;
+    fn set_input_volume(&self, index: u32, volume_percent: f64);
+    fn set_input_muted(&self, index: u32, muted: bool);
+
+    fn sources(&self) -> Vec<Source>;
+    fn set_default_source(&self, name: &str);
+    fn set_source_volume(&self, name: &str, volume_percent: f64);
+    fn set_source_muted(&self, name: &str, muted: bool);
+
+    fn source_outputs(&self) -> Vec<SourceOutput>;
+    fn set_output_muted(&self, index: u32, muted: bool);
+
+    fn cards(&self) -> Vec<Card>;
+    fn set_card_profile(&self, name: &str, profile: &str);
 }
 
-/// Creates a new Pulse volume client.
-pub fn create_client() -> Arc<Client> {
-    let client = Arc::new(Client::new());
+/// The audio server API to use.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum ClientType {
+    /// PulseAudio, or PipeWire through `pipewire-pulse`.
+    #[default]
+    Pulse,
+    /// PipeWire, using its native API.
+    #[cfg(feature = "volume+pipewire")]
+    Pipewire,
+}
 
-    {
-        let client = client.clone();
-        spawn_blocking(move || {
-            client.run();
-        });
+pub fn create_client(client_type: ClientType) -> Arc<dyn VolumeClient> {
+    match client_type {
+        ClientType::Pulse => pulse::create_client(),
+        #[cfg(feature = "volume+pipewire")]
+        ClientType::Pipewire => pipewire::create_client(),
     }
+}
 
-    client
+/// The position of an audio channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "ipc", derive(serde::Serialize))]
+#[cfg_attr(feature = "ipc", serde(rename_all = "snake_case"))]
+pub enum Channel {
+    Mono,
+    FrontLeft,
+    FrontRight,
+    FrontCenter,
+    FrontLeftOfCenter,
+    FrontRightOfCenter,
+    RearLeft,
+    RearRight,
+    RearCenter,
+    SideLeft,
+    SideRight,
+    Lfe,
+    Other,
 }
 
-fn on_state_change(context: &Arc<Mutex<Context>>, data: &Data, tx: &broadcast::Sender<Event>) {
-    let Ok(state) = context.try_lock().map(|lock| lock.get_state()) else {
-        return;
-    };
-
-    match state {
-        State::Ready => {
-            info!("connected to server");
-
-            let introspect = lock!(context).introspect();
-            let introspect2 = lock!(context).introspect();
-
-            introspect.get_sink_info_list({
-                let sinks = data.sinks.clone();
-                let default_sink = data.default_sink_name.clone();
-
-                let tx = tx.clone();
-
-                move |info| match info {
-                    ListResult::Item(_) => sink::add(info, &sinks, &tx),
-                    ListResult::End => {
-                        introspect2.get_server_info({
-                            let sinks = sinks.clone();
-                            let default_sink = default_sink.clone();
-                            let tx = tx.clone();
-
-                            move |info| set_default_sink(info, &sinks, &default_sink, &tx)
-                        });
-                    }
-                    ListResult::Error => error!("Error while receiving sinks"),
-                }
-            });
-
-            introspect.get_sink_input_info_list({
-                let inputs = data.sink_inputs.clone();
-                let tx = tx.clone();
-
-                move |info| sink_input::add(info, &inputs, &tx)
-            });
-
-            let subscribe_callback = Box::new({
-                let context = context.clone();
-                let data = data.clone();
-                let tx = tx.clone();
-
-                move |facility, op, i| on_event(&context, &data, &tx, facility, op, i)
-            });
-
-            lock!(context).set_subscribe_callback(Some(subscribe_callback));
-            lock!(context).subscribe(
-                InterestMaskSet::SERVER | InterestMaskSet::SINK_INPUT | InterestMaskSet::SINK,
-                |_| (),
-            );
-        }
-        State::Failed => error!("Failed to connect to audio server"),
-        State::Terminated => error!("Connection to audio server terminated"),
-        _ => {}
+impl Channel {
+    fn is_left(self) -> bool {
+        matches!(
+            self,
+            Self::FrontLeft | Self::FrontLeftOfCenter | Self::RearLeft | Self::SideLeft
+        )
     }
-}
 
-fn on_event(
-    context: &Arc<Mutex<Context>>,
-    data: &Data,
-    tx: &broadcast::Sender<Event>,
-    facility: Option<Facility>,
-    op: Option<Operation>,
-    i: u32,
-) {
-    let (Some(facility), Some(op)) = (facility, op) else {
-        return;
-    };
-
-    trace!("server event: {facility:?}, op: {op:?}, i: {i}");
-
-    match facility {
-        Facility::Server => on_server_event(context, &data.sinks, &data.default_sink_name, tx),
-        Facility::Sink => sink::on_event(context, &data.sinks, &data.default_sink_name, tx, op, i),
-        Facility::SinkInput => sink_input::on_event(context, &data.sink_inputs, tx, op, i),
-        _ => error!("Received unhandled facility: {facility:?}"),
+    fn is_right(self) -> bool {
+        matches!(
+            self,
+            Self::FrontRight | Self::FrontRightOfCenter | Self::RearRight | Self::SideRight
+        )
     }
-}
 
-fn on_server_event(
-    context: &Arc<Mutex<Context>>,
-    sinks: &ArcMutVec<Sink>,
-    default_sink: &Arc<Mutex<Option<String>>>,
-    tx: &broadcast::Sender<Event>,
-) {
-    lock!(context).introspect().get_server_info({
-        let sinks = sinks.clone();
-        let default_sink = default_sink.clone();
-        let tx = tx.clone();
-
-        move |info| set_default_sink(info, &sinks, &default_sink, &tx)
-    });
+    /// Checks whether the channels include both a left and right side,
+    /// which is required to change the balance.
+    pub fn has_balance(channels: &[Self]) -> bool {
+        channels.iter().any(|c| c.is_left()) && channels.iter().any(|c| c.is_right())
+    }
 }
 
-fn set_default_sink(
-    info: &ServerInfo,
-    sinks: &ArcMutVec<Sink>,
-    default_sink: &Arc<Mutex<Option<String>>>,
-    tx: &broadcast::Sender<Event>,
-) {
-    let default_sink_name = info.default_sink_name.as_ref().map(ToString::to_string);
-
-    if default_sink_name != *lock!(default_sink)
-        && let Some(ref default_sink_name) = default_sink_name
-    {
-        if let Some(sink) = lock!(sinks)
-            .iter_mut()
-            .find(|s| s.name.as_str() == default_sink_name.as_str())
-        {
-            sink.active = true;
-            debug!("Set sink active: {}", sink.name);
-            tx.send_expect(Event::UpdateSink(sink.clone()));
-        } else {
-            warn!("Couldn't find sink: {}", default_sink_name);
+impl From<Position> for Channel {
+    fn from(position: Position) -> Self {
+        match position {
+            Position::Mono => Self::Mono,
+            Position::FrontLeft => Self::FrontLeft,
+            Position::FrontRight => Self::FrontRight,
+            Position::FrontCenter => Self::FrontCenter,
+            Position::FrontLeftOfCenter => Self::FrontLeftOfCenter,
+            Position::FrontRightOfCenter => Self::FrontRightOfCenter,
+            Position::RearLeft => Self::RearLeft,
+            Position::RearRight => Self::RearRight,
+            Position::RearCenter => Self::RearCenter,
+            Position::SideLeft => Self::SideLeft,
+            Position::SideRight => Self::SideRight,
+            Position::Lfe => Self::Lfe,
+            _ => Self::Other,
         }
     }
-
-    *lock!(default_sink) = default_sink_name;
 }
 
 #[derive(Debug, Clone)]
 pub struct VolumeLevels(Vec<u32>);
 
+/// Serializes as the volume percentage.
+#[cfg(feature = "ipc")]
+impl serde::Serialize for VolumeLevels {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.is_empty() {
+            serializer.serialize_f64(0.0)
+        } else {
+            serializer.serialize_f64(self.percent())
+        }
+    }
+}
+
 impl VolumeLevels {
     pub fn percent(&self) -> f64 {
         let avg: u32 = self.iter().sum::<u32>() / self.len() as u32;
@@ -297,6 +250,89 @@ impl VolumeLevels {
         let volume = percent_to_volume(percent);
         self.fill(volume);
     }
+
+    /// Sets the volume of each channel at the position.
+    pub fn set_channel_percent(&mut self, channels: &[Channel], channel: Channel, percent: f64) {
+        let volume = percent_to_volume(percent);
+
+        for (level, _) in self
+            .iter_mut()
+            .zip(channels)
+            .filter(|&(_, &c)| c == channel)
+        {
+            *level = volume;
+        }
+    }
+
+    /// Gets the balance between the left and right channels,
+    /// from `-1.0` (left only) to `1.0` (right only).
+    ///
+    /// This uses the same calculation as PulseAudio.
+    pub fn balance(&self, channels: &[Channel]) -> f64 {
+        let (left, right) = self.side_averages(channels);
+
+        if left == right {
+            0.0
+        } else if left > right {
+            right / left - 1.0
+        } else {
+            1.0 - left / right
+        }
+    }
+
+    /// Sets the balance between the left and right channels,
+    /// keeping the loudest side at its current volume.
+    pub fn set_balance(&mut self, channels: &[Channel], balance: f64) {
+        if !Channel::has_balance(channels) {
+            return;
+        }
+
+        let (left, right) = self.side_averages(channels);
+        let max = left.max(right);
+        let balance = balance.clamp(-1.0, 1.0);
+
+        let (new_left, new_right) = if balance <= 0.0 {
+            (max, (balance + 1.0) * max)
+        } else {
+            ((1.0 - balance) * max, max)
+        };
+
+        let scale = |level: u32, old: f64, new: f64| {
+            if old == 0.0 {
+                new.round() as u32
+            } else {
+                (level as f64 * new / old).round() as u32
+            }
+        };
+
+        for (level, channel) in self.iter_mut().zip(channels) {
+            if channel.is_left() {
+                *level = scale(*level, left, new_left);
+            } else if channel.is_right() {
+                *level = scale(*level, right, new_right);
+            }
+        }
+    }
+
+    /// Gets the average volume of the left and right channels.
+    fn side_averages(&self, channels: &[Channel]) -> (f64, f64) {
+        let average = |side: fn(Channel) -> bool| {
+            let levels = self
+                .iter()
+                .zip(channels)
+                .filter(|&(_, &c)| side(c))
+                .map(|(&level, _)| level as f64)
+                .collect::<Vec<_>>();
+
+            if levels.is_empty() {
+                0.0
+            } else {
+                levels.iter().sum::<f64>() / levels.len() as f64
+            }
+        };
+
+        (average(Channel::is_left), average(Channel::is_right))
+    }
 }
 
 impl Deref for VolumeLevels {
@@ -349,4 +385,36 @@ pub fn percent_to_volume(target_percent: f64) -> u32 {
     }
 }
 
-register_client!(Client, volume);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEREO: [Channel; 2] = [Channel::FrontLeft, Channel::FrontRight];
+
+    #[test]
+    fn test_balance() {
+        let levels = VolumeLevels(vec![Volume::NORMAL.0, Volume::NORMAL.0 / 2]);
+        assert_eq!(levels.balance(&STEREO), -0.5);
+
+        let levels = VolumeLevels(vec![Volume::NORMAL.0, Volume::NORMAL.0]);
+        assert_eq!(levels.balance(&STEREO), 0.0);
+    }
+
+    #[test]
+    fn test_set_balance() {
+        let mut levels = VolumeLevels(vec![Volume::NORMAL.0, Volume::NORMAL.0]);
+
+        levels.set_balance(&STEREO, 0.5);
+        assert_eq!(*levels, vec![Volume::NORMAL.0 / 2, Volume::NORMAL.0]);
+
+        levels.set_balance(&STEREO, 0.0);
+        assert_eq!(*levels, vec![Volume::NORMAL.0, Volume::NORMAL.0]);
+    }
+
+    #[test]
+    fn test_set_balance_mono() {
+        let mut levels = VolumeLevels(vec![Volume::NORMAL.0]);
+        levels.set_balance(&[Channel::Mono], 1.0);
+        assert_eq!(*levels, vec![Volume::NORMAL.0]);
+    }
+}
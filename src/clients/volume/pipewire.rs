@@ -0,0 +1,841 @@
+use super::{
+    ArcMutVec, Card, Channel, Event, Sink, SinkInput, Source, SourceOutput, VolumeClient,
+    VolumeLevels,
+};
+use crate::channels::SyncSenderExt;
+use crate::clients::reconnect::{self, Session};
+use crate::{lock, spawn_blocking};
+use color_eyre::{Report, Result};
+use libpulse_binding::volume::Volume;
+use pipewire::context::Context;
+use pipewire::core::PW_ID_CORE;
+use pipewire::main_loop::MainLoop;
+use pipewire::metadata::{Metadata, MetadataListener};
+use pipewire::node::{Node, NodeInfoRef, NodeListener};
+use pipewire::registry::{GlobalObject, Registry};
+use pipewire::spa::param::ParamType;
+use pipewire::spa::pod::deserialize::PodDeserializer;
+use pipewire::spa::pod::serialize::PodSerializer;
+use pipewire::spa::pod::{Object, Pod, Property, PropertyFlags, Value, ValueArray};
+use pipewire::spa::sys::{
+    SPA_AUDIO_CHANNEL_FC, SPA_AUDIO_CHANNEL_FL, SPA_AUDIO_CHANNEL_FLC, SPA_AUDIO_CHANNEL_FR,
+    SPA_AUDIO_CHANNEL_FRC, SPA_AUDIO_CHANNEL_LFE, SPA_AUDIO_CHANNEL_MONO, SPA_AUDIO_CHANNEL_RC,
+    SPA_AUDIO_CHANNEL_RL, SPA_AUDIO_CHANNEL_RR, SPA_AUDIO_CHANNEL_SL, SPA_AUDIO_CHANNEL_SR,
+    SPA_PARAM_Props, SPA_PROP_channelMap, SPA_PROP_channelVolumes, SPA_PROP_mute,
+    SPA_TYPE_OBJECT_Props,
+};
+use pipewire::spa::utils::dict::DictRef;
+use pipewire::types::ObjectType;
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::io::Cursor;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::{debug, error, instrument, trace};
+
+/// The metadata key holding the name of the default sink.
+const DEFAULT_SINK_KEY: &str = "default.audio.sink";
+/// The metadata key holding the name of the sink chosen by the user,
+/// which the session manager uses as the default while it exists.
+const CONFIGURED_SINK_KEY: &str = "default.configured.audio.sink";
+/// The metadata key holding the name of the default source.
+const DEFAULT_SOURCE_KEY: &str = "default.audio.source";
+/// The metadata key holding the name of the source chosen by the user.
+const CONFIGURED_SOURCE_KEY: &str = "default.configured.audio.source";
+
+/// A request from the client to the main loop,
+/// which owns every PipeWire object.
+#[derive(Debug)]
+enum Command {
+    SetDefaultSink(String),
+    SetDefaultSource(String),
+    SetVolume(u32, VolumeLevels),
+    SetMuted(u32, bool),
+}
+
+type CommandSender = pipewire::channel::Sender<Command>;
+
+pub struct Client {
+    commands: Mutex<Option<CommandSender>>,
+
+    data: Data,
+
+    tx: broadcast::Sender<Event>,
+    _rx: broadcast::Receiver<Event>,
+}
+
+impl Debug for Client {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Client")
+            .field("connected", &lock!(self.commands).is_some())
+            .field("data", &self.data)
+            .finish()
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct Data {
+    sinks: ArcMutVec<Sink>,
+    sink_inputs: ArcMutVec<SinkInput>,
+    sources: ArcMutVec<Source>,
+    source_outputs: ArcMutVec<SourceOutput>,
+
+    default_sink_name: Arc<Mutex<Option<String>>>,
+    default_source_name: Arc<Mutex<Option<String>>>,
+}
+
+/// The proxies bound on the main loop.
+/// Each is kept alive alongside its listener,
+/// as dropping either stops events for the object.
+#[derive(Default)]
+struct Objects {
+    nodes: HashMap<u32, (Node, NodeListener)>,
+    metadata: Option<(Metadata, MetadataListener)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NodeKind {
+    Sink,
+    Input,
+    Source,
+    Output,
+}
+
+/// The latest known state of a device or stream node,
+/// built up from its info and params.
+#[derive(Debug)]
+struct NodeEntry {
+    id: u32,
+    kind: NodeKind,
+    name: String,
+    description: String,
+    /// Unknown until the node's props are received.
+    volume: Option<VolumeLevels>,
+    channels: Vec<Channel>,
+    muted: bool,
+    latency: Option<String>,
+}
+
+/// The value of the default node metadata entries.
+#[derive(Deserialize)]
+struct DefaultNode {
+    name: String,
+}
+
+impl Client {
+    fn new() -> Self {
+        let (tx, rx) = broadcast::channel(32);
+
+        Self {
+            commands: Mutex::new(None),
+            data: Data::default(),
+            tx,
+            _rx: rx,
+        }
+    }
+
+    /// Connects to the server and runs the main loop
+    /// until the connection is lost.
+    fn run(&self, session: &Session) -> Result<()> {
+        let mainloop = MainLoop::new(None)?;
+        let context = Context::new(&mainloop)?;
+        let core = context.connect(None)?;
+        let registry = Rc::new(core.get_registry()?);
+
+        let objects = Rc::new(RefCell::new(Objects::default()));
+
+        let _core_listener = core
+            .add_listener_local()
+            .error({
+                let mainloop = mainloop.clone();
+                move |id, _seq, _res, message| {
+                    // errors on other objects are not fatal
+                    if id == PW_ID_CORE {
+                        error!("PipeWire error: {message}");
+                        mainloop.quit();
+                    }
+                }
+            })
+            .register();
+
+        let _registry_listener = registry
+            .add_listener_local()
+            .global({
+                let registry = Rc::downgrade(&registry);
+                let objects = objects.clone();
+                let data = self.data.clone();
+                let tx = self.tx.clone();
+
+                move |global| {
+                    if let Some(registry) = registry.upgrade() {
+                        on_global(&registry, global, &objects, &data, &tx);
+                    }
+                }
+            })
+            .global_remove({
+                let objects = objects.clone();
+                let data = self.data.clone();
+                let tx = self.tx.clone();
+
+                move |id| on_global_remove(id, &objects, &data, &tx)
+            })
+            .register();
+
+        let (commands_tx, commands_rx) = pipewire::channel::channel();
+        let _commands_rx = commands_rx.attach(mainloop.loop_(), {
+            let objects = objects.clone();
+            move |command| on_command(command, &objects.borrow())
+        });
+
+        *lock!(self.commands) = Some(commands_tx);
+        session.connected();
+
+        mainloop.run();
+
+        lock!(self.commands).take();
+        self.clear();
+
+        Err(Report::msg("Lost connection to PipeWire"))
+    }
+
+    /// Removes all devices and streams,
+    /// which are re-added once reconnected.
+    fn clear(&self) {
+        for sink in lock!(self.data.sinks).drain(..) {
+            self.tx.send_expect(Event::RemoveSink(sink.name));
+        }
+
+        for input in lock!(self.data.sink_inputs).drain(..) {
+            self.tx.send_expect(Event::RemoveInput(input.index));
+        }
+
+        for source in lock!(self.data.sources).drain(..) {
+            self.tx.send_expect(Event::RemoveSource(source.name));
+        }
+
+        for output in lock!(self.data.source_outputs).drain(..) {
+            self.tx.send_expect(Event::RemoveOutput(output.index));
+        }
+
+        lock!(self.data.default_sink_name).take();
+        lock!(self.data.default_source_name).take();
+    }
+
+    /// Modifies the sink's channel volumes,
+    /// and sends the result to the main loop.
+    fn update_sink_volume(&self, name: &str, f: impl FnOnce(&mut VolumeLevels, &[Channel])) {
+        let Some((index, mut volume_levels, channels)) = lock!(self.data.sinks)
+            .iter()
+            .find(|s| s.name == name)
+            .map(|s| (s.index, s.volume.clone(), s.channels.clone()))
+        else {
+            return;
+        };
+
+        f(&mut volume_levels, &channels);
+        self.send(Command::SetVolume(index, volume_levels));
+    }
+
+    fn send(&self, command: Command) {
+        if let Some(commands) = &*lock!(self.commands)
+            && let Err(command) = commands.send(command)
+        {
+            error!("Failed to send command to PipeWire main loop: {command:?}");
+        }
+    }
+}
+
+impl VolumeClient for Client {
+    fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.tx.subscribe()
+    }
+
+    #[instrument(level = "trace")]
+    fn sinks(&self) -> Vec<Sink> {
+        lock!(self.data.sinks).clone()
+    }
+
+    #[instrument(level = "trace")]
+    fn set_default_sink(&self, name: &str) {
+        self.send(Command::SetDefaultSink(name.to_string()));
+    }
+
+    #[instrument(level = "trace")]
+    fn set_sink_volume(&self, name: &str, volume_percent: f64) {
+        let Some((index, mut volume_levels)) = lock!(self.data.sinks)
+            .iter()
+            .find(|s| s.name == name)
+            .map(|s| (s.index, s.volume.clone()))
+        else {
+            return;
+        };
+
+        volume_levels.set_percent(volume_percent);
+        self.send(Command::SetVolume(index, volume_levels));
+    }
+
+    #[instrument(level = "trace")]
+    fn set_sink_muted(&self, name: &str, muted: bool) {
+        let index = lock!(self.data.sinks)
+            .iter()
+            .find(|s| s.name == name)
+            .map(|s| s.index);
+
+        if let Some(index) = index {
+            self.send(Command::SetMuted(index, muted));
+        }
+    }
+
+    #[instrument(level = "trace")]
+    fn set_sink_balance(&self, name: &str, balance: f64) {
+        self.update_sink_volume(name, |volume_levels, channels| {
+            volume_levels.set_balance(channels, balance);
+        });
+    }
+
+    #[instrument(level = "trace")]
+    fn set_sink_channel_volume(&self, name: &str, channel: Channel, volume_percent: f64) {
+        self.update_sink_volume(name, |volume_levels, channels| {
+            volume_levels.set_channel_percent(channels, channel, volume_percent);
+        });
+    }
+
+    #[instrument(level = "trace")]
+    fn sink_inputs(&self) -> Vec<SinkInput> {
+        lock!(self.data.sink_inputs).clone()
+    }
+
+    #[instrument(level = "trace")]
+    fn set_input_volume(&self, index: u32, volume_percent: f64) {
+        let Some(mut volume_levels) = lock!(self.data.sink_inputs)
+            .iter()
+            .find(|s| s.index == index)
+            .map(|s| s.volume.clone())
+        else {
+            return;
+        };
+
+        volume_levels.set_percent(volume_percent);
+        self.send(Command::SetVolume(index, volume_levels));
+    }
+
+    #[instrument(level = "trace")]
+    fn set_input_muted(&self, index: u32, muted: bool) {
+        self.send(Command::SetMuted(index, muted));
+    }
+
+    #[instrument(level = "trace")]
+    fn sources(&self) -> Vec<Source> {
+        lock!(self.data.sources).clone()
+    }
+
+    #[instrument(level = "trace")]
+    fn set_default_source(&self, name: &str) {
+        self.send(Command::SetDefaultSource(name.to_string()));
+    }
+
+    #[instrument(level = "trace")]
+    fn set_source_volume(&self, name: &str, volume_percent: f64) {
+        let Some((index, mut volume_levels)) = lock!(self.data.sources)
+            .iter()
+            .find(|s| s.name == name)
+            .map(|s| (s.index, s.volume.clone()))
+        else {
+            return;
+        };
+
+        volume_levels.set_percent(volume_percent);
+        self.send(Command::SetVolume(index, volume_levels));
+    }
+
+    #[instrument(level = "trace")]
+    fn set_source_muted(&self, name: &str, muted: bool) {
+        let index = lock!(self.data.sources)
+            .iter()
+            .find(|s| s.name == name)
+            .map(|s| s.index);
+
+        if let Some(index) = index {
+            self.send(Command::SetMuted(index, muted));
+        }
+    }
+
+    #[instrument(level = "trace")]
+    fn source_outputs(&self) -> Vec<SourceOutput> {
+        lock!(self.data.source_outputs).clone()
+    }
+
+    #[instrument(level = "trace")]
+    fn set_output_muted(&self, index: u32, muted: bool) {
+        self.send(Command::SetMuted(index, muted));
+    }
+
+    // card profiles are not yet supported natively,
+    // and can be switched using the `pulse` backend.
+    fn cards(&self) -> Vec<Card> {
+        vec![]
+    }
+
+    fn set_card_profile(&self, _name: &str, _profile: &str) {}
+}
+
+/// Creates a new native PipeWire volume client.
+pub fn create_client() -> Arc<Client> {
+    let client = Arc::new(Client::new());
+
+    {
+        let client = client.clone();
+        spawn_blocking(move || {
+            pipewire::init();
+            reconnect::run_blocking("pipewire", |session| client.run(&session));
+        });
+    }
+
+    client
+}
+
+fn on_global(
+    registry: &Registry,
+    global: &GlobalObject<&DictRef>,
+    objects: &RefCell<Objects>,
+    data: &Data,
+    tx: &broadcast::Sender<Event>,
+) {
+    let Some(props) = global.props else {
+        return;
+    };
+
+    match global.type_ {
+        ObjectType::Node => {
+            let kind = match props.get("media.class") {
+                Some("Audio/Sink") => NodeKind::Sink,
+                Some("Stream/Output/Audio") => NodeKind::Input,
+                Some("Audio/Source") => NodeKind::Source,
+                Some("Stream/Input/Audio") => NodeKind::Output,
+                _ => return,
+            };
+
+            let node: Node = match registry.bind(global) {
+                Ok(node) => node,
+                Err(err) => {
+                    error!("Failed to bind node {}: {err:?}", global.id);
+                    return;
+                }
+            };
+
+            trace!("binding {kind:?} node {}", global.id);
+
+            let entry = Rc::new(RefCell::new(NodeEntry::new(global.id, kind)));
+
+            let listener = node
+                .add_listener_local()
+                .info({
+                    let entry = entry.clone();
+                    let data = data.clone();
+                    let tx = tx.clone();
+
+                    move |info| {
+                        let mut entry = entry.borrow_mut();
+                        entry.update_info(info);
+                        entry.publish(&data, &tx);
+                    }
+                })
+                .param({
+                    let data = data.clone();
+                    let tx = tx.clone();
+
+                    move |_seq, _id, _index, _next, param| {
+                        let Some(param) = param else {
+                            return;
+                        };
+
+                        let mut entry = entry.borrow_mut();
+                        entry.update_props(param);
+                        entry.publish(&data, &tx);
+                    }
+                })
+                .register();
+
+            node.subscribe_params(&[ParamType::Props]);
+            objects
+                .borrow_mut()
+                .nodes
+                .insert(global.id, (node, listener));
+        }
+        ObjectType::Metadata if props.get("metadata.name") == Some("default") => {
+            let metadata: Metadata = match registry.bind(global) {
+                Ok(metadata) => metadata,
+                Err(err) => {
+                    error!("Failed to bind metadata {}: {err:?}", global.id);
+                    return;
+                }
+            };
+
+            let listener = metadata
+                .add_listener_local()
+                .property({
+                    let data = data.clone();
+                    let tx = tx.clone();
+
+                    move |subject, key, _type, value| {
+                        if subject == PW_ID_CORE {
+                            match key {
+                                Some(DEFAULT_SINK_KEY) => set_default_sink(value, &data, &tx),
+                                Some(DEFAULT_SOURCE_KEY) => set_default_source(value, &data, &tx),
+                                _ => {}
+                            }
+                        }
+
+                        0
+                    }
+                })
+                .register();
+
+            objects.borrow_mut().metadata = Some((metadata, listener));
+        }
+        _ => {}
+    }
+}
+
+fn on_global_remove(
+    id: u32,
+    objects: &RefCell<Objects>,
+    data: &Data,
+    tx: &broadcast::Sender<Event>,
+) {
+    if objects.borrow_mut().nodes.remove(&id).is_none() {
+        return;
+    }
+
+    trace!("removing node {id}");
+
+    {
+        let mut sinks = lock!(data.sinks);
+        if let Some(pos) = sinks.iter().position(|s| s.index == id) {
+            let sink = sinks.remove(pos);
+            tx.send_expect(Event::RemoveSink(sink.name));
+        }
+    }
+
+    {
+        let mut inputs = lock!(data.sink_inputs);
+        if let Some(pos) = inputs.iter().position(|s| s.index == id) {
+            let input = inputs.remove(pos);
+            tx.send_expect(Event::RemoveInput(input.index));
+        }
+    }
+
+    {
+        let mut sources = lock!(data.sources);
+        if let Some(pos) = sources.iter().position(|s| s.index == id) {
+            let source = sources.remove(pos);
+            tx.send_expect(Event::RemoveSource(source.name));
+        }
+    }
+
+    let mut outputs = lock!(data.source_outputs);
+    if let Some(pos) = outputs.iter().position(|s| s.index == id) {
+        let output = outputs.remove(pos);
+        tx.send_expect(Event::RemoveOutput(output.index));
+    }
+}
+
+fn on_command(command: Command, objects: &Objects) {
+    trace!("received command: {command:?}");
+
+    match command {
+        Command::SetDefaultSink(name) => set_configured(objects, CONFIGURED_SINK_KEY, &name),
+        Command::SetDefaultSource(name) => set_configured(objects, CONFIGURED_SOURCE_KEY, &name),
+        Command::SetVolume(id, volume_levels) => {
+            let volumes = volume_levels
+                .iter()
+                .copied()
+                .map(volume_to_linear)
+                .collect();
+            set_prop(
+                objects,
+                id,
+                SPA_PROP_channelVolumes,
+                Value::ValueArray(ValueArray::Float(volumes)),
+            );
+        }
+        Command::SetMuted(id, muted) => set_prop(objects, id, SPA_PROP_mute, Value::Bool(muted)),
+    }
+}
+
+/// Sets the default node chosen by the user
+/// in the session manager's metadata.
+fn set_configured(objects: &Objects, key: &str, name: &str) {
+    let Some((metadata, _)) = &objects.metadata else {
+        error!("Cannot set default device without a session manager");
+        return;
+    };
+
+    let value = serde_json::json!({ "name": name }).to_string();
+    metadata.set_property(PW_ID_CORE, key, Some("Spa:String:JSON"), Some(&value));
+}
+
+/// Sets a single property in the node's `Props` param.
+fn set_prop(objects: &Objects, id: u32, key: u32, value: Value) {
+    let Some((node, _)) = objects.nodes.get(&id) else {
+        return;
+    };
+
+    let props = Value::Object(Object {
+        type_: SPA_TYPE_OBJECT_Props,
+        id: SPA_PARAM_Props,
+        properties: vec![Property {
+            key,
+            flags: PropertyFlags::empty(),
+            value,
+        }],
+    });
+
+    let bytes = match PodSerializer::serialize(Cursor::new(Vec::new()), &props) {
+        Ok((cursor, _)) => cursor.into_inner(),
+        Err(err) => {
+            error!("Failed to serialize props: {err:?}");
+            return;
+        }
+    };
+
+    if let Some(pod) = Pod::from_bytes(&bytes) {
+        node.set_param(ParamType::Props, 0, pod);
+    }
+}
+
+fn set_default_sink(value: Option<&str>, data: &Data, tx: &broadcast::Sender<Event>) {
+    let default_sink_name = value
+        .and_then(|value| serde_json::from_str::<DefaultNode>(value).ok())
+        .map(|node| node.name);
+
+    debug!("default sink changed: {default_sink_name:?}");
+
+    for sink in lock!(data.sinks).iter_mut() {
+        let active = default_sink_name.as_ref() == Some(&sink.name);
+        if sink.active != active {
+            sink.active = active;
+            tx.send_expect(Event::UpdateSink(sink.clone()));
+        }
+    }
+
+    *lock!(data.default_sink_name) = default_sink_name;
+}
+
+fn set_default_source(value: Option<&str>, data: &Data, tx: &broadcast::Sender<Event>) {
+    let default_source_name = value
+        .and_then(|value| serde_json::from_str::<DefaultNode>(value).ok())
+        .map(|node| node.name);
+
+    debug!("default source changed: {default_source_name:?}");
+
+    for source in lock!(data.sources).iter_mut() {
+        let active = default_source_name.as_ref() == Some(&source.name);
+        if source.active != active {
+            source.active = active;
+            tx.send_expect(Event::UpdateSource(source.clone()));
+        }
+    }
+
+    *lock!(data.default_source_name) = default_source_name;
+}
+
+impl NodeEntry {
+    fn new(id: u32, kind: NodeKind) -> Self {
+        Self {
+            id,
+            kind,
+            name: String::new(),
+            description: String::new(),
+            volume: None,
+            channels: vec![],
+            muted: false,
+            latency: None,
+        }
+    }
+
+    fn update_info(&mut self, info: &NodeInfoRef) {
+        let Some(props) = info.props() else {
+            return;
+        };
+
+        match self.kind {
+            NodeKind::Sink | NodeKind::Source => {
+                self.name = props.get("node.name").unwrap_or_default().to_string();
+                self.description = props
+                    .get("node.description")
+                    .unwrap_or_default()
+                    .to_string();
+            }
+            NodeKind::Input | NodeKind::Output => {
+                self.name = props
+                    .get("media.name")
+                    .or_else(|| props.get("application.name"))
+                    .unwrap_or_default()
+                    .to_string();
+                self.latency = props.get("node.latency").map(ToString::to_string);
+            }
+        }
+    }
+
+    fn update_props(&mut self, param: &Pod) {
+        let Ok((_, Value::Object(object))) =
+            PodDeserializer::deserialize_any_from(param.as_bytes())
+        else {
+            return;
+        };
+
+        for property in object.properties {
+            match (property.key, property.value) {
+                (SPA_PROP_channelVolumes, Value::ValueArray(ValueArray::Float(volumes)))
+                    if !volumes.is_empty() =>
+                {
+                    self.volume = Some(VolumeLevels(
+                        volumes.into_iter().map(linear_to_volume).collect(),
+                    ));
+                }
+                (SPA_PROP_channelMap, Value::ValueArray(ValueArray::Id(ids))) => {
+                    self.channels = ids.into_iter().map(|id| spa_channel(id.0)).collect();
+                }
+                (SPA_PROP_mute, Value::Bool(muted)) => self.muted = muted,
+                _ => {}
+            }
+        }
+    }
+
+    /// Adds or updates the node in the client's data,
+    /// once its volume is known.
+    fn publish(&self, data: &Data, tx: &broadcast::Sender<Event>) {
+        let Some(volume) = self.volume.clone() else {
+            return;
+        };
+
+        match self.kind {
+            NodeKind::Sink => {
+                let active = lock!(data.default_sink_name).as_ref() == Some(&self.name);
+
+                let sink = Sink {
+                    index: self.id,
+                    name: self.name.clone(),
+                    description: self.description.clone(),
+                    volume,
+                    channels: self.channels.clone(),
+                    muted: self.muted,
+                    active,
+                };
+
+                let mut sinks = lock!(data.sinks);
+                if let Some(existing) = sinks.iter_mut().find(|s| s.index == self.id) {
+                    *existing = sink.clone();
+                    tx.send_expect(Event::UpdateSink(sink));
+                } else {
+                    sinks.push(sink.clone());
+                    tx.send_expect(Event::AddSink(sink));
+                }
+            }
+            NodeKind::Input => {
+                let input = SinkInput {
+                    index: self.id,
+                    name: self.name.clone(),
+                    volume,
+                    muted: self.muted,
+                    can_set_volume: true,
+                    latency: self.latency.clone(),
+                };
+
+                let mut inputs = lock!(data.sink_inputs);
+                if let Some(existing) = inputs.iter_mut().find(|s| s.index == self.id) {
+                    *existing = input.clone();
+                    tx.send_expect(Event::UpdateInput(input));
+                } else {
+                    inputs.push(input.clone());
+                    tx.send_expect(Event::AddInput(input));
+                }
+            }
+            NodeKind::Source => {
+                let active = lock!(data.default_source_name).as_ref() == Some(&self.name);
+
+                let source = Source {
+                    index: self.id,
+                    name: self.name.clone(),
+                    description: self.description.clone(),
+                    volume,
+                    muted: self.muted,
+                    active,
+                };
+
+                let mut sources = lock!(data.sources);
+                if let Some(existing) = sources.iter_mut().find(|s| s.index == self.id) {
+                    *existing = source.clone();
+                    tx.send_expect(Event::UpdateSource(source));
+                } else {
+                    sources.push(source.clone());
+                    tx.send_expect(Event::AddSource(source));
+                }
+            }
+            NodeKind::Output => {
+                let output = SourceOutput {
+                    index: self.id,
+                    name: self.name.clone(),
+                    muted: self.muted,
+                };
+
+                let mut outputs = lock!(data.source_outputs);
+                if let Some(existing) = outputs.iter_mut().find(|s| s.index == self.id) {
+                    *existing = output.clone();
+                    tx.send_expect(Event::UpdateOutput(output));
+                } else {
+                    outputs.push(output.clone());
+                    tx.send_expect(Event::AddOutput(output));
+                }
+            }
+        }
+    }
+}
+
+/// Converts an SPA audio channel position into a channel.
+fn spa_channel(id: u32) -> Channel {
+    match id {
+        SPA_AUDIO_CHANNEL_MONO => Channel::Mono,
+        SPA_AUDIO_CHANNEL_FL => Channel::FrontLeft,
+        SPA_AUDIO_CHANNEL_FR => Channel::FrontRight,
+        SPA_AUDIO_CHANNEL_FC => Channel::FrontCenter,
+        SPA_AUDIO_CHANNEL_FLC => Channel::FrontLeftOfCenter,
+        SPA_AUDIO_CHANNEL_FRC => Channel::FrontRightOfCenter,
+        SPA_AUDIO_CHANNEL_RL => Channel::RearLeft,
+        SPA_AUDIO_CHANNEL_RR => Channel::RearRight,
+        SPA_AUDIO_CHANNEL_RC => Channel::RearCenter,
+        SPA_AUDIO_CHANNEL_SL => Channel::SideLeft,
+        SPA_AUDIO_CHANNEL_SR => Channel::SideRight,
+        SPA_AUDIO_CHANNEL_LFE => Channel::Lfe,
+        _ => Channel::Other,
+    }
+}
+
+/// Converts a linear PipeWire channel volume
+/// into the cubic scale used for Pulse volumes.
+fn linear_to_volume(linear: f32) -> u32 {
+    (linear.cbrt() * Volume::NORMAL.0 as f32).round() as u32
+}
+
+/// Converts a Pulse volume into a linear PipeWire channel volume.
+fn volume_to_linear(volume: u32) -> f32 {
+    (volume as f32 / Volume::NORMAL.0 as f32).powi(3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_volume_round_trip() {
+        assert_eq!(linear_to_volume(1.0), Volume::NORMAL.0);
+        assert_eq!(linear_to_volume(0.0), Volume::MUTED.0);
+
+        let half = Volume::NORMAL.0 / 2;
+        assert_eq!(linear_to_volume(volume_to_linear(half)), half);
+    }
+}
@@ -0,0 +1,133 @@
+use crate::channels::SyncSenderExt;
+use crate::clients::volume::{ArcMutVec, Card, CardProfile, Event};
+use crate::lock;
+use libpulse_binding::callbacks::ListResult;
+use libpulse_binding::context::Context;
+use libpulse_binding::context::introspect::{CardInfo, CardProfileInfo};
+use libpulse_binding::context::subscribe::Operation;
+use libpulse_binding::proplist::properties;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::{debug, error, trace};
+
+impl From<&CardProfileInfo<'_>> for CardProfile {
+    fn from(value: &CardProfileInfo) -> Self {
+        Self {
+            name: value
+                .name
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            description: value
+                .description
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            available: value.available,
+        }
+    }
+}
+
+impl From<&CardInfo<'_>> for Card {
+    fn from(value: &CardInfo) -> Self {
+        let name = value
+            .name
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+
+        Self {
+            index: value.index,
+            description: value
+                .proplist
+                .get_str(properties::DEVICE_DESCRIPTION)
+                .unwrap_or_else(|| name.clone()),
+            name,
+            profiles: value.profiles.iter().map(CardProfile::from).collect(),
+            active_profile: value
+                .active_profile
+                .as_ref()
+                .and_then(|profile| profile.name.as_ref())
+                .map(ToString::to_string),
+        }
+    }
+}
+
+pub fn on_event(
+    context: &Arc<Mutex<Context>>,
+    cards: &ArcMutVec<Card>,
+    tx: &broadcast::Sender<Event>,
+    op: Operation,
+    i: u32,
+) {
+    let introspect = lock!(context).introspect();
+
+    match op {
+        Operation::New => {
+            debug!("new card");
+            introspect.get_card_info_by_index(i, {
+                let cards = cards.clone();
+                let tx = tx.clone();
+
+                move |info| add(info, &cards, &tx)
+            });
+        }
+        Operation::Changed => {
+            debug!("card changed");
+            introspect.get_card_info_by_index(i, {
+                let cards = cards.clone();
+                let tx = tx.clone();
+
+                move |info| update(info, &cards, &tx)
+            });
+        }
+        Operation::Removed => {
+            debug!("card removed");
+            remove(i, cards, tx);
+        }
+    }
+}
+
+pub fn add(info: ListResult<&CardInfo>, cards: &ArcMutVec<Card>, tx: &broadcast::Sender<Event>) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    trace!("adding {info:?}");
+
+    lock!(cards).push(info.into());
+    tx.send_expect(Event::AddCard(info.into()));
+}
+
+fn update(info: ListResult<&CardInfo>, cards: &ArcMutVec<Card>, tx: &broadcast::Sender<Event>) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    trace!("updating {info:?}");
+
+    let card: Card = info.into();
+
+    {
+        let mut cards = lock!(cards);
+        let Some(pos) = cards.iter().position(|c| c.index == info.index) else {
+            error!("received update to untracked card");
+            return;
+        };
+
+        cards[pos] = card.clone();
+    }
+
+    tx.send_expect(Event::UpdateCard(card));
+}
+
+fn remove(index: u32, cards: &ArcMutVec<Card>, tx: &broadcast::Sender<Event>) {
+    trace!("removing {index}");
+
+    let mut cards = lock!(cards);
+
+    if let Some(pos) = cards.iter().position(|c| c.index == index) {
+        let info = cards.remove(pos);
+        tx.send_expect(Event::RemoveCard(info.name));
+    }
+}
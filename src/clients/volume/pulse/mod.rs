@@ -0,0 +1,541 @@
+mod card;
+mod sink;
+mod sink_input;
+mod source;
+mod source_output;
+
+use super::{
+    ArcMutVec, Card, Channel, Event, Sink, SinkInput, Source, SourceOutput, VolumeClient,
+    VolumeLevels,
+};
+use crate::channels::SyncSenderExt;
+use crate::clients::reconnect::{self, Session};
+use crate::{APP_ID, arc_mut, lock, spawn_blocking};
+use color_eyre::{Report, Result};
+use libpulse_binding::callbacks::ListResult;
+use libpulse_binding::context::introspect::{Introspector, ServerInfo};
+use libpulse_binding::context::subscribe::{Facility, InterestMaskSet, Operation};
+use libpulse_binding::context::{Context, FlagSet, State};
+use libpulse_binding::mainloop::standard::{IterateResult, Mainloop};
+use libpulse_binding::proplist::Proplist;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::{debug, error, instrument, trace, warn};
+
+#[derive(Debug)]
+pub struct Client {
+    connection: Arc<Mutex<ConnectionState>>,
+
+    data: Data,
+
+    tx: broadcast::Sender<Event>,
+    _rx: broadcast::Receiver<Event>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct Data {
+    sinks: ArcMutVec<Sink>,
+    sink_inputs: ArcMutVec<SinkInput>,
+    sources: ArcMutVec<Source>,
+    source_outputs: ArcMutVec<SourceOutput>,
+    cards: ArcMutVec<Card>,
+
+    default_sink_name: Arc<Mutex<Option<String>>>,
+    default_source_name: Arc<Mutex<Option<String>>>,
+}
+
+pub enum ConnectionState {
+    Disconnected,
+    Connected {
+        context: Arc<Mutex<Context>>,
+        introspector: Introspector,
+    },
+}
+
+impl Debug for ConnectionState {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Disconnected => "Disconnected",
+                Self::Connected { .. } => "Connected",
+            }
+        )
+    }
+}
+
+impl Client {
+    pub fn new() -> Self {
+        let (tx, rx) = broadcast::channel(32);
+
+        Self {
+            connection: arc_mut!(ConnectionState::Disconnected),
+            data: Data::default(),
+            tx,
+            _rx: rx,
+        }
+    }
+
+    /// Connects to the server and runs the client
+    /// until the connection is lost.
+    fn run(&self, session: &Session) -> Result<()> {
+        let mut proplist =
+            Proplist::new().ok_or_else(|| Report::msg("Failed to create PA proplist"))?;
+
+        if proplist.set_str("APPLICATION_NAME", APP_ID).is_err() {
+            error!("Failed to update PA proplist");
+        }
+
+        let mut mainloop =
+            Mainloop::new().ok_or_else(|| Report::msg("Failed to create PA mainloop"))?;
+
+        let context = Context::new_with_proplist(&mainloop, "Ironbar Context", &proplist)
+            .ok_or_else(|| Report::msg("Failed to create PA context"))?;
+
+        let context = arc_mut!(context);
+
+        let state_callback = Box::new({
+            let context = context.clone();
+            let data = self.data.clone();
+            let tx = self.tx.clone();
+            let session = session.clone();
+
+            move || on_state_change(&context, &data, &tx, &session)
+        });
+
+        lock!(context).set_state_callback(Some(state_callback));
+        lock!(context).connect(None, FlagSet::NOAUTOSPAWN, None)?;
+
+        let introspector = lock!(context).introspect();
+
+        {
+            let mut inner = lock!(self.connection);
+            *inner = ConnectionState::Connected {
+                context: context.clone(),
+                introspector,
+            };
+        }
+
+        loop {
+            match mainloop.iterate(true) {
+                IterateResult::Success(_) => {}
+                IterateResult::Err(err) => error!("{err:?}"),
+                IterateResult::Quit(_) => break,
+            }
+
+            if matches!(
+                lock!(context).get_state(),
+                State::Failed | State::Terminated
+            ) {
+                break;
+            }
+        }
+
+        *lock!(self.connection) = ConnectionState::Disconnected;
+        self.clear();
+
+        Err(Report::msg("Lost connection to audio server"))
+    }
+
+    /// Removes all devices and streams,
+    /// which are re-added once reconnected.
+    fn clear(&self) {
+        for sink in lock!(self.data.sinks).drain(..) {
+            self.tx.send_expect(Event::RemoveSink(sink.name));
+        }
+
+        for input in lock!(self.data.sink_inputs).drain(..) {
+            self.tx.send_expect(Event::RemoveInput(input.index));
+        }
+
+        for source in lock!(self.data.sources).drain(..) {
+            self.tx.send_expect(Event::RemoveSource(source.name));
+        }
+
+        for output in lock!(self.data.source_outputs).drain(..) {
+            self.tx.send_expect(Event::RemoveOutput(output.index));
+        }
+
+        for card in lock!(self.data.cards).drain(..) {
+            self.tx.send_expect(Event::RemoveCard(card.name));
+        }
+
+        lock!(self.data.default_sink_name).take();
+        lock!(self.data.default_source_name).take();
+    }
+
+    /// Modifies the sink's channel volumes,
+    /// and sends the result to the server.
+    fn update_sink_volume(&self, name: &str, f: impl FnOnce(&mut VolumeLevels, &[Channel])) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            let Some((mut volume_levels, channels)) = lock!(self.data.sinks)
+                .iter()
+                .find(|s| s.name == name)
+                .map(|s| (s.volume.clone(), s.channels.clone()))
+            else {
+                return;
+            };
+
+            f(&mut volume_levels, &channels);
+            introspector.set_sink_volume_by_name(name, &volume_levels.into(), None);
+        }
+    }
+}
+
+impl VolumeClient for Client {
+    fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.tx.subscribe()
+    }
+
+    #[instrument(level = "trace")]
+    fn sinks(&self) -> Vec<Sink> {
+        lock!(self.data.sinks).clone()
+    }
+
+    #[instrument(level = "trace")]
+    fn set_default_sink(&self, name: &str) {
+        if let ConnectionState::Connected { context, .. } = &*lock!(self.connection) {
+            lock!(context).set_default_sink(name, |_| {});
+        }
+    }
+
+    #[instrument(level = "trace")]
+    fn set_sink_volume(&self, name: &str, volume_percent: f64) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            let Some(mut volume_levels) = lock!(self.data.sinks)
+                .iter()
+                .find(|s| s.name == name)
+                .map(|s| s.volume.clone())
+            else {
+                return;
+            };
+
+            volume_levels.set_percent(volume_percent);
+            introspector.set_sink_volume_by_name(name, &volume_levels.into(), None);
+        }
+    }
+
+    #[instrument(level = "trace")]
+    fn set_sink_muted(&self, name: &str, muted: bool) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            introspector.set_sink_mute_by_name(name, muted, None);
+        }
+    }
+
+    #[instrument(level = "trace")]
+    fn set_sink_balance(&self, name: &str, balance: f64) {
+        self.update_sink_volume(name, |volume_levels, channels| {
+            volume_levels.set_balance(channels, balance);
+        });
+    }
+
+    #[instrument(level = "trace")]
+    fn set_sink_channel_volume(&self, name: &str, channel: Channel, volume_percent: f64) {
+        self.update_sink_volume(name, |volume_levels, channels| {
+            volume_levels.set_channel_percent(channels, channel, volume_percent);
+        });
+    }
+
+    #[instrument(level = "trace")]
+    fn sink_inputs(&self) -> Vec<SinkInput> {
+        lock!(self.data.sink_inputs).clone()
+    }
+
+    #[instrument(level = "trace")]
+    fn set_input_volume(&self, index: u32, volume_percent: f64) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            let Some(mut volume_levels) = lock!(self.data.sink_inputs)
+                .iter()
+                .find(|s| s.index == index)
+                .map(|s| s.volume.clone())
+            else {
+                return;
+            };
+
+            volume_levels.set_percent(volume_percent);
+            introspector.set_sink_input_volume(index, &volume_levels.into(), None);
+        }
+    }
+
+    #[instrument(level = "trace")]
+    fn set_input_muted(&self, index: u32, muted: bool) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            introspector.set_sink_input_mute(index, muted, None);
+        }
+    }
+
+    #[instrument(level = "trace")]
+    fn sources(&self) -> Vec<Source> {
+        lock!(self.data.sources).clone()
+    }
+
+    #[instrument(level = "trace")]
+    fn set_default_source(&self, name: &str) {
+        if let ConnectionState::Connected { context, .. } = &*lock!(self.connection) {
+            lock!(context).set_default_source(name, |_| {});
+        }
+    }
+
+    #[instrument(level = "trace")]
+    fn set_source_volume(&self, name: &str, volume_percent: f64) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            let Some(mut volume_levels) = lock!(self.data.sources)
+                .iter()
+                .find(|s| s.name == name)
+                .map(|s| s.volume.clone())
+            else {
+                return;
+            };
+
+            volume_levels.set_percent(volume_percent);
+            introspector.set_source_volume_by_name(name, &volume_levels.into(), None);
+        }
+    }
+
+    #[instrument(level = "trace")]
+    fn set_source_muted(&self, name: &str, muted: bool) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            introspector.set_source_mute_by_name(name, muted, None);
+        }
+    }
+
+    #[instrument(level = "trace")]
+    fn source_outputs(&self) -> Vec<SourceOutput> {
+        lock!(self.data.source_outputs).clone()
+    }
+
+    #[instrument(level = "trace")]
+    fn set_output_muted(&self, index: u32, muted: bool) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            introspector.set_source_output_mute(index, muted, None);
+        }
+    }
+
+    #[instrument(level = "trace")]
+    fn cards(&self) -> Vec<Card> {
+        lock!(self.data.cards).clone()
+    }
+
+    #[instrument(level = "trace")]
+    fn set_card_profile(&self, name: &str, profile: &str) {
+        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
+            introspector.set_card_profile_by_name(name, profile, None);
+        }
+    }
+}
+
+/// Creates a new Pulse volume client.
+pub fn create_client() -> Arc<Client> {
+    let client = Arc::new(Client::new());
+
+    {
+        let client = client.clone();
+        spawn_blocking(move || {
+            reconnect::run_blocking("pulse", |session| client.run(&session));
+        });
+    }
+
+    client
+}
+
+fn on_state_change(
+    context: &Arc<Mutex<Context>>,
+    data: &Data,
+    tx: &broadcast::Sender<Event>,
+    session: &Session,
+) {
+    let Ok(state) = context.try_lock().map(|lock| lock.get_state()) else {
+        return;
+    };
+
+    match state {
+        State::Ready => {
+            session.connected();
+
+            let introspect = lock!(context).introspect();
+            let introspect2 = lock!(context).introspect();
+
+            introspect.get_sink_info_list({
+                let sinks = data.sinks.clone();
+                let default_sink = data.default_sink_name.clone();
+
+                let tx = tx.clone();
+
+                move |info| match info {
+                    ListResult::Item(_) => sink::add(info, &sinks, &tx),
+                    ListResult::End => {
+                        introspect2.get_server_info({
+                            let sinks = sinks.clone();
+                            let default_sink = default_sink.clone();
+                            let tx = tx.clone();
+
+                            move |info| set_default_sink(info, &sinks, &default_sink, &tx)
+                        });
+                    }
+                    ListResult::Error => error!("Error while receiving sinks"),
+                }
+            });
+
+            introspect.get_sink_input_info_list({
+                let inputs = data.sink_inputs.clone();
+                let tx = tx.clone();
+
+                move |info| sink_input::add(info, &inputs, &tx)
+            });
+
+            let introspect3 = lock!(context).introspect();
+
+            introspect.get_source_info_list({
+                let sources = data.sources.clone();
+                let default_source = data.default_source_name.clone();
+
+                let tx = tx.clone();
+
+                move |info| match info {
+                    ListResult::Item(_) => source::add(info, &sources, &tx),
+                    ListResult::End => {
+                        introspect3.get_server_info({
+                            let sources = sources.clone();
+                            let default_source = default_source.clone();
+                            let tx = tx.clone();
+
+                            move |info| set_default_source(info, &sources, &default_source, &tx)
+                        });
+                    }
+                    ListResult::Error => error!("Error while receiving sources"),
+                }
+            });
+
+            introspect.get_source_output_info_list({
+                let outputs = data.source_outputs.clone();
+                let tx = tx.clone();
+
+                move |info| source_output::add(info, &outputs, &tx)
+            });
+
+            introspect.get_card_info_list({
+                let cards = data.cards.clone();
+                let tx = tx.clone();
+
+                move |info| card::add(info, &cards, &tx)
+            });
+
+            let subscribe_callback = Box::new({
+                let context = context.clone();
+                let data = data.clone();
+                let tx = tx.clone();
+
+                move |facility, op, i| on_event(&context, &data, &tx, facility, op, i)
+            });
+
+            lock!(context).set_subscribe_callback(Some(subscribe_callback));
+            lock!(context).subscribe(
+                InterestMaskSet::SERVER
+                    | InterestMaskSet::SINK_INPUT
+                    | InterestMaskSet::SINK
+                    | InterestMaskSet::SOURCE_OUTPUT
+                    | InterestMaskSet::SOURCE
+                    | InterestMaskSet::CARD,
+                |_| (),
+            );
+        }
+        State::Failed => error!("Failed to connect to audio server"),
+        State::Terminated => error!("Connection to audio server terminated"),
+        _ => {}
+    }
+}
+
+fn on_event(
+    context: &Arc<Mutex<Context>>,
+    data: &Data,
+    tx: &broadcast::Sender<Event>,
+    facility: Option<Facility>,
+    op: Option<Operation>,
+    i: u32,
+) {
+    let (Some(facility), Some(op)) = (facility, op) else {
+        return;
+    };
+
+    trace!("server event: {facility:?}, op: {op:?}, i: {i}");
+
+    match facility {
+        Facility::Server => on_server_event(context, data, tx),
+        Facility::Sink => sink::on_event(context, &data.sinks, &data.default_sink_name, tx, op, i),
+        Facility::SinkInput => sink_input::on_event(context, &data.sink_inputs, tx, op, i),
+        Facility::Source => {
+            source::on_event(context, &data.sources, &data.default_source_name, tx, op, i);
+        }
+        Facility::SourceOutput => {
+            source_output::on_event(context, &data.source_outputs, tx, op, i);
+        }
+        Facility::Card => card::on_event(context, &data.cards, tx, op, i),
+        _ => error!("Received unhandled facility: {facility:?}"),
+    }
+}
+
+fn on_server_event(context: &Arc<Mutex<Context>>, data: &Data, tx: &broadcast::Sender<Event>) {
+    lock!(context).introspect().get_server_info({
+        let data = data.clone();
+        let tx = tx.clone();
+
+        move |info| {
+            set_default_sink(info, &data.sinks, &data.default_sink_name, &tx);
+            set_default_source(info, &data.sources, &data.default_source_name, &tx);
+        }
+    });
+}
+
+fn set_default_sink(
+    info: &ServerInfo,
+    sinks: &ArcMutVec<Sink>,
+    default_sink: &Arc<Mutex<Option<String>>>,
+    tx: &broadcast::Sender<Event>,
+) {
+    let default_sink_name = info.default_sink_name.as_ref().map(ToString::to_string);
+
+    if default_sink_name != *lock!(default_sink)
+        && let Some(ref default_sink_name) = default_sink_name
+    {
+        if let Some(sink) = lock!(sinks)
+            .iter_mut()
+            .find(|s| s.name.as_str() == default_sink_name.as_str())
+        {
+            sink.active = true;
+            debug!("Set sink active: {}", sink.name);
+            tx.send_expect(Event::UpdateSink(sink.clone()));
+        } else {
+            warn!("Couldn't find sink: {}", default_sink_name);
+        }
+    }
+
+    *lock!(default_sink) = default_sink_name;
+}
+
+fn set_default_source(
+    info: &ServerInfo,
+    sources: &ArcMutVec<Source>,
+    default_source: &Arc<Mutex<Option<String>>>,
+    tx: &broadcast::Sender<Event>,
+) {
+    let default_source_name = info.default_source_name.as_ref().map(ToString::to_string);
+
+    if default_source_name != *lock!(default_source)
+        && let Some(ref default_source_name) = default_source_name
+    {
+        if let Some(source) = lock!(sources)
+            .iter_mut()
+            .find(|s| s.name.as_str() == default_source_name.as_str())
+        {
+            source.active = true;
+            debug!("Set source active: {}", source.name);
+            tx.send_expect(Event::UpdateSource(source.clone()));
+        } else {
+            warn!("Couldn't find source: {}", default_source_name);
+        }
+    }
+
+    *lock!(default_source) = default_source_name;
+}
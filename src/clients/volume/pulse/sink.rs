@@ -1,5 +1,5 @@
-use super::{ArcMutVec, Client, ConnectionState, Event, VolumeLevels};
 use crate::channels::SyncSenderExt;
+use crate::clients::volume::{ArcMutVec, Channel, Event, Sink};
 use crate::lock;
 use libpulse_binding::callbacks::ListResult;
 use libpulse_binding::context::Context;
@@ -8,17 +8,7 @@ use libpulse_binding::context::subscribe::Operation;
 use libpulse_binding::def::SinkState;
 use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast;
-use tracing::{debug, error, instrument, trace};
-
-#[derive(Debug, Clone)]
-pub struct Sink {
-    index: u32,
-    pub name: String,
-    pub description: String,
-    pub volume: VolumeLevels,
-    pub muted: bool,
-    pub active: bool,
-}
+use tracing::{debug, error, trace};
 
 impl From<&SinkInfo<'_>> for Sink {
     fn from(value: &SinkInfo) -> Self {
@@ -36,53 +26,18 @@ impl From<&SinkInfo<'_>> for Sink {
                 .unwrap_or_default(),
             muted: value.mute,
             volume: value.volume.into(),
+            channels: value
+                .channel_map
+                .get()
+                .iter()
+                .copied()
+                .map(Channel::from)
+                .collect(),
             active: value.state == SinkState::Running,
         }
     }
 }
 
-impl Client {
-    #[instrument(level = "trace")]
-    pub fn sinks(&self) -> ArcMutVec<Sink> {
-        self.data.sinks.clone()
-    }
-
-    #[instrument(level = "trace")]
-    pub fn set_default_sink(&self, name: &str) {
-        if let ConnectionState::Connected { context, .. } = &*lock!(self.connection) {
-            lock!(context).set_default_sink(name, |_| {});
-        }
-    }
-
-    #[instrument(level = "trace")]
-    pub fn set_sink_volume(&self, name: &str, volume: f64) {
-        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
-            let Some(mut volume_levels) = ({
-                let sinks = self.sinks();
-                lock!(sinks).iter().find_map(|s| {
-                    if s.name == name {
-                        Some(s.volume.clone())
-                    } else {
-                        None
-                    }
-                })
-            }) else {
-                return;
-            };
-
-            volume_levels.set_percent(volume);
-            introspector.set_sink_volume_by_name(name, &volume_levels.into(), None);
-        }
-    }
-
-    #[instrument(level = "trace")]
-    pub fn set_sink_muted(&self, name: &str, muted: bool) {
-        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
-            introspector.set_sink_mute_by_name(name, muted, None);
-        }
-    }
-}
-
 pub fn on_event(
     context: &Arc<Mutex<Context>>,
     sinks: &ArcMutVec<Sink>,
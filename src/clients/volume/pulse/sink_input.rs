@@ -1,5 +1,5 @@
-use super::{ArcMutVec, Client, ConnectionState, Event, VolumeLevels};
 use crate::channels::SyncSenderExt;
+use crate::clients::volume::{ArcMutVec, Event, SinkInput};
 use crate::lock;
 use libpulse_binding::callbacks::ListResult;
 use libpulse_binding::context::Context;
@@ -7,17 +7,7 @@ use libpulse_binding::context::introspect::SinkInputInfo;
 use libpulse_binding::context::subscribe::Operation;
 use std::sync::{Arc, Mutex};
 use tokio::sync::broadcast;
-use tracing::{debug, error, instrument, trace};
-
-#[derive(Debug, Clone)]
-pub struct SinkInput {
-    pub index: u32,
-    pub name: String,
-    pub volume: VolumeLevels,
-    pub muted: bool,
-
-    pub can_set_volume: bool,
-}
+use tracing::{debug, error, trace};
 
 impl From<&SinkInputInfo<'_>> for SinkInput {
     fn from(value: &SinkInputInfo) -> Self {
@@ -31,41 +21,7 @@ impl From<&SinkInputInfo<'_>> for SinkInput {
             muted: value.mute,
             volume: value.volume.into(),
             can_set_volume: value.has_volume && value.volume_writable,
-        }
-    }
-}
-
-impl Client {
-    #[instrument(level = "trace")]
-    pub fn sink_inputs(&self) -> ArcMutVec<SinkInput> {
-        self.data.sink_inputs.clone()
-    }
-
-    #[instrument(level = "trace")]
-    pub fn set_input_volume(&self, index: u32, volume_percent: f64) {
-        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
-            let Some(mut volume_levels) = ({
-                let inputs = self.sink_inputs();
-                lock!(inputs).iter().find_map(|s| {
-                    if s.index == index {
-                        Some(s.volume.clone())
-                    } else {
-                        None
-                    }
-                })
-            }) else {
-                return;
-            };
-
-            volume_levels.set_percent(volume_percent);
-            introspector.set_sink_input_volume(index, &volume_levels.into(), None);
-        }
-    }
-
-    #[instrument(level = "trace")]
-    pub fn set_input_muted(&self, index: u32, muted: bool) {
-        if let ConnectionState::Connected { introspector, .. } = &mut *lock!(self.connection) {
-            introspector.set_sink_input_mute(index, muted, None);
+            latency: None,
         }
     }
 }
@@ -0,0 +1,142 @@
+use crate::channels::SyncSenderExt;
+use crate::clients::volume::{ArcMutVec, Event, Source};
+use crate::lock;
+use libpulse_binding::callbacks::ListResult;
+use libpulse_binding::context::Context;
+use libpulse_binding::context::introspect::SourceInfo;
+use libpulse_binding::context::subscribe::Operation;
+use libpulse_binding::def::SourceState;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+use tracing::{debug, error, trace};
+
+impl From<&SourceInfo<'_>> for Source {
+    fn from(value: &SourceInfo) -> Self {
+        Self {
+            index: value.index,
+            name: value
+                .name
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            description: value
+                .description
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            muted: value.mute,
+            volume: value.volume.into(),
+            active: value.state == SourceState::Running,
+        }
+    }
+}
+
+/// Checks whether the source is the monitor of a sink,
+/// which are not shown as input devices.
+fn is_monitor(info: &SourceInfo) -> bool {
+    info.monitor_of_sink.is_some()
+}
+
+pub fn on_event(
+    context: &Arc<Mutex<Context>>,
+    sources: &ArcMutVec<Source>,
+    default_source: &Arc<Mutex<Option<String>>>,
+    tx: &broadcast::Sender<Event>,
+    op: Operation,
+    i: u32,
+) {
+    let introspect = lock!(context).introspect();
+
+    match op {
+        Operation::New => {
+            debug!("new source");
+            introspect.get_source_info_by_index(i, {
+                let sources = sources.clone();
+                let tx = tx.clone();
+
+                move |info| add(info, &sources, &tx)
+            });
+        }
+        Operation::Changed => {
+            debug!("source changed");
+            introspect.get_source_info_by_index(i, {
+                let sources = sources.clone();
+                let default_source = default_source.clone();
+                let tx = tx.clone();
+
+                move |info| update(info, &sources, &default_source, &tx)
+            });
+        }
+        Operation::Removed => {
+            debug!("source removed");
+            remove(i, sources, tx);
+        }
+    }
+}
+
+pub fn add(
+    info: ListResult<&SourceInfo>,
+    sources: &ArcMutVec<Source>,
+    tx: &broadcast::Sender<Event>,
+) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    if is_monitor(info) {
+        return;
+    }
+
+    trace!("adding {info:?}");
+
+    lock!(sources).push(info.into());
+    tx.send_expect(Event::AddSource(info.into()));
+}
+
+fn update(
+    info: ListResult<&SourceInfo>,
+    sources: &ArcMutVec<Source>,
+    default_source: &Arc<Mutex<Option<String>>>,
+    tx: &broadcast::Sender<Event>,
+) {
+    let ListResult::Item(info) = info else {
+        return;
+    };
+
+    if is_monitor(info) {
+        return;
+    }
+
+    trace!("updating {info:?}");
+
+    let mut source: Source = info.into();
+
+    if !source.active
+        && let Some(default_source) = &*lock!(default_source)
+    {
+        source.active = &source.name == default_source;
+    }
+
+    {
+        let mut sources = lock!(sources);
+        let Some(pos) = sources.iter().position(|s| s.index == info.index) else {
+            error!("received update to untracked source");
+            return;
+        };
+
+        sources[pos] = source.clone();
+    }
+
+    tx.send_expect(Event::UpdateSource(source));
+}
+
+fn remove(index: u32, sources: &ArcMutVec<Source>, tx: &broadcast::Sender<Event>) {
+    trace!("removing {index}");
+
+    let mut sources = lock!(sources);
+
+    if let Some(pos) = sources.iter().position(|s| s.index == index) {
+        let info = sources.remove(pos);
+        tx.send_expect(Event::RemoveSource(info.name));
+    }
+}
@@ -0,0 +1,424 @@
+//! Captures the contents of individual toplevels (windows)
+//! using the `ext-image-copy-capture` protocol.
+//!
+//! `wlr-screencopy` can only capture whole outputs,
+//! so per-window thumbnails require a compositor supporting
+//! `ext-image-copy-capture` and `ext-foreign-toplevel-image-capture-source`.
+//!
+//! The capture sources are created from `ext-foreign-toplevel-list` handles,
+//! which are matched against the wlr toplevel by app ID and title.
+
+use super::{Client, Environment, Request, Response, ToplevelHandle};
+use crate::lock;
+use smithay_client_toolkit::foreign_toplevel_list::{
+    ForeignToplevelList, ForeignToplevelListHandler,
+};
+use smithay_client_toolkit::globals::GlobalData;
+use smithay_client_toolkit::shm::raw::RawPool;
+use smithay_client_toolkit::shm::{Shm, ShmHandler};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+use tracing::{debug, error, trace};
+use wayland_client::globals::{BindError, GlobalList};
+use wayland_client::protocol::wl_buffer::WlBuffer;
+use wayland_client::protocol::wl_shm::Format;
+use wayland_client::{Connection, Dispatch, QueueHandle, WEnum};
+use wayland_protocols::ext::foreign_toplevel_list::v1::client::ext_foreign_toplevel_handle_v1::ExtForeignToplevelHandleV1;
+use wayland_protocols::ext::image_capture_source::v1::client::ext_foreign_toplevel_image_capture_source_manager_v1::ExtForeignToplevelImageCaptureSourceManagerV1;
+use wayland_protocols::ext::image_capture_source::v1::client::ext_image_capture_source_v1::ExtImageCaptureSourceV1;
+use wayland_protocols::ext::image_copy_capture::v1::client::ext_image_copy_capture_frame_v1::{
+    self, ExtImageCopyCaptureFrameV1,
+};
+use wayland_protocols::ext::image_copy_capture::v1::client::ext_image_copy_capture_manager_v1::{
+    ExtImageCopyCaptureManagerV1, Options,
+};
+use wayland_protocols::ext::image_copy_capture::v1::client::ext_image_copy_capture_session_v1::{
+    self, ExtImageCopyCaptureSessionV1,
+};
+
+/// A single captured frame of a toplevel.
+///
+/// Pixels are stored in `wl_shm` ARGB8888 order with premultiplied alpha,
+/// which is BGRA in memory on little-endian systems.
+#[derive(Debug)]
+pub struct Thumbnail {
+    pub width: u32,
+    pub height: u32,
+    /// The number of bytes between the start of each row.
+    pub stride: u32,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct ToplevelCaptureState {
+    copy_manager: ExtImageCopyCaptureManagerV1,
+    source_manager: ExtForeignToplevelImageCaptureSourceManagerV1,
+    toplevel_list: ForeignToplevelList,
+    shm: Shm,
+}
+
+impl ToplevelCaptureState {
+    pub fn bind(globals: &GlobalList, qh: &QueueHandle<Environment>) -> Result<Self, BindError> {
+        let copy_manager = globals.bind(qh, 1..=1, GlobalData)?;
+        let source_manager = globals.bind(qh, 1..=1, GlobalData)?;
+        let shm = Shm::bind(globals, qh)?;
+
+        // only bound once capturing is known to be supported,
+        // to avoid tracking every toplevel twice for nothing.
+        let toplevel_list = ForeignToplevelList::new(globals, qh);
+
+        debug!("Bound to ext image copy capture globals");
+
+        Ok(Self {
+            copy_manager,
+            source_manager,
+            toplevel_list,
+            shm,
+        })
+    }
+}
+
+/// The state of an in-progress capture,
+/// shared between its session and frame.
+#[derive(Debug, Default)]
+pub struct CaptureData {
+    inner: Mutex<Capture>,
+}
+
+#[derive(Debug, Default)]
+struct Capture {
+    tx: Option<oneshot::Sender<Option<Thumbnail>>>,
+
+    source: Option<ExtImageCaptureSourceV1>,
+    session: Option<ExtImageCopyCaptureSessionV1>,
+
+    width: u32,
+    height: u32,
+    format: Option<Format>,
+
+    pool: Option<RawPool>,
+    buffer: Option<WlBuffer>,
+}
+
+impl Capture {
+    /// Sends the result, if not already sent,
+    /// and destroys all objects used for the capture.
+    fn finish(&mut self, thumbnail: Option<Thumbnail>) {
+        if let Some(tx) = self.tx.take() {
+            let _ = tx.send(thumbnail);
+        }
+
+        if let Some(buffer) = self.buffer.take() {
+            buffer.destroy();
+        }
+        if let Some(session) = self.session.take() {
+            session.destroy();
+        }
+        if let Some(source) = self.source.take() {
+            source.destroy();
+        }
+
+        self.pool = None;
+    }
+}
+
+impl Client {
+    /// Captures a single frame of the toplevel with the provided ID.
+    ///
+    /// Returns `None` if the compositor does not support capturing toplevels,
+    /// or the capture fails.
+    pub async fn toplevel_capture(&self, handle_id: usize) -> Option<Thumbnail> {
+        let (tx, rx) = oneshot::channel();
+
+        match self.send_request(Request::ToplevelCapture(handle_id, tx)) {
+            Response::Ok => (),
+            _ => unreachable!(),
+        }
+
+        rx.await.ok().flatten()
+    }
+}
+
+impl Environment {
+    /// Begins capturing the toplevel with the provided ID.
+    /// The result is sent on `tx` once the frame is ready.
+    pub(super) fn capture_toplevel(&self, id: usize, tx: oneshot::Sender<Option<Thumbnail>>) {
+        let Some(state) = &self.toplevel_capture else {
+            let _ = tx.send(None);
+            return;
+        };
+
+        let Some(handle) = self
+            .handles
+            .iter()
+            .find(|handle| handle.info().is_some_and(|info| info.id == id))
+            .and_then(|handle| find_capture_handle(state, handle))
+        else {
+            debug!("No capture handle found for toplevel {id}");
+            let _ = tx.send(None);
+            return;
+        };
+
+        let qh = &self.queue_handle;
+        let data = Arc::new(CaptureData::default());
+
+        let source = state.source_manager.create_source(&handle, qh, GlobalData);
+        let session =
+            state
+                .copy_manager
+                .create_session(&source, Options::empty(), qh, data.clone());
+
+        let mut capture = lock!(data.inner);
+        capture.tx = Some(tx);
+        capture.source = Some(source);
+        capture.session = Some(session);
+    }
+}
+
+/// Finds the `ext-foreign-toplevel-list` handle
+/// representing the same window as the wlr handle.
+fn find_capture_handle(
+    state: &ToplevelCaptureState,
+    handle: &ToplevelHandle,
+) -> Option<ExtForeignToplevelHandleV1> {
+    let info = handle.info()?;
+
+    state
+        .toplevel_list
+        .toplevels()
+        .iter()
+        .find(|toplevel| {
+            state
+                .toplevel_list
+                .info(toplevel)
+                .is_some_and(|ext| ext.app_id == info.app_id && ext.title == info.title)
+        })
+        .cloned()
+}
+
+impl ShmHandler for Environment {
+    fn shm_state(&mut self) -> &mut Shm {
+        // `wl_shm` is only bound alongside the rest of the capture state,
+        // so no events can arrive without it.
+        &mut self
+            .toplevel_capture
+            .as_mut()
+            .expect("capture state to exist")
+            .shm
+    }
+}
+
+impl ForeignToplevelListHandler for Environment {
+    fn foreign_toplevel_list_state(&mut self) -> &mut ForeignToplevelList {
+        &mut self
+            .toplevel_capture
+            .as_mut()
+            .expect("capture state to exist")
+            .toplevel_list
+    }
+
+    fn new_toplevel(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _handle: ExtForeignToplevelHandleV1,
+    ) {
+        trace!("Capture list received new toplevel");
+    }
+
+    fn update_toplevel(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _handle: ExtForeignToplevelHandleV1,
+    ) {
+    }
+
+    fn toplevel_closed(
+        &mut self,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+        _handle: ExtForeignToplevelHandleV1,
+    ) {
+        trace!("Capture list toplevel closed");
+    }
+}
+
+impl Dispatch<ExtImageCopyCaptureManagerV1, GlobalData> for Environment {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ExtImageCopyCaptureManagerV1,
+        _event: <ExtImageCopyCaptureManagerV1 as wayland_client::Proxy>::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtForeignToplevelImageCaptureSourceManagerV1, GlobalData> for Environment {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ExtForeignToplevelImageCaptureSourceManagerV1,
+        _event: <ExtForeignToplevelImageCaptureSourceManagerV1 as wayland_client::Proxy>::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtImageCaptureSourceV1, GlobalData> for Environment {
+    fn event(
+        _state: &mut Self,
+        _proxy: &ExtImageCaptureSourceV1,
+        _event: <ExtImageCaptureSourceV1 as wayland_client::Proxy>::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlBuffer, GlobalData> for Environment {
+    fn event(
+        _state: &mut Self,
+        _proxy: &WlBuffer,
+        _event: <WlBuffer as wayland_client::Proxy>::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtImageCopyCaptureSessionV1, Arc<CaptureData>> for Environment {
+    fn event(
+        state: &mut Self,
+        session: &ExtImageCopyCaptureSessionV1,
+        event: ext_image_copy_capture_session_v1::Event,
+        data: &Arc<CaptureData>,
+        _conn: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        use ext_image_copy_capture_session_v1::Event;
+
+        trace!("Processing capture session event: {event:?}");
+
+        let mut capture = lock!(data.inner);
+
+        match event {
+            Event::BufferSize { width, height } => {
+                capture.width = width;
+                capture.height = height;
+            }
+            Event::ShmFormat {
+                format: WEnum::Value(format @ (Format::Argb8888 | Format::Xrgb8888)),
+            } => {
+                // prefer keeping the alpha channel where offered
+                if capture.format != Some(Format::Argb8888) {
+                    capture.format = Some(format);
+                }
+            }
+            // the constraints may be re-sent at any time,
+            // but only a single frame is captured per session.
+            Event::Done if capture.pool.is_none() => {
+                let Some(format) = capture.format else {
+                    error!("Compositor offered no supported capture formats");
+                    capture.finish(None);
+                    return;
+                };
+
+                let Some(shm) = state.toplevel_capture.as_ref().map(|state| &state.shm) else {
+                    capture.finish(None);
+                    return;
+                };
+
+                let (width, height) = (capture.width, capture.height);
+                let stride = width * 4;
+
+                let mut pool = match RawPool::new((stride * height) as usize, shm) {
+                    Ok(pool) => pool,
+                    Err(err) => {
+                        error!("Failed to create capture buffer: {err:?}");
+                        capture.finish(None);
+                        return;
+                    }
+                };
+
+                let buffer = pool.create_buffer(
+                    0,
+                    width as i32,
+                    height as i32,
+                    stride as i32,
+                    format,
+                    GlobalData,
+                    qh,
+                );
+
+                let frame = session.create_frame(qh, data.clone());
+                frame.attach_buffer(&buffer);
+                frame.damage_buffer(0, 0, width as i32, height as i32);
+                frame.capture();
+
+                capture.pool = Some(pool);
+                capture.buffer = Some(buffer);
+            }
+            Event::Stopped => capture.finish(None),
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ExtImageCopyCaptureFrameV1, Arc<CaptureData>> for Environment {
+    fn event(
+        _state: &mut Self,
+        frame: &ExtImageCopyCaptureFrameV1,
+        event: ext_image_copy_capture_frame_v1::Event,
+        data: &Arc<CaptureData>,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        use ext_image_copy_capture_frame_v1::Event;
+
+        trace!("Processing capture frame event: {event:?}");
+
+        let mut capture = lock!(data.inner);
+
+        match event {
+            Event::Ready => {
+                let (width, height) = (capture.width, capture.height);
+                let stride = width * 4;
+                let opaque = capture.format == Some(Format::Xrgb8888);
+
+                let thumbnail = capture.pool.as_mut().map(|pool| {
+                    let mut bytes = pool.mmap()[..(stride * height) as usize].to_vec();
+
+                    // the alpha channel of XRGB8888 is undefined
+                    if opaque {
+                        for pixel in bytes.chunks_exact_mut(4) {
+                            pixel[3] = u8::MAX;
+                        }
+                    }
+
+                    Thumbnail {
+                        width,
+                        height,
+                        stride,
+                        bytes,
+                    }
+                });
+
+                frame.destroy();
+                capture.finish(thumbnail);
+            }
+            Event::Failed { reason } => {
+                debug!("Toplevel capture failed: {reason:?}");
+
+                frame.destroy();
+                capture.finish(None);
+            }
+            _ => {}
+        }
+    }
+}
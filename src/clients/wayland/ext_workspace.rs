@@ -0,0 +1,471 @@
+//! Tracks and activates workspaces
+//! using the `ext-workspace` protocol.
+//!
+//! This is used by the workspaces module on compositors
+//! without a dedicated IPC client, such as COSMIC.
+//!
+//! The protocol has no numeric workspace IDs,
+//! so each handle is assigned one as it is announced.
+//! Changes are applied atomically on the manager's `done` event,
+//! where they are diffed against the previous state
+//! to produce workspace updates.
+
+use super::{Client, Environment, Event, Request, Response};
+use crate::channels::{AsyncSenderExt, SyncSenderExt};
+use crate::clients::compositor::{Visibility, Workspace, WorkspaceUpdate};
+use smithay_client_toolkit::globals::GlobalData;
+use tokio::sync::broadcast;
+use tracing::{debug, trace};
+use wayland_client::globals::{BindError, GlobalList};
+use wayland_client::protocol::wl_output::WlOutput;
+use wayland_client::{Connection, Dispatch, QueueHandle, WEnum, event_created_child};
+use wayland_protocols::ext::workspace::v1::client::ext_workspace_group_handle_v1::{
+    self, ExtWorkspaceGroupHandleV1,
+};
+use wayland_protocols::ext::workspace::v1::client::ext_workspace_handle_v1::{
+    self, ExtWorkspaceHandleV1, State,
+};
+use wayland_protocols::ext::workspace::v1::client::ext_workspace_manager_v1::{
+    self, ExtWorkspaceManagerV1,
+};
+
+#[derive(Debug)]
+pub struct WorkspaceManagerState {
+    manager: ExtWorkspaceManagerV1,
+
+    groups: Vec<Group>,
+    workspaces: Vec<WorkspaceEntry>,
+
+    next_id: i64,
+    /// The ID of the most recently activated workspace.
+    focused: Option<i64>,
+}
+
+/// A workspace group,
+/// which holds workspaces shown on the same set of outputs.
+#[derive(Debug)]
+struct Group {
+    handle: ExtWorkspaceGroupHandleV1,
+    outputs: Vec<WlOutput>,
+}
+
+#[derive(Debug)]
+struct WorkspaceEntry {
+    handle: ExtWorkspaceHandleV1,
+    id: i64,
+
+    /// Properties received since the last `done` event.
+    pending: Properties,
+    /// Properties as of the last `done` event,
+    /// or `None` if the workspace has not been announced yet.
+    current: Option<Properties>,
+
+    removed: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Properties {
+    name: String,
+    coordinates: Vec<u32>,
+    active: bool,
+    urgent: bool,
+    hidden: bool,
+    group: Option<ExtWorkspaceGroupHandleV1>,
+}
+
+impl WorkspaceManagerState {
+    pub fn bind(globals: &GlobalList, qh: &QueueHandle<Environment>) -> Result<Self, BindError> {
+        let manager = globals.bind(qh, 1..=1, GlobalData)?;
+
+        debug!("Bound to ext workspace manager");
+
+        Ok(Self {
+            manager,
+            groups: vec![],
+            workspaces: vec![],
+            next_id: 0,
+            focused: None,
+        })
+    }
+
+    fn entry_mut(&mut self, handle: &ExtWorkspaceHandleV1) -> Option<&mut WorkspaceEntry> {
+        self.workspaces
+            .iter_mut()
+            .find(|entry| &entry.handle == handle)
+    }
+}
+
+impl Client {
+    /// Gets the information for all current workspaces.
+    pub fn workspace_info_all(&self) -> Vec<Workspace> {
+        match self.send_request(Request::WorkspaceInfoAll) {
+            Response::WorkspaceInfoAll(workspaces) => workspaces,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Activates the workspace with the provided ID.
+    pub fn workspace_activate(&self, id: i64) {
+        match self.send_request(Request::WorkspaceActivate(id)) {
+            Response::Ok => (),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Subscribes to workspace updates.
+    /// This re-sends the current workspaces to all subscribers.
+    pub fn subscribe_workspaces(&self) -> broadcast::Receiver<WorkspaceUpdate> {
+        let rx = self.workspace_channel.0.subscribe();
+
+        let workspaces = self.workspace_info_all();
+        if !workspaces.is_empty() {
+            self.workspace_channel
+                .0
+                .send_expect(WorkspaceUpdate::Init(workspaces));
+        }
+
+        rx
+    }
+}
+
+impl Environment {
+    /// Gets the information for all announced workspaces
+    /// which are not hidden.
+    pub(super) fn workspace_info_all(&self) -> Vec<Workspace> {
+        let Some(state) = &self.workspaces else {
+            return vec![];
+        };
+
+        state
+            .workspaces
+            .iter()
+            .filter_map(|entry| {
+                let props = entry.current.as_ref()?;
+                (!props.hidden).then(|| self.workspace_info(state, entry.id, props))
+            })
+            .collect()
+    }
+
+    pub(super) fn workspace_activate(&self, id: i64) {
+        let Some(state) = &self.workspaces else {
+            return;
+        };
+
+        if let Some(entry) = state.workspaces.iter().find(|entry| entry.id == id) {
+            entry.handle.activate();
+            state.manager.commit();
+        }
+    }
+
+    fn workspace_info(
+        &self,
+        state: &WorkspaceManagerState,
+        id: i64,
+        props: &Properties,
+    ) -> Workspace {
+        // workspaces on multiple outputs are shown on the first
+        let monitor = props
+            .group
+            .as_ref()
+            .and_then(|handle| state.groups.iter().find(|group| &group.handle == handle))
+            .and_then(|group| group.outputs.first())
+            .and_then(|output| self.output_state.info(output))
+            .and_then(|info| info.name)
+            .unwrap_or_default();
+
+        let index = props
+            .coordinates
+            .first()
+            .map_or(id, |&coordinate| i64::from(coordinate) + 1);
+
+        let name = if props.name.is_empty() {
+            index.to_string()
+        } else {
+            props.name.clone()
+        };
+
+        let visibility = if state.focused == Some(id) {
+            Visibility::focused()
+        } else if props.active {
+            Visibility::visible()
+        } else {
+            Visibility::Hidden
+        };
+
+        Workspace {
+            id,
+            index,
+            name,
+            monitor,
+            visibility,
+        }
+    }
+
+    /// Applies pending changes,
+    /// sending an update for each difference from the previous state.
+    fn apply_workspace_changes(&mut self) {
+        let Some(mut state) = self.workspaces.take() else {
+            return;
+        };
+
+        let mut updates = vec![];
+
+        let old_focused = state.focused;
+
+        let was_active =
+            |entry: &WorkspaceEntry| entry.current.as_ref().is_some_and(|props| props.active);
+        let mut active = state
+            .workspaces
+            .iter()
+            .filter(|entry| !entry.removed && entry.pending.active && !entry.pending.hidden);
+
+        // activating a workspace deactivates others on the same output,
+        // so the newly active workspace is the focused one
+        let newly_active = active
+            .clone()
+            .find(|entry| !was_active(entry))
+            .or_else(|| active.next().filter(|_| old_focused.is_none()))
+            .map(|entry| entry.id);
+
+        if newly_active.is_some() {
+            state.focused = newly_active;
+        }
+
+        for entry in &state.workspaces {
+            if entry.removed {
+                if entry.current.as_ref().is_some_and(|props| !props.hidden) {
+                    updates.push(WorkspaceUpdate::Remove(entry.id));
+                }
+                continue;
+            }
+
+            let new = &entry.pending;
+            let info = || self.workspace_info(&state, entry.id, new);
+
+            match &entry.current {
+                None if !new.hidden => updates.push(WorkspaceUpdate::Add(info())),
+                None => {}
+                Some(old) if old.hidden != new.hidden => {
+                    if new.hidden {
+                        updates.push(WorkspaceUpdate::Remove(entry.id));
+                    } else {
+                        updates.push(WorkspaceUpdate::Add(info()));
+                    }
+                }
+                Some(_) if new.hidden => {}
+                Some(old) => {
+                    if old.name != new.name {
+                        updates.push(WorkspaceUpdate::Rename {
+                            id: entry.id,
+                            name: info().name,
+                        });
+                    }
+
+                    if old.group != new.group || old.coordinates != new.coordinates {
+                        updates.push(WorkspaceUpdate::Move(info()));
+                    }
+
+                    if old.urgent != new.urgent {
+                        updates.push(WorkspaceUpdate::Urgent {
+                            id: entry.id,
+                            urgent: new.urgent,
+                        });
+                    }
+                }
+            }
+        }
+
+        if let Some(id) = newly_active
+            && old_focused != newly_active
+            && let Some(entry) = state.workspaces.iter().find(|entry| entry.id == id)
+        {
+            let old = old_focused.and_then(|old_id| {
+                state
+                    .workspaces
+                    .iter()
+                    .find(|entry| entry.id == old_id && !entry.removed)
+                    .map(|entry| self.workspace_info(&state, old_id, &entry.pending))
+            });
+
+            updates.push(WorkspaceUpdate::Focus {
+                old,
+                new: self.workspace_info(&state, id, &entry.pending),
+            });
+        }
+
+        state.workspaces.retain(|entry| {
+            if entry.removed {
+                entry.handle.destroy();
+            }
+            !entry.removed
+        });
+
+        for entry in &mut state.workspaces {
+            entry.current = Some(entry.pending.clone());
+        }
+
+        if state
+            .focused
+            .is_some_and(|id| !state.workspaces.iter().any(|entry| entry.id == id))
+        {
+            state.focused = None;
+        }
+
+        self.workspaces = Some(state);
+
+        for update in updates {
+            trace!("Sending workspace update: {update:?}");
+            self.event_tx.send_spawn(Event::Workspace(update));
+        }
+    }
+}
+
+impl Dispatch<ExtWorkspaceManagerV1, GlobalData> for Environment {
+    event_created_child!(Environment, ExtWorkspaceManagerV1, [
+        ext_workspace_manager_v1::EVT_WORKSPACE_GROUP_OPCODE => (ExtWorkspaceGroupHandleV1, GlobalData),
+        ext_workspace_manager_v1::EVT_WORKSPACE_OPCODE => (ExtWorkspaceHandleV1, GlobalData)
+    ]);
+
+    fn event(
+        state: &mut Self,
+        _proxy: &ExtWorkspaceManagerV1,
+        event: ext_workspace_manager_v1::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        use ext_workspace_manager_v1::Event;
+
+        trace!("Processing workspace manager event: {event:?}");
+
+        match event {
+            Event::WorkspaceGroup { workspace_group } => {
+                if let Some(workspaces) = &mut state.workspaces {
+                    workspaces.groups.push(Group {
+                        handle: workspace_group,
+                        outputs: vec![],
+                    });
+                }
+            }
+            Event::Workspace { workspace } => {
+                if let Some(workspaces) = &mut state.workspaces {
+                    let id = workspaces.next_id;
+                    workspaces.next_id += 1;
+
+                    workspaces.workspaces.push(WorkspaceEntry {
+                        handle: workspace,
+                        id,
+                        pending: Properties::default(),
+                        current: None,
+                        removed: false,
+                    });
+                }
+            }
+            Event::Done => state.apply_workspace_changes(),
+            Event::Finished => {
+                debug!("Workspace manager finished");
+                state.workspaces = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ExtWorkspaceGroupHandleV1, GlobalData> for Environment {
+    fn event(
+        state: &mut Self,
+        handle: &ExtWorkspaceGroupHandleV1,
+        event: ext_workspace_group_handle_v1::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        use ext_workspace_group_handle_v1::Event;
+
+        trace!("Processing workspace group event: {event:?}");
+
+        let Some(workspaces) = &mut state.workspaces else {
+            return;
+        };
+
+        match event {
+            Event::OutputEnter { output } => {
+                if let Some(group) = workspaces
+                    .groups
+                    .iter_mut()
+                    .find(|group| &group.handle == handle)
+                {
+                    group.outputs.push(output);
+                }
+            }
+            Event::OutputLeave { output } => {
+                if let Some(group) = workspaces
+                    .groups
+                    .iter_mut()
+                    .find(|group| &group.handle == handle)
+                {
+                    group.outputs.retain(|o| o != &output);
+                }
+            }
+            Event::WorkspaceEnter { workspace } => {
+                if let Some(entry) = workspaces.entry_mut(&workspace) {
+                    entry.pending.group = Some(handle.clone());
+                }
+            }
+            Event::WorkspaceLeave { workspace } => {
+                if let Some(entry) = workspaces.entry_mut(&workspace)
+                    && entry.pending.group.as_ref() == Some(handle)
+                {
+                    entry.pending.group = None;
+                }
+            }
+            Event::Removed => {
+                workspaces.groups.retain(|group| &group.handle != handle);
+                handle.destroy();
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ExtWorkspaceHandleV1, GlobalData> for Environment {
+    fn event(
+        state: &mut Self,
+        handle: &ExtWorkspaceHandleV1,
+        event: ext_workspace_handle_v1::Event,
+        _data: &GlobalData,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        use ext_workspace_handle_v1::Event;
+
+        trace!("Processing workspace event: {event:?}");
+
+        let Some(entry) = state
+            .workspaces
+            .as_mut()
+            .and_then(|workspaces| workspaces.entry_mut(handle))
+        else {
+            return;
+        };
+
+        match event {
+            Event::Name { name } => entry.pending.name = name,
+            Event::Coordinates { coordinates } => {
+                entry.pending.coordinates = coordinates
+                    .chunks_exact(4)
+                    .map(|bytes| u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+                    .collect();
+            }
+            Event::State {
+                state: WEnum::Value(flags),
+            } => {
+                entry.pending.active = flags.contains(State::Active);
+                entry.pending.urgent = flags.contains(State::Urgent);
+                entry.pending.hidden = flags.contains(State::Hidden);
+            }
+            Event::Removed => entry.removed = true,
+            _ => {}
+        }
+    }
+}
@@ -28,7 +28,7 @@ use wayland_client::{Connection, QueueHandle};
 pub use wl_output::{OutputEvent, OutputEventType};
 
 cfg_if! {
-    if #[cfg(any(feature = "focused", feature = "launcher"))] {
+    if #[cfg(feature = "toplevel")] {
         mod wlr_foreign_toplevel;
         use crate::{delegate_foreign_toplevel_handle, delegate_foreign_toplevel_manager};
         use wlr_foreign_toplevel::manager::ToplevelManagerState;
@@ -37,6 +37,27 @@ cfg_if! {
     }
 }
 
+cfg_if! {
+    if #[cfg(feature = "taskbar")] {
+        mod ext_image_copy_capture;
+
+        use self::ext_image_copy_capture::ToplevelCaptureState;
+        use smithay_client_toolkit::{delegate_foreign_toplevel_list, delegate_shm};
+        use tokio::sync::oneshot;
+
+        pub use ext_image_copy_capture::Thumbnail;
+    }
+}
+
+cfg_if! {
+    if #[cfg(feature = "workspaces+ext")] {
+        mod ext_workspace;
+
+        use self::ext_workspace::WorkspaceManagerState;
+        use crate::clients::compositor::{Compositor, Workspace, WorkspaceUpdate};
+    }
+}
+
 cfg_if! {
     if #[cfg(feature = "clipboard")] {
         mod wlr_data_control;
@@ -60,8 +81,10 @@ cfg_if! {
 #[derive(Debug)]
 pub enum Event {
     Output(OutputEvent),
-    #[cfg(any(feature = "focused", feature = "launcher"))]
+    #[cfg(feature = "toplevel")]
     Toplevel(ToplevelEvent),
+    #[cfg(feature = "workspaces+ext")]
+    Workspace(WorkspaceUpdate),
     #[cfg(feature = "clipboard")]
     Clipboard(ClipboardItem),
 }
@@ -72,12 +95,21 @@ pub enum Request {
 
     OutputInfoAll,
 
-    #[cfg(any(feature = "focused", feature = "launcher"))]
+    #[cfg(feature = "toplevel")]
     ToplevelInfoAll,
-    #[cfg(feature = "launcher")]
+    #[cfg(any(feature = "launcher", feature = "taskbar"))]
     ToplevelFocus(usize),
     #[cfg(feature = "launcher")]
     ToplevelMinimize(usize),
+    #[cfg(feature = "taskbar")]
+    ToplevelClose(usize),
+    #[cfg(feature = "taskbar")]
+    ToplevelCapture(usize, oneshot::Sender<Option<Thumbnail>>),
+
+    #[cfg(feature = "workspaces+ext")]
+    WorkspaceInfoAll,
+    #[cfg(feature = "workspaces+ext")]
+    WorkspaceActivate(i64),
 
     #[cfg(feature = "clipboard")]
     CopyToClipboard(ClipboardItem),
@@ -92,9 +124,12 @@ pub enum Response {
 
     OutputInfoAll(Vec<smithay_client_toolkit::output::OutputInfo>),
 
-    #[cfg(any(feature = "focused", feature = "launcher"))]
+    #[cfg(feature = "toplevel")]
     ToplevelInfoAll(Vec<ToplevelInfo>),
 
+    #[cfg(feature = "workspaces+ext")]
+    WorkspaceInfoAll(Vec<Workspace>),
+
     #[cfg(feature = "clipboard")]
     ClipboardItem(Option<ClipboardItem>),
 }
@@ -129,8 +164,10 @@ pub struct Client {
     rx: Arc<Mutex<std::sync::mpsc::Receiver<Response>>>,
 
     output_channel: BroadcastChannel<OutputEvent>,
-    #[cfg(any(feature = "focused", feature = "launcher"))]
+    #[cfg(feature = "toplevel")]
     toplevel_channel: BroadcastChannel<ToplevelEvent>,
+    #[cfg(feature = "workspaces+ext")]
+    workspace_channel: BroadcastChannel<WorkspaceUpdate>,
     #[cfg(feature = "clipboard")]
     clipboard_channel: BroadcastChannel<ClipboardItem>,
 }
@@ -143,8 +180,10 @@ impl Client {
         let (response_tx, response_rx) = std::sync::mpsc::channel();
 
         let output_channel = broadcast::channel(32);
-        #[cfg(any(feature = "focused", feature = "launcher"))]
+        #[cfg(feature = "toplevel")]
         let toplevel_channel = broadcast::channel(32);
+        #[cfg(feature = "workspaces+ext")]
+        let workspace_channel = broadcast::channel(32);
 
         #[cfg(feature = "clipboard")]
         let clipboard_channel = broadcast::channel(32);
@@ -156,8 +195,10 @@ impl Client {
         // listen to events
         {
             let output_tx = output_channel.0.clone();
-            #[cfg(any(feature = "focused", feature = "launcher"))]
+            #[cfg(feature = "toplevel")]
             let toplevel_tx = toplevel_channel.0.clone();
+            #[cfg(feature = "workspaces+ext")]
+            let workspace_tx = workspace_channel.0.clone();
 
             #[cfg(feature = "clipboard")]
             let clipboard_tx = clipboard_channel.0.clone();
@@ -166,8 +207,10 @@ impl Client {
                 while let Some(event) = event_rx.recv().await {
                     match event {
                         Event::Output(event) => output_tx.send_expect(event),
-                        #[cfg(any(feature = "focused", feature = "launcher"))]
+                        #[cfg(feature = "toplevel")]
                         Event::Toplevel(event) => toplevel_tx.send_expect(event),
+                        #[cfg(feature = "workspaces+ext")]
+                        Event::Workspace(update) => workspace_tx.send_expect(update),
                         #[cfg(feature = "clipboard")]
                         Event::Clipboard(item) => clipboard_tx.send_expect(item),
                     }
@@ -180,8 +223,10 @@ impl Client {
             rx: arc_mut!(response_rx),
 
             output_channel: output_channel.into(),
-            #[cfg(any(feature = "focused", feature = "launcher"))]
+            #[cfg(feature = "toplevel")]
             toplevel_channel: toplevel_channel.into(),
+            #[cfg(feature = "workspaces+ext")]
+            workspace_channel: workspace_channel.into(),
             #[cfg(feature = "clipboard")]
             clipboard_channel: clipboard_channel.into(),
         }
@@ -213,9 +258,17 @@ pub struct Environment {
     response_tx: std::sync::mpsc::Sender<Response>,
 
     // local state
-    #[cfg(any(feature = "focused", feature = "launcher"))]
+    #[cfg(feature = "toplevel")]
     handles: Vec<ToplevelHandle>,
 
+    // -- taskbar --
+    #[cfg(feature = "taskbar")]
+    toplevel_capture: Option<ToplevelCaptureState>,
+
+    // -- workspaces --
+    #[cfg(feature = "workspaces+ext")]
+    workspaces: Option<WorkspaceManagerState>,
+
     // -- clipboard --
     #[cfg(feature = "clipboard")]
     data_control_device_manager_state: Option<DataControlDeviceManagerState>,
@@ -236,12 +289,19 @@ delegate_output!(Environment);
 delegate_seat!(Environment);
 
 cfg_if! {
-    if #[cfg(any(feature = "focused", feature = "launcher"))] {
+    if #[cfg(feature = "toplevel")] {
         delegate_foreign_toplevel_manager!(Environment);
         delegate_foreign_toplevel_handle!(Environment);
     }
 }
 
+cfg_if! {
+    if #[cfg(feature = "taskbar")] {
+        delegate_foreign_toplevel_list!(Environment);
+        delegate_shm!(Environment);
+    }
+}
+
 cfg_if! {
     if #[cfg(feature = "clipboard")] {
         delegate_data_control_device_manager!(Environment);
@@ -276,18 +336,56 @@ impl Environment {
 
         let output_state = OutputState::new(&globals, &qh);
         let seat_state = SeatState::new(&globals, &qh);
-        #[cfg(any(feature = "focused", feature = "launcher"))]
+        #[cfg(feature = "toplevel")]
         if let Err(error) = ToplevelManagerState::bind(&globals, &qh) {
             error!(
                 "{}",
                 Error::UnsupportedProtocol {
                     error,
                     name: "wlr_foreign_toplevel_manager",
-                    modules: &["launcher", "focused"]
+                    modules: &["launcher", "focused", "taskbar"]
                 }
             );
         }
 
+        #[cfg(feature = "taskbar")]
+        let toplevel_capture = match ToplevelCaptureState::bind(&globals, &qh) {
+            Ok(state) => Some(state),
+            Err(error) => {
+                error!(
+                    "{}",
+                    Error::UnsupportedProtocol {
+                        error,
+                        name: "ext_image_copy_capture_manager",
+                        modules: &["taskbar (thumbnails)"]
+                    }
+                );
+                None
+            }
+        };
+
+        // compositors with their own client do not need the protocol,
+        // so it is only bound as a fallback
+        #[cfg(feature = "workspaces+ext")]
+        let workspaces = match Compositor::get_current() {
+            Compositor::Unsupported => match WorkspaceManagerState::bind(&globals, &qh) {
+                Ok(state) => Some(state),
+                Err(error) => {
+                    error!(
+                        "{}",
+                        Error::UnsupportedProtocol {
+                            error,
+                            name: "ext_workspace_manager",
+                            modules: &["workspaces"]
+                        }
+                    );
+                    None
+                }
+            },
+            #[allow(unreachable_patterns)]
+            _ => None,
+        };
+
         #[cfg(feature = "clipboard")]
         let data_control_device_manager_state =
             match DataControlDeviceManagerState::bind(&globals, &qh) {
@@ -314,8 +412,12 @@ impl Environment {
             queue_handle: qh,
             event_tx,
             response_tx,
-            #[cfg(any(feature = "focused", feature = "launcher"))]
+            #[cfg(feature = "toplevel")]
             handles: vec![],
+            #[cfg(feature = "taskbar")]
+            toplevel_capture,
+            #[cfg(feature = "workspaces+ext")]
+            workspaces,
 
             #[cfg(feature = "clipboard")]
             data_control_devices: vec![],
@@ -352,18 +454,18 @@ impl Environment {
                 let infos = env.output_info_all();
                 env.response_tx.send_expect(Response::OutputInfoAll(infos));
             }
-            #[cfg(any(feature = "focused", feature = "launcher"))]
+            #[cfg(feature = "toplevel")]
             Msg(Request::ToplevelInfoAll) => {
                 let infos = env
                     .handles
                     .iter()
-                    .filter_map(ToplevelHandle::info)
+                    .filter_map(|handle| env.toplevel_info(handle))
                     .collect();
 
                 env.response_tx
                     .send_expect(Response::ToplevelInfoAll(infos));
             }
-            #[cfg(feature = "launcher")]
+            #[cfg(any(feature = "launcher", feature = "taskbar"))]
             Msg(Request::ToplevelFocus(id)) => {
                 let handle = env
                     .handles
@@ -390,6 +492,35 @@ impl Environment {
 
                 env.response_tx.send_expect(Response::Ok);
             }
+            #[cfg(feature = "taskbar")]
+            Msg(Request::ToplevelClose(id)) => {
+                let handle = env
+                    .handles
+                    .iter()
+                    .find(|handle| handle.info().is_some_and(|info| info.id == id));
+
+                if let Some(handle) = handle {
+                    handle.close();
+                }
+
+                env.response_tx.send_expect(Response::Ok);
+            }
+            #[cfg(feature = "taskbar")]
+            Msg(Request::ToplevelCapture(id, tx)) => {
+                env.capture_toplevel(id, tx);
+                env.response_tx.send_expect(Response::Ok);
+            }
+            #[cfg(feature = "workspaces+ext")]
+            Msg(Request::WorkspaceInfoAll) => {
+                let workspaces = env.workspace_info_all();
+                env.response_tx
+                    .send_expect(Response::WorkspaceInfoAll(workspaces));
+            }
+            #[cfg(feature = "workspaces+ext")]
+            Msg(Request::WorkspaceActivate(id)) => {
+                env.workspace_activate(id);
+                env.response_tx.send_expect(Response::Ok);
+            }
             #[cfg(feature = "clipboard")]
             Msg(Request::CopyToClipboard(item)) => {
                 env.copy_to_clipboard(item);
@@ -42,6 +42,33 @@ pub struct ClipboardItem {
     pub id: usize,
     pub value: Arc<ClipboardValue>,
     pub mime_type: Arc<str>,
+    /// Every mime type offered by the source,
+    /// including hints such as `x-kde-passwordManagerHint`.
+    pub offered_mime_types: Arc<[String]>,
+}
+
+impl ClipboardItem {
+    /// Creates a new item from a raw value,
+    /// if the mime type is supported.
+    pub fn from_bytes(mime_type: &str, bytes: &[u8]) -> Option<Self> {
+        let mime_type = MimeType::parse(mime_type)?;
+
+        Some(Self {
+            id: Ironbar::unique_id(),
+            value: Arc::new(mime_type.category.value(bytes)),
+            offered_mime_types: Arc::new([mime_type.value.clone()]),
+            mime_type: mime_type.value.into(),
+        })
+    }
+
+    /// Gets the raw value, as sent to other applications.
+    pub fn bytes(&self) -> Option<&[u8]> {
+        match self.value.as_ref() {
+            ClipboardValue::Text(text) => Some(text.as_bytes()),
+            ClipboardValue::Image(bytes) => Some(bytes),
+            ClipboardValue::Other => None,
+        }
+    }
 }
 
 impl PartialEq<Self> for ClipboardItem {
@@ -85,6 +112,21 @@ enum MimeTypeCategory {
     Image,
 }
 
+impl MimeTypeCategory {
+    fn value(&self, buf: &[u8]) -> ClipboardValue {
+        match self {
+            Self::Text => {
+                let txt = String::from_utf8_lossy(buf).to_string();
+                ClipboardValue::Text(txt)
+            }
+            Self::Image => {
+                let bytes = Bytes::from(buf);
+                ClipboardValue::Image(bytes)
+            }
+        }
+    }
+}
+
 impl MimeType {
     fn parse(mime_type: &str) -> Option<Self> {
         match mime_type.to_lowercase().as_str() {
@@ -173,26 +215,19 @@ impl Environment {
     /// Reads an offer file handle into a new `ClipboardItem`.
     async fn read_file(
         mime_type: &MimeType,
+        offered_mime_types: Arc<[String]>,
         file: &mut tokio::net::unix::pipe::Receiver,
     ) -> io::Result<ClipboardItem> {
         let mut buf = vec![];
         file.read_to_end(&mut buf).await?;
 
-        let value = match mime_type.category {
-            MimeTypeCategory::Text => {
-                let txt = String::from_utf8_lossy(&buf).to_string();
-                ClipboardValue::Text(txt)
-            }
-            MimeTypeCategory::Image => {
-                let bytes = Bytes::from(&buf);
-                ClipboardValue::Image(bytes)
-            }
-        };
+        let value = mime_type.category.value(&buf);
 
         Ok(ClipboardItem {
             id: Ironbar::unique_id(),
             value: Arc::new(value),
             mime_type: mime_type.value.clone().into(),
+            offered_mime_types,
         })
     }
 }
@@ -223,6 +258,7 @@ impl DataControlDeviceHandler for Environment {
                     id: usize::MAX,
                     mime_type: String::new().into(),
                     value: Arc::new(ClipboardValue::Other),
+                    offered_mime_types: mime_types.into(),
                 }));
 
                 return;
@@ -232,9 +268,10 @@ impl DataControlDeviceHandler for Environment {
             if let Ok(mut read_pipe) = offer.receive(mime_type.value.clone()) {
                 let tx = self.event_tx.clone();
                 let clipboard = self.clipboard.clone();
+                let mime_types = mime_types.into();
 
                 spawn(async move {
-                    match Self::read_file(&mime_type, &mut read_pipe).await {
+                    match Self::read_file(&mime_type, mime_types, &mut read_pipe).await {
                         Ok(item) => {
                             lock!(clipboard).replace(item.clone());
                             tx.send_spawn(Event::Clipboard(item));
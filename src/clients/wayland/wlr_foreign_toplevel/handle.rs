@@ -29,6 +29,12 @@ impl ToplevelHandle {
         data.info()
     }
 
+    /// Gets the output the toplevel is currently on, if any.
+    pub fn output(&self) -> Option<WlOutput> {
+        let data = self.handle.data::<ToplevelHandleData>()?;
+        lock!(data.inner).output.clone()
+    }
+
     pub fn focus(&self, seat: &WlSeat) {
         trace!("Activating handle");
         self.handle.activate(seat);
@@ -38,6 +44,11 @@ impl ToplevelHandle {
         trace!("Minimizing handle");
         self.handle.set_minimized();
     }
+
+    pub fn close(&self) {
+        trace!("Closing handle");
+        self.handle.close();
+    }
 }
 
 #[derive(Debug, Default)]
@@ -62,12 +73,15 @@ pub struct ToplevelHandleDataInner {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "ipc", derive(serde::Serialize))]
 pub struct ToplevelInfo {
     pub id: usize,
     pub app_id: String,
     pub title: String,
     pub fullscreen: bool,
     pub focused: bool,
+    /// Name of the output the toplevel is on.
+    pub output: Option<String>,
 }
 
 impl Default for ToplevelInfo {
@@ -78,6 +92,7 @@ impl Default for ToplevelInfo {
             title: String::new(),
             fullscreen: false,
             focused: false,
+            output: None,
         }
     }
 }
@@ -28,7 +28,7 @@ impl Client {
     }
 
     /// Focuses the toplevel with the provided ID.
-    #[cfg(feature = "launcher")]
+    #[cfg(any(feature = "launcher", feature = "taskbar"))]
     pub fn toplevel_focus(&self, handle_id: usize) {
         match self.send_request(Request::ToplevelFocus(handle_id)) {
             Response::Ok => (),
@@ -45,12 +45,36 @@ impl Client {
         }
     }
 
+    /// Requests the toplevel with the provided ID closes.
+    #[cfg(feature = "taskbar")]
+    pub fn toplevel_close(&self, handle_id: usize) {
+        match self.send_request(Request::ToplevelClose(handle_id)) {
+            Response::Ok => (),
+            _ => unreachable!(),
+        }
+    }
+
     /// Subscribes to events from toplevels.
     pub fn subscribe_toplevels(&self) -> broadcast::Receiver<ToplevelEvent> {
         self.toplevel_channel.0.subscribe()
     }
 }
 
+impl Environment {
+    /// Gets the information for a toplevel,
+    /// including the name of the output it is on.
+    pub(super) fn toplevel_info(&self, handle: &ToplevelHandle) -> Option<ToplevelInfo> {
+        let mut info = handle.info()?;
+
+        info.output = handle
+            .output()
+            .and_then(|output| self.output_state.info(&output))
+            .and_then(|output| output.name);
+
+        Some(info)
+    }
+}
+
 impl ToplevelManagerHandler for Environment {
     fn toplevel(&mut self, _conn: &Connection, _qh: &QueueHandle<Self>) {
         debug!("Manager received new handle");
@@ -70,7 +94,7 @@ impl ToplevelHandleHandler for Environment {
 
                 trace!("Adding new handle: {info:?}");
                 self.handles.push(handle.clone());
-                if let Some(info) = handle.info() {
+                if let Some(info) = self.toplevel_info(&handle) {
                     self.event_tx
                         .send_spawn(Event::Toplevel(ToplevelEvent::New(info)));
                 }
@@ -92,7 +116,7 @@ impl ToplevelHandleHandler for Environment {
         match handle.info() {
             Some(info) => {
                 trace!("Updating handle: {info:?}");
-                if let Some(info) = handle.info() {
+                if let Some(info) = self.toplevel_info(&handle) {
                     self.event_tx
                         .send_spawn(Event::Toplevel(ToplevelEvent::Update(info)));
                 }
@@ -112,7 +136,7 @@ impl ToplevelHandleHandler for Environment {
         debug!("Handler received handle close");
 
         self.handles.retain(|h| h != &handle);
-        if let Some(info) = handle.info() {
+        if let Some(info) = self.toplevel_info(&handle) {
             self.event_tx
                 .send_spawn(Event::Toplevel(ToplevelEvent::Remove(info)));
         }
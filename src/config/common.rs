@@ -1,14 +1,19 @@
-use crate::dynamic_value::{DynamicBool, dynamic_string};
+use crate::Ironbar;
+use crate::config::{BarPosition, PopupKeyboardMode};
+use crate::dynamic_value::{DynamicBool, VisibilityRule, dynamic_string};
 use crate::gtk_helpers::{IronbarGtkExt, MouseButton};
+use crate::popup::PopupLayout;
 use crate::script::{Script, ScriptInput};
 use glib::Propagation;
 use gtk::prelude::*;
 use gtk::{
     EventControllerMotion, EventControllerScroll, EventControllerScrollFlags, Justification,
-    Orientation, Revealer, RevealerTransitionType, Widget,
+    Orientation, PropagationPhase, Revealer, RevealerTransitionType, Widget,
 };
 use serde::Deserialize;
 use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Duration;
 use tracing::trace;
 
 /// The following are module-level options which are present on **all** modules.
@@ -45,20 +50,83 @@ pub struct CommonConfig {
     ///
     /// Note that full dynamic string support is not currently supported.
     ///
+    /// `tooltip_format` is accepted as an alias.
+    ///
     /// **Default**: `null`
+    #[serde(alias = "tooltip_format")]
     pub tooltip: Option<String>,
 
+    /// The name read out for the module by screen readers
+    /// and other assistive technologies.
+    /// Supports embedding scripts between `{{double braces}}`.
+    ///
+    /// Modules which show a value, such as volume or battery,
+    /// expose the current value separately to this.
+    ///
+    /// **Default**: `null`
+    pub accessible_label: Option<String>,
+
+    /// The name of the GTK icon theme to use for icons in this module,
+    /// overriding the bar's `icon_theme`.
+    ///
+    /// **Default**: `null`
+    pub icon_theme: Option<String>,
+
+    /// The number of milliseconds between each refresh,
+    /// for modules which poll for their data.
+    ///
+    /// Each polling module has its own default.
+    /// Modules which are event-driven ignore this option.
+    ///
+    /// **Default**: `null`
+    pub interval: Option<u64>,
+
+    /// The format string for the text shown on the bar.
+    ///
+    /// The available tokens and the default are specific to each module,
+    /// and are listed in its documentation.
+    /// Modules which do not show formatted text ignore this option.
+    ///
+    /// **Default**: `null`
+    pub format: Option<String>,
+
+    /// The number of milliseconds to gather updates over before applying them,
+    /// keeping only the latest of any which replace each other.
+    ///
+    /// This reduces redundant redraws for modules which update rapidly,
+    /// such as while dragging a volume slider,
+    /// at the cost of delaying updates by up to this amount.
+    /// Modules which do not support this ignore it.
+    ///
+    /// **Default**: `null`
+    pub debounce: Option<u64>,
+
     /// Shows the module only if the dynamic boolean evaluates to true.
     ///
     /// This allows for modules to be dynamically shown or hidden
     /// based on custom events.
     ///
+    /// The module starts hidden, and does not load data
+    /// or connect to any services until it is first shown.
+    ///
     /// **Default**: `null`
     pub show_if: Option<DynamicBool>,
 
+    /// Shows the module only while all conditions in the rule are met,
+    /// such as a workspace being active or a time window.
+    ///
+    /// Unlike `show_if`, the module loads as normal while hidden.
+    /// If both are set, both must be met for the module to be shown.
+    ///
+    /// See [visibility rules](#visibility-rules) for the available conditions.
+    ///
+    /// **Default**: `null`
+    pub visible_when: Option<VisibilityRule>,
+
     /// The transition animation to use when showing/hiding the widget.
     ///
-    /// Note this has no effect if `show_if` is not configured.
+    /// Note this has no effect if neither `show_if`
+    /// nor `transition_on_create` is configured.
     ///
     /// **Valid options**: `slide_start`, `slide_end`, `crossfade`, `none`
     /// <br>
@@ -68,12 +136,44 @@ pub struct CommonConfig {
     /// The length in milliseconds
     /// of the transition animation to use when showing/hiding the widget.
     ///
-    /// Note this has no effect if `show_if` is not configured.
+    /// This is also the length of time the `updating` class is applied for
+    /// when `transition_on_update` is enabled.
     ///
     /// **Default**: `250`
     pub transition_duration: Option<u32>,
 
+    /// Whether to animate the widget in using `transition_type`
+    /// when it first appears on the bar.
+    ///
+    /// This has no effect if `show_if` is configured.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub transition_on_create: bool,
+
+    /// Whether to apply the `updating` CSS class to the widget
+    /// for `transition_duration` each time its content changes.
+    ///
+    /// This can be used with CSS animations to animate updates,
+    /// with the easing set in CSS.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub transition_on_update: bool,
+
+    /// Whether to turn off animations for this module.
+    /// This includes its show/hide transitions, smooth value changes
+    /// and marquee scrolling.
+    ///
+    /// Animations can be turned off for all modules
+    /// using the top-level `disable_animations` option.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    pub disable_animations: bool,
+
     /// A [script](scripts) to run when the module is left-clicked.
+    /// This replaces the module's own left-click action, such as opening its popup.
     ///
     /// **Supported script types**: `oneshot`.
     /// <br>
@@ -87,6 +187,7 @@ pub struct CommonConfig {
     pub on_click_left: Option<ScriptInput>,
 
     /// A [script](scripts) to run when the module is right-clicked.
+    /// This replaces the module's own right-click action, such as opening its popup.
     ///
     /// **Supported script types**: `oneshot`.
     /// <br>
@@ -99,6 +200,7 @@ pub struct CommonConfig {
     pub on_click_right: Option<ScriptInput>,
 
     /// A [script](scripts) to run when the module is middle-clicked.
+    /// This replaces the module's own middle-click action, such as opening its popup.
     ///
     /// **Supported script types**: `oneshot`.
     /// <br>
@@ -206,6 +308,94 @@ pub struct CommonConfig {
     /// Prevents the popup from opening on-click for this widget.
     #[serde(default)]
     pub disable_popup: bool,
+
+    /// The side of the widget (or bar) to open the popup on.
+    /// By default, popups open away from the bar.
+    ///
+    /// **Valid options**: `top`, `bottom`, `left`, `right`
+    /// <br>
+    /// **Default**: `null`
+    pub popup_position: Option<BarPosition>,
+
+    /// The number of pixels to move the popup horizontally by,
+    /// on top of the bar's `popup_gap`.
+    ///
+    /// **Default**: `0`
+    #[serde(default)]
+    pub popup_offset_x: i32,
+
+    /// The number of pixels to move the popup vertically by,
+    /// on top of the bar's `popup_gap`.
+    ///
+    /// **Default**: `0`
+    #[serde(default)]
+    pub popup_offset_y: i32,
+
+    /// The maximum width of the popup, in pixels.
+    /// Content larger than this can be scrolled.
+    ///
+    /// **Default**: `null`
+    pub popup_max_width: Option<i32>,
+
+    /// The maximum height of the popup, in pixels.
+    /// Content larger than this can be scrolled.
+    ///
+    /// **Default**: `null`
+    pub popup_max_height: Option<i32>,
+
+    /// What the popup is positioned relative to.
+    /// With `widget`, it opens next to the widget it belongs to.
+    /// With `bar`, it is centered on the bar.
+    ///
+    /// **Valid options**: `widget`, `bar`
+    /// <br>
+    /// **Default**: `widget`
+    #[serde(default)]
+    pub popup_anchor: PopupAnchor,
+
+    /// Whether the popup takes keyboard focus while open,
+    /// overriding the bar's `popup_keyboard_mode`.
+    ///
+    /// **Valid options**: `none`, `on_demand`, `exclusive`
+    /// <br>
+    /// **Default**: `null`
+    pub popup_keyboard_mode: Option<PopupKeyboardMode>,
+
+    /// The number of milliseconds after which the popup closes itself
+    /// when opened by hovering or scrolling, rather than by a click.
+    /// Popups opened by a click stay open until dismissed.
+    ///
+    /// The popup is kept open while hovered.
+    ///
+    /// **Default**: `null`
+    pub popup_auto_close: Option<u64>,
+
+    /// The number of milliseconds to wait after the pointer leaves
+    /// an automatically closing popup before closing it.
+    ///
+    /// **Default**: `500`
+    pub popup_hover_grace: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum PopupAnchor {
+    #[default]
+    Widget,
+    Bar,
+}
+
+/// The widget used to show that something is loading.
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum LoadingIndicatorType {
+    /// A spinning circle.
+    #[default]
+    Spinner,
+    /// A progress bar which pulses back and forth.
+    Progress,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -221,6 +411,7 @@ pub enum TransitionType {
 #[derive(Debug, Default, Deserialize, Clone, Copy)]
 #[serde(rename_all = "snake_case")]
 #[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "extras", schemars(extend("x-aliases" = ["h", "v"])))]
 pub enum ModuleOrientation {
     #[default]
     #[serde(alias = "h")]
@@ -286,14 +477,33 @@ impl TransitionType {
 }
 
 impl CommonConfig {
+    /// Gets the placement and sizing options for the module's popup.
+    pub fn popup_layout(&self) -> PopupLayout {
+        PopupLayout {
+            position: self.popup_position,
+            offset: (self.popup_offset_x, self.popup_offset_y),
+            max_width: self.popup_max_width,
+            max_height: self.popup_max_height,
+            anchor: self.popup_anchor,
+            keyboard_mode: self.popup_keyboard_mode,
+            auto_close: self.popup_auto_close.map(Duration::from_millis),
+            hover_grace: Duration::from_millis(self.popup_hover_grace.unwrap_or(500)),
+        }
+    }
+
     /// Configures the module's container according to the common config options.
-    pub fn install_events<W>(mut self, container: &W, revealer: &Revealer)
-    where
+    pub fn install_events<W>(
+        mut self,
+        container: &W,
+        revealer: &Revealer,
+        ironbar: &Rc<Ironbar>,
+        monitor_name: &str,
+    ) where
         W: IsA<Widget>,
     {
         const SMOOTH_SCROLL_REQUIRED_DELTA: f64 = 10.0;
 
-        self.install_show_if(container, revealer);
+        self.install_show_if(container, revealer, ironbar, monitor_name);
 
         // Helper to install click handlers with optional double-click support
         let install_click_handler =
@@ -305,8 +515,10 @@ impl CommonConfig {
                 let double = double.map(Script::new_polling);
 
                 if single.is_some() || double.is_some() {
+                    // capture the click so the script overrides the module's own action
                     container.connect_pressed_with_double_click(
                         button,
+                        PropagationPhase::Capture,
                         move || {
                             if let Some(script) = &single {
                                 trace!("Running on-click script: {}", button_name);
@@ -335,6 +547,14 @@ impl CommonConfig {
             self.on_click_middle_double,
             "middle",
         );
+        // long-pressing a touchscreen acts as a right-click
+        if let Some(script) = self.on_click_right.clone().map(Script::new_polling) {
+            container.connect_long_press(move || {
+                trace!("Running on-long-press script");
+                script.run_as_oneshot(None);
+            });
+        }
+
         install_click_handler(
             MouseButton::Secondary,
             self.on_click_right,
@@ -412,31 +632,71 @@ impl CommonConfig {
             });
         }
 
+        if let Some(label) = self.accessible_label {
+            dynamic_string(&label, container, move |container, string| {
+                container.set_accessible_label(&string);
+            });
+        }
+
         container.add_controller(event_controller);
     }
 
-    fn install_show_if<W>(&mut self, container: &W, revealer: &Revealer)
-    where
+    fn install_show_if<W>(
+        &mut self,
+        container: &W,
+        revealer: &Revealer,
+        ironbar: &Rc<Ironbar>,
+        monitor_name: &str,
+    ) where
         W: IsA<Widget>,
     {
-        if let Some(show_if) = self.show_if.take() {
-            // need to keep clone here for the notify callback
-            let container = container.clone();
+        let show_if = self.show_if.take();
+        let visible_when = self.visible_when.take();
 
-            show_if.subscribe((revealer, &container), |(revealer, container), success| {
-                if success {
+        if show_if.is_none() && visible_when.is_none() {
+            revealer.set_visible(true);
+            return;
+        }
+
+        // whether `show_if` and `visible_when` are met,
+        // where an unset option is always met
+        let conditions = Rc::new(Cell::new((show_if.is_none(), visible_when.is_none())));
+
+        let reveal = {
+            let revealer = revealer.clone();
+            let container = container.clone();
+            move |(show_if, visible_when): (bool, bool)| {
+                let visible = show_if && visible_when;
+                if visible {
                     container.set_visible(true);
                 }
-                revealer.set_reveal_child(success);
+                revealer.set_reveal_child(visible);
+            }
+        };
+
+        if let Some(show_if) = show_if {
+            let conditions = conditions.clone();
+            let reveal = reveal.clone();
+            show_if.subscribe((), move |(), success| {
+                conditions.set((success, conditions.get().1));
+                reveal(conditions.get());
             });
+        }
 
-            revealer.connect_child_revealed_notify(move |revealer| {
-                if !revealer.reveals_child() {
-                    container.set_visible(false);
-                }
+        if let Some(visible_when) = visible_when {
+            let conditions = conditions.clone();
+            visible_when.subscribe(ironbar, monitor_name, move |visible| {
+                conditions.set((conditions.get().0, visible));
+                reveal(conditions.get());
             });
-        } else {
-            revealer.set_visible(true);
         }
+
+        // need to keep clone here for the notify callback
+        let container = container.clone();
+        revealer.connect_child_revealed_notify(move |revealer| {
+            if !revealer.reveals_child() {
+                container.set_visible(false);
+            }
+        });
     }
 }
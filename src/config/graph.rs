@@ -0,0 +1,69 @@
+use serde::Deserialize;
+
+/// How samples are drawn on a graph.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum GraphStyle {
+    /// A line joining each sample, with the area beneath it filled.
+    #[default]
+    Line,
+    /// A bar for each sample.
+    Bar,
+}
+
+/// Some modules can show a value's history as a small graph.
+/// This is controlled using a common `GraphConfig` type,
+/// which is defined below.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct GraphConfig {
+    /// The number of samples to keep and draw.
+    /// Once full, the oldest sample is dropped for each new one.
+    ///
+    /// **Default**: `30`
+    pub samples: usize,
+
+    /// How samples are drawn.
+    ///
+    /// **Valid options**: `line`, `bar`
+    /// <br>
+    /// **Default**: `line`
+    pub style: GraphStyle,
+
+    /// The value drawn at the bottom of the graph.
+    ///
+    /// **Default**: `0`
+    pub min: f64,
+
+    /// The value drawn at the top of the graph.
+    /// If not set, the graph scales to the largest sample.
+    ///
+    /// **Default**: `null`
+    pub max: Option<f64>,
+
+    /// The width of the graph, in pixels.
+    ///
+    /// **Default**: `60`
+    pub width: i32,
+
+    /// The height of the graph, in pixels.
+    /// If not set, the graph fills the available space.
+    ///
+    /// **Default**: `null`
+    pub height: Option<i32>,
+}
+
+impl Default for GraphConfig {
+    fn default() -> Self {
+        Self {
+            samples: 30,
+            style: GraphStyle::default(),
+            min: 0.0,
+            max: None,
+            width: 60,
+            height: None,
+        }
+    }
+}
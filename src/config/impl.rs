@@ -1,8 +1,12 @@
-use super::{BarConfig, BarPosition, MonitorConfig};
+use super::{
+    BarConfig, BarPosition, BarTransitionType, Config, GroupSizes, LayoutDirection, MonitorConfig,
+    PopupKeyboardMode,
+};
 use color_eyre::{Help, Report};
-use gtk::Orientation;
+use gtk::{Orientation, RevealerTransitionType, TextDirection};
 use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
 use serde::{Deserialize, Deserializer, de};
+use std::collections::HashMap;
 use std::fmt;
 
 // Manually implement for better untagged enum error handling:
@@ -89,13 +93,133 @@ impl BarPosition {
         }
     }
 
-    /// Gets the angle that label text should be displayed at
-    /// based on this position.
-    pub const fn angle(self) -> f64 {
+    /// Gets the name of the position,
+    /// as used in config and CSS classes.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Top => "top",
+            Self::Bottom => "bottom",
+            Self::Left => "left",
+            Self::Right => "right",
+        }
+    }
+}
+
+impl BarTransitionType {
+    /// Gets the revealer transition type
+    /// for a bar at the given position.
+    pub const fn to_revealer_transition_type(
+        self,
+        position: BarPosition,
+    ) -> RevealerTransitionType {
+        match (self, position) {
+            (Self::Slide, BarPosition::Top) => RevealerTransitionType::SlideDown,
+            (Self::Slide, BarPosition::Bottom) => RevealerTransitionType::SlideUp,
+            (Self::Slide, BarPosition::Left) => RevealerTransitionType::SlideRight,
+            (Self::Slide, BarPosition::Right) => RevealerTransitionType::SlideLeft,
+            (Self::Crossfade, _) => RevealerTransitionType::Crossfade,
+            (Self::None, _) => RevealerTransitionType::None,
+        }
+    }
+}
+
+impl PopupKeyboardMode {
+    /// Gets the layer shell keyboard mode
+    /// to set on the bar window while the popup is open.
+    pub const fn to_layer_shell_mode(self) -> gtk_layer_shell::KeyboardMode {
         match self {
-            Self::Top | Self::Bottom => 0.0,
-            Self::Left => 90.0,
-            Self::Right => 270.0,
+            Self::None => gtk_layer_shell::KeyboardMode::None,
+            Self::OnDemand => gtk_layer_shell::KeyboardMode::OnDemand,
+            Self::Exclusive => gtk_layer_shell::KeyboardMode::Exclusive,
         }
     }
 }
+
+impl LayoutDirection {
+    /// Gets the GTK text direction,
+    /// resolving `auto` using the current locale.
+    pub fn to_text_direction(self) -> TextDirection {
+        match self {
+            Self::Auto => gtk::locale_direction(),
+            Self::Ltr => TextDirection::Ltr,
+            Self::Rtl => TextDirection::Rtl,
+        }
+    }
+}
+
+impl GroupSizes {
+    /// Gets the sizes for the `start`, `center` and `end` groups, in that order.
+    pub const fn per_group(self) -> [i32; 3] {
+        match self {
+            Self::All(size) => [size; 3],
+            Self::Each { start, center, end } => [start, center, end],
+        }
+    }
+}
+
+impl Config {
+    /// Gets the bar config for the monitor with the given connector name and description.
+    ///
+    /// An exact match on the connector name is preferred
+    /// over a match on the start of the description.
+    pub fn monitor_config(&self, name: &str, description: &str) -> Option<&MonitorConfig> {
+        find_monitor(self.monitors.as_ref()?, name, description)
+    }
+
+    /// Gets the top-level bar config for the monitor with the given connector name and description,
+    /// with any `monitor_overrides` for that monitor applied.
+    pub fn monitor_bar(&self, name: &str, description: &str) -> &BarConfig {
+        self.monitor_overrides
+            .as_ref()
+            .and_then(|overrides| find_monitor(overrides, name, description))
+            .unwrap_or(&self.bar)
+    }
+
+    /// Checks whether bars should never be shown on the given monitor.
+    pub fn excludes_monitor(&self, name: &str, description: &str) -> bool {
+        self.exclude_monitors
+            .iter()
+            .any(|key| key == name || matches_description(key, description))
+    }
+}
+
+/// Gets the value for a monitor from a map keyed by monitor.
+///
+/// An exact match on the connector name is preferred
+/// over a match on the start of the description.
+fn find_monitor<'a, T>(
+    monitors: &'a HashMap<String, T>,
+    name: &str,
+    description: &str,
+) -> Option<&'a T> {
+    monitors.get(name).or_else(|| {
+        monitors
+            .iter()
+            .find(|(key, _)| matches_description(key, description))
+            .map(|(_, config)| config)
+    })
+}
+
+/// Checks whether a monitor key matches the start of a monitor description,
+/// ignoring case.
+fn matches_description(key: &str, description: &str) -> bool {
+    !description.is_empty() && description.to_lowercase().starts_with(&key.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_description() {
+        let description = "ASUSTek COMPUTER INC PA278QV M4LMQS060475";
+
+        assert!(matches_description("ASUSTek COMPUTER INC", description));
+        assert!(matches_description(
+            "asustek computer inc pa278qv",
+            description
+        ));
+        assert!(!matches_description("PA278QV", description));
+        assert!(!matches_description("DP-1", ""));
+    }
+}
@@ -0,0 +1,237 @@
+use config::{FileStoredFormat, Format, Map, Value, ValueKind};
+use indexmap::IndexMap;
+use kdl::{KdlDocument, KdlNode, KdlValue};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+type ParseResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// The node name used to denote an array item.
+///
+/// A node whose children are all named `-` is converted into an array.
+const ARRAY_ITEM: &str = "-";
+
+/// [KDL](https://kdl.dev) config format support.
+///
+/// KDL documents are node-based rather than key/value based,
+/// so they are mapped onto the config structure using the following rules:
+///
+/// - A node with a single argument becomes a `key = value` pair.
+/// - A node with multiple arguments becomes an array of values.
+/// - A node with properties and/or children becomes a map.
+/// - A node whose children are all named `-` becomes an array of those children.
+/// - Repeated nodes with the same name are collected into an array.
+#[derive(Debug, Clone, Copy)]
+pub struct Kdl;
+
+impl Format for Kdl {
+    fn parse(&self, uri: Option<&String>, text: &str) -> ParseResult<Map<String, Value>> {
+        let document = text.parse::<KdlDocument>()?;
+        document_to_map(uri, &document)
+    }
+}
+
+impl FileStoredFormat for Kdl {
+    fn file_extensions(&self) -> &'static [&'static str] {
+        &["kdl"]
+    }
+}
+
+/// Attempts to resolve the KDL file for the given config path.
+///
+/// If the path has a `.kdl` extension, it is returned as-is.
+/// If the path has no extension, the `.kdl` extension is tried
+/// as long as no file already exists at the exact path.
+pub fn find_file(path: &Path) -> Option<PathBuf> {
+    match path.extension() {
+        Some(ext) if ext == "kdl" => Some(path.to_path_buf()),
+        None if !path.is_file() => {
+            let path = path.with_extension("kdl");
+            path.is_file().then_some(path)
+        }
+        _ => None,
+    }
+}
+
+fn document_to_map(
+    uri: Option<&String>,
+    document: &KdlDocument,
+) -> ParseResult<Map<String, Value>> {
+    let mut groups: IndexMap<&str, Vec<&KdlNode>> = IndexMap::new();
+    for node in document.nodes() {
+        groups.entry(node.name().value()).or_default().push(node);
+    }
+
+    let mut map = Map::new();
+
+    for (key, nodes) in groups {
+        let value = match nodes.as_slice() {
+            [node] => node_to_value(uri, node)?,
+            nodes => {
+                let values = nodes
+                    .iter()
+                    .map(|node| node_to_value(uri, node))
+                    .collect::<ParseResult<_>>()?;
+
+                Value::new(uri, ValueKind::Array(values))
+            }
+        };
+
+        map.insert(key.to_string(), value);
+    }
+
+    Ok(map)
+}
+
+fn node_to_value(uri: Option<&String>, node: &KdlNode) -> ParseResult<Value> {
+    let (args, props): (Vec<_>, Vec<_>) = node.entries().iter().partition(|e| e.name().is_none());
+
+    if !args.is_empty() && (!props.is_empty() || node.children().is_some()) {
+        return Err(format!(
+            "node '{}' mixes arguments with properties or children, which is not supported",
+            node.name().value()
+        )
+        .into());
+    }
+
+    match (args.as_slice(), node.children()) {
+        ([arg], None) => Ok(convert_value(uri, arg.value())),
+        ([], Some(children)) if is_array(children) && props.is_empty() => {
+            let values = children
+                .nodes()
+                .iter()
+                .map(|node| node_to_value(uri, node))
+                .collect::<ParseResult<_>>()?;
+
+            Ok(Value::new(uri, ValueKind::Array(values)))
+        }
+        ([], children) => {
+            let mut map = match children {
+                Some(children) => document_to_map(uri, children)?,
+                None => Map::new(),
+            };
+
+            for prop in props {
+                let key = prop
+                    .name()
+                    .map(|n| n.value().to_string())
+                    .unwrap_or_default();
+                map.insert(key, convert_value(uri, prop.value()));
+            }
+
+            Ok(Value::new(uri, ValueKind::Table(map)))
+        }
+        (args, _) => {
+            let values = args
+                .iter()
+                .map(|arg| convert_value(uri, arg.value()))
+                .collect();
+
+            Ok(Value::new(uri, ValueKind::Array(values)))
+        }
+    }
+}
+
+fn is_array(document: &KdlDocument) -> bool {
+    !document.nodes().is_empty()
+        && document
+            .nodes()
+            .iter()
+            .all(|node| node.name().value() == ARRAY_ITEM)
+}
+
+fn convert_value(uri: Option<&String>, value: &KdlValue) -> Value {
+    let kind = match value {
+        KdlValue::String(str) => ValueKind::String(str.clone()),
+        KdlValue::Integer(int) => i64::try_from(*int).map_or(ValueKind::I128(*int), ValueKind::I64),
+        KdlValue::Float(float) => ValueKind::Float(*float),
+        KdlValue::Bool(bool) => ValueKind::Boolean(*bool),
+        KdlValue::Null => ValueKind::Nil,
+    };
+
+    Value::new(uri, kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Map<String, Value> {
+        Kdl.parse(None, text).expect("valid kdl")
+    }
+
+    #[test]
+    fn scalar_args() {
+        let map = parse(
+            r#"position "top"
+height 24
+anchor_to_edges #false"#,
+        );
+
+        assert_eq!(
+            map["position"].clone().into_string().ok(),
+            Some("top".to_string())
+        );
+        assert_eq!(map["height"].clone().into_int().ok(), Some(24));
+        assert_eq!(map["anchor_to_edges"].clone().into_bool().ok(), Some(false));
+    }
+
+    #[test]
+    fn array_items() {
+        let map = parse(
+            r#"end {
+    - type="clock" format="%H:%M"
+    - type="tray"
+}"#,
+        );
+
+        let end = map["end"].clone().into_array().expect("array");
+        assert_eq!(end.len(), 2);
+
+        let clock = end[0].clone().into_table().expect("table");
+        assert_eq!(
+            clock["type"].clone().into_string().ok(),
+            Some("clock".to_string())
+        );
+        assert_eq!(
+            clock["format"].clone().into_string().ok(),
+            Some("%H:%M".to_string())
+        );
+    }
+
+    #[test]
+    fn multiple_args() {
+        let map = parse(r#"favorites "firefox" "kitty""#);
+        let favorites = map["favorites"].clone().into_array().expect("array");
+        assert_eq!(favorites.len(), 2);
+    }
+
+    #[test]
+    fn repeated_nodes() {
+        let map = parse(
+            r#"format "a"
+format "b"
+format "c""#,
+        );
+        let format = map["format"].clone().into_array().expect("array");
+        assert_eq!(format.len(), 3);
+    }
+
+    #[test]
+    fn nested_maps() {
+        let map = parse(
+            r#"margin top=10 {
+    bottom 5
+}"#,
+        );
+
+        let margin = map["margin"].clone().into_table().expect("table");
+        assert_eq!(margin["top"].clone().into_int().ok(), Some(10));
+        assert_eq!(margin["bottom"].clone().into_int().ok(), Some(5));
+    }
+
+    #[test]
+    fn mixed_args_props() {
+        assert!(Kdl.parse(None, r#"foo "bar" baz=1"#).is_err());
+    }
+}
@@ -8,9 +8,12 @@ pub struct LayoutConfig {
     /// The orientation to display the widget contents.
     /// Setting to vertical will rotate text 90 degrees.
     ///
+    /// If unset, this follows the bar,
+    /// but text is not rotated.
+    ///
     /// **Valid options**: `horizontal`, `vertical`
     /// <br>
-    /// **Default**: `horizontal`
+    /// **Default**: `null`
     orientation: Option<ModuleOrientation>,
 
     /// The justification (alignment) of the widget text shown on the bar.
@@ -27,4 +30,11 @@ impl LayoutConfig {
         self.orientation
             .map_or(info.bar_position.orientation(), ModuleOrientation::into)
     }
+
+    /// Gets the angle that label text should be displayed at.
+    ///
+    /// Text is only rotated if the orientation is set to vertical.
+    pub fn angle(&self) -> f64 {
+        self.orientation.map_or(0.0, ModuleOrientation::to_angle)
+    }
 }
@@ -0,0 +1,819 @@
+use super::Config;
+use config::{Map, Value, ValueKind};
+use serde_json::Value as Schema;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// Origin set by the `config` crate on values read from environment variables.
+/// These are shared with other tools, so are never reported.
+const ENV_ORIGIN: &str = "the environment";
+
+/// Key used by editors to locate the schema,
+/// which is not itself part of the schema.
+const SCHEMA_KEY: &str = "$schema";
+
+/// Schema extension listing the aliases of an enum's values,
+/// which are accepted but not suggested by editors.
+const ALIASES_KEY: &str = "x-aliases";
+
+/// Maximum depth to follow schema references,
+/// used to guard against recursive definitions.
+const MAX_DEPTH: usize = 32;
+
+/// Maximum edit distance for a known option to be suggested.
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnostic {
+    /// An option which is not recognised,
+    /// and will be ignored.
+    Unknown {
+        path: String,
+        suggestion: Option<String>,
+    },
+    /// An option which is recognised,
+    /// but is deprecated and may be removed in a future release.
+    Deprecated { path: String },
+    /// An option whose value is the wrong type,
+    /// which prevents the config from loading.
+    InvalidType {
+        path: String,
+        expected: Vec<String>,
+        found: &'static str,
+    },
+    /// An option whose value is not one of the accepted values,
+    /// which prevents the config from loading.
+    InvalidValue {
+        path: String,
+        value: String,
+        expected: Vec<String>,
+        suggestion: Option<String>,
+    },
+}
+
+impl Diagnostic {
+    /// Whether the diagnostic prevents the config from loading,
+    /// rather than being a warning.
+    pub const fn is_error(&self) -> bool {
+        matches!(self, Self::InvalidType { .. } | Self::InvalidValue { .. })
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unknown {
+                path,
+                suggestion: Some(suggestion),
+            } => write!(
+                f,
+                "Unknown config option '{path}' will be ignored. Did you mean '{suggestion}'?"
+            ),
+            Self::Unknown {
+                path,
+                suggestion: None,
+            } => write!(f, "Unknown config option '{path}' will be ignored"),
+            Self::Deprecated { path } => write!(
+                f,
+                "Config option '{path}' is deprecated and may be removed in a future release"
+            ),
+            Self::InvalidType {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Config option '{path}' is {found}, but expected {}",
+                expected.join(" or ")
+            ),
+            Self::InvalidValue {
+                path,
+                value,
+                expected,
+                suggestion,
+            } => {
+                write!(
+                    f,
+                    "Config option '{path}' has invalid value '{value}', expected one of: {}",
+                    expected.join(", ")
+                )?;
+
+                if let Some(suggestion) = suggestion {
+                    write!(f, ". Did you mean '{suggestion}'?")?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Checks the config tree against the config schema,
+/// returning any options which are unknown, deprecated or invalid.
+pub fn lint(root: &Map<String, Value>) -> Vec<Diagnostic> {
+    let schema = schemars::schema_for!(Config);
+    lint_with_schema(root, schema.as_value())
+}
+
+fn lint_with_schema(root: &Map<String, Value>, schema: &Schema) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        root: schema,
+        diagnostics: vec![],
+    };
+
+    linter.lint_table(root, schema, "");
+    linter.diagnostics
+}
+
+/// The options accepted by an object schema.
+#[derive(Default)]
+struct Shape<'a> {
+    properties: HashMap<&'a str, &'a Schema>,
+    additional: Option<&'a Schema>,
+    /// The accepted `type` tags of a tagged union,
+    /// if the table's `type` matched none of them.
+    unknown_tag: Option<Vec<&'a str>>,
+}
+
+struct Linter<'a> {
+    root: &'a Schema,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Linter<'a> {
+    fn lint_value(&mut self, value: &Value, schema: &'a Schema, path: &str) {
+        if value.origin() == Some(ENV_ORIGIN) {
+            return;
+        }
+
+        if let Some(expected) = self.types(schema, 0)
+            && !accepts(&value.kind, &expected)
+        {
+            self.diagnostics.push(Diagnostic::InvalidType {
+                path: path.to_string(),
+                expected: expected.into_iter().map(ToString::to_string).collect(),
+                found: type_name(&value.kind),
+            });
+            return;
+        }
+
+        match &value.kind {
+            ValueKind::Table(table) => self.lint_table(table, schema, path),
+            ValueKind::Array(array) => {
+                if let Some(items) = self.find(schema, 0, &|schema| schema.get("items")) {
+                    for (i, value) in array.iter().enumerate() {
+                        self.lint_value(value, items, &format!("{path}[{i}]"));
+                    }
+                }
+            }
+            ValueKind::String(value) => {
+                if let Some(expected) = self.values(schema, 0)
+                    && !expected.is_empty()
+                    && !expected.contains(&value.as_str())
+                {
+                    self.diagnostics.push(Diagnostic::InvalidValue {
+                        path: path.to_string(),
+                        value: value.clone(),
+                        suggestion: suggest(value, expected.iter().copied()),
+                        expected: expected.into_iter().map(ToString::to_string).collect(),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn lint_table(&mut self, table: &Map<String, Value>, schema: &'a Schema, path: &str) {
+        let mut shape = Shape::default();
+        self.collect_shape(schema, table, &mut shape, 0);
+
+        // the options depend on the variant, so cannot be checked
+        if let Some(expected) = shape.unknown_tag
+            && let Some(tag) = table.get("type")
+        {
+            let tag = tag.to_string();
+            self.diagnostics.push(Diagnostic::InvalidValue {
+                path: if path.is_empty() {
+                    "type".to_string()
+                } else {
+                    format!("{path}.type")
+                },
+                suggestion: suggest(&tag, expected.iter().copied()),
+                expected: expected.into_iter().map(ToString::to_string).collect(),
+                value: tag,
+            });
+            return;
+        }
+
+        for (key, value) in table {
+            if key == SCHEMA_KEY {
+                continue;
+            }
+
+            let path = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{path}.{key}")
+            };
+
+            if let Some(property) = shape.properties.get(key.as_str()).copied() {
+                if self.is_deprecated(property) {
+                    self.diagnostics
+                        .push(Diagnostic::Deprecated { path: path.clone() });
+                }
+
+                self.lint_value(value, property, &path);
+            } else if let Some(additional) = shape.additional {
+                self.lint_value(value, additional, &path);
+            } else if !shape.properties.is_empty() && value.origin() != Some(ENV_ORIGIN) {
+                let suggestion = suggest(key, shape.properties.keys().copied());
+                self.diagnostics
+                    .push(Diagnostic::Unknown { path, suggestion });
+            }
+        }
+    }
+
+    /// Collects the accepted options of an object schema,
+    /// merging in any referenced or combined schemas.
+    ///
+    /// Where the schema is a tagged union,
+    /// only the variant matching the table's `type` is used.
+    fn collect_shape(
+        &self,
+        schema: &'a Schema,
+        table: &Map<String, Value>,
+        shape: &mut Shape<'a>,
+        depth: usize,
+    ) {
+        if depth > MAX_DEPTH {
+            return;
+        }
+
+        if let Some(properties) = schema.get("properties").and_then(Schema::as_object) {
+            for (key, property) in properties {
+                shape.properties.entry(key.as_str()).or_insert(property);
+            }
+        }
+
+        if let Some(additional) = schema.get("additionalProperties")
+            && additional.is_object()
+        {
+            shape.additional.get_or_insert(additional);
+        }
+
+        if let Some(reference) = self.reference(schema) {
+            self.collect_shape(reference, table, shape, depth + 1);
+        }
+
+        if let Some(all) = schema.get("allOf").and_then(Schema::as_array) {
+            for schema in all {
+                self.collect_shape(schema, table, shape, depth + 1);
+            }
+        }
+
+        let tag = table.get("type").and_then(|tag| match &tag.kind {
+            ValueKind::String(tag) => Some(tag.as_str()),
+            _ => None,
+        });
+
+        for key in ["anyOf", "oneOf"] {
+            let Some(variants) = schema.get(key).and_then(Schema::as_array) else {
+                continue;
+            };
+
+            let variants = variants
+                .iter()
+                .filter(|variant| self.accepts_object(*variant, depth))
+                .collect::<Vec<_>>();
+
+            let tagged = tag.and_then(|tag| {
+                variants
+                    .iter()
+                    .copied()
+                    .find(|variant| self.matches_tag(*variant, tag, depth))
+            });
+
+            match tagged {
+                Some(variant) => self.collect_shape(variant, table, shape, depth + 1),
+                None => {
+                    let tags = variants
+                        .iter()
+                        .filter_map(|variant| self.tag(variant, depth))
+                        .collect::<Vec<_>>();
+
+                    if tag.is_some() && !tags.is_empty() && tags.len() == variants.len() {
+                        shape.unknown_tag.get_or_insert(tags);
+                    }
+
+                    for variant in variants {
+                        self.collect_shape(variant, table, shape, depth + 1);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Gets the schema referenced by `$ref`, if present.
+    fn reference(&self, schema: &Schema) -> Option<&'a Schema> {
+        schema
+            .get("$ref")
+            .and_then(Schema::as_str)
+            .and_then(|reference| reference.strip_prefix('#'))
+            .and_then(|pointer| self.root.pointer(pointer))
+    }
+
+    /// Searches the schema and any referenced or combined schemas
+    /// for the first value returned by `f`.
+    fn find<F>(&self, schema: &'a Schema, depth: usize, f: &F) -> Option<&'a Schema>
+    where
+        F: Fn(&'a Schema) -> Option<&'a Schema>,
+    {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        f(schema)
+            .or_else(|| {
+                self.reference(schema)
+                    .and_then(|schema| self.find(schema, depth + 1, f))
+            })
+            .or_else(|| {
+                ["allOf", "anyOf", "oneOf"]
+                    .iter()
+                    .filter_map(|key| schema.get(key).and_then(Schema::as_array))
+                    .flatten()
+                    .find_map(|schema| self.find(schema, depth + 1, f))
+            })
+    }
+
+    fn accepts_object(&self, schema: &'a Schema, depth: usize) -> bool {
+        match self.find(schema, depth, &|schema| schema.get("type")) {
+            Some(Schema::String(ty)) => ty == "object",
+            Some(Schema::Array(types)) => types.iter().any(|ty| ty.as_str() == Some("object")),
+            Some(_) => false,
+            // no type constraint
+            None => true,
+        }
+    }
+
+    fn matches_tag(&self, schema: &'a Schema, tag: &str, depth: usize) -> bool {
+        self.find(schema, depth, &|schema| {
+            schema
+                .get("properties")
+                .and_then(|properties| properties.get("type"))
+        })
+        .is_some_and(|property| {
+            property.get("const").and_then(Schema::as_str) == Some(tag)
+                || property
+                    .get("enum")
+                    .and_then(Schema::as_array)
+                    .is_some_and(|values| values.iter().any(|value| value.as_str() == Some(tag)))
+        })
+    }
+
+    /// Gets the `type` tag of a tagged union variant.
+    fn tag(&self, schema: &'a Schema, depth: usize) -> Option<&'a str> {
+        self.find(schema, depth, &|schema| {
+            schema
+                .get("properties")
+                .and_then(|properties| properties.get("type"))
+        })
+        .and_then(|property| {
+            property.get("const").or_else(|| {
+                property
+                    .get("enum")
+                    .and_then(Schema::as_array)
+                    .and_then(|values| values.first())
+            })
+        })
+        .and_then(Schema::as_str)
+    }
+
+    /// Gets the JSON types accepted by a schema,
+    /// or `None` if it accepts any type.
+    fn types(&self, schema: &'a Schema, depth: usize) -> Option<Vec<&'a str>> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        match schema.get("type") {
+            Some(Schema::String(ty)) => return Some(vec![ty]),
+            Some(Schema::Array(types)) => {
+                return Some(types.iter().filter_map(Schema::as_str).collect());
+            }
+            _ => {}
+        }
+
+        if let Some(value) = schema.get("const") {
+            return Some(vec![json_type(value)]);
+        }
+
+        if let Some(values) = schema.get("enum").and_then(Schema::as_array) {
+            return Some(values.iter().map(json_type).collect());
+        }
+
+        if let Some(reference) = self.reference(schema) {
+            return self.types(reference, depth + 1);
+        }
+
+        // every schema must match, so any one constrains the type
+        if let Some(types) = schema
+            .get("allOf")
+            .and_then(Schema::as_array)
+            .and_then(|all| all.iter().find_map(|schema| self.types(schema, depth + 1)))
+        {
+            return Some(types);
+        }
+
+        let variants = ["anyOf", "oneOf"]
+            .iter()
+            .filter_map(|key| schema.get(key).and_then(Schema::as_array))
+            .flatten()
+            .collect::<Vec<_>>();
+
+        if variants.is_empty() {
+            return None;
+        }
+
+        let mut types = vec![];
+        for variant in variants {
+            types.extend(self.types(variant, depth + 1)?);
+        }
+
+        types.dedup();
+        Some(types)
+    }
+
+    /// Gets the strings accepted by an enum schema,
+    /// or `None` if it accepts any string.
+    fn values(&self, schema: &'a Schema, depth: usize) -> Option<Vec<&'a str>> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+
+        // values accepted through `#[serde(alias)]`
+        let aliases = schema
+            .get(ALIASES_KEY)
+            .and_then(Schema::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Schema::as_str);
+
+        if let Some(value) = schema.get("const") {
+            return Some(value.as_str().into_iter().chain(aliases).collect());
+        }
+
+        if let Some(values) = schema.get("enum").and_then(Schema::as_array) {
+            return Some(
+                values
+                    .iter()
+                    .filter_map(Schema::as_str)
+                    .chain(aliases)
+                    .collect(),
+            );
+        }
+
+        // accepts no strings, such as `null` for optional values
+        if let Some(Schema::String(ty)) = schema.get("type")
+            && ty != "string"
+        {
+            return Some(vec![]);
+        }
+
+        if let Some(reference) = self.reference(schema) {
+            return self.values(reference, depth + 1);
+        }
+
+        let variants = ["anyOf", "oneOf"]
+            .iter()
+            .filter_map(|key| schema.get(key).and_then(Schema::as_array))
+            .flatten()
+            .collect::<Vec<_>>();
+
+        if variants.is_empty() {
+            return None;
+        }
+
+        let mut values = vec![];
+        for variant in variants {
+            values.extend(self.values(variant, depth + 1)?);
+        }
+
+        Some(values)
+    }
+
+    fn is_deprecated(&self, schema: &'a Schema) -> bool {
+        self.find(schema, 0, &|schema| schema.get("deprecated"))
+            .and_then(Schema::as_bool)
+            .unwrap_or_default()
+    }
+}
+
+/// Gets the JSON type of a schema value.
+fn json_type(value: &Schema) -> &'static str {
+    match value {
+        Schema::Null => "null",
+        Schema::Bool(_) => "boolean",
+        Schema::Number(number) if number.is_f64() => "number",
+        Schema::Number(_) => "integer",
+        Schema::String(_) => "string",
+        Schema::Array(_) => "array",
+        Schema::Object(_) => "object",
+    }
+}
+
+/// Gets the name of a config value's type, for use in diagnostics.
+fn type_name(kind: &ValueKind) -> &'static str {
+    match kind {
+        ValueKind::Nil => "null",
+        ValueKind::Boolean(_) => "a boolean",
+        ValueKind::I64(_) | ValueKind::I128(_) | ValueKind::U64(_) | ValueKind::U128(_) => {
+            "an integer"
+        }
+        ValueKind::Float(_) => "a number",
+        ValueKind::String(_) => "a string",
+        ValueKind::Table(_) => "an object",
+        ValueKind::Array(_) => "an array",
+    }
+}
+
+/// Checks whether a config value can be deserialized as one of the JSON `types`.
+///
+/// The `config` crate converts between scalar types when deserializing,
+/// and some formats and environment variables only produce strings,
+/// so scalars are accepted wherever they can be converted.
+fn accepts(kind: &ValueKind, types: &[&str]) -> bool {
+    let is_scalar = matches!(
+        kind,
+        ValueKind::Boolean(_)
+            | ValueKind::I64(_)
+            | ValueKind::I128(_)
+            | ValueKind::U64(_)
+            | ValueKind::U128(_)
+            | ValueKind::Float(_)
+    );
+
+    types.iter().any(|ty| match (*ty, kind) {
+        ("null", ValueKind::Nil)
+        | ("object", ValueKind::Table(_))
+        | ("array", ValueKind::Array(_))
+        | ("string", ValueKind::String(_)) => true,
+        ("boolean" | "integer" | "number" | "string", _) if is_scalar => true,
+        ("boolean", ValueKind::String(value)) => matches!(
+            value.to_lowercase().as_str(),
+            "true" | "false" | "on" | "off" | "yes" | "no" | "1" | "0"
+        ),
+        ("integer" | "number", ValueKind::String(value)) => value.parse::<f64>().is_ok(),
+        _ => false,
+    })
+}
+
+/// Finds the closest known option to `key`, if any are similar enough.
+fn suggest<'a>(key: &str, options: impl Iterator<Item = &'a str>) -> Option<String> {
+    options
+        .map(|option| (distance(key, option), option))
+        .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+        .min()
+        .map(|(_, option)| option.to_string())
+}
+
+/// Calculates the Levenshtein distance between two strings.
+fn distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+
+        for (j, b) in b.iter().enumerate() {
+            let cost = usize::from(a != *b);
+            let next = (row[j + 1] + 1).min(row[j] + 1).min(prev + cost);
+            prev = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn value(kind: ValueKind) -> Value {
+        Value::new(None, kind)
+    }
+
+    fn table<const N: usize>(entries: [(&str, Value); N]) -> Map<String, Value> {
+        entries
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v))
+            .collect()
+    }
+
+    fn schema() -> Schema {
+        json!({
+            "type": "object",
+            "properties": {
+                "height": { "type": "integer" },
+                "old_height": { "type": "integer", "deprecated": true },
+                "position": {
+                    "anyOf": [{ "$ref": "#/$defs/Position" }, { "type": "null" }]
+                },
+                "end": {
+                    "type": ["array", "null"],
+                    "items": { "$ref": "#/$defs/ModuleConfig" }
+                },
+                "monitors": {
+                    "type": "object",
+                    "additionalProperties": { "$ref": "#/$defs/Bar" }
+                }
+            },
+            "$defs": {
+                "Position": {
+                    "type": "string",
+                    "enum": ["top", "bottom"],
+                    "x-aliases": ["t", "b"]
+                },
+                "Bar": {
+                    "type": "object",
+                    "properties": { "height": { "type": "integer" } }
+                },
+                "ModuleConfig": {
+                    "oneOf": [
+                        {
+                            "type": "object",
+                            "properties": { "type": { "const": "clock" } },
+                            "$ref": "#/$defs/Clock"
+                        },
+                        {
+                            "type": "object",
+                            "properties": { "type": { "const": "script" } },
+                            "$ref": "#/$defs/Script"
+                        }
+                    ]
+                },
+                "Clock": {
+                    "type": "object",
+                    "properties": { "format": { "type": "string" } }
+                },
+                "Script": {
+                    "type": "object",
+                    "properties": { "interval": { "type": "integer" } }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn unknown_with_suggestion() {
+        let root = table([("heigth", value(ValueKind::I64(32)))]);
+        assert_eq!(
+            lint_with_schema(&root, &schema()),
+            vec![Diagnostic::Unknown {
+                path: "heigth".to_string(),
+                suggestion: Some("height".to_string())
+            }]
+        );
+    }
+
+    #[test]
+    fn deprecated() {
+        let root = table([("old_height", value(ValueKind::I64(32)))]);
+        assert_eq!(
+            lint_with_schema(&root, &schema()),
+            vec![Diagnostic::Deprecated {
+                path: "old_height".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn tagged_module() {
+        let module = table([
+            ("type", value(ValueKind::String("script".to_string()))),
+            ("intervall", value(ValueKind::I64(500))),
+            ("format", value(ValueKind::String(String::new()))),
+        ]);
+
+        let root = table([(
+            "end",
+            value(ValueKind::Array(vec![value(ValueKind::Table(module))])),
+        )]);
+
+        let mut diagnostics = lint_with_schema(&root, &schema());
+        diagnostics.sort_by_key(ToString::to_string);
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic::Unknown {
+                    path: "end[0].format".to_string(),
+                    suggestion: None
+                },
+                Diagnostic::Unknown {
+                    path: "end[0].intervall".to_string(),
+                    suggestion: Some("interval".to_string())
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_type() {
+        let root = table([
+            ("height", value(ValueKind::String("tall".to_string()))),
+            ("end", value(ValueKind::String(String::new()))),
+        ]);
+
+        let mut diagnostics = lint_with_schema(&root, &schema());
+        diagnostics.sort_by_key(ToString::to_string);
+
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic::InvalidType {
+                    path: "end".to_string(),
+                    expected: vec!["array".to_string(), "null".to_string()],
+                    found: "a string"
+                },
+                Diagnostic::InvalidType {
+                    path: "height".to_string(),
+                    expected: vec!["integer".to_string()],
+                    found: "a string"
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn convertible_type() {
+        let root = table([("height", value(ValueKind::String("32".to_string())))]);
+        assert!(lint_with_schema(&root, &schema()).is_empty());
+    }
+
+    #[test]
+    fn invalid_value() {
+        let root = table([("position", value(ValueKind::String("botom".to_string())))]);
+        assert_eq!(
+            lint_with_schema(&root, &schema()),
+            vec![Diagnostic::InvalidValue {
+                path: "position".to_string(),
+                value: "botom".to_string(),
+                expected: vec![
+                    "top".to_string(),
+                    "bottom".to_string(),
+                    "t".to_string(),
+                    "b".to_string()
+                ],
+                suggestion: Some("bottom".to_string())
+            }]
+        );
+
+        let root = table([("position", value(ValueKind::String("b".to_string())))]);
+        assert!(lint_with_schema(&root, &schema()).is_empty());
+    }
+
+    #[test]
+    fn unknown_module_type() {
+        let module = table([("type", value(ValueKind::String("clok".to_string())))]);
+        let root = table([(
+            "end",
+            value(ValueKind::Array(vec![value(ValueKind::Table(module))])),
+        )]);
+
+        assert_eq!(
+            lint_with_schema(&root, &schema()),
+            vec![Diagnostic::InvalidValue {
+                path: "end[0].type".to_string(),
+                value: "clok".to_string(),
+                expected: vec!["clock".to_string(), "script".to_string()],
+                suggestion: Some("clock".to_string())
+            }]
+        );
+    }
+
+    #[test]
+    fn additional_properties() {
+        let bar = table([("height", value(ValueKind::I64(32)))]);
+        let monitors = table([("DP-1", value(ValueKind::Table(bar)))]);
+        let root = table([("monitors", value(ValueKind::Table(monitors)))]);
+
+        assert!(lint_with_schema(&root, &schema()).is_empty());
+    }
+
+    #[test]
+    fn environment_ignored() {
+        let root = table([(
+            "config",
+            Value::new(
+                Some(&ENV_ORIGIN.to_string()),
+                ValueKind::String(String::new()),
+            ),
+        )]);
+
+        assert!(lint_with_schema(&root, &schema()).is_empty());
+    }
+}
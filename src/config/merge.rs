@@ -0,0 +1,293 @@
+use super::Config;
+use config::{ConfigError, Map, Value, ValueKind};
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Top-level key containing the paths of files to include.
+const INCLUDE_KEY: &str = "include";
+/// Top-level key containing the per-monitor bar overrides.
+const OVERRIDES_KEY: &str = "monitor_overrides";
+/// Top-level key containing the named config profiles.
+const PROFILES_KEY: &str = "config_profiles";
+/// Top-level key containing the name of the profile to use.
+const PROFILE_KEY: &str = "config_profile";
+/// Top-level keys which are not part of the bar config,
+/// so are not copied into overrides.
+const NON_BAR_KEYS: &[&str] = &[OVERRIDES_KEY, PROFILES_KEY, PROFILE_KEY, "monitors", "bars"];
+
+/// Maximum depth of nested includes,
+/// used to catch files which include themselves.
+const MAX_DEPTH: usize = 16;
+
+/// Merges each file listed in the top-level `include` key into the config tree.
+///
+/// Paths are relative to the directory of the file containing them,
+/// and included files can include further files.
+/// Values in the including file take priority over those it includes,
+/// and later includes take priority over earlier ones.
+///
+/// The `include` key is removed from the tree.
+/// Returns the paths of every included file.
+pub fn include(root: &mut Map<String, Value>, dir: &Path) -> Result<Vec<PathBuf>, ConfigError> {
+    let mut files = vec![];
+    include_into(root, dir, &mut files, 0)?;
+    Ok(files)
+}
+
+fn include_into(
+    root: &mut Map<String, Value>,
+    dir: &Path,
+    files: &mut Vec<PathBuf>,
+    depth: usize,
+) -> Result<(), ConfigError> {
+    let Some(includes) = root.remove(INCLUDE_KEY) else {
+        return Ok(());
+    };
+
+    if depth >= MAX_DEPTH {
+        return Err(ConfigError::Message(
+            "include exceeded maximum nesting depth. Does a file include itself?".to_string(),
+        ));
+    }
+
+    let paths = match includes.kind {
+        ValueKind::Array(_) => includes
+            .into_array()?
+            .into_iter()
+            .map(Value::into_string)
+            .collect::<Result<Vec<_>, _>>()?,
+        _ => vec![includes.into_string()?],
+    };
+
+    let mut merged = Map::new();
+
+    for path in paths {
+        let path = dir.join(path);
+
+        let mut included = Config::add_file_source(config::Config::builder(), path.clone())
+            .build()?
+            .collect()?;
+
+        include_into(
+            &mut included,
+            path.parent().unwrap_or(dir),
+            files,
+            depth + 1,
+        )?;
+
+        merge(&mut merged, included);
+        files.push(path);
+    }
+
+    merge(&mut merged, std::mem::take(root));
+    *root = merged;
+
+    Ok(())
+}
+
+/// Merges the selected profile in the top-level `config_profiles` key
+/// over the rest of the config tree.
+///
+/// `profile` takes priority over the top-level `config_profile` key.
+/// Afterwards, `config_profile` contains the name of the applied profile,
+/// or is removed if no profile was applied.
+pub fn apply_profile(root: &mut Map<String, Value>, profile: Option<&str>) {
+    let default = root
+        .remove(PROFILE_KEY)
+        .and_then(|name| name.into_string().ok());
+
+    let Some(name) = profile.map(ToString::to_string).or(default) else {
+        return;
+    };
+
+    let overlay = root
+        .get(PROFILES_KEY)
+        .and_then(|profiles| match &profiles.kind {
+            ValueKind::Table(profiles) => profiles.get(&name),
+            _ => None,
+        })
+        .and_then(|overlay| overlay.clone().into_table().ok());
+
+    let Some(mut overlay) = overlay else {
+        warn!("Config profile '{name}' not found, using the base config");
+        return;
+    };
+
+    // profiles cannot select further profiles
+    overlay.remove(PROFILES_KEY);
+    overlay.remove(PROFILE_KEY);
+
+    merge(root, overlay);
+    root.insert(
+        PROFILE_KEY.to_string(),
+        Value::new(None, ValueKind::String(name)),
+    );
+}
+
+/// Replaces each per-monitor override in the top-level `monitor_overrides` key
+/// with the top-level bar config, merged with the override.
+pub fn expand_overrides(root: &mut Map<String, Value>) {
+    let Some(overrides) = root.remove(OVERRIDES_KEY) else {
+        return;
+    };
+
+    let origin = overrides.origin().map(ToString::to_string);
+
+    let overrides = match overrides.kind {
+        ValueKind::Table(overrides) => overrides,
+        // left as-is to fail deserialization
+        kind => {
+            root.insert(OVERRIDES_KEY.to_string(), Value::new(origin.as_ref(), kind));
+            return;
+        }
+    };
+
+    let mut base = root.clone();
+    for key in NON_BAR_KEYS {
+        base.remove(*key);
+    }
+
+    let overrides = overrides
+        .into_iter()
+        .map(|(monitor, value)| {
+            let origin = value.origin().map(ToString::to_string);
+
+            let kind = match value.kind {
+                ValueKind::Table(table) => {
+                    let mut bar = base.clone();
+                    merge(&mut bar, table);
+                    ValueKind::Table(bar)
+                }
+                kind => kind,
+            };
+
+            (monitor, Value::new(origin.as_ref(), kind))
+        })
+        .collect();
+
+    root.insert(
+        OVERRIDES_KEY.to_string(),
+        Value::new(origin.as_ref(), ValueKind::Table(overrides)),
+    );
+}
+
+/// Merges `overlay` into `base`, recursively merging tables.
+///
+/// Values in `overlay` take priority.
+/// Arrays are replaced rather than merged.
+fn merge(base: &mut Map<String, Value>, overlay: Map<String, Value>) {
+    for (key, value) in overlay {
+        if let Some(existing) = base.get_mut(&key)
+            && let ValueKind::Table(existing) = &mut existing.kind
+            && let ValueKind::Table(table) = &value.kind
+        {
+            merge(existing, table.clone());
+            continue;
+        }
+
+        base.insert(key, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(s: &str) -> Value {
+        Value::new(None, ValueKind::String(s.to_string()))
+    }
+
+    fn table<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::new(
+            None,
+            ValueKind::Table(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v))
+                    .collect(),
+            ),
+        )
+    }
+
+    fn root<const N: usize>(entries: [(&str, Value); N]) -> Map<String, Value> {
+        table(entries).into_table().expect("table")
+    }
+
+    #[test]
+    fn merge_nested_tables() {
+        let mut base = root([
+            ("position", string("top")),
+            (
+                "margin",
+                table([("top", string("5")), ("left", string("5"))]),
+            ),
+        ]);
+
+        merge(
+            &mut base,
+            root([("margin", table([("top", string("10"))]))]),
+        );
+
+        assert_eq!(
+            base,
+            root([
+                ("position", string("top")),
+                (
+                    "margin",
+                    table([("top", string("10")), ("left", string("5"))])
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn apply_selected_profile() {
+        let profiles = || {
+            table([
+                ("work", table([("position", string("bottom"))])),
+                ("gaming", table([("height", string("24"))])),
+            ])
+        };
+
+        let mut config = root([
+            ("position", string("top")),
+            (PROFILE_KEY, string("work")),
+            (PROFILES_KEY, profiles()),
+        ]);
+
+        apply_profile(&mut config, Some("gaming"));
+
+        assert_eq!(
+            config,
+            root([
+                ("position", string("top")),
+                ("height", string("24")),
+                (PROFILE_KEY, string("gaming")),
+                (PROFILES_KEY, profiles()),
+            ])
+        );
+    }
+
+    #[test]
+    fn expand_monitor_override() {
+        let mut config = root([
+            ("position", string("top")),
+            ("height", string("32")),
+            ("monitors", table([])),
+            (
+                OVERRIDES_KEY,
+                table([("DP-1", table([("position", string("bottom"))]))]),
+            ),
+        ]);
+
+        expand_overrides(&mut config);
+
+        assert_eq!(
+            config.get(OVERRIDES_KEY),
+            Some(&table([(
+                "DP-1",
+                table([("position", string("bottom")), ("height", string("32"))])
+            )]))
+        );
+    }
+}
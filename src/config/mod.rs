@@ -1,10 +1,30 @@
 mod common;
 pub mod default;
+#[cfg(any(feature = "custom", feature = "sys_info"))]
+mod graph;
 mod r#impl;
+#[cfg(feature = "config+kdl")]
+mod kdl;
 mod layout;
+#[cfg(all(feature = "config", feature = "extras"))]
+mod lint;
 mod marquee;
+#[cfg(feature = "config")]
+mod merge;
+#[cfg(feature = "osd")]
+mod osd;
+mod positioned;
 mod profiles;
+#[cfg(feature = "ipc")]
+mod remote;
+mod secret;
+#[cfg(feature = "config")]
+mod template;
+mod theme;
+mod thresholds;
 mod truncate;
+#[cfg(feature = "config")]
+mod watch;
 
 #[cfg(feature = "battery")]
 use crate::modules::battery::BatteryModule;
@@ -12,49 +32,107 @@ use crate::modules::battery::BatteryModule;
 use crate::modules::bindmode::Bindmode;
 #[cfg(feature = "bluetooth")]
 use crate::modules::bluetooth::BluetoothModule;
+#[cfg(feature = "brightness")]
+use crate::modules::brightness::BrightnessModule;
 #[cfg(feature = "cairo")]
 use crate::modules::cairo::CairoModule;
+#[cfg(feature = "capture")]
+use crate::modules::capture::CaptureModule;
 #[cfg(feature = "clipboard")]
 use crate::modules::clipboard::ClipboardModule;
 #[cfg(feature = "clock")]
 use crate::modules::clock::ClockModule;
+#[cfg(feature = "containers")]
+use crate::modules::containers::ContainersModule;
 #[cfg(feature = "custom")]
 use crate::modules::custom::CustomModule;
+#[cfg(feature = "feed")]
+use crate::modules::feed::FeedModule;
 #[cfg(feature = "focused")]
 use crate::modules::focused::FocusedModule;
+#[cfg(feature = "git")]
+use crate::modules::git::GitModule;
+#[cfg(feature = "home_assistant")]
+use crate::modules::home_assistant::HomeAssistantModule;
 #[cfg(feature = "inhibit")]
 use crate::modules::inhibit::InhibitModule;
+#[cfg(feature = "kdeconnect")]
+use crate::modules::kdeconnect::KdeConnectModule;
 #[cfg(feature = "keyboard")]
 use crate::modules::keyboard::KeyboardModule;
 #[cfg(feature = "label")]
 use crate::modules::label::LabelModule;
 #[cfg(feature = "launcher")]
 use crate::modules::launcher::LauncherModule;
+#[cfg(feature = "mail")]
+use crate::modules::mail::MailModule;
 #[cfg(feature = "menu")]
 use crate::modules::menu::MenuModule;
+#[cfg(feature = "mqtt")]
+use crate::modules::mqtt::MqttModule;
 #[cfg(feature = "music")]
 use crate::modules::music::MusicModule;
 #[cfg(feature = "network_manager")]
 use crate::modules::networkmanager::NetworkManagerModule;
 #[cfg(feature = "notifications")]
 use crate::modules::notifications::NotificationsModule;
+#[cfg(feature = "power_menu")]
+use crate::modules::power_menu::PowerMenuModule;
+#[cfg(feature = "privacy")]
+use crate::modules::privacy::PrivacyModule;
+#[cfg(feature = "screencast")]
+use crate::modules::screencast::ScreencastModule;
 #[cfg(feature = "script")]
 use crate::modules::script::ScriptModule;
+#[cfg(feature = "separator")]
+use crate::modules::separator::SeparatorModule;
+#[cfg(feature = "syncthing")]
+use crate::modules::syncthing::SyncthingModule;
 #[cfg(feature = "sys_info")]
 use crate::modules::sysinfo::SysInfoModule;
+#[cfg(feature = "systemd")]
+use crate::modules::systemd::SystemdModule;
+#[cfg(feature = "tablet")]
+use crate::modules::tablet::TabletModule;
+#[cfg(feature = "taskbar")]
+use crate::modules::taskbar::TaskbarModule;
+#[cfg(feature = "timer")]
+use crate::modules::timer::TimerModule;
 #[cfg(feature = "tray")]
 use crate::modules::tray::TrayModule;
+#[cfg(feature = "updates")]
+use crate::modules::updates::UpdatesModule;
 #[cfg(feature = "volume")]
 use crate::modules::volume::VolumeModule;
+#[cfg(feature = "vpn")]
+use crate::modules::vpn::VpnModule;
+#[cfg(feature = "wallpaper")]
+use crate::modules::wallpaper::WallpaperModule;
 #[cfg(feature = "workspaces")]
 use crate::modules::workspaces::WorkspacesModule;
 
-pub use self::common::{CommonConfig, ModuleJustification, ModuleOrientation, TransitionType};
+pub use self::common::{
+    CommonConfig, LoadingIndicatorType, ModuleJustification, ModuleOrientation, PopupAnchor,
+    TransitionType,
+};
+#[cfg(any(feature = "custom", feature = "sys_info"))]
+pub use self::graph::{GraphConfig, GraphStyle};
 pub use self::layout::LayoutConfig;
 pub use self::marquee::{MarqueeMode, MarqueeOnHover};
+#[cfg(feature = "osd")]
+pub use self::osd::{OsdConfig, OsdEvent, OsdPosition};
+pub use self::positioned::PositionedConfig;
 pub use self::profiles::{Profile, ProfileUpdateEvent, Profiles, State};
+#[cfg(feature = "ipc")]
+pub use self::remote::{RemoteInstanceConfig, RemoteIpcConfig};
+pub use self::secret::Secret;
+pub use self::theme::{ColorScheme, Palette, ThemeConfig};
+pub use self::thresholds::{ThresholdLevel, Thresholds};
 pub use self::truncate::{EllipsizeMode, TruncateMode};
+#[cfg(feature = "config")]
+pub use self::watch::watch_config;
 
+use crate::dynamic_value::VisibilityRule;
 use gtk::prelude::ObjectExt;
 use std::sync::OnceLock;
 
@@ -95,16 +173,18 @@ pub fn get_double_click_time_ms() -> u64 {
 }
 use crate::Ironbar;
 use crate::modules::{AnyModuleFactory, ModuleFactory, ModuleInfo, ModuleRef};
+use crate::poll::PollingConfig;
 use crate::style::CssSource;
 use cfg_if::cfg_if;
 use color_eyre::Result;
-use config::FileFormat;
+use config::builder::DefaultState;
+use config::{ConfigBuilder, FileFormat, ValueKind};
 #[cfg(feature = "extras")]
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::convert::Infallible;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use tracing::{error, warn};
 
@@ -118,40 +198,83 @@ pub enum ModuleConfig {
     Bindmode(Box<Bindmode>),
     #[cfg(feature = "bluetooth")]
     Bluetooth(Box<BluetoothModule>),
+    #[cfg(feature = "brightness")]
+    Brightness(Box<BrightnessModule>),
     #[cfg(feature = "cairo")]
     Cairo(Box<CairoModule>),
+    #[cfg(feature = "capture")]
+    Capture(Box<CaptureModule>),
     #[cfg(feature = "clipboard")]
     Clipboard(Box<ClipboardModule>),
     #[cfg(feature = "clock")]
     Clock(Box<ClockModule>),
+    #[cfg(feature = "containers")]
+    Containers(Box<ContainersModule>),
     #[cfg(feature = "custom")]
     Custom(Box<CustomModule>),
+    #[cfg(feature = "feed")]
+    Feed(Box<FeedModule>),
     #[cfg(feature = "focused")]
     Focused(Box<FocusedModule>),
+    #[cfg(feature = "git")]
+    Git(Box<GitModule>),
+    #[cfg(feature = "home_assistant")]
+    HomeAssistant(Box<HomeAssistantModule>),
     #[cfg(feature = "inhibit")]
     Inhibit(Box<InhibitModule>),
+    #[cfg(feature = "kdeconnect")]
+    #[serde(rename = "kdeconnect")]
+    KdeConnect(Box<KdeConnectModule>),
     #[cfg(feature = "keyboard")]
     Keyboard(Box<KeyboardModule>),
     #[cfg(feature = "label")]
     Label(Box<LabelModule>),
     #[cfg(feature = "launcher")]
     Launcher(Box<LauncherModule>),
+    #[cfg(feature = "mail")]
+    Mail(Box<MailModule>),
     #[cfg(feature = "menu")]
     Menu(Box<MenuModule>),
+    #[cfg(feature = "mqtt")]
+    Mqtt(Box<MqttModule>),
     #[cfg(feature = "music")]
     Music(Box<MusicModule>),
     #[cfg(feature = "network_manager")]
     NetworkManager(Box<NetworkManagerModule>),
     #[cfg(feature = "notifications")]
     Notifications(Box<NotificationsModule>),
+    #[cfg(feature = "power_menu")]
+    PowerMenu(Box<PowerMenuModule>),
+    #[cfg(feature = "privacy")]
+    Privacy(Box<PrivacyModule>),
+    #[cfg(feature = "screencast")]
+    Screencast(Box<ScreencastModule>),
     #[cfg(feature = "script")]
     Script(Box<ScriptModule>),
+    #[cfg(feature = "separator")]
+    Separator(Box<SeparatorModule>),
+    #[cfg(feature = "syncthing")]
+    Syncthing(Box<SyncthingModule>),
     #[cfg(feature = "sys_info")]
     SysInfo(Box<SysInfoModule>),
+    #[cfg(feature = "systemd")]
+    Systemd(Box<SystemdModule>),
+    #[cfg(feature = "tablet")]
+    Tablet(Box<TabletModule>),
+    #[cfg(feature = "taskbar")]
+    Taskbar(Box<TaskbarModule>),
+    #[cfg(feature = "timer")]
+    Timer(Box<TimerModule>),
     #[cfg(feature = "tray")]
     Tray(Box<TrayModule>),
+    #[cfg(feature = "updates")]
+    Updates(Box<UpdatesModule>),
     #[cfg(feature = "volume")]
     Volume(Box<VolumeModule>),
+    #[cfg(feature = "vpn")]
+    Vpn(Box<VpnModule>),
+    #[cfg(feature = "wallpaper")]
+    Wallpaper(Box<WallpaperModule>),
     #[cfg(feature = "workspaces")]
     Workspaces(Box<WorkspacesModule>),
 }
@@ -176,40 +299,82 @@ impl ModuleConfig {
             Self::Bindmode(module) => create!(module),
             #[cfg(feature = "bluetooth")]
             Self::Bluetooth(module) => create!(module),
+            #[cfg(feature = "brightness")]
+            Self::Brightness(module) => create!(module),
             #[cfg(feature = "cairo")]
             Self::Cairo(module) => create!(module),
+            #[cfg(feature = "capture")]
+            Self::Capture(module) => create!(module),
             #[cfg(feature = "clipboard")]
             Self::Clipboard(module) => create!(module),
             #[cfg(feature = "clock")]
             Self::Clock(module) => create!(module),
+            #[cfg(feature = "containers")]
+            Self::Containers(module) => create!(module),
             #[cfg(feature = "custom")]
             Self::Custom(module) => create!(module),
+            #[cfg(feature = "feed")]
+            Self::Feed(module) => create!(module),
             #[cfg(feature = "focused")]
             Self::Focused(module) => create!(module),
+            #[cfg(feature = "git")]
+            Self::Git(module) => create!(module),
+            #[cfg(feature = "home_assistant")]
+            Self::HomeAssistant(module) => create!(module),
             #[cfg(feature = "inhibit")]
             Self::Inhibit(module) => create!(module),
+            #[cfg(feature = "kdeconnect")]
+            Self::KdeConnect(module) => create!(module),
             #[cfg(feature = "keyboard")]
             Self::Keyboard(module) => create!(module),
             #[cfg(feature = "label")]
             Self::Label(module) => create!(module),
             #[cfg(feature = "launcher")]
             Self::Launcher(module) => create!(module),
+            #[cfg(feature = "mail")]
+            Self::Mail(module) => create!(module),
             #[cfg(feature = "menu")]
             Self::Menu(module) => create!(module),
+            #[cfg(feature = "mqtt")]
+            Self::Mqtt(module) => create!(module),
             #[cfg(feature = "music")]
             Self::Music(module) => create!(module),
             #[cfg(feature = "network_manager")]
             Self::NetworkManager(module) => create!(module),
             #[cfg(feature = "notifications")]
             Self::Notifications(module) => create!(module),
+            #[cfg(feature = "power_menu")]
+            Self::PowerMenu(module) => create!(module),
+            #[cfg(feature = "privacy")]
+            Self::Privacy(module) => create!(module),
+            #[cfg(feature = "screencast")]
+            Self::Screencast(module) => create!(module),
             #[cfg(feature = "script")]
             Self::Script(module) => create!(module),
+            #[cfg(feature = "separator")]
+            Self::Separator(module) => create!(module),
+            #[cfg(feature = "syncthing")]
+            Self::Syncthing(module) => create!(module),
             #[cfg(feature = "sys_info")]
             Self::SysInfo(module) => create!(module),
+            #[cfg(feature = "systemd")]
+            Self::Systemd(module) => create!(module),
+            #[cfg(feature = "tablet")]
+            Self::Tablet(module) => create!(module),
+            #[cfg(feature = "taskbar")]
+            Self::Taskbar(module) => create!(module),
+            #[cfg(feature = "timer")]
+            Self::Timer(module) => create!(module),
             #[cfg(feature = "tray")]
             Self::Tray(module) => create!(module),
+            #[cfg(feature = "updates")]
+            Self::Updates(module) => create!(module),
             #[cfg(feature = "volume")]
             Self::Volume(module) => create!(module),
+            #[cfg(feature = "vpn")]
+            Self::Vpn(module) => create!(module),
+            #[cfg(feature = "wallpaper")]
+            Self::Wallpaper(module) => create!(module),
             #[cfg(feature = "workspaces")]
             Self::Workspaces(module) => create!(module),
         }
@@ -221,40 +386,82 @@ impl ModuleConfig {
             ModuleConfig::Battery(_) => "Battery",
             #[cfg(feature = "bindmode")]
             ModuleConfig::Bindmode(_) => "Bindmode",
+            #[cfg(feature = "brightness")]
+            ModuleConfig::Brightness(_) => "Brightness",
             #[cfg(feature = "cairo")]
             ModuleConfig::Cairo(_) => "Cairo",
+            #[cfg(feature = "capture")]
+            ModuleConfig::Capture(_) => "Capture",
             #[cfg(feature = "clipboard")]
             ModuleConfig::Clipboard(_) => "Clipboard",
             #[cfg(feature = "clock")]
             ModuleConfig::Clock(_) => "Clock",
+            #[cfg(feature = "containers")]
+            ModuleConfig::Containers(_) => "Containers",
             #[cfg(feature = "custom")]
             ModuleConfig::Custom(_) => "Custom",
+            #[cfg(feature = "feed")]
+            ModuleConfig::Feed(_) => "Feed",
             #[cfg(feature = "focused")]
             ModuleConfig::Focused(_) => "Focused",
+            #[cfg(feature = "git")]
+            ModuleConfig::Git(_) => "Git",
+            #[cfg(feature = "home_assistant")]
+            ModuleConfig::HomeAssistant(_) => "HomeAssistant",
             #[cfg(feature = "inhibit")]
             ModuleConfig::Inhibit(_) => "Inhibit",
+            #[cfg(feature = "kdeconnect")]
+            ModuleConfig::KdeConnect(_) => "KdeConnect",
             #[cfg(feature = "keyboard")]
             ModuleConfig::Keyboard(_) => "Keyboard",
             #[cfg(feature = "label")]
             ModuleConfig::Label(_) => "Label",
             #[cfg(feature = "launcher")]
             ModuleConfig::Launcher(_) => "Launcher",
+            #[cfg(feature = "mail")]
+            ModuleConfig::Mail(_) => "Mail",
             #[cfg(feature = "menu")]
             ModuleConfig::Menu(_) => "Menu",
+            #[cfg(feature = "mqtt")]
+            ModuleConfig::Mqtt(_) => "Mqtt",
             #[cfg(feature = "music")]
             ModuleConfig::Music(_) => "Music",
             #[cfg(feature = "network_manager")]
             ModuleConfig::NetworkManager(_) => "NetworkManager",
             #[cfg(feature = "notifications")]
             ModuleConfig::Notifications(_) => "Notifications",
+            #[cfg(feature = "power_menu")]
+            ModuleConfig::PowerMenu(_) => "PowerMenu",
+            #[cfg(feature = "privacy")]
+            ModuleConfig::Privacy(_) => "Privacy",
+            #[cfg(feature = "screencast")]
+            ModuleConfig::Screencast(_) => "Screencast",
             #[cfg(feature = "script")]
             ModuleConfig::Script(_) => "Script",
+            #[cfg(feature = "separator")]
+            ModuleConfig::Separator(_) => "Separator",
+            #[cfg(feature = "syncthing")]
+            ModuleConfig::Syncthing(_) => "Syncthing",
             #[cfg(feature = "sys_info")]
             ModuleConfig::SysInfo(_) => "SysInfo",
+            #[cfg(feature = "systemd")]
+            ModuleConfig::Systemd(_) => "Systemd",
+            #[cfg(feature = "tablet")]
+            ModuleConfig::Tablet(_) => "Tablet",
+            #[cfg(feature = "taskbar")]
+            ModuleConfig::Taskbar(_) => "Taskbar",
+            #[cfg(feature = "timer")]
+            ModuleConfig::Timer(_) => "Timer",
             #[cfg(feature = "tray")]
             ModuleConfig::Tray(_) => "Tray",
+            #[cfg(feature = "updates")]
+            ModuleConfig::Updates(_) => "Updates",
             #[cfg(feature = "volume")]
             ModuleConfig::Volume(_) => "Volume",
+            #[cfg(feature = "vpn")]
+            ModuleConfig::Vpn(_) => "Vpn",
+            #[cfg(feature = "wallpaper")]
+            ModuleConfig::Wallpaper(_) => "Wallpaper",
             #[cfg(feature = "workspaces")]
             ModuleConfig::Workspaces(_) => "Workspaces",
             // in case no modules are compiled
@@ -267,6 +474,7 @@ impl ModuleConfig {
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "extras", derive(JsonSchema))]
+#[cfg_attr(feature = "extras", schemars(untagged))]
 pub enum MonitorConfig {
     Single(BarConfig),
     Multiple(Vec<BarConfig>),
@@ -283,6 +491,91 @@ pub enum BarPosition {
     Right,
 }
 
+#[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+#[cfg_attr(feature = "extras", derive(JsonSchema))]
+pub enum ExclusiveZone {
+    /// Whether to reserve space sized to fit the bar.
+    Auto(bool),
+    /// A fixed amount of space to reserve, in pixels.
+    Pixels(i32),
+}
+
+#[derive(Debug, Default, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(JsonSchema))]
+pub enum BarTransitionType {
+    None,
+    Crossfade,
+    /// Slides in from the edge of the screen the bar is anchored to.
+    #[default]
+    Slide,
+}
+
+#[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+#[cfg_attr(feature = "extras", derive(JsonSchema))]
+pub enum GroupSizes {
+    /// The same size for every group, in pixels.
+    All(i32),
+    /// A separate size for each group, in pixels.
+    Each {
+        #[serde(default)]
+        start: i32,
+        #[serde(default)]
+        center: i32,
+        #[serde(default)]
+        end: i32,
+    },
+}
+
+impl Default for GroupSizes {
+    fn default() -> Self {
+        Self::All(0)
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(JsonSchema))]
+pub enum CenterMode {
+    /// Centered on the bar where there is room,
+    /// otherwise moved aside by larger start or end modules.
+    #[default]
+    Auto,
+    /// Always centered on the bar.
+    Bar,
+    /// Centered in the space between the start and end modules.
+    Space,
+}
+
+#[derive(Debug, Default, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(JsonSchema))]
+pub enum PopupKeyboardMode {
+    /// The popup never receives keyboard input.
+    #[default]
+    None,
+    /// The popup receives keyboard focus when clicked,
+    /// and the compositor may move focus away again.
+    OnDemand,
+    /// The popup holds keyboard focus until it is closed.
+    Exclusive,
+}
+
+#[derive(Debug, Default, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(JsonSchema))]
+pub enum LayoutDirection {
+    /// Follows the text direction of the current locale.
+    #[default]
+    Auto,
+    /// Left-to-right.
+    Ltr,
+    /// Right-to-left.
+    Rtl,
+}
+
 #[derive(Debug, Default, Deserialize, Copy, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "extras", derive(JsonSchema))]
 #[serde(default)]
@@ -304,11 +597,21 @@ pub struct MarginConfig {
 #[serde(default)]
 pub struct BarConfig {
     /// A unique identifier for the bar, used for controlling it over IPC.
-    /// If not set, uses a generated integer suffix.
+    /// If not set, the bar is named after its position.
+    ///
+    /// Names are unique on each monitor.
+    /// Where several bars on a monitor share a name,
+    /// a numeric suffix is added to the later bars, such as `bar-top-2`.
     ///
-    /// **Default**: `bar-n`
+    /// **Default**: `bar-<position>`
     pub name: Option<String>,
 
+    /// Sets one or more CSS classes on the bar window,
+    /// allowing you to target it in CSS using `.class`.
+    ///
+    /// **Default**: `null`
+    pub class: Option<String>,
+
     /// The bar's position on screen.
     ///
     /// **Valid options**: `top`, `bottom`, `left`, `right`
@@ -372,9 +675,13 @@ pub struct BarConfig {
     ///
     /// When true, this prevents windows from rendering in the same space
     /// as the bar, causing them to shift.
+    /// The reserved space is automatically sized to fit the bar and its margin.
+    ///
+    /// This can also be set to a number of pixels to reserve a fixed amount of space,
+    /// for example to leave a larger gap between a floating bar and windows.
     ///
-    /// **Default**: `true` unless `start_hidden` is set.
-    pub exclusive_zone: Option<bool>,
+    /// **Default**: `true` unless `start_hidden` or `intellihide` is set.
+    pub exclusive_zone: Option<ExclusiveZone>,
 
     /// The size of the gap in pixels
     /// between the bar and the popup window.
@@ -390,9 +697,33 @@ pub struct BarConfig {
     /// **Default**: `false`
     pub popup_autohide: bool,
 
+    /// Whether popups take keyboard focus while open.
+    ///
+    /// When set, controls inside the popup can be moved between
+    /// using `Tab` and the arrow keys,
+    /// and the popup can be closed by pressing `Escape`.
+    /// This is required to type into popups, such as password entries.
+    ///
+    /// **Valid options**: `none`, `on_demand`, `exclusive`
+    /// <br>
+    /// **Default**: `none`
+    pub popup_keyboard_mode: PopupKeyboardMode,
+
+    /// Whether to show modules which fail to load on the bar.
+    ///
+    /// When enabled, a module which cannot be created,
+    /// or whose controller fails after it is created,
+    /// is replaced by an error widget, and the bar is marked as degraded,
+    /// rather than the module only being logged and left out.
+    ///
+    /// Modules which panic are always replaced by an error widget.
+    ///
+    /// **Default**: `false`
+    pub strict: bool,
+
     /// Whether the bar should be hidden when Ironbar starts.
     ///
-    /// **Default**: `false`, unless `autohide` is set.
+    /// **Default**: `false`, unless `autohide` is set without `intellihide`.
     pub start_hidden: Option<bool>,
 
     /// The duration in milliseconds before the bar is hidden after the cursor leaves.
@@ -401,6 +732,64 @@ pub struct BarConfig {
     /// **Default**: `null`
     pub autohide: Option<u64>,
 
+    /// The size in pixels of the area along the screen edge
+    /// which reveals the bar when the pointer enters it,
+    /// while the bar is hidden by `autohide`.
+    ///
+    /// **Default**: `5`
+    pub autohide_hotspot_size: i32,
+
+    /// The transition animation to use when revealing and hiding the bar
+    /// using `autohide`.
+    ///
+    /// **Valid options**: `slide`, `crossfade`, `none`
+    /// <br>
+    /// **Default**: `slide`
+    pub autohide_transition_type: BarTransitionType,
+
+    /// The length in milliseconds
+    /// of the transition animation to use when revealing and hiding the bar
+    /// using `autohide`.
+    ///
+    /// **Default**: `250`
+    pub autohide_transition_duration: u32,
+
+    /// Whether `autohide` should only hide the bar
+    /// while a window on the active workspace overlaps it.
+    /// The bar is revealed again once no window overlaps it.
+    ///
+    /// This requires `autohide` to be set,
+    /// and is only supported on Sway and Hyprland.
+    ///
+    /// **Default**: `false`
+    pub intellihide: bool,
+
+    /// Whether to hide the bar while the focused window
+    /// on its monitor is fullscreen.
+    /// The bar is shown again once the window leaves fullscreen or loses focus.
+    ///
+    /// **Default**: `false`
+    pub hide_on_fullscreen: bool,
+
+    /// Shows the bar only while all conditions in the rule are met,
+    /// such as no fullscreen window being focused or a time window.
+    /// The bar is shown again once the conditions are met.
+    ///
+    /// See [visibility rules](#visibility-rules) for the available conditions.
+    ///
+    /// **Default**: `null`
+    pub visible_when: Option<VisibilityRule>,
+
+    /// Shows an on-screen display on the bar's monitor
+    /// when the volume, screen brightness or keyboard layout changes.
+    /// Leave unset to disable.
+    ///
+    /// See [on-screen display](on-screen-display) for the available options.
+    ///
+    /// **Default**: `null`
+    #[cfg(feature = "osd")]
+    pub osd: Option<OsdConfig>,
+
     /// An array of modules to append to the start of the bar.
     /// Depending on the orientation, this is either the top of the left edge.
     ///
@@ -412,11 +801,59 @@ pub struct BarConfig {
     /// **Default**: `[]`
     pub center: Option<Vec<ModuleConfig>>,
 
+    /// How the `center` modules are centered.
+    ///
+    /// With `auto`, they are centered on the bar where there is room,
+    /// but are pushed aside if the `start` or `end` modules would overlap them.
+    /// With `bar`, they are always centered on the bar,
+    /// even if the `start` or `end` modules would run underneath them.
+    /// With `space`, they are centered in the space
+    /// left between the `start` and `end` modules.
+    ///
+    /// **Valid options**: `auto`, `bar`, `space`
+    /// <br>
+    /// **Default**: `auto`
+    pub center_mode: CenterMode,
+
     /// An array of modules to append to the end of the bar.
     /// Depending on the orientation, this is either the bottom or right edge.
     ///
     /// **Default**: `[]`
     pub end: Option<Vec<ModuleConfig>>,
+
+    /// The space between modules inside each of the
+    /// `start`, `center` and `end` groups, in pixels.
+    ///
+    /// This can be a single value for every group,
+    /// or an object setting each group separately.
+    ///
+    /// **Default**: `0`
+    ///
+    /// # Example
+    ///
+    /// ```corn
+    /// {
+    ///     spacing.start = 4
+    ///     spacing.end = 8
+    /// }
+    /// ```
+    pub spacing: GroupSizes,
+
+    /// The space, in pixels, along the bar either side of each of the
+    /// `start`, `center` and `end` groups.
+    ///
+    /// As with `spacing`, this can be a single value for every group,
+    /// or an object setting each group separately.
+    ///
+    /// **Default**: `0`
+    pub padding: GroupSizes,
+
+    /// An array of module groups anchored at a fixed point along the bar,
+    /// either in pixels or as a percentage of its length.
+    /// These are drawn over the `start`, `center` and `end` modules.
+    ///
+    /// **Default**: `[]`
+    pub positioned: Option<Vec<PositionedConfig>>,
 }
 
 impl Default for BarConfig {
@@ -425,17 +862,32 @@ impl Default for BarConfig {
             position: BarPosition::default(),
             margin: MarginConfig::default(),
             name: None,
+            class: None,
             layer: gtk_layer_shell::Layer::Top,
             exclusive_zone: None,
             height: 42,
             start_hidden: None,
             autohide: None,
+            autohide_hotspot_size: 5,
+            autohide_transition_type: BarTransitionType::default(),
+            autohide_transition_duration: 250,
+            intellihide: false,
+            hide_on_fullscreen: false,
+            visible_when: None,
+            #[cfg(feature = "osd")]
+            osd: None,
             start: None,
             center: None,
+            center_mode: CenterMode::default(),
             end: None,
+            spacing: GroupSizes::default(),
+            padding: GroupSizes::default(),
+            positioned: None,
             anchor_to_edges: true,
             popup_gap: 5,
             popup_autohide: false,
+            popup_keyboard_mode: PopupKeyboardMode::default(),
+            strict: false,
         }
     }
 }
@@ -460,17 +912,68 @@ pub struct Config {
     /// The variable can then be immediately fetched without needing to be manually set:
     ///
     /// ```sh
-    /// $ ironbar get foo
+    /// $ ironbar var get foo
     /// ok
     /// bar
     /// ```
-    pub ironvar_defaults: Option<HashMap<Box<str>, String>>,
+    ///
+    /// Each variable takes the type of its default,
+    /// so booleans, numbers, lists and maps declare typed variables.
+    #[cfg(any(feature = "ipc", feature = "cairo"))]
+    pub ironvar_defaults: Option<HashMap<Box<str>, crate::ironvar::IronvarValue>>,
+
+    /// A map of [ironvar](ironvar) keys against their declarations,
+    /// setting each variable's type, default value and whether it is persisted.
+    ///
+    /// **Default**: `{}`
+    ///
+    /// # Example
+    ///
+    /// The following declares a counter which is restored after a restart:
+    ///
+    /// ```corn
+    /// { ironvars.counter = { type = "int" default = 0 persist = true } }
+    /// ```
+    #[cfg(any(feature = "ipc", feature = "cairo"))]
+    pub ironvars: Option<HashMap<Box<str>, crate::ironvar::IronvarConfig>>,
+
+    /// Listens for IPC commands over TCP,
+    /// allowing Ironbar to be controlled from other machines.
+    ///
+    /// Each connection must authenticate with the configured token.
+    /// See [remote control](ipc#remote-control) for more info.
+    ///
+    /// **Default**: `null`
+    #[cfg(feature = "ipc")]
+    pub ipc_remote: Option<RemoteIpcConfig>,
+
+    /// Map of names against remote Ironbar instances
+    /// to copy variables from into local ironvars,
+    /// allowing modules to show values from other machines.
+    ///
+    /// See [mirroring remote instances](ipc#mirroring-remote-instances) for more info.
+    ///
+    /// **Default**: `{}`
+    #[cfg(feature = "ipc")]
+    pub remotes: HashMap<String, RemoteInstanceConfig>,
 
     /// The configuration for the bar.
     /// Setting through this will enable a single identical bar on each monitor.
     #[serde(flatten)]
     pub bar: BarConfig,
 
+    /// A list of additional bar configs.
+    /// Each of these is shown on every monitor
+    /// not otherwise configured in `monitors`,
+    /// alongside the top-level bar.
+    ///
+    /// This can be used to show multiple bars on each monitor,
+    /// such as a top info bar and a bottom taskbar.
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    pub bars: Vec<BarConfig>,
+
     /// A map of monitor names to configs.
     /// Monitor names can be supplied in two formats:
     ///
@@ -486,6 +989,74 @@ pub struct Config {
     /// Providing this option overrides the single, global `bar` option.
     pub monitors: Option<HashMap<String, MonitorConfig>>,
 
+    /// A map of monitor names to partial bar configs,
+    /// which are merged over the top-level bar config for that monitor.
+    /// Monitor names are matched in the same way as for `monitors`,
+    /// which takes priority where both match.
+    ///
+    /// Nested objects are merged, while other values (including module lists) are replaced.
+    ///
+    /// **Default**: `{}`
+    ///
+    /// # Example
+    ///
+    /// The following moves the bar to the bottom of `DP-2`, and replaces its `end` modules:
+    ///
+    /// ```corn
+    /// {
+    ///     monitor_overrides.DP-2 = {
+    ///         position = "bottom"
+    ///         end = [ { type = "clock" } ]
+    ///     }
+    /// }
+    /// ```
+    pub monitor_overrides: Option<HashMap<String, BarConfig>>,
+
+    /// A map of profile names to partial configs,
+    /// which can be switched between at runtime using `ironbar profile set`.
+    /// The active profile is merged over the rest of the config
+    /// in the same way as `monitor_overrides`.
+    ///
+    /// See [config profiles](#36-config-profiles) for more info.
+    ///
+    /// **Default**: `{}`
+    ///
+    /// # Example
+    ///
+    /// ```corn
+    /// {
+    ///     config_profiles.presentation = {
+    ///         start = []
+    ///         end = [ { type = "clock" } ]
+    ///     }
+    /// }
+    /// ```
+    pub config_profiles: HashMap<String, Config>,
+
+    /// The name of the profile in `config_profiles` to use on startup.
+    /// Leave unset to use the base config.
+    ///
+    /// This is replaced by the name of the active profile when the config is loaded,
+    /// and is exposed as the `config_profile` [ironvar](ironvars).
+    ///
+    /// **Default**: `null`
+    pub config_profile: Option<String>,
+
+    /// A list of monitors to never show bars on.
+    /// Names are matched in the same way as for `monitors`.
+    ///
+    /// **Default**: `[]`
+    pub exclude_monitors: Vec<String>,
+
+    /// Whether to only show bars on the primary monitor.
+    /// This is the first connected monitor not listed in `exclude_monitors`.
+    ///
+    /// If the primary monitor is disconnected,
+    /// bars move to the next available monitor.
+    ///
+    /// **Default**: `false`
+    pub primary_only: bool,
+
     /// The name of the GTK icon theme to use.
     /// Leave unset to use the default system theme.
     ///
@@ -498,12 +1069,76 @@ pub struct Config {
     /// **Default**: `{}`
     pub icon_overrides: HashMap<String, String>,
 
+    /// The memory budget for cached icons and images, in megabytes.
+    /// Once exceeded, the least recently used images are evicted.
+    ///
+    /// **Default**: `64`
+    pub image_cache_size: Option<u32>,
+
+    /// The number of updates each module can queue
+    /// before slow widgets start to miss them.
+    /// Increase this if `ironbar debug lag` reports modules lagging behind.
+    ///
+    /// **Default**: `64`
+    pub channel_capacity: Option<usize>,
+
+    /// The maximum number of scripts which can run at once.
+    /// Further scripts wait until one finishes.
+    /// Long-running `watch` scripts do not count towards this.
+    ///
+    /// **Default**: `8`
+    pub script_concurrency: Option<usize>,
+
+    /// The minimum time in milliseconds between runs of the same script.
+    /// Poll scripts with a shorter interval run at this interval instead,
+    /// and repeat runs of the same command within it are skipped.
+    ///
+    /// **Default**: `250`
+    pub script_min_interval: Option<u64>,
+
+    /// Options for batching and slowing down interval-driven work,
+    /// such as polling scripts, refreshing system info and updating the clock.
+    ///
+    /// See [polling schedule](#polling-schedule) for more info.
+    pub polling: PollingConfig,
+
     /// The time in milliseconds to wait for a double-click.
     /// Can be set to a number (e.g., `250`) or `"gtk"` to use GTK's setting.
     ///
     /// **Default**: `250`
     #[serde(default)]
     pub double_click_time: DoubleClickTime,
+
+    /// Turns off all animations,
+    /// including module and bar transitions, smooth value changes,
+    /// marquee scrolling and animations set in CSS.
+    ///
+    /// **Default**: `false`
+    pub disable_animations: bool,
+
+    /// The direction to lay out bars, popups and text in.
+    ///
+    /// In right-to-left mode, the `start` and `end` module groups
+    /// on horizontal bars are mirrored, as are the contents of each module
+    /// and the horizontal offsets of positioned groups and popups.
+    ///
+    /// **Valid options**: `auto`, `ltr`, `rtl`
+    /// <br>
+    /// **Default**: `auto`
+    pub direction: LayoutDirection,
+
+    /// Named color and size tokens to make available to the stylesheet
+    /// as CSS variables, with separate light and dark palettes.
+    ///
+    /// See [theme tokens](styling-guide#theme-tokens) for more info.
+    ///
+    /// **Default**: `{}`
+    pub theme: ThemeConfig,
+
+    /// The paths of all files included by the config,
+    /// which are watched for changes alongside it.
+    #[serde(skip)]
+    pub included_files: Vec<PathBuf>,
 }
 
 /// Double-click time configuration
@@ -581,46 +1216,72 @@ impl ErrorLevel {
     }
 }
 
+/// Checks whether the path is to a supported stylesheet format.
+#[cfg(feature = "config")]
+fn is_stylesheet(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext == "css" || (cfg!(feature = "scss") && ext == "scss"))
+}
+
+/// Gets the path to the stylesheet in a directory,
+/// preferring `style.scss` over `style.css` where it exists.
+#[cfg(feature = "config")]
+fn find_stylesheet(dir: &Path) -> PathBuf {
+    let scss_path = dir.join("style.scss");
+
+    if cfg!(feature = "scss") && scss_path.exists() {
+        scss_path
+    } else {
+        dir.join("style.css")
+    }
+}
+
+/// Name of the ironvar containing the active config profile.
+#[cfg(all(feature = "config", any(feature = "ipc", feature = "cairo")))]
+const PROFILE_VARIABLE: &str = "config_profile";
+
+cfg_if! {
+    if #[cfg(feature = "config+corn")] {
+        const CONFIG_MINIMAL: (&str, FileFormat) = (include_str!("../../examples/minimal/config.corn"), FileFormat::Corn);
+        const CONFIG_DESKTOP: (&str, FileFormat) = (include_str!("../../examples/desktop/config.corn"), FileFormat::Corn);
+    } else if #[cfg(feature = "config+json")] {
+        const CONFIG_MINIMAL: (&str, FileFormat) = (include_str!("../../examples/minimal/config.json"), FileFormat::Json);
+        const CONFIG_DESKTOP: (&str, FileFormat) = (include_str!("../../examples/desktop/config.json"), FileFormat::Json);
+    } else if #[cfg(feature = "config+yaml")] {
+        const CONFIG_MINIMAL: (&str, FileFormat) = (include_str!("../../examples/minimal/config.yaml"), FileFormat::Yaml);
+        const CONFIG_DESKTOP: (&str, FileFormat) = (include_str!("../../examples/desktop/config.yaml"), FileFormat::Yaml);
+    } else if #[cfg(feature = "config+toml")] {
+        const CONFIG_MINIMAL: (&str, FileFormat) = (include_str!("../../examples/minimal/config.toml"), FileFormat::Toml);
+        const CONFIG_DESKTOP: (&str, FileFormat) = (include_str!("../../examples/desktop/config.toml"), FileFormat::Toml);
+    } else if #[cfg(feature = "config+kdl")] {
+        const CONFIG_MINIMAL: (&str, kdl::Kdl) = (include_str!("../../examples/minimal/config.kdl"), kdl::Kdl);
+        const CONFIG_DESKTOP: (&str, kdl::Kdl) = (include_str!("../../examples/desktop/config.kdl"), kdl::Kdl);
+    }
+}
+
 impl Config {
     #[cfg(feature = "config")]
     pub fn load(
         config_location: ConfigLocation,
         css_location: Option<ConfigLocation>,
+        profile: Option<&str>,
     ) -> (Config, CssSource, ErrorLevel) {
         let mut error_level = ErrorLevel::None;
 
-        cfg_if! {
-            if #[cfg(feature = "config+corn")] {
-                const CONFIG_MINIMAL: (&str, FileFormat) = (include_str!("../../examples/minimal/config.corn"), FileFormat::Corn);
-                const CONFIG_DESKTOP: (&str, FileFormat) = (include_str!("../../examples/desktop/config.corn"), FileFormat::Corn);
-            } else if #[cfg(feature = "config+json")] {
-                const CONFIG_MINIMAL: (&str, FileFormat) = (include_str!("../../examples/minimal/config.json"), FileFormat::Json);
-                const CONFIG_DESKTOP: (&str, FileFormat) = (include_str!("../../examples/desktop/config.json"), FileFormat::Json);
-            } else if #[cfg(feature = "config+yaml")] {
-                const CONFIG_MINIMAL: (&str, FileFormat) = (include_str!("../../examples/minimal/config.yaml"), FileFormat::Yaml);
-                const CONFIG_DESKTOP: (&str, FileFormat) = (include_str!("../../examples/desktop/config.yaml"), FileFormat::Yaml);
-            } else if #[cfg(feature = "config+toml")] {
-                const CONFIG_MINIMAL: (&str, FileFormat) = (include_str!("../../examples/minimal/config.toml"), FileFormat::Toml);
-                const CONFIG_DESKTOP: (&str, FileFormat) = (include_str!("../../examples/desktop/config.toml"), FileFormat::Toml);
-            }
-        }
-
         const CSS_MINIMAL: CssSource =
             CssSource::String(include_str!("../../examples/minimal/style.css"));
 
         const CSS_DESKTOP: CssSource =
             CssSource::String(include_str!("../../examples/desktop/style.css"));
 
-        let config_builder = config::Config::builder();
-
         let css_source = match css_location.unwrap_or_else(|| config_location.clone()) {
             ConfigLocation::Minimal => CSS_MINIMAL,
             ConfigLocation::Desktop => CSS_DESKTOP,
             ConfigLocation::Custom(mut path) => {
                 if path.is_dir() {
-                    path = path.join("style.css")
-                } else if path.extension().is_none_or(|ext| ext != "css") {
-                    path = path.parent().unwrap_or(&path).join("style.css");
+                    path = find_stylesheet(&path);
+                } else if !is_stylesheet(&path) {
+                    path = find_stylesheet(path.parent().unwrap_or(&path));
                 };
 
                 if path.exists() {
@@ -636,19 +1297,8 @@ impl Config {
             }
         };
 
-        let config_builder = match config_location {
-            ConfigLocation::Minimal => config_builder
-                .add_source(config::File::from_str(CONFIG_MINIMAL.0, CONFIG_MINIMAL.1)),
-            ConfigLocation::Desktop => config_builder
-                .add_source(config::File::from_str(CONFIG_DESKTOP.0, CONFIG_DESKTOP.1)),
-            ConfigLocation::Custom(path) => config_builder.add_source(config::File::from(path)),
-        };
-
-        let mut config: Config = config_builder
-            .add_source(config::Environment::with_prefix("IRONBAR_"))
-            .build()
-            .and_then(|conf| conf.try_deserialize())
-            .unwrap_or_else(|err| {
+        let mut config =
+            Self::parse(config_location, profile, &mut error_level).unwrap_or_else(|err| {
                 error_level = error_level.error();
                 error!("Error loading config: {err:?}");
                 config::Config::builder()
@@ -659,30 +1309,145 @@ impl Config {
                     .expect("should be a valid config")
             });
 
-        #[cfg(any(feature = "ipc", feature = "cairo"))]
-        if let Some(ironvars) = config.ironvar_defaults.take() {
-            use crate::ironvar::WritableNamespace;
+        config.init_globals(&mut error_level);
+
+        (config, css_source, error_level)
+    }
+
+    /// Re-reads the config from disk.
+    ///
+    /// Unlike [`Config::load`], this returns an error
+    /// rather than falling back to the minimal config.
+    #[cfg(feature = "config")]
+    pub fn reload(config_location: ConfigLocation, profile: Option<&str>) -> Result<Config> {
+        let mut error_level = ErrorLevel::None;
+
+        let mut config = Self::parse(config_location, profile, &mut error_level)?;
+        config.init_globals(&mut error_level);
+
+        Ok(config)
+    }
+
+    /// Reads the config at `config_location`,
+    /// expanding templates, linting it and applying the selected profile.
+    ///
+    /// If `profile` is not set, the profile set in the config is used.
+    #[cfg(feature = "config")]
+    fn parse(
+        config_location: ConfigLocation,
+        profile: Option<&str>,
+        error_level: &mut ErrorLevel,
+    ) -> std::result::Result<Config, config::ConfigError> {
+        let config_builder = config::Config::builder();
+
+        // included paths are relative to the config file
+        let mut dir = PathBuf::new();
 
+        let config_builder = match config_location {
+            ConfigLocation::Minimal => config_builder
+                .add_source(config::File::from_str(CONFIG_MINIMAL.0, CONFIG_MINIMAL.1)),
+            ConfigLocation::Desktop => config_builder
+                .add_source(config::File::from_str(CONFIG_DESKTOP.0, CONFIG_DESKTOP.1)),
+            ConfigLocation::Custom(path) => {
+                dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+                Self::add_file_source(config_builder, path)
+            }
+        };
+
+        let mut included_files = vec![];
+
+        let mut config = config_builder
+            .add_source(config::Environment::with_prefix("IRONBAR_"))
+            .build()
+            .and_then(|conf| {
+                let mut root = conf.collect()?;
+                included_files = merge::include(&mut root, &dir)?;
+                template::expand(root)
+            })
+            .and_then(|mut root| {
+                #[cfg(feature = "extras")]
+                for diagnostic in lint::lint(&root) {
+                    if diagnostic.is_error() {
+                        *error_level = error_level.error();
+                        error!("{diagnostic}");
+                    } else {
+                        *error_level = error_level.warn();
+                        warn!("{diagnostic}");
+                    }
+                }
+
+                merge::apply_profile(&mut root, profile);
+                merge::expand_overrides(&mut root);
+
+                Config::deserialize(config::Value::new(None, ValueKind::Table(root)))
+            })?;
+
+        config.included_files = included_files;
+        Ok(config)
+    }
+
+    /// Applies the parts of the config which are stored globally,
+    /// such as ironvar defaults.
+    #[cfg(feature = "config")]
+    fn init_globals(&mut self, error_level: &mut ErrorLevel) {
+        #[cfg(any(feature = "ipc", feature = "cairo"))]
+        {
             let variable_manager = Ironbar::variable_manager();
-            for (k, v) in ironvars {
-                if variable_manager.set(&k, v).is_err() {
-                    error_level = error_level.warn();
-                    warn!("Ignoring invalid ironvar: '{k}'");
+
+            if let Some(ironvars) = self.ironvar_defaults.take() {
+                for (k, v) in ironvars {
+                    if variable_manager.set_value(&k, v).is_err() {
+                        *error_level = error_level.warn();
+                        warn!("Ignoring invalid ironvar: '{k}'");
+                    }
+                }
+            }
+
+            if let Some(ironvars) = self.ironvars.take() {
+                for (k, v) in ironvars {
+                    if let Err(err) = variable_manager.declare(&k, v) {
+                        *error_level = error_level.warn();
+                        warn!("Ignoring invalid ironvar '{k}': {err}");
+                    }
                 }
             }
+
+            let profile = crate::ironvar::IronvarValue::String(
+                self.config_profile.clone().unwrap_or_default(),
+            );
+            if let Err(err) = variable_manager.set_value(PROFILE_VARIABLE, profile) {
+                *error_level = error_level.warn();
+                warn!("Failed to set ironvar '{PROFILE_VARIABLE}': {err}");
+            }
         }
 
         // Store the double-click time globally
         // GTK's setting will be set lazily on first use (after GTK is initialized)
-        set_double_click_time(config.double_click_time.clone());
+        set_double_click_time(self.double_click_time.clone());
+    }
 
-        (config, css_source, error_level)
+    /// Adds the config file at `path` as a source,
+    /// detecting the format from its extension.
+    #[cfg(feature = "config")]
+    fn add_file_source(
+        config_builder: ConfigBuilder<DefaultState>,
+        path: PathBuf,
+    ) -> ConfigBuilder<DefaultState> {
+        // KDL is not natively supported by the `config` crate,
+        // so needs to be explicitly checked for first.
+        #[cfg(feature = "config+kdl")]
+        if let Some(path) = kdl::find_file(&path) {
+            return config_builder.add_source(config::File::new(&path.to_string_lossy(), kdl::Kdl));
+        }
+
+        config_builder.add_source(config::File::from(path))
     }
 
     #[cfg(not(feature = "config"))]
     pub fn load(
         config_location: ConfigLocation,
         css_location: Option<ConfigLocation>,
+        profile: Option<&str>,
     ) -> (Config, CssSource) {
         panic!(
             "Ironbar has been configured without config support. This won't work. Please reconfigure with at least one `config` feature flag enabled."
@@ -0,0 +1,109 @@
+#[cfg(feature = "volume")]
+use crate::clients::volume;
+use serde::Deserialize;
+
+/// Configuration for the on-screen display,
+/// which briefly shows the new value when a setting changes.
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct OsdConfig {
+    /// The position of the on-screen display on the bar's monitor.
+    ///
+    /// **Valid options**: `top`, `center`, `bottom`
+    /// <br>
+    /// **Default**: `bottom`
+    pub position: OsdPosition,
+
+    /// The distance in pixels between the on-screen display
+    /// and the screen edge it is positioned against.
+    /// Has no effect when `position` is `center`.
+    ///
+    /// **Default**: `100`
+    pub margin: i32,
+
+    /// The number of milliseconds to show the on-screen display for
+    /// after the last change.
+    ///
+    /// **Default**: `1500`
+    pub timeout: u64,
+
+    /// The changes which show the on-screen display.
+    ///
+    /// **Valid options**: `volume`, `brightness`, `keyboard_layout`
+    /// <br>
+    /// **Default**: `["volume", "brightness", "keyboard_layout"]`
+    pub events: Vec<OsdEvent>,
+
+    /// The icon to show for volume changes.
+    ///
+    /// **Default**: `󰕾`
+    pub icon_volume: String,
+
+    /// The icon to show for volume changes while muted.
+    ///
+    /// **Default**: `󰝟`
+    pub icon_muted: String,
+
+    /// The icon to show for brightness changes.
+    ///
+    /// **Default**: `󰃠`
+    pub icon_brightness: String,
+
+    /// The icon to show for keyboard layout changes.
+    ///
+    /// **Default**: `󰌌`
+    pub icon_keyboard_layout: String,
+
+    /// The audio server API to use for volume changes.
+    /// Either `pulse` or `pipewire`.
+    ///
+    /// **Default**: `pulse`
+    #[cfg(feature = "volume")]
+    pub volume_backend: volume::ClientType,
+}
+
+impl Default for OsdConfig {
+    fn default() -> Self {
+        Self {
+            position: OsdPosition::default(),
+            margin: 100,
+            timeout: 1500,
+            events: vec![
+                OsdEvent::Volume,
+                OsdEvent::Brightness,
+                OsdEvent::KeyboardLayout,
+            ],
+            icon_volume: "󰕾".to_string(),
+            icon_muted: "󰝟".to_string(),
+            icon_brightness: "󰃠".to_string(),
+            icon_keyboard_layout: "󰌌".to_string(),
+            #[cfg(feature = "volume")]
+            volume_backend: volume::ClientType::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum OsdPosition {
+    Top,
+    Center,
+    #[default]
+    Bottom,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum OsdEvent {
+    /// The volume or mute state of the default output device.
+    /// Requires the `volume` feature.
+    Volume,
+    /// The brightness of the screen backlight.
+    Brightness,
+    /// The active keyboard layout.
+    /// Requires the `keyboard` feature.
+    KeyboardLayout,
+}
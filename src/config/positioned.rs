@@ -0,0 +1,156 @@
+use super::ModuleConfig;
+use serde::Deserialize;
+
+/// A group of modules anchored at a fixed point along the bar,
+/// independent of the `start`, `center` and `end` containers.
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct PositionedConfig {
+    /// The point along the bar to anchor the group at.
+    /// This can be a number of pixels from the start of the bar,
+    /// or a percentage of its length, such as `"25%"`.
+    #[cfg_attr(feature = "extras", schemars(schema_with = "schema_offset"))]
+    pub offset: GroupOffset,
+
+    /// Which part of the group is placed on the anchor point.
+    ///
+    /// **Valid options**: `start`, `center`, `end`
+    /// <br>
+    /// **Default**: `center`
+    #[serde(default)]
+    pub align: GroupAlign,
+
+    /// Name of the group container, used for targeting in CSS.
+    ///
+    /// **Default**: `null`
+    pub name: Option<String>,
+
+    /// The space between modules in the group, in pixels.
+    ///
+    /// **Default**: `0`
+    #[serde(default)]
+    pub spacing: i32,
+
+    /// Array of modules to place in the group.
+    pub modules: Vec<ModuleConfig>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "OffsetValue")]
+pub enum GroupOffset {
+    Pixels(i32),
+    Percent(f64),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OffsetValue {
+    Pixels(i32),
+    String(String),
+}
+
+impl TryFrom<OffsetValue> for GroupOffset {
+    type Error = String;
+
+    fn try_from(value: OffsetValue) -> Result<Self, Self::Error> {
+        match value {
+            OffsetValue::Pixels(pixels) => Ok(Self::Pixels(pixels)),
+            OffsetValue::String(string) => string.parse(),
+        }
+    }
+}
+
+impl std::str::FromStr for GroupOffset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let err = || format!("invalid offset '{s}': expected pixels or a percentage");
+
+        if let Some(percent) = s.strip_suffix('%') {
+            percent.trim().parse().map(Self::Percent).map_err(|_| err())
+        } else {
+            s.strip_suffix("px")
+                .unwrap_or(s)
+                .trim()
+                .parse()
+                .map(Self::Pixels)
+                .map_err(|_| err())
+        }
+    }
+}
+
+impl GroupOffset {
+    /// Resolves the offset to a pixel value
+    /// along a bar of the given length.
+    pub fn resolve(self, length: i32) -> i32 {
+        match self {
+            Self::Pixels(pixels) => pixels,
+            Self::Percent(percent) => (f64::from(length) * percent / 100.0).round() as i32,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum GroupAlign {
+    Start,
+    #[default]
+    Center,
+    End,
+}
+
+impl GroupAlign {
+    /// Gets the position of a group of the given size
+    /// along a bar of the given length,
+    /// keeping the group within the bar.
+    pub fn position(self, offset: GroupOffset, size: i32, length: i32) -> i32 {
+        let anchor = offset.resolve(length);
+
+        let start = match self {
+            Self::Start => anchor,
+            Self::Center => anchor - size / 2,
+            Self::End => anchor - size,
+        };
+
+        start.clamp(0, (length - size).max(0))
+    }
+}
+
+#[cfg(feature = "extras")]
+fn schema_offset(_generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+    schemars::json_schema!({
+        "type": ["integer", "string"],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_offset() {
+        assert_eq!("25%".parse(), Ok(GroupOffset::Percent(25.0)));
+        assert_eq!("100px".parse(), Ok(GroupOffset::Pixels(100)));
+        assert_eq!("100".parse(), Ok(GroupOffset::Pixels(100)));
+        assert!("left".parse::<GroupOffset>().is_err());
+    }
+
+    #[test]
+    fn position_group() {
+        let offset = GroupOffset::Percent(25.0);
+        assert_eq!(GroupAlign::Center.position(offset, 100, 1000), 200);
+        assert_eq!(GroupAlign::Start.position(offset, 100, 1000), 250);
+        assert_eq!(GroupAlign::End.position(offset, 100, 1000), 150);
+    }
+
+    #[test]
+    fn position_clamped() {
+        let offset = GroupOffset::Pixels(0);
+        assert_eq!(GroupAlign::Center.position(offset, 100, 1000), 0);
+
+        let offset = GroupOffset::Percent(100.0);
+        assert_eq!(GroupAlign::Start.position(offset, 100, 1000), 900);
+    }
+}
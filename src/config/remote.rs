@@ -0,0 +1,59 @@
+use super::Secret;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Configuration for controlling Ironbar over the network.
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct RemoteIpcConfig {
+    /// The address and port to listen for connections on.
+    ///
+    /// Use `0.0.0.0` to accept connections from other machines,
+    /// or `127.0.0.1` to only accept local connections.
+    ///
+    /// **Default**: `127.0.0.1:7420`
+    #[serde(default = "default_address")]
+    pub address: String,
+
+    /// The token clients must send before each command.
+    /// Connections with a missing or incorrect token are rejected.
+    ///
+    /// See [secrets](configuration-guide#34-secrets) for other ways to provide this.
+    pub token: Secret,
+}
+
+fn default_address() -> String {
+    String::from("127.0.0.1:7420")
+}
+
+/// A remote Ironbar instance to mirror variables from,
+/// using its [remote IPC](ipc#remote-control).
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct RemoteInstanceConfig {
+    /// The address and port of the remote instance,
+    /// as set in its `ipc_remote.address`.
+    pub address: String,
+
+    /// The token to authenticate with the remote instance.
+    ///
+    /// See [secrets](configuration-guide#34-secrets) for other ways to provide this.
+    pub token: Secret,
+
+    /// Map of local ironvar keys against the remote keys to copy into them.
+    /// Remote keys can be inside namespaces, such as `sysinfo.cpu_percent.mean`.
+    ///
+    /// **Default**: `{}`
+    #[serde(default)]
+    pub vars: HashMap<Box<str>, Box<str>>,
+
+    /// The number of seconds between each refresh.
+    ///
+    /// **Default**: `5`
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+}
+
+const fn default_interval() -> u64 {
+    5
+}
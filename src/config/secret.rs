@@ -0,0 +1,126 @@
+use color_eyre::eyre::WrapErr;
+use color_eyre::{Help, Report, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter};
+use std::process::{Command, Output};
+
+/// A credential such as a password, API key or access token.
+///
+/// This can be written directly in the config,
+/// read from the output of a command,
+/// or looked up in the [Secret Service](https://specifications.freedesktop.org/secret-service-spec/latest/)
+/// (GNOME Keyring, KWallet, KeePassXC) using libsecret's `secret-tool`.
+///
+/// # Example
+///
+/// ```corn
+/// {
+///     // written directly in the config
+///     token = "abc123"
+///
+///     // read from the first line of the command's output
+///     token.command = "pass show home-assistant"
+///
+///     // looked up by its attributes in the Secret Service
+///     token.secret_service = { service = "home-assistant" user = "jake" }
+/// }
+/// ```
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum Secret {
+    /// The secret itself, written in plain text.
+    Plain(String),
+    /// A shell command which writes the secret to `stdout`.
+    Command { command: String },
+    /// The attributes of the secret to look up in the Secret Service.
+    SecretService {
+        secret_service: BTreeMap<String, String>,
+    },
+}
+
+impl Debug for Secret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // the secret itself must never end up in logs
+        match self {
+            Self::Plain(_) => write!(f, "Plain(<redacted>)"),
+            Self::Command { command } => {
+                f.debug_struct("Command").field("command", command).finish()
+            }
+            Self::SecretService { secret_service } => f
+                .debug_struct("SecretService")
+                .field("secret_service", secret_service)
+                .finish(),
+        }
+    }
+}
+
+impl Secret {
+    /// Gets the value of the secret,
+    /// running the command or querying the Secret Service if necessary.
+    ///
+    /// This blocks until the command exits,
+    /// which may include waiting for the user to unlock a keyring.
+    pub fn resolve(&self) -> Result<String> {
+        match self {
+            Self::Plain(secret) => Ok(secret.clone()),
+            Self::Command { command } => {
+                let output = Command::new("/bin/sh").args(["-c", command]).output()?;
+                first_line(output).wrap_err(format!("Failed to get secret from `{command}`"))
+            }
+            Self::SecretService { secret_service } => {
+                let args = secret_service
+                    .iter()
+                    .flat_map(|(attribute, value)| [attribute, value]);
+
+                let output = Command::new("secret-tool")
+                    .arg("lookup")
+                    .args(args)
+                    .output()
+                    .map_err(Report::new)
+                    .suggestion("Is libsecret installed?")?;
+
+                first_line(output)
+                    .wrap_err("Failed to find secret in the Secret Service")
+                    .suggestion("Check the attributes match with `secret-tool search`")
+            }
+        }
+    }
+}
+
+/// Gets the first line of the command's `stdout`,
+/// or an error containing its `stderr` if it failed.
+fn first_line(output: Output) -> Result<String> {
+    if !output.status.success() {
+        return Err(Report::msg(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    let stdout = String::from_utf8(output.stdout)?;
+    match stdout.lines().next() {
+        Some(line) if !line.is_empty() => Ok(line.to_string()),
+        _ => Err(Report::msg("No output")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_command() {
+        let secret = Secret::Command {
+            command: "printf 'hunter2\\nignored'".to_string(),
+        };
+
+        assert_eq!(secret.resolve().unwrap(), "hunter2");
+    }
+
+    #[test]
+    fn test_debug_redacts_plain() {
+        let secret = Secret::Plain("hunter2".to_string());
+        assert!(!format!("{secret:?}").contains("hunter2"));
+    }
+}
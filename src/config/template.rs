@@ -0,0 +1,281 @@
+use config::{ConfigError, Map, Value, ValueKind};
+
+/// Top-level key containing template definitions.
+const TEMPLATES_KEY: &str = "templates";
+/// Module key referencing the template to instantiate.
+const USE_KEY: &str = "use";
+/// Key containing parameters, on both templates (defaults) and instances.
+const ARGS_KEY: &str = "args";
+
+/// Maximum depth of nested template references,
+/// used to catch templates which reference themselves.
+const MAX_DEPTH: usize = 16;
+
+/// Expands all template instances in the config tree.
+///
+/// Templates are defined in the top-level `templates` map,
+/// and instantiated by any object containing a `use` key.
+/// `${name}` placeholders inside the template are replaced
+/// with the values from the instance's `args` (falling back to the template's `args`).
+/// Any other keys on the instance override those in the template.
+///
+/// The `templates` key is removed from the tree.
+pub fn expand(mut root: Map<String, Value>) -> Result<Map<String, Value>, ConfigError> {
+    let templates = match root.remove(TEMPLATES_KEY) {
+        Some(templates) => templates.into_table()?,
+        None => return Ok(root),
+    };
+
+    root.into_iter()
+        .map(|(key, value)| Ok((key, expand_value(value, &templates, 0)?)))
+        .collect()
+}
+
+fn expand_value(
+    value: Value,
+    templates: &Map<String, Value>,
+    depth: usize,
+) -> Result<Value, ConfigError> {
+    let origin = value.origin().map(ToString::to_string);
+
+    let kind = match value.kind {
+        ValueKind::Table(table) if table.contains_key(USE_KEY) => {
+            return instantiate(table, templates, depth);
+        }
+        ValueKind::Table(table) => ValueKind::Table(
+            table
+                .into_iter()
+                .map(|(key, value)| Ok((key, expand_value(value, templates, depth)?)))
+                .collect::<Result<_, ConfigError>>()?,
+        ),
+        ValueKind::Array(array) => ValueKind::Array(
+            array
+                .into_iter()
+                .map(|value| expand_value(value, templates, depth))
+                .collect::<Result<_, ConfigError>>()?,
+        ),
+        kind => kind,
+    };
+
+    Ok(Value::new(origin.as_ref(), kind))
+}
+
+/// Creates a new value from the template referenced by `instance`.
+fn instantiate(
+    mut instance: Map<String, Value>,
+    templates: &Map<String, Value>,
+    depth: usize,
+) -> Result<Value, ConfigError> {
+    let name = instance
+        .remove(USE_KEY)
+        .map(Value::into_string)
+        .transpose()?
+        .unwrap_or_default();
+
+    if depth >= MAX_DEPTH {
+        return Err(ConfigError::Message(format!(
+            "template '{name}' exceeded maximum nesting depth. Does it reference itself?"
+        )));
+    }
+
+    let Some(template) = templates.get(&name) else {
+        return Err(ConfigError::Message(format!(
+            "template '{name}' is not defined"
+        )));
+    };
+
+    let origin = template.origin().map(ToString::to_string);
+    let mut template = template.clone().into_table()?;
+
+    let mut args = match template.remove(ARGS_KEY) {
+        Some(defaults) => defaults.into_table()?,
+        None => Map::new(),
+    };
+
+    if let Some(instance_args) = instance.remove(ARGS_KEY) {
+        args.extend(instance_args.into_table()?);
+    }
+
+    let mut table = substitute_table(template, &args);
+    table.extend(instance);
+
+    expand_value(
+        Value::new(origin.as_ref(), ValueKind::Table(table)),
+        templates,
+        depth + 1,
+    )
+}
+
+fn substitute_table(table: Map<String, Value>, args: &Map<String, Value>) -> Map<String, Value> {
+    table
+        .into_iter()
+        .map(|(key, value)| (key, substitute(value, args)))
+        .collect()
+}
+
+/// Replaces `${name}` placeholders inside `value` with their argument values.
+///
+/// Where a string consists solely of a single placeholder,
+/// the argument value is used as-is, preserving its type.
+/// Placeholders which do not match an argument are left untouched.
+fn substitute(value: Value, args: &Map<String, Value>) -> Value {
+    let origin = value.origin().map(ToString::to_string);
+
+    let kind = match value.kind {
+        ValueKind::String(string) => {
+            if let Some(arg) = string
+                .strip_prefix("${")
+                .and_then(|s| s.strip_suffix('}'))
+                .and_then(|name| args.get(name))
+            {
+                return arg.clone();
+            }
+
+            ValueKind::String(substitute_str(&string, args))
+        }
+        ValueKind::Table(table) => ValueKind::Table(substitute_table(table, args)),
+        ValueKind::Array(array) => ValueKind::Array(
+            array
+                .into_iter()
+                .map(|value| substitute(value, args))
+                .collect(),
+        ),
+        kind => kind,
+    };
+
+    Value::new(origin.as_ref(), kind)
+}
+
+fn substitute_str(string: &str, args: &Map<String, Value>) -> String {
+    let mut result = String::with_capacity(string.len());
+    let mut rest = string;
+
+    while let Some(start) = rest.find("${") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+
+        match after
+            .find('}')
+            .map(|end| (end, &after[..end]))
+            .and_then(|(end, name)| args.get(name).map(|arg| (end, arg)))
+        {
+            Some((end, arg)) => {
+                result.push_str(&arg.to_string());
+                rest = &after[end + 1..];
+            }
+            None => {
+                result.push_str("${");
+                rest = after;
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(s: &str) -> Value {
+        Value::new(None, ValueKind::String(s.to_string()))
+    }
+
+    fn table<const N: usize>(entries: [(&str, Value); N]) -> Value {
+        Value::new(
+            None,
+            ValueKind::Table(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v))
+                    .collect(),
+            ),
+        )
+    }
+
+    fn args<const N: usize>(entries: [(&str, Value); N]) -> Map<String, Value> {
+        table(entries).into_table().expect("table")
+    }
+
+    #[test]
+    fn substitute_inline() {
+        let args = args([("cmd", string("poweroff"))]);
+        assert_eq!(
+            substitute_str("!systemctl ${cmd} now", &args),
+            "!systemctl poweroff now"
+        );
+    }
+
+    #[test]
+    fn substitute_unknown_untouched() {
+        let args = args([("cmd", string("poweroff"))]);
+        assert_eq!(substitute_str("echo ${HOME}", &args), "echo ${HOME}");
+    }
+
+    #[test]
+    fn substitute_preserves_type() {
+        let args = args([("size", Value::new(None, ValueKind::I64(32)))]);
+        let value = substitute(string("${size}"), &args);
+        assert_eq!(value.into_int().ok(), Some(32));
+    }
+
+    #[test]
+    fn expand_instance() {
+        let root = args([
+            (
+                "templates",
+                table([(
+                    "btn",
+                    table([
+                        ("type", string("label")),
+                        ("label", string("${icon}")),
+                        ("args", table([("icon", string("x"))])),
+                    ]),
+                )]),
+            ),
+            (
+                "end",
+                Value::new(
+                    None,
+                    ValueKind::Array(vec![
+                        table([("use", string("btn"))]),
+                        table([
+                            ("use", string("btn")),
+                            ("args", table([("icon", string("y"))])),
+                            ("name", string("second")),
+                        ]),
+                    ]),
+                ),
+            ),
+        ]);
+
+        let root = expand(root).expect("to expand");
+        assert!(!root.contains_key("templates"));
+
+        let end = root["end"].clone().into_array().expect("array");
+        let first = end[0].clone().into_table().expect("table");
+        let second = end[1].clone().into_table().expect("table");
+
+        assert_eq!(first["label"].clone().into_string().ok(), Some("x".into()));
+        assert_eq!(second["label"].clone().into_string().ok(), Some("y".into()));
+        assert_eq!(
+            second["name"].clone().into_string().ok(),
+            Some("second".into())
+        );
+        assert!(!second.contains_key("args"));
+    }
+
+    #[test]
+    fn recursive_template() {
+        let root = args([
+            (
+                "templates",
+                table([("loop", table([("use", string("loop"))]))]),
+            ),
+            ("end", table([("use", string("loop"))])),
+        ]);
+
+        assert!(expand(root).is_err());
+    }
+}
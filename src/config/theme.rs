@@ -0,0 +1,140 @@
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+#[derive(Debug, Default, Deserialize, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum ColorScheme {
+    /// Follows the system color scheme.
+    #[default]
+    Auto,
+    Light,
+    Dark,
+}
+
+/// A set of named tokens,
+/// with separate overrides for the light and dark color schemes.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct Palette {
+    /// Tokens used with both color schemes.
+    ///
+    /// **Default**: `{}`
+    pub tokens: HashMap<String, String>,
+
+    /// Tokens used with the light color scheme,
+    /// taking priority over `tokens`.
+    ///
+    /// **Default**: `{}`
+    pub light: HashMap<String, String>,
+
+    /// Tokens used with the dark color scheme,
+    /// taking priority over `tokens`.
+    ///
+    /// **Default**: `{}`
+    pub dark: HashMap<String, String>,
+}
+
+/// Named color and size tokens,
+/// made available to the stylesheet as CSS variables.
+///
+/// # Example
+///
+/// ```corn
+/// {
+///     theme.tokens.radius = "6px"
+///     theme.light.accent = "#1e66f5"
+///     theme.dark.accent = "#89b4fa"
+/// }
+/// ```
+///
+/// These can then be used in CSS with `var(--accent)`.
+#[derive(Debug, Default, Deserialize, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct ThemeConfig {
+    #[serde(flatten)]
+    pub palette: Palette,
+
+    /// The path to a palette file, in any supported config format,
+    /// containing `tokens`, `light` and `dark` keys.
+    /// Tokens set directly in the config take priority over the file.
+    ///
+    /// Pywal `colors.json` files are also accepted,
+    /// providing the `background`, `foreground`, `cursor`
+    /// and `color0` to `color15` tokens,
+    /// as are matugen `--json hex` files,
+    /// providing each generated color for the light and dark schemes.
+    ///
+    /// The file is watched, and tokens are updated when it changes.
+    /// A leading `~` is expanded to the home directory.
+    ///
+    /// **Default**: `null`
+    pub palette_file: Option<PathBuf>,
+
+    /// Whether to load tokens from the current pywal color scheme,
+    /// at `~/.cache/wal/colors.json`.
+    /// This is the same as setting `palette_file` to that path,
+    /// and can be used alongside another palette file.
+    ///
+    /// **Default**: `false`
+    pub wal: bool,
+
+    /// The color scheme to use tokens for.
+    /// With `auto`, this follows the system setting,
+    /// switching automatically when it changes.
+    ///
+    /// **Valid options**: `auto`, `light`, `dark`
+    /// <br>
+    /// **Default**: `auto`
+    pub color_scheme: ColorScheme,
+}
+
+impl ThemeConfig {
+    /// Gets the paths of each palette file to load tokens from,
+    /// in order of priority.
+    pub fn palette_files(&self) -> Vec<PathBuf> {
+        let wal = self
+            .wal
+            .then(|| dirs::cache_dir().map(|dir| dir.join("wal/colors.json")))
+            .flatten();
+
+        let palette_file = self.palette_file.as_ref().map(|path| {
+            match (path.strip_prefix("~"), dirs::home_dir()) {
+                (Ok(rest), Some(home)) => home.join(rest),
+                _ => path.clone(),
+            }
+        });
+
+        palette_file.into_iter().chain(wal).collect()
+    }
+}
+
+impl Palette {
+    /// Adds the tokens from `other` which are not already set.
+    pub fn merge(&mut self, other: Palette) {
+        for (tokens, other) in [
+            (&mut self.tokens, other.tokens),
+            (&mut self.light, other.light),
+            (&mut self.dark, other.dark),
+        ] {
+            for (key, value) in other {
+                tokens.entry(key).or_insert(value);
+            }
+        }
+    }
+
+    /// Gets the tokens to use for the light or dark color scheme,
+    /// sorted by name.
+    pub fn resolve(&self, dark: bool) -> BTreeMap<&str, &str> {
+        let scheme = if dark { &self.dark } else { &self.light };
+
+        self.tokens
+            .iter()
+            .chain(scheme)
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect()
+    }
+}
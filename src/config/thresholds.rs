@@ -0,0 +1,147 @@
+use gtk::Widget;
+use gtk::prelude::*;
+use serde::Deserialize;
+
+/// A warning state, in increasing order of severity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ThresholdLevel {
+    Low,
+    Warning,
+    Critical,
+}
+
+impl ThresholdLevel {
+    const ALL: [Self; 3] = [Self::Low, Self::Warning, Self::Critical];
+
+    /// Gets the CSS class applied while in this state.
+    pub const fn class_name(self) -> &'static str {
+        match self {
+            Self::Low => "low",
+            Self::Warning => "warning",
+            Self::Critical => "critical",
+        }
+    }
+
+    /// Applies the class for `level` to the widget,
+    /// removing the classes for any other states.
+    ///
+    /// Passing `None` removes all state classes.
+    pub fn apply<W: IsA<Widget>>(widget: &W, level: Option<Self>) {
+        for other in Self::ALL {
+            if Some(other) == level {
+                widget.add_css_class(other.class_name());
+            } else {
+                widget.remove_css_class(other.class_name());
+            }
+        }
+    }
+}
+
+/// The values at which a module enters each warning state,
+/// applying the `.low`, `.warning` or `.critical` class.
+///
+/// Whether a state is entered above or below its value
+/// depends on the module.
+/// For example, battery states apply as the charge drops,
+/// while CPU states apply as usage rises.
+///
+/// # Example
+///
+/// ```corn
+/// { thresholds.warning = 20 thresholds.critical = 10 }
+/// ```
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct Thresholds {
+    /// The value at which the `.low` class is applied.
+    ///
+    /// **Default**: `null`
+    pub low: Option<f64>,
+
+    /// The value at which the `.warning` class is applied.
+    ///
+    /// **Default**: `null`
+    pub warning: Option<f64>,
+
+    /// The value at which the `.critical` class is applied.
+    ///
+    /// **Default**: `null`
+    pub critical: Option<f64>,
+}
+
+impl Thresholds {
+    pub const fn new(low: f64, warning: f64, critical: f64) -> Self {
+        Self {
+            low: Some(low),
+            warning: Some(warning),
+            critical: Some(critical),
+        }
+    }
+
+    const fn threshold(&self, level: ThresholdLevel) -> Option<f64> {
+        match level {
+            ThresholdLevel::Low => self.low,
+            ThresholdLevel::Warning => self.warning,
+            ThresholdLevel::Critical => self.critical,
+        }
+    }
+
+    /// Gets the most severe state reached by `value`.
+    ///
+    /// If `rising` is true, states are reached at or above their value,
+    /// otherwise at or below it.
+    pub fn level(&self, value: f64, rising: bool) -> Option<ThresholdLevel> {
+        ThresholdLevel::ALL.into_iter().rev().find(|&level| {
+            self.threshold(level).is_some_and(|threshold| {
+                if rising {
+                    value >= threshold
+                } else {
+                    value <= threshold
+                }
+            })
+        })
+    }
+
+    /// Applies the class for the state reached by `value` to the widget,
+    /// removing the classes for any other states.
+    ///
+    /// Passing `None` removes all state classes.
+    pub fn apply<W: IsA<Widget>>(&self, widget: &W, value: Option<f64>, rising: bool) {
+        let level = value.and_then(|value| self.level(value, rising));
+        ThresholdLevel::apply(widget, level);
+    }
+
+    /// Whether no state values are set.
+    pub const fn is_empty(&self) -> bool {
+        self.low.is_none() && self.warning.is_none() && self.critical.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_falling() {
+        let thresholds = Thresholds::new(30.0, 15.0, 5.0);
+
+        assert_eq!(thresholds.level(50.0, false), None);
+        assert_eq!(thresholds.level(30.0, false), Some(ThresholdLevel::Low));
+        assert_eq!(thresholds.level(10.0, false), Some(ThresholdLevel::Warning));
+        assert_eq!(thresholds.level(0.0, false), Some(ThresholdLevel::Critical));
+    }
+
+    #[test]
+    fn level_rising() {
+        let thresholds = Thresholds {
+            warning: Some(70.0),
+            critical: Some(90.0),
+            ..Thresholds::default()
+        };
+
+        assert_eq!(thresholds.level(50.0, true), None);
+        assert_eq!(thresholds.level(75.0, true), Some(ThresholdLevel::Warning));
+        assert_eq!(thresholds.level(95.0, true), Some(ThresholdLevel::Critical));
+    }
+}
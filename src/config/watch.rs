@@ -0,0 +1,210 @@
+use super::{Config, ConfigLocation};
+use crate::channels::{AsyncSenderExt, MpscReceiverExt};
+use crate::{Ironbar, arc_mut, lock};
+use glib::SourceId;
+use gtk::prelude::*;
+use gtk::{Application, gio};
+use notify::{Event, EventKind, RecursiveMode, Watcher, recommended_watcher};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info};
+
+/// Time to wait after the last change before reloading,
+/// as editors often save a file in several steps.
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// ID of the notification shown when the config fails to reload,
+/// so that it is replaced rather than duplicated.
+const ERROR_NOTIFICATION_ID: &str = "config-error";
+
+/// File extensions of the supported config formats.
+const EXTENSIONS: &[&str] = &[
+    "json", "json5", "toml", "yaml", "yml", "ini", "ron", "corn", "kdl",
+];
+
+/// Installs a file watcher on the config file and any files it includes,
+/// reloading the config and rebuilding the bars when one changes.
+///
+/// If the new config fails to load, the current bars are kept
+/// and a notification is shown with the error.
+pub fn watch_config(ironbar: &Rc<Ironbar>, app: &Application) {
+    let ConfigLocation::Custom(path) = &ironbar.config_location else {
+        return;
+    };
+
+    let (tx, rx) = mpsc::channel(8);
+
+    // the config file and all files it includes
+    let watched = arc_mut!(vec![]);
+
+    let watcher = {
+        let watched = watched.clone();
+        recommended_watcher(move |res: notify::Result<Event>| match res {
+            // editors often replace the file rather than writing to it
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                let watched = lock!(watched);
+                if event
+                    .paths
+                    .iter()
+                    .any(|changed| watched.iter().any(|file| is_config_file(changed, file)))
+                {
+                    tx.send_spawn(());
+                }
+            }
+            Err(err) => error!("Error occurred when watching config: {err:?}"),
+            _ => {}
+        })
+    };
+
+    let watcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(err) => {
+            error!("Failed to create config file watcher: {err:?}");
+            return;
+        }
+    };
+
+    let watcher = Rc::new(RefCell::new(watcher));
+    let watched_dirs = Rc::new(RefCell::new(HashSet::new()));
+    let path = path.clone();
+
+    let update_watched = move |ironbar: &Ironbar| {
+        let files = std::iter::once(path.clone())
+            .chain(ironbar.config.borrow().included_files.iter().cloned())
+            .collect();
+
+        watch_files(
+            &mut *watcher.borrow_mut(),
+            files,
+            &watched,
+            &mut watched_dirs.borrow_mut(),
+        );
+    };
+
+    update_watched(ironbar);
+
+    let ironbar = ironbar.clone();
+    let update_watched = Rc::new(update_watched);
+    let pending = Rc::new(RefCell::new(None::<SourceId>));
+
+    rx.recv_glib(app, move |app, ()| {
+        if let Some(source) = pending.take() {
+            source.remove();
+        }
+
+        let ironbar = ironbar.clone();
+        let app = app.clone();
+        let update_watched = update_watched.clone();
+        let source = {
+            let pending = pending.clone();
+            glib::timeout_add_local_once(DEBOUNCE, move || {
+                pending.take();
+
+                if reload(&ironbar, &app) {
+                    // includes may have been added or removed
+                    update_watched(&ironbar);
+                }
+            })
+        };
+
+        pending.replace(Some(source));
+    });
+}
+
+/// Updates the set of watched files,
+/// watching any directories containing those files not already watched.
+fn watch_files(
+    watcher: &mut impl Watcher,
+    files: Vec<PathBuf>,
+    watched: &Arc<Mutex<Vec<PathBuf>>>,
+    watched_dirs: &mut HashSet<PathBuf>,
+) {
+    // file watcher requires absolute paths
+    let files = files
+        .into_iter()
+        .map(|path| {
+            if path.is_absolute() {
+                path
+            } else {
+                env::current_dir().expect("to exist").join(path)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    for dir_path in files.iter().filter_map(|path| path.parent()) {
+        if watched_dirs.contains(dir_path) {
+            continue;
+        }
+
+        match watcher.watch(dir_path, RecursiveMode::NonRecursive) {
+            Ok(()) => {
+                debug!("Installed config file watcher on '{}'", dir_path.display());
+                watched_dirs.insert(dir_path.to_path_buf());
+            }
+            Err(err) => error!(
+                "Failed to start config file watcher on '{}': {err:?}",
+                dir_path.display()
+            ),
+        }
+    }
+
+    *lock!(watched) = files;
+}
+
+/// Checks whether a changed file is the config file.
+///
+/// The config path may omit its extension,
+/// in which case a file in any supported format matches.
+fn is_config_file(changed: &Path, config: &Path) -> bool {
+    if config.extension().is_some() {
+        return changed == config;
+    }
+
+    changed.parent() == config.parent()
+        && changed.file_stem() == config.file_name()
+        && changed
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| EXTENSIONS.contains(&ext))
+}
+
+/// Reloads the config and rebuilds the bars,
+/// or shows a notification if the config fails to load.
+///
+/// Returns whether the config loaded successfully.
+fn reload(ironbar: &Rc<Ironbar>, app: &Application) -> bool {
+    info!("Config changed, reloading");
+
+    match Config::reload(
+        ironbar.config_location.clone(),
+        ironbar.profile.borrow().as_deref(),
+    ) {
+        Ok(config) => {
+            app.withdraw_notification(ERROR_NOTIFICATION_ID);
+
+            ironbar.config.replace(config);
+            ironbar.reload_bars(app);
+            ironbar.reload_services();
+
+            true
+        }
+        Err(err) => {
+            error!("Failed to reload config, keeping the current config: {err:?}");
+
+            let notification = gio::Notification::new("Ironbar config error");
+            notification.set_body(Some(&format!(
+                "The config failed to load, so the previous config is still in use.\n\n{err}"
+            )));
+
+            app.send_notification(Some(ERROR_NOTIFICATION_ID), &notification);
+
+            false
+        }
+    }
+}
@@ -57,12 +57,52 @@ impl DesktopFileRef {
         let mut has_icon = false;
         let mut has_categories = false;
         let mut has_no_display = false;
+        let mut has_actions = false;
+
+        let mut group = Group::Other;
+        // action IDs with their names and commands, in their declared order
+        let mut actions: Vec<(String, Option<String>, Option<String>)> = vec![];
 
         while let Ok(Some(line)) = lines.next_line().await {
+            if let Some(header) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                // parsing complete - don't bother with the rest of the lines
+                if group == Group::Entry && actions.is_empty() {
+                    break;
+                }
+
+                group = match header {
+                    "Desktop Entry" => Group::Entry,
+                    _ => header
+                        .strip_prefix("Desktop Action ")
+                        .and_then(|id| actions.iter().position(|(action_id, ..)| action_id == id))
+                        .map_or(Group::Other, Group::Action),
+                };
+
+                continue;
+            }
+
             let Some((key, value)) = line.split_once('=') else {
                 continue;
             };
 
+            if let Group::Action(index) = group {
+                let (_, name, exec) = &mut actions[index];
+                match key {
+                    "Name" if name.is_none() => *name = Some(value.to_string()),
+                    "Exec" if exec.is_none() => *exec = Some(value.to_string()),
+                    _ => {}
+                }
+
+                continue;
+            }
+
+            if group != Group::Entry {
+                continue;
+            }
+
             match key {
                 "Name" if !has_name => {
                     desktop_file.name = Some(value.to_string());
@@ -92,26 +132,42 @@ impl DesktopFileRef {
                     desktop_file.no_display = Some(value.parse()?);
                     has_no_display = true;
                 }
+                "Actions" if !has_actions => {
+                    actions = value
+                        .split(';')
+                        .filter(|id| !id.is_empty())
+                        .map(|id| (id.to_string(), None, None))
+                        .collect();
+                    has_actions = true;
+                }
                 _ => {}
             }
-
-            // parsing complete - don't bother with the rest of the lines
-            if has_name
-                && has_type
-                && has_wm_class
-                && has_exec
-                && has_icon
-                && has_categories
-                && has_no_display
-            {
-                break;
-            }
         }
 
+        desktop_file.actions = actions
+            .into_iter()
+            .filter_map(|(id, name, exec)| {
+                Some(DesktopAction {
+                    id,
+                    name: name?,
+                    exec: exec?,
+                })
+            })
+            .collect();
+
         Ok(desktop_file)
     }
 }
 
+/// The group of keys currently being parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Group {
+    Entry,
+    /// The index of the action in its declared order.
+    Action(usize),
+    Other,
+}
+
 #[derive(Debug, Clone)]
 pub struct DesktopFile {
     pub file_name: String,
@@ -122,6 +178,17 @@ pub struct DesktopFile {
     pub icon: Option<String>,
     pub categories: Vec<String>,
     pub no_display: Option<bool>,
+    /// Additional ways to launch the application,
+    /// such as opening a new private window.
+    pub actions: Vec<DesktopAction>,
+}
+
+/// An action declared in the `Actions` key of a desktop file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesktopAction {
+    pub id: String,
+    pub name: String,
+    pub exec: String,
 }
 
 impl DesktopFile {
@@ -135,6 +202,7 @@ impl DesktopFile {
             icon: None,
             categories: vec![],
             no_display: None,
+            actions: vec![],
         }
     }
 }
@@ -334,15 +402,41 @@ pub async fn open_program(file_name: &str, launch_command: &str) {
         .map(|part| part.replace("{app_name}", file_name))
         .collect();
 
+    run(&launch_command_parts, None).await;
+}
+
+/// Starts a desktop file action.
+///
+/// The activation token allows the launched application
+/// to take focus on compositors which require it.
+pub async fn open_action(action: &DesktopAction, activation_token: Option<&str>) {
+    let args = exec_args(&action.exec);
+    if args.is_empty() {
+        error!("Desktop action '{}' has an empty command", action.id);
+        return;
+    }
+
+    run(&args, activation_token).await;
+}
+
+async fn run(launch_command_parts: &[String], activation_token: Option<&str>) {
     debug!("running {launch_command_parts:?}");
-    let exit_status = match Command::new(&launch_command_parts[0])
+
+    let mut command = Command::new(&launch_command_parts[0]);
+    command
         .args(&launch_command_parts[1..])
         .stdin(Stdio::null())
         .stdout(Stdio::null())
         .stderr(Stdio::null())
-        .kill_on_drop(true)
-        .spawn()
-    {
+        .kill_on_drop(true);
+
+    if let Some(token) = activation_token {
+        command
+            .env("XDG_ACTIVATION_TOKEN", token)
+            .env("DESKTOP_STARTUP_ID", token);
+    }
+
+    let exit_status = match command.spawn() {
         Ok(mut child) => Some(child.wait().await),
         Err(err) => {
             error!(
@@ -364,6 +458,49 @@ pub async fn open_program(file_name: &str, launch_command: &str) {
     }
 }
 
+/// Splits a desktop file `Exec` value into its arguments,
+/// following the quoting rules of the desktop entry spec.
+///
+/// Field codes such as `%u` are removed,
+/// as no files or URLs are passed when launching.
+fn exec_args(exec: &str) -> Vec<String> {
+    let mut args = vec![];
+
+    let mut arg = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+
+    let mut chars = exec.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                quoted = true;
+            }
+            '\\' if in_quotes => arg.extend(chars.next()),
+            // field codes are dropped
+            '%' => {
+                if chars.next() == Some('%') {
+                    arg.push('%');
+                }
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !arg.is_empty() || quoted {
+                    args.push(std::mem::take(&mut arg));
+                }
+                quoted = false;
+            }
+            c => arg.push(c),
+        }
+    }
+
+    if !arg.is_empty() || quoted {
+        args.push(arg);
+    }
+
+    args
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -415,6 +552,33 @@ mod tests {
         assert_eq!(file.startup_wm_class, Some("firefox".to_string()));
         assert_eq!(file.app_type, Some("Application".to_string()));
 
+        assert_eq!(file.actions.len(), 3);
+        assert_eq!(
+            file.actions[0],
+            DesktopAction {
+                id: "new-window".to_string(),
+                name: "New Window".to_string(),
+                exec: "/usr/lib/firefox/firefox --new-window %u".to_string(),
+            }
+        );
+
         Ok(())
     }
+
+    #[test]
+    fn exec_args_removes_field_codes() {
+        assert_eq!(
+            exec_args("/usr/lib/firefox/firefox --private-window %u"),
+            ["/usr/lib/firefox/firefox", "--private-window"]
+        );
+        assert_eq!(exec_args("app --percent=100%%"), ["app", "--percent=100%"]);
+    }
+
+    #[test]
+    fn exec_args_quoting() {
+        assert_eq!(
+            exec_args(r#""/opt/My App/app" "" --name "a \"b\"" end"#),
+            ["/opt/My App/app", "", "--name", r#"a "b""#, "end"]
+        );
+    }
 }
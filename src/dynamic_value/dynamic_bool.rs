@@ -1,10 +1,14 @@
 #[cfg(feature = "ipc")]
+use super::expression::Expression;
+#[cfg(feature = "ipc")]
 use crate::Ironbar;
 use crate::channels::{AsyncSenderExt, Dependency, MpscReceiverExt};
 use crate::script::Script;
 use crate::spawn;
 use cfg_if::cfg_if;
 use serde::Deserialize;
+#[cfg(feature = "ipc")]
+use std::collections::HashMap;
 use tokio::sync::mpsc;
 
 #[derive(Debug, Deserialize, Clone)]
@@ -16,6 +20,11 @@ pub enum DynamicBool {
     Script(Script),
     #[cfg(feature = "ipc")]
     Variable(Box<str>),
+    /// An expression referencing at least one variable,
+    /// such as `$battery < 20`.
+    #[cfg(feature = "ipc")]
+    #[serde(skip)]
+    Expression(Expression),
 }
 
 impl DynamicBool {
@@ -36,8 +45,19 @@ impl DynamicBool {
                         }
                     }
                 } else {
-                    let script = Script::from(input.as_str());
-                    Self::Script(script)
+                    let script = || Self::Script(Script::from(input.as_str()));
+
+                    // expressions must reference a variable,
+                    // so existing scripts keep working
+                    cfg_if! {
+                        if #[cfg(feature = "ipc")] {
+                            Expression::parse(&input)
+                                .filter(|expression| !expression.variables().is_empty())
+                                .map_or_else(script, Self::Expression)
+                        } else {
+                            script()
+                        }
+                    }
                 }
             }
             _ => self,
@@ -68,6 +88,38 @@ impl DynamicBool {
                         tx.send_expect(has_value).await;
                     }
                 }
+                #[cfg(feature = "ipc")]
+                DynamicBool::Expression(expression) => {
+                    let variable_manager = Ironbar::variable_manager();
+                    let (values_tx, mut values_rx) = mpsc::channel(32);
+
+                    for name in expression.variables() {
+                        let mut rx = variable_manager.subscribe(name.clone());
+                        let values_tx = values_tx.clone();
+
+                        spawn(async move {
+                            while let Ok(value) = rx.recv().await {
+                                values_tx.send_expect((name.clone(), value)).await;
+                            }
+                        });
+                    }
+
+                    let mut variables = HashMap::new();
+                    let mut last = None;
+
+                    while let Some((name, value)) = values_rx.recv().await {
+                        match value {
+                            Some(value) => variables.insert(name, value),
+                            None => variables.remove(&name),
+                        };
+
+                        let value = expression.evaluate(&variables).is_truthy();
+                        if last != Some(value) {
+                            last = Some(value);
+                            tx.send_expect(value).await;
+                        }
+                    }
+                }
                 DynamicBool::Unknown(_) => unreachable!(),
             }
         });
@@ -1,19 +1,107 @@
 #[cfg(feature = "ipc")]
+use super::expression::Expression;
+#[cfg(feature = "ipc")]
 use crate::Ironbar;
 use crate::channels::{AsyncSenderExt, Dependency, MpscReceiverExt};
 use crate::script::{OutputStream, Script};
 use crate::{arc_mut, lock, spawn};
+#[cfg(feature = "ipc")]
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use tokio::sync::mpsc;
 
 /// A segment of a dynamic string,
-/// containing either a static string
-/// or a script.
+/// containing either a static string,
+/// a script, a variable or an expression tag.
 #[derive(Debug)]
 enum DynamicStringSegment {
     Static(String),
     Script(Script),
     #[cfg(feature = "ipc")]
     Variable(Box<str>),
+    /// `{{ $var + 1 }}`
+    #[cfg(feature = "ipc")]
+    Expression(Expression),
+    /// `{{ if $var < 20 }}`
+    #[cfg(feature = "ipc")]
+    If(Expression),
+    /// `{{ else }}`
+    #[cfg(feature = "ipc")]
+    Else,
+    /// `{{ end }}`
+    #[cfg(feature = "ipc")]
+    End,
+}
+
+/// How each segment is rendered into the final string.
+#[derive(Debug)]
+enum Block {
+    /// The segment's latest value.
+    Value,
+    #[cfg(feature = "ipc")]
+    Expression(Expression),
+    #[cfg(feature = "ipc")]
+    If(Expression),
+    #[cfg(feature = "ipc")]
+    Else,
+    #[cfg(feature = "ipc")]
+    End,
+}
+
+/// The latest value of each segment,
+/// and of each variable referenced by expressions.
+#[derive(Debug, Default)]
+struct Values {
+    segments: Vec<String>,
+    #[cfg(feature = "ipc")]
+    variables: HashMap<Box<str>, String>,
+}
+
+impl Values {
+    /// Renders the string from the latest values.
+    ///
+    /// Segments between an `if` tag and its `else` or `end` tag
+    /// are only included while its condition is truthy,
+    /// and those between `else` and `end` only while it is not.
+    fn render(&self, blocks: &[Block]) -> String {
+        #[cfg(feature = "ipc")]
+        let mut conditions: Vec<bool> = vec![];
+
+        let mut string = String::new();
+
+        for (block, value) in blocks.iter().zip(&self.segments) {
+            #[cfg(feature = "ipc")]
+            let shown = conditions.iter().all(|&condition| condition);
+
+            match block {
+                #[cfg(feature = "ipc")]
+                Block::Value if !shown => {}
+                Block::Value => string.push_str(value),
+                #[cfg(feature = "ipc")]
+                Block::Expression(expression) => {
+                    if shown {
+                        string.push_str(&expression.evaluate(&self.variables).to_string());
+                    }
+                }
+                #[cfg(feature = "ipc")]
+                Block::If(condition) => {
+                    conditions.push(condition.evaluate(&self.variables).is_truthy());
+                }
+                #[cfg(feature = "ipc")]
+                Block::Else => {
+                    if let Some(condition) = conditions.last_mut() {
+                        *condition = !*condition;
+                    }
+                }
+                #[cfg(feature = "ipc")]
+                Block::End => {
+                    conditions.pop();
+                }
+            }
+        }
+
+        string
+    }
 }
 
 /// Creates a new dynamic string, based off the input template.
@@ -34,30 +122,57 @@ where
 {
     let (tokens, is_static) = parse_input(input);
 
-    let label_parts = arc_mut!(vec![]);
+    let values = arc_mut!(Values::default());
+    let mut blocks = vec![];
+    #[cfg(feature = "ipc")]
+    let mut expression_variables = HashSet::new();
+
     let (tx, rx) = mpsc::channel(32);
 
+    for segment in &tokens {
+        blocks.push(match segment {
+            #[cfg(feature = "ipc")]
+            DynamicStringSegment::Expression(expression) => {
+                expression_variables.extend(expression.variables());
+                Block::Expression(expression.clone())
+            }
+            #[cfg(feature = "ipc")]
+            DynamicStringSegment::If(condition) => {
+                expression_variables.extend(condition.variables());
+                Block::If(condition.clone())
+            }
+            #[cfg(feature = "ipc")]
+            DynamicStringSegment::Else => Block::Else,
+            #[cfg(feature = "ipc")]
+            DynamicStringSegment::End => Block::End,
+            _ => Block::Value,
+        });
+    }
+
+    let blocks = Arc::new(blocks);
+
     for (i, segment) in tokens.into_iter().enumerate() {
         match segment {
             DynamicStringSegment::Static(str) => {
-                lock!(label_parts).push(str);
+                lock!(values).segments.push(str);
             }
             DynamicStringSegment::Script(script) => {
                 let tx = tx.clone();
-                let label_parts = label_parts.clone();
+                let values = values.clone();
+                let blocks = blocks.clone();
 
                 // insert blank value to preserve segment order
-                lock!(label_parts).push(String::new());
+                lock!(values).segments.push(String::new());
 
                 spawn(async move {
                     script
                         .run(None, |out, _| {
                             if let OutputStream::Stdout(out) = out {
-                                let mut label_parts = lock!(label_parts);
+                                let mut values = lock!(values);
 
-                                let _: String = std::mem::replace(&mut label_parts[i], out);
+                                let _: String = std::mem::replace(&mut values.segments[i], out);
 
-                                let string = label_parts.join("");
+                                let string = values.render(&blocks);
                                 tx.send_spawn(string);
                             }
                         })
@@ -67,10 +182,11 @@ where
             #[cfg(feature = "ipc")]
             DynamicStringSegment::Variable(name) => {
                 let tx = tx.clone();
-                let label_parts = label_parts.clone();
+                let values = values.clone();
+                let blocks = blocks.clone();
 
                 // insert blank value to preserve segment order
-                lock!(label_parts).push(String::new());
+                lock!(values).segments.push(String::new());
 
                 spawn(async move {
                     let variable_manager = Ironbar::variable_manager();
@@ -78,25 +194,57 @@ where
 
                     while let Ok(value) = rx.recv().await {
                         if let Some(value) = value {
-                            let mut label_parts = lock!(label_parts);
+                            let mut values = lock!(values);
 
-                            let _: String = std::mem::replace(&mut label_parts[i], value);
+                            let _: String = std::mem::replace(&mut values.segments[i], value);
 
-                            let string = label_parts.join("");
+                            let string = values.render(&blocks);
                             tx.send_spawn(string);
                         }
                     }
                 });
             }
+            // rendered from `blocks`
+            #[cfg(feature = "ipc")]
+            DynamicStringSegment::Expression(_)
+            | DynamicStringSegment::If(_)
+            | DynamicStringSegment::Else
+            | DynamicStringSegment::End => lock!(values).segments.push(String::new()),
         }
     }
 
+    // expressions are re-rendered each time a variable they reference changes,
+    // including unset variables, which are `null`.
+    #[cfg(feature = "ipc")]
+    for name in expression_variables {
+        let tx = tx.clone();
+        let values = values.clone();
+        let blocks = blocks.clone();
+
+        spawn(async move {
+            let variable_manager = Ironbar::variable_manager();
+            let mut rx = variable_manager.subscribe(name.clone());
+
+            while let Ok(value) = rx.recv().await {
+                let mut values = lock!(values);
+
+                match value {
+                    Some(value) => values.variables.insert(name.clone(), value),
+                    None => values.variables.remove(&name),
+                };
+
+                let string = values.render(&blocks);
+                tx.send_spawn(string);
+            }
+        });
+    }
+
     rx.recv_glib(deps, f);
 
     // initialize
     if is_static {
-        let label_parts = lock!(label_parts).join("");
-        tx.send_spawn(label_parts);
+        let string = lock!(values).render(&blocks);
+        tx.send_spawn(string);
     }
 }
 
@@ -146,11 +294,46 @@ fn parse_script(chars: &[char]) -> (DynamicStringSegment, usize) {
         .collect::<String>();
 
     let len = str.chars().count() + SKIP_BRACKETS;
+
+    #[cfg(feature = "ipc")]
+    if let Some(segment) = parse_tag(&str) {
+        return (segment, len);
+    }
+
     let script = Script::from(str.as_str());
 
     (DynamicStringSegment::Script(script), len)
 }
 
+/// Parses an expression tag from the contents of a `{{block}}`,
+/// returning `None` if the block is a script.
+///
+/// To avoid changing the meaning of existing scripts,
+/// blocks are only expressions if they are valid
+/// and reference at least one variable,
+/// or are an `if`, `else` or `end` tag.
+#[cfg(feature = "ipc")]
+fn parse_tag(str: &str) -> Option<DynamicStringSegment> {
+    let str = str.trim();
+
+    match str {
+        "else" => return Some(DynamicStringSegment::Else),
+        "end" => return Some(DynamicStringSegment::End),
+        _ => {}
+    }
+
+    if let Some(condition) = str
+        .strip_prefix("if")
+        .filter(|condition| condition.starts_with([' ', '(']))
+    {
+        return Expression::parse(condition).map(DynamicStringSegment::If);
+    }
+
+    Expression::parse(str)
+        .filter(|expression| !expression.variables().is_empty())
+        .map(DynamicStringSegment::Expression)
+}
+
 #[cfg(feature = "ipc")]
 fn parse_variable(chars: &[char]) -> (DynamicStringSegment, usize) {
     const SKIP_HASH: usize = 1;
@@ -320,6 +503,63 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_expression() {
+        const INPUT: &str = "{{ $battery }}%";
+        let (tokens, _) = parse_input(INPUT);
+
+        assert_eq!(tokens.len(), 2);
+        assert!(matches!(&tokens[0], DynamicStringSegment::Expression(_)));
+        assert!(matches!(&tokens[1], DynamicStringSegment::Static(str) if str == "%"));
+    }
+
+    #[test]
+    fn test_script_not_expression() {
+        const INPUT: &str = "{{$HOME/bin/status}} {{if [ -f x ]; then echo y; fi}}";
+        let (tokens, _) = parse_input(INPUT);
+
+        assert_eq!(tokens.len(), 3);
+        assert!(
+            matches!(&tokens[0], DynamicStringSegment::Script(script) if script.cmd == "$HOME/bin/status")
+        );
+        assert!(matches!(&tokens[2], DynamicStringSegment::Script(_)));
+    }
+
+    #[test]
+    fn test_render_if() {
+        const INPUT: &str = "{{ if $battery < 20 }}low{{ else }}ok{{ end }} {{ $battery }}%";
+        let (tokens, _) = parse_input(INPUT);
+
+        let blocks = tokens
+            .iter()
+            .map(|token| match token {
+                DynamicStringSegment::Expression(expression) => {
+                    Block::Expression(expression.clone())
+                }
+                DynamicStringSegment::If(condition) => Block::If(condition.clone()),
+                DynamicStringSegment::Else => Block::Else,
+                DynamicStringSegment::End => Block::End,
+                _ => Block::Value,
+            })
+            .collect::<Vec<_>>();
+
+        let mut values = Values {
+            segments: tokens
+                .iter()
+                .map(|token| match token {
+                    DynamicStringSegment::Static(str) => str.clone(),
+                    _ => String::new(),
+                })
+                .collect(),
+            variables: HashMap::from([("battery".into(), "15".to_string())]),
+        };
+
+        assert_eq!(values.render(&blocks), "low 15%");
+
+        values.variables.insert("battery".into(), "85".to_string());
+        assert_eq!(values.render(&blocks), "ok 85%");
+    }
+
     #[test]
     fn test_pango_attribute() {
         const INPUT: &str = "<span color='#color'>hello</span>";
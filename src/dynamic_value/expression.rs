@@ -0,0 +1,410 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+/// A value produced while evaluating an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// An unset variable, or the result of invalid arithmetic.
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+}
+
+impl Value {
+    /// Checks whether the value is 'truthy'.
+    ///
+    /// This matches [`super::DynamicBool`] variables,
+    /// so `null`, an empty string, `0` and `false` are false.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Self::Null => false,
+            Self::Bool(bool) => *bool,
+            Self::Number(number) => *number != 0.0,
+            Self::String(string) => !(string.is_empty() || string == "0" || string == "false"),
+        }
+    }
+
+    /// Gets the value as a number,
+    /// parsing strings such as variable values.
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            Self::Number(number) => Some(*number),
+            Self::String(string) => string.trim().parse().ok(),
+            Self::Null | Self::Bool(_) => None,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Null => Ok(()),
+            Self::Bool(bool) => write!(f, "{bool}"),
+            // whole numbers are shown without a decimal point
+            Self::Number(number) if number.fract() == 0.0 && number.abs() < 1e15 => {
+                write!(f, "{}", *number as i64)
+            }
+            Self::Number(number) => write!(f, "{number}"),
+            Self::String(string) => write!(f, "{string}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl BinaryOp {
+    fn apply(self, lhs: &Value, rhs: &Value) -> Value {
+        let numbers = lhs.as_number().zip(rhs.as_number());
+
+        match self {
+            Self::Or => Value::Bool(lhs.is_truthy() || rhs.is_truthy()),
+            Self::And => Value::Bool(lhs.is_truthy() && rhs.is_truthy()),
+            Self::Eq | Self::Ne => {
+                let equal = match numbers {
+                    Some((lhs, rhs)) => lhs == rhs,
+                    None => lhs.to_string() == rhs.to_string(),
+                };
+                Value::Bool(equal == (self == Self::Eq))
+            }
+            Self::Lt | Self::Le | Self::Gt | Self::Ge => {
+                let ordering = match numbers {
+                    Some((lhs, rhs)) => lhs.partial_cmp(&rhs),
+                    None if *lhs == Value::Null || *rhs == Value::Null => None,
+                    None => Some(lhs.to_string().cmp(&rhs.to_string())),
+                };
+
+                Value::Bool(ordering.is_some_and(|ordering| match self {
+                    Self::Lt => ordering.is_lt(),
+                    Self::Le => ordering.is_le(),
+                    Self::Gt => ordering.is_gt(),
+                    _ => ordering.is_ge(),
+                }))
+            }
+            Self::Add => match numbers {
+                Some((lhs, rhs)) => Value::Number(lhs + rhs),
+                None => Value::String(format!("{lhs}{rhs}")),
+            },
+            Self::Sub | Self::Mul | Self::Div | Self::Rem => match numbers {
+                Some((lhs, rhs)) => Value::Number(match self {
+                    Self::Sub => lhs - rhs,
+                    Self::Mul => lhs * rhs,
+                    Self::Div => lhs / rhs,
+                    _ => lhs % rhs,
+                }),
+                None => Value::Null,
+            },
+        }
+    }
+}
+
+/// A parsed expression,
+/// which can be evaluated against the values of ironvars.
+///
+/// See the [dynamic values docs](dynamic-values#expressions) for the syntax.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Literal(Value),
+    Variable(Box<str>),
+    Not(Box<Expression>),
+    Negate(Box<Expression>),
+    Binary(BinaryOp, Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    /// Parses the expression,
+    /// or returns `None` if it is not valid.
+    pub fn parse(input: &str) -> Option<Self> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+
+        let expression = parser.or()?;
+        (parser.pos == parser.tokens.len()).then_some(expression)
+    }
+
+    /// Evaluates the expression,
+    /// treating variables missing from `variables` as `null`.
+    pub fn evaluate(&self, variables: &HashMap<Box<str>, String>) -> Value {
+        match self {
+            Self::Literal(value) => value.clone(),
+            Self::Variable(name) => variables
+                .get(name)
+                .map_or(Value::Null, |value| Value::String(value.clone())),
+            Self::Not(expression) => Value::Bool(!expression.evaluate(variables).is_truthy()),
+            Self::Negate(expression) => expression
+                .evaluate(variables)
+                .as_number()
+                .map_or(Value::Null, |number| Value::Number(-number)),
+            Self::Binary(op, lhs, rhs) => {
+                op.apply(&lhs.evaluate(variables), &rhs.evaluate(variables))
+            }
+        }
+    }
+
+    /// Gets the name of each variable referenced by the expression.
+    pub fn variables(&self) -> HashSet<Box<str>> {
+        let mut variables = HashSet::new();
+        self.collect_variables(&mut variables);
+        variables
+    }
+
+    fn collect_variables(&self, variables: &mut HashSet<Box<str>>) {
+        match self {
+            Self::Literal(_) => {}
+            Self::Variable(name) => {
+                variables.insert(name.clone());
+            }
+            Self::Not(expression) | Self::Negate(expression) => {
+                expression.collect_variables(variables);
+            }
+            Self::Binary(_, lhs, rhs) => {
+                lhs.collect_variables(variables);
+                rhs.collect_variables(variables);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    String(String),
+    Variable(Box<str>),
+    Bool(bool),
+    Op(&'static str),
+    OpenParen,
+    CloseParen,
+}
+
+fn tokenize(input: &str) -> Option<Vec<Token>> {
+    const OPS: [&str; 14] = [
+        "==", "!=", "<=", ">=", "&&", "||", "<", ">", "+", "-", "*", "/", "%", "!",
+    ];
+
+    let mut tokens = vec![];
+    let mut rest = input.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        let (token, len) = match c {
+            '(' => (Token::OpenParen, 1),
+            ')' => (Token::CloseParen, 1),
+            '$' => {
+                let len = rest[1..]
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+                    .unwrap_or(rest.len() - 1);
+
+                if len == 0 {
+                    return None;
+                }
+
+                (Token::Variable(rest[1..=len].into()), len + 1)
+            }
+            '"' | '\'' => {
+                let len = rest[1..].find(c)?;
+                (Token::String(rest[1..=len].to_string()), len + 2)
+            }
+            '0'..='9' | '.' => {
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                    .unwrap_or(rest.len());
+                (Token::Number(rest[..len].parse().ok()?), len)
+            }
+            _ if rest.starts_with("true") => (Token::Bool(true), 4),
+            _ if rest.starts_with("false") => (Token::Bool(false), 5),
+            _ => {
+                let op = OPS.iter().find(|op| rest.starts_with(*op))?;
+                (Token::Op(op), op.len())
+            }
+        };
+
+        tokens.push(token);
+        rest = rest[len..].trim_start();
+    }
+
+    Some(tokens)
+}
+
+/// A recursive descent parser,
+/// with a method for each level of precedence from lowest to highest.
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next_op(&mut self, ops: &[(&str, BinaryOp)]) -> Option<BinaryOp> {
+        let Some(Token::Op(token)) = self.tokens.get(self.pos) else {
+            return None;
+        };
+
+        let op = ops.iter().find(|(op, _)| op == token).map(|(_, op)| *op)?;
+        self.pos += 1;
+        Some(op)
+    }
+
+    fn binary(
+        &mut self,
+        ops: &[(&str, BinaryOp)],
+        operand: fn(&mut Self) -> Option<Expression>,
+    ) -> Option<Expression> {
+        let mut lhs = operand(self)?;
+
+        while let Some(op) = self.next_op(ops) {
+            let rhs = operand(self)?;
+            lhs = Expression::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+
+        Some(lhs)
+    }
+
+    fn or(&mut self) -> Option<Expression> {
+        self.binary(&[("||", BinaryOp::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> Option<Expression> {
+        self.binary(&[("&&", BinaryOp::And)], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Option<Expression> {
+        self.binary(
+            &[
+                ("==", BinaryOp::Eq),
+                ("!=", BinaryOp::Ne),
+                ("<", BinaryOp::Lt),
+                ("<=", BinaryOp::Le),
+                (">", BinaryOp::Gt),
+                (">=", BinaryOp::Ge),
+            ],
+            Self::sum,
+        )
+    }
+
+    fn sum(&mut self) -> Option<Expression> {
+        self.binary(&[("+", BinaryOp::Add), ("-", BinaryOp::Sub)], Self::product)
+    }
+
+    fn product(&mut self) -> Option<Expression> {
+        self.binary(
+            &[
+                ("*", BinaryOp::Mul),
+                ("/", BinaryOp::Div),
+                ("%", BinaryOp::Rem),
+            ],
+            Self::unary,
+        )
+    }
+
+    fn unary(&mut self) -> Option<Expression> {
+        match self.tokens.get(self.pos)? {
+            Token::Op("!") => {
+                self.pos += 1;
+                Some(Expression::Not(Box::new(self.unary()?)))
+            }
+            Token::Op("-") => {
+                self.pos += 1;
+                Some(Expression::Negate(Box::new(self.unary()?)))
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Option<Expression> {
+        let token = self.tokens.get(self.pos)?.clone();
+        self.pos += 1;
+
+        match token {
+            Token::Number(number) => Some(Expression::Literal(Value::Number(number))),
+            Token::String(string) => Some(Expression::Literal(Value::String(string))),
+            Token::Bool(bool) => Some(Expression::Literal(Value::Bool(bool))),
+            Token::Variable(name) => Some(Expression::Variable(name)),
+            Token::OpenParen => {
+                let expression = self.or()?;
+                (self.tokens.get(self.pos) == Some(&Token::CloseParen)).then(|| {
+                    self.pos += 1;
+                    expression
+                })
+            }
+            Token::Op(_) | Token::CloseParen => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(input: &str, variables: &[(&str, &str)]) -> Value {
+        let variables = variables
+            .iter()
+            .map(|(name, value)| ((*name).into(), (*value).to_string()))
+            .collect();
+
+        Expression::parse(input)
+            .expect("expression should parse")
+            .evaluate(&variables)
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(eval("1 + 2 * 3", &[]), Value::Number(7.0));
+        assert_eq!(eval("(1 + 2) * 3", &[]), Value::Number(9.0));
+        assert_eq!(eval("!false && 1 < 2", &[]), Value::Bool(true));
+    }
+
+    #[test]
+    fn test_variables() {
+        assert_eq!(
+            eval("$battery < 20", &[("battery", "15")]),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            eval("$battery < 20", &[("battery", "85")]),
+            Value::Bool(false)
+        );
+        assert_eq!(eval("$battery < 20", &[]), Value::Bool(false));
+        assert_eq!(
+            eval("$state == 'charging'", &[("state", "charging")]),
+            Value::Bool(true)
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(eval("$battery + 5", &[("battery", "15")]).to_string(), "20");
+        assert_eq!(eval("1 / 4", &[]).to_string(), "0.25");
+        assert_eq!(eval("$missing", &[]).to_string(), "");
+        assert_eq!(eval("'a' + $b", &[("b", "c")]).to_string(), "ac");
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert_eq!(Expression::parse("uptime -p"), None);
+        assert_eq!(Expression::parse("$HOME/bin/script"), None);
+        assert_eq!(Expression::parse("(1 + 2"), None);
+        assert_eq!(Expression::parse("1 +"), None);
+    }
+
+    #[test]
+    fn test_referenced_variables() {
+        let expression = Expression::parse("$a + $b > $a").expect("expression should parse");
+        assert_eq!(
+            expression.variables(),
+            HashSet::from(["a".into(), "b".into()])
+        );
+    }
+}
@@ -0,0 +1,350 @@
+use super::DynamicBool;
+use crate::Ironbar;
+use serde::Deserialize;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+use tracing::{error, warn};
+
+/// A set of conditions controlling whether a module or bar is shown.
+///
+/// All conditions which are set must be met for it to be shown.
+/// Each condition is re-evaluated whenever the state it depends on changes.
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(default)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct VisibilityRule {
+    /// A [dynamic boolean](dynamic-values#dynamic-boolean),
+    /// such as an ironvar, expression or script,
+    /// which must be true.
+    ///
+    /// **Default**: `null`
+    pub condition: Option<DynamicBool>,
+
+    /// Whether the focused window on the monitor must be fullscreen (`true`)
+    /// or must not be fullscreen (`false`).
+    ///
+    /// Requires the `toplevel` feature.
+    ///
+    /// **Default**: `null`
+    pub fullscreen: Option<bool>,
+
+    /// The names of workspaces, one of which must be active on the monitor.
+    ///
+    /// Requires the `workspaces` feature.
+    ///
+    /// **Default**: `[]`
+    pub workspaces: Vec<String>,
+
+    /// A local time window which the current time must be inside,
+    /// in the format `HH:MM-HH:MM`.
+    /// Windows may wrap past midnight, such as `22:00-06:00`.
+    ///
+    /// **Default**: `null`
+    pub time: Option<String>,
+}
+
+/// The latest value of each condition in a rule,
+/// which are combined to decide visibility.
+struct Conditions<F> {
+    /// The value of each condition, or `None` until it is first evaluated.
+    values: RefCell<Vec<Option<bool>>>,
+    last: Cell<Option<bool>>,
+    f: RefCell<F>,
+}
+
+impl<F: FnMut(bool)> Conditions<F> {
+    /// Sets the value of the condition at `index`,
+    /// calling `f` if the combined value changes.
+    ///
+    /// Nothing is called until every condition has a value.
+    fn set(&self, index: usize, value: bool) {
+        let visible = {
+            let mut values = self.values.borrow_mut();
+            values[index] = Some(value);
+
+            if values.iter().any(Option::is_none) {
+                return;
+            }
+
+            values.iter().all(|value| *value == Some(true))
+        };
+
+        if self.last.replace(Some(visible)) != Some(visible) {
+            (self.f.borrow_mut())(visible);
+        }
+    }
+}
+
+impl VisibilityRule {
+    /// Evaluates the rule for a bar or module on `monitor_name`,
+    /// calling `f` with whether it should be visible each time this changes.
+    ///
+    /// `f` is first called once every condition has been evaluated.
+    pub fn subscribe<F>(self, ironbar: &Rc<Ironbar>, monitor_name: &str, mut f: F)
+    where
+        F: FnMut(bool) + 'static,
+    {
+        let count = usize::from(self.condition.is_some())
+            + usize::from(self.fullscreen.is_some())
+            + usize::from(!self.workspaces.is_empty())
+            + usize::from(self.time.is_some());
+
+        if count == 0 {
+            f(true);
+            return;
+        }
+
+        let conditions = Rc::new(Conditions {
+            values: RefCell::new(vec![None; count]),
+            last: Cell::new(None),
+            f: RefCell::new(f),
+        });
+
+        // index of the next condition's value
+        let mut index = 0;
+
+        if let Some(condition) = self.condition {
+            let conditions = conditions.clone();
+            condition.subscribe((), move |(), value| conditions.set(index, value));
+            index += 1;
+        }
+
+        if let Some(expected) = self.fullscreen {
+            let conditions = conditions.clone();
+            subscribe_fullscreen(ironbar, monitor_name, move |fullscreen| {
+                conditions.set(index, fullscreen == expected);
+            });
+            index += 1;
+        }
+
+        if !self.workspaces.is_empty() {
+            let conditions = conditions.clone();
+            subscribe_workspaces(ironbar, monitor_name, self.workspaces, move |active| {
+                conditions.set(index, active);
+            });
+            index += 1;
+        }
+
+        if let Some(time) = self.time {
+            match TimeWindow::parse(&time) {
+                Some(window) => window.subscribe(move |inside| conditions.set(index, inside)),
+                None => {
+                    error!("Invalid time window '{time}', expected the format `HH:MM-HH:MM`");
+                    conditions.set(index, true);
+                }
+            }
+        }
+    }
+}
+
+/// Calls `f` with whether the focused window on `monitor_name` is fullscreen,
+/// each time this changes.
+#[cfg(feature = "toplevel")]
+pub fn subscribe_fullscreen<F>(ironbar: &Ironbar, monitor_name: &str, mut f: F)
+where
+    F: FnMut(bool) + 'static,
+{
+    use crate::channels::BroadcastReceiverExt;
+    use crate::clients::wayland::{ToplevelEvent, ToplevelInfo};
+
+    let wl = ironbar.clients.borrow_mut().wayland();
+
+    let is_fullscreen = {
+        let monitor_name = monitor_name.to_string();
+        move |info: &ToplevelInfo| {
+            info.fullscreen && info.output.as_deref() == Some(monitor_name.as_str())
+        }
+    };
+
+    // id of the focused toplevel, if it is fullscreen on the monitor.
+    let mut current = wl
+        .toplevel_info_all()
+        .into_iter()
+        .find(|info| info.focused && is_fullscreen(info))
+        .map(|info| info.id);
+
+    f(current.is_some());
+
+    wl.subscribe_toplevels().recv_glib((), move |(), event| {
+        match event {
+            ToplevelEvent::New(info) | ToplevelEvent::Update(info) if info.focused => {
+                current = is_fullscreen(&info).then_some(info.id);
+            }
+            ToplevelEvent::Update(info) | ToplevelEvent::Remove(info)
+                if current == Some(info.id) =>
+            {
+                current = None;
+            }
+            _ => {}
+        }
+
+        f(current.is_some());
+    });
+}
+
+#[cfg(not(feature = "toplevel"))]
+pub fn subscribe_fullscreen<F>(_ironbar: &Ironbar, _monitor_name: &str, mut f: F)
+where
+    F: FnMut(bool) + 'static,
+{
+    warn!("The `fullscreen` visibility condition requires the `toplevel` feature");
+    f(false);
+}
+
+/// Calls `f` with whether one of the named workspaces
+/// is active on `monitor_name`, each time this changes.
+#[cfg(feature = "workspaces")]
+fn subscribe_workspaces<F>(ironbar: &Ironbar, monitor_name: &str, names: Vec<String>, mut f: F)
+where
+    F: FnMut(bool) + 'static,
+{
+    use crate::channels::BroadcastReceiverExt;
+    use crate::clients::compositor::{Visibility, Workspace, WorkspaceUpdate};
+    use std::collections::HashMap;
+
+    let client = match ironbar.clients.borrow_mut().workspaces() {
+        Ok(client) => client,
+        Err(err) => {
+            warn!("Failed to evaluate `workspaces` visibility condition: {err}");
+            f(true);
+            return;
+        }
+    };
+
+    let monitor_name = monitor_name.to_string();
+    let mut workspaces = HashMap::<i64, Workspace>::new();
+    let mut last = None;
+
+    client.subscribe().recv_glib((), move |(), event| {
+        match event {
+            WorkspaceUpdate::Init(init) => {
+                workspaces = init.into_iter().map(|w| (w.id, w)).collect();
+            }
+            WorkspaceUpdate::Add(workspace) | WorkspaceUpdate::Move(workspace) => {
+                workspaces.insert(workspace.id, workspace);
+            }
+            WorkspaceUpdate::Remove(id) => {
+                workspaces.remove(&id);
+            }
+            WorkspaceUpdate::Focus { new, .. } => {
+                // only one workspace is active on each monitor
+                for workspace in workspaces.values_mut() {
+                    if workspace.monitor == new.monitor {
+                        workspace.visibility = Visibility::Hidden;
+                    }
+                }
+
+                workspaces.insert(new.id, new);
+            }
+            WorkspaceUpdate::Rename { id, name } => {
+                if let Some(workspace) = workspaces.get_mut(&id) {
+                    workspace.name = name;
+                }
+            }
+            WorkspaceUpdate::Urgent { .. } | WorkspaceUpdate::Unknown => return,
+        }
+
+        let active = workspaces.values().any(|workspace| {
+            workspace.monitor == monitor_name
+                && workspace.visibility.is_visible()
+                && names.contains(&workspace.name)
+        });
+
+        if last.replace(active) != Some(active) {
+            f(active);
+        }
+    });
+}
+
+#[cfg(not(feature = "workspaces"))]
+fn subscribe_workspaces<F>(_ironbar: &Ironbar, _monitor_name: &str, _names: Vec<String>, mut f: F)
+where
+    F: FnMut(bool) + 'static,
+{
+    warn!("The `workspaces` visibility condition requires the `workspaces` feature");
+    f(true);
+}
+
+/// A daily window of local time,
+/// stored as minutes since midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TimeWindow {
+    start: u32,
+    end: u32,
+}
+
+impl TimeWindow {
+    /// Parses a window in the format `HH:MM-HH:MM`.
+    fn parse(input: &str) -> Option<Self> {
+        let parse_time = |time: &str| {
+            let (hours, minutes) = time.trim().split_once(':')?;
+            let hours = hours.parse::<u32>().ok().filter(|h| *h < 24)?;
+            let minutes = minutes.parse::<u32>().ok().filter(|m| *m < 60)?;
+            Some(hours * 60 + minutes)
+        };
+
+        let (start, end) = input.split_once('-')?;
+
+        Some(Self {
+            start: parse_time(start)?,
+            end: parse_time(end)?,
+        })
+    }
+
+    /// Checks whether the minute of the day is inside the window.
+    fn contains(self, minute: u32) -> bool {
+        if self.start <= self.end {
+            (self.start..self.end).contains(&minute)
+        } else {
+            minute >= self.start || minute < self.end
+        }
+    }
+
+    /// Calls `f` with whether the current time is inside the window,
+    /// checking again at the start of each minute.
+    fn subscribe(self, f: impl FnMut(bool) + 'static) {
+        fn check(window: TimeWindow, mut f: impl FnMut(bool) + 'static) {
+            let Ok(now) = glib::DateTime::now_local() else {
+                error!("Failed to get local time");
+                return;
+            };
+
+            f(window.contains(now.hour() as u32 * 60 + now.minute() as u32));
+
+            let delay = Duration::from_secs(60 - now.second() as u64);
+            glib::timeout_add_local_once(delay, move || check(window, f));
+        }
+
+        check(self, f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_time_window() {
+        assert_eq!(
+            TimeWindow::parse("09:00-17:30"),
+            Some(TimeWindow {
+                start: 540,
+                end: 1050
+            })
+        );
+
+        assert_eq!(TimeWindow::parse("9:00"), None);
+        assert_eq!(TimeWindow::parse("24:00-01:00"), None);
+    }
+
+    #[test]
+    fn time_window_wraps_midnight() {
+        let window = TimeWindow::parse("22:00-06:00").expect("valid window");
+
+        assert!(window.contains(23 * 60));
+        assert!(window.contains(60));
+        assert!(!window.contains(12 * 60));
+        assert!(!window.contains(6 * 60));
+    }
+}
@@ -1,15 +1,25 @@
-use crate::config::{MarqueeMode, MarqueeOnHover, TruncateMode};
+use crate::animation;
+#[cfg(any(feature = "custom", feature = "sys_info"))]
+use crate::config::{GraphConfig, GraphStyle};
+use crate::config::{LoadingIndicatorType, MarqueeMode, MarqueeOnHover, TruncateMode};
 use glib::ControlFlow;
 use glib::{SignalHandlerId, markup_escape_text};
+#[cfg(any(feature = "custom", feature = "sys_info"))]
+use gtk::DrawingArea;
 use gtk::gdk::{BUTTON_MIDDLE, BUTTON_PRIMARY, BUTTON_SECONDARY, Paintable};
 use gtk::glib;
+use gtk::graphene::Point;
+use gtk::gsk::Transform;
 use gtk::pango::EllipsizeMode;
 use gtk::prelude::*;
 use gtk::{
-    EventControllerMotion, EventSequenceState, GestureClick, Label, ScrolledWindow, Snapshot,
-    Widget,
+    EventControllerMotion, EventSequenceState, GestureClick, GestureDrag, GestureLongPress, Label,
+    Orientation, ProgressBar, PropagationPhase, ScrolledWindow, Snapshot, Spinner, Widget,
+    accessible,
 };
 use std::cell::{Cell, RefCell};
+#[cfg(any(feature = "custom", feature = "sys_info"))]
+use std::collections::VecDeque;
 use std::rc::Rc;
 use std::time::{Duration, Instant};
 
@@ -52,15 +62,43 @@ pub trait IronbarGtkExt {
     /// Adds a `GestureClick` controller with separate handlers for single and double clicks.
     /// Single-click is delayed by the double-click timeout (250ms) to distinguish from double-clicks.
     /// If `on_double` is None, behaves like `connect_pressed` with no delay.
+    ///
+    /// Using the capture `phase` claims the click before any child widgets receive it,
+    /// overriding their own click handling.
     fn connect_pressed_with_double_click<F1, F2>(
         &self,
         button: MouseButton,
+        phase: PropagationPhase,
         on_single: F1,
         on_double: Option<F2>,
     ) -> SignalHandlerId
     where
         F1: Fn() + 'static,
         F2: Fn() + 'static;
+
+    /// Adds a touch-only `GestureLongPress` controller,
+    /// calling `f` when the widget is pressed and held on a touchscreen.
+    fn connect_long_press<F>(&self, f: F) -> SignalHandlerId
+    where
+        F: Fn() + 'static;
+
+    /// Adds a touch-only `GestureDrag` controller for horizontal swipes.
+    ///
+    /// `on_begin` is called once the swipe passes a small threshold,
+    /// after which `on_update` is called with the horizontal distance from the start point.
+    /// Taps which do not pass the threshold are left to other controllers.
+    fn connect_swipe_horizontal<F1, F2>(&self, on_begin: F1, on_update: F2) -> SignalHandlerId
+    where
+        F1: Fn() + 'static,
+        F2: Fn(f64) + 'static;
+
+    /// Sets the name read out for the widget by assistive technologies.
+    fn set_accessible_label(&self, label: &str);
+
+    /// Sets the description read out for the widget by assistive technologies,
+    /// after its name.
+    /// This is used to expose the current value of modules.
+    fn set_accessible_description(&self, description: &str);
 }
 
 impl<W: IsA<Widget>> IronbarGtkExt for W {
@@ -90,6 +128,7 @@ impl<W: IsA<Widget>> IronbarGtkExt for W {
     fn connect_pressed_with_double_click<F1, F2>(
         &self,
         button: MouseButton,
+        phase: PropagationPhase,
         on_single: F1,
         on_double: Option<F2>,
     ) -> SignalHandlerId
@@ -98,6 +137,7 @@ impl<W: IsA<Widget>> IronbarGtkExt for W {
         F2: Fn() + 'static,
     {
         let controller = GestureClick::new();
+        controller.set_propagation_phase(phase);
 
         if button != MouseButton::Any {
             controller.set_button(button as u32);
@@ -163,6 +203,59 @@ impl<W: IsA<Widget>> IronbarGtkExt for W {
         self.add_controller(controller);
         id
     }
+
+    fn connect_long_press<F>(&self, f: F) -> SignalHandlerId
+    where
+        F: Fn() + 'static,
+    {
+        let controller = GestureLongPress::builder().touch_only(true).build();
+
+        let id = controller.connect_pressed(move |gesture, _, _| {
+            gesture.set_state(EventSequenceState::Claimed);
+            f();
+        });
+
+        self.add_controller(controller);
+        id
+    }
+
+    fn connect_swipe_horizontal<F1, F2>(&self, on_begin: F1, on_update: F2) -> SignalHandlerId
+    where
+        F1: Fn() + 'static,
+        F2: Fn(f64) + 'static,
+    {
+        const THRESHOLD: f64 = 10.0;
+
+        let controller = GestureDrag::builder().touch_only(true).build();
+
+        let id = controller.connect_drag_update(move |gesture, offset_x, _| {
+            if gesture.sequence_state(gesture.current_sequence().as_ref())
+                != EventSequenceState::Claimed
+            {
+                if offset_x.abs() < THRESHOLD {
+                    return;
+                }
+
+                gesture.set_state(EventSequenceState::Claimed);
+                on_begin();
+            }
+
+            on_update(offset_x);
+        });
+
+        self.add_controller(controller);
+        id
+    }
+
+    fn set_accessible_label(&self, label: &str) {
+        self.upcast_ref::<Widget>()
+            .update_property(&[accessible::Property::Label(label)]);
+    }
+
+    fn set_accessible_description(&self, description: &str) {
+        self.upcast_ref::<Widget>()
+            .update_property(&[accessible::Property::Description(description)]);
+    }
 }
 
 pub struct ChildIterator {
@@ -197,6 +290,14 @@ pub trait IronbarLabelExt {
     fn set_label_escaped(&self, label: &str);
 
     fn truncate(&self, mode: TruncateMode);
+
+    /// Wraps the label in a container which rotates it by `angle` degrees,
+    /// updating as the text changes.
+    /// This allows text to be read along vertical bars.
+    ///
+    /// Only right angles are supported.
+    /// If the angle is `0`, the label is returned unwrapped.
+    fn rotated(&self, angle: f64) -> Widget;
 }
 
 impl IronbarLabelExt for Label {
@@ -219,6 +320,46 @@ impl IronbarLabelExt for Label {
             self.set_max_width_chars(length);
         }
     }
+
+    fn rotated(&self, angle: f64) -> Widget {
+        let angle = angle.rem_euclid(360.0) as i32;
+        if angle == 0 {
+            return self.clone().upcast();
+        }
+
+        let container = gtk::Fixed::new();
+        container.put(self, 0.0, 0.0);
+
+        // the child is rotated around its origin,
+        // so must be moved back into the container's bounds.
+        let update_transform = {
+            let container = container.downgrade();
+            move |label: &Label| {
+                let Some(container) = container.upgrade() else {
+                    return;
+                };
+
+                let (_, width, _, _) = label.measure(Orientation::Horizontal, -1);
+                let (_, height, _, _) = label.measure(Orientation::Vertical, width);
+                let (width, height) = (width as f32, height as f32);
+
+                let offset = match angle {
+                    90 => Point::new(height, 0.0),
+                    180 => Point::new(width, height),
+                    270 => Point::new(0.0, width),
+                    _ => Point::zero(),
+                };
+
+                let transform = Transform::new().translate(&offset).rotate(angle as f32);
+                container.set_child_transform(label, Some(&transform));
+            }
+        };
+
+        update_transform(self);
+        self.connect_label_notify(update_transform);
+
+        container.upcast()
+    }
 }
 
 pub trait IronbarPaintableExt {
@@ -455,7 +596,7 @@ impl MarqueeInner {
                 MarqueeOnHover::Play => self.is_hovered.get(),
                 MarqueeOnHover::Pause => !self.is_hovered.get(),
                 MarqueeOnHover::None => true,
-            };
+            } && animation::enabled(scrolled);
 
             if should_scroll {
                 let hadjustment = scrolled.hadjustment();
@@ -478,3 +619,207 @@ impl MarqueeInner {
         ControlFlow::Continue
     }
 }
+
+/// Time between each pulse of a progress loading indicator.
+const LOADING_PULSE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A spinner or pulsing progress bar,
+/// shown while something is loading to give a consistent loading state.
+///
+/// The indicator has the `.loading` class,
+/// as well as `.spinner` or `.progress` depending on its type,
+/// and is hidden while inactive.
+#[derive(Clone)]
+pub struct LoadingIndicator {
+    widget: Widget,
+    pulse: Rc<Cell<Option<glib::SourceId>>>,
+}
+
+impl LoadingIndicator {
+    pub fn new(indicator_type: LoadingIndicatorType) -> Self {
+        let widget = match indicator_type {
+            LoadingIndicatorType::Spinner => Spinner::new().upcast::<Widget>(),
+            LoadingIndicatorType::Progress => ProgressBar::new().upcast::<Widget>(),
+        };
+
+        widget.add_css_class("loading");
+        widget.add_css_class(match indicator_type {
+            LoadingIndicatorType::Spinner => "spinner",
+            LoadingIndicatorType::Progress => "progress",
+        });
+        widget.set_visible(false);
+
+        Self {
+            widget,
+            pulse: Rc::new(Cell::new(None)),
+        }
+    }
+
+    pub fn widget(&self) -> &Widget {
+        &self.widget
+    }
+
+    /// Shows and animates the indicator while `active`,
+    /// or stops and hides it otherwise.
+    pub fn set_active(&self, active: bool) {
+        self.widget.set_visible(active);
+
+        if let Some(spinner) = self.widget.downcast_ref::<Spinner>() {
+            spinner.set_spinning(active);
+        } else if let Some(progress) = self.widget.downcast_ref::<ProgressBar>() {
+            match (active, self.pulse.take()) {
+                (true, None) => {
+                    let progress = progress.clone();
+                    let source_id = glib::timeout_add_local(LOADING_PULSE_INTERVAL, move || {
+                        progress.pulse();
+                        ControlFlow::Continue
+                    });
+                    self.pulse.set(Some(source_id));
+                }
+                (true, Some(source_id)) => self.pulse.set(Some(source_id)),
+                (false, Some(source_id)) => source_id.remove(),
+                (false, None) => {}
+            }
+        }
+    }
+}
+
+/// A small graph of a value's recent history,
+/// keeping up to a fixed number of samples.
+///
+/// Samples are drawn in the widget's CSS `color`,
+/// so graphs can be styled like any other text.
+/// The widget has the `.graph` class,
+/// as well as `.line` or `.bar` depending on its style.
+#[cfg(any(feature = "custom", feature = "sys_info"))]
+#[derive(Clone)]
+pub struct Graph {
+    area: DrawingArea,
+    samples: Rc<RefCell<VecDeque<f64>>>,
+    capacity: usize,
+}
+
+#[cfg(any(feature = "custom", feature = "sys_info"))]
+impl Graph {
+    pub fn new(config: &GraphConfig) -> Self {
+        let area = DrawingArea::new();
+        area.add_css_class("graph");
+        area.add_css_class(match config.style {
+            GraphStyle::Line => "line",
+            GraphStyle::Bar => "bar",
+        });
+
+        area.set_content_width(config.width);
+        if let Some(height) = config.height {
+            area.set_content_height(height);
+        }
+
+        let capacity = config.samples.max(2);
+        let samples = Rc::new(RefCell::new(VecDeque::with_capacity(capacity)));
+
+        {
+            let samples = samples.clone();
+            let style = config.style;
+            let min = config.min;
+            let max = config.max;
+
+            area.set_draw_func(move |area, cr, width, height| {
+                let samples = samples.borrow();
+                if samples.is_empty() {
+                    return;
+                }
+
+                let width = f64::from(width);
+                let height = f64::from(height);
+
+                let max = max.unwrap_or_else(|| samples.iter().copied().fold(min, f64::max));
+                let range = if max > min { max - min } else { 1.0 };
+
+                // the height of the sample from the bottom
+                let scale = |value: f64| ((value - min) / range).clamp(0.0, 1.0) * height;
+
+                let color = area.color();
+                let (red, green, blue, alpha) = (
+                    f64::from(color.red()),
+                    f64::from(color.green()),
+                    f64::from(color.blue()),
+                    f64::from(color.alpha()),
+                );
+
+                // newest samples are on the right,
+                // so the graph fills from the right until full
+                let offset = (capacity - samples.len()) as f64;
+
+                let res = match style {
+                    GraphStyle::Line => {
+                        let step = width / (capacity - 1) as f64;
+                        let x = |index: usize| (offset + index as f64) * step;
+
+                        for (index, &value) in samples.iter().enumerate() {
+                            cr.line_to(x(index), height - scale(value));
+                        }
+
+                        cr.set_source_rgba(red, green, blue, alpha);
+                        cr.set_line_width(1.5);
+
+                        cr.stroke_preserve().and_then(|()| {
+                            cr.line_to(x(samples.len() - 1), height);
+                            cr.line_to(x(0), height);
+                            cr.close_path();
+
+                            cr.set_source_rgba(red, green, blue, alpha * 0.3);
+                            cr.fill()
+                        })
+                    }
+                    GraphStyle::Bar => {
+                        let step = width / capacity as f64;
+
+                        for (index, &value) in samples.iter().enumerate() {
+                            let bar_height = scale(value);
+                            cr.rectangle(
+                                (offset + index as f64) * step,
+                                height - bar_height,
+                                step,
+                                bar_height,
+                            );
+                        }
+
+                        cr.set_source_rgba(red, green, blue, alpha);
+                        cr.fill()
+                    }
+                };
+
+                if let Err(err) = res {
+                    tracing::error!("Failed to draw graph: {err}");
+                }
+            });
+        }
+
+        Self {
+            area,
+            samples,
+            capacity,
+        }
+    }
+
+    pub fn widget(&self) -> &DrawingArea {
+        &self.area
+    }
+
+    /// Adds a sample, dropping the oldest if the graph is full.
+    pub fn push(&self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+
+        {
+            let mut samples = self.samples.borrow_mut();
+            if samples.len() == self.capacity {
+                samples.pop_front();
+            }
+            samples.push_back(value);
+        }
+
+        self.area.queue_draw();
+    }
+}
@@ -0,0 +1,155 @@
+use indexmap::IndexMap;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Map which tracks the approximate memory used by its entries,
+/// evicting the least recently used entries once over budget.
+#[derive(Debug)]
+pub struct LruCache<K, V> {
+    /// Entries and their size in bytes, ordered from least to most recently used.
+    entries: IndexMap<K, (V, usize)>,
+    size: usize,
+}
+
+impl<K: Hash + Eq, V: Clone> LruCache<K, V> {
+    pub fn new() -> Self {
+        Self {
+            entries: IndexMap::new(),
+            size: 0,
+        }
+    }
+
+    /// Gets a clone of the value for `key`,
+    /// marking it as the most recently used.
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        let index = self.entries.get_index_of(key)?;
+        let last = self.entries.len() - 1;
+        self.entries.move_index(index, last);
+
+        self.entries
+            .get_index(last)
+            .map(|(_, (value, _))| value.clone())
+    }
+
+    /// Inserts a value taking up approximately `size` bytes,
+    /// as the most recently used.
+    pub fn insert(&mut self, key: K, value: V, size: usize) {
+        if let Some((_, old_size)) = self.entries.shift_remove(&key) {
+            self.size -= old_size;
+        }
+
+        self.entries.insert(key, (value, size));
+        self.size += size;
+    }
+
+    /// Evicts the least recently used entries until the size is within `budget`.
+    pub fn evict_to(&mut self, budget: usize) {
+        while self.size > budget {
+            let Some((_, (_, size))) = self.entries.shift_remove_index(0) else {
+                break;
+            };
+
+            self.size -= size;
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+/// Current memory usage of the image caches,
+/// which can be read from any thread.
+#[derive(Debug, Default)]
+pub struct CacheStats {
+    /// The memory budget in bytes.
+    pub budget: AtomicUsize,
+    /// The approximate memory used in bytes.
+    pub size: AtomicUsize,
+    /// The number of cached images.
+    pub images: AtomicUsize,
+    /// The number of cached image locations.
+    pub locations: AtomicUsize,
+}
+
+impl CacheStats {
+    pub fn new(budget: usize) -> Arc<Self> {
+        let stats = Arc::new(Self {
+            budget: AtomicUsize::new(budget),
+            ..Self::default()
+        });
+
+        #[cfg(feature = "ipc")]
+        crate::Ironbar::variable_manager().register_namespace("image_cache", stats.clone());
+
+        stats
+    }
+}
+
+/// Exposes the cache usage in the `image_cache` ironvar namespace.
+#[cfg(feature = "ipc")]
+impl crate::ironvar::Namespace for CacheStats {
+    fn get(&self, key: &str) -> Option<String> {
+        let value = match key {
+            "budget" => &self.budget,
+            "size" => &self.size,
+            "images" => &self.images,
+            "locations" => &self.locations,
+            _ => return None,
+        };
+
+        Some(value.load(Ordering::Relaxed).to_string())
+    }
+
+    fn list(&self) -> Vec<String> {
+        ["budget", "size", "images", "locations"]
+            .into_iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    fn namespaces(&self) -> Vec<String> {
+        vec![]
+    }
+
+    fn get_namespace(&self, _key: &str) -> Option<crate::ironvar::NamespaceTrait> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let mut cache = LruCache::new();
+        cache.insert("a", 1, 10);
+        cache.insert("b", 2, 10);
+        cache.insert("c", 3, 10);
+
+        assert_eq!(cache.get(&"a"), Some(1));
+
+        cache.evict_to(20);
+
+        assert_eq!(cache.size(), 20);
+        assert_eq!(cache.get(&"b"), None);
+        assert_eq!(cache.get(&"a"), Some(1));
+        assert_eq!(cache.get(&"c"), Some(3));
+    }
+
+    #[test]
+    fn insert_replaces_size() {
+        let mut cache = LruCache::new();
+        cache.insert("a", 1, 10);
+        cache.insert("a", 2, 5);
+
+        assert_eq!(cache.size(), 5);
+        assert_eq!(cache.len(), 1);
+    }
+}
@@ -56,6 +56,8 @@ where
     feature = "launcher",
     feature = "music",
     feature = "notifications",
+    feature = "tray",
+    feature = "wallpaper",
     feature = "workspaces",
 ))]
 pub struct IconButton {
@@ -70,6 +72,8 @@ pub struct IconButton {
     feature = "launcher",
     feature = "music",
     feature = "notifications",
+    feature = "tray",
+    feature = "wallpaper",
     feature = "workspaces",
 ))]
 impl IconButton {
@@ -109,6 +113,8 @@ impl IconButton {
     feature = "launcher",
     feature = "music",
     feature = "notifications",
+    feature = "tray",
+    feature = "wallpaper",
     feature = "workspaces",
 ))]
 impl Deref for IconButton {
@@ -127,6 +133,7 @@ impl Deref for IconButton {
     feature = "music",
     feature = "workspaces",
 ))]
+#[derive(Debug)]
 pub struct IconLabel {
     provider: image::Provider,
     container: gtk::Box,
@@ -1,3 +1,4 @@
+mod cache;
 #[cfg(any(
     feature = "battery",
     feature = "bluetooth",
@@ -7,6 +8,8 @@
     feature = "menu",
     feature = "music",
     feature = "notifications",
+    feature = "tray",
+    feature = "wallpaper",
     feature = "workspaces"
 ))]
 mod gtk;
@@ -21,7 +24,9 @@ mod provider;
     feature = "menu",
     feature = "music",
     feature = "notifications",
+    feature = "tray",
+    feature = "wallpaper",
     feature = "workspaces"
 ))]
 pub use self::gtk::*;
-pub use provider::Provider;
+pub use provider::{DEFAULT_CACHE_SIZE, Provider};
@@ -1,6 +1,7 @@
+use super::cache::{CacheStats, LruCache};
 use crate::desktop_file::DesktopFiles;
 use crate::gtk_helpers::{IronbarGlibExt, IronbarPaintableExt};
-use crate::{arc_mut, lock, spawn};
+use crate::{arc_mut, lock, spawn, spawn_blocking};
 use color_eyre::{Help, Report, Result};
 use glib::Bytes;
 use gtk::gdk::{Paintable, Texture};
@@ -10,9 +11,21 @@ use gtk::{IconLookupFlags, IconPaintable, IconTheme, Picture, TextDirection};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
 use tracing::{debug, trace, warn};
 
+/// Icon shown in place of an image while it is being decoded.
+const PLACEHOLDER_ICON_NAME: &str = "image-loading-symbolic";
+
+/// The default memory budget for cached images, in megabytes.
+pub const DEFAULT_CACHE_SIZE: u32 = 64;
+
+/// Approximate memory used by each cached location,
+/// as these are not worth measuring exactly.
+const LOCATION_SIZE: usize = 256;
+
 fn lookup_icon(theme: &IconTheme, name: &str, size: i32, scale: i32) -> IconPaintable {
     theme.lookup_icon(
         name,
@@ -50,19 +63,64 @@ enum ImageLocation {
     Remote(reqwest::Url),
 }
 
+impl ImageLocation {
+    /// Whether the image must be decoded from a file or bytes,
+    /// rather than being looked up from the icon theme.
+    fn is_decoded(&self) -> bool {
+        !matches!(self, Self::Icon(_))
+    }
+}
+
+/// The last image loaded into a picture,
+/// used to reload it when the picture's scale changes.
+#[derive(Debug, Clone)]
+struct PictureSource {
+    input: String,
+    size: i32,
+    use_fallback: bool,
+}
+
 #[derive(Debug)]
 struct Cache {
-    location_cache: HashMap<(Box<str>, i32), ImageRef>,
-    paintable_cache: HashMap<ImageRef, Option<Paintable>>,
+    location_cache: LruCache<(Box<str>, i32, IconTheme), ImageRef>,
+    /// Icon themes created for per-module overrides, keyed by theme name.
+    icon_themes: HashMap<String, IconTheme>,
+    /// Paintables keyed by their image and the scale they were rendered at.
+    paintable_cache: LruCache<(ImageRef, i32), Option<Paintable>>,
+    stats: Arc<CacheStats>,
 }
 
 impl Cache {
-    fn new() -> Self {
+    fn new(budget: usize) -> Self {
         Self {
-            location_cache: HashMap::new(),
-            paintable_cache: HashMap::new(),
+            location_cache: LruCache::new(),
+            icon_themes: HashMap::new(),
+            paintable_cache: LruCache::new(),
+            stats: CacheStats::new(budget),
         }
     }
+
+    /// Evicts the least recently used images, then locations,
+    /// until the caches are within the memory budget.
+    fn evict(&mut self) {
+        let budget = self.stats.budget.load(Ordering::Relaxed);
+
+        self.paintable_cache
+            .evict_to(budget.saturating_sub(self.location_cache.size()));
+        self.location_cache
+            .evict_to(budget.saturating_sub(self.paintable_cache.size()));
+
+        self.stats.size.store(
+            self.paintable_cache.size() + self.location_cache.size(),
+            Ordering::Relaxed,
+        );
+        self.stats
+            .images
+            .store(self.paintable_cache.len(), Ordering::Relaxed);
+        self.stats
+            .locations
+            .store(self.location_cache.len(), Ordering::Relaxed);
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -74,7 +132,13 @@ pub struct Provider {
 }
 
 impl Provider {
-    pub fn new(desktop_files: DesktopFiles, overrides: &mut HashMap<String, String>) -> Self {
+    /// Creates a new provider,
+    /// caching up to `cache_size` megabytes of images.
+    pub fn new(
+        desktop_files: DesktopFiles,
+        overrides: &mut HashMap<String, String>,
+        cache_size: u32,
+    ) -> Self {
         let mut overrides_map = HashMap::with_capacity(overrides.len());
         overrides_map.extend(overrides.drain());
 
@@ -82,7 +146,7 @@ impl Provider {
             desktop_files,
             icon_theme: RefCell::new(None),
             overrides: overrides_map,
-            cache: arc_mut!(Cache::new()),
+            cache: arc_mut!(Cache::new(cache_size as usize * 1024 * 1024)),
         }
     }
 
@@ -92,6 +156,12 @@ impl Provider {
     /// If `use_fallback` is `true`, a fallback icon will be used
     /// where an image cannot be found.
     ///
+    /// The image is rendered at the picture's scale factor,
+    /// and re-rendered whenever that changes,
+    /// such as when the picture is first shown on a HiDPI monitor.
+    /// With fractional scaling, the scale factor is rounded up
+    /// and GTK scales the result down, so images are never blurry.
+    ///
     /// Returns `true` if the image was successfully loaded,
     /// or `false` if the image could not be found.
     /// May also return an error if the resolution or loading process failed.
@@ -102,18 +172,41 @@ impl Provider {
         use_fallback: bool,
         picture: &Picture,
     ) -> Result<bool> {
+        self.reload_on_scale_change(input, size, use_fallback, picture);
+
+        let scale = picture.scale_factor();
+
         let image_ref = self.get_ref(input, size).await?;
         debug!("image ref for {input}: {:?}", image_ref);
 
-        let paintable = if let Some(pixbuf) = lock!(self.cache).paintable_cache.get(&image_ref) {
-            pixbuf.clone()
+        let key = (image_ref, scale);
+
+        let cached = lock!(self.cache).paintable_cache.get(&key);
+        let paintable = if let Some(pixbuf) = cached {
+            pixbuf
         } else {
-            let pixbuf =
-                Self::get_paintable(&image_ref, picture.scale_factor(), use_fallback).await?;
+            let show_placeholder = key
+                .0
+                .location
+                .as_ref()
+                .is_some_and(ImageLocation::is_decoded);
+            if show_placeholder {
+                picture.add_css_class("loading");
+                picture.set_paintable(Some(&lookup_icon(
+                    &key.0.theme,
+                    PLACEHOLDER_ICON_NAME,
+                    size,
+                    scale,
+                )));
+            }
 
-            lock!(self.cache)
-                .paintable_cache
-                .insert(image_ref, pixbuf.clone());
+            let pixbuf = Self::get_paintable(&key.0, scale, use_fallback).await;
+            picture.remove_css_class("loading");
+            let (pixbuf, bytes) = pixbuf?;
+
+            let mut cache = lock!(self.cache);
+            cache.paintable_cache.insert(key, pixbuf.clone(), bytes);
+            cache.evict();
 
             pixbuf
         };
@@ -124,6 +217,52 @@ impl Provider {
         Ok(has_match)
     }
 
+    /// Tracks the image loaded into the picture,
+    /// reloading it whenever the picture's scale factor changes.
+    ///
+    /// The signal handler is only connected on the first load into each picture.
+    fn reload_on_scale_change(
+        &self,
+        input: &str,
+        size: i32,
+        use_fallback: bool,
+        picture: &Picture,
+    ) {
+        const TAG: &str = "image-source";
+
+        let source = PictureSource {
+            input: input.to_string(),
+            size,
+            use_fallback,
+        };
+
+        if let Some(current) = picture.get_tag::<Rc<RefCell<PictureSource>>>(TAG) {
+            *current.borrow_mut() = source;
+            return;
+        }
+
+        let current = Rc::new(RefCell::new(source));
+        picture.set_tag(TAG, current.clone());
+
+        let provider = self.clone();
+        picture.connect_scale_factor_notify(move |picture| {
+            let source = current.borrow().clone();
+            let provider = provider.clone();
+            let picture = picture.clone();
+
+            glib::spawn_future_local(async move {
+                provider
+                    .load_into_picture_silent(
+                        &source.input,
+                        source.size,
+                        source.use_fallback,
+                        &picture,
+                    )
+                    .await;
+            });
+        });
+    }
+
     /// Like [`Provider::load_into_picture`], but does not return an error if the image could not be found.
     ///
     /// If an image is not resolved, a warning is logged. Errors are also logged.
@@ -149,17 +288,22 @@ impl Provider {
     /// This contains the location of the image if it can be resolved.
     /// The ref will be loaded from cache if present.
     async fn get_ref(&self, input: &str, size: i32) -> Result<ImageRef> {
-        let key = (input.into(), size);
+        let icon_theme = self.icon_theme();
+        let key = (input.into(), size, icon_theme.clone());
 
-        if let Some(location) = lock!(self.cache).location_cache.get(&key) {
-            Ok(location.clone())
+        let cached = lock!(self.cache).location_cache.get(&key);
+        if let Some(location) = cached {
+            Ok(location)
         } else {
             let location = self.resolve_location(input, size, 0).await?;
-            let image_ref = ImageRef::new(size, location, self.icon_theme());
+            let image_ref = ImageRef::new(size, location, icon_theme);
 
-            lock!(self.cache)
+            let mut cache = lock!(self.cache);
+            cache
                 .location_cache
-                .insert(key, image_ref.clone());
+                .insert(key, image_ref.clone(), LOCATION_SIZE);
+            cache.evict();
+
             Ok(image_ref)
         }
     }
@@ -236,7 +380,8 @@ impl Provider {
         Ok(location)
     }
 
-    /// Attempts to load the provided `ImageRef` into a `Pixbuf`.
+    /// Attempts to load the provided `ImageRef` into a `Pixbuf`,
+    /// along with the approximate memory it uses in bytes.
     ///
     /// If `use_fallback` is `true`, a fallback icon will be used
     /// where an image cannot be found.
@@ -244,26 +389,39 @@ impl Provider {
         image_ref: &ImageRef,
         scale: i32,
         use_fallback: bool,
-    ) -> Result<Option<Paintable>> {
+    ) -> Result<(Option<Paintable>, usize)> {
         const FALLBACK_ICON_NAME: &str = "dialog-question-symbolic";
 
-        let buf = match &image_ref.location {
-            Some(ImageLocation::Icon(name)) => Ok(Some(
-                lookup_icon(&image_ref.theme, name, image_ref.size, scale).upcast::<Paintable>(),
+        let icon_bytes = texture_bytes(image_ref.size * scale, image_ref.size * scale);
+
+        let (buf, bytes) = match &image_ref.location {
+            Some(ImageLocation::Icon(name)) => Ok((
+                Some(
+                    lookup_icon(&image_ref.theme, name, image_ref.size, scale)
+                        .upcast::<Paintable>(),
+                ),
+                icon_bytes,
             )),
             Some(ImageLocation::Local(path)) if path.extension().unwrap_or_default() == "svg" => {
                 let scaled_size = image_ref.size * scale;
+                let path = path.clone();
+
+                let texture = spawn_blocking(move || {
+                    let pixbuf = Pixbuf::from_file_at_scale(path, scaled_size, scaled_size, true)?;
 
-                let pixbuf = Pixbuf::from_file_at_scale(path, scaled_size, scaled_size, true)?;
+                    let buffer = pixbuf.save_to_bufferv("png", &[])?;
+                    let bytes = Bytes::from_owned(buffer);
 
-                let buffer = pixbuf.save_to_bufferv("png", &[])?;
-                let bytes = Bytes::from_owned(buffer);
+                    Texture::from_bytes(&bytes).map_err(Report::from)
+                })
+                .await??;
 
-                let texture = Texture::from_bytes(&bytes)?;
-                Ok(Some(texture.upcast::<Paintable>()))
+                let bytes = texture_bytes(texture.width(), texture.height());
+                Ok((Some(texture.upcast::<Paintable>()), bytes))
             }
-            Some(ImageLocation::Local(path)) => Texture::from_filename(path)
-                .map(|t| t.scale(image_ref.size as f64, image_ref.size as f64)),
+            Some(ImageLocation::Local(path)) => decode_file(path.clone())
+                .await
+                .map(|t| scale_texture(t, image_ref.size)),
             Some(ImageLocation::Steam(app_id)) => {
                 const SIZES: [i32; 8] = [16, 24, 32, 48, 64, 96, 128, 256];
                 let size = SIZES
@@ -281,8 +439,9 @@ impl Provider {
                     },
                 )?;
 
-                Texture::from_filename(path)
-                    .map(|t| t.scale(image_ref.size as f64, image_ref.size as f64))
+                decode_file(path)
+                    .await
+                    .map(|t| scale_texture(t, image_ref.size))
             }
             #[cfg(feature = "http")]
             Some(ImageLocation::Remote(uri)) => {
@@ -306,17 +465,21 @@ impl Provider {
                 let bytes = rx
                     .await
                     .map_err(|_| Report::msg("HTTP fetch cancelled"))??;
-                Texture::from_bytes(&Bytes::from_owned(bytes))
-                    .map(|t| t.scale(image_ref.size as f64, image_ref.size as f64))
+                decode_bytes(Bytes::from_owned(bytes))
+                    .await
+                    .map(|t| scale_texture(t, image_ref.size))
             }
             None if use_fallback => {
                 warn!("failed to resolve {:?}, using fallback", image_ref.location);
-                Ok(Some(
-                    lookup_icon(&image_ref.theme, FALLBACK_ICON_NAME, image_ref.size, scale)
-                        .upcast::<Paintable>(),
+                Ok((
+                    Some(
+                        lookup_icon(&image_ref.theme, FALLBACK_ICON_NAME, image_ref.size, scale)
+                            .upcast::<Paintable>(),
+                    ),
+                    icon_bytes,
                 ))
             }
-            None => Ok(None),
+            None => Ok((None, 0)),
         }?;
 
         if let Some(buf) = &buf {
@@ -326,7 +489,7 @@ impl Provider {
             buf.set_tag("use-fallback", use_fallback);
         }
 
-        Ok(buf)
+        Ok((buf, bytes))
     }
 
     /// Returns true if the input starts with a prefix
@@ -369,4 +532,55 @@ impl Provider {
 
         *self.icon_theme.borrow_mut() = Some(icon_theme);
     }
+
+    /// Creates a provider which uses a different icon theme,
+    /// sharing the same overrides and cache.
+    ///
+    /// If no name is provided, the current theme is kept.
+    pub fn with_icon_theme(&self, theme: Option<&str>) -> Self {
+        let provider = self.clone();
+
+        if let Some(theme) = theme {
+            let icon_theme = lock!(self.cache)
+                .icon_themes
+                .entry(theme.to_string())
+                .or_insert_with(|| {
+                    let icon_theme = IconTheme::new();
+                    icon_theme.set_theme_name(Some(theme));
+                    icon_theme.set_display(Some(&crate::get_display()));
+                    icon_theme
+                })
+                .clone();
+
+            *provider.icon_theme.borrow_mut() = Some(icon_theme);
+        }
+
+        provider
+    }
+}
+
+/// Gets the approximate memory used by a texture of the given size,
+/// assuming four bytes per pixel.
+fn texture_bytes(width: i32, height: i32) -> usize {
+    (width.max(0) as usize) * (height.max(0) as usize) * 4
+}
+
+/// Scales a decoded texture to `size`, getting the memory it uses.
+/// The scaled paintable keeps a reference to the full size texture.
+fn scale_texture(texture: Texture, size: i32) -> (Option<Paintable>, usize) {
+    let bytes = texture_bytes(texture.width(), texture.height());
+    (texture.scale(size as f64, size as f64), bytes)
+}
+
+/// Decodes an image file into a texture on a blocking thread,
+/// so that large images do not stall the main loop.
+async fn decode_file(path: PathBuf) -> Result<Texture> {
+    spawn_blocking(move || Texture::from_filename(path).map_err(Report::from)).await?
+}
+
+/// Decodes encoded image bytes into a texture on a blocking thread,
+/// so that large images do not stall the main loop.
+#[cfg(feature = "http")]
+async fn decode_bytes(bytes: Bytes) -> Result<Texture> {
+    spawn_blocking(move || Texture::from_bytes(&bytes).map_err(Report::from)).await?
 }
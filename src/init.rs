@@ -0,0 +1,424 @@
+use crate::cli::InitArgs;
+use crate::config::ConfigLocation;
+use crate::error::ExitCode;
+use color_eyre::{Help, Report, Result};
+use serde_json::{Value, json};
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use tracing::error;
+
+/// Base stylesheet written alongside the generated config.
+const STYLE: &str = include_str!("../examples/minimal/style.css");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compositor {
+    Sway,
+    Hyprland,
+    Niri,
+    Other,
+}
+
+impl Compositor {
+    /// Detects the running compositor from its environment variables.
+    fn detect() -> Self {
+        if env::var_os("SWAYSOCK").is_some() {
+            Self::Sway
+        } else if env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+            Self::Hyprland
+        } else if env::var_os("NIRI_SOCKET").is_some() {
+            Self::Niri
+        } else {
+            Self::Other
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Sway => "Sway",
+            Self::Hyprland => "Hyprland",
+            Self::Niri => "Niri",
+            Self::Other => "unknown",
+        }
+    }
+
+    /// Whether the compositor has a workspaces client,
+    /// and Ironbar was compiled with support for it.
+    fn has_workspaces(self) -> bool {
+        match self {
+            Self::Sway => cfg!(feature = "workspaces+sway"),
+            Self::Hyprland => cfg!(feature = "workspaces+hyprland"),
+            Self::Niri => cfg!(feature = "workspaces+niri"),
+            Self::Other => false,
+        }
+    }
+
+    /// Whether the compositor has a bindmode client,
+    /// and Ironbar was compiled with support for it.
+    fn has_bindmode(self) -> bool {
+        match self {
+            Self::Sway => cfg!(feature = "bindmode+sway"),
+            Self::Hyprland => cfg!(feature = "bindmode+hyprland"),
+            Self::Niri | Self::Other => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SoundServer {
+    PipeWire,
+    PulseAudio,
+    None,
+}
+
+impl SoundServer {
+    /// Detects the sound server by checking for its socket in the runtime directory.
+    fn detect() -> Self {
+        let Some(runtime_dir) = env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from) else {
+            return Self::None;
+        };
+
+        let pulse = runtime_dir.join("pulse/native").exists();
+        let pipewire = runtime_dir.join("pipewire-0").exists();
+
+        match (pulse, pipewire) {
+            // the volume module talks to the Pulse API,
+            // so PipeWire is only usable through `pipewire-pulse`.
+            (true, true) => Self::PipeWire,
+            (true, false) => Self::PulseAudio,
+            _ => Self::None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::PipeWire => "PipeWire",
+            Self::PulseAudio => "PulseAudio",
+            Self::None => "none",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NetworkStack {
+    NetworkManager,
+    Other,
+}
+
+impl NetworkStack {
+    fn detect() -> Self {
+        if Path::new("/run/NetworkManager").exists() {
+            Self::NetworkManager
+        } else {
+            Self::Other
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::NetworkManager => "NetworkManager",
+            Self::Other => "unknown",
+        }
+    }
+}
+
+/// Summary of the detected system.
+#[derive(Debug, Clone, Copy)]
+struct Environment {
+    compositor: Compositor,
+    sound: SoundServer,
+    battery: bool,
+    network: NetworkStack,
+}
+
+impl Environment {
+    fn detect() -> Self {
+        Self {
+            compositor: Compositor::detect(),
+            sound: SoundServer::detect(),
+            battery: has_battery(),
+            network: NetworkStack::detect(),
+        }
+    }
+
+    /// Asks whether to use each detected component,
+    /// dropping any which are declined.
+    fn confirm(mut self, input: &mut impl BufRead) -> Result<Self> {
+        if self.compositor != Compositor::Other
+            && !ask(
+                input,
+                &format!("Add modules for {}?", self.compositor.name()),
+            )?
+        {
+            self.compositor = Compositor::Other;
+        }
+
+        if self.sound != SoundServer::None
+            && !ask(
+                input,
+                &format!("Add volume module for {}?", self.sound.name()),
+            )?
+        {
+            self.sound = SoundServer::None;
+        }
+
+        if self.battery && !ask(input, "Add battery module?")? {
+            self.battery = false;
+        }
+
+        if self.network != NetworkStack::Other
+            && !ask(
+                input,
+                &format!("Add network module for {}?", self.network.name()),
+            )?
+        {
+            self.network = NetworkStack::Other;
+        }
+
+        Ok(self)
+    }
+}
+
+/// Asks a yes/no question on `stderr`, defaulting to yes.
+fn ask(input: &mut impl BufRead, question: &str) -> Result<bool> {
+    eprint!("{question} [Y/n] ");
+    io::stderr().flush()?;
+
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+
+    Ok(!matches!(answer.trim().to_lowercase().as_str(), "n" | "no"))
+}
+
+fn has_battery() -> bool {
+    fs::read_dir("/sys/class/power_supply")
+        .map(|entries| {
+            entries
+                .flatten()
+                .any(|entry| entry.file_name().to_string_lossy().starts_with("BAT"))
+        })
+        .unwrap_or_default()
+}
+
+/// Runs the `init` CLI command,
+/// writing a starter config and stylesheet
+/// based on the detected environment.
+pub fn run(args: InitArgs) {
+    if let Err(err) = try_run(args) {
+        error!("{err:?}");
+        exit(ExitCode::CliError as i32);
+    }
+}
+
+fn try_run(args: InitArgs) -> Result<()> {
+    let mut environment = Environment::detect();
+
+    eprintln!("Compositor: {}", environment.compositor.name());
+    eprintln!("Sound server: {}", environment.sound.name());
+    eprintln!(
+        "Battery: {}",
+        if environment.battery { "yes" } else { "no" }
+    );
+    eprintln!("Network: {}", environment.network.name());
+
+    let stdin = io::stdin();
+    if !args.yes && stdin.is_terminal() {
+        eprintln!();
+        environment = environment.confirm(&mut stdin.lock())?;
+    }
+
+    let config = serde_json::to_string_pretty(&build_config(environment))?;
+
+    if args.print {
+        println!("{config}");
+        return Ok(());
+    }
+
+    let dir = args.output.unwrap_or_else(|| {
+        ConfigLocation::default_path()
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default()
+    });
+
+    let config_path = dir.join("config.json");
+    let style_path = dir.join("style.css");
+
+    // check both before writing either,
+    // so that a conflict never leaves a config without its stylesheet
+    if !args.force {
+        check_missing(&[&config_path, &style_path])?;
+    }
+
+    fs::create_dir_all(&dir)?;
+
+    fs::write(&config_path, config)?;
+    eprintln!("\nWrote config to '{}'", config_path.display());
+
+    fs::write(&style_path, STYLE)?;
+    eprintln!("Wrote stylesheet to '{}'", style_path.display());
+
+    Ok(())
+}
+
+/// Checks that none of `paths` exist.
+fn check_missing(paths: &[&Path]) -> Result<()> {
+    let existing = paths
+        .iter()
+        .filter(|path| path.exists())
+        .map(|path| format!("'{}'", path.display()))
+        .collect::<Vec<_>>();
+
+    if existing.is_empty() {
+        Ok(())
+    } else {
+        Err(
+            Report::msg(format!("{} already exists", existing.join(" and ")))
+                .suggestion("Pass `--force` to overwrite, or choose a different output directory"),
+        )
+    }
+}
+
+/// Builds a starter config containing modules
+/// which are available on the detected system.
+fn build_config(environment: Environment) -> Value {
+    let compositor = environment.compositor;
+
+    let mut start = vec![];
+    if compositor.has_workspaces() {
+        start.push(json!({ "type": "workspaces" }));
+    }
+    if compositor.has_bindmode() {
+        start.push(json!({ "type": "bindmode" }));
+    }
+
+    let mut center = vec![];
+    if cfg!(feature = "focused") {
+        center.push(json!({ "type": "focused", "icon_size": 16 }));
+    }
+
+    let mut end = vec![];
+
+    if cfg!(feature = "sys_info") {
+        end.push(json!({
+            "type": "sys_info",
+            "format": ["{cpu_percent}% ", "{memory_percent}% "],
+            "intervals": { "cpu": 1000 }
+        }));
+    }
+
+    if cfg!(feature = "volume") && environment.sound != SoundServer::None {
+        end.push(json!({ "type": "volume" }));
+    }
+
+    if cfg!(feature = "network_manager") && environment.network == NetworkStack::NetworkManager {
+        end.push(json!({ "type": "network_manager" }));
+    }
+
+    if cfg!(feature = "battery") && environment.battery {
+        end.push(json!({ "type": "battery" }));
+    }
+
+    if cfg!(feature = "tray") {
+        end.push(json!({ "type": "tray" }));
+    }
+
+    if cfg!(feature = "clock") {
+        end.push(json!({ "type": "clock" }));
+    }
+
+    json!({
+        "position": "top",
+        "height": 24,
+        "start": start,
+        "center": center,
+        "end": end,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module_types(config: &Value, key: &str) -> Vec<String> {
+        config[key]
+            .as_array()
+            .expect("array")
+            .iter()
+            .map(|module| module["type"].as_str().expect("type").to_string())
+            .collect()
+    }
+
+    #[test]
+    fn unknown_environment() {
+        let config = build_config(Environment {
+            compositor: Compositor::Other,
+            sound: SoundServer::None,
+            battery: false,
+            network: NetworkStack::Other,
+        });
+
+        assert!(module_types(&config, "start").is_empty());
+
+        let end = module_types(&config, "end");
+        assert!(!end.contains(&"volume".to_string()));
+        assert!(!end.contains(&"battery".to_string()));
+        assert!(!end.contains(&"network_manager".to_string()));
+    }
+
+    #[test]
+    fn confirm_drops_declined() {
+        let environment = Environment {
+            compositor: Compositor::Sway,
+            sound: SoundServer::PipeWire,
+            battery: true,
+            network: NetworkStack::NetworkManager,
+        };
+
+        let mut input = "\nn\nyes\nNo\n".as_bytes();
+        let environment = environment
+            .confirm(&mut input)
+            .expect("should read answers");
+
+        assert_eq!(environment.compositor, Compositor::Sway);
+        assert_eq!(environment.sound, SoundServer::None);
+        assert!(environment.battery);
+        assert_eq!(environment.network, NetworkStack::Other);
+    }
+
+    #[test]
+    fn check_missing_lists_existing() {
+        let dir = std::env::temp_dir().join(format!("ironbar-init-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("should create dir");
+
+        let config = dir.join("config.json");
+        let style = dir.join("style.css");
+        let _ = fs::remove_file(&config);
+        fs::write(&style, "").expect("should write style");
+
+        assert!(check_missing(&[&config]).is_ok());
+
+        let err = check_missing(&[&config, &style]).expect_err("style should exist");
+        assert!(err.to_string().contains("style.css"));
+        assert!(!err.to_string().contains("config.json"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    #[cfg(all(feature = "battery", feature = "volume"))]
+    fn laptop_environment() {
+        let config = build_config(Environment {
+            compositor: Compositor::Other,
+            sound: SoundServer::PipeWire,
+            battery: true,
+            network: NetworkStack::Other,
+        });
+
+        let end = module_types(&config, "end");
+        assert!(end.contains(&"volume".to_string()));
+        assert!(end.contains(&"battery".to_string()));
+    }
+}
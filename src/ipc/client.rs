@@ -1,35 +1,88 @@
-use super::Ipc;
+use super::{Ipc, RemoteAuth};
 use crate::ipc::{Command, Response};
 use color_eyre::Result;
 use color_eyre::{Help, Report};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::UnixStream;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::{TcpStream, UnixStream};
+
+/// A connection to either the local socket or a remote instance.
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
 
 impl Ipc {
     /// Sends a command to the IPC server.
     /// The server response is returned.
     pub async fn send(&self, command: Command, debug: bool) -> Result<Response> {
-        let mut stream = match UnixStream::connect(&self.path).await {
-            Ok(stream) => Ok(stream),
-            Err(err) => Err(Report::new(err)
-                .wrap_err("Failed to connect to Ironbar IPC server")
-                .suggestion("Is Ironbar running?")),
-        }?;
+        let stream = self.write_command(&command, debug).await?;
+
+        let mut read_buffer = String::new();
+        let mut reader = BufReader::new(stream);
+        let bytes = reader.read_line(&mut read_buffer).await?;
+
+        let response = serde_json::from_str(&read_buffer[..bytes])?;
+        Ok(response)
+    }
+
+    /// Sends a command to the IPC server,
+    /// calling `f` with each response until the server closes the connection.
+    pub async fn stream<F>(&self, command: Command, debug: bool, mut f: F) -> Result<()>
+    where
+        F: FnMut(Response),
+    {
+        let stream = self.write_command(&command, debug).await?;
+        let mut lines = BufReader::new(stream).lines();
 
-        let mut write_buffer = serde_json::to_vec(&command)?;
+        while let Some(line) = lines.next_line().await? {
+            f(serde_json::from_str(&line)?);
+        }
+
+        Ok(())
+    }
+
+    /// Connects to the IPC server and writes the command.
+    async fn write_command(&self, command: &Command, debug: bool) -> Result<Box<dyn Stream>> {
+        let mut stream = self.connect().await?;
+
+        let mut write_buffer = vec![];
+
+        if let Some(remote) = &self.remote {
+            serde_json::to_writer(
+                &mut write_buffer,
+                &RemoteAuth {
+                    token: remote.token.clone(),
+                },
+            )?;
+            write_buffer.push(b'\n');
+        }
+
+        serde_json::to_writer(&mut write_buffer, command)?;
 
         if debug {
-            eprintln!("REQUEST JSON: {}", serde_json::to_string(&command)?);
+            eprintln!("REQUEST JSON: {}", serde_json::to_string(command)?);
         }
 
         write_buffer.push(b'\n');
         stream.write_all(&write_buffer).await?;
 
-        let mut read_buffer = String::new();
-        let mut reader = BufReader::new(stream);
-        let bytes = reader.read_line(&mut read_buffer).await?;
+        Ok(stream)
+    }
 
-        let response = serde_json::from_str(&read_buffer[..bytes])?;
-        Ok(response)
+    /// Connects to the remote instance if set,
+    /// otherwise the local socket.
+    async fn connect(&self) -> Result<Box<dyn Stream>> {
+        let stream: std::io::Result<Box<dyn Stream>> = match &self.remote {
+            Some(remote) => TcpStream::connect(&remote.address)
+                .await
+                .map(|stream| Box::new(stream) as _),
+            None => UnixStream::connect(&self.path)
+                .await
+                .map(|stream| Box::new(stream) as _),
+        };
+
+        stream.map_err(|err| {
+            Report::new(err)
+                .wrap_err("Failed to connect to Ironbar IPC server")
+                .suggestion("Is Ironbar running?")
+        })
     }
 }
@@ -1,3 +1,4 @@
+use crate::ironvar::IronvarType;
 use clap::ArgAction;
 use std::path::PathBuf;
 
@@ -20,12 +21,75 @@ pub enum Command {
     #[command(subcommand)]
     Var(IronvarCommand),
 
+    /// Get the current state of a client as JSON.
+    #[command(subcommand)]
+    Get(StateCommand),
+
     /// Interact with a specific bar.
     Bar(BarCommand),
 
+    /// Switch between config profiles.
+    #[command(subcommand)]
+    Profile(ProfileCommand),
+
     /// Load stylesheets and dynamically add/remove classes
     #[command(subcommand)]
     Style(StyleCommand),
+
+    /// Add, remove or toggle CSS classes on bars or modules.
+    #[command(subcommand)]
+    Class(ClassCommand),
+
+    /// Show the status of each client and bar,
+    /// along with the most recent errors.
+    Health,
+
+    /// Inspect Ironbar's internals, for debugging.
+    #[command(subcommand)]
+    Debug(DebugCommand),
+
+    /// Stream events published by modules, such as timer sessions starting and ending.
+    /// Each event is printed as it happens until interrupted.
+    Events {
+        /// Only show events from this type of module.
+        #[serde(default)]
+        source: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug, Serialize, Deserialize)]
+#[serde(tag = "subcommand", rename_all = "snake_case")]
+pub enum DebugCommand {
+    /// List each channel receiver which has fallen behind its sender,
+    /// with how many times it lagged and how many updates it missed.
+    Lag,
+}
+
+#[derive(Subcommand, Debug, Serialize, Deserialize)]
+#[serde(tag = "subcommand", rename_all = "snake_case")]
+pub enum ProfileCommand {
+    /// Switch to a config profile,
+    /// reloading the config and rebuilding the bars.
+    /// The profile is kept when the config is reloaded.
+    Set {
+        /// The name of the profile.
+        name: String,
+    },
+    /// Get the name of the active config profile.
+    Get,
+    /// List the names of all config profiles.
+    List,
+}
+
+#[derive(Subcommand, Debug, Serialize, Deserialize)]
+#[serde(tag = "subcommand", rename_all = "snake_case")]
+pub enum StateCommand {
+    /// The audio sinks, sources and streams.
+    Volume,
+    /// The workspaces on all monitors.
+    Workspaces,
+    /// The focused window, or `null` if no window has focus.
+    Focused,
 }
 
 #[derive(Subcommand, Debug, Serialize, Deserialize)]
@@ -40,6 +104,12 @@ pub enum IronvarCommand {
         key: Box<str>,
         /// Variable value. Can be any valid UTF-8 string.
         value: String,
+        /// The variable type.
+        /// If not set, the value is parsed as the variable's existing type,
+        /// or as a string for new variables.
+        #[arg(long = "type")]
+        #[serde(default, rename = "type")]
+        kind: Option<IronvarType>,
     },
 
     /// Get the current value of an `ironvar`.
@@ -50,12 +120,45 @@ pub enum IronvarCommand {
 
     /// Gets the current value of all `ironvar`s.
     List { namespace: Option<Box<str>> },
+
+    /// Append an item to a list `ironvar`,
+    /// or insert a `key=value` entry into a map `ironvar`.
+    /// Unset variables are created as a list.
+    Push {
+        /// Variable key.
+        key: Box<str>,
+        /// The list item or map entry.
+        value: String,
+    },
+
+    /// Remove an item from a list `ironvar`,
+    /// or the entry with a key from a map `ironvar`.
+    Remove {
+        /// Variable key.
+        key: Box<str>,
+        /// The list item or map key.
+        value: String,
+    },
+
+    /// Get the keys of a map `ironvar`,
+    /// or the indices of a list `ironvar`.
+    Keys {
+        /// Variable key.
+        key: Box<str>,
+    },
 }
 
 #[derive(Args, Debug, Serialize, Deserialize)]
 pub struct BarCommand {
     /// The name of the bar.
-    pub name: String,
+    /// If not set, all bars are targeted.
+    #[serde(default)]
+    pub name: Option<String>,
+
+    /// Only target bars on this monitor (output), such as `DP-1`.
+    #[arg(long, global = true)]
+    #[serde(default)]
+    pub monitor: Option<String>,
 
     #[command(subcommand)]
     #[serde(flatten)]
@@ -65,6 +168,9 @@ pub struct BarCommand {
 #[derive(Subcommand, Debug, Serialize, Deserialize)]
 #[serde(tag = "subcommand", rename_all = "snake_case")]
 pub enum BarCommandType {
+    /// List the monitor and name of each targeted bar.
+    List,
+
     // == Visibility == \\
     /// Force the bar to be shown, regardless of current visibility state.
     Show,
@@ -81,6 +187,8 @@ pub enum BarCommandType {
         visible: bool,
     },
     /// Toggle the current visibility state between shown and hidden.
+    #[command(visible_alias = "toggle")]
+    #[serde(alias = "toggle")]
     ToggleVisible,
     /// Get the bar's visibility state.
     GetVisible,
@@ -115,8 +223,33 @@ pub enum BarCommandType {
     },
     /// Get the popup's current visibility state.
     GetPopupVisible,
+    /// Pin the open popup, keeping it open until it is explicitly hidden,
+    /// or unpin it to restore its usual closing behaviour.
+    SetPopupPinned {
+        #[clap(
+            num_args(1),
+            require_equals(true),
+            action = ArgAction::Set,
+        )]
+        pinned: bool,
+    },
+    /// Get whether the popup is pinned open.
+    GetPopupPinned,
+    /// Give the open popup keyboard focus,
+    /// allowing its contents to be navigated with the arrow and tab keys.
+    FocusPopup,
 
     // == Exclusivity == \\
+    /// Show the bar above fullscreen windows until peeked again,
+    /// without reserving an exclusive zone.
+    /// If already peeking, this ends the peek and restores the bar.
+    Peek {
+        /// Automatically end the peek after this many milliseconds.
+        #[arg(long)]
+        #[serde(default)]
+        duration: Option<u64>,
+    },
+
     /// Set whether the bar reserves an exclusive zone.
     SetExclusive {
         #[clap(
@@ -126,6 +259,56 @@ pub enum BarCommandType {
         )]
         exclusive: bool,
     },
+
+    // == Modules == \\
+    /// Add a module to the bar.
+    ///
+    /// Returns the ID of the new module.
+    AddModule {
+        /// The module config, as JSON.
+        config: String,
+
+        /// The group to add the module to.
+        #[arg(long, value_enum, default_value_t = ModuleGroup::End)]
+        #[serde(default)]
+        group: ModuleGroup,
+
+        /// The position within the group.
+        /// Defaults to the end of the group.
+        #[arg(long)]
+        #[serde(default)]
+        index: Option<usize>,
+    },
+    /// Remove a module from the bar.
+    RemoveModule {
+        /// The ID or configured name of the module.
+        module: String,
+    },
+    /// Move a module to a new position.
+    MoveModule {
+        /// The ID or configured name of the module.
+        module: String,
+
+        /// The position within the group.
+        index: usize,
+
+        /// The group to move the module to.
+        /// Defaults to the module's current group.
+        #[arg(long, value_enum)]
+        #[serde(default)]
+        group: Option<ModuleGroup>,
+    },
+    /// List the ID and name of each module on the bar.
+    ListModules,
+}
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModuleGroup {
+    Start,
+    Center,
+    #[default]
+    End,
 }
 
 #[derive(Subcommand, Debug, Serialize, Deserialize)]
@@ -165,3 +348,37 @@ pub enum StyleCommand {
         name: String,
     },
 }
+
+#[derive(Subcommand, Debug, Serialize, Deserialize)]
+#[serde(tag = "subcommand", rename_all = "snake_case")]
+pub enum ClassCommand {
+    /// Add a CSS class `name` to all widgets matching `selector`.
+    Add {
+        /// The widgets to target.
+        /// Use `bar` for every bar, `bar:<name>` for a single bar,
+        /// or a module name for all modules with that name.
+        selector: String,
+        /// The class name to add.
+        name: String,
+    },
+
+    /// Remove a CSS class `name` from all widgets matching `selector`.
+    Remove {
+        /// The widgets to target.
+        /// Use `bar` for every bar, `bar:<name>` for a single bar,
+        /// or a module name for all modules with that name.
+        selector: String,
+        /// The class name to remove.
+        name: String,
+    },
+
+    /// Toggle a CSS class `name` on all widgets matching `selector`.
+    Toggle {
+        /// The widgets to target.
+        /// Use `bar` for every bar, `bar:<name>` for a single bar,
+        /// or a module name for all modules with that name.
+        selector: String,
+        /// The class name to toggle.
+        name: String,
+    },
+}
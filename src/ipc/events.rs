@@ -0,0 +1,52 @@
+use crate::Ironbar;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+
+/// An event published by a module,
+/// which is streamed to any IPC clients subscribed with the `events` command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpcEvent {
+    /// The type of module which published the event.
+    pub source: String,
+    /// The name of the event.
+    pub event: String,
+    /// Extra information about the event.
+    #[serde(default)]
+    pub data: BTreeMap<String, String>,
+}
+
+impl IpcEvent {
+    pub fn new(source: &str, event: &str) -> Self {
+        Self {
+            source: source.to_string(),
+            event: event.to_string(),
+            data: BTreeMap::new(),
+        }
+    }
+
+    /// Adds a value to the event data.
+    #[must_use]
+    pub fn with(mut self, key: &str, value: impl ToString) -> Self {
+        self.data.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Sends the event to all subscribed IPC clients.
+    /// If nobody is subscribed, the event is dropped.
+    pub fn publish(self) {
+        Ironbar::ipc_events().send(self).ok();
+    }
+}
+
+impl Display for IpcEvent {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.source, self.event)?;
+
+        for (key, value) in &self.data {
+            write!(f, " {key}={value}")?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+use super::Ipc;
+use crate::clients::reconnect::{self, Session};
+use crate::config::RemoteInstanceConfig;
+use crate::ipc::{Command, IronvarCommand, Response};
+use crate::ironvar::WritableNamespace;
+use crate::{Ironbar, lock, poll, spawn, spawn_blocking};
+use color_eyre::{Report, Result};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{debug, error};
+
+/// The task copying variables from each remote, keyed by remote name.
+static TASKS: Mutex<BTreeMap<String, JoinHandle<()>>> = Mutex::new(BTreeMap::new());
+
+/// The connection status name of each remote, keyed by remote name.
+static STATUS_NAMES: Mutex<BTreeMap<String, &'static str>> = Mutex::new(BTreeMap::new());
+
+/// Starts copying variables from each remote instance into local ironvars,
+/// stopping any remotes started previously.
+///
+/// This is called again when the config is reloaded,
+/// so that added, changed and removed remotes take effect.
+///
+/// Each remote is polled on its own interval,
+/// and reconnected to with backoff if it cannot be reached.
+/// Local variables keep their last value while the remote is unreachable.
+pub fn start(remotes: HashMap<String, RemoteInstanceConfig>) {
+    let mut tasks = lock!(TASKS);
+
+    for (name, task) in std::mem::take(&mut *tasks) {
+        task.abort();
+
+        if !remotes.contains_key(&name) {
+            reconnect::remove_status(status_name(&name));
+        }
+    }
+
+    for (name, config) in remotes {
+        let status_name = status_name(&name);
+
+        let task = spawn(async move {
+            let token = config.token.clone();
+            let token = match spawn_blocking(move || token.resolve()).await {
+                Ok(Ok(token)) => token,
+                Ok(Err(err)) => {
+                    error!(
+                        "{:?}",
+                        err.wrap_err(format!("Unable to connect to remote '{name}'"))
+                    );
+                    return;
+                }
+                Err(err) => {
+                    error!("{err:?}");
+                    return;
+                }
+            };
+
+            let ipc = Ipc::new().with_remote(config.address.clone(), token);
+
+            let ipc = &ipc;
+            let config = &config;
+            reconnect::run(status_name, move |session| mirror(ipc, config, session)).await;
+        });
+
+        tasks.insert(name, task);
+    }
+}
+
+/// Gets the connection status name for the remote,
+/// such as `remote_server`.
+///
+/// Statuses are keyed by static names,
+/// so each name is leaked once and re-used when remotes are restarted.
+fn status_name(name: &str) -> &'static str {
+    let mut names = lock!(STATUS_NAMES);
+
+    if let Some(status_name) = names.get(name) {
+        return status_name;
+    }
+
+    let status_name: &'static str = Box::leak(format!("remote_{name}").into_boxed_str());
+    names.insert(name.to_string(), status_name);
+
+    status_name
+}
+
+/// Copies each configured variable from the remote instance on an interval.
+///
+/// This runs until the remote cannot be reached or rejects the token.
+async fn mirror(ipc: &Ipc, config: &RemoteInstanceConfig, session: Session) -> Result<()> {
+    check_connection(ipc).await?;
+    session.connected();
+
+    let interval = Duration::from_secs(config.interval.max(1));
+
+    loop {
+        copy_vars(ipc, &config.vars).await?;
+        poll::sleep(interval).await;
+    }
+}
+
+/// Checks the remote instance can be reached and accepts the token.
+async fn check_connection(ipc: &Ipc) -> Result<()> {
+    match ipc.send(Command::Ping, false).await? {
+        Response::Ok => Ok(()),
+        Response::Err { message } => Err(Report::msg(message.unwrap_or_default())
+            .wrap_err("Remote instance rejected the connection")),
+        res => Err(Report::msg(format!("Unexpected response: {res:?}"))),
+    }
+}
+
+/// Copies each remote variable into its local ironvar.
+///
+/// Variables which are not set on the remote are skipped.
+async fn copy_vars(ipc: &Ipc, vars: &HashMap<Box<str>, Box<str>>) -> Result<()> {
+    let variable_manager = Ironbar::variable_manager();
+
+    for (local, remote) in vars {
+        let command = Command::Var(IronvarCommand::Get {
+            key: remote.clone(),
+        });
+
+        match ipc.send(command, false).await? {
+            Response::OkValue { value } => {
+                if let Err(err) = variable_manager.set(local, value) {
+                    error!("Failed to set ironvar '{local}': {err}");
+                }
+            }
+            res => debug!("Failed to get remote ironvar '{remote}': {res:?}"),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ironvar::Namespace;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    /// Answers each connection as a remote instance would,
+    /// replying to `var get` with the key reversed.
+    async fn serve(listener: TcpListener) {
+        loop {
+            let Ok((stream, _)) = listener.accept().await else {
+                return;
+            };
+
+            let mut lines = BufReader::new(stream).lines();
+
+            let auth = lines.next_line().await.ok().flatten().unwrap_or_default();
+            let command = lines.next_line().await.ok().flatten().unwrap_or_default();
+
+            let res = if !auth.contains("secret") {
+                Response::error("Invalid token")
+            } else {
+                match serde_json::from_str(&command) {
+                    Ok(Command::Ping) => Response::Ok,
+                    Ok(Command::Var(IronvarCommand::Get { key })) => Response::OkValue {
+                        value: key.chars().rev().collect(),
+                    },
+                    _ => Response::error("Unexpected command"),
+                }
+            };
+
+            let mut res = serde_json::to_vec(&res).expect("should serialize");
+            res.push(b'\n');
+            lines
+                .get_mut()
+                .get_mut()
+                .write_all(&res)
+                .await
+                .expect("should write");
+        }
+    }
+
+    async fn start_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("should bind");
+        let address = listener.local_addr().expect("should have address");
+        tokio::spawn(serve(listener));
+
+        address.to_string()
+    }
+
+    #[tokio::test]
+    async fn copy_remote_vars() {
+        let ipc = Ipc::new().with_remote(start_server().await, "secret".to_string());
+        let vars = HashMap::from([("mirror_test".into(), "remote.key".into())]);
+
+        copy_vars(&ipc, &vars).await.expect("should copy vars");
+
+        assert_eq!(
+            Ironbar::variable_manager().get("mirror_test").as_deref(),
+            Some("yek.etomer")
+        );
+    }
+
+    #[tokio::test]
+    async fn reject_invalid_token() {
+        let address = start_server().await;
+
+        let ipc = Ipc::new().with_remote(address.clone(), "secret".to_string());
+        assert!(check_connection(&ipc).await.is_ok());
+
+        let ipc = Ipc::new().with_remote(address, "guess".to_string());
+        assert!(check_connection(&ipc).await.is_err());
+    }
+}
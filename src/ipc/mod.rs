@@ -1,17 +1,36 @@
 mod client;
 pub mod commands;
+mod events;
+pub mod mirror;
 pub mod responses;
 mod server;
 
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use tracing::warn;
 
 pub use commands::*;
+pub use events::IpcEvent;
 pub use responses::Response;
 
 #[derive(Debug)]
 pub struct Ipc {
     path: PathBuf,
+    remote: Option<Remote>,
+}
+
+/// A remote Ironbar instance to connect to over TCP,
+/// instead of the local socket.
+#[derive(Debug)]
+struct Remote {
+    address: String,
+    token: String,
+}
+
+/// Sent by remote clients ahead of the command.
+#[derive(Debug, Serialize, Deserialize)]
+struct RemoteAuth {
+    token: String,
 }
 
 impl Ipc {
@@ -30,9 +49,17 @@ impl Ipc {
 
         Self {
             path: ipc_socket_file,
+            remote: None,
         }
     }
 
+    /// Sends commands to a remote instance over TCP,
+    /// authenticating with `token`.
+    pub fn with_remote(mut self, address: String, token: String) -> Self {
+        self.remote = Some(Remote { address, token });
+        self
+    }
+
     pub fn path(&self) -> &Path {
         self.path.as_path()
     }
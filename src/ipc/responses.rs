@@ -1,3 +1,4 @@
+use super::IpcEvent;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -7,6 +8,7 @@ pub enum Response {
     OkValue { value: String },
     Multi { values: Vec<String> },
     Err { message: Option<String> },
+    Event(IpcEvent),
 }
 
 impl Response {
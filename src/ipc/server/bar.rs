@@ -1,16 +1,22 @@
 use super::Response;
 use crate::Ironbar;
 use crate::bar::Bar;
-use crate::ipc::{BarCommand, BarCommandType};
+use crate::config::ModuleConfig;
+use crate::ipc::{BarCommand, BarCommandType, ModuleGroup};
+use crate::modules::ModuleLocation;
 use std::rc::Rc;
+use std::time::Duration;
 
 pub fn handle_command(command: &BarCommand, ironbar: &Rc<Ironbar>) -> Response {
     use BarCommandType::*;
 
-    let bars = ironbar.bars_by_name(&command.name);
+    let bars = ironbar.bars_matching(command.name.as_deref(), command.monitor.as_deref());
 
     bars.into_iter()
         .map(|bar| match &command.subcommand {
+            List => Response::OkValue {
+                value: format!("{}: {}", bar.monitor_name(), bar.name()),
+            },
             Show => set_visible(&bar, true),
             Hide => set_visible(&bar, false),
             SetVisible { visible } => set_visible(&bar, *visible),
@@ -42,10 +48,62 @@ pub fn handle_command(command: &BarCommand, ironbar: &Rc<Ironbar>) -> Response {
             GetPopupVisible => Response::OkValue {
                 value: bar.popup().visible().to_string(),
             },
+            SetPopupPinned { pinned } => {
+                if bar.popup().set_pinned(*pinned) {
+                    Response::Ok
+                } else {
+                    Response::error("Popup is not open")
+                }
+            }
+            GetPopupPinned => Response::OkValue {
+                value: bar.popup().pinned().to_string(),
+            },
+            FocusPopup => {
+                if bar.popup().focus() {
+                    Response::Ok
+                } else {
+                    Response::error("Popup is not open")
+                }
+            }
+            Peek { duration } => {
+                bar.toggle_peek(duration.map(Duration::from_millis));
+                Response::Ok
+            }
             SetExclusive { exclusive } => {
                 bar.set_exclusive(*exclusive);
                 Response::Ok
             }
+            AddModule {
+                config,
+                group,
+                index,
+            } => add_module(&bar, config, *group, *index),
+            RemoveModule { module } => match bar.find_module(module) {
+                Some(module) => {
+                    bar.remove_module(&module);
+                    Response::Ok
+                }
+                None => Response::error("Module not found"),
+            },
+            MoveModule {
+                module,
+                group,
+                index,
+            } => match bar.find_module(module) {
+                Some(module) => {
+                    bar.move_module(&module, group.map(ModuleLocation::from), *index);
+                    Response::Ok
+                }
+                None => Response::error("Module not found"),
+            },
+            ListModules => Response::OkValue {
+                value: bar
+                    .modules()
+                    .iter()
+                    .map(|m| format!("{}: {}", m.id, m.name))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            },
         })
         .reduce(|acc, rsp| match (acc, rsp) {
             // If all responses are `Ok`, return one `Ok`. We assume we'll never mix `Ok` and `OkValue`.
@@ -60,7 +118,7 @@ pub fn handle_command(command: &BarCommand, ironbar: &Rc<Ironbar>) -> Response {
             }
             (acc, _) => acc,
         })
-        .unwrap_or(Response::error("Invalid bar name"))
+        .unwrap_or(Response::error("No matching bars"))
 }
 
 fn set_visible(bar: &Bar, visible: bool) -> Response {
@@ -74,7 +132,8 @@ fn show_popup(bar: &Bar, widget_name: &str) -> Response {
     // only one popup per bar, so hide if open for another widget
     popup.hide();
 
-    let module_ref = bar.modules().iter().find(|m| m.name == widget_name);
+    let modules = bar.modules();
+    let module_ref = modules.iter().find(|m| m.name == widget_name);
 
     let module_button = module_ref
         .and_then(|m| m.popup.clone())
@@ -93,6 +152,30 @@ fn show_popup(bar: &Bar, widget_name: &str) -> Response {
     }
 }
 
+fn add_module(bar: &Bar, config: &str, group: ModuleGroup, index: Option<usize>) -> Response {
+    let config = match serde_json::from_str::<ModuleConfig>(config) {
+        Ok(config) => config,
+        Err(err) => return Response::error(&format!("Invalid module config: {err}")),
+    };
+
+    match bar.add_module(config, group.into(), index) {
+        Ok(id) => Response::OkValue {
+            value: id.to_string(),
+        },
+        Err(err) => Response::error(&format!("{err}")),
+    }
+}
+
+impl From<ModuleGroup> for ModuleLocation {
+    fn from(group: ModuleGroup) -> Self {
+        match group {
+            ModuleGroup::Start => Self::Left,
+            ModuleGroup::Center => Self::Center,
+            ModuleGroup::End => Self::Right,
+        }
+    }
+}
+
 fn hide_popup(bar: &Bar) -> Response {
     let popup = bar.popup();
     popup.hide();
@@ -0,0 +1,91 @@
+use super::style::modules_by_name;
+use crate::Ironbar;
+use crate::bar::Bar;
+use crate::ipc::{ClassCommand, Response};
+use gtk::prelude::*;
+
+/// The widgets targeted by a class command.
+enum Selector<'a> {
+    /// Every bar.
+    AllBars,
+    /// The bar with the given name.
+    Bar(&'a str),
+    /// All modules with the given name.
+    Module(&'a str),
+}
+
+impl<'a> From<&'a str> for Selector<'a> {
+    fn from(selector: &'a str) -> Self {
+        match selector {
+            "bar" => Self::AllBars,
+            _ => selector
+                .strip_prefix("bar:")
+                .map_or(Self::Module(selector), Self::Bar),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Action {
+    Add,
+    Remove,
+    Toggle,
+}
+
+pub fn handle_command(command: ClassCommand, ironbar: &Ironbar) -> Response {
+    let (action, selector, name) = match &command {
+        ClassCommand::Add { selector, name } => (Action::Add, selector, name),
+        ClassCommand::Remove { selector, name } => (Action::Remove, selector, name),
+        ClassCommand::Toggle { selector, name } => (Action::Toggle, selector, name),
+    };
+
+    match Selector::from(selector.as_str()) {
+        Selector::AllBars => apply_bars(ironbar.bars_matching(None, None), action, name),
+        Selector::Bar(bar_name) => {
+            apply_bars(ironbar.bars_matching(Some(bar_name), None), action, name)
+        }
+        Selector::Module(module_name) => {
+            let bars = ironbar.bars.borrow();
+            let modules = modules_by_name(&bars, module_name);
+
+            if modules.is_empty() {
+                return Response::error("Module not found");
+            }
+
+            for module in modules {
+                let add = match action {
+                    Action::Add => true,
+                    Action::Remove => false,
+                    Action::Toggle => !module.root_widget.has_css_class(name),
+                };
+
+                if add {
+                    module.add_css_class(name);
+                } else {
+                    module.remove_css_class(name);
+                }
+            }
+
+            Response::Ok
+        }
+    }
+}
+
+fn apply_bars(bars: Vec<Bar>, action: Action, name: &str) -> Response {
+    if bars.is_empty() {
+        return Response::error("Bar not found");
+    }
+
+    for bar in bars {
+        let window = bar.window();
+
+        match action {
+            Action::Add => window.add_css_class(name),
+            Action::Remove => window.remove_css_class(name),
+            Action::Toggle if window.has_css_class(name) => window.remove_css_class(name),
+            Action::Toggle => window.add_css_class(name),
+        }
+    }
+
+    Response::Ok
+}
@@ -0,0 +1,20 @@
+use crate::channels;
+use crate::ipc::{DebugCommand, Response};
+
+pub fn handle_command(command: &DebugCommand) -> Response {
+    match command {
+        DebugCommand::Lag => {
+            let values = channels::lag_stats()
+                .into_iter()
+                .map(|(name, stats)| {
+                    format!(
+                        "{name}: lagged {} times, missed {} updates",
+                        stats.events, stats.dropped
+                    )
+                })
+                .collect();
+
+            Response::Multi { values }
+        }
+    }
+}
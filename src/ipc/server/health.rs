@@ -0,0 +1,81 @@
+use crate::ipc::Response;
+use crate::{Ironbar, clients, logging};
+use cfg_if::cfg_if;
+use std::collections::BTreeMap;
+
+/// Gets an overview of the state of each client and bar,
+/// along with the most recent errors.
+pub fn handle_command(ironbar: &Ironbar) -> Response {
+    let mut values = vec![];
+
+    #[cfg(any(
+        feature = "bindmode",
+        feature = "focused",
+        feature = "hyprland",
+        feature = "keyboard",
+        feature = "workspaces",
+    ))]
+    values.push(format!(
+        "compositor: {}",
+        clients::compositor::Compositor::get_current()
+    ));
+
+    // reconnecting clients override the plain started status.
+    // some name their connection after the service they connect to,
+    // such as `pulse` for the volume client.
+    let statuses = clients::started()
+        .into_iter()
+        .map(|name| (name, String::from("started")))
+        .chain(connection_statuses())
+        .collect::<BTreeMap<_, _>>();
+
+    values.extend(
+        statuses
+            .into_iter()
+            .map(|(name, status)| format!("client {name}: {status}")),
+    );
+
+    values.extend(ironbar.bars.borrow().iter().map(|bar| {
+        format!(
+            "bar {} on {}: {}",
+            bar.name(),
+            bar.monitor_name(),
+            if bar.degraded() { "degraded" } else { "ok" }
+        )
+    }));
+
+    values.extend(
+        logging::recent_errors()
+            .into_iter()
+            .map(|error| format!("error: {error}")),
+    );
+
+    Response::Multi { values }
+}
+
+/// Gets the connection status of each reconnecting client.
+fn connection_statuses() -> Vec<(&'static str, String)> {
+    cfg_if! {
+        if #[cfg(any(
+            feature = "home_assistant",
+            feature = "hyprland",
+            feature = "kdeconnect",
+            feature = "mqtt",
+            feature = "music+mpd",
+            feature = "niri",
+            feature = "notifications",
+            feature = "screencast",
+            feature = "sway",
+            feature = "syncthing",
+            feature = "tray",
+            feature = "volume",
+        ))] {
+            clients::reconnect::statuses()
+                .into_iter()
+                .map(|(name, status)| (name, status.to_string()))
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+}
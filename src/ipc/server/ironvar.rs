@@ -5,26 +5,57 @@ use std::sync::Arc;
 
 pub fn handle_command(command: IronvarCommand) -> Response {
     match command {
-        IronvarCommand::Set { key, value } => {
+        IronvarCommand::Set { key, value, kind } => {
             let variable_manager = Ironbar::variable_manager();
-            match variable_manager.set(&key, value) {
+            let res = match kind {
+                Some(kind) => variable_manager.set_typed(&key, &value, kind),
+                None => variable_manager.set(&key, value),
+            };
+
+            match res {
+                Ok(()) => Response::Ok,
+                Err(err) => Response::error(&format!("{err}")),
+            }
+        }
+        IronvarCommand::Push { key, value } => {
+            match Ironbar::variable_manager().push(&key, &value) {
+                Ok(()) => Response::Ok,
+                Err(err) => Response::error(&format!("{err}")),
+            }
+        }
+        IronvarCommand::Remove { key, value } => {
+            match Ironbar::variable_manager().remove(&key, &value) {
                 Ok(()) => Response::Ok,
                 Err(err) => Response::error(&format!("{err}")),
             }
         }
+        IronvarCommand::Keys { key } => match Ironbar::variable_manager().keys(&key) {
+            Ok(keys) => Response::OkValue {
+                value: keys.join("\n"),
+            },
+            Err(err) => Response::error(&format!("{err}")),
+        },
         IronvarCommand::Get { mut key } => {
             let variable_manager = Ironbar::variable_manager();
             let mut ns: Arc<dyn Namespace + Sync + Send> = variable_manager;
 
             if key.contains('.') {
-                for part in key.split('.') {
-                    ns = if let Some(ns) = ns.get_namespace(part) {
-                        ns.clone()
-                    } else {
-                        key = part.into();
-                        break;
-                    };
+                let full_key = key.clone();
+                let mut rest = &*full_key;
+
+                // the remainder after the last namespace is the key,
+                // which may be a list index or map key
+                while let Some((part, remainder)) = rest.split_once('.') {
+                    match ns.get_namespace(part) {
+                        Some(child) => {
+                            ns = child;
+                            rest = remainder;
+                        }
+                        None => break,
+                    }
                 }
+
+                key = rest.into();
             }
 
             let value = ns.get(&key);
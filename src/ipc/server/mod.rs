@@ -1,23 +1,48 @@
 mod bar;
+mod class;
+mod debug;
+mod health;
 mod ironvar;
+mod profile;
+mod state;
 mod style;
 
 use std::fs;
 use std::path::Path;
 use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
 
 use color_eyre::{Report, Result};
 use gtk::Application;
 use gtk::prelude::*;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::{UnixListener, UnixStream};
-use tokio::sync::mpsc::{self, Receiver, Sender};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::net::{TcpListener, UnixListener};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::{self, Sender};
+use tokio::sync::oneshot;
+use tokio::time::timeout;
 use tracing::{debug, error, info, trace, warn};
 
-use super::Ipc;
-use crate::channels::{AsyncSenderExt, MpscReceiverExt};
+use super::{Ipc, RemoteAuth};
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt, MpscReceiverExt};
+use crate::config::RemoteIpcConfig;
 use crate::ipc::{Command, Response};
-use crate::{Ironbar, spawn};
+use crate::{Ironbar, spawn, spawn_blocking};
+
+/// The maximum length of the authentication message
+/// sent by remote clients, in bytes.
+const MAX_AUTH_LEN: u64 = 1024;
+
+/// The time remote clients have to authenticate
+/// before the connection is dropped.
+const AUTH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A command received over IPC,
+/// with the channel to send its response on.
+type CommandRequest = (Command, oneshot::Sender<Response>);
 
 impl Ipc {
     /// Starts the IPC server on its socket.
@@ -25,7 +50,12 @@ impl Ipc {
     /// Once started, the server will begin accepting connections.
     pub fn start(&self, application: &Application, ironbar: Rc<Ironbar>) {
         let (cmd_tx, cmd_rx) = mpsc::channel(32);
-        let (res_tx, mut res_rx) = mpsc::channel(32);
+
+        if let Some(config) = ironbar.config.borrow().ipc_remote.clone() {
+            Self::start_remote(config, cmd_tx.clone());
+        }
+
+        super::mirror::start(ironbar.config.borrow().remotes.clone());
 
         let path = self.path.clone();
 
@@ -53,9 +83,7 @@ impl Ipc {
                 match listener.accept().await {
                     Ok((stream, _addr)) => {
                         debug!("handling incoming connection");
-                        if let Err(err) =
-                            Self::handle_connection(stream, &cmd_tx, &mut res_rx).await
-                        {
+                        if let Err(err) = Self::handle_connection(stream, &cmd_tx, None).await {
                             error!("{err:?}");
                         }
                         debug!("done");
@@ -67,27 +95,103 @@ impl Ipc {
             }
         });
 
-        cmd_rx.recv_glib(application, move |application, command| {
+        cmd_rx.recv_glib(application, move |application, (command, res_tx)| {
             let res = Self::handle_command(command, application, &ironbar);
-            res_tx.send_spawn(res);
+            // the client may have disconnected
+            let _ = res_tx.send(res);
+        });
+    }
+
+    /// Starts listening for commands over TCP.
+    ///
+    /// Each connection is handled separately,
+    /// so an unauthenticated client cannot hold up others.
+    fn start_remote(config: RemoteIpcConfig, cmd_tx: Sender<CommandRequest>) {
+        spawn(async move {
+            let token = match spawn_blocking(move || config.token.resolve()).await {
+                Ok(Ok(token)) if !token.is_empty() => Arc::<str>::from(token),
+                Ok(Ok(_)) => {
+                    error!("Remote IPC token is empty, refusing to start remote IPC");
+                    return;
+                }
+                Ok(Err(err)) => {
+                    error!("{:?}", err.wrap_err("Unable to start remote IPC"));
+                    return;
+                }
+                Err(err) => {
+                    error!("{err:?}");
+                    return;
+                }
+            };
+
+            info!("Starting remote IPC on {}", config.address);
+
+            let listener = match TcpListener::bind(&config.address).await {
+                Ok(listener) => listener,
+                Err(err) => {
+                    error!(
+                        "{:?}",
+                        Report::new(err).wrap_err("Unable to start remote IPC server")
+                    );
+                    return;
+                }
+            };
+
+            loop {
+                match listener.accept().await {
+                    Ok((stream, addr)) => {
+                        debug!("handling incoming remote connection from {addr}");
+
+                        let cmd_tx = cmd_tx.clone();
+                        let token = token.clone();
+
+                        spawn(async move {
+                            if let Err(err) =
+                                Self::handle_connection(stream, &cmd_tx, Some(&token)).await
+                            {
+                                error!("{err:?}");
+                            }
+                        });
+                    }
+                    Err(err) => {
+                        error!("{err:?}");
+                    }
+                }
+            }
         });
     }
 
     /// Takes an incoming connections,
     /// reads the command message, and sends the response.
     ///
+    /// If `token` is set, the client must first send a matching token.
+    ///
     /// The connection is closed once the response has been written.
-    async fn handle_connection(
-        mut stream: UnixStream,
-        cmd_tx: &Sender<Command>,
-        res_rx: &mut Receiver<Response>,
-    ) -> Result<()> {
-        trace!("awaiting readable state");
-        stream.readable().await?;
+    async fn handle_connection<S>(
+        stream: S,
+        cmd_tx: &Sender<CommandRequest>,
+        token: Option<&str>,
+    ) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
+        let mut reader = BufReader::new(stream);
 
-        let mut read_buffer = Vec::with_capacity(1024);
+        if let Some(token) = token {
+            let Ok(authenticated) = timeout(AUTH_TIMEOUT, authenticate(&mut reader, token)).await
+            else {
+                warn!("Dropped remote IPC connection which did not authenticate in time");
+                return Ok(());
+            };
+
+            if !authenticated {
+                warn!("Rejected remote IPC connection with invalid token");
+                return Self::write_response(reader.get_mut(), &Response::error("Invalid token"))
+                    .await;
+            }
+        }
 
-        let mut reader = BufReader::new(&mut stream);
+        let mut read_buffer = Vec::with_capacity(1024);
 
         trace!("reading bytes");
         let bytes = reader.read_until(b'\n', &mut read_buffer).await?;
@@ -98,17 +202,28 @@ impl Ipc {
 
         debug!("Received command: {command:?}");
 
-        cmd_tx.send_expect(command).await;
-        let res = res_rx
-            .recv()
-            .await
-            .unwrap_or(Response::Err { message: None });
+        // events are streamed until the client disconnects,
+        // so must not hold up other connections
+        if let Command::Events { source } = command {
+            spawn(Self::stream_events(reader.into_inner(), source));
+            return Ok(());
+        }
 
-        let mut res = serde_json::to_vec(&res)?;
-        res.push(b'\n');
+        let (res_tx, res_rx) = oneshot::channel();
+        cmd_tx.send_expect((command, res_tx)).await;
+        let res = res_rx.await.unwrap_or(Response::Err { message: None });
 
-        trace!("awaiting writable state");
-        stream.writable().await?;
+        Self::write_response(reader.get_mut(), &res).await
+    }
+
+    /// Writes a response to the stream,
+    /// and shuts down the stream.
+    async fn write_response<S>(stream: &mut S, res: &Response) -> Result<()>
+    where
+        S: AsyncWrite + Unpin,
+    {
+        let mut res = serde_json::to_vec(res)?;
+        res.push(b'\n');
 
         debug!("writing {} bytes", res.len());
         stream.write_all(&res).await?;
@@ -119,6 +234,42 @@ impl Ipc {
         Ok(())
     }
 
+    /// Writes each published event to the stream,
+    /// optionally only those from modules of type `source`.
+    ///
+    /// This continues until the client disconnects.
+    async fn stream_events<S>(mut stream: S, source: Option<String>)
+    where
+        S: AsyncWrite + Unpin,
+    {
+        let mut events = Ironbar::ipc_events().subscribe();
+
+        loop {
+            let event = match events.recv_tracked("ipc events").await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            };
+
+            if source
+                .as_ref()
+                .is_some_and(|source| *source != event.source)
+            {
+                continue;
+            }
+
+            let Ok(mut res) = serde_json::to_vec(&Response::Event(event)) else {
+                continue;
+            };
+            res.push(b'\n');
+
+            if stream.write_all(&res).await.is_err() {
+                debug!("event stream closed");
+                break;
+            }
+        }
+    }
+
     /// Takes an input command, runs it and returns with the appropriate response.
     ///
     /// This runs on the main thread, allowing commands to interact with GTK.
@@ -134,25 +285,21 @@ impl Ipc {
                 Response::Ok
             }
             Command::Reload => {
-                info!("Closing existing bars");
-                ironbar.bars.borrow_mut().clear();
-
-                let windows = application.windows();
-                for window in windows {
-                    window.close();
-                }
-
                 ironbar.reload_config();
-
-                match crate::load_output_bars(ironbar, application) {
-                    Ok(_) => {}
-                    Err(err) => error!("{err:?}"),
-                }
+                ironbar.reload_bars(application);
+                ironbar.reload_services();
                 Response::Ok
             }
             Command::Var(cmd) => ironvar::handle_command(cmd),
+            Command::Get(cmd) => state::handle_command(&cmd, ironbar),
             Command::Bar(cmd) => bar::handle_command(&cmd, ironbar),
+            Command::Profile(cmd) => profile::handle_command(cmd, application, ironbar),
             Command::Style(cmd) => style::handle_command(cmd, ironbar),
+            Command::Class(cmd) => class::handle_command(cmd, ironbar),
+            Command::Debug(cmd) => debug::handle_command(&cmd),
+            Command::Health => health::handle_command(ironbar),
+            // handled when the connection is received
+            Command::Events { .. } => Response::Ok,
         }
     }
 
@@ -164,3 +311,86 @@ impl Ipc {
         fs::remove_file(&path).ok();
     }
 }
+
+/// Reads the authentication message from a remote client,
+/// checking it contains a matching token.
+///
+/// The message must be a single line of at most [`MAX_AUTH_LEN`] bytes.
+async fn authenticate<R>(reader: &mut R, token: &str) -> bool
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut auth = Vec::new();
+
+    match reader.take(MAX_AUTH_LEN).read_until(b'\n', &mut auth).await {
+        // the limit was reached or the client disconnected before the line ended
+        Ok(_) if auth.last() != Some(&b'\n') => false,
+        Ok(_) => serde_json::from_slice::<RemoteAuth>(&auth)
+            .is_ok_and(|auth| tokens_match(&auth.token, token)),
+        Err(_) => false,
+    }
+}
+
+/// Compares two tokens in constant time,
+/// so that neither a token nor its length can be guessed from how long the check takes.
+///
+/// Both tokens are padded to [`MAX_AUTH_LEN`] bytes first,
+/// so the same number of bytes are compared whatever their lengths.
+fn tokens_match(a: &str, b: &str) -> bool {
+    let diff = pad_token(a)
+        .iter()
+        .zip(pad_token(b).iter())
+        .fold(0, |acc, (a, b)| acc | (a ^ b));
+
+    // tokens longer than the padding differ in length from any client token,
+    // as a client cannot send one that long
+    (diff == 0) & (a.len() == b.len())
+}
+
+/// Copies the token into a fixed-length buffer, padded with zeros.
+fn pad_token(token: &str) -> [u8; MAX_AUTH_LEN as usize] {
+    let mut padded = [0; MAX_AUTH_LEN as usize];
+
+    let len = token.len().min(padded.len());
+    padded[..len].copy_from_slice(&token.as_bytes()[..len]);
+
+    padded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_tokens() {
+        assert!(tokens_match("secret", "secret"));
+        assert!(!tokens_match("secret", "secreT"));
+        assert!(!tokens_match("secret", "secret2"));
+        assert!(!tokens_match("", "secret"));
+        assert!(!tokens_match("secret", "secret\0"));
+    }
+
+    #[tokio::test]
+    async fn accept_valid_token() {
+        let mut reader = &b"{\"token\":\"secret\"}\n"[..];
+        assert!(authenticate(&mut reader, "secret").await);
+    }
+
+    #[tokio::test]
+    async fn reject_invalid_token() {
+        let mut reader = &b"{\"token\":\"guess\"}\n"[..];
+        assert!(!authenticate(&mut reader, "secret").await);
+
+        let mut reader = &b"not json\n"[..];
+        assert!(!authenticate(&mut reader, "secret").await);
+    }
+
+    #[tokio::test]
+    async fn reject_oversized_auth() {
+        let padding = " ".repeat(MAX_AUTH_LEN as usize);
+        let auth = format!("{{\"token\":\"secret\"{padding}}}\n");
+
+        let mut reader = auth.as_bytes();
+        assert!(!authenticate(&mut reader, "secret").await);
+    }
+}
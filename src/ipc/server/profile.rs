@@ -0,0 +1,41 @@
+use crate::Ironbar;
+use crate::ipc::{ProfileCommand, Response};
+use gtk::Application;
+use std::rc::Rc;
+
+pub fn handle_command(
+    command: ProfileCommand,
+    application: &Application,
+    ironbar: &Rc<Ironbar>,
+) -> Response {
+    match command {
+        ProfileCommand::Set { name } => {
+            if !ironbar.config.borrow().config_profiles.contains_key(&name) {
+                return Response::error(&format!("Config profile '{name}' not found"));
+            }
+
+            ironbar.profile.replace(Some(name));
+            ironbar.reload_config();
+            ironbar.reload_bars(application);
+            ironbar.reload_services();
+
+            Response::Ok
+        }
+        ProfileCommand::Get => match ironbar.config.borrow().config_profile.clone() {
+            Some(value) => Response::OkValue { value },
+            None => Response::error("No config profile is active"),
+        },
+        ProfileCommand::List => {
+            let mut values = ironbar
+                .config
+                .borrow()
+                .config_profiles
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>();
+
+            values.sort();
+            Response::Multi { values }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use crate::Ironbar;
+use crate::ipc::{Response, StateCommand};
+use serde::Serialize;
+
+/// Gets the current state of a client as JSON,
+/// starting the client if it is not already running.
+pub fn handle_command(command: &StateCommand, ironbar: &Ironbar) -> Response {
+    match command {
+        StateCommand::Volume => volume(ironbar),
+        StateCommand::Workspaces => workspaces(ironbar),
+        StateCommand::Focused => focused(ironbar),
+    }
+}
+
+fn to_response(state: &impl Serialize) -> Response {
+    match serde_json::to_string(state) {
+        Ok(value) => Response::OkValue { value },
+        Err(err) => Response::error(&format!("Failed to serialize state: {err}")),
+    }
+}
+
+#[cfg(feature = "volume")]
+fn volume(ironbar: &Ironbar) -> Response {
+    use crate::clients::volume::{Card, Sink, SinkInput, Source, SourceOutput};
+
+    #[derive(Serialize)]
+    struct VolumeState {
+        sinks: Vec<Sink>,
+        sources: Vec<Source>,
+        sink_inputs: Vec<SinkInput>,
+        source_outputs: Vec<SourceOutput>,
+        cards: Vec<Card>,
+    }
+
+    let client = ironbar.clients.borrow_mut().any_volume();
+
+    to_response(&VolumeState {
+        sinks: client.sinks(),
+        sources: client.sources(),
+        sink_inputs: client.sink_inputs(),
+        source_outputs: client.source_outputs(),
+        cards: client.cards(),
+    })
+}
+
+#[cfg(not(feature = "volume"))]
+fn volume(_ironbar: &Ironbar) -> Response {
+    Response::error("Volume support is not enabled")
+}
+
+#[cfg(feature = "workspaces")]
+fn workspaces(ironbar: &Ironbar) -> Response {
+    let client = ironbar.clients.borrow_mut().workspaces();
+
+    match client {
+        Ok(client) => to_response(&client.workspaces()),
+        Err(err) => Response::error(&format!("{err}")),
+    }
+}
+
+#[cfg(not(feature = "workspaces"))]
+fn workspaces(_ironbar: &Ironbar) -> Response {
+    Response::error("Workspaces support is not enabled")
+}
+
+#[cfg(feature = "toplevel")]
+fn focused(ironbar: &Ironbar) -> Response {
+    let client = ironbar.clients.borrow_mut().wayland();
+
+    let focused = client
+        .toplevel_info_all()
+        .into_iter()
+        .find(|info| info.focused);
+
+    to_response(&focused)
+}
+
+#[cfg(not(feature = "toplevel"))]
+fn focused(_ironbar: &Ironbar) -> Response {
+    Response::error("Focused window support is not enabled")
+}
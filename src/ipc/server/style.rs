@@ -64,7 +64,7 @@ pub fn handle_command(command: StyleCommand, ironbar: &Ironbar) -> Response {
     }
 }
 
-fn modules_by_name<'a>(bars: &'a [Bar], name: &str) -> Vec<&'a ModuleRef> {
+pub(super) fn modules_by_name(bars: &[Bar], name: &str) -> Vec<ModuleRef> {
     bars.iter()
         .flat_map(Bar::modules)
         .filter(|w| w.name == name)
@@ -72,7 +72,7 @@ fn modules_by_name<'a>(bars: &'a [Bar], name: &str) -> Vec<&'a ModuleRef> {
 }
 
 impl ModuleRef {
-    fn add_css_class(&self, name: &str) {
+    pub(super) fn add_css_class(&self, name: &str) {
         self.root_widget.add_css_class(name);
 
         if let Some(ref popup) = self.popup {
@@ -80,7 +80,7 @@ impl ModuleRef {
         }
     }
 
-    fn remove_css_class(&self, name: &str) {
+    pub(super) fn remove_css_class(&self, name: &str) {
         self.root_widget.remove_css_class(name);
 
         if let Some(ref popup) = self.popup {
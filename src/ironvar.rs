@@ -1,11 +1,23 @@
 #![doc = include_str!("../docs/Ironvars.md")]
 
 use crate::channels::SyncSenderExt;
-use crate::{arc_rw, read_lock, write_lock};
+use crate::{arc_rw, read_lock, spawn, spawn_blocking, write_lock};
+use color_eyre::eyre::WrapErr;
 use color_eyre::{Report, Result};
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
-use tokio::sync::broadcast;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+use tokio::sync::{Notify, broadcast};
+use tokio::time::sleep;
+use tracing::error;
+
+/// How long to wait after a variable changes before saving,
+/// so that changes in quick succession are written once.
+const SAVE_DELAY: Duration = Duration::from_millis(500);
 
 pub type NamespaceTrait = Arc<dyn Namespace + Sync + Send>;
 
@@ -32,6 +44,26 @@ pub trait WritableNamespace: Namespace {
 pub struct VariableManager {
     variables: Arc<RwLock<HashMap<Box<str>, IronVar>>>,
     namespaces: Arc<RwLock<HashMap<Box<str>, NamespaceTrait>>>,
+    persisted: Arc<RwLock<Persisted>>,
+    /// Wakes the task which saves variables, once started.
+    save: OnceLock<Arc<Notify>>,
+}
+
+/// Variables saved to the state file on change.
+#[derive(Debug, Default)]
+struct Persisted {
+    path: Option<PathBuf>,
+    keys: HashSet<Box<str>>,
+    /// The contents of the state file, read when the first variable is declared.
+    /// Values of variables which are no longer declared are kept.
+    values: Option<HashMap<String, IronvarValue>>,
+}
+
+impl Persisted {
+    fn values(&mut self) -> &mut HashMap<String, IronvarValue> {
+        self.values
+            .get_or_insert_with(|| self.path.as_deref().map(load_persisted).unwrap_or_default())
+    }
 }
 
 impl Default for VariableManager {
@@ -42,12 +74,61 @@ impl Default for VariableManager {
 
 impl VariableManager {
     pub fn new() -> Self {
+        Self::with_state_path(persisted_path())
+    }
+
+    /// Creates a manager which persists variables to the file at `path`.
+    fn with_state_path(path: Option<PathBuf>) -> Self {
+        #[cfg_attr(not(feature = "cli"), allow(unused_mut))]
+        let mut variables = HashMap::new();
+
+        // restore variables from before the bar was restarted
+        #[cfg(feature = "cli")]
+        variables.extend(
+            crate::supervisor::load_vars()
+                .into_iter()
+                .map(|(key, value)| (key.into(), IronVar::new(Some(value)))),
+        );
+
         Self {
-            variables: arc_rw!(HashMap::new()),
+            variables: arc_rw!(variables),
             namespaces: arc_rw!(HashMap::new()),
+            persisted: arc_rw!(Persisted {
+                path,
+                ..Persisted::default()
+            }),
+            save: OnceLock::new(),
         }
     }
 
+    /// Declares a variable from the config,
+    /// setting its type and default value.
+    ///
+    /// Persisted variables are restored from the state file.
+    /// Variables which already have a value, such as after a config reload, keep it.
+    pub fn declare(&self, key: &str, config: IronvarConfig) -> Result<()> {
+        let restored = if config.persist {
+            let mut persisted = write_lock!(self.persisted);
+            if persisted.path.is_none() {
+                error!("Missing XDG state dir, ironvars will not be persisted");
+            }
+
+            persisted.keys.insert(key.into());
+            persisted.values().get(key).cloned()
+        } else {
+            None
+        };
+
+        let value = match (restored.or(config.default), config.kind) {
+            (Some(value), Some(kind)) => value.into_kind(kind)?,
+            (Some(value), None) => value,
+            (None, Some(kind)) => kind.empty(),
+            (None, None) => return Ok(()),
+        };
+
+        self.update(key, |current| Ok(current.unwrap_or(value)))
+    }
+
     /// Subscribes to an `ironvar`, creating it if it does not exist.
     /// Any time the var is set, its value is sent on the channel.
     pub fn subscribe(&self, key: Box<str>) -> broadcast::Receiver<Option<String>> {
@@ -64,6 +145,156 @@ impl VariableManager {
                 .all(|char| char.is_alphanumeric() || char == '_' || char == '-')
     }
 
+    /// Sets the typed value for a variable,
+    /// creating it if it does not exist.
+    /// This changes the type of an existing variable.
+    pub fn set_value(&self, key: &str, value: IronvarValue) -> Result<()> {
+        self.update(key, |_| Ok(value))
+    }
+
+    /// Sets the value for a variable as the given type,
+    /// creating it if it does not exist.
+    /// This changes the type of an existing variable.
+    pub fn set_typed(&self, key: &str, value: &str, kind: IronvarType) -> Result<()> {
+        let value = kind.parse(value)?;
+        self.update(key, |_| Ok(value))
+    }
+
+    /// Appends an item to a list variable,
+    /// or inserts a `key=value` entry into a map variable.
+    ///
+    /// Unset variables are created as a list.
+    pub fn push(&self, key: &str, item: &str) -> Result<()> {
+        self.update(key, |value| match value {
+            None => Ok(IronvarValue::List(vec![item.to_string()])),
+            Some(IronvarValue::List(mut list)) => {
+                list.push(item.to_string());
+                Ok(IronvarValue::List(list))
+            }
+            Some(IronvarValue::Map(mut map)) => {
+                let (key, value) = parse_map_entry(item)?;
+                map.insert(key, value);
+                Ok(IronvarValue::Map(map))
+            }
+            Some(value) => Err(Report::msg(format!(
+                "Cannot push to a variable of type '{}'",
+                value.kind()
+            ))),
+        })
+    }
+
+    /// Removes all matching items from a list variable,
+    /// or the entry with the matching key from a map variable.
+    pub fn remove(&self, key: &str, item: &str) -> Result<()> {
+        self.update(key, |value| match value {
+            Some(IronvarValue::List(mut list)) => {
+                list.retain(|value| value != item);
+                Ok(IronvarValue::List(list))
+            }
+            Some(IronvarValue::Map(mut map)) => {
+                map.remove(item);
+                Ok(IronvarValue::Map(map))
+            }
+            Some(value) => Err(Report::msg(format!(
+                "Cannot remove from a variable of type '{}'",
+                value.kind()
+            ))),
+            None => Err(Report::msg("Variable not found")),
+        })
+    }
+
+    /// Gets the keys of a map variable,
+    /// or the indices of a list variable.
+    pub fn keys(&self, key: &str) -> Result<Vec<String>> {
+        let variables = read_lock!(self.variables);
+        let value = variables
+            .get(key)
+            .and_then(|var| var.value.as_ref())
+            .ok_or_else(|| Report::msg("Variable not found"))?;
+
+        match value {
+            IronvarValue::List(list) => Ok((0..list.len()).map(|i| i.to_string()).collect()),
+            IronvarValue::Map(map) => Ok(map.keys().cloned().collect()),
+            value => Err(Report::msg(format!(
+                "Variable of type '{}' has no keys",
+                value.kind()
+            ))),
+        }
+    }
+
+    /// Replaces the value of a variable with the result of `f`,
+    /// creating it if it does not exist.
+    fn update<F>(&self, key: &str, f: F) -> Result<()>
+    where
+        F: FnOnce(Option<IronvarValue>) -> Result<IronvarValue>,
+    {
+        if !Self::key_is_valid(key) {
+            return Err(Report::msg("Invalid key"));
+        }
+
+        let persist = {
+            let mut variables = write_lock!(self.variables);
+
+            // the variable is only created once `f` succeeds,
+            // so failed updates do not leave behind an empty variable.
+            let current = variables.get(key).and_then(|var| var.value.clone());
+            let value = f(current)?;
+
+            let var = variables
+                .entry(key.into())
+                .or_insert_with(|| IronVar::new(None));
+
+            let mut persisted = write_lock!(self.persisted);
+            let persist = persisted.keys.contains(key);
+            if persist {
+                persisted.values().insert(key.to_string(), value.clone());
+            }
+
+            var.set(Some(value));
+            persist
+        };
+
+        // all variables are restored if the bar is restarted by the supervisor
+        #[cfg(feature = "cli")]
+        let persist = persist || crate::supervisor::state_file().is_some();
+
+        if persist {
+            self.schedule_save();
+        }
+
+        Ok(())
+    }
+
+    /// Saves variables to disk shortly after they change.
+    ///
+    /// Saving happens in a background task outside the variable locks,
+    /// so changes in quick succession are batched into a single write.
+    fn schedule_save(&self) {
+        let save = self.save.get_or_init(|| {
+            let save = Arc::new(Notify::new());
+
+            let notify = save.clone();
+            let variables = self.variables.clone();
+            let persisted = self.persisted.clone();
+
+            spawn(async move {
+                loop {
+                    notify.notified().await;
+                    sleep(SAVE_DELAY).await;
+
+                    let snapshot = Snapshot::take(&variables, &persisted);
+                    if let Err(err) = spawn_blocking(move || snapshot.write()).await {
+                        error!("Failed to save ironvars: {err:?}");
+                    }
+                }
+            });
+
+            save
+        });
+
+        save.notify_one();
+    }
+
     pub fn register_namespace<N>(&self, name: &str, namespace: Arc<N>)
     where
         N: Namespace + Sync + Send + 'static,
@@ -78,9 +309,14 @@ impl Namespace for VariableManager {
             let (ns, key) = key.split_once('.')?;
 
             let namespaces = read_lock!(self.namespaces);
-            let ns = namespaces.get(ns)?;
-
-            ns.get(key).as_deref().map(ToOwned::to_owned)
+            match namespaces.get(ns) {
+                Some(ns) => ns.get(key).as_deref().map(ToOwned::to_owned),
+                // list index or map key
+                None => read_lock!(self.variables)
+                    .get(ns)
+                    .and_then(|var| var.value.as_ref())
+                    .and_then(|value| value.get(key)),
+            }
         } else {
             read_lock!(self.variables).get(key).and_then(IronVar::get)
         }
@@ -115,18 +351,293 @@ impl Namespace for VariableManager {
 impl WritableNamespace for VariableManager {
     /// Sets the value for a variable,
     /// creating it if it does not exist.
+    ///
+    /// The value is parsed as the variable's existing type.
     fn set(&self, key: &str, value: String) -> Result<()> {
-        if Self::key_is_valid(key) {
-            if let Some(var) = write_lock!(self.variables).get_mut(&Box::from(key)) {
-                var.set(Some(value));
-            } else {
-                let var = IronVar::new(Some(value));
-                write_lock!(self.variables).insert(key.into(), var);
+        self.update(key, |current| {
+            current
+                .as_ref()
+                .map_or(IronvarType::String, IronvarValue::kind)
+                .parse(&value)
+        })
+    }
+}
+
+/// The type of an ironvar.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "ipc", derive(clap::ValueEnum))]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum IronvarType {
+    Bool,
+    Int,
+    Float,
+    String,
+    List,
+    Map,
+}
+
+impl Display for IronvarType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Bool => "bool",
+                Self::Int => "int",
+                Self::Float => "float",
+                Self::String => "string",
+                Self::List => "list",
+                Self::Map => "map",
             }
+        )
+    }
+}
+
+impl IronvarType {
+    /// Gets the empty value of this type,
+    /// for variables declared without a default.
+    fn empty(self) -> IronvarValue {
+        match self {
+            Self::Bool => IronvarValue::Bool(false),
+            Self::Int => IronvarValue::Int(0),
+            Self::Float => IronvarValue::Float(0.0),
+            Self::String => IronvarValue::String(String::new()),
+            Self::List => IronvarValue::List(vec![]),
+            Self::Map => IronvarValue::Map(BTreeMap::new()),
+        }
+    }
+
+    /// Parses a string as a value of this type.
+    ///
+    /// Lists are comma-separated items,
+    /// and maps are comma-separated `key=value` entries.
+    pub fn parse(self, value: &str) -> Result<IronvarValue> {
+        match self {
+            Self::Bool => match value.trim() {
+                "true" | "1" | "yes" | "on" => Ok(IronvarValue::Bool(true)),
+                "false" | "0" | "no" | "off" => Ok(IronvarValue::Bool(false)),
+                _ => Err(Report::msg(format!("Invalid bool: '{value}'"))),
+            },
+            Self::Int => value
+                .trim()
+                .parse()
+                .map(IronvarValue::Int)
+                .wrap_err_with(|| format!("Invalid int: '{value}'")),
+            Self::Float => value
+                .trim()
+                .parse()
+                .map(IronvarValue::Float)
+                .wrap_err_with(|| format!("Invalid float: '{value}'")),
+            Self::String => Ok(IronvarValue::String(value.to_string())),
+            Self::List => Ok(IronvarValue::List(
+                split_items(value).map(ToString::to_string).collect(),
+            )),
+            Self::Map => split_items(value)
+                .map(parse_map_entry)
+                .collect::<Result<_>>()
+                .map(IronvarValue::Map),
+        }
+    }
+}
+
+fn split_items(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+fn parse_map_entry(entry: &str) -> Result<(String, String)> {
+    entry
+        .split_once('=')
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .ok_or_else(|| {
+            Report::msg(format!(
+                "Invalid map entry: '{entry}', expected 'key=value'"
+            ))
+        })
+}
+
+/// Config for declaring an ironvar.
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(default)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct IronvarConfig {
+    /// The initial value.
+    ///
+    /// **Default**: `null`
+    default: Option<IronvarValue>,
+
+    /// The variable type.
+    /// If not set, this is the type of `default`.
+    ///
+    /// **Valid options**: `bool`, `int`, `float`, `string`, `list`, `map`
+    /// <br>
+    /// **Default**: `null`
+    #[serde(rename = "type")]
+    kind: Option<IronvarType>,
+
+    /// Whether to save the value to the state directory each time it changes,
+    /// restoring it on startup.
+    ///
+    /// **Default**: `false`
+    persist: bool,
+}
+
+/// Gets the path of the file storing persisted variables,
+/// as a JSON object of keys against values.
+fn persisted_path() -> Option<PathBuf> {
+    dirs::state_dir().map(|dir| dir.join("ironbar").join("ironvars.json"))
+}
+
+fn load_persisted(path: &Path) -> HashMap<String, IronvarValue> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|err| {
+            error!("Failed to parse persisted ironvars: {err:?}");
+            HashMap::new()
+        }),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+        Err(err) => {
+            error!("Failed to load persisted ironvars: {err:?}");
+            HashMap::new()
+        }
+    }
+}
 
-            Ok(())
+/// Variables to save, copied out of the manager
+/// so that they can be written without holding its locks.
+struct Snapshot {
+    persisted: Option<(PathBuf, HashMap<String, IronvarValue>)>,
+    #[cfg(feature = "cli")]
+    supervised: Option<(PathBuf, HashMap<Box<str>, IronvarValue>)>,
+}
+
+impl Snapshot {
+    #[cfg_attr(not(feature = "cli"), allow(unused_variables))]
+    fn take(variables: &RwLock<HashMap<Box<str>, IronVar>>, persisted: &RwLock<Persisted>) -> Self {
+        let persisted = {
+            let persisted = read_lock!(persisted);
+            persisted.path.clone().zip(persisted.values.clone())
+        };
+
+        #[cfg(feature = "cli")]
+        let supervised = crate::supervisor::state_file().map(|path| {
+            let values = read_lock!(variables)
+                .iter()
+                .filter_map(|(k, v)| v.value.clone().map(|value| (k.clone(), value)))
+                .collect();
+
+            (path, values)
+        });
+
+        Self {
+            persisted,
+            #[cfg(feature = "cli")]
+            supervised,
+        }
+    }
+
+    fn write(self) {
+        if let Some((path, values)) = self.persisted
+            && let Err(err) = write_atomic(&path, &values)
+        {
+            error!("Failed to save persisted ironvars: {err:?}");
+        }
+
+        #[cfg(feature = "cli")]
+        if let Some((path, values)) = self.supervised
+            && let Err(err) = write_atomic(&path, &values)
+        {
+            tracing::warn!("Failed to save ironvar state: {err:?}");
+        }
+    }
+}
+
+/// Writes `value` as JSON to a temporary file beside `path`,
+/// then renames it into place so that a partial file is never left behind.
+fn write_atomic(path: &Path, value: &impl Serialize) -> Result<()> {
+    let contents = serde_json::to_string(value)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(&tmp, path)?;
+
+    Ok(())
+}
+
+/// A typed ironvar value.
+///
+/// Values are sent to subscribers as strings,
+/// with lists and maps formatted as comma-separated items.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum IronvarValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    List(Vec<String>),
+    Map(BTreeMap<String, String>),
+}
+
+impl Display for IronvarValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bool(value) => write!(f, "{value}"),
+            Self::Int(value) => write!(f, "{value}"),
+            Self::Float(value) => write!(f, "{value}"),
+            Self::String(value) => write!(f, "{value}"),
+            Self::List(list) => write!(f, "{}", list.join(", ")),
+            Self::Map(map) => write!(
+                f,
+                "{}",
+                map.iter()
+                    .map(|(key, value)| format!("{key}: {value}"))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+impl IronvarValue {
+    pub fn kind(&self) -> IronvarType {
+        match self {
+            Self::Bool(_) => IronvarType::Bool,
+            Self::Int(_) => IronvarType::Int,
+            Self::Float(_) => IronvarType::Float,
+            Self::String(_) => IronvarType::String,
+            Self::List(_) => IronvarType::List,
+            Self::Map(_) => IronvarType::Map,
+        }
+    }
+
+    /// Converts the value to the given type,
+    /// parsing its string representation if the type differs.
+    fn into_kind(self, kind: IronvarType) -> Result<Self> {
+        if self.kind() == kind {
+            Ok(self)
         } else {
-            Err(Report::msg("Invalid key"))
+            kind.parse(&self.to_string())
+        }
+    }
+
+    /// Gets an item from a list by index,
+    /// or an entry from a map by key.
+    fn get(&self, key: &str) -> Option<String> {
+        match self {
+            Self::List(list) => key
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| list.get(index).cloned()),
+            Self::Map(map) => map.get(key).cloned(),
+            _ => None,
         }
     }
 }
@@ -135,14 +646,14 @@ impl WritableNamespace for VariableManager {
 /// Interact with them through the `VARIABLE_MANAGER` `VariableManager` singleton.
 #[derive(Debug)]
 pub struct IronVar {
-    value: Option<String>,
+    value: Option<IronvarValue>,
     tx: broadcast::Sender<Option<String>>,
     _rx: broadcast::Receiver<Option<String>>,
 }
 
 impl IronVar {
     /// Creates a new variable.
-    fn new(value: Option<String>) -> Self {
+    fn new(value: Option<IronvarValue>) -> Self {
         let (tx, rx) = broadcast::channel(32);
 
         Self { value, tx, _rx: rx }
@@ -151,21 +662,268 @@ impl IronVar {
     /// Gets the current variable value.
     /// Prefer to subscribe to changes where possible.
     pub fn get(&self) -> Option<String> {
-        self.value.clone()
+        self.value.as_ref().map(ToString::to_string)
     }
 
     /// Sets the current variable value.
     /// The change is broadcast to all receivers.
-    fn set(&mut self, value: Option<String>) {
-        self.value.clone_from(&value);
-        self.tx.send_expect(value);
+    fn set(&mut self, value: Option<IronvarValue>) {
+        self.tx.send_expect(value.as_ref().map(ToString::to_string));
+        self.value = value;
     }
 
     /// Subscribes to the variable.
     /// The latest value is immediately sent to all receivers.
     fn subscribe(&self) -> broadcast::Receiver<Option<String>> {
         let rx = self.tx.subscribe();
-        self.tx.send_expect(self.value.clone());
+        self.tx.send_expect(self.get());
         rx
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map(entries: &[(&str, &str)]) -> IronvarValue {
+        IronvarValue::Map(
+            entries
+                .iter()
+                .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+                .collect(),
+        )
+    }
+
+    fn list(items: &[&str]) -> IronvarValue {
+        IronvarValue::List(items.iter().map(ToString::to_string).collect())
+    }
+
+    #[test]
+    fn parse_scalars() {
+        assert_eq!(
+            IronvarType::Bool.parse(" yes ").expect("should parse"),
+            IronvarValue::Bool(true)
+        );
+        assert_eq!(
+            IronvarType::Bool.parse("off").expect("should parse"),
+            IronvarValue::Bool(false)
+        );
+        assert_eq!(
+            IronvarType::Int.parse(" -3").expect("should parse"),
+            IronvarValue::Int(-3)
+        );
+        assert_eq!(
+            IronvarType::Float.parse("1.5").expect("should parse"),
+            IronvarValue::Float(1.5)
+        );
+        assert_eq!(
+            IronvarType::String.parse(" a, b ").expect("should parse"),
+            IronvarValue::String(" a, b ".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_invalid() {
+        assert!(IronvarType::Bool.parse("maybe").is_err());
+        assert!(IronvarType::Int.parse("1.5").is_err());
+        assert!(IronvarType::Float.parse("abc").is_err());
+        assert!(IronvarType::Map.parse("a=1,b").is_err());
+    }
+
+    #[test]
+    fn parse_collections() {
+        assert_eq!(
+            IronvarType::List.parse("a, b,,c ").expect("should parse"),
+            list(&["a", "b", "c"])
+        );
+        assert_eq!(
+            IronvarType::List.parse("").expect("should parse"),
+            list(&[])
+        );
+        assert_eq!(
+            IronvarType::Map.parse("a = 1, b=2").expect("should parse"),
+            map(&[("a", "1"), ("b", "2")])
+        );
+    }
+
+    #[test]
+    fn get_item() {
+        let value = list(&["a", "b"]);
+        assert_eq!(value.get("1"), Some("b".to_string()));
+        assert_eq!(value.get("2"), None);
+        assert_eq!(value.get("b"), None);
+
+        let value = map(&[("a", "1")]);
+        assert_eq!(value.get("a"), Some("1".to_string()));
+        assert_eq!(value.get("b"), None);
+
+        assert_eq!(IronvarValue::Int(1).get("0"), None);
+    }
+
+    #[test]
+    fn push_remove_list() {
+        let manager = VariableManager::new();
+
+        manager.push("list", "a").expect("should update");
+        manager.push("list", "b").expect("should update");
+        manager.push("list", "a").expect("should update");
+        assert_eq!(manager.get("list"), Some("a, b, a".to_string()));
+        assert_eq!(manager.get("list.1"), Some("b".to_string()));
+        assert_eq!(
+            manager.keys("list").expect("should have keys"),
+            vec!["0", "1", "2"]
+        );
+
+        manager.remove("list", "a").expect("should update");
+        assert_eq!(manager.get("list"), Some("b".to_string()));
+    }
+
+    #[test]
+    fn push_remove_map() {
+        let manager = VariableManager::new();
+
+        manager
+            .set_typed("map", "a=1", IronvarType::Map)
+            .expect("should update");
+        manager.push("map", "b = 2").expect("should update");
+        manager.push("map", "a=3").expect("should update");
+        assert_eq!(manager.get("map"), Some("a: 3, b: 2".to_string()));
+        assert_eq!(manager.get("map.b"), Some("2".to_string()));
+        assert_eq!(
+            manager.keys("map").expect("should have keys"),
+            vec!["a", "b"]
+        );
+
+        assert!(manager.push("map", "c").is_err());
+
+        manager.remove("map", "a").expect("should update");
+        assert_eq!(manager.get("map"), Some("b: 2".to_string()));
+    }
+
+    #[test]
+    fn push_remove_invalid() {
+        let manager = VariableManager::new();
+
+        manager
+            .set_typed("int", "1", IronvarType::Int)
+            .expect("should update");
+        assert!(manager.push("int", "2").is_err());
+        assert!(manager.remove("int", "1").is_err());
+        assert!(manager.keys("int").is_err());
+        assert_eq!(manager.get("int"), Some("1".to_string()));
+
+        assert!(manager.push("invalid key", "a").is_err());
+    }
+
+    #[test]
+    fn failed_update_does_not_create() {
+        let manager = VariableManager::new();
+
+        assert!(manager.remove("missing", "a").is_err());
+        assert!(
+            manager
+                .set_typed("missing", "abc", IronvarType::Int)
+                .is_err()
+        );
+        assert!(!manager.list().contains(&"missing".to_string()));
+    }
+
+    /// Gets a unique path for a test state file.
+    fn state_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("ironbar-test-{}-{name}.json", std::process::id()))
+    }
+
+    #[test]
+    fn declare_defaults() {
+        let manager = VariableManager::with_state_path(None);
+
+        let config = IronvarConfig {
+            default: Some(IronvarValue::String("5".to_string())),
+            kind: Some(IronvarType::Int),
+            ..IronvarConfig::default()
+        };
+        manager.declare("count", config).expect("should declare");
+        assert_eq!(manager.get("count"), Some("5".to_string()));
+
+        let config = IronvarConfig {
+            kind: Some(IronvarType::List),
+            ..IronvarConfig::default()
+        };
+        manager.declare("items", config).expect("should declare");
+        assert_eq!(
+            manager.keys("items").expect("should have keys"),
+            Vec::<String>::new()
+        );
+
+        manager
+            .declare("unset", IronvarConfig::default())
+            .expect("should declare");
+        assert!(!manager.list().contains(&"unset".to_string()));
+    }
+
+    #[test]
+    fn declare_keeps_value() {
+        let manager = VariableManager::with_state_path(None);
+
+        manager
+            .set("count", "2".to_string())
+            .expect("should update");
+
+        let config = IronvarConfig {
+            default: Some(IronvarValue::Int(1)),
+            ..IronvarConfig::default()
+        };
+        manager.declare("count", config).expect("should declare");
+        assert_eq!(manager.get("count"), Some("2".to_string()));
+    }
+
+    #[test]
+    fn restore_persisted() {
+        let path = state_path("restore");
+        fs::write(&path, r#"{"count": 3, "other": "kept"}"#).expect("should write");
+
+        let manager = VariableManager::with_state_path(Some(path.clone()));
+        // saved explicitly below, instead of by the background task
+        let _ = manager.save.set(Arc::new(Notify::new()));
+
+        let config = IronvarConfig {
+            default: Some(IronvarValue::Int(0)),
+            persist: true,
+            ..IronvarConfig::default()
+        };
+        manager.declare("count", config).expect("should declare");
+        assert_eq!(manager.get("count"), Some("3".to_string()));
+
+        manager
+            .set("count", "4".to_string())
+            .expect("should update");
+        manager
+            .set("other", "changed".to_string())
+            .expect("should update");
+
+        Snapshot::take(&manager.variables, &manager.persisted).write();
+        let saved = load_persisted(&path);
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(saved.get("count"), Some(&IronvarValue::Int(4)));
+        // not declared with `persist`, so the saved value is left alone
+        assert_eq!(
+            saved.get("other"),
+            Some(&IronvarValue::String("kept".to_string()))
+        );
+    }
+
+    #[test]
+    fn set_keeps_type() {
+        let manager = VariableManager::new();
+
+        manager
+            .set_typed("count", "1", IronvarType::Int)
+            .expect("should update");
+        manager
+            .set("count", "2".to_string())
+            .expect("should update");
+        assert!(manager.set("count", "abc".to_string()).is_err());
+        assert_eq!(manager.get("count"), Some("2".to_string()));
+    }
+}
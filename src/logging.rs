@@ -1,16 +1,69 @@
+use crate::lock;
 use color_eyre::Result;
 use dirs::data_dir;
 use glib::{LogLevel, LogWriterOutput};
+use std::collections::VecDeque;
+use std::fmt::{Debug, Write};
+use std::sync::Mutex;
 use std::{env, panic};
 use strip_ansi_escapes::Writer;
-use tracing::{debug, error, info, warn};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber, debug, error, info, warn};
 use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
 use tracing_appender::rolling::Rotation;
 use tracing_error::ErrorLayer;
 use tracing_subscriber::fmt::{Layer, MakeWriter};
+use tracing_subscriber::layer::Context;
 use tracing_subscriber::prelude::*;
 use tracing_subscriber::{EnvFilter, fmt};
 
+/// The number of recent errors to keep.
+const MAX_RECENT_ERRORS: usize = 10;
+
+/// The most recently logged errors, oldest first.
+static RECENT_ERRORS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Gets the most recently logged errors, oldest first.
+pub fn recent_errors() -> Vec<String> {
+    lock!(RECENT_ERRORS).iter().cloned().collect()
+}
+
+/// Layer which keeps the most recently logged errors,
+/// so they can be shown by `ironbar health`.
+struct RecentErrorsLayer;
+
+impl<S: Subscriber> tracing_subscriber::Layer<S> for RecentErrorsLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if *event.metadata().level() != Level::ERROR {
+            return;
+        }
+
+        let mut message = MessageVisitor(String::new());
+        event.record(&mut message);
+
+        // panic reports and eyre errors span many lines
+        let message = strip_ansi_escapes::strip_str(&message.0);
+        let message = message.lines().next().unwrap_or_default();
+
+        let mut errors = lock!(RECENT_ERRORS);
+        if errors.len() == MAX_RECENT_ERRORS {
+            errors.pop_front();
+        }
+
+        errors.push_back(format!("[{}] {message}", event.metadata().target()));
+    }
+}
+
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, "{value:?}");
+        }
+    }
+}
+
 struct MakeFileWriter {
     file_writer: NonBlocking,
 }
@@ -78,6 +131,7 @@ fn install_tracing(debug: bool) -> Result<WorkerGuard> {
         .with(filter_layer)
         .with(fmt_layer)
         .with(ErrorLayer::default())
+        .with(RecentErrorsLayer)
         .with(
             Layer::default()
                 .with_writer(MakeFileWriter::new(file_writer))
@@ -2,6 +2,7 @@
 #![deny(clippy::unwrap_used)]
 
 use std::cell::RefCell;
+use std::collections::HashSet;
 use std::env;
 use std::future::Future;
 use std::process::exit;
@@ -19,19 +20,21 @@ use gtk::prelude::*;
 use smithay_client_toolkit::output::OutputInfo;
 use tokio::runtime::Runtime;
 use tokio::task::{JoinHandle, block_in_place};
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use crate::bar::{Bar, create_bar};
 use crate::channels::SyncSenderExt;
 use crate::clients::Clients;
 use crate::clients::outputs::MonitorState;
-use crate::config::{Config, ConfigLocation, MonitorConfig};
+use crate::config::{BarConfig, Config, ConfigLocation, MonitorConfig};
 use crate::desktop_file::DesktopFiles;
 use crate::error::ExitCode;
 #[cfg(any(feature = "ipc", feature = "cairo"))]
 use crate::ironvar::VariableManager;
+use crate::panic_guard::PanicGuard;
 use crate::style::{CssSource, load_css};
 
+mod animation;
 mod bar;
 mod channels;
 #[cfg(feature = "cli")]
@@ -43,16 +46,28 @@ mod dynamic_value;
 mod error;
 mod gtk_helpers;
 mod image;
+#[cfg(feature = "cli")]
+mod init;
 #[cfg(feature = "ipc")]
 mod ipc;
 #[cfg(any(feature = "ipc", feature = "cairo"))]
 mod ironvar;
 mod logging;
 mod macros;
+#[cfg(feature = "cli")]
+mod migrate;
 mod modules;
+#[cfg(feature = "osd")]
+mod osd;
+mod panic_guard;
+mod poll;
 mod popup;
 mod script;
+mod startup;
 mod style;
+#[cfg(feature = "cli")]
+mod supervisor;
+mod theme;
 
 pub const APP_ID: &str = "dev.jstanger.ironbar";
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -74,11 +89,7 @@ fn run_with_args() {
 
     #[cfg(feature = "extras")]
     if args.print_schema {
-        let schema = schemars::schema_for!(Config);
-        println!(
-            "{}",
-            serde_json::to_string_pretty(&schema).expect("to be serializable")
-        );
+        print_schema();
         return;
     }
 
@@ -98,21 +109,63 @@ fn run_with_args() {
     }
 
     match args.command {
-        Some(command) => {
+        Some(cli::CliCommand::Migrate(migrate_args)) => {
+            let _guard = logging::install_logging(args.debug);
+            migrate::run(migrate_args);
+        }
+        Some(cli::CliCommand::Init(init_args)) => {
+            let _guard = logging::install_logging(args.debug);
+            init::run(init_args);
+        }
+        #[cfg(feature = "extras")]
+        Some(cli::CliCommand::Schema) => print_schema(),
+        Some(cli::CliCommand::Ipc(command)) => {
             if args.debug {
                 eprintln!("REQUEST: {command:?}");
             }
 
+            let token = match args.token_file {
+                Some(path) => match std::fs::read_to_string(&path) {
+                    Ok(token) => Some(token.trim_end_matches(['\r', '\n']).to_string()),
+                    Err(err) => {
+                        error!("Failed to read token file '{}': {err}", path.display());
+                        exit(ExitCode::CliError as i32)
+                    }
+                },
+                None => args.token,
+            };
+
+            let remote = args.remote.zip(token);
+
             let rt = create_runtime();
             rt.block_on(async move {
-                let ipc = ipc::Ipc::new();
+                let mut ipc = ipc::Ipc::new();
+                if let Some((address, token)) = remote {
+                    ipc = ipc.with_remote(address, token);
+                }
+
+                let format = args.format.unwrap_or_default();
+
+                if matches!(command, ipc::Command::Events { .. }) {
+                    let res = ipc
+                        .stream(command, args.debug, |res| cli::handle_response(res, format))
+                        .await;
+
+                    if let Err(err) = res {
+                        error!("{err:#}");
+                        exit(ExitCode::IpcResponseError as i32)
+                    }
+
+                    return;
+                }
+
                 match ipc.send(command, args.debug).await {
                     Ok(res) => {
                         if args.debug {
                             eprintln!("RESPONSE: {res:?}");
                         }
 
-                        cli::handle_response(res, args.format.unwrap_or_default());
+                        cli::handle_response(res, format);
                     }
                     Err(err) => {
                         error!("{err:#}");
@@ -122,23 +175,29 @@ fn run_with_args() {
             });
         }
         #[cfg(feature = "config")]
+        Some(cli::CliCommand::Config(cli::ConfigCommand::Validate { strict })) => {
+            validate_config(
+                args.debug,
+                args.config,
+                args.theme,
+                if strict { 2 } else { 1 },
+            );
+        }
+        #[cfg(feature = "config")]
         None if args.validate_config > 0 => {
+            validate_config(args.debug, args.config, args.theme, args.validate_config);
+        }
+        None if args.supervise => {
             let _guard = logging::install_logging(args.debug);
+            supervisor::run();
+        }
+        None => {
+            if args.profile_startup {
+                startup::enable();
+            }
 
-            let (_, _, error_level) = Config::load(args.config.unwrap_or_default(), args.theme);
-
-            let err = match args.validate_config {
-                1 => error_level >= config::ErrorLevel::Error,
-                2 => error_level >= config::ErrorLevel::Warn,
-                _ => {
-                    error!("invalid validate_config level");
-                    exit(ExitCode::CliError as i32)
-                }
-            };
-
-            exit(err as i32);
+            start_ironbar(args.debug, args.config.unwrap_or_default(), args.theme);
         }
-        None => start_ironbar(args.debug, args.config.unwrap_or_default(), args.theme),
     }
 }
 
@@ -150,9 +209,13 @@ pub struct Ironbar {
     css_source: Rc<CssSource>,
     config_location: ConfigLocation,
     css_location: Option<ConfigLocation>,
+    /// The config profile selected at runtime,
+    /// which takes priority over the profile set in the config.
+    profile: RefCell<Option<String>>,
 
     desktop_files: DesktopFiles,
     image_provider: image::Provider,
+    theme: theme::Theme,
 }
 
 impl Ironbar {
@@ -160,16 +223,30 @@ impl Ironbar {
         cfg_if!(
             if #[cfg(feature = "config")] {
                 let (mut config, css_source, _) =
-                    Config::load(config_location.clone(), css_location.clone());
+                    Config::load(config_location.clone(), css_location.clone(), None);
             } else {
                 let (mut config, css_source) =
-                    Config::load(config_location.clone(), css_location.clone());
+                    Config::load(config_location.clone(), css_location.clone(), None);
             }
         );
 
+        script::set_limits(
+            config
+                .script_concurrency
+                .unwrap_or(script::DEFAULT_CONCURRENCY),
+            config
+                .script_min_interval
+                .unwrap_or(script::DEFAULT_MIN_INTERVAL),
+        );
+
+        poll::configure(config.polling);
+
         let desktop_files = DesktopFiles::new();
-        let image_provider =
-            image::Provider::new(desktop_files.clone(), &mut config.icon_overrides);
+        let image_provider = image::Provider::new(
+            desktop_files.clone(),
+            &mut config.icon_overrides,
+            config.image_cache_size.unwrap_or(image::DEFAULT_CACHE_SIZE),
+        );
 
         Self {
             bars: rc_mut!(vec![]),
@@ -178,8 +255,10 @@ impl Ironbar {
             css_source: Rc::new(css_source),
             config_location,
             css_location,
+            profile: RefCell::new(None),
             desktop_files,
             image_provider,
+            theme: theme::Theme::default(),
         }
     }
 
@@ -263,6 +342,11 @@ impl Ironbar {
                     }
                 };
 
+                startup::report();
+
+                #[cfg(feature = "config")]
+                config::watch_config(&instance, &app);
+
                 let outputs = instance.clients.borrow_mut().outputs();
                 let mut rx_outputs = outputs.subscribe();
 
@@ -270,24 +354,33 @@ impl Ironbar {
 
                 // Listen for monitor events
                 while let Ok(event) = rx_outputs.recv().await {
-                    match event.state {
-                        MonitorState::Disconnected => {
-                            instance
-                                .bars
-                                .borrow_mut()
-                                .extract_if(.., |bar| bar.monitor_name() == event.connector)
-                                .for_each(Bar::close);
+                    // outputs can be announced again after their details change,
+                    // so any existing bars are replaced to re-evaluate which config matches.
+                    instance
+                        .bars
+                        .borrow_mut()
+                        .extract_if(.., |bar| bar.monitor_name() == event.connector)
+                        .for_each(Bar::close);
+
+                    // bars move to the next output if the primary goes away
+                    if instance.config.borrow().primary_only {
+                        if instance.bars.borrow().is_empty()
+                            && let Err(err) = load_output_bars(&instance, &app)
+                        {
+                            error!("{err:?}");
                         }
-                        MonitorState::Connected(wl_output, gdk_output) => {
-                            if let Some(gdk_output) = gdk_output.upgrade() {
-                                match load_output_bars_for(&instance, &app, &wl_output, &gdk_output)
-                                {
-                                    Ok(mut new_bars) => {
-                                        instance.bars.borrow_mut().append(&mut new_bars);
-                                    }
-                                    Err(err) => error!("{err:?}"),
-                                }
+
+                        continue;
+                    }
+
+                    if let MonitorState::Connected(wl_output, gdk_output) = event.state
+                        && let Some(gdk_output) = gdk_output.upgrade()
+                    {
+                        match load_output_bars_for(&instance, &app, &wl_output, &gdk_output) {
+                            Ok(mut new_bars) => {
+                                instance.bars.borrow_mut().append(&mut new_bars);
                             }
+                            Err(err) => error!("{err:?}"),
                         }
                     }
                 }
@@ -324,6 +417,17 @@ impl Ironbar {
             .clone()
     }
 
+    /// Gets the sender for events published to IPC clients.
+    #[cfg(feature = "ipc")]
+    #[must_use]
+    pub fn ipc_events() -> tokio::sync::broadcast::Sender<ipc::IpcEvent> {
+        static IPC_EVENTS: OnceLock<tokio::sync::broadcast::Sender<ipc::IpcEvent>> =
+            OnceLock::new();
+        IPC_EVENTS
+            .get_or_init(|| tokio::sync::broadcast::channel(64).0)
+            .clone()
+    }
+
     #[must_use]
     pub fn desktop_files(&self) -> DesktopFiles {
         self.desktop_files.clone()
@@ -334,16 +438,18 @@ impl Ironbar {
         self.image_provider.clone()
     }
 
-    /// Gets clones of bars by their name.
+    /// Gets clones of bars by their name and the monitor they are on.
+    /// Where either filter is `None`, it is ignored.
     ///
     /// Since the bars contain mostly GTK objects,
     /// the clone is cheap enough to not worry about.
     #[must_use]
-    pub fn bars_by_name(&self, name: &str) -> Vec<Bar> {
+    pub fn bars_matching(&self, name: Option<&str>, monitor: Option<&str>) -> Vec<Bar> {
         self.bars
             .borrow()
             .iter()
-            .filter(|&bar| bar.name() == name)
+            .filter(|&bar| name.is_none_or(|name| bar.name() == name))
+            .filter(|&bar| monitor.is_none_or(|monitor| bar.monitor_name() == monitor))
             .cloned()
             .collect()
     }
@@ -352,9 +458,79 @@ impl Ironbar {
     /// Note this does *not* reload bars, which must be performed separately.
     #[cfg(feature = "ipc")]
     fn reload_config(&self) {
-        self.config
-            .replace(Config::load(self.config_location.clone(), self.css_location.clone()).0);
+        self.config.replace(
+            Config::load(
+                self.config_location.clone(),
+                self.css_location.clone(),
+                self.profile.borrow().as_deref(),
+            )
+            .0,
+        );
+    }
+
+    /// Restarts the background services which read the top-level config,
+    /// so that they pick up changes after a reload.
+    #[cfg(any(feature = "ipc", feature = "config"))]
+    fn reload_services(&self) {
+        let config = self.config.borrow();
+
+        poll::configure(config.polling);
+
+        #[cfg(feature = "ipc")]
+        ipc::mirror::start(config.remotes.clone());
     }
+
+    /// Closes all bars and creates them again from the active config.
+    #[cfg(any(feature = "ipc", feature = "config"))]
+    fn reload_bars(self: &Rc<Self>, app: &Application) {
+        info!("Closing existing bars");
+        self.bars.borrow_mut().clear();
+
+        for window in app.windows() {
+            window.close();
+        }
+
+        if let Err(err) = load_output_bars(self, app) {
+            error!("{err:?}");
+        }
+    }
+}
+
+/// Prints the config JSON schema to `stdout`.
+#[cfg(all(feature = "cli", feature = "extras"))]
+fn print_schema() {
+    let schema = schemars::schema_for!(Config);
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&schema).expect("to be serializable")
+    );
+}
+
+/// Loads the config and stylesheet, then exits.
+///
+/// Exits with code `1` if any errors were encountered,
+/// or any warnings where `level` is `2`.
+#[cfg(all(feature = "cli", feature = "config"))]
+fn validate_config(
+    debug: bool,
+    config: Option<ConfigLocation>,
+    theme: Option<ConfigLocation>,
+    level: u8,
+) -> ! {
+    let _guard = logging::install_logging(debug);
+
+    let (_, _, error_level) = Config::load(config.unwrap_or_default(), theme, None);
+
+    let err = match level {
+        1 => error_level >= config::ErrorLevel::Error,
+        2 => error_level >= config::ErrorLevel::Warn,
+        _ => {
+            error!("invalid validate_config level");
+            exit(ExitCode::CliError as i32)
+        }
+    };
+
+    exit(err as i32);
 }
 
 fn start_ironbar(
@@ -393,63 +569,119 @@ fn load_output_bars_for(
 
     let config = ironbar.config.borrow();
 
-    let show_default_bar =
-        config.bar.start.is_some() || config.bar.center.is_some() || config.bar.end.is_some();
+    if config.excludes_monitor(monitor_name, monitor_desc) {
+        debug!("Monitor {monitor_name} is excluded, skipping bars");
+        return Ok(vec![]);
+    }
 
-    let bars = match config.monitors.as_ref().and_then(|config| {
-        config.get(monitor_name).or_else(|| {
-            config
-                .keys()
-                .find(|&k| monitor_desc.to_lowercase().starts_with(&k.to_lowercase()))
-                .and_then(|key| config.get(key))
-        })
-    }) {
-        Some(MonitorConfig::Single(config)) => {
-            vec![create_bar(
+    let bar = config.monitor_bar(monitor_name, monitor_desc);
+    let show_default_bar = bar.start.is_some() || bar.center.is_some() || bar.end.is_some();
+
+    let configs = match config.monitor_config(monitor_name, monitor_desc) {
+        Some(MonitorConfig::Single(config)) => vec![config.clone()],
+        Some(MonitorConfig::Multiple(configs)) => configs.clone(),
+        None => show_default_bar
+            .then_some(bar)
+            .into_iter()
+            .chain(&config.bars)
+            .cloned()
+            .collect(),
+    };
+
+    let bars = assign_bar_names(configs, monitor_name)
+        .into_iter()
+        .map(|config| {
+            create_bar(
                 app,
                 monitor,
                 monitor_name.to_string(),
-                config.clone(),
+                config,
                 ironbar.clone(),
-            )]
-        }
-        Some(MonitorConfig::Multiple(configs)) => configs
-            .iter()
-            .map(|config| {
-                create_bar(
-                    app,
-                    monitor,
-                    monitor_name.to_string(),
-                    config.clone(),
-                    ironbar.clone(),
-                )
-            })
-            .collect(),
-        None if show_default_bar => vec![create_bar(
-            app,
-            monitor,
-            monitor_name.to_string(),
-            config.bar.clone(),
-            ironbar.clone(),
-        )],
-        None => vec![],
-    };
+            )
+        })
+        .collect();
 
     Ok(bars)
 }
 
+/// Gives each bar on a monitor a unique name,
+/// so that it can be targeted over IPC.
+///
+/// Unnamed bars are named after their position, such as `bar-top`.
+/// Where a name is already taken, a numeric suffix is added.
+fn assign_bar_names(mut configs: Vec<BarConfig>, monitor_name: &str) -> Vec<BarConfig> {
+    let explicit = configs
+        .iter()
+        .filter_map(|config| config.name.clone())
+        .collect::<HashSet<_>>();
+
+    let mut names = HashSet::new();
+
+    for config in &mut configs {
+        let base = config
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("bar-{}", config.position.name()));
+
+        // generated names must not take a name set on a later bar
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while names.contains(&name)
+            || (config.name.as_ref() != Some(&name) && explicit.contains(&name))
+        {
+            name = format!("{base}-{suffix}");
+            suffix += 1;
+        }
+
+        if config
+            .name
+            .as_ref()
+            .is_some_and(|requested| requested != &name)
+        {
+            warn!("Multiple bars on '{monitor_name}' are named '{base}', renaming one to '{name}'");
+        }
+
+        names.insert(name.clone());
+        config.name = Some(name);
+    }
+
+    configs
+}
+
 pub fn load_output_bars(ironbar: &Rc<Ironbar>, app: &Application) -> Result<()> {
+    if let Some(settings) = gtk::Settings::default() {
+        if ironbar.config.borrow().disable_animations {
+            settings.set_gtk_enable_animations(false);
+        } else {
+            settings.reset_property("gtk-enable-animations");
+        }
+    }
+
+    gtk::Widget::set_default_direction(ironbar.config.borrow().direction.to_text_direction());
+
+    ironbar.theme.apply(ironbar.config.borrow().theme.clone());
+
     let wl = ironbar.clients.borrow_mut().wayland();
     let outputs = wl.output_info_all();
 
     let display = get_display();
     let monitors = display.monitors();
 
+    let primary_only = ironbar.config.borrow().primary_only;
+
     for output in outputs {
         let Some(monitor_name) = &output.name else {
             return Err(Report::msg("Output missing monitor name"));
         };
         let monitor_desc = &output.description.clone().unwrap_or_default();
+
+        if ironbar
+            .config
+            .borrow()
+            .excludes_monitor(monitor_name, monitor_desc)
+        {
+            continue;
+        }
         let find_monitor = || {
             for i in 0..monitors.n_items() {
                 let Some(monitor) = monitors.item(i).and_downcast::<Monitor>() else {
@@ -475,6 +707,11 @@ pub fn load_output_bars(ironbar: &Rc<Ironbar>, app: &Application) -> Result<()>
             Ok(mut bars) => ironbar.bars.borrow_mut().append(&mut bars),
             Err(err) => error!("{err:?}"),
         }
+
+        // the first usable output is the primary
+        if primary_only {
+            break;
+        }
     }
 
     Ok(())
@@ -488,21 +725,52 @@ fn create_runtime() -> Runtime {
 }
 
 /// Calls `spawn` on the Tokio runtime.
+///
+/// If the task is spawned while a module is being created,
+/// it belongs to that module's [`PanicGuard`].
+/// Panics are reported to the guard,
+/// and the task is aborted if the module is removed.
 pub fn spawn<F>(f: F) -> JoinHandle<F::Output>
 where
     F: Future + Send + 'static,
     F::Output: Send + 'static,
 {
-    Ironbar::runtime().spawn(f)
+    let guard = PanicGuard::current();
+    let task_guard = guard.clone();
+
+    let handle = Ironbar::runtime().spawn(async move {
+        let f = panic_guard::scope_future(task_guard.clone(), f);
+        match panic_guard::catch_unwind(f).await {
+            Ok(output) => output,
+            Err(payload) => {
+                panic_guard::report(task_guard.as_ref(), &*payload);
+                std::panic::resume_unwind(payload)
+            }
+        }
+    });
+
+    if let Some(guard) = guard {
+        guard.track(handle.abort_handle());
+    }
+
+    handle
 }
 
 /// Calls `spawn_blocking` on the Tokio runtime.
+///
+/// The task belongs to the current module's [`PanicGuard`], if any,
+/// so that it can report errors to the module.
 pub fn spawn_blocking<F, R>(f: F) -> JoinHandle<R>
 where
     F: FnOnce() -> R + Send + 'static,
     R: Send + 'static,
 {
-    Ironbar::runtime().spawn_blocking(f)
+    let guard = PanicGuard::current();
+
+    Ironbar::runtime().spawn_blocking(move || match guard {
+        Some(guard) => guard.scope(f),
+        None => f(),
+    })
 }
 
 /// Blocks on a `Future` until it resolves.
@@ -515,3 +783,36 @@ where
 pub fn await_sync<F: Future>(f: F) -> F::Output {
     block_in_place(|| Ironbar::runtime().block_on(f))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::BarPosition;
+
+    #[test]
+    fn assign_unique_bar_names() {
+        let bar = |name: Option<&str>, position| BarConfig {
+            name: name.map(ToString::to_string),
+            position,
+            ..BarConfig::default()
+        };
+
+        let configs = vec![
+            bar(None, BarPosition::Top),
+            bar(Some("main"), BarPosition::Top),
+            bar(None, BarPosition::Top),
+            bar(Some("main"), BarPosition::Bottom),
+            bar(Some("bar-top-2"), BarPosition::Left),
+        ];
+
+        let names = assign_bar_names(configs, "DP-1")
+            .into_iter()
+            .map(|config| config.name.unwrap_or_default())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            names,
+            ["bar-top", "main", "bar-top-3", "main-2", "bar-top-2"]
+        );
+    }
+}
@@ -0,0 +1,86 @@
+mod waybar;
+
+use crate::cli::MigrateArgs;
+use crate::error::ExitCode;
+use color_eyre::{Help, Report, Result};
+use std::fs;
+use std::path::Path;
+use std::process::exit;
+use tracing::error;
+
+/// Runs the `migrate` CLI command,
+/// converting another bar's config into an Ironbar config.
+///
+/// The converted config is written to `stdout`
+/// unless an output directory is provided.
+/// Anything which could not be translated is listed on `stderr`.
+pub fn run(args: MigrateArgs) {
+    if let Err(err) = try_run(args) {
+        error!("{err:?}");
+        exit(ExitCode::CliError as i32);
+    }
+}
+
+fn try_run(args: MigrateArgs) -> Result<()> {
+    let config = fs::read_to_string(&args.from_waybar)
+        .map_err(|err| Report::new(err).wrap_err("Failed to read Waybar config"))?;
+
+    let style_path = args.style.unwrap_or_else(|| {
+        args.from_waybar
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join("style.css")
+    });
+
+    let style = fs::read_to_string(&style_path).ok();
+
+    let migration = waybar::migrate(&config, style.as_deref())
+        .map_err(|err| Report::new(err).wrap_err("Failed to parse Waybar config"))?;
+
+    let config = serde_json::to_string_pretty(&migration.config)?;
+
+    match args.output {
+        Some(dir) => {
+            fs::create_dir_all(&dir)?;
+
+            let config_path = dir.join("config.json");
+            write_new(&config_path, &config)?;
+            eprintln!("Wrote config to '{}'", config_path.display());
+
+            if let Some(style) = &migration.style {
+                let style_path = dir.join("style.css");
+                write_new(&style_path, style)?;
+                eprintln!("Wrote stylesheet to '{}'", style_path.display());
+            }
+        }
+        None => println!("{config}"),
+    }
+
+    if style.is_none() {
+        eprintln!(
+            "No stylesheet found at '{}', skipping style conversion",
+            style_path.display()
+        );
+    }
+
+    if !migration.untranslated.is_empty() {
+        eprintln!("\nThe following could not be translated and will need to be manually migrated:");
+        for item in &migration.untranslated {
+            eprintln!("  - {item}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes `contents` to a new file at `path`,
+/// refusing to overwrite an existing file.
+pub fn write_new(path: &Path, contents: &str) -> Result<()> {
+    if path.exists() {
+        return Err(Report::msg(format!("'{}' already exists", path.display()))
+            .suggestion("Choose a different output directory, or remove the existing file"));
+    }
+
+    fs::write(path, contents)?;
+    Ok(())
+}
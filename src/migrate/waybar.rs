@@ -0,0 +1,797 @@
+use serde_json::{Map, Value, json};
+
+/// Bar-level Waybar options which are handled explicitly,
+/// and should not be treated as module configs.
+const BAR_KEYS: &[&str] = &[
+    "layer",
+    "output",
+    "position",
+    "height",
+    "width",
+    "margin",
+    "margin-top",
+    "margin-bottom",
+    "margin-left",
+    "margin-right",
+    "spacing",
+    "name",
+    "mode",
+    "exclusive",
+    "passthrough",
+    "ipc",
+    "id",
+    "include",
+    "reload_style_on_change",
+    "modules-left",
+    "modules-center",
+    "modules-right",
+];
+
+/// Mapping of Waybar CSS selectors to their Ironbar equivalent.
+///
+/// Longer selectors are listed first so that they take priority.
+const SELECTORS: &[(&str, &str)] = &[
+    ("window#waybar", ".background"),
+    ("#waybar", ".background"),
+    ("#workspaces button.focused", ".workspaces .item.focused"),
+    ("#workspaces button.active", ".workspaces .item.focused"),
+    ("#workspaces button.visible", ".workspaces .item.visible"),
+    ("#workspaces button.urgent", ".workspaces .item.urgent"),
+    ("#workspaces button", ".workspaces .item"),
+    (".modules-left", "#bar #start"),
+    (".modules-center", "#bar #center"),
+    (".modules-right", "#bar #end"),
+];
+
+/// Mapping of Waybar module IDs (as used in CSS) to Ironbar module classes.
+const MODULE_CLASSES: &[(&str, &str)] = &[
+    ("workspaces", "workspaces"),
+    ("window", "focused"),
+    ("mode", "bindmode"),
+    ("submap", "bindmode"),
+    ("clock", "clock"),
+    ("battery", "battery"),
+    ("cpu", "sysinfo"),
+    ("memory", "sysinfo"),
+    ("temperature", "sysinfo"),
+    ("disk", "sysinfo"),
+    ("tray", "tray"),
+    ("pulseaudio", "volume"),
+    ("wireplumber", "volume"),
+    ("network", "network_manager"),
+    ("bluetooth", "bluetooth"),
+    ("idle_inhibitor", "inhibit"),
+    ("mpd", "music"),
+    ("mpris", "music"),
+    ("language", "keyboard"),
+    ("keyboard-state", "keyboard"),
+];
+
+/// The result of a conversion.
+#[derive(Debug, Default)]
+pub struct Migration {
+    /// The converted Ironbar config.
+    pub config: Value,
+    /// The converted Ironbar stylesheet, if one was provided.
+    pub style: Option<String>,
+    /// Human-readable descriptions of anything which could not be translated.
+    pub untranslated: Vec<String>,
+}
+
+/// Converts a Waybar config (JSONC), and optionally its stylesheet,
+/// into an Ironbar config.
+pub fn migrate(config: &str, style: Option<&str>) -> Result<Migration, serde_json::Error> {
+    let config: Value = serde_json::from_str(&strip_jsonc(config))?;
+
+    let mut migration = Migration::default();
+
+    let bars = match config {
+        Value::Array(bars) => bars,
+        bar => vec![bar],
+    };
+
+    migration.config = match bars.as_slice() {
+        [bar] if bar.get("output").is_none() => convert_bar(bar, &mut migration.untranslated),
+        bars => convert_multiple(bars, &mut migration.untranslated),
+    };
+
+    migration.style = style.map(|style| convert_style(style, &mut migration.untranslated));
+
+    Ok(migration)
+}
+
+/// Converts multiple Waybar bars into a `monitors` map.
+fn convert_multiple(bars: &[Value], untranslated: &mut Vec<String>) -> Value {
+    let mut monitors: Map<String, Value> = Map::new();
+
+    for bar in bars {
+        let outputs = match bar.get("output") {
+            Some(Value::String(output)) => vec![output.clone()],
+            Some(Value::Array(outputs)) => outputs
+                .iter()
+                .filter_map(Value::as_str)
+                .map(ToString::to_string)
+                .collect(),
+            _ => {
+                untranslated.push(
+                    "bar without `output` in multi-bar config: added under `*`, which must be renamed to a monitor name"
+                        .to_string(),
+                );
+                vec!["*".to_string()]
+            }
+        };
+
+        let config = convert_bar(bar, untranslated);
+
+        for output in outputs {
+            if output.starts_with('!') || output.contains('*') {
+                untranslated.push(format!(
+                    "output pattern `{output}`: negation and wildcards are not supported"
+                ));
+            }
+
+            match monitors.get_mut(&output) {
+                Some(Value::Array(existing)) => existing.push(config.clone()),
+                Some(existing) => *existing = Value::Array(vec![existing.take(), config.clone()]),
+                None => {
+                    monitors.insert(output, config.clone());
+                }
+            }
+        }
+    }
+
+    json!({ "monitors": monitors })
+}
+
+fn convert_bar(bar: &Value, untranslated: &mut Vec<String>) -> Value {
+    let mut config = Map::new();
+
+    let get = |key: &str| bar.get(key);
+
+    if let Some(name) = get("name").and_then(Value::as_str) {
+        config.insert("name".to_string(), json!(name));
+    }
+
+    if let Some(position) = get("position").and_then(Value::as_str) {
+        config.insert("position".to_string(), json!(position));
+    } else {
+        // waybar defaults to top, ironbar to bottom
+        config.insert("position".to_string(), json!("top"));
+    }
+
+    let vertical = matches!(
+        get("position").and_then(Value::as_str),
+        Some("left" | "right")
+    );
+
+    let size = if vertical {
+        get("width")
+    } else {
+        get("height")
+    };
+    if let Some(size) = size.and_then(Value::as_i64) {
+        config.insert("height".to_string(), json!(size));
+    }
+
+    if let Some(layer) = get("layer").and_then(Value::as_str) {
+        config.insert("layer".to_string(), json!(layer));
+    }
+
+    if let Some(exclusive) = get("exclusive").and_then(Value::as_bool) {
+        config.insert("exclusive_zone".to_string(), json!(exclusive));
+    }
+
+    if let Some(margin) = convert_margin(bar) {
+        config.insert("margin".to_string(), margin);
+    }
+
+    for key in ["spacing", "mode", "passthrough", "ipc", "include", "width"] {
+        if get(key).is_some() && !(key == "width" && vertical) {
+            untranslated.push(format!("bar option `{key}`"));
+        }
+    }
+
+    for (waybar_key, ironbar_key) in [
+        ("modules-left", "start"),
+        ("modules-center", "center"),
+        ("modules-right", "end"),
+    ] {
+        let Some(modules) = get(waybar_key).and_then(Value::as_array) else {
+            continue;
+        };
+
+        let modules = modules
+            .iter()
+            .filter_map(Value::as_str)
+            .filter_map(|name| convert_module(name, bar.get(name), untranslated))
+            .collect::<Vec<_>>();
+
+        config.insert(ironbar_key.to_string(), Value::Array(modules));
+    }
+
+    for key in bar.as_object().into_iter().flat_map(Map::keys) {
+        let is_listed = ["modules-left", "modules-center", "modules-right"]
+            .iter()
+            .filter_map(|k| get(k).and_then(Value::as_array))
+            .flatten()
+            .any(|name| name.as_str() == Some(key));
+
+        if !BAR_KEYS.contains(&key.as_str()) && !is_listed {
+            untranslated.push(format!(
+                "module config `{key}`: module is not used by any bar"
+            ));
+        }
+    }
+
+    Value::Object(config)
+}
+
+fn convert_margin(bar: &Value) -> Option<Value> {
+    let mut margin = match bar.get("margin") {
+        Some(Value::Number(n)) => {
+            let n = n.as_i64().unwrap_or_default();
+            [n, n, n, n]
+        }
+        // CSS shorthand: top right bottom left
+        Some(Value::String(s)) => {
+            let parts = s
+                .split_whitespace()
+                .map(|p| p.trim_end_matches("px").parse().unwrap_or_default())
+                .collect::<Vec<i64>>();
+
+            match parts.as_slice() {
+                [a] => [*a, *a, *a, *a],
+                [v, h] => [*v, *h, *v, *h],
+                [t, h, b] => [*t, *h, *b, *h],
+                [t, r, b, l, ..] => [*t, *r, *b, *l],
+                [] => [0; 4],
+            }
+        }
+        _ => [0; 4],
+    };
+
+    for (i, side) in ["top", "right", "bottom", "left"].iter().enumerate() {
+        if let Some(n) = bar.get(format!("margin-{side}")).and_then(Value::as_i64) {
+            margin[i] = n;
+        }
+    }
+
+    (margin != [0; 4]).then(|| {
+        json!({
+            "top": margin[0],
+            "right": margin[1],
+            "bottom": margin[2],
+            "left": margin[3],
+        })
+    })
+}
+
+/// Converts a single Waybar module into an Ironbar module.
+///
+/// `name` is the module name as listed in `modules-*`,
+/// including any `#id` suffix.
+fn convert_module(
+    name: &str,
+    config: Option<&Value>,
+    untranslated: &mut Vec<String>,
+) -> Option<Value> {
+    let empty = Value::Object(Map::new());
+    let config = config.unwrap_or(&empty);
+
+    let (kind, id) = name
+        .split_once('#')
+        .map_or((name, None), |(k, id)| (k, Some(id)));
+    let get = |key: &str| config.get(key);
+    let get_str = |key: &str| config.get(key).and_then(Value::as_str);
+
+    let mut module = Map::new();
+    // ironbar only shows tooltips when explicitly configured,
+    // so `tooltip` can be safely ignored.
+    let mut handled = vec![
+        "on-click",
+        "on-click-right",
+        "on-click-middle",
+        "on-scroll-up",
+        "on-scroll-down",
+        "tooltip",
+    ];
+
+    let mut set = |key: &str, value: Value| {
+        module.insert(key.to_string(), value);
+    };
+
+    match kind {
+        "clock" => {
+            set("type", json!("clock"));
+            if let Some(format) = get_str("format") {
+                set("format", json!(convert_clock_format(format)));
+                handled.push("format");
+            }
+            if let Some(locale) = get_str("locale") {
+                set("locale", json!(locale));
+                handled.push("locale");
+            }
+        }
+        "battery" => {
+            set("type", json!("battery"));
+            if let Some(format) = get_str("format") {
+                set(
+                    "format",
+                    json!(replace_tokens(
+                        format,
+                        &[
+                            ("{capacity}", "{percentage}"),
+                            ("{time}", "{time_remaining}")
+                        ],
+                        name,
+                        untranslated
+                    )),
+                );
+                handled.push("format");
+            }
+        }
+        "cpu" | "memory" | "temperature" | "disk" => {
+            set("type", json!("sys_info"));
+
+            let default_format = match kind {
+                "cpu" => "{usage}%",
+                "memory" => "{}%",
+                "temperature" => "{temperatureC}°C",
+                _ => "{percentage_used}%",
+            };
+
+            let format = get_str("format").unwrap_or(default_format);
+            handled.push("format");
+
+            let tokens: &[(&str, &str)] = match kind {
+                "cpu" => &[
+                    ("{usage}", "{cpu_percent}"),
+                    ("{load}", "{load_average_1}"),
+                    ("{avg_frequency}", "{cpu_frequency}"),
+                ],
+                "memory" => &[
+                    ("{}", "{memory_percent}"),
+                    ("{percentage}", "{memory_percent}"),
+                    ("{used}", "{memory_used}"),
+                    ("{total}", "{memory_total}"),
+                    ("{avail}", "{memory_available}"),
+                    ("{swapPercentage}", "{swap_percent}"),
+                ],
+                "temperature" => &[
+                    ("{temperatureC}", "{temp_c}"),
+                    ("{temperatureF}", "{temp_f}"),
+                ],
+                _ => &[
+                    ("{percentage_used}", "{disk_percent}"),
+                    ("{used}", "{disk_used}"),
+                    ("{total}", "{disk_total}"),
+                    ("{free}", "{disk_free}"),
+                ],
+            };
+
+            set(
+                "format",
+                json!([replace_tokens(format, tokens, name, untranslated)]),
+            );
+
+            if let Some(interval) = get("interval").and_then(Value::as_u64) {
+                let key = match kind {
+                    "cpu" => "cpu",
+                    "memory" => "memory",
+                    "temperature" => "temps",
+                    _ => "disks",
+                };
+
+                set("intervals", json!({ key: interval * 1000 }));
+                handled.push("interval");
+            }
+        }
+        "tray" => {
+            set("type", json!("tray"));
+            if let Some(size) = get("icon-size").and_then(Value::as_u64) {
+                set("icon_size", json!(size));
+                handled.push("icon-size");
+            }
+        }
+        "pulseaudio" | "wireplumber" => {
+            set("type", json!("volume"));
+        }
+        "network" => {
+            set("type", json!("network_manager"));
+        }
+        "bluetooth" => {
+            set("type", json!("bluetooth"));
+        }
+        "idle_inhibitor" => {
+            set("type", json!("inhibit"));
+        }
+        "mpd" | "mpris" => {
+            set("type", json!("music"));
+            set("player_type", json!(kind));
+        }
+        "keyboard-state" | "sway/language" | "hyprland/language" => {
+            set("type", json!("keyboard"));
+            if kind == "keyboard-state" {
+                set("show_layout", json!(false));
+                for (waybar_key, ironbar_key) in [
+                    ("capslock", "show_caps"),
+                    ("numlock", "show_num"),
+                    ("scrolllock", "show_scroll"),
+                ] {
+                    let enabled = get(waybar_key).and_then(Value::as_bool).unwrap_or(false);
+                    set(ironbar_key, json!(enabled));
+                    handled.push(waybar_key);
+                }
+            } else {
+                set("show_caps", json!(false));
+                set("show_num", json!(false));
+                set("show_scroll", json!(false));
+            }
+        }
+        "sway/workspaces" | "hyprland/workspaces" | "niri/workspaces" | "wlr/workspaces" => {
+            set("type", json!("workspaces"));
+            if let Some(icons) = get("format-icons").and_then(Value::as_object) {
+                let name_map = icons
+                    .iter()
+                    .filter(|(k, _)| {
+                        !matches!(k.as_str(), "default" | "focused" | "active" | "urgent")
+                    })
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect::<Map<_, _>>();
+
+                set("name_map", Value::Object(name_map));
+                handled.push("format-icons");
+            }
+            if let Some(all_outputs) = get("all-outputs").and_then(Value::as_bool) {
+                set("all_monitors", json!(all_outputs));
+                handled.push("all-outputs");
+            }
+        }
+        "sway/window" | "hyprland/window" | "niri/window" => {
+            set("type", json!("focused"));
+            if let Some(max_length) = get("max-length").and_then(Value::as_u64) {
+                set(
+                    "truncate",
+                    json!({ "mode": "end", "max_length": max_length }),
+                );
+                handled.push("max-length");
+            }
+        }
+        "wlr/taskbar" => {
+            set("type", json!("launcher"));
+        }
+        "sway/mode" | "hyprland/submap" => {
+            set("type", json!("bindmode"));
+        }
+        custom if custom.starts_with("custom/") => {
+            let custom_name = custom.trim_start_matches("custom/");
+            set("name", json!(custom_name));
+
+            match get_str("exec") {
+                Some(exec) => {
+                    set("type", json!("script"));
+                    set("cmd", json!(exec));
+                    handled.push("exec");
+
+                    match get("interval") {
+                        Some(Value::Number(interval)) => {
+                            set("mode", json!("poll"));
+                            set("interval", json!(interval.as_u64().unwrap_or(1) * 1000));
+                        }
+                        Some(Value::String(once)) if once == "once" => {
+                            set("mode", json!("poll"));
+                            set("interval", json!(u32::MAX));
+                        }
+                        _ => set("mode", json!("watch")),
+                    }
+                    handled.push("interval");
+
+                    if get_str("return-type") == Some("json") {
+                        untranslated.push(format!(
+                            "`{name}.return-type`: JSON output is not supported, script output is displayed as-is"
+                        ));
+                        handled.push("return-type");
+                    }
+                }
+                None => {
+                    set("type", json!("label"));
+                    set("label", json!(get_str("format").unwrap_or_default()));
+                    handled.push("format");
+                }
+            }
+
+            if let Some(exec_if) = get_str("exec-if") {
+                set("show_if", json!(exec_if));
+                handled.push("exec-if");
+            }
+        }
+        _ => {
+            untranslated.push(format!("module `{name}`: no Ironbar equivalent"));
+            return None;
+        }
+    }
+
+    if let Some(id) = id {
+        set("name", json!(id));
+    }
+
+    for (waybar_key, ironbar_key) in [
+        ("on-click", "on_click_left"),
+        ("on-click-right", "on_click_right"),
+        ("on-click-middle", "on_click_middle"),
+        ("on-scroll-up", "on_scroll_up"),
+        ("on-scroll-down", "on_scroll_down"),
+    ] {
+        if let Some(cmd) = get_str(waybar_key) {
+            set(ironbar_key, json!(format!("!{cmd}")));
+        }
+    }
+
+    for key in config.as_object().into_iter().flat_map(Map::keys) {
+        if !handled.contains(&key.as_str()) {
+            untranslated.push(format!("`{name}.{key}`"));
+        }
+    }
+
+    Some(Value::Object(module))
+}
+
+/// Converts a Waybar `{:%H:%M}` format string
+/// into a plain chrono format string.
+fn convert_clock_format(format: &str) -> String {
+    let mut result = String::with_capacity(format.len());
+    let mut rest = format;
+
+    while let Some(start) = rest.find("{:") {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+
+        match after.find('}') {
+            Some(end) => {
+                result.push_str(&after[..end]);
+                rest = &after[end + 1..];
+            }
+            None => {
+                rest = after;
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+/// Replaces each Waybar format token with its Ironbar equivalent.
+/// Any remaining unknown tokens are reported as untranslated.
+fn replace_tokens(
+    format: &str,
+    tokens: &[(&str, &str)],
+    name: &str,
+    untranslated: &mut Vec<String>,
+) -> String {
+    let mut format = format.to_string();
+
+    for (from, to) in tokens {
+        format = format.replace(from, to);
+    }
+
+    for (start, _) in format.match_indices('{') {
+        let token = format[start..]
+            .split_inclusive('}')
+            .next()
+            .unwrap_or_default();
+
+        if !tokens.iter().any(|(_, to)| *to == token) {
+            untranslated.push(format!("`{name}.format`: token `{token}`"));
+        }
+    }
+
+    format
+}
+
+/// Converts a Waybar stylesheet.
+///
+/// Only selectors are rewritten.
+/// Declarations are copied as-is, although some properties
+/// may not be supported by GTK4.
+fn convert_style(style: &str, untranslated: &mut Vec<String>) -> String {
+    let mut result = String::with_capacity(style.len());
+    let mut selector = String::new();
+    let mut depth = 0;
+
+    for c in style.chars() {
+        match c {
+            '{' => {
+                if depth == 0 {
+                    result.push_str(&convert_selector(&selector, untranslated));
+                    selector.clear();
+                }
+                depth += 1;
+                result.push(c);
+            }
+            '}' => {
+                depth -= 1;
+                result.push(c);
+            }
+            ';' if depth == 0 => {
+                // at-rules such as `@import`
+                result.push_str(&selector);
+                result.push(c);
+                selector.clear();
+            }
+            c if depth == 0 => selector.push(c),
+            c => result.push(c),
+        }
+    }
+
+    result.push_str(&selector);
+    result
+}
+
+fn convert_selector(selector: &str, untranslated: &mut Vec<String>) -> String {
+    let mut selector = selector.to_string();
+
+    for (from, to) in SELECTORS {
+        selector = selector.replace(from, to);
+    }
+
+    let mut result = String::with_capacity(selector.len());
+    let mut chars = selector.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        if c != '#' {
+            result.push(c);
+            continue;
+        }
+
+        let ident: String = selector[i + 1..]
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+            .collect();
+
+        for _ in 0..ident.chars().count() {
+            chars.next();
+        }
+
+        if let Some((_, class)) = MODULE_CLASSES.iter().find(|(id, _)| *id == ident) {
+            result.push('.');
+            result.push_str(class);
+        } else if let Some(custom) = ident.strip_prefix("custom-") {
+            result.push('#');
+            result.push_str(custom);
+        } else {
+            if !matches!(ident.as_str(), "bar" | "start" | "center" | "end") {
+                untranslated.push(format!("CSS selector `#{ident}`"));
+            }
+            result.push('#');
+            result.push_str(&ident);
+        }
+    }
+
+    result
+}
+
+/// Strips comments and trailing commas from a JSONC string,
+/// producing standard JSON.
+fn strip_jsonc(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+    let mut chars = input.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            result.push(c);
+            match c {
+                '\\' => {
+                    if let Some(next) = chars.next() {
+                        result.push(next);
+                    }
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                result.push(c);
+            }
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        result.push(c);
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut prev = '\0';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            (',', _) => {
+                let rest = chars.clone().find(|c| !c.is_whitespace());
+                if !matches!(rest, Some(']' | '}')) {
+                    result.push(c);
+                }
+            }
+            _ => result.push(c),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jsonc() {
+        let input = r#"{
+            // comment
+            "a": "http://example.com", /* block */
+            "b": [1, 2,],
+        }"#;
+
+        let value: Value = serde_json::from_str(&strip_jsonc(input)).expect("valid json");
+        assert_eq!(value["a"], "http://example.com");
+        assert_eq!(value["b"], json!([1, 2]));
+    }
+
+    #[test]
+    fn clock_format() {
+        assert_eq!(convert_clock_format("{:%H:%M}"), "%H:%M");
+        assert_eq!(convert_clock_format(" {:%a %d} "), " %a %d ");
+    }
+
+    #[test]
+    fn bar() {
+        let migration = migrate(
+            r#"{
+                "position": "top",
+                "height": 30,
+                "modules-left": ["sway/workspaces"],
+                "modules-right": ["battery", "clock", "custom/weather", "cava"],
+                "battery": { "format": "{capacity}% {icon}" },
+                "custom/weather": { "exec": "weather.sh", "interval": 60 }
+            }"#,
+            None,
+        )
+        .expect("to migrate");
+
+        let config = migration.config;
+        assert_eq!(config["height"], 30);
+        assert_eq!(config["start"][0]["type"], "workspaces");
+        assert_eq!(config["end"][0]["format"], "{percentage}% {icon}");
+        assert_eq!(config["end"][1]["type"], "clock");
+        assert_eq!(config["end"][2]["type"], "script");
+        assert_eq!(config["end"][2]["interval"], 60_000);
+        assert_eq!(config["end"].as_array().map(Vec::len), Some(3));
+
+        assert!(migration.untranslated.iter().any(|u| u.contains("cava")));
+        assert!(migration.untranslated.iter().any(|u| u.contains("{icon}")));
+    }
+
+    #[test]
+    fn style() {
+        let mut untranslated = vec![];
+        let style = convert_style(
+            "window#waybar { background: #000; }\n#workspaces button.focused, #clock { color: #fff; }",
+            &mut untranslated,
+        );
+
+        assert_eq!(
+            style,
+            ".background { background: #000; }\n.workspaces .item.focused, .clock { color: #fff; }"
+        );
+        assert!(untranslated.is_empty());
+    }
+}
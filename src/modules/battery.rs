@@ -1,9 +1,9 @@
 use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
-use crate::clients::upower;
 use crate::clients::upower::BatteryState;
-use crate::config::{CommonConfig, LayoutConfig, Profiles, State, default};
-use crate::gtk_helpers::IronbarLabelExt;
-use crate::image::IconLabel;
+use crate::clients::{power_profiles, upower};
+use crate::config::{CommonConfig, LayoutConfig, Profiles, State, Thresholds, default};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::image::{self, IconLabel};
 use crate::modules::PopupButton;
 use crate::modules::{
     Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, WidgetContext,
@@ -15,7 +15,10 @@ use gtk::{Label, Orientation};
 use serde::Deserialize;
 use std::cmp::Ordering;
 use std::fmt::Write;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::mpsc;
+use tracing::error;
 
 const DAY: i64 = 24 * 60 * 60;
 const HOUR: i64 = 60 * 60;
@@ -94,6 +97,29 @@ pub struct BatteryModule {
     #[serde(flatten)]
     profiles: Profiles<ProfileState, BatteryProfile>,
 
+    /// The charge percentages at or below which
+    /// the `.low`, `.warning` and `.critical` classes are applied.
+    /// These only apply while the battery is not charging.
+    ///
+    /// **Default**: `{ low = 30 warning = 15 critical = 5 }`
+    thresholds: Thresholds,
+
+    /// Where to show the batteries of peripherals,
+    /// such as Bluetooth headsets, mice and controllers.
+    ///
+    /// **Valid options**: `none`, `popup`, `bar`
+    /// <br>
+    /// **Default**: `none`
+    peripherals: PeripheralsMode,
+
+    /// Whether to show buttons in the popup to switch power profile,
+    /// and add the `.profile-<name>` class for the active profile to the widget.
+    ///
+    /// Requires power-profiles-daemon.
+    ///
+    /// **Default**: `false`
+    power_profiles: bool,
+
     /// See [common options](module-level-options#common-options).
     #[serde(flatten)]
     pub common: Option<CommonConfig>,
@@ -107,11 +133,28 @@ impl Default for BatteryModule {
             show_icon: true,
             show_label: true,
             profiles: Profiles::default(),
+            thresholds: Thresholds::new(30.0, 15.0, 5.0),
+            peripherals: PeripheralsMode::default(),
+            power_profiles: false,
             common: Some(CommonConfig::default()),
         }
     }
 }
 
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum PeripheralsMode {
+    /// Peripherals are not shown.
+    #[default]
+    None,
+    /// Each peripheral is listed in the popup.
+    Popup,
+    /// Each peripheral is shown on the bar,
+    /// as well as being listed in the popup.
+    Bar,
+}
+
 impl Default for BatteryProfile {
     fn default() -> Self {
         Self {
@@ -120,6 +163,23 @@ impl Default for BatteryProfile {
     }
 }
 
+#[derive(Debug, Clone)]
+pub enum BatteryUpdate {
+    /// The state of the display device,
+    /// which combines the system's batteries.
+    Display(upower::State),
+    /// The state of each peripheral.
+    Peripherals(Vec<upower::Device>),
+    /// The available and active power profiles.
+    PowerProfiles(power_profiles::State),
+}
+
+#[derive(Debug, Clone)]
+pub enum UiEvent {
+    /// Switches to the named power profile.
+    SetPowerProfile(String),
+}
+
 struct BatteryUiUpdate {
     time_to_full: i64,
     time_to_empty: i64,
@@ -128,28 +188,87 @@ struct BatteryUiUpdate {
 }
 
 impl Module<Button> for BatteryModule {
-    type SendMessage = upower::State;
-    type ReceiveMessage = ();
+    type SendMessage = BatteryUpdate;
+    type ReceiveMessage = UiEvent;
 
     module_impl!("battery");
 
+    fn coalesce_key(update: &Self::SendMessage) -> Option<String> {
+        // each update is the full state of its kind
+        let key = match update {
+            BatteryUpdate::Display(_) => "display",
+            BatteryUpdate::Peripherals(_) => "peripherals",
+            BatteryUpdate::PowerProfiles(_) => "power_profiles",
+        };
+
+        Some(key.to_string())
+    }
+
     fn spawn_controller(
         &self,
         _info: &ModuleInfo,
         context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
-        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+        rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
         let tx = context.tx.clone();
 
         let client = context.try_client::<upower::Client>()?;
 
+        if self.power_profiles {
+            match context.try_client::<power_profiles::Client>() {
+                Ok(client) => spawn_power_profiles(client, tx.clone(), rx),
+                Err(err) => error!("Failed to connect to power-profiles-daemon: {err:?}"),
+            }
+        }
+
+        if self.peripherals != PeripheralsMode::None {
+            let tx = tx.clone();
+            let client = client.clone();
+
+            spawn(async move {
+                let peripherals = |devices: Vec<upower::Device>| {
+                    BatteryUpdate::Peripherals(
+                        devices
+                            .into_iter()
+                            .filter(upower::Device::is_peripheral)
+                            .collect(),
+                    )
+                };
+
+                // subscribe first so that no changes are missed
+                let mut rx = client.subscribe_devices();
+                tx.send_update(peripherals(client.devices().await?)).await;
+
+                loop {
+                    match rx.recv_tracked("battery peripherals").await {
+                        Ok(devices) => tx.send_update(peripherals(devices)).await,
+                        // missed updates are replaced by the latest state
+                        Err(RecvError::Lagged(_)) => {
+                            tx.send_update(peripherals(client.devices().await?)).await;
+                        }
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+
+                Result::<()>::Ok(())
+            });
+        }
+
         spawn(async move {
             let properties = client.state().await?;
-            tx.send_update(properties).await;
+            tx.send_update(BatteryUpdate::Display(properties)).await;
 
             let mut rx = client.subscribe();
-            while let Ok(properties) = rx.recv().await {
-                tx.send_update(properties).await;
+            loop {
+                match rx.recv_tracked("battery").await {
+                    Ok(properties) => tx.send_update(BatteryUpdate::Display(properties)).await,
+                    // missed updates are replaced by the latest state
+                    Err(RecvError::Lagged(_)) => {
+                        tx.send_update(BatteryUpdate::Display(client.state().await?))
+                            .await;
+                    }
+                    Err(RecvError::Closed) => break,
+                }
             }
 
             Result::<()>::Ok(())
@@ -165,7 +284,7 @@ impl Module<Button> for BatteryModule {
     ) -> Result<ModuleParts<Button>> {
         let icon = match self.show_icon {
             true => {
-                let icon = IconLabel::new("", self.icon_size, &context.ironbar.image_provider());
+                let icon = IconLabel::new("", self.icon_size, &context.image_provider());
                 icon.add_css_class("icon");
                 Some(icon)
             }
@@ -188,13 +307,22 @@ impl Module<Button> for BatteryModule {
 
         let button = Button::new();
         button.add_css_class("button");
+        button.set_accessible_label("Battery");
 
         if let Some(i) = &icon {
             container.append(&**i);
         }
         if let Some(l) = &label {
-            container.append(l);
+            container.append(&l.rotated(self.layout.angle()));
+        }
+
+        let peripherals = gtk::Box::new(self.layout.orientation(info), 5);
+        peripherals.add_css_class("peripherals");
+
+        if self.peripherals == PeripheralsMode::Bar {
+            container.append(&peripherals);
         }
+
         button.set_child(Some(&container));
 
         let tx = context.tx.clone();
@@ -202,12 +330,22 @@ impl Module<Button> for BatteryModule {
             tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
         });
 
-        let mut manager = self.profiles.attach(&button, move |_button, event| {
+        let thresholds = self.thresholds;
+        let mut manager = self.profiles.attach(&button, move |button, event| {
             let state = event.state;
             let properties: BatteryUiUpdate = event.data;
 
+            let charging = state.charging.expect("should be present on state");
+            thresholds.apply(button, (!charging).then_some(state.percent), false);
+
+            button.set_accessible_description(&format!(
+                "{}%, {}",
+                state.percent.round(),
+                properties.state_name
+            ));
+
             if let Some(l) = &label {
-                let time_remaining = if state.charging.expect("should be present on state") {
+                let time_remaining = if charging {
                     seconds_to_string(properties.time_to_full)
                 } else {
                     seconds_to_string(properties.time_to_empty)
@@ -228,8 +366,61 @@ impl Module<Button> for BatteryModule {
             }
         });
 
+        let image_provider = context.image_provider();
+        let (show_icon, show_label, icon_size) = (self.show_icon, self.show_label, self.icon_size);
+
+        let mut profile_class = None;
+
         let rx = context.subscribe();
-        rx.recv_glib((), move |(), properties| {
+        rx.recv_glib(&button, move |button, update| {
+            let properties = match update {
+                BatteryUpdate::Display(properties) => properties,
+                BatteryUpdate::PowerProfiles(state) => {
+                    if let Some(class) = profile_class.take() {
+                        button.remove_css_class(&class);
+                    }
+
+                    if !state.active.is_empty() {
+                        let class = format!("profile-{}", state.active);
+                        button.add_css_class(&class);
+                        profile_class = Some(class);
+                    }
+
+                    return;
+                }
+                BatteryUpdate::Peripherals(devices) => {
+                    while let Some(child) = peripherals.first_child() {
+                        peripherals.remove(&child);
+                    }
+
+                    for device in devices {
+                        let widget = gtk::Box::new(Orientation::Horizontal, 5);
+                        widget.add_css_class("peripheral");
+                        widget.add_css_class(&device.kind.css_class());
+                        widget.set_tooltip_text(Some(&peripheral_details(&device)));
+
+                        let state = device.state;
+                        let charging = state.state == BatteryState::Charging
+                            || state.state == BatteryState::PendingCharge;
+                        thresholds.apply(&widget, (!charging).then_some(state.percentage), false);
+
+                        if show_icon {
+                            widget.append(&*peripheral_icon(&state, icon_size, &image_provider));
+                        }
+
+                        if show_label {
+                            let label = Label::new(Some(&format!("{}%", state.percentage.round())));
+                            label.add_css_class("label");
+                            widget.append(&label);
+                        }
+
+                        peripherals.append(&widget);
+                    }
+
+                    return;
+                }
+            };
+
             let percent = properties.percentage;
 
             let state = properties.state;
@@ -267,14 +458,80 @@ impl Module<Button> for BatteryModule {
         Self: Sized,
     {
         let container = gtk::Box::builder()
-            .orientation(Orientation::Horizontal)
+            .orientation(Orientation::Vertical)
             .build();
 
         let label = Label::builder().use_markup(true).build();
         label.add_css_class("details");
         container.append(&label);
 
-        context.subscribe().recv_glib((), move |(), properties| {
+        let peripherals = gtk::Box::new(Orientation::Vertical, 5);
+        peripherals.add_css_class("peripherals");
+
+        if self.peripherals != PeripheralsMode::None {
+            container.append(&peripherals);
+        }
+
+        let profiles = gtk::Box::new(Orientation::Horizontal, 5);
+        profiles.add_css_class("power-profiles");
+
+        if self.power_profiles {
+            container.append(&profiles);
+        }
+
+        let image_provider = context.image_provider();
+        let icon_size = self.icon_size;
+
+        context.subscribe().recv_glib((), move |(), update| {
+            let properties = match update {
+                BatteryUpdate::Display(properties) => properties,
+                BatteryUpdate::PowerProfiles(state) => {
+                    while let Some(child) = profiles.first_child() {
+                        profiles.remove(&child);
+                    }
+
+                    for profile in state.profiles {
+                        let button = Button::with_label(&profile_name(&profile));
+                        button.add_css_class("power-profile");
+                        button.add_css_class(&profile);
+
+                        if profile == state.active {
+                            button.add_css_class("active");
+                        }
+
+                        let tx = context.controller_tx.clone();
+                        button.connect_clicked(move |_| {
+                            tx.send_spawn(UiEvent::SetPowerProfile(profile.clone()));
+                        });
+
+                        profiles.append(&button);
+                    }
+
+                    return;
+                }
+                BatteryUpdate::Peripherals(devices) => {
+                    while let Some(child) = peripherals.first_child() {
+                        peripherals.remove(&child);
+                    }
+
+                    for device in devices {
+                        let row = gtk::Box::new(Orientation::Horizontal, 5);
+                        row.add_css_class("peripheral");
+                        row.add_css_class(&device.kind.css_class());
+
+                        row.append(&*peripheral_icon(&device.state, icon_size, &image_provider));
+
+                        let details = Label::new(Some(&peripheral_details(&device)));
+                        details.add_css_class("details");
+                        row.append(&details);
+
+                        peripherals.append(&row);
+                    }
+
+                    return;
+                }
+            };
+
             let state = properties.state;
             let format = match state {
                 BatteryState::Charging | BatteryState::PendingCharge => {
@@ -303,6 +560,71 @@ impl Module<Button> for BatteryModule {
     }
 }
 
+fn peripheral_icon(
+    state: &upower::State,
+    size: i32,
+    image_provider: &image::Provider,
+) -> IconLabel {
+    let icon = IconLabel::new(&format!("icon:{}", state.icon_name), size, image_provider);
+    icon.add_css_class("icon");
+    icon
+}
+
+/// Gets the peripheral's name, charge and state,
+/// such as `Headset: 60%, Discharging`.
+fn peripheral_details(device: &upower::Device) -> String {
+    format!(
+        "{}: {}%, {}",
+        device.name(),
+        device.state.percentage.round(),
+        device.state.state
+    )
+}
+
+/// Sends the power profiles state to the widget,
+/// and switches profile when one is chosen from the popup.
+fn spawn_power_profiles(
+    client: Arc<power_profiles::Client>,
+    tx: mpsc::Sender<ModuleUpdateEvent<BatteryUpdate>>,
+    mut rx: mpsc::Receiver<UiEvent>,
+) {
+    spawn(async move {
+        let mut profiles_rx = client.subscribe();
+        tx.send_update(BatteryUpdate::PowerProfiles(client.state()))
+            .await;
+
+        loop {
+            tokio::select! {
+                res = profiles_rx.recv_tracked("power profiles") => match res {
+                    Ok(state) => tx.send_update(BatteryUpdate::PowerProfiles(state)).await,
+                    // missed updates are replaced by the latest state
+                    Err(RecvError::Lagged(_)) => {
+                        tx.send_update(BatteryUpdate::PowerProfiles(client.state())).await;
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                Some(UiEvent::SetPowerProfile(profile)) = rx.recv() => {
+                    if let Err(err) = client.set_profile(&profile).await {
+                        error!("Failed to set power profile: {err:?}");
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Gets the display name of a power profile,
+/// such as `Power saver` for `power-saver`.
+fn profile_name(profile: &str) -> String {
+    let name = profile.replace('-', " ");
+
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => name,
+    }
+}
+
 fn seconds_to_string(seconds: i64) -> Result<String> {
     let mut time_string = String::new();
     let days = seconds / (DAY);
@@ -115,6 +115,11 @@ pub struct PopupConfig {
     /// **Default**: `"{adapter_status}"`
     pub disabled: String,
 
+    /// Label of the button which scans for new devices to pair with.
+    ///
+    /// **Default**: `"Scan"`
+    pub scan: String,
+
     /// Device box related configuration
     pub device: PopupDeviceConfig,
 }
@@ -125,6 +130,7 @@ impl Default for PopupConfig {
             max_height: Some(SizeLimit::Pixels(330)),
             header: " Enable Bluetooth".to_string(),
             disabled: "{adapter_status}".to_string(),
+            scan: "Scan".to_string(),
             device: PopupDeviceConfig::default(),
         }
     }
@@ -176,6 +182,11 @@ impl Default for AdapterStatus {
 #[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct DeviceStatus {
+    /// The value of `{device_status}` formatting token when device is pairing.
+    ///
+    /// **Default**: `"Pairing..."`
+    pub pairing: String,
+
     /// The value of `{device_status}` formatting token when device is connecting.
     ///
     /// **Default**: `"Connecting..."`
@@ -195,15 +206,22 @@ pub struct DeviceStatus {
     ///
     /// **Default**: `"Disconnect"`
     pub disconnected: String,
+
+    /// The value of `{device_status}` formatting token when device is not paired.
+    ///
+    /// **Default**: `"Not paired"`
+    pub unpaired: String,
 }
 
 impl Default for DeviceStatus {
     fn default() -> Self {
         Self {
+            pairing: "Pairing...".to_string(),
             connecting: "Connecting...".to_string(),
             connected: "Connected".to_string(),
             disconnecting: "Disconnecting...".to_string(),
             disconnected: "Disconnected".to_string(),
+            unpaired: "Not paired".to_string(),
         }
     }
 }
@@ -267,6 +285,8 @@ impl BluetoothModule {
         str: &str,
     ) -> String {
         let status = match device.status {
+            BluetoothDeviceStatus::Disconnected if !device.paired => &device_status.unpaired,
+            BluetoothDeviceStatus::Pairing => &device_status.pairing,
             BluetoothDeviceStatus::Connecting => &device_status.connecting,
             BluetoothDeviceStatus::Connected => &device_status.connected,
             BluetoothDeviceStatus::Disconnecting => &device_status.disconnecting,
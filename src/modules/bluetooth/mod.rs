@@ -1,17 +1,19 @@
 use std::collections::HashMap;
 use std::ops::Deref;
+use std::time::Duration;
 
 use color_eyre::Result;
 use glib::SignalHandlerId;
 use gtk::{Align, Button, Label, Orientation};
-use gtk::{ScrolledWindow, Spinner, prelude::*};
+use gtk::{ScrolledWindow, prelude::*};
 use tokio::sync::mpsc;
 
 pub use self::config::BluetoothModule;
 use self::config::PopupDeviceConfig;
 use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
 use crate::clients::bluetooth::{self, BluetoothDevice, BluetoothDeviceStatus, BluetoothState};
-use crate::gtk_helpers::IronbarGtkExt;
+use crate::config::LoadingIndicatorType;
+use crate::gtk_helpers::{IronbarGtkExt, LoadingIndicator};
 use crate::image::IconLabel;
 use crate::modules::bluetooth::config::SizeLimit;
 use crate::modules::{
@@ -21,9 +23,14 @@ use crate::{image, module_impl, spawn};
 
 mod config;
 
+/// How long to scan for new devices for.
+const SCAN_DURATION: Duration = Duration::from_secs(30);
+
 pub enum BluetoothAction {
     Enable,
     Disable,
+    Scan,
+    Pair(bluer::Address),
     Connect(bluer::Address),
     Disconnect(bluer::Address),
 }
@@ -34,7 +41,7 @@ struct BluetoothDeviceBox {
     header: Label,
 
     footer: Label,
-    spinner: Spinner,
+    spinner: LoadingIndicator,
     switch: gtk::Switch,
     switch_handler: Option<SignalHandlerId>,
     tx: mpsc::Sender<BluetoothAction>,
@@ -65,16 +72,14 @@ impl BluetoothDeviceBox {
         switch.set_valign(Align::Center);
         switch.add_css_class("switch");
 
-        let spinner = Spinner::new();
-        spinner.add_css_class("spinner");
-        spinner.start();
+        let spinner = LoadingIndicator::new(LoadingIndicatorType::Spinner);
 
         icon_box.set_halign(Align::Start);
         header.set_halign(Align::Start);
         footer.set_halign(Align::Start);
         status.set_halign(Align::Start);
 
-        spinner.set_halign(Align::End);
+        spinner.widget().set_halign(Align::End);
         switch.set_halign(Align::End);
 
         header.set_hexpand(true);
@@ -83,7 +88,7 @@ impl BluetoothDeviceBox {
         status.append(&header);
         status.append(&footer);
         container.append(&status);
-        container.append(&spinner);
+        container.append(spinner.widget());
         container.append(&switch);
 
         Self {
@@ -108,19 +113,29 @@ impl BluetoothDeviceBox {
             self.switch.disconnect(handler);
         }
 
-        self.spinner.set_spinning(
-            data.status == BluetoothDeviceStatus::Connecting
-                || data.status == BluetoothDeviceStatus::Disconnecting,
-        );
+        self.spinner.set_active(matches!(
+            data.status,
+            BluetoothDeviceStatus::Pairing
+                | BluetoothDeviceStatus::Connecting
+                | BluetoothDeviceStatus::Disconnecting
+        ));
 
         self.switch.set_sensitive(
             data.status == BluetoothDeviceStatus::Connected
                 || data.status == BluetoothDeviceStatus::Disconnected,
         );
-        self.switch.set_state(
-            data.status == BluetoothDeviceStatus::Connected
-                || data.status == BluetoothDeviceStatus::Connecting,
-        );
+        self.switch.set_state(matches!(
+            data.status,
+            BluetoothDeviceStatus::Pairing
+                | BluetoothDeviceStatus::Connecting
+                | BluetoothDeviceStatus::Connected
+        ));
+
+        if data.paired {
+            self.container.remove_css_class("unpaired");
+        } else {
+            self.container.add_css_class("unpaired");
+        }
 
         if data.battery_percent.is_some() {
             self.header.set_text(&device_strings.header_battery);
@@ -131,9 +146,15 @@ impl BluetoothDeviceBox {
         }
 
         if data.status == BluetoothDeviceStatus::Disconnected {
+            let action = if data.paired {
+                BluetoothAction::Connect
+            } else {
+                BluetoothAction::Pair
+            };
+
             let tx = self.tx.clone();
             self.switch_handler = Some(self.switch.connect_active_notify(move |switch| {
-                tx.send_spawn(BluetoothAction::Connect(data.address));
+                tx.send_spawn(action(data.address));
                 switch.set_sensitive(false);
             }));
         }
@@ -162,6 +183,11 @@ impl Module<Button> for BluetoothModule {
 
     module_impl!("bluetooth");
 
+    fn coalesce_key(_update: &Self::SendMessage) -> Option<String> {
+        // each update is the full state
+        Some(String::new())
+    }
+
     fn spawn_controller(
         &self,
         _info: &ModuleInfo,
@@ -191,6 +217,8 @@ impl Module<Button> for BluetoothModule {
                 match action {
                     BluetoothAction::Enable => client.enable(),
                     BluetoothAction::Disable => client.disable(),
+                    BluetoothAction::Scan => client.scan(SCAN_DURATION),
+                    BluetoothAction::Pair(addr) => client.pair(addr),
                     BluetoothAction::Connect(addr) => client.connect(addr),
                     BluetoothAction::Disconnect(addr) => client.disconnect(addr),
                 }
@@ -233,8 +261,11 @@ impl Module<Button> for BluetoothModule {
                         Self::format_adapter(&state, &adapter_status, &format_strings.disabled),
                         "disabled",
                     ),
-                    BluetoothState::Enabled { devices } => match devices.iter().next() {
-                        Some(device) if device.status == BluetoothDeviceStatus::Connected => {
+                    BluetoothState::Enabled { devices, .. } => match devices
+                        .iter()
+                        .find(|device| device.status == BluetoothDeviceStatus::Connected)
+                    {
+                        Some(device) => {
                             let res = if device.battery_percent.is_some() {
                                 let res = Self::format_adapter(
                                     &state,
@@ -252,7 +283,7 @@ impl Module<Button> for BluetoothModule {
                             };
                             (res, "connected")
                         }
-                        _ => (
+                        None => (
                             Self::format_adapter(&state, &adapter_status, &format_strings.enabled),
                             "enabled",
                         ),
@@ -294,8 +325,22 @@ impl Module<Button> for BluetoothModule {
         let header_label = Label::new(None);
         header_label.add_css_class("label");
 
+        let header_scan = Button::with_label(&self.popup.scan);
+        header_scan.add_css_class("btn-scan");
+        header_scan.set_halign(Align::End);
+        header_scan.set_hexpand(true);
+
+        {
+            let tx = tx.clone();
+            header_scan.connect_clicked(move |button| {
+                tx.send_spawn(BluetoothAction::Scan);
+                button.set_sensitive(false);
+            });
+        }
+
         header.append(&header_switch);
         header.append(&header_label);
+        header.append(&header_scan);
 
         container.append(&header);
 
@@ -324,10 +369,8 @@ impl Module<Button> for BluetoothModule {
         disabled.set_valign(Align::Center);
         disabled.set_vexpand(true);
 
-        let disabled_spinner = Spinner::new();
-        disabled_spinner.add_css_class("spinner");
-        disabled_spinner.start();
-        disabled.append(&disabled_spinner);
+        let disabled_spinner = LoadingIndicator::new(LoadingIndicatorType::Spinner);
+        disabled.append(disabled_spinner.widget());
 
         let disabled_label = Label::new(None);
         disabled_label.add_css_class("label");
@@ -350,6 +393,7 @@ impl Module<Button> for BluetoothModule {
             let mut seq = 0u32;
             let mut num_pinned = 0;
 
+            let image_provider = context.image_provider();
             let rx = context.subscribe();
             rx.recv_glib((), move |(), state: BluetoothState| {
                 if let Some(handle) = enable_handle.take() {
@@ -359,7 +403,7 @@ impl Module<Button> for BluetoothModule {
                 devices_window.set_visible(state.is_enabled());
 
                 disabled.set_visible(!state.is_enabled());
-                disabled_spinner.set_visible(
+                disabled_spinner.set_active(
                     state == BluetoothState::Enabling || state == BluetoothState::Disabling,
                 );
 
@@ -389,7 +433,20 @@ impl Module<Button> for BluetoothModule {
                         .inspect(|h| header_switch.unblock_signal(h));
                 }
 
-                if let BluetoothState::Enabled { devices } = state {
+                header_scan.set_visible(state.is_enabled());
+
+                if let BluetoothState::Enabled {
+                    devices,
+                    discovering,
+                } = state
+                {
+                    header_scan.set_sensitive(!discovering);
+                    if discovering {
+                        header_scan.add_css_class("scanning");
+                    } else {
+                        header_scan.remove_css_class("scanning");
+                    }
+
                     {
                         let tx = tx.clone();
                         enable_handle = Some(header_switch.connect_active_notify(move |switch| {
@@ -401,7 +458,6 @@ impl Module<Button> for BluetoothModule {
                     // `seq` is used here to find device boxes to remove
                     seq = seq.wrapping_add(1);
 
-                    let image_provider = context.ironbar.image_provider();
                     for device in devices {
                         let (device_box, local_seq) =
                             device_map.entry(device.address).or_insert_with(|| {
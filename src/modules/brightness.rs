@@ -0,0 +1,293 @@
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::clients::backlight::Device;
+use crate::config::{CommonConfig, LayoutConfig};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::{module_impl, spawn};
+use color_eyre::Result;
+use glib::Propagation;
+use gtk::prelude::*;
+use gtk::{
+    Align, Button, EventControllerScroll, EventControllerScrollFlags, Label, Orientation, Scale,
+};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tracing::error;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct BrightnessModule {
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{icon} {percentage}%`
+    #[serde(skip)]
+    format: String,
+
+    /// The icon to use for the `{icon}` token.
+    ///
+    /// **Default**: `󰃠`
+    icon: String,
+
+    /// The percentage to change the brightness by
+    /// for each step when scrolling over the widget.
+    ///
+    /// **Default**: `5`
+    step: f64,
+
+    /// Whether to list external monitors which support DDC/CI in the popup.
+    /// Requires `ddcutil` to be installed.
+    ///
+    /// **Default**: `true`
+    ddc: bool,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for BrightnessModule {
+    fn default() -> Self {
+        Self {
+            format: "{icon} {percentage}%".to_string(),
+            icon: "󰃠".to_string(),
+            step: 5.0,
+            ddc: true,
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum UiEvent {
+    /// Sets the brightness percentage of the named device.
+    SetBrightness(String, f64),
+}
+
+impl Module<Button> for BrightnessModule {
+    type SendMessage = Vec<Device>;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("brightness");
+
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
+    fn coalesce_key(_update: &Self::SendMessage) -> Option<String> {
+        // each update is the full state
+        Some(String::new())
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let client = context.ironbar.clients.borrow_mut().backlight();
+
+        if self.ddc {
+            client.watch_ddc();
+        }
+
+        {
+            let client = client.clone();
+            let tx = context.tx.clone();
+
+            spawn(async move {
+                let mut updates = client.subscribe();
+                tx.send_update(client.devices()).await;
+
+                loop {
+                    match updates.recv_tracked("brightness").await {
+                        Ok(devices) => tx.send_update(devices).await,
+                        Err(RecvError::Lagged(_)) => tx.send_update(client.devices()).await,
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+
+        spawn(async move {
+            while let Some(event) = rx.recv().await {
+                // setting brightness over DDC is slow,
+                // so only the latest value queued for each device is used
+                let mut latest = HashMap::new();
+
+                let mut event = Some(event);
+                while let Some(UiEvent::SetBrightness(name, percent)) = event {
+                    latest.insert(name, percent);
+                    event = rx.try_recv().ok();
+                }
+
+                for (name, percent) in latest {
+                    if let Err(err) = client.set_percent(&name, percent).await {
+                        error!("Failed to set brightness of '{name}': {err:?}");
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let label = Label::builder()
+            .use_markup(true)
+            .justify(self.layout.justify.into())
+            .build();
+
+        let button = Button::new();
+        button.set_child(Some(&label.rotated(self.layout.angle())));
+        button.set_accessible_label("Brightness");
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        // the device adjusted by scrolling, and its brightness
+        let primary = Rc::new(RefCell::new(None::<(String, f64)>));
+
+        {
+            let primary = primary.clone();
+            let tx = context.controller_tx.clone();
+            let step = self.step;
+
+            let controller = EventControllerScroll::new(EventControllerScrollFlags::VERTICAL);
+            controller.connect_scroll(move |_, _dx, dy| {
+                if let Some((name, percent)) = &mut *primary.borrow_mut() {
+                    let delta = if dy > 0.0 { -step } else { step };
+                    *percent = (*percent + delta).clamp(0.0, 100.0);
+
+                    tx.send_spawn(UiEvent::SetBrightness(name.clone(), *percent));
+                }
+
+                Propagation::Stop
+            });
+
+            button.add_controller(controller);
+        }
+
+        {
+            let button = button.clone();
+            let format = self.format.clone();
+            let icon = self.icon.clone();
+
+            context.subscribe().recv_glib((), move |(), devices| {
+                let Some(device) = devices.first() else {
+                    button.set_visible(false);
+                    primary.replace(None);
+                    return;
+                };
+
+                button.set_visible(true);
+
+                let percent = device.percent();
+                primary.replace(Some((device.name.clone(), percent)));
+
+                let text = format
+                    .replace("{icon}", &icon)
+                    .replace("{percentage}", &percent.to_string())
+                    .replace("{name}", &device.description);
+
+                label.set_label_escaped(&text);
+                button.set_accessible_description(&format!("{percent}%"));
+            });
+        }
+
+        let popup = self
+            .into_popup(context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::new(Orientation::Vertical, 10);
+
+        let tx = context.controller_tx.clone();
+        let mut rows = HashMap::<String, (gtk::Box, Scale)>::new();
+
+        context
+            .subscribe()
+            .recv_glib(&container, move |container, devices| {
+                rows.retain(|name, (row, _)| {
+                    let keep = devices.iter().any(|device| &device.name == name);
+                    if !keep {
+                        container.remove(row);
+                    }
+                    keep
+                });
+
+                for device in devices {
+                    let (_, slider) = rows.entry(device.name.clone()).or_insert_with(|| {
+                        let (row, slider) = device_row(&device, &tx);
+                        container.append(&row);
+                        (row, slider)
+                    });
+
+                    if !slider.has_css_class("dragging") {
+                        slider.set_value(device.percent());
+                    }
+                }
+            });
+
+        Some(container)
+    }
+}
+
+/// Creates a popup row with the device name and a slider to set its brightness.
+fn device_row(device: &Device, tx: &mpsc::Sender<UiEvent>) -> (gtk::Box, Scale) {
+    let row = gtk::Box::new(Orientation::Vertical, 5);
+    row.add_css_class("device");
+
+    let title = Label::new(Some(&device.description));
+    title.add_css_class("title");
+    title.set_halign(Align::Start);
+    row.append(&title);
+
+    let slider = Scale::with_range(Orientation::Horizontal, 0.0, 100.0, 1.0);
+    slider.add_css_class("slider");
+    slider.set_accessible_label(&format!("{} brightness", device.description));
+    row.append(&slider);
+
+    let tx = tx.clone();
+    let name = device.name.clone();
+    slider.connect_value_changed(move |scale| {
+        if scale.has_css_class("dragging") {
+            // GTK will send values outside min/max range
+            let percent = scale.value().clamp(0.0, 100.0);
+            tx.send_spawn(UiEvent::SetBrightness(name.clone(), percent));
+        }
+    });
+
+    (row, slider)
+}
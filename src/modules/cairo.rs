@@ -3,15 +3,22 @@ use crate::clients::lua::LuaEngine;
 use crate::config::{CommonConfig, ConfigLocation};
 use crate::modules::{Module, ModuleInfo, ModuleParts, WidgetContext};
 use crate::{module_impl, rc_mut, spawn};
+use glib::Propagation;
 use glib::translate::ToGlibPtr;
-use gtk::DrawingArea;
 use gtk::cairo::{Format, ImageSurface};
+use gtk::gdk::RGBA;
 use gtk::prelude::*;
-use mlua::{Error, Function, LightUserData, MetaMethod, Value};
+use gtk::{
+    DrawingArea, EventControllerMotion, EventControllerScroll, EventControllerScrollFlags,
+    GestureClick,
+};
+use mlua::{Error, Function, LightUserData, Lua, MetaMethod, Table, Value};
 use notify::event::ModifyKind;
 use notify::{Event, EventKind, RecursiveMode, Watcher, recommended_watcher};
 use serde::Deserialize;
+use std::cell::Cell;
 use std::path::PathBuf;
+use std::rc::Rc;
 use std::time::Duration;
 use tokio::sync::mpsc::Receiver;
 use tokio::time::sleep;
@@ -31,9 +38,17 @@ pub struct CairoModule {
 
     /// The number of milliseconds between each draw call.
     ///
+    /// Set to `0` to only draw on demand,
+    /// when an event handler returns `true` or a `redraw_on` variable changes.
+    ///
     /// **Default**: `200`
     frequency: u64,
 
+    /// The names of [ironvars](ironvars) to redraw on each time they change.
+    ///
+    /// **Default**: `[]`
+    redraw_on: Vec<String>,
+
     /// The canvas width in pixels.
     ///
     /// **Default**: `42`
@@ -53,6 +68,7 @@ impl Default for CairoModule {
         Self {
             path: PathBuf::default(),
             frequency: 200,
+            redraw_on: vec![],
             width: 42,
             height: 42,
             common: Some(CommonConfig::default()),
@@ -103,12 +119,110 @@ impl CairoModule {
     }
 }
 
+/// Creates a Lua table containing the components of a color.
+fn color_table(lua: &Lua, color: RGBA) -> mlua::Result<Table> {
+    let table = lua.create_table()?;
+    table.set("r", color.red())?;
+    table.set("g", color.green())?;
+    table.set("b", color.blue())?;
+    table.set("a", color.alpha())?;
+    Ok(table)
+}
+
+/// Calls the draw function with the drawing area's style,
+/// so that scripts can take their colors from CSS.
+fn draw(
+    lua: &Lua,
+    area: &DrawingArea,
+    draw_wrapper: &Function,
+    draw_function: &Value,
+    ptr: *mut gtk::cairo::ffi::cairo_t,
+    size: (i32, i32),
+) -> mlua::Result<()> {
+    lua.scope(|scope| {
+        let style = lua.create_table()?;
+        style.set("color", color_table(lua, area.color())?)?;
+
+        let lookup_color = scope.create_function(|lua, name: String| {
+            // Deprecation warning is an error in gtk-rs bindings
+            // <https://github.com/gtk-rs/gtk4-rs/pull/2161>
+            #[allow(deprecated)]
+            let color = area.style_context().lookup_color(&name);
+
+            color.map(|color| color_table(lua, color)).transpose()
+        })?;
+        style.set("lookup_color", lookup_color)?;
+
+        // mlua needs a valid return type, even if we don't return anything
+        draw_wrapper.call::<Option<bool>>((
+            draw_function,
+            LightUserData(ptr.cast()),
+            size.0,
+            size.1,
+            style,
+        ))?;
+
+        Ok(())
+    })
+}
+
+/// The script's event handler names.
+const ON_CLICK: &str = "on_click";
+const ON_SCROLL: &str = "on_scroll";
+const ON_HOVER: &str = "on_hover";
+
+/// Calls the named event handler on the script,
+/// if it is a table which has one.
+///
+/// Returns whether the handler asked for the area to be redrawn.
+fn call_handler(
+    lua: &Lua,
+    draw_function: Option<&Value>,
+    name: &str,
+    event: impl FnOnce(&Table) -> mlua::Result<()>,
+) -> bool {
+    let Some(Value::Table(script)) = draw_function else {
+        return false;
+    };
+
+    let res = (|| {
+        let Some(handler) = script.get::<Option<Function>>(name)? else {
+            return Ok(false);
+        };
+
+        let table = lua.create_table()?;
+        event(&table)?;
+
+        // handlers are called as methods on the script table
+        handler
+            .call::<Option<bool>>((script, table))
+            .map(Option::unwrap_or_default)
+    })();
+
+    res.unwrap_or_else(|err| {
+        error!("lua error in {name}: {err}");
+        false
+    })
+}
+
+#[derive(Debug, Clone)]
+pub enum CairoUpdate {
+    /// The script file changed.
+    Reload,
+    /// A `redraw_on` variable changed.
+    Redraw,
+}
+
 impl Module<gtk::Box> for CairoModule {
-    type SendMessage = ();
+    type SendMessage = CairoUpdate;
     type ReceiveMessage = ();
 
     module_impl!("cairo");
 
+    fn set_interval(&mut self, interval: u64) {
+        self.frequency = interval;
+    }
+
     fn spawn_controller(
         &self,
         _info: &ModuleInfo,
@@ -120,6 +234,17 @@ impl Module<gtk::Box> for CairoModule {
     {
         let path = self.path.clone();
 
+        for name in &self.redraw_on {
+            let tx = context.tx.clone();
+            let mut rx = crate::Ironbar::variable_manager().subscribe(name.as_str().into());
+
+            spawn(async move {
+                while rx.recv().await.is_ok() {
+                    tx.send_update(CairoUpdate::Redraw).await;
+                }
+            });
+        }
+
         let tx = context.tx.clone();
         spawn(async move {
             let parent = path.parent().expect("to have parent path");
@@ -131,7 +256,7 @@ impl Module<gtk::Box> for CairoModule {
                         debug!("{event:?}");
 
                         if event.paths.first().is_some_and(|p| p == &path) {
-                            tx.send_update_spawn(());
+                            tx.send_update_spawn(CairoUpdate::Reload);
                         }
                     }
                     Err(e) => error!("Error occurred when watching stylesheet: {:?}", e),
@@ -190,7 +315,8 @@ impl Module<gtk::Box> for CairoModule {
                 .eval()
                 .expect("to be valid");
 
-            area.set_draw_func(move |_, cr, w, h| {
+            let lua = lua.clone();
+            area.set_draw_func(move |area, cr, w, h| {
                 if let Err(err) = cr.set_source_surface(&surface, 0.0, 0.0) {
                     error!("{err}");
                     return;
@@ -198,16 +324,17 @@ impl Module<gtk::Box> for CairoModule {
 
                 let ptr = cr.to_glib_full();
 
-                if let Some(ref current_draw_function) = *draw_function.borrow() {
-                    // mlua needs a valid return type, even if we don't return anything
-                    if let Err(err) = draw_wrapper.call::<Option<bool>>((
+                if let Some(ref current_draw_function) = *draw_function.borrow()
+                    && let Err(err) = draw(
+                        &lua,
+                        area,
+                        &draw_wrapper,
                         current_draw_function,
-                        LightUserData(ptr.cast()),
-                        w,
-                        h,
-                    )) {
-                        error!("lua error: {err}");
-                    }
+                        ptr,
+                        (w, h),
+                    )
+                {
+                    error!("lua error: {err}");
                 }
 
                 unsafe {
@@ -219,17 +346,129 @@ impl Module<gtk::Box> for CairoModule {
         area.set_size_request(self.width as i32, self.height as i32);
         container.append(&area);
 
-        glib::spawn_future_local(async move {
-            loop {
-                area.queue_draw();
-                glib::timeout_future(Duration::from_millis(self.frequency)).await;
+        // scroll events do not include the pointer position
+        let pointer = Rc::new(Cell::new((0.0, 0.0)));
+
+        {
+            let click = GestureClick::new();
+            click.set_button(0);
+
+            let lua = lua.clone();
+            let draw_function = draw_function.clone();
+            let area = area.clone();
+
+            click.connect_pressed(move |click, n_press, x, y| {
+                let redraw =
+                    call_handler(&lua, draw_function.borrow().as_ref(), ON_CLICK, |event| {
+                        event.set("x", x)?;
+                        event.set("y", y)?;
+                        event.set("button", click.current_button())?;
+                        event.set("n_press", n_press)
+                    });
+
+                if redraw {
+                    area.queue_draw();
+                }
+            });
+
+            area.add_controller(click);
+        }
+
+        {
+            let scroll = EventControllerScroll::new(EventControllerScrollFlags::BOTH_AXES);
+
+            let lua = lua.clone();
+            let draw_function = draw_function.clone();
+            let area = area.clone();
+            let pointer = pointer.clone();
+
+            scroll.connect_scroll(move |_, dx, dy| {
+                let (x, y) = pointer.get();
+                let redraw =
+                    call_handler(&lua, draw_function.borrow().as_ref(), ON_SCROLL, |event| {
+                        event.set("x", x)?;
+                        event.set("y", y)?;
+                        event.set("dx", dx)?;
+                        event.set("dy", dy)
+                    });
+
+                if redraw {
+                    area.queue_draw();
+                }
+
+                Propagation::Proceed
+            });
+
+            area.add_controller(scroll);
+        }
+
+        {
+            let motion = EventControllerMotion::new();
+
+            let on_hover = {
+                let lua = lua.clone();
+                let draw_function = draw_function.clone();
+                let area = area.clone();
+
+                move |position: Option<(f64, f64)>| {
+                    if let Some(position) = position {
+                        pointer.set(position);
+                    }
+
+                    let redraw =
+                        call_handler(&lua, draw_function.borrow().as_ref(), ON_HOVER, |event| {
+                            event.set("inside", position.is_some())?;
+                            if let Some((x, y)) = position {
+                                event.set("x", x)?;
+                                event.set("y", y)?;
+                            }
+                            Ok(())
+                        });
+
+                    if redraw {
+                        area.queue_draw();
+                    }
+                }
+            };
+
+            let on_hover = Rc::new(on_hover);
+
+            {
+                let on_hover = on_hover.clone();
+                motion.connect_enter(move |_, x, y| on_hover(Some((x, y))));
             }
-        });
-        context.subscribe().recv_glib((), move |(), _ev| {
-            // Reload/replace on file change
-            if let Some(function) = self.load_draw_function(&lua) {
-                draw_function.borrow_mut().replace(function);
+
+            {
+                let on_hover = on_hover.clone();
+                motion.connect_motion(move |_, x, y| on_hover(Some((x, y))));
+            }
+
+            motion.connect_leave(move |_| on_hover(None));
+
+            area.add_controller(motion);
+        }
+
+        if self.frequency > 0 {
+            let area = area.clone();
+            let frequency = self.frequency;
+
+            glib::spawn_future_local(async move {
+                loop {
+                    area.queue_draw();
+                    glib::timeout_future(Duration::from_millis(frequency)).await;
+                }
+            });
+        }
+
+        context.subscribe().recv_glib((), move |(), ev| match ev {
+            CairoUpdate::Reload => {
+                // Reload/replace on file change
+                if let Some(function) = self.load_draw_function(&lua) {
+                    draw_function.borrow_mut().replace(function);
+                }
+                area.queue_draw();
             }
+            CairoUpdate::Redraw => area.queue_draw(),
         });
 
         Ok(ModuleParts {
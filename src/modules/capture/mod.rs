@@ -0,0 +1,553 @@
+mod portal;
+
+use self::portal::{Portal, Screencast, Source};
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::config::{CommonConfig, LayoutConfig};
+use crate::gtk_helpers::IronbarLabelExt;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::panic_guard;
+use crate::{module_impl, spawn};
+use color_eyre::{Result, eyre::eyre};
+use glib::SourceId;
+use gtk::prelude::*;
+use gtk::{Align, Button, ContentFit, Label, Orientation, Picture};
+use rustix::io::{FdFlags, fcntl_setfd};
+use rustix::process::{Pid, Signal, kill_process};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::os::fd::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use tokio::process::{Child, Command};
+use tokio::sync::mpsc;
+use tracing::{debug, error};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct CaptureModule {
+    /// The label to show on the widget button while not recording.
+    /// Pango markup is supported.
+    ///
+    /// **Default**: `󰄀`
+    icon: String,
+
+    /// The format string to use for the widget button label while recording.
+    /// Pango markup is supported.
+    ///
+    /// The `{elapsed}` token is replaced with the recording length.
+    ///
+    /// **Default**: `󰑊 {elapsed}`
+    format_recording: String,
+
+    /// The directory to save recordings to.
+    /// Screenshots are saved wherever the portal backend chooses.
+    ///
+    /// **Default**: `$XDG_VIDEOS_DIR` or `~/Videos`
+    directory: PathBuf,
+
+    /// The command used to record the screen.
+    /// It is stopped with `SIGINT` and must finish writing the file on exit.
+    ///
+    /// The `{fd}` and `{node}` tokens are replaced with the PipeWire remote file descriptor
+    /// and stream node ID, and `{file}` with the path to save to.
+    ///
+    /// **Default**: A `gst-launch-1.0` pipeline which saves an H.264 MP4 file.
+    record_command: String,
+
+    /// Whether to open the popup with a preview once a capture is saved.
+    /// Use the `popup_auto_close` option to close it automatically.
+    ///
+    /// **Default**: `true`
+    show_result: bool,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for CaptureModule {
+    fn default() -> Self {
+        Self {
+            icon: "󰄀".to_string(),
+            format_recording: "󰑊 {elapsed}".to_string(),
+            directory: dirs::video_dir()
+                .or_else(|| dirs::home_dir().map(|dir| dir.join("Videos")))
+                .unwrap_or_default(),
+            record_command: "gst-launch-1.0 -e pipewiresrc fd={fd} path={node} do-timestamp=true \
+                ! videoconvert ! queue ! x264enc tune=zerolatency ! mp4mux \
+                ! filesink location=\"{file}\""
+                .to_string(),
+            show_result: true,
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum UiEvent {
+    /// Takes a screenshot.
+    /// If `true`, the user chooses what to capture.
+    Screenshot(bool),
+    Record(Source),
+    StopRecording,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureKind {
+    Screenshot,
+    Recording,
+}
+
+#[derive(Debug, Clone)]
+pub enum ControllerEvent {
+    /// Whether a recording is in progress.
+    Recording(bool),
+    /// A capture has been saved to the path.
+    Saved(CaptureKind, PathBuf),
+}
+
+/// A recording in progress.
+#[derive(Debug)]
+struct Recording {
+    screencast: Screencast,
+    recorder: Child,
+    file: PathBuf,
+}
+
+impl Module<Button> for CaptureModule {
+    type SendMessage = ControllerEvent;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("capture");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let tx = context.tx.clone();
+        let directory = self.directory.clone();
+        let record_command = self.record_command.clone();
+
+        spawn(async move {
+            let portal = match Portal::new().await {
+                Ok(portal) => portal,
+                Err(err) => {
+                    panic_guard::report_error(&err.wrap_err("Failed to connect to session bus"));
+                    return;
+                }
+            };
+
+            let mut recording = None::<Recording>;
+
+            loop {
+                // a recorder exiting by itself, such as when the stream is stopped
+                // by the compositor, finishes the recording the same as the stop button
+                let event = match &mut recording {
+                    Some(recording) => tokio::select! {
+                        event = rx.recv() => event,
+                        _ = recording.recorder.wait() => Some(UiEvent::StopRecording),
+                    },
+                    None => rx.recv().await,
+                };
+
+                let Some(event) = event else {
+                    break;
+                };
+
+                match event {
+                    UiEvent::Screenshot(interactive) => {
+                        match portal.screenshot(interactive).await {
+                            Ok(Some(uri)) => match glib::filename_from_uri(&uri) {
+                                Ok((path, _)) => {
+                                    tx.send_update(ControllerEvent::Saved(
+                                        CaptureKind::Screenshot,
+                                        path,
+                                    ))
+                                    .await;
+                                }
+                                Err(err) => {
+                                    error!("Screenshot saved to invalid URI '{uri}': {err:?}");
+                                }
+                            },
+                            Ok(None) => debug!("Screenshot cancelled"),
+                            Err(err) => error!("Failed to take screenshot: {err:?}"),
+                        }
+                    }
+                    UiEvent::Record(_) if recording.is_some() => {}
+                    UiEvent::Record(source) => match portal.screencast(source).await {
+                        Ok(Some(screencast)) => {
+                            match start_recorder(&screencast, &record_command, &directory).await {
+                                Ok((recorder, file)) => {
+                                    recording = Some(Recording {
+                                        screencast,
+                                        recorder,
+                                        file,
+                                    });
+                                    tx.send_update(ControllerEvent::Recording(true)).await;
+                                }
+                                Err(err) => {
+                                    error!("Failed to start recording: {err:?}");
+                                    if let Err(err) = screencast.session.close().await {
+                                        error!("Failed to close screencast session: {err:?}");
+                                    }
+                                }
+                            }
+                        }
+                        Ok(None) => debug!("Recording cancelled"),
+                        Err(err) => error!("Failed to start screencast: {err:?}"),
+                    },
+                    UiEvent::StopRecording => {
+                        let Some(recording) = recording.take() else {
+                            continue;
+                        };
+
+                        tx.send_update(ControllerEvent::Recording(false)).await;
+
+                        match stop_recording(recording).await {
+                            Ok(file) => {
+                                tx.send_update(ControllerEvent::Saved(
+                                    CaptureKind::Recording,
+                                    file,
+                                ))
+                                .await;
+                            }
+                            Err(err) => error!("Failed to save recording: {err:?}"),
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let label = Label::builder()
+            .use_markup(true)
+            .justify(self.layout.justify.into())
+            .build();
+        label.set_label(&self.icon);
+
+        let button = Button::new();
+        button.set_child(Some(&label.rotated(self.layout.angle())));
+        button.set_accessible_label("Screen capture");
+
+        // the time the current recording started
+        let started = Rc::new(RefCell::new(None::<Instant>));
+
+        {
+            let tx = context.tx.clone();
+            let controller_tx = context.controller_tx.clone();
+            let started = started.clone();
+
+            button.connect_clicked(move |button| {
+                if started.borrow().is_some() {
+                    controller_tx.send_spawn(UiEvent::StopRecording);
+                } else {
+                    tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
+                }
+            });
+        }
+
+        {
+            let button = button.clone();
+            let icon = self.icon.clone();
+            let format = self.format_recording.clone();
+            let show_result = self.show_result;
+            let tx = context.tx.clone();
+            let timer = Rc::new(RefCell::new(None::<SourceId>));
+
+            context.subscribe().recv_glib((), move |(), event| {
+                let recording = match event {
+                    ControllerEvent::Recording(recording) => recording,
+                    ControllerEvent::Saved(..) => {
+                        if show_result {
+                            tx.send_spawn(ModuleUpdateEvent::OpenPopup(button.popup_id()));
+                        }
+                        return;
+                    }
+                };
+
+                if let Some(id) = timer.take() {
+                    id.remove();
+                }
+
+                if recording {
+                    button.add_css_class("recording");
+                    started.replace(Some(Instant::now()));
+
+                    let update = {
+                        let label = label.clone();
+                        let format = format.clone();
+                        let started = started.clone();
+
+                        move || {
+                            if let Some(started) = *started.borrow() {
+                                label.set_label(
+                                    &format
+                                        .replace("{elapsed}", &format_elapsed(started.elapsed())),
+                                );
+                            }
+                        }
+                    };
+
+                    update();
+                    timer.replace(Some(glib::timeout_add_local(
+                        Duration::from_secs(1),
+                        move || {
+                            update();
+                            glib::ControlFlow::Continue
+                        },
+                    )));
+                } else {
+                    button.remove_css_class("recording");
+                    started.replace(None);
+                    label.set_label(&icon);
+                }
+            });
+        }
+
+        let popup = self
+            .into_popup(context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::new(Orientation::Vertical, 0);
+
+        let actions = gtk::Box::new(Orientation::Vertical, 5);
+        actions.add_css_class("actions");
+        container.append(&actions);
+
+        // the popup must be closed before capturing so it is not included
+        let capture = {
+            let tx = context.tx.clone();
+            let controller_tx = context.controller_tx.clone();
+
+            move |event: UiEvent| {
+                tx.send_spawn(ModuleUpdateEvent::ClosePopup);
+                controller_tx.send_spawn(event);
+            }
+        };
+
+        actions.append(&action_row(
+            "screenshot",
+            "Screenshot",
+            [
+                ("output", "󰍹 Screen", UiEvent::Screenshot(false)),
+                ("select", "󰩭 Select", UiEvent::Screenshot(true)),
+            ],
+            &capture,
+        ));
+
+        actions.append(&action_row(
+            "record",
+            "Record",
+            [
+                ("output", "󰍹 Screen", UiEvent::Record(Source::Monitor)),
+                ("window", "󰖯 Window", UiEvent::Record(Source::Window)),
+            ],
+            &capture,
+        ));
+
+        let result = gtk::Box::new(Orientation::Vertical, 5);
+        result.add_css_class("result");
+        result.set_visible(false);
+        container.append(&result);
+
+        let thumbnail = Picture::builder()
+            .content_fit(ContentFit::Contain)
+            .can_shrink(true)
+            .height_request(150)
+            .build();
+        thumbnail.add_css_class("thumbnail");
+        result.append(&thumbnail);
+
+        let path_label = Label::new(None);
+        path_label.add_css_class("path");
+        path_label.set_halign(Align::Start);
+        path_label.set_ellipsize(gtk::pango::EllipsizeMode::Middle);
+        result.append(&path_label);
+
+        let open = Button::with_label("Open");
+        open.add_css_class("btn-open");
+        open.set_halign(Align::End);
+        result.append(&open);
+
+        let saved_path = Rc::new(RefCell::new(None::<PathBuf>));
+
+        {
+            let saved_path = saved_path.clone();
+            open.connect_clicked(move |_| {
+                if let Some(path) = &*saved_path.borrow() {
+                    open_file(path);
+                }
+            });
+        }
+
+        if self.show_result {
+            let actions = actions.clone();
+            let result = result.clone();
+
+            context.subscribe().recv_glib((), move |(), event| {
+                let ControllerEvent::Saved(kind, path) = event else {
+                    return;
+                };
+
+                if kind == CaptureKind::Screenshot {
+                    thumbnail.set_filename(Some(&path));
+                }
+                thumbnail.set_visible(kind == CaptureKind::Screenshot);
+
+                path_label.set_label_escaped(&path.to_string_lossy());
+                path_label.set_tooltip_text(Some(&path.to_string_lossy()));
+                saved_path.replace(Some(path));
+
+                actions.set_visible(false);
+                result.set_visible(true);
+            });
+        }
+
+        // opening the popup manually always shows the capture buttons
+        context.popup.popover.connect_hide(move |_| {
+            result.set_visible(false);
+            actions.set_visible(true);
+        });
+
+        Some(container)
+    }
+}
+
+/// Creates a row with a title and a button for each capture option.
+fn action_row<const N: usize>(
+    class: &str,
+    title: &str,
+    options: [(&str, &str, UiEvent); N],
+    capture: &(impl Fn(UiEvent) + Clone + 'static),
+) -> gtk::Box {
+    let row = gtk::Box::new(Orientation::Vertical, 5);
+    row.add_css_class(class);
+
+    let title = Label::new(Some(title));
+    title.add_css_class("title");
+    title.set_halign(Align::Start);
+    row.append(&title);
+
+    let buttons = gtk::Box::new(Orientation::Horizontal, 5);
+    row.append(&buttons);
+
+    for (class, label, event) in options {
+        let button = Button::with_label(label);
+        button.add_css_class(class);
+
+        let capture = capture.clone();
+        button.connect_clicked(move |_| capture(event.clone()));
+
+        buttons.append(&button);
+    }
+
+    row
+}
+
+/// Starts the recorder command on the screencast stream,
+/// returning its process and the file it is saving to.
+async fn start_recorder(
+    screencast: &Screencast,
+    command: &str,
+    directory: &Path,
+) -> Result<(Child, PathBuf)> {
+    tokio::fs::create_dir_all(directory).await?;
+
+    let name = glib::DateTime::now_local()?.format("ironbar-recording-%Y-%m-%d_%H-%M-%S.mp4")?;
+    let file = directory.join(name.as_str());
+
+    // the recorder needs to inherit the PipeWire remote
+    fcntl_setfd(&screencast.fd, FdFlags::empty())?;
+
+    let command = command
+        .replace("{fd}", &screencast.fd.as_raw_fd().to_string())
+        .replace("{node}", &screencast.node.to_string())
+        .replace("{file}", &file.to_string_lossy());
+
+    debug!("Starting recorder: {command}");
+
+    // exec so the recorder receives the stop signal, rather than the shell
+    let recorder = Command::new("/bin/sh")
+        .args(["-c", &format!("exec {command}")])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .spawn()?;
+
+    Ok((recorder, file))
+}
+
+/// Stops the recorder and waits for it to finish writing,
+/// then closes the screencast session.
+async fn stop_recording(mut recording: Recording) -> Result<PathBuf> {
+    if let Some(pid) = recording
+        .recorder
+        .id()
+        .and_then(|id| Pid::from_raw(i32::try_from(id).ok()?))
+        && let Err(err) = kill_process(pid, Signal::INT)
+    {
+        debug!("Failed to signal recorder, it may have already exited: {err:?}");
+    }
+
+    let status = recording.recorder.wait().await?;
+    recording.screencast.session.close().await?;
+
+    if tokio::fs::try_exists(&recording.file).await? {
+        Ok(recording.file)
+    } else {
+        Err(eyre!("Recorder exited with {status} without saving a file"))
+    }
+}
+
+/// Formats the duration as `M:SS`, or `H:MM:SS` after an hour.
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    let (hours, mins, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+
+    if hours > 0 {
+        format!("{hours}:{mins:02}:{secs:02}")
+    } else {
+        format!("{mins}:{secs:02}")
+    }
+}
+
+/// Opens the file in its default application.
+fn open_file(path: &Path) {
+    let uri = gtk::gio::File::for_path(path).uri();
+
+    if let Err(err) =
+        gtk::gio::AppInfo::launch_default_for_uri(&uri, None::<&gtk::gio::AppLaunchContext>)
+    {
+        error!("Failed to open '{}': {err:?}", path.display());
+    }
+}
@@ -0,0 +1,300 @@
+use color_eyre::{Result, eyre::eyre};
+use futures_lite::StreamExt;
+use std::collections::HashMap;
+use std::os::fd::OwnedFd;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+use zbus::{Connection, proxy};
+
+const REQUEST_PATH: &str = "/org/freedesktop/portal/desktop/request";
+
+/// `SelectSources` option to only allow sharing monitors.
+const SOURCE_MONITOR: u32 = 1;
+/// `SelectSources` option to only allow sharing windows.
+const SOURCE_WINDOW: u32 = 2;
+/// `SelectSources` option to draw the cursor into the stream.
+const CURSOR_EMBEDDED: u32 = 2;
+
+#[proxy(
+    interface = "org.freedesktop.portal.Request",
+    default_service = "org.freedesktop.portal.Desktop"
+)]
+trait Request {
+    /// Emitted once the user has answered the request.
+    /// A `response` of `0` is success, `1` cancelled by the user, and `2` any other error.
+    #[zbus(signal)]
+    fn response(&self, response: u32, results: HashMap<String, OwnedValue>) -> zbus::Result<()>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.portal.Screenshot",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+trait Screenshot {
+    fn screenshot(
+        &self,
+        parent_window: &str,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<OwnedObjectPath>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.portal.ScreenCast",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+trait ScreenCast {
+    fn create_session(&self, options: HashMap<&str, Value<'_>>) -> zbus::Result<OwnedObjectPath>;
+
+    fn select_sources(
+        &self,
+        session_handle: &ObjectPath<'_>,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    fn start(
+        &self,
+        session_handle: &ObjectPath<'_>,
+        parent_window: &str,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<OwnedObjectPath>;
+
+    fn open_pipe_wire_remote(
+        &self,
+        session_handle: &ObjectPath<'_>,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<zbus::zvariant::OwnedFd>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.portal.Session",
+    default_service = "org.freedesktop.portal.Desktop"
+)]
+pub trait Session {
+    fn close(&self) -> zbus::Result<()>;
+}
+
+/// The kind of source to record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Monitor,
+    Window,
+}
+
+/// A started screencast session.
+#[derive(Debug)]
+pub struct Screencast {
+    /// The session, which must be closed once recording finishes.
+    pub session: SessionProxy<'static>,
+    /// The PipeWire node ID of the stream.
+    pub node: u32,
+    /// A connection to the PipeWire remote, which only has access to the stream.
+    pub fd: OwnedFd,
+}
+
+/// Client for the xdg-desktop-portal interfaces used to capture the screen.
+/// The portal backend is responsible for asking the user what to capture.
+#[derive(Debug)]
+pub struct Portal {
+    connection: Connection,
+}
+
+impl Portal {
+    pub async fn new() -> Result<Self> {
+        let connection = crate::clients::dbus::session().await?;
+        Ok(Self { connection })
+    }
+
+    /// Takes a screenshot, returning its file URI.
+    ///
+    /// If `interactive` is set, the backend lets the user choose
+    /// what to capture, such as a region or window.
+    /// Otherwise, the whole screen is captured.
+    ///
+    /// Returns `None` if the user cancelled.
+    pub async fn screenshot(&self, interactive: bool) -> Result<Option<String>> {
+        let proxy = ScreenshotProxy::new(&self.connection).await?;
+
+        let Some(results) = self
+            .request(|token| async move {
+                proxy
+                    .screenshot(
+                        "",
+                        HashMap::from([
+                            ("handle_token", Value::from(token.as_str())),
+                            ("interactive", Value::from(interactive)),
+                        ]),
+                    )
+                    .await
+            })
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let uri = results
+            .get("uri")
+            .ok_or_else(|| eyre!("Screenshot response is missing the URI"))?
+            .downcast_ref::<&str>()?
+            .to_string();
+
+        Ok(Some(uri))
+    }
+
+    /// Starts a screencast of a single source chosen by the user.
+    ///
+    /// Returns `None` if the user cancelled.
+    pub async fn screencast(&self, source: Source) -> Result<Option<Screencast>> {
+        let proxy = &ScreenCastProxy::new(&self.connection).await?;
+
+        let session_token = next_token();
+        let Some(results) = self
+            .request(|token| async move {
+                proxy
+                    .create_session(HashMap::from([
+                        ("handle_token", Value::from(token.as_str())),
+                        ("session_handle_token", Value::from(session_token.as_str())),
+                    ]))
+                    .await
+            })
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let session_handle = results
+            .get("session_handle")
+            .ok_or_else(|| eyre!("CreateSession response is missing the session handle"))?
+            .downcast_ref::<&str>()?;
+        let session_handle = OwnedObjectPath::try_from(session_handle)?;
+
+        let session = SessionProxy::builder(&self.connection)
+            .path(session_handle.clone())?
+            .build()
+            .await?;
+
+        let types = match source {
+            Source::Monitor => SOURCE_MONITOR,
+            Source::Window => SOURCE_WINDOW,
+        };
+
+        let session_handle = &session_handle;
+
+        let res = async {
+            let selected = self
+                .request(|token| async move {
+                    proxy
+                        .select_sources(
+                            session_handle,
+                            HashMap::from([
+                                ("handle_token", Value::from(token.as_str())),
+                                ("types", Value::from(types)),
+                                ("multiple", Value::from(false)),
+                                ("cursor_mode", Value::from(CURSOR_EMBEDDED)),
+                            ]),
+                        )
+                        .await
+                })
+                .await?;
+
+            if selected.is_none() {
+                return Ok(None);
+            }
+
+            let Some(results) = self
+                .request(|token| async move {
+                    proxy
+                        .start(
+                            session_handle,
+                            "",
+                            HashMap::from([("handle_token", Value::from(token.as_str()))]),
+                        )
+                        .await
+                })
+                .await?
+            else {
+                return Ok(None);
+            };
+
+            let streams: Vec<(u32, HashMap<String, OwnedValue>)> = results
+                .get("streams")
+                .ok_or_else(|| eyre!("Start response is missing the streams"))?
+                .try_clone()?
+                .try_into()?;
+
+            let node = streams
+                .first()
+                .map(|(node, _)| *node)
+                .ok_or_else(|| eyre!("No streams were started"))?;
+
+            let fd = proxy
+                .open_pipe_wire_remote(session_handle, HashMap::new())
+                .await?;
+
+            Ok::<_, color_eyre::Report>(Some((node, OwnedFd::from(fd))))
+        }
+        .await;
+
+        match res {
+            Ok(Some((node, fd))) => Ok(Some(Screencast { session, node, fd })),
+            Ok(None) => {
+                session.close().await?;
+                Ok(None)
+            }
+            Err(err) => {
+                session.close().await?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Makes a portal request, waiting for its response.
+    ///
+    /// The request object path is predicted from the handle token,
+    /// so the response can be subscribed to before the request is made
+    /// and cannot be missed.
+    ///
+    /// Returns `None` if the user cancelled.
+    async fn request<F, Fut>(&self, call: F) -> Result<Option<HashMap<String, OwnedValue>>>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = zbus::Result<OwnedObjectPath>>,
+    {
+        let token = next_token();
+
+        let sender = self
+            .connection
+            .unique_name()
+            .ok_or_else(|| eyre!("Connection has no unique name"))?
+            .trim_start_matches(':')
+            .replace('.', "_");
+
+        let request = RequestProxy::builder(&self.connection)
+            .path(format!("{REQUEST_PATH}/{sender}/{token}"))?
+            .build()
+            .await?;
+
+        let mut responses = request.receive_response().await?;
+
+        call(token).await?;
+
+        let response = responses
+            .next()
+            .await
+            .ok_or_else(|| eyre!("Portal request closed without a response"))?;
+        let args = response.args()?;
+
+        match args.response {
+            0 => Ok(Some(args.results)),
+            1 => Ok(None),
+            code => Err(eyre!("Portal request failed with response code {code}")),
+        }
+    }
+}
+
+/// Gets a unique token to identify a request or session.
+fn next_token() -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    format!("ironbar{}", COUNTER.fetch_add(1, Ordering::Relaxed))
+}
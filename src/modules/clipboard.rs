@@ -1,23 +1,37 @@
 use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
-use crate::clients::clipboard::{self, ClipboardEvent};
+use crate::clients::clipboard::{ClipboardEvent, StorageConfig};
 use crate::clients::wayland::{ClipboardItem, ClipboardValue};
 use crate::config::{CommonConfig, LayoutConfig, TruncateMode};
 use crate::gtk_helpers::IronbarLabelExt;
 use crate::gtk_helpers::IronbarPaintableExt;
+use crate::gtk_helpers::{IronbarGlibExt, IronbarGtkExt};
 use crate::image::IconButton;
 use crate::modules::{
     Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
 };
 use crate::{module_impl, spawn};
-use gtk::gdk::{BUTTON_PRIMARY, Texture};
+use glib::Propagation;
+use gtk::gdk::{BUTTON_PRIMARY, Key, Texture};
 use gtk::prelude::*;
-use gtk::{Button, CheckButton, ContentFit, GestureClick, Label, Orientation, Picture, Widget};
+use gtk::{
+    Button, CheckButton, ContentFit, EventControllerKey, GestureClick, Label, Orientation, Picture,
+    PropagationPhase, SearchEntry, Widget,
+};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::ops::Deref;
 use tokio::sync::mpsc;
 use tracing::{debug, error};
 
+/// The tag storing the text to match search queries against on each row.
+const SEARCH_TAG: &str = "search_text";
+
+/// The maximum number of characters to show in the hover preview for text items.
+const TOOLTIP_MAX_LENGTH: usize = 2000;
+
+/// The size to render the hover preview for image items at.
+const IMAGE_PREVIEW_SIZE: f64 = 512.0;
+
 #[derive(Debug, Deserialize, Clone)]
 #[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
 #[serde(default)]
@@ -50,6 +64,29 @@ pub struct ClipboardModule {
     /// **Default**: `64.0`
     image_max_height: f64,
 
+    /// Whether to show a search entry at the top of the popup,
+    /// which filters the items as you type.
+    ///
+    /// The popup must receive keyboard input to type into the entry.
+    /// See the `popup_keyboard_mode` [common option](module-level-options#common-options).
+    ///
+    /// **Default**: `true`
+    search: bool,
+
+    /// The maximum number of characters to show for text items.
+    /// Longer items are cut short,
+    /// and the full value is shown when hovering over them.
+    ///
+    /// **Default**: `200`
+    max_text_length: usize,
+
+    /// Options for saving the history between restarts,
+    /// and for ignoring sensitive items.
+    ///
+    /// These are shared by all clipboard modules,
+    /// so the options from the first module to start are used.
+    storage: StorageConfig,
+
     // -- Common --
     /// See [truncate options](module-level-options#truncate-mode).
     ///
@@ -73,6 +110,9 @@ impl Default for ClipboardModule {
             max_items: 10,
             image_max_width: 256.0,
             image_max_height: 64.0,
+            search: true,
+            max_text_length: 200,
+            storage: StorageConfig::default(),
             truncate: None,
             layout: LayoutConfig::default(),
             common: Some(CommonConfig::default()),
@@ -109,34 +149,42 @@ impl Module<Button> for ClipboardModule {
         let max_items = self.max_items;
 
         let tx = context.tx.clone();
-        let client = context.client::<clipboard::Client>();
+        let client = context
+            .ironbar
+            .clients
+            .borrow_mut()
+            .clipboard(&self.storage);
 
         // listen to clipboard events
-        spawn(async move {
-            let mut rx = client.subscribe(max_items);
-
-            while let Some(event) = rx.recv().await {
-                match event {
-                    ClipboardEvent::Add(item) => {
-                        let msg = match item.value.as_ref() {
-                            ClipboardValue::Other => ControllerEvent::Deactivate,
-                            _ => ControllerEvent::Add(item.id, item),
-                        };
-                        tx.send_update_spawn(msg);
-                    }
-                    ClipboardEvent::Remove(id) => {
-                        tx.send_update_spawn(ControllerEvent::Remove(id));
-                    }
-                    ClipboardEvent::Activate(id) => {
-                        tx.send_update_spawn(ControllerEvent::Activate(id));
+        {
+            let client = client.clone();
+            spawn(async move {
+                let mut rx = client.subscribe(max_items);
+
+                while let Some(event) = rx.recv().await {
+                    match event {
+                        ClipboardEvent::Add(item) => {
+                            let msg = match item.value.as_ref() {
+                                ClipboardValue::Other => ControllerEvent::Deactivate,
+                                _ => ControllerEvent::Add(item.id, item),
+                            };
+                            tx.send_update_spawn(msg);
+                        }
+                        ClipboardEvent::Remove(id) => {
+                            tx.send_update_spawn(ControllerEvent::Remove(id));
+                        }
+                        ClipboardEvent::Activate(id) => {
+                            tx.send_update_spawn(ControllerEvent::Activate(id));
+                        }
+                        ClipboardEvent::Deactivate => {
+                            tx.send_update_spawn(ControllerEvent::Deactivate);
+                        }
                     }
                 }
-            }
 
-            error!("Clipboard client unexpectedly closed");
-        });
-
-        let client = context.client::<clipboard::Client>();
+                error!("Clipboard client unexpectedly closed");
+            });
+        }
 
         // listen to ui events
         spawn(async move {
@@ -156,7 +204,7 @@ impl Module<Button> for ClipboardModule {
         context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
         info: &ModuleInfo,
     ) -> color_eyre::Result<ModuleParts<Button>> {
-        let button = IconButton::new(&self.icon, self.icon_size, context.ironbar.image_provider());
+        let button = IconButton::new(&self.icon, self.icon_size, context.image_provider());
 
         button.label().set_justify(self.layout.justify.into());
         button.add_css_class("btn");
@@ -183,12 +231,67 @@ impl Module<Button> for ClipboardModule {
     {
         let container = gtk::Box::new(Orientation::Vertical, 10);
 
+        let search = SearchEntry::new();
+        search.add_css_class("search");
+        search.set_visible(self.search);
+        container.append(&search);
+
         let entries = gtk::Box::new(Orientation::Vertical, 5);
         container.append(&entries);
 
         let hidden_option = CheckButton::new();
         entries.append(&hidden_option);
 
+        {
+            let entries = entries.clone();
+            search.connect_search_changed(move |search| {
+                filter(&entries, &search.text());
+                select_first(&entries);
+            });
+        }
+
+        // start each time the popup opens with a fresh search
+        {
+            let search = search.clone();
+            let entries = entries.clone();
+            container.connect_map(move |_| {
+                search.set_text("");
+                search.grab_focus();
+                select_first(&entries);
+            });
+        }
+
+        let key_controller = EventControllerKey::new();
+        key_controller.set_propagation_phase(PropagationPhase::Capture);
+
+        {
+            let entries = entries.clone();
+            let tx = context.controller_tx.clone();
+            let module_tx = context.tx.clone();
+
+            key_controller.connect_key_pressed(move |_, key, _, _| match key {
+                Key::Up => {
+                    move_selection(&entries, -1);
+                    Propagation::Stop
+                }
+                Key::Down => {
+                    move_selection(&entries, 1);
+                    Propagation::Stop
+                }
+                Key::Return | Key::KP_Enter => {
+                    if let Some(id) = selected_id(&entries) {
+                        debug!("Copying item with id: {id}");
+                        tx.send_spawn(UIEvent::Copy(id));
+                        module_tx.send_spawn(ModuleUpdateEvent::ClosePopup);
+                    }
+                    Propagation::Stop
+                }
+                _ => Propagation::Proceed,
+            });
+        }
+
+        container.add_controller(key_controller);
+
         let mut items = HashMap::new();
 
         context
@@ -200,12 +303,24 @@ impl Module<Button> for ClipboardModule {
 
                         let row = gtk::Box::new(Orientation::Horizontal, 0);
                         row.add_css_class("item");
+                        row.set_widget_name(&format!("item-{id}"));
 
-                        let button = match item.value.as_ref() {
+                        let (button, search_text) = match item.value.as_ref() {
                             ClipboardValue::Text(value) => {
                                 let button = CheckButton::builder().group(hidden_option).build();
 
-                                let label = Label::new(Some(value));
+                                let shortened = value.chars().count() > self.max_text_length;
+
+                                let label = if shortened {
+                                    let text = value
+                                        .chars()
+                                        .take(self.max_text_length)
+                                        .collect::<String>();
+                                    Label::new(Some(&format!("{text}…")))
+                                } else {
+                                    Label::new(Some(value))
+                                };
+
                                 label.set_xalign(0.1);
                                 button.set_child(Some(&label));
 
@@ -213,24 +328,47 @@ impl Module<Button> for ClipboardModule {
                                     label.truncate(truncate);
                                 }
 
+                                if shortened || self.truncate.is_some() {
+                                    let preview =
+                                        value.chars().take(TOOLTIP_MAX_LENGTH).collect::<String>();
+                                    button.set_tooltip_text(Some(&preview));
+                                }
+
                                 button.add_css_class("text");
-                                button
+                                (button, value.clone())
                             }
                             ClipboardValue::Image(bytes) => match Texture::from_bytes(bytes) {
                                 Ok(texture) => {
-                                    let texture =
-                                        texture.scale(self.image_max_width, self.image_max_height);
+                                    let thumbnail = texture
+                                        .clone()
+                                        .scale(self.image_max_width, self.image_max_height);
 
                                     let image = Picture::new();
                                     image.set_content_fit(ContentFit::ScaleDown);
-                                    image.set_paintable(texture.as_ref());
+                                    image.set_paintable(thumbnail.as_ref());
 
                                     let button =
                                         CheckButton::builder().group(hidden_option).build();
                                     button.set_child(Some(&image));
                                     button.add_css_class("image");
 
-                                    button
+                                    button.set_has_tooltip(true);
+                                    button.connect_query_tooltip(move |_, _, _, _, tooltip| {
+                                        let preview = Picture::new();
+                                        preview.set_content_fit(ContentFit::ScaleDown);
+                                        preview.set_paintable(
+                                            texture
+                                                .clone()
+                                                .scale(IMAGE_PREVIEW_SIZE, IMAGE_PREVIEW_SIZE)
+                                                .as_ref(),
+                                        );
+
+                                        tooltip.set_custom(Some(&preview));
+                                        true
+                                    });
+
+                                    // lets images be found by searching for their type
+                                    (button, item.mime_type.to_string())
                                 }
                                 Err(err) => {
                                     error!("{err:?}");
@@ -287,7 +425,11 @@ impl Module<Button> for ClipboardModule {
                         row.append(&remove_button);
                         button.set_hexpand(true);
 
+                        row.set_visible(fuzzy_match(&search.text(), &search_text));
+                        row.set_tag(SEARCH_TAG, search_text);
+
                         entries.prepend(&row);
+                        select_first(&entries);
 
                         items.insert(id, (row, button));
                     }
@@ -337,3 +479,96 @@ where
         .split_once('-')
         .and_then(|(_, id)| id.parse().ok())
 }
+
+/// Checks whether every character of the query
+/// appears in the value in the same order, ignoring case and whitespace.
+fn fuzzy_match(query: &str, value: &str) -> bool {
+    let mut chars = value.chars().flat_map(char::to_lowercase);
+
+    query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .all(|query_char| chars.any(|c| c == query_char))
+}
+
+/// Shows only the item rows matching the query.
+fn filter(entries: &gtk::Box, query: &str) {
+    for row in entries.children() {
+        if let Some(search_text) = row.get_tag::<String>(SEARCH_TAG) {
+            row.set_visible(fuzzy_match(query, search_text));
+        }
+    }
+}
+
+/// Gets the visible item rows, newest first.
+fn visible_rows(entries: &gtk::Box) -> Vec<Widget> {
+    entries
+        .children()
+        .filter(|row| row.is_visible() && row.has_css_class("item"))
+        .collect()
+}
+
+/// Highlights the newest visible item for keyboard navigation.
+fn select_first(entries: &gtk::Box) {
+    for row in entries.children() {
+        row.remove_css_class("selected");
+    }
+
+    if let Some(row) = visible_rows(entries).first() {
+        row.add_css_class("selected");
+    }
+}
+
+/// Moves the keyboard navigation highlight by `offset` visible items,
+/// stopping at the first and last items.
+fn move_selection(entries: &gtk::Box, offset: isize) {
+    let rows = visible_rows(entries);
+    if rows.is_empty() {
+        return;
+    }
+
+    let index = rows
+        .iter()
+        .position(|row| row.has_css_class("selected"))
+        .map_or(0, |index| {
+            index.saturating_add_signed(offset).min(rows.len() - 1)
+        });
+
+    for row in entries.children() {
+        row.remove_css_class("selected");
+    }
+
+    rows[index].add_css_class("selected");
+}
+
+/// Gets the ID of the highlighted item, if it is visible.
+fn selected_id(entries: &gtk::Box) -> Option<usize> {
+    visible_rows(entries)
+        .iter()
+        .find(|row| row.has_css_class("selected"))
+        .and_then(get_button_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fuzzy_match;
+
+    #[test]
+    fn fuzzy_match_empty_query() {
+        assert!(fuzzy_match("", "anything"));
+    }
+
+    #[test]
+    fn fuzzy_match_subsequence() {
+        assert!(fuzzy_match("hlo", "hello"));
+        assert!(fuzzy_match("HeLLo", "hello world"));
+        assert!(fuzzy_match("hello world", "helloworld"));
+    }
+
+    #[test]
+    fn fuzzy_match_order() {
+        assert!(!fuzzy_match("olh", "hello"));
+        assert!(!fuzzy_match("helloo", "hello"));
+    }
+}
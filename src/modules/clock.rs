@@ -1,177 +0,0 @@
-use std::env;
-
-use chrono::{DateTime, Local, Locale};
-use color_eyre::Result;
-use gtk::prelude::*;
-use gtk::{Align, Button, Calendar, Label, Orientation};
-use serde::Deserialize;
-use tokio::sync::mpsc;
-use tokio::time::sleep;
-
-use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
-use crate::config::{CommonConfig, LayoutConfig};
-use crate::modules::{
-    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
-};
-use crate::{module_impl, spawn};
-
-#[derive(Debug, Deserialize, Clone)]
-#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
-#[serde(default)]
-pub struct ClockModule {
-    /// The format string to use for the date/time shown on the bar.
-    /// Pango markup is supported.
-    ///
-    /// Detail on available tokens can be found here:
-    /// <https://docs.rs/chrono/latest/chrono/format/strftime/index.html>
-    ///
-    /// **Default**: `%d/%m/%Y %H:%M`
-    format: String,
-
-    /// The format string to use for the date/time shown in the popup header.
-    /// Pango markup is supported.
-    ///
-    /// Detail on available tokens can be found here:
-    /// <https://docs.rs/chrono/latest/chrono/format/strftime/index.html>
-    ///
-    /// **Default**: `%H:%M:%S`
-    format_popup: String,
-
-    /// The locale to use when formatting dates.
-    ///
-    /// Note this will not control the calendar -
-    /// for that you must set `LC_TIME`.
-    ///
-    /// **Valid options**: See [here](https://docs.rs/pure-rust-locales/0.8.1/pure_rust_locales/enum.Locale.html#variants)
-    /// <br>
-    /// **Default**: `$LC_TIME` or `$LANG` or `'POSIX'`
-    locale: String,
-
-    /// See [layout options](module-level-options#layout)
-    #[serde(flatten)]
-    layout: LayoutConfig,
-
-    /// See [common options](module-level-options#common-options).
-    #[serde(flatten)]
-    pub common: Option<CommonConfig>,
-}
-
-impl Default for ClockModule {
-    fn default() -> Self {
-        ClockModule {
-            format: "%d/%m/%Y %H:%M".to_string(),
-            format_popup: "%H:%M:%S".to_string(),
-            locale: default_locale(),
-            layout: LayoutConfig::default(),
-            common: Some(CommonConfig::default()),
-        }
-    }
-}
-
-fn default_locale() -> String {
-    env::var("LC_TIME")
-        .or_else(|_| env::var("LANG"))
-        .map_or_else(|_| "POSIX".to_string(), strip_tail)
-}
-
-fn strip_tail(string: String) -> String {
-    string
-        .split_once('.')
-        .map(|(head, _)| head.to_string())
-        .unwrap_or(string)
-}
-
-impl Module<Button> for ClockModule {
-    type SendMessage = DateTime<Local>;
-    type ReceiveMessage = ();
-
-    module_impl!("clock");
-
-    fn spawn_controller(
-        &self,
-        _info: &ModuleInfo,
-        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
-        _rx: mpsc::Receiver<Self::ReceiveMessage>,
-    ) -> Result<()> {
-        let tx = context.tx.clone();
-        spawn(async move {
-            loop {
-                let date = Local::now();
-                tx.send_update(date).await;
-                sleep(tokio::time::Duration::from_millis(500)).await;
-            }
-        });
-
-        Ok(())
-    }
-
-    fn into_widget(
-        self,
-        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
-        info: &ModuleInfo,
-    ) -> Result<ModuleParts<Button>> {
-        let button = Button::new();
-        let label = Label::builder()
-            .use_markup(true)
-            .justify(self.layout.justify.into())
-            .build();
-
-        button.set_child(Some(&label));
-
-        let tx = context.tx.clone();
-        button.connect_clicked(move |button| {
-            tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
-        });
-
-        let format = self.format.clone();
-        let locale = Locale::try_from(self.locale.as_str()).unwrap_or(Locale::POSIX);
-
-        let rx = context.subscribe();
-        rx.recv_glib((), move |(), date| {
-            let date_string = format!("{}", date.format_localized(&format, locale));
-            label.set_label(&date_string);
-        });
-
-        let popup = self
-            .into_popup(context, info)
-            .into_popup_parts(vec![&button]);
-
-        Ok(ModuleParts::new(button, popup))
-    }
-
-    fn into_popup(
-        self,
-        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
-        _info: &ModuleInfo,
-    ) -> Option<gtk::Box> {
-        let container = gtk::Box::new(Orientation::Vertical, 0);
-
-        let clock = Label::builder()
-            .halign(Align::Center)
-            .use_markup(true)
-            .build();
-        clock.add_css_class("calendar-clock");
-
-        container.append(&clock);
-
-        let calendar = Calendar::new();
-        calendar.add_css_class("calendar");
-        container.append(&calendar);
-
-        let format = self.format_popup;
-        let locale = Locale::try_from(self.locale.as_str()).unwrap_or(Locale::POSIX);
-
-        context.subscribe().recv_glib((), move |(), date| {
-            let date_string = format!("{}", date.format_localized(&format, locale));
-            clock.set_label(&date_string);
-        });
-
-        // Reset selected date on each popup open
-        context.popup.popover.connect_show(move |_| {
-            let date = glib::DateTime::now_local().expect("should get current time");
-            calendar.select_day(&date);
-        });
-
-        Some(container)
-    }
-}
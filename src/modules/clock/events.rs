@@ -0,0 +1,495 @@
+use crate::config::Secret;
+use chrono::{DateTime, Days, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use color_eyre::Result;
+use serde::Deserialize;
+#[cfg(feature = "http")]
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tracing::error;
+#[cfg(not(feature = "http"))]
+use tracing::warn;
+
+/// A source to load calendar events from.
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CalendarSource {
+    /// A local iCalendar (`.ics`) file.
+    File {
+        /// The path to the file. A leading `~` is expanded to the home directory.
+        path: PathBuf,
+    },
+    /// A calendar collection on a CalDAV server.
+    /// Requires the `http` feature.
+    Caldav {
+        /// The URL of the calendar collection.
+        url: String,
+        /// The username to authenticate with, if any.
+        username: Option<String>,
+        /// The password to authenticate with, if any.
+        /// See [secrets](configuration-guide#34-secrets) for other ways to provide this.
+        password: Option<Secret>,
+    },
+}
+
+/// A single calendar event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub summary: String,
+    pub start: DateTime<Local>,
+    /// The exclusive end of the event.
+    pub end: DateTime<Local>,
+    pub all_day: bool,
+}
+
+impl Event {
+    /// Whether the event takes place at any point on the date.
+    pub fn occurs_on(&self, date: NaiveDate) -> bool {
+        let day_start = local_midnight(date);
+        let day_end = date.checked_add_days(Days::new(1)).map(local_midnight);
+
+        match (day_start, day_end) {
+            (Some(day_start), Some(day_end)) => {
+                self.start < day_end && (self.end > day_start || self.start >= day_start)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Loads events from calendar sources.
+#[derive(Debug, Default)]
+pub struct Loader {
+    #[cfg(feature = "http")]
+    http: reqwest::Client,
+    /// Resolved CalDAV passwords, keyed by calendar URL.
+    #[cfg(feature = "http")]
+    passwords: HashMap<String, String>,
+}
+
+impl Loader {
+    /// Creates a loader for the sources, resolving each CalDAV password.
+    ///
+    /// Resolving a password can run a command, so happens on a blocking thread.
+    /// Failures are logged, and the calendar is skipped when loading.
+    pub async fn new(sources: &[CalendarSource]) -> Self {
+        #[cfg_attr(not(feature = "http"), allow(unused_mut))]
+        let mut loader = Self::default();
+
+        #[cfg(feature = "http")]
+        for source in sources {
+            let CalendarSource::Caldav {
+                url,
+                password: Some(password),
+                ..
+            } = source
+            else {
+                continue;
+            };
+
+            let password = password.clone();
+            match crate::spawn_blocking(move || password.resolve()).await {
+                Ok(Ok(password)) => {
+                    loader.passwords.insert(url.clone(), password);
+                }
+                Ok(Err(err)) => error!(
+                    "{:?}",
+                    err.wrap_err(format!("Failed to get password for calendar '{url}'"))
+                ),
+                Err(err) => error!("{err:?}"),
+            }
+        }
+
+        #[cfg(not(feature = "http"))]
+        let _ = sources;
+
+        loader
+    }
+
+    /// Loads events from all sources, sorted by start time.
+    /// Sources which fail to load are logged and skipped.
+    pub async fn load(&self, sources: &[CalendarSource]) -> Vec<Event> {
+        let mut events = vec![];
+
+        for source in sources {
+            let res = match source {
+                CalendarSource::File { path } => load_file(path).await,
+                #[cfg(feature = "http")]
+                CalendarSource::Caldav {
+                    url,
+                    username,
+                    password,
+                } => {
+                    let resolved = self.passwords.get(url).map(String::as_str);
+
+                    // the error was logged when resolving
+                    if password.is_some() && resolved.is_none() {
+                        continue;
+                    }
+
+                    load_caldav(&self.http, url, username.as_deref(), resolved).await
+                }
+                #[cfg(not(feature = "http"))]
+                CalendarSource::Caldav { url, .. } => {
+                    warn!("Cannot load CalDAV calendar '{url}' as the `http` feature is disabled");
+                    continue;
+                }
+            };
+
+            match res {
+                Ok(mut new_events) => events.append(&mut new_events),
+                Err(err) => error!("Failed to load calendar events: {err:?}"),
+            }
+        }
+
+        events.sort_by_key(|event| event.start);
+        events
+    }
+}
+
+async fn load_file(path: &Path) -> Result<Vec<Event>> {
+    let path = match (path.strip_prefix("~"), dirs::home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    };
+
+    let contents = tokio::fs::read_to_string(&path).await?;
+    Ok(parse_ics(&contents))
+}
+
+/// Fetches events from a CalDAV calendar collection,
+/// limited to those from two months ago until a year from now.
+#[cfg(feature = "http")]
+async fn load_caldav(
+    http: &reqwest::Client,
+    url: &str,
+    username: Option<&str>,
+    password: Option<&str>,
+) -> Result<Vec<Event>> {
+    const FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+    let now = Utc::now();
+    let start = now - chrono::Duration::days(62);
+    let end = now + chrono::Duration::days(366);
+
+    let body = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop><C:calendar-data/></D:prop>
+  <C:filter>
+    <C:comp-filter name="VCALENDAR">
+      <C:comp-filter name="VEVENT">
+        <C:time-range start="{}" end="{}"/>
+      </C:comp-filter>
+    </C:comp-filter>
+  </C:filter>
+</C:calendar-query>"#,
+        start.format(FORMAT),
+        end.format(FORMAT)
+    );
+
+    let mut request = http
+        .request(reqwest::Method::from_bytes(b"REPORT")?, url)
+        .header("Depth", "1")
+        .header("Content-Type", "application/xml; charset=utf-8")
+        .body(body);
+
+    if let Some(username) = username {
+        request = request.basic_auth(username, password);
+    }
+
+    let response = request.send().await?.error_for_status()?.text().await?;
+
+    Ok(calendar_data(&response)
+        .iter()
+        .flat_map(|ics| parse_ics(ics))
+        .collect())
+}
+
+/// Extracts the contents of each `calendar-data` element
+/// from a CalDAV multistatus response.
+///
+/// Servers use different namespace prefixes,
+/// so elements are matched on their local name only.
+#[cfg(feature = "http")]
+fn calendar_data(xml: &str) -> Vec<String> {
+    const NAME: &str = "calendar-data";
+
+    let mut blocks = vec![];
+    let mut rest = xml;
+
+    while let Some(index) = rest.find(NAME) {
+        let Some(tag_start) = rest[..index].rfind('<') else {
+            rest = &rest[index + NAME.len()..];
+            continue;
+        };
+        let Some(tag_len) = rest[tag_start..].find('>') else {
+            break;
+        };
+
+        let tag = &rest[tag_start..=tag_start + tag_len];
+        rest = &rest[tag_start + tag_len + 1..];
+
+        // only opening tags with content are of interest
+        if tag.starts_with("</") || tag.ends_with("/>") {
+            continue;
+        }
+
+        let Some(end) = rest.find(NAME).and_then(|index| rest[..index].rfind("</")) else {
+            break;
+        };
+
+        let content = rest[..end].trim();
+        let content = content
+            .strip_prefix("<![CDATA[")
+            .and_then(|content| content.strip_suffix("]]>"))
+            .map_or_else(|| unescape_xml(content), ToString::to_string);
+
+        blocks.push(content);
+        rest = &rest[end..];
+    }
+
+    blocks
+}
+
+#[cfg(feature = "http")]
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&#13;", "\r")
+        .replace("&#xD;", "\r")
+        .replace("&amp;", "&")
+}
+
+/// Parses the events from an iCalendar document.
+///
+/// Times with a `TZID` parameter are treated as local time,
+/// and recurring events only include their first occurrence.
+fn parse_ics(ics: &str) -> Vec<Event> {
+    let mut events = vec![];
+
+    // the component each property belongs to,
+    // so properties of alarms inside events are ignored
+    let mut components = vec![];
+    let mut event = PartialEvent::default();
+
+    for line in unfold(ics) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+
+        let (name, params) = name.split_once(';').unwrap_or((name, ""));
+
+        match name.to_ascii_uppercase().as_str() {
+            "BEGIN" => {
+                if value.eq_ignore_ascii_case("VEVENT") {
+                    event = PartialEvent::default();
+                }
+                components.push(value.to_ascii_uppercase());
+            }
+            "END" => {
+                if components.pop().as_deref() == Some("VEVENT")
+                    && let Some(event) = std::mem::take(&mut event).finish()
+                {
+                    events.push(event);
+                }
+            }
+            _ if components.last().map(String::as_str) != Some("VEVENT") => {}
+            "SUMMARY" => event.summary = Some(unescape_text(value)),
+            "DTSTART" => event.start = parse_time(value, params),
+            "DTEND" => event.end = parse_time(value, params),
+            "STATUS" => event.cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+            _ => {}
+        }
+    }
+
+    events
+}
+
+#[derive(Debug, Default)]
+struct PartialEvent {
+    summary: Option<String>,
+    start: Option<(DateTime<Local>, bool)>,
+    end: Option<(DateTime<Local>, bool)>,
+    cancelled: bool,
+}
+
+impl PartialEvent {
+    fn finish(self) -> Option<Event> {
+        if self.cancelled {
+            return None;
+        }
+
+        let (start, all_day) = self.start?;
+
+        // without an end, all-day events last the day and others are instant
+        let end = match self.end {
+            Some((end, _)) => end,
+            None if all_day => start.checked_add_days(Days::new(1))?,
+            None => start,
+        };
+
+        Some(Event {
+            summary: self.summary.unwrap_or_default(),
+            start,
+            end,
+            all_day,
+        })
+    }
+}
+
+/// Joins folded content lines,
+/// which continue onto the next line when it starts with whitespace.
+fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+
+    for line in ics.lines() {
+        let line = line.trim_end_matches('\r');
+
+        if let Some(continued) = line.strip_prefix([' ', '\t'])
+            && let Some(last) = lines.last_mut()
+        {
+            last.push_str(continued);
+        } else if !line.is_empty() {
+            lines.push(line.to_string());
+        }
+    }
+
+    lines
+}
+
+/// Parses a `DATE` or `DATE-TIME` value,
+/// returning the time and whether it is a date only.
+fn parse_time(value: &str, params: &str) -> Option<(DateTime<Local>, bool)> {
+    let is_date = params
+        .split(';')
+        .any(|param| param.eq_ignore_ascii_case("VALUE=DATE"))
+        || value.len() == 8;
+
+    if is_date {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return local_midnight(date).map(|time| (time, true));
+    }
+
+    let time = if let Some(value) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+        Utc.from_utc_datetime(&time).with_timezone(&Local)
+    } else {
+        let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+        Local.from_local_datetime(&time).earliest()?
+    };
+
+    Some((time, false))
+}
+
+fn local_midnight(date: NaiveDate) -> Option<DateTime<Local>> {
+    Local
+        .from_local_datetime(&date.and_time(NaiveTime::MIN))
+        .earliest()
+}
+
+fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n' | 'N') => unescaped.push('\n'),
+                Some(c) => unescaped.push(c),
+                None => {}
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ICS: &str = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+SUMMARY:Team meeting\\, weekly\r
+DTSTART:20240115T090000\r
+DTEND:20240115T100000\r
+BEGIN:VALARM\r
+SUMMARY:Reminder\r
+END:VALARM\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+SUMMARY:Holiday with a very long name which is fo\r
+ lded\r
+DTSTART;VALUE=DATE:20240120\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+SUMMARY:Cancelled\r
+DTSTART:20240116T090000\r
+STATUS:CANCELLED\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    fn local(date: &str) -> DateTime<Local> {
+        let time = NaiveDateTime::parse_from_str(date, "%Y-%m-%d %H:%M").unwrap();
+        Local.from_local_datetime(&time).unwrap()
+    }
+
+    #[test]
+    fn test_parse_ics() {
+        let events = parse_ics(ICS);
+
+        assert_eq!(
+            events,
+            vec![
+                Event {
+                    summary: "Team meeting, weekly".to_string(),
+                    start: local("2024-01-15 09:00"),
+                    end: local("2024-01-15 10:00"),
+                    all_day: false,
+                },
+                Event {
+                    summary: "Holiday with a very long name which is folded".to_string(),
+                    start: local("2024-01-20 00:00"),
+                    end: local("2024-01-21 00:00"),
+                    all_day: true,
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_occurs_on() {
+        let events = parse_ics(ICS);
+        let date = |day| NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+
+        assert!(events[0].occurs_on(date(15)));
+        assert!(!events[0].occurs_on(date(16)));
+        assert!(events[1].occurs_on(date(20)));
+        assert!(!events[1].occurs_on(date(21)));
+    }
+
+    #[test]
+    #[cfg(feature = "http")]
+    fn test_calendar_data() {
+        let xml = r#"<d:multistatus xmlns:d="DAV:" xmlns:cal="urn:ietf:params:xml:ns:caldav">
+<d:response><d:propstat><d:prop>
+<cal:calendar-data>BEGIN:VCALENDAR&#13;
+SUMMARY:A &amp; B&#13;
+END:VCALENDAR</cal:calendar-data>
+</d:prop></d:propstat></d:response>
+<d:response><d:propstat><d:prop><cal:calendar-data/></d:prop></d:propstat></d:response>
+</d:multistatus>"#;
+
+        assert_eq!(
+            calendar_data(xml),
+            vec!["BEGIN:VCALENDAR\r\nSUMMARY:A & B\r\nEND:VCALENDAR".to_string()]
+        );
+    }
+}
@@ -0,0 +1,382 @@
+mod events;
+
+use std::cell::RefCell;
+use std::env;
+use std::rc::Rc;
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Local, Locale, NaiveDate};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Align, Button, Calendar, Label, Orientation};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::config::{CommonConfig, LayoutConfig};
+use crate::gtk_helpers::IronbarLabelExt;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::{module_impl, poll, spawn};
+pub use events::CalendarSource;
+use events::{Event, Loader};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct ClockModule {
+    /// The format string to use for the date/time shown on the bar.
+    /// Pango markup is supported.
+    ///
+    /// Detail on available tokens can be found here:
+    /// <https://docs.rs/chrono/latest/chrono/format/strftime/index.html>
+    ///
+    /// **Default**: `%d/%m/%Y %H:%M`
+    #[serde(skip)]
+    format: String,
+
+    /// The format string to use for the date/time shown on vertical bars,
+    /// in place of `format`.
+    /// Pango markup is supported.
+    ///
+    /// Tokens can be split across lines to fit a narrow bar,
+    /// for example `%H%n%M`.
+    ///
+    /// **Default**: `null`
+    format_vertical: Option<String>,
+
+    /// The format string to use for the date/time shown in the popup header.
+    /// Pango markup is supported.
+    ///
+    /// Detail on available tokens can be found here:
+    /// <https://docs.rs/chrono/latest/chrono/format/strftime/index.html>
+    ///
+    /// **Default**: `%H:%M:%S`
+    format_popup: String,
+
+    /// The locale to use when formatting dates.
+    ///
+    /// Note this will not control the calendar -
+    /// for that you must set `LC_TIME`.
+    ///
+    /// **Valid options**: See [here](https://docs.rs/pure-rust-locales/0.8.1/pure_rust_locales/enum.Locale.html#variants)
+    /// <br>
+    /// **Default**: `$LC_TIME` or `$LANG` or `'POSIX'`
+    locale: String,
+
+    /// The calendars to load events from.
+    /// Days with events are highlighted in the popup calendar,
+    /// and today's remaining events are listed below it.
+    ///
+    /// Each calendar is either a local `.ics` file
+    /// or a collection on a CalDAV server.
+    ///
+    /// **Default**: `[]`
+    calendars: Vec<CalendarSource>,
+
+    /// The number of seconds between each reload of the calendar events.
+    ///
+    /// **Default**: `900`
+    calendar_refresh_interval: u64,
+
+    /// The format string to use for event start times in the popup.
+    ///
+    /// Detail on available tokens can be found here:
+    /// <https://docs.rs/chrono/latest/chrono/format/strftime/index.html>
+    ///
+    /// **Default**: `%H:%M`
+    format_event: String,
+
+    /// The number of milliseconds between each refresh,
+    /// set from the common `interval` option.
+    #[serde(skip)]
+    interval: u64,
+
+    /// See [layout options](module-level-options#layout)
+    #[serde(flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for ClockModule {
+    fn default() -> Self {
+        ClockModule {
+            format: "%d/%m/%Y %H:%M".to_string(),
+            format_vertical: None,
+            format_popup: "%H:%M:%S".to_string(),
+            locale: default_locale(),
+            calendars: vec![],
+            calendar_refresh_interval: 900,
+            format_event: "%H:%M".to_string(),
+            interval: 500,
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+fn default_locale() -> String {
+    env::var("LC_TIME")
+        .or_else(|_| env::var("LANG"))
+        .map_or_else(|_| "POSIX".to_string(), strip_tail)
+}
+
+fn strip_tail(string: String) -> String {
+    string
+        .split_once('.')
+        .map(|(head, _)| head.to_string())
+        .unwrap_or(string)
+}
+
+#[derive(Debug, Clone)]
+pub enum ClockUpdate {
+    Time(DateTime<Local>),
+    /// Every event from all calendars, sorted by start time.
+    Events(Vec<Event>),
+}
+
+impl Module<Button> for ClockModule {
+    type SendMessage = ClockUpdate;
+    type ReceiveMessage = ();
+
+    module_impl!("clock");
+
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
+    fn coalesce_key(update: &Self::SendMessage) -> Option<String> {
+        // each update is the full state of its kind
+        match update {
+            ClockUpdate::Time(_) => Some("time".to_string()),
+            ClockUpdate::Events(_) => Some("events".to_string()),
+        }
+    }
+
+    fn set_interval(&mut self, interval: u64) {
+        self.interval = interval;
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let tx = context.tx.clone();
+        let interval = self.interval;
+
+        spawn(async move {
+            loop {
+                let date = Local::now();
+                tx.send_update(ClockUpdate::Time(date)).await;
+                poll::sleep(Duration::from_millis(interval)).await;
+            }
+        });
+
+        if !self.calendars.is_empty() {
+            let tx = context.tx.clone();
+            let calendars = self.calendars.clone();
+            let interval = Duration::from_secs(self.calendar_refresh_interval);
+
+            spawn(async move {
+                let loader = Loader::new(&calendars).await;
+
+                loop {
+                    let events = loader.load(&calendars).await;
+                    tx.send_update(ClockUpdate::Events(events)).await;
+                    poll::sleep(interval).await;
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        let label = Label::builder()
+            .use_markup(true)
+            .justify(self.layout.justify.into())
+            .build();
+
+        button.set_child(Some(&label.rotated(self.layout.angle())));
+
+        let tx = context.tx.clone();
+        button.connect_clicked(move |button| {
+            tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
+        });
+
+        let format = self.format.clone();
+        let locale = Locale::try_from(self.locale.as_str()).unwrap_or(Locale::POSIX);
+
+        let rx = context.subscribe();
+        rx.recv_glib((), move |(), update| {
+            if let ClockUpdate::Time(date) = update {
+                let date_string = format!("{}", date.format_localized(&format, locale));
+                label.set_label(&date_string);
+            }
+        });
+
+        let popup = self
+            .into_popup(context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_vertical_widget(
+        mut self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        if let Some(format) = self.format_vertical.take() {
+            self.format = format;
+        }
+
+        self.into_widget(context, info)
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let container = gtk::Box::new(Orientation::Vertical, 0);
+
+        let clock = Label::builder()
+            .halign(Align::Center)
+            .use_markup(true)
+            .build();
+        clock.add_css_class("calendar-clock");
+
+        container.append(&clock);
+
+        let calendar = Calendar::new();
+        calendar.add_css_class("calendar");
+        container.append(&calendar);
+
+        let events_container = gtk::Box::new(Orientation::Vertical, 0);
+        events_container.add_css_class("events");
+        events_container.set_visible(false);
+        container.append(&events_container);
+
+        let format = self.format_popup;
+        let format_event = self.format_event;
+        let locale = Locale::try_from(self.locale.as_str()).unwrap_or(Locale::POSIX);
+
+        let events = Rc::new(RefCell::new(Vec::<Event>::new()));
+
+        {
+            let calendar = calendar.clone();
+            let events = events.clone();
+            let events_container = events_container.clone();
+            let format_event = format_event.clone();
+
+            context
+                .subscribe()
+                .recv_glib((), move |(), update| match update {
+                    ClockUpdate::Time(date) => {
+                        let date_string = format!("{}", date.format_localized(&format, locale));
+                        clock.set_label(&date_string);
+                    }
+                    ClockUpdate::Events(new_events) => {
+                        *events.borrow_mut() = new_events;
+
+                        mark_event_days(&calendar, &events.borrow());
+                        list_upcoming_events(
+                            &events_container,
+                            &events.borrow(),
+                            &format_event,
+                            locale,
+                        );
+                    }
+                });
+        }
+
+        // marks only apply to the shown month, so must be reapplied on navigation
+        for property in ["month", "year"] {
+            let events = events.clone();
+            calendar.connect_notify_local(Some(property), move |calendar, _| {
+                mark_event_days(calendar, &events.borrow());
+            });
+        }
+
+        // Reset selected date on each popup open
+        context.popup.popover.connect_show(move |_| {
+            let date = glib::DateTime::now_local().expect("should get current time");
+            calendar.select_day(&date);
+
+            // events which have finished since the last open should be removed
+            list_upcoming_events(&events_container, &events.borrow(), &format_event, locale);
+        });
+
+        Some(container)
+    }
+}
+
+/// Marks each day of the calendar's shown month which has an event.
+fn mark_event_days(calendar: &Calendar, events: &[Event]) {
+    calendar.clear_marks();
+
+    let date = calendar.date();
+    let Ok(month) = u32::try_from(date.month()) else {
+        return;
+    };
+
+    let days = (1..=31).filter_map(|day| NaiveDate::from_ymd_opt(date.year(), month, day));
+
+    for day in days {
+        if events.iter().any(|event| event.occurs_on(day)) {
+            calendar.mark_day(day.day());
+        }
+    }
+}
+
+/// Replaces the contents of the container with today's events which have not yet finished.
+/// The container is hidden if there are none.
+fn list_upcoming_events(container: &gtk::Box, events: &[Event], format: &str, locale: Locale) {
+    while let Some(child) = container.first_child() {
+        container.remove(&child);
+    }
+
+    let now = Local::now();
+    let today = now.date_naive();
+
+    let upcoming = events
+        .iter()
+        .filter(|event| event.occurs_on(today) && event.end >= now);
+
+    for event in upcoming {
+        let row = gtk::Box::new(Orientation::Horizontal, 10);
+        row.add_css_class("event");
+
+        let time = if event.all_day {
+            "All day".to_string()
+        } else {
+            event.start.format_localized(format, locale).to_string()
+        };
+
+        let time = Label::new(Some(&time));
+        time.add_css_class("time");
+        row.append(&time);
+
+        let summary = Label::new(Some(&event.summary));
+        summary.add_css_class("summary");
+        summary.set_halign(Align::Start);
+        summary.set_wrap(true);
+        row.append(&summary);
+
+        container.append(&row);
+    }
+
+    container.set_visible(container.first_child().is_some());
+}
@@ -0,0 +1,358 @@
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::clients::containers::{self, Action, Container};
+use crate::config::{CommonConfig, LayoutConfig};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::script::Script;
+use crate::{module_impl, spawn};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation};
+use serde::Deserialize;
+use std::path::PathBuf;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tracing::error;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct ContainersModule {
+    /// The path to the Docker or Podman API socket.
+    /// If not set, `DOCKER_HOST` is used if it is a unix socket,
+    /// then `/var/run/docker.sock` if it exists,
+    /// then the Podman user socket.
+    ///
+    /// **Default**: `null`
+    socket: Option<PathBuf>,
+
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰡨 {running}/{total}`
+    #[serde(skip)]
+    format: String,
+
+    /// The names of containers which should always be running.
+    /// The `.warning` class is added to the widget while any of them are not.
+    ///
+    /// **Default**: `[]`
+    watch: Vec<String>,
+
+    /// A command to run to show a container's logs,
+    /// such as opening a terminal running `docker logs`.
+    /// `{id}` and `{name}` are replaced with the container's ID and name.
+    /// If not set, no logs button is shown.
+    ///
+    /// **Default**: `null`
+    logs_command: Option<String>,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(default, flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for ContainersModule {
+    fn default() -> Self {
+        Self {
+            socket: None,
+            format: "󰡨 {running}/{total}".to_string(),
+            watch: vec![],
+            logs_command: None,
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+impl ContainersModule {
+    /// Gets the names of watched containers which are not running.
+    fn down<'a>(&'a self, containers: &[Container]) -> Vec<&'a str> {
+        self.watch
+            .iter()
+            .filter(|name| {
+                !containers
+                    .iter()
+                    .any(|container| container.name() == name.as_str() && container.is_running())
+            })
+            .map(String::as_str)
+            .collect()
+    }
+
+    fn format(&self, containers: &[Container]) -> String {
+        let running = containers.iter().filter(|c| c.is_running()).count();
+
+        self.format
+            .replace("{running}", &running.to_string())
+            .replace("{stopped}", &(containers.len() - running).to_string())
+            .replace("{total}", &containers.len().to_string())
+            .replace("{down}", &self.down(containers).len().to_string())
+            .replace("{unhealthy}", &unhealthy(containers).to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UiEvent {
+    id: String,
+    name: String,
+    action: UiAction,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum UiAction {
+    Container(Action),
+    Logs,
+}
+
+/// Gets the number of containers whose health check is failing.
+fn unhealthy(containers: &[Container]) -> usize {
+    containers.iter().filter(|c| c.is_unhealthy()).count()
+}
+
+impl Module<Button> for ContainersModule {
+    type SendMessage = Vec<Container>;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("containers");
+
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
+    fn coalesce_key(_update: &Self::SendMessage) -> Option<String> {
+        // each update is the full state
+        Some(String::new())
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let socket = self
+            .socket
+            .clone()
+            .unwrap_or_else(containers::default_socket);
+
+        let client = context.ironbar.clients.borrow_mut().containers(socket);
+
+        {
+            let client = client.clone();
+            let tx = context.tx.clone();
+
+            spawn(async move {
+                let mut updates = client.subscribe();
+                tx.send_update(client.containers()).await;
+
+                loop {
+                    match updates.recv_tracked("containers").await {
+                        Ok(containers) => tx.send_update(containers).await,
+                        Err(RecvError::Lagged(_)) => tx.send_update(client.containers()).await,
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+
+        let logs_command = self.logs_command.clone();
+
+        spawn(async move {
+            while let Some(event) = rx.recv().await {
+                match event.action {
+                    UiAction::Container(action) => {
+                        // the engine sends an event once done, which refreshes the list
+                        if let Err(err) = client.perform(&event.id, action).await {
+                            error!("Failed to {action:?} container: {err:?}");
+                        }
+                    }
+                    UiAction::Logs => {
+                        if let Some(command) = &logs_command {
+                            let command = command
+                                .replace("{id}", &event.id)
+                                .replace("{name}", &event.name);
+
+                            Script::from(command.as_str()).run_as_oneshot(None);
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        button.add_css_class("btn");
+
+        let label = Label::builder().use_markup(true).build();
+        label.set_justify(self.layout.justify.into());
+        button.set_child(Some(&label));
+
+        let tx = context.tx.clone();
+        button.connect_clicked(move |button| {
+            tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
+        });
+
+        {
+            let module = self.clone();
+            context
+                .subscribe()
+                .recv_glib((&button, &label), move |(button, label), containers| {
+                    label.set_label_escaped(&module.format(&containers));
+
+                    if unhealthy(&containers) > 0 {
+                        button.add_css_class("unhealthy");
+                    } else {
+                        button.remove_css_class("unhealthy");
+                    }
+
+                    let down = module.down(&containers);
+                    if down.is_empty() {
+                        button.remove_css_class("warning");
+                        button.set_tooltip_text(None);
+                    } else {
+                        button.add_css_class("warning");
+                        button.set_tooltip_text(Some(&format!("Not running: {}", down.join(", "))));
+                    }
+                });
+        }
+
+        let popup = self
+            .into_popup(context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let empty = Label::new(Some("No containers"));
+        empty.add_css_class("empty");
+        container.append(&empty);
+
+        let list = gtk::Box::new(Orientation::Vertical, 5);
+        list.add_css_class("containers");
+        container.append(&list);
+
+        let tx = context.controller_tx.clone();
+        let watch = self.watch;
+        let show_logs = self.logs_command.is_some();
+
+        context
+            .subscribe()
+            .recv_glib(&list, move |list, containers| {
+                empty.set_visible(containers.is_empty());
+
+                for child in list.children() {
+                    list.remove(&child);
+                }
+
+                for container in containers {
+                    let watched = watch.iter().any(|name| name == container.name());
+                    list.append(&container_row(&container, watched, show_logs, &tx));
+                }
+            });
+
+        Some(container)
+    }
+}
+
+/// Creates a popup row for the container,
+/// with buttons to start, stop and restart it,
+/// and optionally to show its logs.
+fn container_row(
+    container: &Container,
+    watched: bool,
+    show_logs: bool,
+    tx: &mpsc::Sender<UiEvent>,
+) -> gtk::Box {
+    let row = gtk::Box::new(Orientation::Horizontal, 10);
+    row.add_css_class("container");
+    row.add_css_class(if container.is_running() {
+        "running"
+    } else {
+        "stopped"
+    });
+
+    if watched {
+        row.add_css_class("watched");
+    }
+
+    if container.is_unhealthy() {
+        row.add_css_class("unhealthy");
+    }
+
+    let details = gtk::Box::new(Orientation::Vertical, 0);
+    details.set_hexpand(true);
+    details.set_tooltip_text(Some(&container.image));
+    row.append(&details);
+
+    let name = Label::new(Some(container.name()));
+    name.add_css_class("name");
+    name.set_halign(Align::Start);
+    details.append(&name);
+
+    let status = Label::new(Some(&container.status));
+    status.add_css_class("status");
+    status.set_halign(Align::Start);
+    details.append(&status);
+
+    let mut actions = if container.is_running() {
+        vec![
+            ("󰓛", "btn-stop", UiAction::Container(Action::Stop)),
+            ("󰑓", "btn-restart", UiAction::Container(Action::Restart)),
+        ]
+    } else {
+        vec![("󰐊", "btn-start", UiAction::Container(Action::Start))]
+    };
+
+    if show_logs {
+        actions.push(("󰦪", "btn-logs", UiAction::Logs));
+    }
+
+    for (icon, class, action) in actions {
+        let button = Button::with_label(icon);
+        button.add_css_class(class);
+        button.set_valign(Align::Center);
+        row.append(&button);
+
+        let tx = tx.clone();
+        let id = container.id.clone();
+        let name = container.name().to_string();
+        button.connect_clicked(move |button| {
+            // logs can be opened more than once
+            if matches!(action, UiAction::Container(_)) {
+                button.set_sensitive(false);
+            }
+
+            tx.send_spawn(UiEvent {
+                id: id.clone(),
+                name: name.clone(),
+                action,
+            });
+        });
+    }
+
+    row
+}
@@ -67,7 +67,7 @@ impl CustomWidget for ButtonWidget {
             let label = Label::new(None);
             label.set_use_markup(true);
 
-            button.set_child(Some(&label));
+            button.set_child(Some(&label.rotated(self.layout.angle())));
 
             dynamic_string(&text, (), move |(), string| {
                 label.set_label_escaped(&string);
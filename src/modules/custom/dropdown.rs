@@ -0,0 +1,198 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gtk::prelude::*;
+use gtk::{DropDown, StringList, StringObject};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tracing::error;
+
+use super::{CustomWidget, CustomWidgetContext, ExecEvent};
+use crate::channels::{AsyncSenderExt, MpscReceiverExt};
+use crate::modules::custom::set_length;
+use crate::script::{OutputStream, Script, ScriptInput};
+use crate::{build, spawn};
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct DropdownWidget {
+    /// Widget name.
+    ///
+    /// **Default**: `null`
+    name: Option<String>,
+
+    /// Widget class name.
+    ///
+    /// **Default**: `null`
+    class: Option<String>,
+
+    /// The options to choose from.
+    ///
+    /// **Default**: `[]`
+    options: Vec<String>,
+
+    /// Script to run to get the options,
+    /// with one option per line of output.
+    /// Each run replaces the options, including any from `options`.
+    ///
+    /// **Default**: `null`
+    options_script: Option<ScriptInput>,
+
+    /// Script to run to get the selected option.
+    /// Output must match one of the options.
+    ///
+    /// **Default**: `null`
+    value: Option<ScriptInput>,
+
+    /// Command to execute when an option is chosen.
+    /// More on this [below](#commands).
+    ///
+    /// Note that this will provide the chosen option as an argument.
+    ///
+    /// **Default**: `null`
+    on_change: Option<String>,
+
+    /// The dropdown length.
+    /// GTK will automatically determine the size if left blank.
+    ///
+    /// **Default**: `null`
+    length: Option<i32>,
+}
+
+#[derive(Debug)]
+enum DropdownUpdate {
+    Options(Vec<String>),
+    Value(String),
+}
+
+/// Shared between the dropdown's callbacks.
+#[derive(Debug, Default)]
+struct DropdownState {
+    options: Vec<String>,
+    selected: Option<String>,
+    /// Whether the selection is being changed from a script,
+    /// so the change is not sent back to `on_change`.
+    updating: bool,
+}
+
+impl CustomWidget for DropdownWidget {
+    type Widget = DropDown;
+
+    fn into_widget(self, context: CustomWidgetContext) -> Self::Widget {
+        let dropdown = build!(self, Self::Widget);
+
+        if let Some(length) = self.length {
+            set_length(&dropdown, length, context.bar_orientation);
+        }
+
+        let state = Rc::new(RefCell::new(DropdownState::default()));
+        set_options(&dropdown, &state, self.options);
+
+        {
+            let state = state.clone();
+            let on_change = self.on_change;
+            let tx = context.tx.clone();
+
+            dropdown.connect_selected_notify(move |dropdown| {
+                let Some(value) = dropdown
+                    .selected_item()
+                    .and_downcast::<StringObject>()
+                    .map(|item| item.string().to_string())
+                else {
+                    return;
+                };
+
+                let mut state = state.borrow_mut();
+                if state.updating || state.selected.as_ref() == Some(&value) {
+                    return;
+                }
+
+                state.selected = Some(value.clone());
+
+                if let Some(on_change) = &on_change {
+                    tx.send_spawn(ExecEvent {
+                        cmd: on_change.clone(),
+                        args: Some(vec![value]),
+                        id: usize::MAX, // ignored
+                    });
+                }
+            });
+        }
+
+        let (tx, rx) = mpsc::channel(32);
+
+        if let Some(options_script) = self.options_script {
+            let script = Script::from(options_script);
+            let tx = tx.clone();
+
+            spawn(async move {
+                script
+                    .run(None, move |stream, _success| match stream {
+                        OutputStream::Stdout(out) => tx.send_spawn(DropdownUpdate::Options(
+                            out.lines().map(ToString::to_string).collect(),
+                        )),
+                        OutputStream::Stderr(err) => error!("{err:?}"),
+                    })
+                    .await;
+            });
+        }
+
+        if let Some(value) = self.value {
+            let script = Script::from(value);
+
+            spawn(async move {
+                script
+                    .run(None, move |stream, _success| match stream {
+                        OutputStream::Stdout(out) => tx.send_spawn(DropdownUpdate::Value(out)),
+                        OutputStream::Stderr(err) => error!("{err:?}"),
+                    })
+                    .await;
+            });
+        }
+
+        rx.recv_glib(&dropdown, move |dropdown, update| match update {
+            DropdownUpdate::Options(options) => set_options(dropdown, &state, options),
+            DropdownUpdate::Value(value) => {
+                state.borrow_mut().selected = Some(value);
+                select(dropdown, &state);
+            }
+        });
+
+        dropdown
+    }
+}
+
+/// Replaces the dropdown options,
+/// keeping the selected option if it is still available.
+fn set_options(dropdown: &DropDown, state: &Rc<RefCell<DropdownState>>, options: Vec<String>) {
+    if state.borrow().options == options {
+        return;
+    }
+
+    let model = StringList::new(&options.iter().map(String::as_str).collect::<Vec<_>>());
+    state.borrow_mut().options = options;
+
+    // GTK selects the first option when the model changes
+    state.borrow_mut().updating = true;
+    dropdown.set_model(Some(&model));
+    state.borrow_mut().updating = false;
+
+    select(dropdown, state);
+}
+
+/// Selects the option matching the current selection,
+/// or nothing if it is not an option.
+fn select(dropdown: &DropDown, state: &Rc<RefCell<DropdownState>>) {
+    let position = {
+        let state = state.borrow();
+        state
+            .selected
+            .as_ref()
+            .and_then(|selected| state.options.iter().position(|option| option == selected))
+    };
+
+    state.borrow_mut().updating = true;
+    dropdown.set_selected(position.map_or(gtk::INVALID_LIST_POSITION, |position| position as u32));
+    state.borrow_mut().updating = false;
+}
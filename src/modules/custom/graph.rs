@@ -0,0 +1,66 @@
+use gtk::DrawingArea;
+use gtk::prelude::*;
+use serde::Deserialize;
+use tracing::error;
+
+use super::{CustomWidget, CustomWidgetContext};
+use crate::config::GraphConfig;
+use crate::dynamic_value::dynamic_string;
+use crate::gtk_helpers::Graph;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct GraphWidget {
+    /// Widget name.
+    ///
+    /// **Default**: `null`
+    #[serde(default)]
+    name: Option<String>,
+
+    /// Widget class name.
+    ///
+    /// **Default**: `null`
+    #[serde(default)]
+    class: Option<String>,
+
+    /// The value to graph.
+    /// Each time this changes, its output is added as a new sample.
+    /// Output must be a number.
+    ///
+    /// This is a [Dynamic String](dynamic-values#dynamic-string).
+    ///
+    /// **Required**
+    value: String,
+
+    /// See [graph options](#graph).
+    #[serde(flatten)]
+    graph: GraphConfig,
+}
+
+impl CustomWidget for GraphWidget {
+    type Widget = DrawingArea;
+
+    fn into_widget(self, _context: CustomWidgetContext) -> Self::Widget {
+        let graph = Graph::new(&self.graph);
+        let widget = graph.widget().clone();
+
+        if let Some(name) = &self.name {
+            widget.set_widget_name(name);
+        }
+
+        if let Some(class) = &self.class {
+            for part in class.split(' ') {
+                widget.add_css_class(part);
+            }
+        }
+
+        dynamic_string(&self.value, (), move |(), value| {
+            match value.trim().parse::<f64>() {
+                Ok(value) => graph.push(value),
+                Err(err) => error!("Invalid graph value '{value}': {err:?}"),
+            }
+        });
+
+        widget
+    }
+}
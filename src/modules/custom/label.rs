@@ -1,5 +1,5 @@
-use gtk::Label;
 use gtk::prelude::*;
+use gtk::{Label, Widget};
 use serde::Deserialize;
 
 use super::{CustomWidget, CustomWidgetContext};
@@ -39,10 +39,10 @@ pub struct LabelWidget {
 }
 
 impl CustomWidget for LabelWidget {
-    type Widget = Label;
+    type Widget = Widget;
 
     fn into_widget(self, _context: CustomWidgetContext) -> Self::Widget {
-        let label = build!(self, Self::Widget);
+        let label = build!(self, Label);
 
         label.set_justify(self.layout.justify.into());
         label.set_use_markup(true);
@@ -55,6 +55,6 @@ impl CustomWidget for LabelWidget {
             label.set_label_escaped(&string);
         });
 
-        label
+        label.rotated(self.layout.angle())
     }
 }
@@ -1,21 +1,31 @@
 mod r#box;
 mod button;
+mod dropdown;
+mod graph;
 mod image;
 mod label;
 mod progress;
 mod slider;
+mod spinner;
+mod switch;
+mod text;
 
 use self::r#box::BoxWidget;
+use self::dropdown::DropdownWidget;
+use self::graph::GraphWidget;
 use self::image::ImageWidget;
 use self::label::LabelWidget;
 use self::slider::SliderWidget;
+use self::spinner::SpinnerWidget;
+use self::switch::SwitchWidget;
+use self::text::TextWidget;
 use crate::channels::AsyncSenderExt;
 use crate::config::{CommonConfig, ModuleConfig};
 use crate::modules::custom::button::ButtonWidget;
 use crate::modules::custom::progress::ProgressWidget;
 use crate::modules::{
-    AnyModuleFactory, BarModuleFactory, Module, ModuleInfo, ModuleParts, ModulePopup,
-    ModuleUpdateEvent, PopupButton, PopupModuleFactory, WidgetContext, add_events,
+    AnyModuleFactory, BarModuleFactory, Module, ModuleFactory, ModuleInfo, ModuleParts,
+    ModulePopup, ModuleUpdateEvent, PopupButton, PopupModuleFactory, WidgetContext, add_events,
 };
 use crate::script::Script;
 use crate::{module_impl, spawn};
@@ -85,6 +95,17 @@ pub enum Widget {
     Slider(SliderWidget),
     /// A progress bar.
     Progress(ProgressWidget),
+    /// A spinner or pulsing progress bar to show that something is loading.
+    Spinner(SpinnerWidget),
+    /// A graph of a value's recent history.
+    Graph(GraphWidget),
+    /// A dropdown to choose between options,
+    /// which can run a command with the chosen option.
+    Dropdown(DropdownWidget),
+    /// A toggle switch, which can be bound to an ironvar.
+    Switch(SwitchWidget),
+    /// A scrollable multi-line text area, such as for log output.
+    Text(TextWidget),
 }
 
 #[derive(Clone)]
@@ -160,6 +181,8 @@ impl Widget {
                     &$widget.into_widget(context.clone()),
                     common,
                     context.bar_orientation,
+                    context.module_factory.ironbar(),
+                    context.info.output_name,
                 )
             };
         }
@@ -171,6 +194,11 @@ impl Widget {
             Self::Image(widget) => create!(widget),
             Self::Slider(widget) => create!(widget),
             Self::Progress(widget) => create!(widget),
+            Self::Spinner(widget) => create!(widget),
+            Self::Graph(widget) => create!(widget),
+            Self::Dropdown(widget) => create!(widget),
+            Self::Switch(widget) => create!(widget),
+            Self::Text(widget) => create!(widget),
         };
 
         parent.append(&event_box);
@@ -243,7 +271,7 @@ impl Module<gtk::Box> for CustomModule {
                 context.popup.clone(),
             )
             .into(),
-            image_provider: context.ironbar.image_provider(),
+            image_provider: context.image_provider(),
         };
 
         self.bar.clone().into_iter().for_each(|widget| {
@@ -287,7 +315,7 @@ impl Module<gtk::Box> for CustomModule {
                 tx: &context.controller_tx,
                 bar_orientation: Orientation::Horizontal,
                 popup_buttons: Rc::new(RefCell::new(vec![])),
-                image_provider: context.ironbar.image_provider(),
+                image_provider: context.image_provider(),
                 module_factory: PopupModuleFactory::new(
                     context.ironbar,
                     context.bar,
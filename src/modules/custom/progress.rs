@@ -1,10 +1,11 @@
-use gtk::ProgressBar;
 use gtk::prelude::*;
+use gtk::{Orientation, ProgressBar};
 use serde::Deserialize;
 use tokio::sync::mpsc;
 use tracing::error;
 
 use super::{CustomWidget, CustomWidgetContext};
+use crate::animation::{self, AnimatedValue};
 use crate::channels::{AsyncSenderExt, MpscReceiverExt};
 use crate::config::ModuleOrientation;
 use crate::dynamic_value::dynamic_string;
@@ -28,11 +29,13 @@ pub struct ProgressWidget {
 
     /// Orientation of the progress bar.
     ///
+    /// If unset, this follows the bar,
+    /// or is horizontal inside popups.
+    ///
     /// **Valid options**: `horizontal`, `vertical`, `h`, `v`
     /// <br />
-    /// **Default**: `horizontal`
-    #[serde(default)]
-    orientation: ModuleOrientation,
+    /// **Default**: `null`
+    orientation: Option<ModuleOrientation>,
 
     /// Text label to show for the progress bar.
     ///
@@ -66,7 +69,7 @@ impl Default for ProgressWidget {
         Self {
             name: None,
             class: None,
-            orientation: ModuleOrientation::default(),
+            orientation: None,
             label: None,
             value: None,
             max: 100.0,
@@ -81,7 +84,16 @@ impl CustomWidget for ProgressWidget {
     fn into_widget(self, context: CustomWidgetContext) -> Self::Widget {
         let progress = build!(self, Self::Widget);
 
-        progress.set_orientation(self.orientation.into());
+        let orientation = self
+            .orientation
+            .map_or(context.bar_orientation, ModuleOrientation::into);
+
+        progress.set_orientation(orientation);
+
+        // fill from the bottom up
+        if orientation == Orientation::Vertical {
+            progress.set_inverted(true);
+        }
 
         if let Some(length) = self.length {
             set_length(&progress, length, context.bar_orientation);
@@ -105,7 +117,18 @@ impl CustomWidget for ProgressWidget {
                     .await;
             });
 
-            rx.recv_glib((), move |(), value| progress.set_fraction(value / self.max));
+            let fraction = {
+                let get = progress.clone();
+                let set = progress.clone();
+                AnimatedValue::new(
+                    &progress,
+                    animation::VALUE_DURATION,
+                    move || get.fraction(),
+                    move |fraction| set.set_fraction(fraction),
+                )
+            };
+
+            rx.recv_glib((), move |(), value| fraction.set(value / self.max));
         }
 
         if let Some(text) = self.label {
@@ -3,7 +3,7 @@ use std::cell::Cell;
 use std::ops::Neg;
 
 use gtk::prelude::*;
-use gtk::{EventControllerScroll, EventControllerScrollFlags, Scale};
+use gtk::{EventControllerScroll, EventControllerScrollFlags, Orientation, Scale};
 use serde::Deserialize;
 use tokio::sync::mpsc;
 use tracing::error;
@@ -31,11 +31,13 @@ pub struct SliderWidget {
 
     /// Orientation of the slider.
     ///
+    /// If unset, this follows the bar,
+    /// or is horizontal inside popups.
+    ///
     /// **Valid options**: `horizontal`, `vertical`, `h`, `v`
     /// <br />
-    /// **Default**: `horizontal`
-    #[serde(default)]
-    orientation: ModuleOrientation,
+    /// **Default**: `null`
+    orientation: Option<ModuleOrientation>,
 
     /// Script to run to get the slider value.
     /// Output must be a valid number.
@@ -86,7 +88,7 @@ impl Default for SliderWidget {
         Self {
             name: None,
             class: None,
-            orientation: ModuleOrientation::default(),
+            orientation: None,
             value: None,
             on_change: None,
             min: 0.0,
@@ -104,7 +106,16 @@ impl CustomWidget for SliderWidget {
     fn into_widget(self, context: CustomWidgetContext) -> Self::Widget {
         let scale = build!(self, Self::Widget);
 
-        scale.set_orientation(self.orientation.into());
+        let orientation = self
+            .orientation
+            .map_or(context.bar_orientation, ModuleOrientation::into);
+
+        scale.set_orientation(orientation);
+
+        // fill from the bottom up
+        if orientation == Orientation::Vertical {
+            scale.set_inverted(true);
+        }
 
         if let Some(length) = self.length {
             set_length(&scale, length, context.bar_orientation);
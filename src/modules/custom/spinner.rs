@@ -0,0 +1,64 @@
+use gtk::Widget;
+use gtk::prelude::*;
+use serde::Deserialize;
+
+use super::{CustomWidget, CustomWidgetContext};
+use crate::config::LoadingIndicatorType;
+use crate::dynamic_value::DynamicBool;
+use crate::gtk_helpers::LoadingIndicator;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct SpinnerWidget {
+    /// Widget name.
+    ///
+    /// **Default**: `null`
+    name: Option<String>,
+
+    /// Widget class name.
+    ///
+    /// **Default**: `null`
+    class: Option<String>,
+
+    /// The type of loading indicator to show.
+    ///
+    /// **Valid options**: `spinner`, `progress`
+    /// <br />
+    /// **Default**: `spinner`
+    #[serde(default)]
+    indicator: LoadingIndicatorType,
+
+    /// Shows the indicator only while this evaluates to true.
+    /// If unset, the indicator is always shown.
+    ///
+    /// This is a [Dynamic Boolean](dynamic-values#dynamic-boolean).
+    ///
+    /// **Default**: `null`
+    active: Option<DynamicBool>,
+}
+
+impl CustomWidget for SpinnerWidget {
+    type Widget = Widget;
+
+    fn into_widget(self, _context: CustomWidgetContext) -> Self::Widget {
+        let indicator = LoadingIndicator::new(self.indicator);
+        let widget = indicator.widget().clone();
+
+        if let Some(name) = &self.name {
+            widget.set_widget_name(name);
+        }
+
+        if let Some(class) = &self.class {
+            for part in class.split(' ') {
+                widget.add_css_class(part);
+            }
+        }
+
+        match self.active {
+            Some(active) => active.subscribe((), move |(), active| indicator.set_active(active)),
+            None => indicator.set_active(true),
+        }
+
+        widget
+    }
+}
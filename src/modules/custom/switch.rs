@@ -0,0 +1,120 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use glib::Propagation;
+use gtk::Switch;
+use gtk::prelude::*;
+use serde::Deserialize;
+
+use super::{CustomWidget, CustomWidgetContext, ExecEvent};
+use crate::build;
+use crate::channels::AsyncSenderExt;
+use crate::dynamic_value::DynamicBool;
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct SwitchWidget {
+    /// Widget name.
+    ///
+    /// **Default**: `null`
+    name: Option<String>,
+
+    /// Widget class name.
+    ///
+    /// **Default**: `null`
+    class: Option<String>,
+
+    /// The name of an [ironvar](ironvars) to bind the switch to.
+    ///
+    /// The switch is on while the variable is truthy,
+    /// and toggling it sets the variable to `true` or `false`.
+    ///
+    /// **Default**: `null`
+    #[cfg(feature = "ipc")]
+    variable: Option<String>,
+
+    /// Script or variable to get whether the switch is on.
+    /// Ignored if `variable` is set.
+    ///
+    /// This is a [Dynamic Boolean](dynamic-values#dynamic-boolean).
+    ///
+    /// **Default**: `null`
+    active: Option<DynamicBool>,
+
+    /// Command to execute when the switch is toggled.
+    /// More on this [below](#commands).
+    ///
+    /// Note that this will provide `true` or `false` as an argument.
+    ///
+    /// **Default**: `null`
+    on_change: Option<String>,
+}
+
+impl CustomWidget for SwitchWidget {
+    type Widget = Switch;
+
+    fn into_widget(self, context: CustomWidgetContext) -> Self::Widget {
+        let switch = build!(self, Self::Widget);
+
+        // whether the state is being set from the variable or script,
+        // so the change is not sent back to it
+        let updating = Rc::new(Cell::new(false));
+
+        {
+            let updating = updating.clone();
+            let on_change = self.on_change;
+            #[cfg(feature = "ipc")]
+            let variable = self.variable.clone();
+            let tx = context.tx.clone();
+
+            switch.connect_state_set(move |_, active| {
+                if updating.get() {
+                    return Propagation::Proceed;
+                }
+
+                #[cfg(feature = "ipc")]
+                if let Some(variable) = &variable {
+                    use crate::ironvar::WritableNamespace;
+                    use tracing::error;
+
+                    if let Err(err) =
+                        crate::Ironbar::variable_manager().set(variable, active.to_string())
+                    {
+                        error!("Failed to set ironvar '{variable}': {err:?}");
+                    }
+                }
+
+                if let Some(on_change) = &on_change {
+                    tx.send_spawn(ExecEvent {
+                        cmd: on_change.clone(),
+                        args: Some(vec![active.to_string()]),
+                        id: usize::MAX, // ignored
+                    });
+                }
+
+                Propagation::Proceed
+            });
+        }
+
+        #[cfg(feature = "ipc")]
+        let active = self
+            .variable
+            .map(|variable| DynamicBool::Unknown(format!("#{variable}")))
+            .or(self.active);
+        #[cfg(not(feature = "ipc"))]
+        let active = self.active;
+
+        if let Some(active) = active {
+            active.subscribe(&switch, move |switch, active| {
+                if switch.is_active() != active {
+                    updating.set(true);
+                    switch.set_active(active);
+                    updating.set(false);
+                }
+            });
+        }
+
+        switch
+    }
+}
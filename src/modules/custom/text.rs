@@ -0,0 +1,155 @@
+use gtk::prelude::*;
+use gtk::{PolicyType, ScrolledWindow, TextView, WrapMode};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tracing::error;
+
+use super::{CustomWidget, CustomWidgetContext};
+use crate::channels::{AsyncSenderExt, MpscReceiverExt};
+use crate::script::{OutputStream, Script, ScriptInput, ScriptMode};
+use crate::{build, spawn};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct TextWidget {
+    /// Widget name.
+    ///
+    /// **Default**: `null`
+    name: Option<String>,
+
+    /// Widget class name.
+    ///
+    /// **Default**: `null`
+    class: Option<String>,
+
+    /// Script to run to get the text.
+    ///
+    /// In polling mode, each run replaces the text.
+    /// In watch mode, each line of output is appended.
+    ///
+    /// **Default**: `null`
+    text: Option<ScriptInput>,
+
+    /// The maximum number of lines to keep,
+    /// removing the oldest first.
+    ///
+    /// **Default**: `500`
+    max_lines: usize,
+
+    /// Whether to scroll to the end when text is added.
+    ///
+    /// **Default**: `true`
+    follow: bool,
+
+    /// Whether to wrap long lines.
+    /// Lines scroll horizontally otherwise.
+    ///
+    /// **Default**: `true`
+    wrap: bool,
+
+    /// The width of the text area, in pixels.
+    /// GTK will automatically determine the size if left blank.
+    ///
+    /// **Default**: `null`
+    width: Option<i32>,
+
+    /// The height of the text area, in pixels.
+    /// Text scrolls vertically beyond this.
+    ///
+    /// **Default**: `200`
+    height: i32,
+}
+
+impl Default for TextWidget {
+    fn default() -> Self {
+        Self {
+            name: None,
+            class: None,
+            text: None,
+            max_lines: 500,
+            follow: true,
+            wrap: true,
+            width: None,
+            height: 200,
+        }
+    }
+}
+
+impl CustomWidget for TextWidget {
+    type Widget = ScrolledWindow;
+
+    fn into_widget(self, _context: CustomWidgetContext) -> Self::Widget {
+        let scrolled = build!(self, Self::Widget);
+
+        scrolled.set_min_content_height(self.height);
+        scrolled.set_max_content_height(self.height);
+
+        if let Some(width) = self.width {
+            scrolled.set_min_content_width(width);
+        }
+
+        let text_view = TextView::builder()
+            .editable(false)
+            .cursor_visible(false)
+            .monospace(true)
+            .build();
+
+        if self.wrap {
+            text_view.set_wrap_mode(WrapMode::WordChar);
+            scrolled.set_policy(PolicyType::Never, PolicyType::Automatic);
+        }
+
+        scrolled.set_child(Some(&text_view));
+
+        let Some(text) = self.text else {
+            return scrolled;
+        };
+
+        let script = Script::from(text);
+        let replace = script.mode == ScriptMode::Poll;
+
+        let (tx, rx) = mpsc::channel(128);
+
+        spawn(async move {
+            script
+                .run(None, move |stream, _success| match stream {
+                    OutputStream::Stdout(out) => tx.send_spawn(out),
+                    OutputStream::Stderr(err) => error!("{err:?}"),
+                })
+                .await;
+        });
+
+        let buffer = text_view.buffer();
+        // stays after text inserted at the end
+        let end_mark = buffer.create_mark(None, &buffer.end_iter(), false);
+
+        let max_lines = i32::try_from(self.max_lines).unwrap_or(i32::MAX);
+        let follow = self.follow;
+
+        rx.recv_glib(&text_view, move |text_view, text| {
+            if replace {
+                buffer.set_text(&text);
+            } else {
+                let mut end = buffer.end_iter();
+                if buffer.char_count() > 0 {
+                    buffer.insert(&mut end, "\n");
+                }
+                buffer.insert(&mut end, &text);
+            }
+
+            let excess = buffer.line_count() - max_lines;
+            if excess > 0
+                && let Some(mut excess_end) = buffer.iter_at_line(excess)
+            {
+                buffer.delete(&mut buffer.start_iter(), &mut excess_end);
+            }
+
+            if follow {
+                text_view.scroll_mark_onscreen(&end_mark);
+            }
+        });
+
+        scrolled
+    }
+}
@@ -0,0 +1,529 @@
+mod parser;
+
+use crate::channels::AsyncSenderExt;
+use crate::config::{CommonConfig, LayoutConfig, MarqueeMode, TruncateMode};
+use crate::gtk_helpers::OverflowLabel;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::{module_impl, poll, spawn};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation, glib};
+use serde::Deserialize;
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{error, warn};
+
+/// The maximum number of read item ids to remember.
+/// The oldest are forgotten first.
+const MAX_READ: usize = 1000;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct FeedModule {
+    /// The RSS or Atom feeds to poll.
+    ///
+    /// **Default**: `[]`
+    feeds: Vec<FeedConfig>,
+
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{icon} {title}`
+    #[serde(skip)]
+    format: String,
+
+    /// The format string to use for the widget button label
+    /// when there are no items to show.
+    ///
+    /// **Default**: `{icon}`
+    format_empty: String,
+
+    /// The icon to use for the `{icon}` token.
+    ///
+    /// **Default**: `󰑫`
+    icon: String,
+
+    /// Time in milliseconds to show each headline for
+    /// before moving on to the next.
+    ///
+    /// **Default**: `10000`
+    cycle_interval: u64,
+
+    /// Whether to only cycle through unread items.
+    ///
+    /// **Default**: `false`
+    unread_only: bool,
+
+    /// The maximum number of items to show in the popup and cycle through,
+    /// newest first.
+    ///
+    /// **Default**: `20`
+    max_items: usize,
+
+    /// Time in milliseconds between refreshes of feeds
+    /// which do not set their own `interval`,
+    /// from the common `interval` option.
+    #[serde(skip)]
+    default_interval: Option<u64>,
+
+    // -- Common --
+    /// See [truncate options](module-level-options#truncate-mode).
+    ///
+    /// **Default**: `null`
+    truncate: Option<TruncateMode>,
+
+    /// See [marquee options](module-level-options#marquee-mode).
+    marquee: MarqueeMode,
+
+    /// See [layout options](module-level-options#layout)
+    #[serde(flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for FeedModule {
+    fn default() -> Self {
+        Self {
+            feeds: vec![],
+            format: "{icon} {title}".to_string(),
+            format_empty: "{icon}".to_string(),
+            icon: "󰑫".to_string(),
+            cycle_interval: 10_000,
+            unread_only: false,
+            max_items: 20,
+            default_interval: None,
+            truncate: None,
+            marquee: MarqueeMode::default(),
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct FeedConfig {
+    /// The URL of the RSS or Atom feed.
+    url: String,
+
+    /// The name to show for the feed.
+    /// Defaults to the title set by the feed.
+    ///
+    /// **Default**: `null`
+    #[serde(default)]
+    name: Option<String>,
+
+    /// Time in milliseconds between refreshes of the feed.
+    /// Defaults to the module's `interval`, or 15 minutes if that is not set.
+    ///
+    /// **Default**: `null`
+    #[serde(default)]
+    interval: Option<u64>,
+}
+
+/// The default time in milliseconds between refreshes of a feed.
+const DEFAULT_INTERVAL: u64 = 900_000;
+
+/// A feed item, as shown on the bar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    id: String,
+    feed: String,
+    title: String,
+    link: String,
+    read: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum UiEvent {
+    /// Marks the item with the given id as read.
+    MarkRead(String),
+    MarkAllRead,
+}
+
+/// Ids of items which have been read,
+/// persisted to the data directory across restarts.
+#[derive(Debug, Default)]
+struct ReadStore {
+    path: Option<PathBuf>,
+    /// Oldest first.
+    ids: Vec<String>,
+}
+
+impl ReadStore {
+    async fn load() -> Self {
+        let path = dirs::data_dir().map(|dir| dir.join("ironbar").join("feed_read"));
+
+        let ids = match &path {
+            Some(path) => tokio::fs::read_to_string(path)
+                .await
+                .map(|contents| contents.lines().map(ToString::to_string).collect())
+                .unwrap_or_default(),
+            None => {
+                warn!("Missing XDG data dir, read items will not be saved");
+                vec![]
+            }
+        };
+
+        Self { path, ids }
+    }
+
+    fn contains(&self, id: &str) -> bool {
+        self.ids.iter().any(|read| read == id)
+    }
+
+    fn insert(&mut self, id: String) {
+        if !self.contains(&id) {
+            self.ids.push(id);
+        }
+
+        if self.ids.len() > MAX_READ {
+            self.ids.drain(..self.ids.len() - MAX_READ);
+        }
+    }
+
+    async fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let write = async {
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+
+            tokio::fs::write(path, self.ids.join("\n")).await
+        };
+
+        if let Err(err) = write.await {
+            error!("Failed to save read feed items: {err:?}");
+        }
+    }
+}
+
+async fn fetch(http: &reqwest::Client, url: &str) -> Result<parser::Feed> {
+    let body = http
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    parser::parse(&body)
+}
+
+/// Combines the items from each loaded feed,
+/// newest first.
+fn items(
+    config: &[FeedConfig],
+    feeds: &[Option<parser::Feed>],
+    read: &ReadStore,
+    max_items: usize,
+) -> Vec<Item> {
+    let mut entries = config
+        .iter()
+        .zip(feeds)
+        .filter_map(|(config, feed)| Some((config, feed.as_ref()?)))
+        .flat_map(|(config, feed)| {
+            let name = config
+                .name
+                .as_deref()
+                .or(feed.title.as_deref())
+                .unwrap_or(&config.url);
+
+            feed.entries.iter().map(move |entry| (name, entry))
+        })
+        .collect::<Vec<_>>();
+
+    // entries without a date keep their feed order, after those with one
+    entries.sort_by(|(_, a), (_, b)| b.date.cmp(&a.date));
+
+    entries
+        .into_iter()
+        .take(max_items)
+        .map(|(feed, entry)| Item {
+            id: entry.id.clone(),
+            feed: feed.to_string(),
+            title: entry.title.clone(),
+            link: entry.link.clone(),
+            read: read.contains(&entry.id),
+        })
+        .collect()
+}
+
+/// Opens the link in the default browser.
+fn open_link(link: &str) {
+    if let Err(err) =
+        gtk::gio::AppInfo::launch_default_for_uri(link, None::<&gtk::gio::AppLaunchContext>)
+    {
+        error!("Failed to open '{link}': {err:?}");
+    }
+}
+
+impl Module<Button> for FeedModule {
+    type SendMessage = Vec<Item>;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("feed");
+
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
+    fn coalesce_key(_update: &Self::SendMessage) -> Option<String> {
+        // each update is the full state
+        Some(String::new())
+    }
+
+    fn set_interval(&mut self, interval: u64) {
+        self.default_interval = Some(interval);
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let http = reqwest::Client::new();
+
+        // each feed sends its latest contents here,
+        // and the combined items are sent in response
+        let (fetched_tx, mut fetched_rx) = mpsc::channel(8);
+
+        for (index, feed) in self.feeds.iter().enumerate() {
+            let http = http.clone();
+            let url = feed.url.clone();
+            let interval = Duration::from_millis(
+                feed.interval
+                    .or(self.default_interval)
+                    .unwrap_or(DEFAULT_INTERVAL),
+            );
+            let fetched_tx = fetched_tx.clone();
+
+            spawn(async move {
+                loop {
+                    match fetch(&http, &url).await {
+                        Ok(feed) => fetched_tx.send_expect((index, feed)).await,
+                        Err(err) => error!("Failed to fetch feed '{url}': {err:?}"),
+                    }
+
+                    poll::sleep(interval).await;
+                }
+            });
+        }
+
+        let config = self.feeds.clone();
+        let max_items = self.max_items;
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            let mut read = ReadStore::load().await;
+            let mut feeds = vec![None; config.len()];
+
+            loop {
+                tokio::select! {
+                    Some((index, feed)) = fetched_rx.recv() => feeds[index] = Some(feed),
+                    Some(event) = rx.recv() => {
+                        match event {
+                            UiEvent::MarkRead(id) => read.insert(id),
+                            UiEvent::MarkAllRead => {
+                                for item in items(&config, &feeds, &read, max_items) {
+                                    read.insert(item.id);
+                                }
+                            }
+                        }
+
+                        read.save().await;
+                    }
+                    else => break,
+                }
+
+                tx.send_update(items(&config, &feeds, &read, max_items))
+                    .await;
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let label = Label::builder()
+            .use_markup(true)
+            .justify(self.layout.justify.into())
+            .build();
+
+        let label = OverflowLabel::new(label, self.truncate, self.marquee.clone());
+
+        let button = Button::new();
+        button.set_child(Some(label.widget()));
+        button.set_accessible_label("Feeds");
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        let items = Rc::new(RefCell::new(Vec::<Item>::new()));
+        let current = Rc::new(Cell::new(0));
+
+        let render = {
+            let button = button.clone();
+            let items = items.clone();
+            let current = current.clone();
+            let format = self.format.clone();
+            let format_empty = self.format_empty.clone();
+            let icon = self.icon.clone();
+            let unread_only = self.unread_only;
+
+            move || {
+                let items = items.borrow();
+
+                let unread = items.iter().filter(|item| !item.read).count();
+
+                if unread > 0 {
+                    button.add_css_class("unread");
+                } else {
+                    button.remove_css_class("unread");
+                }
+
+                let cycled = items
+                    .iter()
+                    .filter(|item| !unread_only || !item.read)
+                    .collect::<Vec<_>>();
+
+                let text = if cycled.is_empty() {
+                    format_empty.clone()
+                } else {
+                    let item = cycled[current.get() % cycled.len()];
+
+                    format
+                        .replace("{title}", &item.title)
+                        .replace("{feed}", &item.feed)
+                };
+
+                label.set_label_escaped(
+                    &text
+                        .replace("{icon}", &icon)
+                        .replace("{unread}", &unread.to_string()),
+                );
+
+                button.set_accessible_description(&format!("{unread} unread"));
+            }
+        };
+
+        {
+            let render = render.clone();
+            let current = current.clone();
+
+            glib::timeout_add_local(Duration::from_millis(self.cycle_interval), move || {
+                current.set(current.get().wrapping_add(1));
+                render();
+                glib::ControlFlow::Continue
+            });
+        }
+
+        context.subscribe().recv_glib((), move |(), new_items| {
+            items.replace(new_items);
+            render();
+        });
+
+        let popup = self
+            .into_popup(context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::new(Orientation::Vertical, 10);
+
+        let header = gtk::Box::new(Orientation::Horizontal, 10);
+        header.add_css_class("header");
+        container.append(&header);
+
+        let title = Label::new(Some("Feeds"));
+        title.add_css_class("title");
+        title.set_halign(Align::Start);
+        title.set_hexpand(true);
+        header.append(&title);
+
+        let mark_all = Button::with_label("Mark all read");
+        mark_all.add_css_class("btn-mark-read");
+        header.append(&mark_all);
+
+        {
+            let tx = context.controller_tx.clone();
+            mark_all.connect_clicked(move |_| tx.send_spawn(UiEvent::MarkAllRead));
+        }
+
+        let list = gtk::Box::new(Orientation::Vertical, 5);
+        list.add_css_class("items");
+        container.append(&list);
+
+        let controller_tx = context.controller_tx.clone();
+
+        context.subscribe().recv_glib((), move |(), items| {
+            while let Some(child) = list.first_child() {
+                list.remove(&child);
+            }
+
+            for item in items {
+                let button = Button::new();
+                button.add_css_class("item");
+                if !item.read {
+                    button.add_css_class("unread");
+                }
+
+                let details = gtk::Box::new(Orientation::Vertical, 0);
+                button.set_child(Some(&details));
+
+                let title = Label::new(Some(&item.title));
+                title.add_css_class("title");
+                title.set_halign(Align::Start);
+                title.set_wrap(true);
+                details.append(&title);
+
+                let feed = Label::new(Some(&item.feed));
+                feed.add_css_class("feed");
+                feed.set_halign(Align::Start);
+                details.append(&feed);
+
+                let tx = controller_tx.clone();
+                button.connect_clicked(move |_| {
+                    if !item.link.is_empty() {
+                        open_link(&item.link);
+                    }
+
+                    tx.send_spawn(UiEvent::MarkRead(item.id.clone()));
+                });
+
+                list.append(&button);
+            }
+        });
+
+        Some(container)
+    }
+}
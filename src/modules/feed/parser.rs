@@ -0,0 +1,191 @@
+use chrono::{DateTime, Utc};
+use color_eyre::Result;
+use quick_xml::Reader;
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::{BytesStart, Event};
+
+/// A feed's title and items, in document order.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Feed {
+    pub title: Option<String>,
+    pub entries: Vec<Entry>,
+}
+
+/// A single RSS item or Atom entry.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The `guid` or `id` of the entry,
+    /// falling back to its link where not set.
+    pub id: String,
+    pub title: String,
+    pub link: String,
+    pub date: Option<DateTime<Utc>>,
+}
+
+/// Parses an RSS 2.0, RSS 1.0 (RDF) or Atom document.
+///
+/// Elements are matched on their local name only,
+/// so namespaced elements such as `dc:date` are also picked up.
+pub fn parse(xml: &str) -> Result<Feed> {
+    let mut reader = Reader::from_str(xml);
+
+    let mut feed = Feed::default();
+    let mut entry = None::<Entry>;
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(tag) => {
+                match tag.local_name().as_ref() {
+                    b"item" | b"entry" => entry = Some(Entry::default()),
+                    b"link" => set_atom_link(entry.as_mut(), &tag)?,
+                    _ => {}
+                }
+
+                text.clear();
+            }
+            Event::Empty(tag) if tag.local_name().as_ref() == b"link" => {
+                set_atom_link(entry.as_mut(), &tag)?;
+            }
+            Event::Text(content) => text.push_str(&content.xml_content()?),
+            Event::CData(content) => text.push_str(&content.decode()?),
+            Event::GeneralRef(reference) => {
+                if let Some(char) = reference.resolve_char_ref()? {
+                    text.push(char);
+                } else if let Some(value) = resolve_predefined_entity(&reference.decode()?) {
+                    text.push_str(value);
+                }
+            }
+            Event::End(tag) => {
+                let value = text.trim();
+
+                match (tag.local_name().as_ref(), entry.as_mut()) {
+                    (b"item" | b"entry", Some(_)) => {
+                        if let Some(mut entry) = entry.take() {
+                            if entry.id.is_empty() {
+                                entry.id.clone_from(&entry.link);
+                            }
+                            feed.entries.push(entry);
+                        }
+                    }
+                    (b"title", None) if feed.title.is_none() => {
+                        feed.title = Some(value.to_string());
+                    }
+                    (b"title", Some(entry)) => entry.title = value.to_string(),
+                    // atom links are set from their `href` attribute
+                    (b"link", Some(entry)) if entry.link.is_empty() => {
+                        entry.link = value.to_string();
+                    }
+                    (b"guid" | b"id", Some(entry)) => entry.id = value.to_string(),
+                    (b"pubDate" | b"published" | b"updated" | b"date", Some(entry))
+                        if entry.date.is_none() =>
+                    {
+                        entry.date = parse_date(value);
+                    }
+                    _ => {}
+                }
+
+                text.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(feed)
+}
+
+/// Sets the entry link from an Atom `link` element,
+/// if it points to the entry's page.
+fn set_atom_link(entry: Option<&mut Entry>, tag: &BytesStart) -> Result<()> {
+    let Some(entry) = entry else {
+        return Ok(());
+    };
+
+    let alternate = match tag.try_get_attribute("rel")? {
+        Some(rel) => rel.unescape_value()? == "alternate",
+        None => true,
+    };
+
+    if alternate && let Some(href) = tag.try_get_attribute("href")? {
+        entry.link = href.unescape_value()?.to_string();
+    }
+
+    Ok(())
+}
+
+/// Parses an RFC 2822 date, as used by RSS,
+/// or an RFC 3339 date, as used by Atom and Dublin Core.
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc2822(value)
+        .or_else(|_| DateTime::parse_from_rfc3339(value))
+        .ok()
+        .map(|date| date.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rss() {
+        let xml = r#"<?xml version="1.0"?>
+<rss version="2.0">
+  <channel>
+    <title>News</title>
+    <link>https://example.com</link>
+    <item>
+      <title>First &amp; foremost</title>
+      <link>https://example.com/1</link>
+      <guid isPermaLink="false">item-1</guid>
+      <pubDate>Tue, 10 Jun 2025 04:00:00 GMT</pubDate>
+    </item>
+    <item>
+      <title><![CDATA[Second <b>post</b>]]></title>
+      <link>https://example.com/2</link>
+    </item>
+  </channel>
+</rss>"#;
+
+        let feed = parse(xml).expect("should parse");
+
+        assert_eq!(feed.title.as_deref(), Some("News"));
+        assert_eq!(feed.entries.len(), 2);
+
+        assert_eq!(feed.entries[0].id, "item-1");
+        assert_eq!(feed.entries[0].title, "First & foremost");
+        assert!(feed.entries[0].date.is_some());
+
+        assert_eq!(feed.entries[1].id, "https://example.com/2");
+        assert_eq!(feed.entries[1].title, "Second <b>post</b>");
+    }
+
+    #[test]
+    fn test_parse_atom() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Blog</title>
+  <link href="https://example.com/"/>
+  <entry>
+    <title>Hello</title>
+    <link rel="edit" href="https://example.com/edit/1"/>
+    <link href="https://example.com/hello"/>
+    <id>urn:uuid:1</id>
+    <updated>2025-06-10T04:00:00Z</updated>
+  </entry>
+</feed>"#;
+
+        let feed = parse(xml).expect("should parse");
+
+        assert_eq!(feed.title.as_deref(), Some("Blog"));
+        assert_eq!(
+            feed.entries,
+            vec![Entry {
+                id: "urn:uuid:1".to_string(),
+                title: "Hello".to_string(),
+                link: "https://example.com/hello".to_string(),
+                date: parse_date("2025-06-10T04:00:00Z"),
+            }]
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::clients::compositor::{XWaylandClient, XWaylandUpdate};
 use crate::clients::wayland::{self, ToplevelEvent};
 use crate::config::{CommonConfig, LayoutConfig, TruncateMode, default};
 use crate::gtk_helpers::IronbarLabelExt;
@@ -7,9 +8,14 @@ use crate::{module_impl, spawn};
 use color_eyre::Result;
 use gtk::prelude::*;
 use gtk::{ContentFit, Label};
+use regex::Regex;
 use serde::Deserialize;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::mpsc;
-use tracing::debug;
+use tracing::{debug, error};
 
 #[derive(Debug, Deserialize, Clone)]
 #[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
@@ -29,6 +35,36 @@ pub struct FocusedModule {
     /// **Default**: `32`
     icon_size: i32,
 
+    /// Text to show while the focused window is an XWayland client.
+    /// Leave null to hide the indicator.
+    ///
+    /// Requires Sway or Hyprland.
+    ///
+    /// **Default**: `null`
+    xwayland_indicator: Option<String>,
+
+    /// Whether to add the `xwayland` class to the module
+    /// while the focused window is an XWayland client.
+    ///
+    /// Requires Sway or Hyprland.
+    ///
+    /// **Default**: `false`
+    xwayland_class: bool,
+
+    /// Rules to rewrite window titles before they are shown,
+    /// such as to remove the app name from the end.
+    /// Rules are applied in order.
+    ///
+    /// **Default**: `[]`
+    title_rewrites: Vec<TitleRewrite>,
+
+    /// Map of app IDs to the icon to show for their windows,
+    /// in place of the app's own icon.
+    /// These are applied before the global `icon_overrides`.
+    ///
+    /// **Default**: `{}`
+    icon_overrides: HashMap<String, String>,
+
     // -- common --
     /// See [truncate options](module-level-options#truncate-mode).
     ///
@@ -50,6 +86,10 @@ impl Default for FocusedModule {
             show_icon: true,
             show_title: true,
             icon_size: default::IconSize::Normal as i32,
+            xwayland_indicator: None,
+            xwayland_class: false,
+            title_rewrites: vec![],
+            icon_overrides: HashMap::new(),
             truncate: None,
             layout: LayoutConfig::default(),
             common: Some(CommonConfig::default()),
@@ -57,18 +97,137 @@ impl Default for FocusedModule {
     }
 }
 
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct TitleRewrite {
+    /// Regular expression to match against the title.
+    pattern: String,
+
+    /// Text to replace each match with.
+    /// Capture groups can be referenced using `$1` or `${name}`.
+    ///
+    /// **Default**: `""`
+    #[serde(default)]
+    replacement: String,
+
+    /// Only rewrite the titles of windows with this app ID.
+    /// Leave null to rewrite all windows.
+    ///
+    /// **Default**: `null`
+    app_id: Option<String>,
+}
+
+/// A compiled [`TitleRewrite`].
+#[derive(Debug)]
+struct Rewrite {
+    regex: Regex,
+    replacement: String,
+    app_id: Option<String>,
+}
+
+impl TryFrom<&TitleRewrite> for Rewrite {
+    type Error = regex::Error;
+
+    fn try_from(rewrite: &TitleRewrite) -> Result<Self, Self::Error> {
+        Ok(Self {
+            regex: Regex::new(&rewrite.pattern)?,
+            replacement: rewrite.replacement.clone(),
+            app_id: rewrite.app_id.clone(),
+        })
+    }
+}
+
+/// Applies each rewrite for the app to the title in turn.
+fn rewrite_title(rewrites: &[Rewrite], app_id: &str, title: &str) -> String {
+    rewrites
+        .iter()
+        .filter(|rewrite| rewrite.app_id.as_deref().is_none_or(|id| id == app_id))
+        .fold(title.to_string(), |title, rewrite| {
+            rewrite
+                .regex
+                .replace_all(&title, rewrite.replacement.as_str())
+                .into_owned()
+        })
+}
+
+impl FocusedModule {
+    fn detect_xwayland(&self) -> bool {
+        self.xwayland_indicator.is_some() || self.xwayland_class
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum FocusedUpdate {
+    /// The rewritten title and icon of the focused window,
+    /// or `None` if no window is focused.
+    Window(Option<(String, String)>),
+    /// Whether the focused window is an XWayland client.
+    XWayland(bool),
+}
+
 impl Module<gtk::Box> for FocusedModule {
-    type SendMessage = Option<(String, String)>;
+    type SendMessage = FocusedUpdate;
     type ReceiveMessage = ();
 
     module_impl!("focused");
 
+    fn coalesce_key(update: &Self::SendMessage) -> Option<String> {
+        // each update is the full state of its kind
+        let key = match update {
+            FocusedUpdate::Window(_) => "window",
+            FocusedUpdate::XWayland(_) => "xwayland",
+        };
+
+        Some(key.to_string())
+    }
+
     fn spawn_controller(
         &self,
         _info: &ModuleInfo,
         context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
         _rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
+        if self.detect_xwayland() {
+            let xwayland_rx = context
+                .try_client::<dyn XWaylandClient>()
+                .and_then(|client| Ok(client.subscribe()?));
+
+            match xwayland_rx {
+                Ok(mut xwayland_rx) => {
+                    let tx = context.tx.clone();
+
+                    spawn(async move {
+                        loop {
+                            match xwayland_rx.recv_tracked("xwayland").await {
+                                Ok(XWaylandUpdate(xwayland)) => {
+                                    tx.send_update(FocusedUpdate::XWayland(xwayland)).await;
+                                }
+                                // the next update carries the full state
+                                Err(RecvError::Lagged(_)) => {}
+                                Err(RecvError::Closed) => break,
+                            }
+                        }
+                    });
+                }
+                Err(err) => error!("Failed to spawn xwayland client: {err:?}"),
+            }
+        }
+
+        let rewrites = self
+            .title_rewrites
+            .iter()
+            .map(Rewrite::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let icon_overrides = self.icon_overrides.clone();
+
+        let window = move |title: &str, app_id: String| {
+            let title = rewrite_title(&rewrites, &app_id, title);
+            let icon = icon_overrides.get(&app_id).cloned().unwrap_or(app_id);
+
+            FocusedUpdate::Window(Some((title, icon)))
+        };
+
         let tx = context.tx.clone();
         let wl = context.client::<wayland::Client>();
 
@@ -83,8 +242,7 @@ impl Module<gtk::Box> for FocusedModule {
             if let Some(focused) = focused {
                 current = Some(focused.id);
 
-                tx.send_update(Some((focused.title.clone(), focused.app_id)))
-                    .await;
+                tx.send_update(window(&focused.title, focused.app_id)).await;
             }
 
             while let Ok(event) = wlrx.recv().await {
@@ -95,19 +253,18 @@ impl Module<gtk::Box> for FocusedModule {
 
                             current = Some(info.id);
 
-                            tx.send_update(Some((info.title.clone(), info.app_id)))
-                                .await;
+                            tx.send_update(window(&info.title, info.app_id)).await;
                         } else if info.id == current.unwrap_or_default() {
                             debug!("Clearing focus");
                             current = None;
-                            tx.send_update(None).await;
+                            tx.send_update(FocusedUpdate::Window(None)).await;
                         }
                     }
                     ToplevelEvent::Remove(info) => {
                         if info.focused {
                             debug!("Clearing focus");
                             current = None;
-                            tx.send_update(None).await;
+                            tx.send_update(FocusedUpdate::Window(None)).await;
                         }
                     }
                     ToplevelEvent::New(_) => {}
@@ -143,15 +300,58 @@ impl Module<gtk::Box> for FocusedModule {
 
         container.append(&label);
 
+        let indicator = Label::new(self.xwayland_indicator.as_deref());
+        indicator.add_css_class("xwayland-indicator");
+        indicator.set_visible(false);
+
+        if self.xwayland_indicator.is_some() {
+            container.append(&indicator);
+        }
+
         {
-            let image_provider = context.ironbar.image_provider();
+            let image_provider = context.image_provider();
+            let container = container.clone();
+
+            let focused = Rc::new(Cell::new(false));
+            let xwayland = Rc::new(Cell::new(false));
 
-            context.subscribe().recv_glib_async((), move |(), data| {
+            context.subscribe().recv_glib_async((), move |(), update| {
+                let container = container.clone();
                 let icon = icon.clone();
                 let label = label.clone();
+                let indicator = indicator.clone();
                 let image_provider = image_provider.clone();
+                let focused = focused.clone();
+                let xwayland = xwayland.clone();
 
                 async move {
+                    let data = match update {
+                        FocusedUpdate::Window(data) => {
+                            focused.set(data.is_some());
+                            data
+                        }
+                        FocusedUpdate::XWayland(value) => {
+                            xwayland.set(value);
+                            None
+                        }
+                    };
+
+                    let is_xwayland = focused.get() && xwayland.get();
+                    indicator.set_visible(is_xwayland);
+
+                    if self.xwayland_class {
+                        if is_xwayland {
+                            container.add_css_class("xwayland");
+                        } else {
+                            container.remove_css_class("xwayland");
+                        }
+                    }
+
+                    if !focused.get() {
+                        icon.set_visible(false);
+                        label.set_visible(false);
+                    }
+
                     if let Some((name, id)) = data {
                         if self.show_icon {
                             match image_provider
@@ -167,9 +367,6 @@ impl Module<gtk::Box> for FocusedModule {
                             label.set_visible(true);
                             label.set_label(&name);
                         }
-                    } else {
-                        icon.set_visible(false);
-                        label.set_visible(false);
                     }
                 }
             });
@@ -181,3 +378,48 @@ impl Module<gtk::Box> for FocusedModule {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrite(pattern: &str, replacement: &str, app_id: Option<&str>) -> Rewrite {
+        Rewrite::try_from(&TitleRewrite {
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            app_id: app_id.map(ToString::to_string),
+        })
+        .expect("valid pattern")
+    }
+
+    #[test]
+    fn rewrite_title_strips_suffix() {
+        let rewrites = [rewrite(" [—-] Mozilla Firefox$", "", None)];
+
+        assert_eq!(
+            rewrite_title(&rewrites, "firefox", "Ironbar — Mozilla Firefox"),
+            "Ironbar"
+        );
+    }
+
+    #[test]
+    fn rewrite_title_only_matching_app() {
+        let rewrites = [rewrite("^(.*) - (.*)$", "$2: $1", Some("code"))];
+
+        assert_eq!(
+            rewrite_title(&rewrites, "code", "main.rs - ironbar"),
+            "ironbar: main.rs"
+        );
+        assert_eq!(
+            rewrite_title(&rewrites, "foot", "main.rs - ironbar"),
+            "main.rs - ironbar"
+        );
+    }
+
+    #[test]
+    fn rewrite_title_in_order() {
+        let rewrites = [rewrite("a", "b", None), rewrite("b", "c", None)];
+
+        assert_eq!(rewrite_title(&rewrites, "foot", "ab"), "cc");
+    }
+}
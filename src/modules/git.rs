@@ -0,0 +1,340 @@
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::config::{CommonConfig, LayoutConfig};
+use crate::gtk_helpers::IronbarLabelExt;
+use crate::modules::{Module, ModuleInfo, ModuleParts, WidgetContext};
+use crate::panic_guard;
+use crate::{module_impl, spawn};
+use color_eyre::{Report, Result};
+use gtk::Label;
+use gtk::prelude::*;
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher, recommended_watcher};
+use serde::Deserialize;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+use tracing::{debug, error};
+
+/// The time to wait for further filesystem events
+/// before refreshing, so that bursts of changes only refresh once.
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct GitModule {
+    /// The absolute paths to the repositories to show.
+    /// Each repository is shown as its own label, in order.
+    ///
+    /// **Required**
+    repos: Vec<PathBuf>,
+
+    /// The format string to use for each repository's label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰊢 {branch}{dirty}`
+    #[serde(skip, default = "default_format")]
+    format: String,
+
+    /// The text to use for the `{dirty}` token
+    /// when the repository has uncommitted changes.
+    ///
+    /// **Default**: `*`
+    #[serde(default = "default_dirty_symbol")]
+    dirty_symbol: String,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(default, flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+fn default_format() -> String {
+    "󰊢 {branch}{dirty}".to_string()
+}
+
+fn default_dirty_symbol() -> String {
+    "*".to_string()
+}
+
+/// The state of a repository's working tree and branch.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Status {
+    /// The branch name, or the short commit hash if detached.
+    branch: String,
+    /// The number of changed, staged and untracked files.
+    changed: usize,
+    ahead: usize,
+    behind: usize,
+}
+
+impl Status {
+    /// Gets the status of the repository
+    /// using `git status`.
+    async fn get(repo: &Path) -> Result<Self> {
+        // optional locks are skipped so that checking the status
+        // does not write to the index and trigger another refresh.
+        let output = Command::new("git")
+            .arg("--no-optional-locks")
+            .arg("-C")
+            .arg(repo)
+            .args(["status", "--porcelain=v2", "--branch"])
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            return Err(Report::msg(
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            ));
+        }
+
+        Ok(Self::parse(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    /// Parses the output of `git status --porcelain=v2 --branch`.
+    fn parse(output: &str) -> Self {
+        let mut status = Self::default();
+        let mut oid = "";
+
+        for line in output.lines() {
+            if let Some(header) = line.strip_prefix("# ") {
+                let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+
+                match key {
+                    "branch.oid" => oid = value,
+                    "branch.head" => status.branch = value.to_string(),
+                    "branch.ab" => {
+                        for count in value.split_whitespace() {
+                            if let Some(ahead) = count.strip_prefix('+') {
+                                status.ahead = ahead.parse().unwrap_or_default();
+                            } else if let Some(behind) = count.strip_prefix('-') {
+                                status.behind = behind.parse().unwrap_or_default();
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            } else if !line.starts_with('!') && !line.is_empty() {
+                status.changed += 1;
+            }
+        }
+
+        if status.branch == "(detached)" {
+            status.branch = oid.chars().take(7).collect();
+        }
+
+        status
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.changed > 0
+    }
+}
+
+impl GitModule {
+    fn format(&self, repo: &Path, status: &Status) -> String {
+        let name = repo
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+
+        let dirty = if status.is_dirty() {
+            self.dirty_symbol.as_str()
+        } else {
+            ""
+        };
+
+        self.format
+            .replace("{name}", &name)
+            .replace("{branch}", &status.branch)
+            .replace("{dirty}", dirty)
+            .replace("{changed}", &status.changed.to_string())
+            .replace("{ahead}", &status.ahead.to_string())
+            .replace("{behind}", &status.behind.to_string())
+    }
+}
+
+/// Checks whether the filesystem event could change the status.
+/// Git's internal object store and lock files are ignored.
+fn is_relevant(event: &Event) -> bool {
+    event.paths.iter().any(|path| {
+        let in_objects = path
+            .components()
+            .collect::<Vec<_>>()
+            .windows(2)
+            .any(|window| {
+                window[0] == Component::Normal(".git".as_ref())
+                    && window[1] == Component::Normal("objects".as_ref())
+            });
+
+        !in_objects && path.extension().is_none_or(|ext| ext != "lock")
+    })
+}
+
+/// Watches the repository for changes,
+/// returning a channel which receives a message when its status may have changed.
+///
+/// The watcher must be kept alive for the channel to receive messages.
+fn watch(repo: &Path) -> Result<(RecommendedWatcher, mpsc::Receiver<()>)> {
+    // a full channel means a refresh is already queued,
+    // so further events can be dropped.
+    let (tx, rx) = mpsc::channel(1);
+
+    let mut watcher = recommended_watcher(move |res: notify::Result<Event>| match res {
+        Ok(event) if is_relevant(&event) => _ = tx.try_send(()),
+        Err(err) => error!("Error occurred when watching repository: {err:?}"),
+        _ => {}
+    })?;
+
+    watcher.watch(repo, RecursiveMode::Recursive)?;
+
+    Ok((watcher, rx))
+}
+
+impl Module<gtk::Box> for GitModule {
+    /// The index of the repository and its status.
+    type SendMessage = (usize, Status);
+    type ReceiveMessage = ();
+
+    module_impl!("git");
+
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        for (index, repo) in self.repos.iter().enumerate() {
+            let repo = repo.clone();
+            let tx = context.tx.clone();
+
+            spawn(async move {
+                let (_watcher, mut event_rx) = match watch(&repo) {
+                    Ok(watch) => watch,
+                    Err(err) => {
+                        let err = err.wrap_err(format!("Failed to watch '{}'", repo.display()));
+                        panic_guard::report_error(&err);
+                        return;
+                    }
+                };
+
+                let mut status = None;
+
+                loop {
+                    match Status::get(&repo).await {
+                        Ok(new_status) => {
+                            if status.as_ref() != Some(&new_status) {
+                                debug!("Status of '{}': {new_status:?}", repo.display());
+                                tx.send_update((index, new_status.clone())).await;
+                                status = Some(new_status);
+                            }
+                        }
+                        Err(err) => {
+                            error!("Failed to get status of '{}': {err:?}", repo.display());
+                        }
+                    }
+
+                    if event_rx.recv().await.is_none() {
+                        break;
+                    }
+
+                    sleep(DEBOUNCE).await;
+                    _ = event_rx.try_recv();
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<gtk::Box>> {
+        let container = gtk::Box::new(self.layout.orientation(info), 10);
+
+        let labels = self
+            .repos
+            .iter()
+            .map(|_| {
+                let label = Label::builder()
+                    .use_markup(true)
+                    .justify(self.layout.justify.into())
+                    .build();
+
+                label.add_css_class("repo");
+                container.append(&label.rotated(self.layout.angle()));
+                label
+            })
+            .collect::<Vec<_>>();
+
+        let module = self;
+        context
+            .subscribe()
+            .recv_glib((), move |(), (index, status)| {
+                let label = &labels[index];
+                label.set_label_escaped(&module.format(&module.repos[index], &status));
+
+                for (class, active) in [
+                    ("dirty", status.is_dirty()),
+                    ("ahead", status.ahead > 0),
+                    ("behind", status.behind > 0),
+                ] {
+                    if active {
+                        label.add_css_class(class);
+                    } else {
+                        label.remove_css_class(class);
+                    }
+                }
+            });
+
+        Ok(ModuleParts {
+            widget: container,
+            popup: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_status() {
+        let output = "# branch.oid 1a2b3c4d5e6f\n\
+                      # branch.head main\n\
+                      # branch.upstream origin/main\n\
+                      # branch.ab +2 -1\n\
+                      1 .M N... 100644 100644 100644 abc abc src/main.rs\n\
+                      ? notes.txt\n\
+                      ! target/\n";
+
+        assert_eq!(
+            Status::parse(output),
+            Status {
+                branch: "main".to_string(),
+                changed: 2,
+                ahead: 2,
+                behind: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_detached() {
+        let output = "# branch.oid 1a2b3c4d5e6f\n# branch.head (detached)\n";
+
+        let status = Status::parse(output);
+        assert_eq!(status.branch, "1a2b3c4");
+        assert!(!status.is_dirty());
+    }
+}
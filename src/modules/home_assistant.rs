@@ -0,0 +1,428 @@
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::clients::home_assistant::{self, Entity, Update};
+use crate::config::{CommonConfig, LayoutConfig, Secret};
+use crate::gtk_helpers::IronbarLabelExt;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::panic_guard::{self, PanicGuard};
+use crate::{module_impl, spawn, spawn_blocking};
+use color_eyre::{Report, Result};
+use glib::SignalHandlerId;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation, Switch};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::env;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tracing::error;
+
+/// Environment variable the access token is read from
+/// if it is not set in the config.
+const TOKEN_ENV: &str = "HASS_TOKEN";
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct HomeAssistantModule {
+    /// The URL of the Home Assistant instance.
+    ///
+    /// **Default**: `http://homeassistant.local:8123`
+    url: String,
+
+    /// A long-lived access token, created from your Home Assistant profile page.
+    /// If not set, the token is read from the `HASS_TOKEN` environment variable.
+    ///
+    /// See [secrets](configuration-guide#34-secrets) for other ways to provide this.
+    ///
+    /// **Default**: `null`
+    token: Option<Secret>,
+
+    /// The format string to use for the widget button label.
+    /// Any `{entity_id}` token is replaced with that entity's state,
+    /// followed by its unit if it has one.
+    ///
+    /// **Default**: `󰟐`
+    #[serde(skip)]
+    format: String,
+
+    /// The IDs of the entities to show in the popup.
+    /// Switches, lights, locks and similar entities can be toggled,
+    /// and scenes, scripts and buttons can be activated.
+    ///
+    /// **Default**: `[]`
+    entities: Vec<String>,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(default, flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for HomeAssistantModule {
+    fn default() -> Self {
+        Self {
+            url: "http://homeassistant.local:8123".to_string(),
+            token: None,
+            format: "󰟐".to_string(),
+            entities: vec![],
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+impl HomeAssistantModule {
+    /// Gets the IDs of the entities used in the format string or popup.
+    fn watched(&self) -> Vec<String> {
+        let mut watched = format_tokens(&self.format);
+        for entity_id in &self.entities {
+            if !watched.contains(entity_id) {
+                watched.push(entity_id.clone());
+            }
+        }
+
+        watched
+    }
+}
+
+/// Gets the entity IDs of each `{token}` in the format string.
+fn format_tokens(format: &str) -> Vec<String> {
+    let mut tokens = vec![];
+
+    let mut rest = format;
+    while let Some((_, after)) = rest.split_once('{') {
+        let Some((token, after)) = after.split_once('}') else {
+            break;
+        };
+
+        if token.contains('.') && !tokens.iter().any(|t| t == token) {
+            tokens.push(token.to_string());
+        }
+
+        rest = after;
+    }
+
+    tokens
+}
+
+/// Replaces each entity token in the format string with its state,
+/// or an empty string if its state is not yet known.
+fn render(format: &str, tokens: &[String], states: &HashMap<String, String>) -> String {
+    tokens.iter().fold(format.to_string(), |text, token| {
+        let state = states.get(token).map_or("", String::as_str);
+        text.replace(&format!("{{{token}}}"), state)
+    })
+}
+
+/// Gets the entity's state, followed by its unit if it has one.
+fn display_state(entity: &Entity) -> String {
+    match entity.unit() {
+        Some(unit) => format!("{}{unit}", entity.state),
+        None => entity.state.clone(),
+    }
+}
+
+/// Gets the access token from the config,
+/// falling back to the environment.
+fn resolve_token(token: Option<&Secret>) -> Result<String> {
+    match token {
+        Some(token) => token.resolve(),
+        None => {
+            env::var(TOKEN_ENV).map_err(|_| Report::msg(format!("No `token` or `{TOKEN_ENV}` set")))
+        }
+    }
+}
+
+/// Sends the current state of each watched entity.
+async fn send_all(
+    client: &home_assistant::Client,
+    watched: &[String],
+    tx: &mpsc::Sender<ModuleUpdateEvent<Entity>>,
+) {
+    for entity_id in watched {
+        if let Some(entity) = client.entity(entity_id) {
+            tx.send_update(entity).await;
+        }
+    }
+}
+
+/// How an entity can be controlled from the popup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Control {
+    /// Turned on or off with a switch,
+    /// calling the first service to turn on and the second to turn off.
+    Toggle(&'static str, &'static str),
+    /// Activated with a button, calling the service.
+    Activate(&'static str),
+    /// Read-only.
+    None,
+}
+
+impl Control {
+    fn for_domain(domain: &str) -> Self {
+        match domain {
+            "light" | "switch" | "fan" | "input_boolean" | "automation" | "siren" => {
+                Self::Toggle("turn_on", "turn_off")
+            }
+            "lock" => Self::Toggle("lock", "unlock"),
+            "scene" | "script" => Self::Activate("turn_on"),
+            "button" | "input_button" => Self::Activate("press"),
+            _ => Self::None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UiEvent {
+    entity_id: String,
+    service: &'static str,
+}
+
+/// A row in the popup showing a single entity.
+#[derive(Debug)]
+struct EntityRow {
+    name: Label,
+    state: Label,
+    switch: Option<(Switch, SignalHandlerId)>,
+}
+
+impl EntityRow {
+    fn new(entity_id: &str, container: &gtk::Box, tx: &mpsc::Sender<UiEvent>) -> Self {
+        let row = gtk::Box::new(Orientation::Horizontal, 10);
+        row.add_css_class("entity");
+        container.append(&row);
+
+        let name = Label::new(Some(entity_id));
+        name.add_css_class("name");
+        name.set_halign(Align::Start);
+        name.set_hexpand(true);
+        row.append(&name);
+
+        let state = Label::new(None);
+        state.add_css_class("state");
+        row.append(&state);
+
+        let domain = home_assistant::domain(entity_id);
+
+        let switch = match Control::for_domain(domain) {
+            Control::Toggle(on, off) => {
+                let switch = Switch::new();
+                switch.add_css_class("switch");
+                switch.set_valign(Align::Center);
+                row.append(&switch);
+
+                let tx = tx.clone();
+                let entity_id = entity_id.to_string();
+                let handler = switch.connect_active_notify(move |switch| {
+                    tx.send_spawn(UiEvent {
+                        entity_id: entity_id.clone(),
+                        service: if switch.is_active() { on } else { off },
+                    });
+                });
+
+                Some((switch, handler))
+            }
+            Control::Activate(service) => {
+                let button = Button::with_label("Activate");
+                button.add_css_class("btn-activate");
+                row.append(&button);
+
+                let tx = tx.clone();
+                let entity_id = entity_id.to_string();
+                button.connect_clicked(move |_| {
+                    tx.send_spawn(UiEvent {
+                        entity_id: entity_id.clone(),
+                        service,
+                    });
+                });
+
+                None
+            }
+            Control::None => None,
+        };
+
+        Self {
+            name,
+            state,
+            switch,
+        }
+    }
+
+    fn update(&self, entity: &Entity) {
+        self.name.set_label(entity.friendly_name());
+        self.state.set_label(&display_state(entity));
+
+        if let Some((switch, handler)) = &self.switch {
+            let active = matches!(entity.state.as_str(), "on" | "locked");
+
+            switch.block_signal(handler);
+            switch.set_active(active);
+            switch.unblock_signal(handler);
+
+            switch.set_sensitive(entity.state != "unavailable");
+        }
+    }
+}
+
+impl Module<Button> for HomeAssistantModule {
+    type SendMessage = Entity;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("home_assistant");
+
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let token = self.token.clone();
+        let url = self.url.clone();
+        let ironbar = context.ironbar.clone();
+
+        let tx = context.tx.clone();
+        let watched = self.watched();
+
+        // resolving the token can run a command, so happens off the main thread
+        let guard = PanicGuard::current();
+        glib::spawn_future_local(panic_guard::scope_future(guard, async move {
+            let token = match spawn_blocking(move || resolve_token(token.as_ref())).await {
+                Ok(Ok(token)) => token,
+                Ok(Err(err)) => {
+                    panic_guard::report_error(&err.wrap_err("Unable to connect to Home Assistant"));
+                    return;
+                }
+                Err(err) => {
+                    error!("{err:?}");
+                    return;
+                }
+            };
+
+            let client = ironbar.clients.borrow_mut().home_assistant(&url, &token);
+
+            spawn(async move {
+                let mut updates = client.subscribe();
+                send_all(&client, &watched, &tx).await;
+
+                loop {
+                    tokio::select! {
+                        res = updates.recv_tracked("home_assistant") => match res {
+                            Ok(Update::Changed(entity)) => {
+                                if watched.contains(&entity.entity_id) {
+                                    tx.send_update(*entity).await;
+                                }
+                            }
+                            Ok(Update::All) | Err(RecvError::Lagged(_)) => {
+                                send_all(&client, &watched, &tx).await;
+                            }
+                            Err(RecvError::Closed) => break,
+                        },
+                        Some(event) = rx.recv() => {
+                            let domain = home_assistant::domain(&event.entity_id);
+                            if let Err(err) = client
+                                .call_service(domain, event.service, &event.entity_id)
+                                .await
+                            {
+                                error!("{err:?}");
+                            }
+                        }
+                    }
+                }
+            });
+        }));
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        button.add_css_class("btn");
+
+        let label = Label::builder().use_markup(true).build();
+        label.set_justify(self.layout.justify.into());
+        button.set_child(Some(&label));
+
+        let tx = context.tx.clone();
+        button.connect_clicked(move |button| {
+            tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
+        });
+
+        {
+            let format = self.format.clone();
+            let tokens = format_tokens(&format);
+            let mut states = HashMap::new();
+
+            label.set_label_escaped(&render(&format, &tokens, &states));
+
+            context.subscribe().recv_glib(&label, move |label, entity| {
+                states.insert(entity.entity_id.clone(), display_state(&entity));
+                label.set_label_escaped(&render(&format, &tokens, &states));
+            });
+        }
+
+        let popup = self
+            .into_popup(context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        if self.entities.is_empty() {
+            return None;
+        }
+
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let rows = self
+            .entities
+            .iter()
+            .map(|entity_id| {
+                let row = EntityRow::new(entity_id, &container, &context.controller_tx);
+                (entity_id.clone(), row)
+            })
+            .collect::<HashMap<_, _>>();
+
+        context.subscribe().recv_glib((), move |(), entity| {
+            if let Some(row) = rows.get(&entity.entity_id) {
+                row.update(&entity);
+            }
+        });
+
+        Some(container)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_tokens() {
+        assert_eq!(
+            format_tokens("{sensor.temperature} {light.desk} {sensor.temperature} {}"),
+            vec!["sensor.temperature", "light.desk"]
+        );
+    }
+}
@@ -96,7 +96,7 @@ impl Module<Button> for InhibitModule {
             .use_markup(true)
             .justify(self.layout.justify.into())
             .build();
-        button.set_child(Some(&label));
+        button.set_child(Some(&label.rotated(self.layout.angle())));
         let tx = ctx.controller_tx.clone();
 
         [
@@ -0,0 +1,339 @@
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::clients::kdeconnect::{self, Device};
+use crate::config::{CommonConfig, LayoutConfig};
+use crate::gtk_helpers::IronbarLabelExt;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::{module_impl, spawn};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Button, Label, Orientation};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tracing::error;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct KdeConnectModule {
+    /// The name or ID of the device to show.
+    /// If not set, the first reachable paired device is shown.
+    ///
+    /// **Default**: `null`
+    device: Option<String>,
+
+    /// The format string to use for the widget button label
+    /// while the device is reachable.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰄜 {battery}%`
+    #[serde(skip)]
+    format: String,
+
+    /// The format string to use for the widget button label
+    /// while the device is not reachable, or no device is paired.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰥐`
+    format_disconnected: String,
+
+    /// The app names whose notifications count towards `{sms}`.
+    ///
+    /// **Default**: `["Messages", "SMS"]`
+    sms_apps: Vec<String>,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(default, flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for KdeConnectModule {
+    fn default() -> Self {
+        Self {
+            device: None,
+            format: "󰄜 {battery}%".to_string(),
+            format_disconnected: "󰥐".to_string(),
+            sms_apps: vec!["Messages".to_string(), "SMS".to_string()],
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+impl KdeConnectModule {
+    /// Picks the configured device,
+    /// or the first reachable device if none is configured.
+    fn pick(&self, devices: Vec<Device>) -> Option<Device> {
+        match &self.device {
+            Some(device) => devices
+                .into_iter()
+                .find(|d| &d.id == device || &d.name == device),
+            None => {
+                let reachable = devices.iter().position(|d| d.reachable).unwrap_or(0);
+                devices.into_iter().nth(reachable)
+            }
+        }
+    }
+
+    fn sms_count(&self, device: &Device) -> usize {
+        device
+            .notifications
+            .iter()
+            .filter(|app| self.sms_apps.contains(app))
+            .count()
+    }
+
+    /// Replaces the formatting tokens in `format` with the device's values.
+    fn format(&self, format: &str, device: Option<&Device>) -> String {
+        let Some(device) = device else {
+            return format
+                .replace("{name}", "")
+                .replace("{battery}", "")
+                .replace("{network}", "")
+                .replace("{signal}", "")
+                .replace("{sms}", "");
+        };
+
+        let (battery, network, signal) = (
+            device
+                .battery
+                .map(|(charge, _)| charge.to_string())
+                .unwrap_or_default(),
+            device
+                .network
+                .as_ref()
+                .map(|(network, _)| network.clone())
+                .unwrap_or_default(),
+            device
+                .network
+                .as_ref()
+                .filter(|(_, strength)| *strength >= 0)
+                .map(|(_, strength)| strength.to_string())
+                .unwrap_or_default(),
+        );
+
+        format
+            .replace("{name}", &device.name)
+            .replace("{battery}", &battery)
+            .replace("{network}", &network)
+            .replace("{signal}", &signal)
+            .replace("{sms}", &self.sms_count(device).to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum UiEvent {
+    Ring,
+    Ping,
+    SendClipboard,
+}
+
+impl Module<Button> for KdeConnectModule {
+    type SendMessage = Option<Device>;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("kdeconnect");
+
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let client = context.try_client::<kdeconnect::Client>()?;
+        let tx = context.tx.clone();
+        let module = self.clone();
+
+        spawn(async move {
+            let mut devices = client.subscribe();
+            let mut device = module.pick(client.devices().await.unwrap_or_default());
+            tx.send_update(device.clone()).await;
+
+            loop {
+                tokio::select! {
+                    res = devices.recv_tracked("kdeconnect") => {
+                        let new_devices = match res {
+                            Ok(new_devices) => new_devices,
+                            Err(RecvError::Lagged(_)) => match client.devices().await {
+                                Ok(new_devices) => new_devices,
+                                Err(err) => {
+                                    error!("{err:?}");
+                                    continue;
+                                }
+                            },
+                            Err(RecvError::Closed) => break,
+                        };
+
+                        let new_device = module.pick(new_devices);
+                        if new_device != device {
+                            device = new_device;
+                            tx.send_update(device.clone()).await;
+                        }
+                    }
+                    Some(event) = rx.recv() => {
+                        let Some(id) = device.as_ref().map(|device| device.id.as_str()) else {
+                            continue;
+                        };
+
+                        match event {
+                            UiEvent::Ring => client.ring(id).await,
+                            UiEvent::Ping => client.ping(id).await,
+                            UiEvent::SendClipboard => client.send_clipboard(id).await,
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        button.add_css_class("btn");
+
+        let label = Label::builder().use_markup(true).build();
+        label.set_justify(self.layout.justify.into());
+        button.set_child(Some(&label));
+
+        let tx = context.tx.clone();
+        button.connect_clicked(move |button| {
+            tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
+        });
+
+        {
+            let module = self.clone();
+            context
+                .subscribe()
+                .recv_glib((&button, &label), move |(button, label), device| {
+                    let reachable = device.as_ref().is_some_and(|device| device.reachable);
+
+                    let format = if reachable {
+                        &module.format
+                    } else {
+                        &module.format_disconnected
+                    };
+
+                    label.set_label_escaped(&module.format(format, device.as_ref()));
+
+                    if reachable {
+                        button.remove_css_class("disconnected");
+                    } else {
+                        button.add_css_class("disconnected");
+                    }
+
+                    if device
+                        .and_then(|device| device.battery)
+                        .is_some_and(|(_, charging)| charging)
+                    {
+                        button.add_css_class("charging");
+                    } else {
+                        button.remove_css_class("charging");
+                    }
+                });
+        }
+
+        let popup = self
+            .into_popup(context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let name = Label::new(None);
+        name.add_css_class("device-name");
+        container.append(&name);
+
+        let battery = Label::new(None);
+        battery.add_css_class("battery");
+        container.append(&battery);
+
+        let network = Label::new(None);
+        network.add_css_class("network");
+        container.append(&network);
+
+        let sms = Label::new(None);
+        sms.add_css_class("sms");
+        container.append(&sms);
+
+        let actions = gtk::Box::new(Orientation::Horizontal, 5);
+        actions.add_css_class("actions");
+        container.append(&actions);
+
+        for (text, class, event) in [
+            ("Find my phone", "btn-ring", UiEvent::Ring),
+            ("Ping", "btn-ping", UiEvent::Ping),
+            ("Send clipboard", "btn-clipboard", UiEvent::SendClipboard),
+        ] {
+            let button = Button::with_label(text);
+            button.add_css_class(class);
+            actions.append(&button);
+
+            let tx = context.controller_tx.clone();
+            button.connect_clicked(move |_| tx.send_spawn(event));
+        }
+
+        context.subscribe().recv_glib(&container, move |_, device| {
+            let Some(device) = device else {
+                name.set_label("No device paired");
+                battery.set_visible(false);
+                network.set_visible(false);
+                sms.set_visible(false);
+                actions.set_sensitive(false);
+                return;
+            };
+
+            name.set_label(&if device.reachable {
+                device.name.clone()
+            } else {
+                format!("{} (disconnected)", device.name)
+            });
+
+            battery.set_visible(device.battery.is_some());
+            if let Some((charge, charging)) = device.battery {
+                battery.set_label(&if charging {
+                    format!("Battery: {charge}% (charging)")
+                } else {
+                    format!("Battery: {charge}%")
+                });
+            }
+
+            network.set_visible(device.network.is_some());
+            if let Some((network_type, strength)) = &device.network {
+                network.set_label(&format!("Network: {network_type} ({strength}/4)"));
+            }
+
+            sms.set_visible(true);
+            sms.set_label(&format!("Unread messages: {}", self.sms_count(&device)));
+
+            actions.set_sensitive(device.reachable);
+        });
+
+        Some(container)
+    }
+}
@@ -1,16 +1,20 @@
 use color_eyre::Result;
 use color_eyre::eyre::Report;
 use gtk::prelude::*;
+use gtk::{Button, Orientation};
 use indexmap::IndexMap;
 use serde::Deserialize;
 use tokio::sync::mpsc;
 use tracing::{debug, error, trace};
 
-use super::{Module, ModuleInfo, ModuleParts, WidgetContext};
+use super::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
 use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
 use crate::clients::compositor::{self, KeyboardLayoutUpdate};
 use crate::clients::libinput::{Event, Key, KeyEvent};
 use crate::config::{CommonConfig, LayoutConfig};
+use crate::gtk_helpers::{IronbarGtkExt, MouseButton};
 use crate::image::{IconButton, IconLabel};
 use crate::{module_impl, spawn};
 
@@ -38,6 +42,14 @@ pub struct KeyboardModule {
     ///  **Default**: `true`
     show_layout: bool,
 
+    /// Whether to remember the layout used in each window,
+    /// switching back to it whenever the window is focused.
+    ///
+    /// This is only supported on Hyprland.
+    ///
+    ///  **Default**: `false`
+    per_window_layout: bool,
+
     /// Size to render the icons at, in pixels (image icons only).
     ///
     /// **Default** `32`
@@ -71,6 +83,7 @@ impl Default for KeyboardModule {
             show_num: true,
             show_scroll: true,
             show_layout: true,
+            per_window_layout: false,
             icon_size: 32,
             icons: Icons::default(),
             seat: "seat0".to_string(),
@@ -148,15 +161,40 @@ impl Default for Icons {
     }
 }
 
+impl Icons {
+    /// Gets the icon or label to show for the layout.
+    fn layout<'a>(&'a self, layout: &'a str) -> &'a str {
+        self.layout_map
+            .iter()
+            .find_map(|(pattern, display_text)| {
+                let is_match = if pattern.ends_with('*') {
+                    layout.starts_with(&pattern[..pattern.len() - 1])
+                } else {
+                    pattern == layout
+                };
+
+                is_match.then_some(display_text.as_str())
+            })
+            .unwrap_or(layout)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum KeyboardUpdate {
     Key(KeyEvent),
     Layout(KeyboardLayoutUpdate),
 }
 
+#[derive(Debug)]
+pub enum UiEvent {
+    NextLayout,
+    /// Switches to the layout at the index.
+    SetLayout(usize),
+}
+
 impl Module<gtk::Box> for KeyboardModule {
     type SendMessage = KeyboardUpdate;
-    type ReceiveMessage = ();
+    type ReceiveMessage = UiEvent;
 
     module_impl!("keyboard");
 
@@ -191,6 +229,12 @@ impl Module<gtk::Box> for KeyboardModule {
 
         match context.try_client::<dyn compositor::KeyboardLayoutClient>() {
             Ok(client) => {
+                if self.per_window_layout
+                    && let Err(err) = client.enable_per_window()
+                {
+                    error!("Failed to enable per-window keyboard layouts: {err:?}");
+                }
+
                 {
                     let client = client.clone();
                     let tx = context.tx.clone();
@@ -200,16 +244,13 @@ impl Module<gtk::Box> for KeyboardModule {
                         trace!("Set up keyboard_layout subscription");
 
                         loop {
-                            match srx.recv().await {
+                            match srx.recv_tracked("keyboard layout").await {
                                 Ok(payload) => {
                                     debug!("Received update: {payload:?}");
                                     tx.send_update(KeyboardUpdate::Layout(payload)).await;
                                 }
-                                Err(tokio::sync::broadcast::error::RecvError::Lagged(count)) => {
-                                    tracing::warn!(
-                                        "Channel lagged behind by {count}, this may result in unexpected or broken behaviour"
-                                    );
-                                }
+                                // the next update carries the full layout
+                                Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {}
                                 Err(err) => {
                                     error!("{err:?}");
                                     break;
@@ -223,8 +264,11 @@ impl Module<gtk::Box> for KeyboardModule {
                 spawn(async move {
                     trace!("Setting up keyboard_layout UI event handler");
 
-                    while let Some(()) = rx.recv().await {
-                        client.set_next_active();
+                    while let Some(event) = rx.recv().await {
+                        match event {
+                            UiEvent::NextLayout => client.set_next_active(),
+                            UiEvent::SetLayout(index) => client.set_active(index),
+                        }
                     }
 
                     Ok::<(), Report>(())
@@ -243,7 +287,7 @@ impl Module<gtk::Box> for KeyboardModule {
     ) -> Result<ModuleParts<gtk::Box>> {
         let container = gtk::Box::new(self.layout.orientation(info), 0);
 
-        let image_provider = context.ironbar.image_provider();
+        let image_provider = context.image_provider();
 
         let caps = IconLabel::new(&self.icons.caps_off, self.icon_size, &image_provider);
         let num = IconLabel::new(&self.icons.num_off, self.icon_size, &image_provider);
@@ -281,28 +325,36 @@ impl Module<gtk::Box> for KeyboardModule {
         {
             let tx = context.controller_tx.clone();
             layout_button.connect_clicked(move |_| {
-                tx.send_spawn(());
+                tx.send_spawn(UiEvent::NextLayout);
+            });
+        }
+
+        {
+            let tx = context.tx.clone();
+            let button = (*layout_button).clone();
+            layout_button.connect_pressed(MouseButton::Secondary, move || {
+                tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
             });
         }
 
-        let icons = self.icons;
+        let icons = self.icons.clone();
+        let show_caps = self.show_caps;
+        let show_num = self.show_num;
+        let show_scroll = self.show_scroll;
+
         context
             .subscribe()
             .recv_glib((), move |(), ev: KeyboardUpdate| match ev {
                 KeyboardUpdate::Key(ev) => {
                     let parts = match (ev.key, ev.state) {
-                        (Key::Caps, true) if self.show_caps => {
-                            Some((&caps, icons.caps_on.as_str()))
-                        }
-                        (Key::Caps, false) if self.show_caps => {
-                            Some((&caps, icons.caps_off.as_str()))
-                        }
-                        (Key::Num, true) if self.show_num => Some((&num, icons.num_on.as_str())),
-                        (Key::Num, false) if self.show_num => Some((&num, icons.num_off.as_str())),
-                        (Key::Scroll, true) if self.show_scroll => {
+                        (Key::Caps, true) if show_caps => Some((&caps, icons.caps_on.as_str())),
+                        (Key::Caps, false) if show_caps => Some((&caps, icons.caps_off.as_str())),
+                        (Key::Num, true) if show_num => Some((&num, icons.num_on.as_str())),
+                        (Key::Num, false) if show_num => Some((&num, icons.num_off.as_str())),
+                        (Key::Scroll, true) if show_scroll => {
                             Some((&scroll, icons.scroll_on.as_str()))
                         }
-                        (Key::Scroll, false) if self.show_scroll => {
+                        (Key::Scroll, false) if show_scroll => {
                             Some((&scroll, icons.scroll_off.as_str()))
                         }
                         _ => None,
@@ -318,23 +370,77 @@ impl Module<gtk::Box> for KeyboardModule {
                         }
                     }
                 }
-                KeyboardUpdate::Layout(KeyboardLayoutUpdate(language)) => {
-                    let text = icons
-                        .layout_map
-                        .iter()
-                        .find_map(|(pattern, display_text)| {
-                            let is_match = if pattern.ends_with('*') {
-                                language.starts_with(&pattern[..pattern.len() - 1])
-                            } else {
-                                pattern == &language
-                            };
+                KeyboardUpdate::Layout(update) => {
+                    layout_button.set_label(icons.layout(&update.name));
+                }
+            });
+
+        let popup = if self.show_layout {
+            self.into_popup(context, info)
+                .into_popup_parts(vec![&*layout_button])
+        } else {
+            None
+        };
+
+        Ok(ModuleParts::new(container, popup))
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let tx = context.controller_tx.clone();
+        let module_tx = context.tx.clone();
+
+        let mut layouts = vec![];
+        let mut buttons = vec![];
+
+        context
+            .subscribe()
+            .recv_glib(&container, move |container, ev: KeyboardUpdate| {
+                let KeyboardUpdate::Layout(update) = ev else {
+                    return;
+                };
+
+                if update.layouts != layouts {
+                    for button in buttons.drain(..) {
+                        container.remove(&button);
+                    }
 
-                            is_match.then_some(display_text)
-                        })
-                        .unwrap_or(&language);
-                    layout_button.set_label(text);
+                    for (index, layout) in update.layouts.iter().enumerate() {
+                        let button = Button::with_label(layout);
+                        button.add_css_class("layout");
+
+                        let tx = tx.clone();
+                        let module_tx = module_tx.clone();
+                        button.connect_clicked(move |_| {
+                            tx.send_spawn(UiEvent::SetLayout(index));
+                            module_tx.send_spawn(ModuleUpdateEvent::ClosePopup);
+                        });
+
+                        container.append(&button);
+                        buttons.push(button);
+                    }
+
+                    layouts.clone_from(&update.layouts);
+                }
+
+                let active = update.index();
+                for (index, button) in buttons.iter().enumerate() {
+                    if active == Some(index) {
+                        button.add_css_class("active");
+                    } else {
+                        button.remove_css_class("active");
+                    }
                 }
             });
-        Ok(ModuleParts::new(container, None))
+
+        Some(container)
     }
 }
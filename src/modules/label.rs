@@ -33,12 +33,17 @@ pub struct LabelModule {
     pub common: Option<CommonConfig>,
 }
 
-impl Module<Label> for LabelModule {
+impl Module<gtk::Widget> for LabelModule {
     type SendMessage = String;
     type ReceiveMessage = ();
 
     module_impl!("label");
 
+    fn coalesce_key(_update: &Self::SendMessage) -> Option<String> {
+        // each update is the full state
+        Some(String::new())
+    }
+
     fn spawn_controller(
         &self,
         _info: &ModuleInfo,
@@ -56,7 +61,7 @@ impl Module<Label> for LabelModule {
         self,
         context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
         _info: &ModuleInfo,
-    ) -> Result<ModuleParts<Label>> {
+    ) -> Result<ModuleParts<gtk::Widget>> {
         let label = Label::builder()
             .use_markup(true)
             .justify(self.layout.justify.into())
@@ -71,7 +76,7 @@ impl Module<Label> for LabelModule {
         });
 
         Ok(ModuleParts {
-            widget: label,
+            widget: label.rotated(self.layout.angle()),
             popup: None,
         })
     }
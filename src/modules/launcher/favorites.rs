@@ -0,0 +1,115 @@
+use std::io;
+use std::path::PathBuf;
+use tokio::fs;
+use tracing::error;
+
+/// Gets the path of the file storing favourites changed from the bar,
+/// with one app ID per line in the order they are shown.
+fn path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("ironbar").join("launcher_favorites"))
+}
+
+/// Loads the saved favourites,
+/// if they have been changed from the bar.
+pub fn load() -> Option<Vec<String>> {
+    let path = path()?;
+
+    match std::fs::read_to_string(path) {
+        Ok(contents) => Some(parse(&contents)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => {
+            error!("Failed to load launcher favorites: {err:?}");
+            None
+        }
+    }
+}
+
+/// Saves the favourites, replacing any previously saved.
+pub async fn save(favorites: &[String]) {
+    let Some(path) = path() else {
+        error!("Missing XDG data dir, launcher favorites will not be saved");
+        return;
+    };
+
+    let res = async {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(&path, serialize(favorites)).await
+    };
+
+    if let Err(err) = res.await {
+        error!("Failed to save launcher favorites: {err:?}");
+    }
+}
+
+fn parse(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(ToString::to_string)
+        .collect()
+}
+
+fn serialize(favorites: &[String]) -> String {
+    favorites
+        .iter()
+        .map(|app_id| format!("{app_id}\n"))
+        .collect()
+}
+
+/// Moves the favourite with `app_id` to the position of `target`,
+/// returning `false` if either is not a favourite.
+pub fn move_favorite(favorites: &mut Vec<String>, app_id: &str, target: &str) -> bool {
+    let (Some(from), Some(to)) = (
+        favorites.iter().position(|fav| fav == app_id),
+        favorites.iter().position(|fav| fav == target),
+    ) else {
+        return false;
+    };
+
+    let app_id = favorites.remove(from);
+    favorites.insert(to, app_id);
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn favorites() -> Vec<String> {
+        vec![
+            "firefox".to_string(),
+            "foot".to_string(),
+            "code".to_string(),
+        ]
+    }
+
+    #[test]
+    fn parse_round_trip() {
+        assert_eq!(parse(&serialize(&favorites())), favorites());
+    }
+
+    #[test]
+    fn move_favorite_forwards() {
+        let mut favorites = favorites();
+        assert!(move_favorite(&mut favorites, "firefox", "code"));
+        assert_eq!(favorites, ["foot", "code", "firefox"]);
+    }
+
+    #[test]
+    fn move_favorite_backwards() {
+        let mut favorites = favorites();
+        assert!(move_favorite(&mut favorites, "code", "firefox"));
+        assert_eq!(favorites, ["code", "firefox", "foot"]);
+    }
+
+    #[test]
+    fn move_favorite_missing() {
+        let mut favorites = favorites();
+        assert!(!move_favorite(&mut favorites, "discord", "firefox"));
+        assert_eq!(favorites, self::favorites());
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::fs;
+use tracing::{error, warn};
+
+const DAY_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Usage {
+    count: u32,
+    /// Unix timestamp of the last launch, in seconds.
+    last_used: u64,
+}
+
+/// Tracks how often and how recently applications are launched from search,
+/// so that those used most are ranked first.
+///
+/// The history is saved in Ironbar's data directory,
+/// with one line per application in the format `<file_name>\t<count>\t<last_used>`.
+#[derive(Debug, Default)]
+pub struct Frecency {
+    path: Option<PathBuf>,
+    usage: HashMap<String, Usage>,
+}
+
+impl Frecency {
+    /// Loads the saved history, if there is one.
+    pub async fn load() -> Self {
+        let path = dirs::data_dir().map(|dir| dir.join("ironbar").join("launcher_history"));
+        if path.is_none() {
+            warn!("Missing XDG data dir, launcher history will not be saved");
+        }
+
+        let usage = match &path {
+            Some(path) => match fs::read_to_string(path).await {
+                Ok(contents) => parse(&contents),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => HashMap::new(),
+                Err(err) => {
+                    error!("Failed to load launcher history: {err:?}");
+                    HashMap::new()
+                }
+            },
+            None => HashMap::new(),
+        };
+
+        Self { path, usage }
+    }
+
+    /// Gets the score for the application,
+    /// which is its launch count weighted by how recently it was last launched.
+    pub fn score(&self, file_name: &str) -> f64 {
+        self.usage
+            .get(file_name)
+            .map_or(0.0, |usage| score(*usage, now()))
+    }
+
+    /// Records a launch of the application and saves the history.
+    pub async fn record(&mut self, file_name: &str) {
+        let usage = self.usage.entry(file_name.to_string()).or_insert(Usage {
+            count: 0,
+            last_used: 0,
+        });
+
+        usage.count = usage.count.saturating_add(1);
+        usage.last_used = now();
+
+        let Some(path) = &self.path else {
+            return;
+        };
+
+        let res = async {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+            fs::write(path, serialize(&self.usage)).await
+        };
+
+        if let Err(err) = res.await {
+            error!("Failed to save launcher history: {err:?}");
+        }
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn score(usage: Usage, now: u64) -> f64 {
+    let age = now.saturating_sub(usage.last_used);
+
+    let weight = if age < DAY_SECS {
+        4.0
+    } else if age < 7 * DAY_SECS {
+        2.0
+    } else if age < 30 * DAY_SECS {
+        1.0
+    } else {
+        0.5
+    };
+
+    f64::from(usage.count) * weight
+}
+
+/// Parses the saved history, skipping invalid lines.
+fn parse(contents: &str) -> HashMap<String, Usage> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split('\t');
+            let file_name = parts.next()?;
+            let count = parts.next()?.parse().ok()?;
+            let last_used = parts.next()?.parse().ok()?;
+
+            Some((file_name.to_string(), Usage { count, last_used }))
+        })
+        .collect()
+}
+
+fn serialize(usage: &HashMap<String, Usage>) -> String {
+    usage
+        .iter()
+        .map(|(file_name, usage)| format!("{file_name}\t{}\t{}\n", usage.count, usage.last_used))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_round_trip() {
+        let usage = HashMap::from([(
+            "firefox.desktop".to_string(),
+            Usage {
+                count: 3,
+                last_used: 1_700_000_000,
+            },
+        )]);
+
+        assert_eq!(parse(&serialize(&usage)), usage);
+    }
+
+    #[test]
+    fn parse_skips_invalid_lines() {
+        let usage = parse("firefox.desktop\t3\nfoot.desktop\t1\t1700000000\n");
+
+        assert_eq!(usage.len(), 1);
+        assert!(usage.contains_key("foot.desktop"));
+    }
+
+    #[test]
+    fn score_decays_with_age() {
+        let now = 100 * DAY_SECS;
+        let usage = |age| Usage {
+            count: 2,
+            last_used: now - age,
+        };
+
+        assert!(score(usage(0), now) > score(usage(2 * DAY_SECS), now));
+        assert!(score(usage(2 * DAY_SECS), now) > score(usage(60 * DAY_SECS), now));
+    }
+}
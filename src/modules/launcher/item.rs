@@ -1,14 +1,19 @@
 use super::open_state::OpenState;
 use crate::channels::AsyncSenderExt;
+use crate::clients::compositor::WindowMatch;
 use crate::clients::wayland::ToplevelInfo;
 use crate::config::{BarPosition, TruncateMode};
-use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt, MouseButton};
+use crate::desktop_file::DesktopAction;
+use crate::gtk_helpers::{IronbarGlibExt, IronbarGtkExt, IronbarLabelExt, MouseButton};
 use crate::modules::launcher::{ItemEvent, LauncherUpdate};
 use crate::modules::{ModuleUpdateEvent, PopupButton};
 use crate::{image, read_lock};
+use gtk::gdk::{ContentProvider, DragAction};
+use gtk::gio::{AppInfo, Menu, SimpleAction, SimpleActionGroup};
 use gtk::prelude::*;
 use gtk::{
-    Align, Button, ContentFit, EventControllerMotion, Justification, Label, Orientation, Picture,
+    Align, Button, ContentFit, DragSource, DropTarget, EventControllerMotion, Justification, Label,
+    Orientation, Picture, PopoverMenu, WidgetPaintable,
 };
 use indexmap::IndexMap;
 use std::ops::Deref;
@@ -137,9 +142,10 @@ pub struct MenuState {
 #[derive(Clone)]
 pub struct ItemButton {
     pub button: ImageTextButton,
-    pub persistent: bool,
     pub show_names: bool,
     pub menu_state: Rc<RwLock<MenuState>>,
+    /// Context menu of the application's desktop actions.
+    actions_menu: PopoverMenu,
 }
 
 #[derive(Clone, Copy)]
@@ -231,6 +237,33 @@ impl ItemButton {
             });
         }
 
+        // the menu is populated once the controller has loaded the actions
+        {
+            let app_id = item.app_id.clone();
+            let controller_tx = controller_tx.clone();
+            let tx = tx.clone();
+
+            button.connect_pressed(MouseButton::Secondary, move || {
+                tx.send_spawn(ModuleUpdateEvent::ClosePopup);
+                controller_tx.send_spawn(ItemEvent::ShowActions(app_id.clone()));
+            });
+        }
+
+        let actions_menu = PopoverMenu::builder().build();
+        actions_menu.add_css_class("actions");
+        actions_menu.set_parent(&*button);
+
+        {
+            let tx = tx.clone();
+            actions_menu.connect_show(move |_| tx.send_spawn(ModuleUpdateEvent::LockVisible(true)));
+        }
+
+        {
+            let tx = tx.clone();
+            actions_menu
+                .connect_hide(move |_| tx.send_spawn(ModuleUpdateEvent::LockVisible(false)));
+        }
+
         let event_controller = EventControllerMotion::new();
 
         {
@@ -280,12 +313,77 @@ impl ItemButton {
 
         button.add_controller(event_controller);
 
+        connect_window_drag(&button, &item.app_id);
+        connect_favorite_drop(&button, &item.app_id, controller_tx.clone());
+
         Self {
             button,
-            persistent: item.favorite,
             show_names: appearance.show_names,
             menu_state,
+            actions_menu,
+        }
+    }
+
+    /// Opens the context menu with the application's desktop actions,
+    /// followed by an option to pin or unpin the application.
+    ///
+    /// Each action is launched with an activation token,
+    /// so that the new window can take focus.
+    pub fn show_actions(
+        &self,
+        app_id: &str,
+        actions: Vec<DesktopAction>,
+        controller_tx: &Sender<ItemEvent>,
+    ) {
+        let menu = Menu::new();
+        let action_group = SimpleActionGroup::new();
+
+        for (index, action) in actions.into_iter().enumerate() {
+            let name = format!("action_{index}");
+            menu.append(Some(&action.name), Some(&format!("launcher.{name}")));
+
+            let simple_action = SimpleAction::new(&name, None);
+
+            let button = self.button.button.clone();
+            let controller_tx = controller_tx.clone();
+            simple_action.connect_activate(move |_, _| {
+                let activation_token = button
+                    .display()
+                    .app_launch_context()
+                    .startup_notify_id(None::<&AppInfo>, &[])
+                    .map(String::from);
+
+                controller_tx.send_spawn(ItemEvent::LaunchAction(action.clone(), activation_token));
+            });
+
+            action_group.add_action(&simple_action);
+        }
+
+        {
+            let favorite = self.is_favorite();
+
+            let pin_menu = Menu::new();
+            pin_menu.append(
+                Some(if favorite { "Unpin" } else { "Pin" }),
+                Some("launcher.pin"),
+            );
+            menu.append_section(None, &pin_menu);
+
+            let simple_action = SimpleAction::new("pin", None);
+
+            let app_id = app_id.to_string();
+            let controller_tx = controller_tx.clone();
+            simple_action.connect_activate(move |_, _| {
+                controller_tx.send_spawn(ItemEvent::SetFavorite(app_id.clone(), !favorite));
+            });
+
+            action_group.add_action(&simple_action);
         }
+
+        self.button
+            .insert_action_group("launcher", Some(&action_group));
+        self.actions_menu.set_menu_model(Some(&menu));
+        self.actions_menu.popup();
     }
 
     pub fn set_open(&self, open: bool) {
@@ -300,6 +398,16 @@ impl ItemButton {
         self.update_class("focused", focused);
     }
 
+    /// Whether the item is a favourite,
+    /// and should stay on the bar once closed.
+    pub fn is_favorite(&self) -> bool {
+        self.button.has_css_class("favorite")
+    }
+
+    pub fn set_favorite(&self, favorite: bool) {
+        self.update_class("favorite", favorite);
+    }
+
     /// Adds or removes a class to the button based on `toggle`.
     fn update_class(&self, class: &str, toggle: bool) {
         if toggle {
@@ -310,11 +418,72 @@ impl ItemButton {
     }
 }
 
+/// Allows open items to be dragged onto a workspace button
+/// to move all of their windows to that workspace.
+fn connect_window_drag(button: &Button, app_id: &str) {
+    let drag_source = DragSource::builder().actions(DragAction::MOVE).build();
+
+    button.set_tag(
+        WindowMatch::TAG,
+        WindowMatch {
+            app_id: app_id.to_string(),
+            title: None,
+        },
+    );
+
+    {
+        let button = button.clone();
+        drag_source.connect_prepare(move |_, _, _| {
+            (button.has_css_class("open") || button.has_css_class("favorite"))
+                .then(|| ContentProvider::for_value(&button.to_value()))
+        });
+    }
+
+    drag_source.connect_drag_begin(|source, _| {
+        if let Some(widget) = source.widget() {
+            source.set_icon(Some(&WidgetPaintable::new(Some(&widget))), 0, 0);
+        }
+    });
+
+    button.add_controller(drag_source);
+}
+
+/// Allows a favourite to be dragged onto another favourite
+/// to move it to that position.
+fn connect_favorite_drop(button: &Button, app_id: &str, controller_tx: Sender<ItemEvent>) {
+    let drop_target = DropTarget::new(Button::static_type(), DragAction::MOVE);
+
+    {
+        let button = button.clone();
+        drop_target.connect_accept(move |_, _| button.has_css_class("favorite"));
+    }
+
+    let app_id = app_id.to_string();
+    drop_target.connect_drop(move |_, value, _, _| {
+        let Some(source_app_id) = value
+            .get::<Button>()
+            .ok()
+            .and_then(|source| source.get_tag::<WindowMatch>(WindowMatch::TAG).cloned())
+            .map(|window_match| window_match.app_id)
+        else {
+            return false;
+        };
+
+        if source_app_id != app_id {
+            controller_tx.send_spawn(ItemEvent::MoveFavorite(source_app_id, app_id.clone()));
+        }
+
+        true
+    });
+
+    button.add_controller(drop_target);
+}
+
 #[derive(Debug, Clone)]
 pub struct ImageTextButton {
     pub(crate) button: Button,
     pub(crate) label: Label,
-    picture: Picture,
+    pub(crate) picture: Picture,
 }
 
 impl ImageTextButton {
@@ -1,9 +1,14 @@
+mod favorites;
+mod frecency;
 mod item;
 mod open_state;
 mod pagination;
+mod search;
 
+use self::frecency::Frecency;
 use self::item::{AppearanceOptions, Item, ItemButton, Window};
 use self::open_state::OpenState;
+use self::search::{AppEntry, SearchView};
 use super::{
     Module, ModuleInfo, ModuleParts, ModulePopup, ModulePopupParts, ModuleUpdateEvent, PopupButton,
     WidgetContext,
@@ -11,16 +16,19 @@ use super::{
 use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
 use crate::clients::wayland::{self, ToplevelEvent};
 use crate::config::{CommonConfig, EllipsizeMode, LayoutConfig, TruncateMode, default};
-use crate::desktop_file::open_program;
+use crate::desktop_file::{DesktopAction, DesktopFile, open_action, open_program};
 use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::image::IconButton;
 use crate::modules::launcher::item::ImageTextButton;
 use crate::modules::launcher::pagination::{IconContext, Pagination};
 use crate::{arc_mut, lock, module_impl, rc_mut, spawn, write_lock};
 use color_eyre::Report;
 use gtk::prelude::*;
 use gtk::{Button, EventControllerMotion, Orientation};
+use gtk_layer_shell::{KeyboardMode, LayerShell};
 use indexmap::IndexMap;
 use serde::Deserialize;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use std::sync::Arc;
 use tokio::sync::mpsc;
@@ -33,6 +41,10 @@ pub struct LauncherModule {
     /// List of app IDs (or classes) to always show regardless of open state,
     /// in the order specified.
     ///
+    /// Favourites can also be pinned, unpinned and reordered from the bar.
+    /// Once changed, they are saved in Ironbar's data directory
+    /// and the saved list is used instead of this option.
+    ///
     /// **Default**: `null`
     favorites: Option<Vec<String>>,
 
@@ -76,6 +88,20 @@ pub struct LauncherModule {
     /// **Default**: `1000`.
     page_size: usize,
 
+    /// Whether to show a button at the start of the launcher
+    /// which opens a search of all installed applications.
+    ///
+    /// Results are ranked by how closely they match
+    /// and how often and recently they have been launched from search.
+    ///
+    /// **Default**: `false`
+    show_search: bool,
+
+    /// The maximum number of applications to show in search results.
+    ///
+    /// **Default**: `8`
+    max_search_results: usize,
+
     /// Module UI icons (separate from app icons shown for items).
     ///
     /// See [icons](#icons).
@@ -124,6 +150,8 @@ impl Default for LauncherModule {
             reversed: false,
             minimize_focused: true,
             page_size: 1000,
+            show_search: false,
+            max_search_results: 8,
             icons: Icons::default(),
             pagination_icon_size: default::IconSize::Tiny as i32,
             truncate: TruncateMode::default(),
@@ -152,6 +180,11 @@ struct Icons {
     ///
     /// **Default**: `>`
     page_forward: String,
+
+    /// Icon to show for the search button.
+    ///
+    /// **Default**: `󰍉`
+    search: String,
 }
 
 impl Default for Icons {
@@ -159,6 +192,7 @@ impl Default for Icons {
         Self {
             page_back: "󰅁".to_string(),
             page_forward: "󰅂".to_string(),
+            search: "󰍉".to_string(),
         }
     }
 }
@@ -179,6 +213,14 @@ pub enum LauncherUpdate {
     Focus(String, bool),
     /// Declares the item with `app_id` has been hovered over
     Hover(String),
+    /// Shows the search in the popup,
+    /// with the applications which can be launched.
+    Search(Vec<AppEntry>),
+    /// Opens the context menu for the item with `app_id`,
+    /// with its desktop file actions.
+    Actions(String, Vec<DesktopAction>),
+    /// Sets the favourites, in order.
+    Favorites(Vec<String>),
 }
 
 #[derive(Debug)]
@@ -187,6 +229,22 @@ pub enum ItemEvent {
     FocusWindow(usize),
     OpenItem(String),
     MinimizeItem(String),
+    /// Opens the popup for the button with the popup ID
+    /// to search applications.
+    OpenSearch(usize),
+    /// Launches the application with the desktop file name from search.
+    Launch(String),
+    /// Loads the desktop file actions for the item with `app_id`
+    /// to show in its context menu.
+    ShowActions(String),
+    /// Launches the desktop file action,
+    /// using the activation token if there is one.
+    LaunchAction(DesktopAction, Option<String>),
+    /// Pins or unpins the item with `app_id`.
+    SetFavorite(String, bool),
+    /// Moves the favourite with the first `app_id`
+    /// to the position of the favourite with the second.
+    MoveFavorite(String, String),
 }
 
 enum ItemOrWindow {
@@ -211,20 +269,19 @@ impl Module<gtk::Box> for LauncherModule {
         context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
         mut rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> crate::Result<()> {
-        let items = self
-            .favorites
-            .as_ref()
-            .map_or_else(IndexMap::new, |favorites| {
-                favorites
-                    .iter()
-                    .map(|app_id| {
-                        (
-                            app_id.to_string(),
-                            Item::new(app_id.to_string(), OpenState::Closed, true),
-                        )
-                    })
-                    .collect::<IndexMap<_, _>>()
-            });
+        let favorites = favorites::load()
+            .or_else(|| self.favorites.clone())
+            .unwrap_or_default();
+
+        let items = favorites
+            .iter()
+            .map(|app_id| {
+                (
+                    app_id.to_string(),
+                    Item::new(app_id.to_string(), OpenState::Closed, true),
+                )
+            })
+            .collect::<IndexMap<_, _>>();
 
         let items = arc_mut!(items);
         let items2 = Arc::clone(&items);
@@ -394,6 +451,11 @@ impl Module<gtk::Box> for LauncherModule {
         let launch_command_str: String = self.launch_command.clone();
 
         spawn(async move {
+            let mut favorites = favorites;
+
+            // only loaded once search is first used
+            let mut frecency = None;
+
             while let Some(event) = rx.recv().await {
                 if let ItemEvent::OpenItem(app_id) = event {
                     match desktop_files.find(&app_id).await {
@@ -403,6 +465,65 @@ impl Module<gtk::Box> for LauncherModule {
                         Ok(None) => warn!("Could not find applications file for {}", app_id),
                         Err(err) => error!("Failed to find parse file for {}: {}", app_id, err),
                     }
+                } else if let ItemEvent::OpenSearch(popup_id) = event {
+                    if frecency.is_none() {
+                        frecency = Some(Frecency::load().await);
+                    }
+
+                    match desktop_files.get_all().await {
+                        Ok(files) => {
+                            let apps = files
+                                .into_iter()
+                                .filter_map(|file| app_entry(file, frecency.as_ref()))
+                                .collect();
+
+                            tx.send_update(LauncherUpdate::Search(apps)).await;
+                            tx.send_expect(ModuleUpdateEvent::OpenPopup(popup_id)).await;
+                        }
+                        Err(err) => error!("Failed to load applications files: {err:?}"),
+                    }
+                } else if let ItemEvent::Launch(file_name) = event {
+                    if let Some(frecency) = &mut frecency {
+                        frecency.record(&file_name).await;
+                    }
+
+                    open_program(&file_name, &launch_command_str).await;
+                } else if let ItemEvent::ShowActions(app_id) = event {
+                    match desktop_files.find(&app_id).await {
+                        Ok(file) => {
+                            let actions = file.map(|file| file.actions).unwrap_or_default();
+                            tx.send_update(LauncherUpdate::Actions(app_id, actions))
+                                .await;
+                        }
+                        Err(err) => error!("Failed to find parse file for {}: {}", app_id, err),
+                    }
+                } else if let ItemEvent::LaunchAction(action, activation_token) = event {
+                    open_action(&action, activation_token.as_deref()).await;
+                } else if let ItemEvent::SetFavorite(app_id, favorite) = event {
+                    if favorite && !favorites.contains(&app_id) {
+                        favorites.push(app_id);
+                    } else if !favorite {
+                        favorites.retain(|fav| fav != &app_id);
+
+                        // closed items are only kept while they are favourites
+                        let mut items = lock!(items);
+                        if items
+                            .get(&app_id)
+                            .is_some_and(|item| item.windows.is_empty())
+                        {
+                            items.shift_remove(&app_id);
+                        }
+                    }
+
+                    tx.send_update(LauncherUpdate::Favorites(favorites.clone()))
+                        .await;
+                    favorites::save(&favorites).await;
+                } else if let ItemEvent::MoveFavorite(app_id, target) = event {
+                    if favorites::move_favorite(&mut favorites, &app_id, &target) {
+                        tx.send_update(LauncherUpdate::Favorites(favorites.clone()))
+                            .await;
+                        favorites::save(&favorites).await;
+                    }
                 } else {
                     tx.send_expect(ModuleUpdateEvent::ClosePopup).await;
 
@@ -419,7 +540,13 @@ impl Module<gtk::Box> for LauncherModule {
                             })
                         }
                         ItemEvent::FocusWindow(id) => Some(id),
-                        ItemEvent::OpenItem(_) => unreachable!(),
+                        ItemEvent::OpenItem(_)
+                        | ItemEvent::OpenSearch(_)
+                        | ItemEvent::Launch(_)
+                        | ItemEvent::ShowActions(_)
+                        | ItemEvent::LaunchAction(..)
+                        | ItemEvent::SetFavorite(..)
+                        | ItemEvent::MoveFavorite(..) => unreachable!(),
                     };
 
                     if let Some(id) = id
@@ -449,7 +576,7 @@ impl Module<gtk::Box> for LauncherModule {
         let container = gtk::Box::new(self.layout.orientation(info), 0);
         let page_size = self.page_size;
 
-        let image_provider = context.ironbar.image_provider();
+        let image_provider = context.image_provider();
         let buttons = rc_mut!(IndexMap::<String, ItemButton>::new());
 
         let pagination = Pagination::new(
@@ -464,6 +591,30 @@ impl Module<gtk::Box> for LauncherModule {
             &image_provider,
         );
 
+        let search_button = self.show_search.then(|| {
+            let button = IconButton::new(
+                &self.icons.search,
+                self.pagination_icon_size,
+                image_provider.clone(),
+            );
+            button.add_css_class("search");
+            button.ensure_popup_id();
+            container.prepend(&*button);
+
+            let tx = context.controller_tx.clone();
+            button.connect_clicked(move |button| {
+                // the search entry needs keyboard focus,
+                // which is returned once the popup closes
+                if let Some(window) = button.root().and_downcast::<gtk::Window>() {
+                    window.set_keyboard_mode(KeyboardMode::OnDemand);
+                }
+
+                tx.send_spawn(ItemEvent::OpenSearch(button.popup_id()));
+            });
+
+            button
+        });
+
         {
             let appearance_options = AppearanceOptions {
                 show_names: self.show_names,
@@ -539,7 +690,7 @@ impl Module<gtk::Box> for LauncherModule {
 
                             let button = buttons.borrow().get(&app_id).cloned();
                             if let Some(button) = button {
-                                if button.persistent {
+                                if button.is_favorite() {
                                     button.set_open(false);
                                     if button.show_names {
                                         button.button.label.set_label(&app_id);
@@ -582,7 +733,15 @@ impl Module<gtk::Box> for LauncherModule {
                                 button.button.label.set_label(&name);
                             }
                         }
-                        LauncherUpdate::Hover(_) => {}
+                        LauncherUpdate::Actions(app_id, actions) => {
+                            if let Some(button) = buttons.borrow().get(&app_id) {
+                                button.show_actions(&app_id, actions, controller_tx);
+                            }
+                        }
+                        LauncherUpdate::Favorites(favorites) => {
+                            set_favorites(container, &buttons, &favorites, self.reversed);
+                        }
+                        LauncherUpdate::Hover(_) | LauncherUpdate::Search(_) => {}
                     }
                 },
             );
@@ -596,6 +755,12 @@ impl Module<gtk::Box> for LauncherModule {
                     .values()
                     .find(|b| b.button.button.popup_id() == id)
                     .map(|b| b.button.button.clone())
+                    .or_else(|| {
+                        search_button
+                            .as_ref()
+                            .filter(|button| button.popup_id() == id)
+                            .map(|button| (**button).clone())
+                    })
             }))
             .map(ModulePopupParts::disable_autohide);
 
@@ -614,11 +779,38 @@ impl Module<gtk::Box> for LauncherModule {
 
         let container = gtk::Box::new(Orientation::Vertical, 0);
 
+        // whether the popup is showing search instead of an item's windows
+        let searching = Rc::new(Cell::new(false));
+
         // Close popup when mouse leaves the popup container
-        let tx = context.tx.clone();
-        let event_controller = EventControllerMotion::new();
-        event_controller.connect_leave(move |_| tx.send_spawn(ModuleUpdateEvent::ClosePopup));
-        container.add_controller(event_controller);
+        {
+            let tx = context.tx.clone();
+            let searching = searching.clone();
+            let event_controller = EventControllerMotion::new();
+            event_controller.connect_leave(move |_| {
+                if !searching.get() {
+                    tx.send_spawn(ModuleUpdateEvent::ClosePopup);
+                }
+            });
+            container.add_controller(event_controller);
+        }
+
+        let search_view = SearchView::new(
+            self.max_search_results,
+            self.icon_size,
+            context.image_provider(),
+            context.controller_tx.clone(),
+            context.tx.clone(),
+        );
+
+        // start each time the search opens with a fresh query
+        {
+            let search_view = search_view.clone();
+            search_view
+                .container
+                .clone()
+                .connect_map(move |_| search_view.reset());
+        }
 
         // we need some content to force the container to have a size
         let placeholder = Button::with_label("PLACEHOLDER");
@@ -700,6 +892,8 @@ impl Module<gtk::Box> for LauncherModule {
                         }
                     }
                     LauncherUpdate::Hover(app_id) => {
+                        searching.set(false);
+
                         // empty current buttons
                         for child in container.children() {
                             container.remove(&child);
@@ -716,6 +910,17 @@ impl Module<gtk::Box> for LauncherModule {
                             container.set_width_request(MAX_WIDTH);
                         }
                     }
+                    LauncherUpdate::Search(apps) => {
+                        searching.set(true);
+
+                        for child in container.children() {
+                            container.remove(&child);
+                        }
+
+                        search_view.set_apps(apps);
+                        container.append(&search_view.container);
+                        container.set_width_request(MAX_WIDTH);
+                    }
                     _ => {}
                 }
             });
@@ -723,3 +928,75 @@ impl Module<gtk::Box> for LauncherModule {
         Some(container)
     }
 }
+
+/// Updates which buttons are favourites,
+/// removing any unpinned buttons which are not open
+/// and moving the favourites to the start in order.
+fn set_favorites(
+    container: &gtk::Box,
+    buttons: &RefCell<IndexMap<String, ItemButton>>,
+    favorites: &[String],
+    reversed: bool,
+) {
+    buttons.borrow_mut().retain(|app_id, button| {
+        let favorite = favorites.contains(app_id);
+        button.set_favorite(favorite);
+
+        let keep = favorite || button.button.has_css_class("open");
+        if !keep {
+            container.remove(&*button.button);
+        }
+
+        keep
+    });
+
+    // items are added after the controls,
+    // or before them when reversed
+    let mut sibling = if reversed {
+        container
+            .children()
+            .find(|child| !child.has_css_class("item"))
+    } else {
+        container
+            .children()
+            .take_while(|child| !child.has_css_class("item"))
+            .last()
+    };
+
+    let buttons = buttons.borrow();
+    for app_id in favorites {
+        let Some(button) = buttons.get(app_id) else {
+            continue;
+        };
+
+        let widget = button.button.upcast_ref::<gtk::Widget>();
+
+        if reversed {
+            let previous = sibling.as_ref().and_then(WidgetExt::prev_sibling);
+            if previous.as_ref() != Some(widget) {
+                container.reorder_child_after(widget, previous.as_ref());
+            }
+        } else {
+            container.reorder_child_after(widget, sibling.as_ref());
+        }
+
+        sibling = Some(widget.clone());
+    }
+}
+
+/// Creates a search entry for the desktop file,
+/// if it is a visible application.
+fn app_entry(file: DesktopFile, frecency: Option<&Frecency>) -> Option<AppEntry> {
+    if file.app_type.as_deref() != Some("Application") || file.no_display == Some(true) {
+        return None;
+    }
+
+    let frecency = frecency.map_or(0.0, |frecency| frecency.score(&file.file_name));
+
+    Some(AppEntry {
+        name: file.name?,
+        icon: file.icon,
+        file_name: file.file_name,
+        frecency,
+    })
+}
@@ -56,7 +56,8 @@ impl Pagination {
 
             scroll_fwd.connect_clicked(move |btn| {
                 let mut offset = offset.borrow_mut();
-                let child_count = container.children().count();
+                // offsets start at 1, after the controls
+                let child_count = Self::items(&container).count() + 1;
 
                 *offset = std::cmp::min(child_count - 1, *offset + page_size);
 
@@ -102,12 +103,17 @@ impl Pagination {
         }
     }
 
+    /// Gets the item buttons in the container,
+    /// skipping the controls and any other buttons.
+    fn items(container: &gtk::Box) -> impl Iterator<Item = gtk::Widget> {
+        container
+            .children()
+            .filter(|child| child.has_css_class("item"))
+    }
+
     fn update_page(container: &gtk::Box, offset: usize, page_size: usize) {
-        for (i, btn) in container.children().enumerate() {
-            // skip offset buttons
-            if i == 0 {
-                continue;
-            }
+        for (i, btn) in Self::items(container).enumerate() {
+            let i = i + 1;
 
             if i >= offset && i < offset + page_size {
                 btn.set_visible(true);
@@ -0,0 +1,328 @@
+use super::ItemEvent;
+use super::item::ImageTextButton;
+use crate::channels::AsyncSenderExt;
+use crate::gtk_helpers::{IronbarGlibExt, IronbarGtkExt};
+use crate::image;
+use crate::modules::ModuleUpdateEvent;
+use glib::Propagation;
+use gtk::gdk::Key;
+use gtk::prelude::*;
+use gtk::{Align, EventControllerKey, Orientation, PropagationPhase, SearchEntry, Widget};
+use std::cell::RefCell;
+use std::rc::Rc;
+use tokio::sync::mpsc;
+
+/// How much an application's frecency counts towards its rank,
+/// relative to how closely it matches the query.
+const FRECENCY_WEIGHT: f64 = 2.0;
+
+/// An application which can be launched from search.
+#[derive(Debug, Clone)]
+pub struct AppEntry {
+    pub file_name: String,
+    pub name: String,
+    pub icon: Option<String>,
+    /// See [`super::frecency::Frecency::score`].
+    pub frecency: f64,
+}
+
+impl AppEntry {
+    /// Gets the rank of the application for the query,
+    /// or `None` if it does not match.
+    ///
+    /// Applications are matched on their name or desktop file name.
+    fn rank(&self, query: &str) -> Option<f64> {
+        let file_name = self
+            .file_name
+            .strip_suffix(".desktop")
+            .unwrap_or(&self.file_name);
+
+        let score = match (
+            fuzzy_score(query, &self.name),
+            fuzzy_score(query, file_name),
+        ) {
+            (Some(name), Some(file_name)) => name.max(file_name),
+            (score, None) | (None, score) => score?,
+        };
+
+        Some(f64::from(score) + self.frecency * FRECENCY_WEIGHT)
+    }
+}
+
+/// Scores how well the query matches the value,
+/// or returns `None` if every character of the query
+/// does not appear in the value in the same order.
+/// Case and whitespace in the query are ignored.
+///
+/// Each matched character scores a point,
+/// with bonuses for matches at the start of a word
+/// and for consecutive matches.
+/// The best scoring set of matches is used.
+fn fuzzy_score(query: &str, value: &str) -> Option<u32> {
+    const WORD_START_BONUS: u32 = 5;
+    const CONSECUTIVE_BONUS: u32 = 3;
+
+    let value = value
+        .chars()
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<_>>();
+
+    // the best score for the query so far
+    // with its last character matched at each position
+    let mut prev: Option<Vec<Option<u32>>> = None;
+
+    for query_char in query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+    {
+        // the best score of the previous row before the current position
+        let mut best_before = prev.is_none().then_some(0);
+        let mut row = vec![None; value.len()];
+
+        for (index, &c) in value.iter().enumerate() {
+            if c == query_char {
+                let consecutive = index
+                    .checked_sub(1)
+                    .and_then(|prev_index| prev.as_ref()?[prev_index])
+                    .map(|score| score + CONSECUTIVE_BONUS);
+
+                if let Some(base) = best_before.max(consecutive) {
+                    let word_start = index == 0 || !value[index - 1].is_alphanumeric();
+                    row[index] = Some(base + 1 + if word_start { WORD_START_BONUS } else { 0 });
+                }
+            }
+
+            if let Some(prev) = &prev {
+                best_before = best_before.max(prev[index]);
+            }
+        }
+
+        prev = Some(row);
+    }
+
+    match prev {
+        Some(row) => row.into_iter().flatten().max(),
+        None => Some(0),
+    }
+}
+
+/// The search entry and list of matching applications shown in the popup.
+///
+/// The highlighted result can be moved with the arrow keys
+/// and launched with enter.
+#[derive(Clone)]
+pub struct SearchView {
+    pub container: gtk::Box,
+    entry: SearchEntry,
+    results: gtk::Box,
+    apps: Rc<RefCell<Vec<AppEntry>>>,
+    max_results: usize,
+    icon_size: i32,
+    image_provider: image::Provider,
+    controller_tx: mpsc::Sender<ItemEvent>,
+    tx: mpsc::Sender<ModuleUpdateEvent<super::LauncherUpdate>>,
+}
+
+impl SearchView {
+    pub fn new(
+        max_results: usize,
+        icon_size: i32,
+        image_provider: image::Provider,
+        controller_tx: mpsc::Sender<ItemEvent>,
+        tx: mpsc::Sender<ModuleUpdateEvent<super::LauncherUpdate>>,
+    ) -> Self {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+        container.add_css_class("search");
+
+        let entry = SearchEntry::new();
+        entry.add_css_class("entry");
+        container.append(&entry);
+
+        let results = gtk::Box::new(Orientation::Vertical, 0);
+        results.add_css_class("results");
+        container.append(&results);
+
+        let view = Self {
+            container,
+            entry,
+            results,
+            apps: Rc::default(),
+            max_results,
+            icon_size,
+            image_provider,
+            controller_tx,
+            tx,
+        };
+
+        {
+            let view = view.clone();
+            view.entry
+                .clone()
+                .connect_search_changed(move |_| view.refresh());
+        }
+
+        let key_controller = EventControllerKey::new();
+        key_controller.set_propagation_phase(PropagationPhase::Capture);
+
+        {
+            let view = view.clone();
+            key_controller.connect_key_pressed(move |_, key, _, _| match key {
+                Key::Up => {
+                    view.move_selection(-1);
+                    Propagation::Stop
+                }
+                Key::Down => {
+                    view.move_selection(1);
+                    Propagation::Stop
+                }
+                Key::Return | Key::KP_Enter => {
+                    if let Some(file_name) = view
+                        .results
+                        .children()
+                        .find(|row| row.has_css_class("selected"))
+                        .and_then(|row| row.get_tag::<String>(FILE_NAME_TAG).cloned())
+                    {
+                        view.launch(file_name);
+                    }
+                    Propagation::Stop
+                }
+                _ => Propagation::Proceed,
+            });
+        }
+
+        view.container.add_controller(key_controller);
+
+        view
+    }
+
+    /// Replaces the applications which can be searched.
+    pub fn set_apps(&self, apps: Vec<AppEntry>) {
+        *self.apps.borrow_mut() = apps;
+        self.refresh();
+    }
+
+    /// Clears the query and focuses the entry,
+    /// ready for typing.
+    pub fn reset(&self) {
+        self.entry.set_text("");
+        self.entry.grab_focus();
+        self.refresh();
+    }
+
+    /// Shows the best matching applications for the current query,
+    /// highlighting the first.
+    fn refresh(&self) {
+        while let Some(child) = self.results.first_child() {
+            self.results.remove(&child);
+        }
+
+        let query = self.entry.text();
+
+        let mut matches = self
+            .apps
+            .borrow()
+            .iter()
+            .filter_map(|app| app.rank(&query).map(|rank| (rank, app.clone())))
+            .collect::<Vec<_>>();
+
+        matches.sort_by(|(rank_a, app_a), (rank_b, app_b)| {
+            rank_b
+                .total_cmp(rank_a)
+                .then_with(|| app_a.name.cmp(&app_b.name))
+        });
+
+        for (index, (_, app)) in matches.into_iter().take(self.max_results).enumerate() {
+            let button = ImageTextButton::new(Orientation::Horizontal);
+            button.add_css_class("result");
+            button.label.set_label(&app.name);
+
+            if let Some(child) = button.child() {
+                child.set_halign(Align::Start);
+            }
+
+            if let Some(icon) = app.icon {
+                let button = button.clone();
+                let image_provider = self.image_provider.clone();
+                let icon_size = self.icon_size;
+
+                glib::spawn_future_local(async move {
+                    image_provider
+                        .load_into_picture_silent(&icon, icon_size, true, &button.picture)
+                        .await;
+                });
+            }
+
+            if index == 0 {
+                button.add_css_class("selected");
+            }
+
+            button.set_tag(FILE_NAME_TAG, app.file_name.clone());
+
+            {
+                let view = self.clone();
+                button.connect_clicked(move |_| view.launch(app.file_name.clone()));
+            }
+
+            self.results.append(&*button);
+        }
+    }
+
+    /// Moves the highlight by `offset` results,
+    /// stopping at the first and last results.
+    fn move_selection(&self, offset: isize) {
+        let rows = self.results.children().collect::<Vec<Widget>>();
+        if rows.is_empty() {
+            return;
+        }
+
+        let index = rows
+            .iter()
+            .position(|row| row.has_css_class("selected"))
+            .map_or(0, |index| {
+                index.saturating_add_signed(offset).min(rows.len() - 1)
+            });
+
+        for row in &rows {
+            row.remove_css_class("selected");
+        }
+
+        rows[index].add_css_class("selected");
+    }
+
+    fn launch(&self, file_name: String) {
+        self.controller_tx.send_spawn(ItemEvent::Launch(file_name));
+        self.tx.send_spawn(ModuleUpdateEvent::ClosePopup);
+    }
+}
+
+/// The tag storing the desktop file name on each result.
+const FILE_NAME_TAG: &str = "file_name";
+
+#[cfg(test)]
+mod tests {
+    use super::fuzzy_score;
+
+    #[test]
+    fn fuzzy_score_no_match() {
+        assert_eq!(fuzzy_score("xyz", "firefox"), None);
+        assert_eq!(fuzzy_score("fxo", "firefox"), None);
+        assert_eq!(fuzzy_score("firefox", "fire"), None);
+    }
+
+    #[test]
+    fn fuzzy_score_empty_query() {
+        assert_eq!(fuzzy_score("", "firefox"), Some(0));
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_word_starts() {
+        assert!(fuzzy_score("s", "Visual Studio") > fuzzy_score("s", "Visual"));
+        assert!(fuzzy_score("vs", "Visual Studio") > fuzzy_score("vs", "Visual"));
+    }
+
+    #[test]
+    fn fuzzy_score_prefers_consecutive() {
+        assert!(fuzzy_score("ab", "xaby") > fuzzy_score("ab", "xayb"));
+    }
+}
@@ -0,0 +1,198 @@
+//! Minimal async IMAP client,
+//! supporting only what is needed to count and watch for unread messages.
+
+use color_eyre::{Report, Result};
+use rustls_platform_verifier::ConfigVerifierExt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_rustls::TlsConnector;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::ClientConfig;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tracing::trace;
+
+/// An authenticated connection to an IMAP server over TLS.
+#[derive(Debug)]
+pub struct Connection {
+    stream: BufReader<TlsStream<TcpStream>>,
+    /// The number of the last command tag sent.
+    tag: u32,
+}
+
+impl Connection {
+    /// Connects to the server and logs in.
+    pub async fn connect(host: &str, port: u16, username: &str, password: &str) -> Result<Self> {
+        let config = ClientConfig::with_platform_verifier()?;
+        let connector = TlsConnector::from(Arc::new(config));
+
+        let server_name = ServerName::try_from(host.to_string())?;
+        let stream = TcpStream::connect((host, port)).await?;
+        let stream = connector.connect(server_name, stream).await?;
+
+        let mut connection = Self {
+            stream: BufReader::new(stream),
+            tag: 0,
+        };
+
+        let greeting = connection.read_line().await?;
+        if !greeting.starts_with("* OK") {
+            return Err(Report::msg(format!("Unexpected greeting: {greeting}")));
+        }
+
+        connection
+            .command(&format!("LOGIN {} {}", quote(username), quote(password)))
+            .await?;
+
+        Ok(connection)
+    }
+
+    /// Checks whether the server supports the `IDLE` extension,
+    /// used to be told about changes rather than polling.
+    pub async fn supports_idle(&mut self) -> Result<bool> {
+        let responses = self.command("CAPABILITY").await?;
+
+        Ok(responses.iter().any(|response| {
+            response.starts_with("* CAPABILITY")
+                && response
+                    .split_whitespace()
+                    .any(|capability| capability.eq_ignore_ascii_case("IDLE"))
+        }))
+    }
+
+    /// Opens the mailbox as read-only,
+    /// so that checking it never marks messages as read.
+    pub async fn examine(&mut self, mailbox: &str) -> Result<()> {
+        self.command(&format!("EXAMINE {}", quote(mailbox)))
+            .await
+            .map(|_| ())
+    }
+
+    /// Gets the number of unread messages in the open mailbox.
+    pub async fn unread(&mut self) -> Result<usize> {
+        let responses = self.command("SEARCH UNSEEN").await?;
+
+        Ok(responses
+            .iter()
+            .filter_map(|response| response.strip_prefix("* SEARCH"))
+            .map(|ids| ids.split_whitespace().count())
+            .sum())
+    }
+
+    /// Keeps the connection alive, checking for changes.
+    /// Used in place of [`Connection::idle`] where it is not supported.
+    pub async fn noop(&mut self) -> Result<()> {
+        self.command("NOOP").await.map(|_| ())
+    }
+
+    /// Waits until the server reports a change to the open mailbox,
+    /// or the timeout passes.
+    pub async fn idle(&mut self, timeout: Duration) -> Result<()> {
+        let tag = self.send("IDLE").await?;
+
+        loop {
+            let line = self.read_line().await?;
+            if line.starts_with('+') {
+                break;
+            }
+
+            if line.starts_with(&tag) {
+                return Err(Report::msg(format!("IDLE failed: {line}")));
+            }
+        }
+
+        let changed = tokio::time::timeout(timeout, async {
+            loop {
+                let line = self.read_line().await?;
+
+                // servers periodically send `* OK` to keep the connection open
+                if line.starts_with('*') && !line.starts_with("* OK") {
+                    return Ok::<_, Report>(());
+                }
+            }
+        })
+        .await;
+
+        if let Ok(Err(err)) = changed {
+            return Err(err);
+        }
+
+        self.stream.write_all(b"DONE\r\n").await?;
+        self.stream.flush().await?;
+
+        self.read_until_tagged(&tag).await.map(|_| ())
+    }
+
+    /// Sends the command and waits for it to complete,
+    /// returning the untagged responses received in the meantime.
+    async fn command(&mut self, command: &str) -> Result<Vec<String>> {
+        let tag = self.send(command).await?;
+        self.read_until_tagged(&tag).await
+    }
+
+    /// Sends the command with a new tag, returning the tag.
+    async fn send(&mut self, command: &str) -> Result<String> {
+        self.tag += 1;
+        let tag = format!("A{:04}", self.tag);
+
+        // the command may contain the password
+        trace!("sending IMAP command {tag}");
+
+        self.stream
+            .write_all(format!("{tag} {command}\r\n").as_bytes())
+            .await?;
+        self.stream.flush().await?;
+
+        Ok(tag)
+    }
+
+    /// Reads responses until the one tagged with `tag`,
+    /// returning the untagged responses received before it.
+    async fn read_until_tagged(&mut self, tag: &str) -> Result<Vec<String>> {
+        let mut responses = vec![];
+
+        loop {
+            let line = self.read_line().await?;
+
+            if let Some(status) = line.strip_prefix(tag) {
+                let status = status.trim_start();
+
+                return if status.starts_with("OK") {
+                    Ok(responses)
+                } else {
+                    Err(Report::msg(format!("IMAP command failed: {status}")))
+                };
+            }
+
+            responses.push(line);
+        }
+    }
+
+    /// Reads a single response line, without the trailing CRLF.
+    async fn read_line(&mut self) -> Result<String> {
+        let mut line = vec![];
+        let bytes = self.stream.read_until(b'\n', &mut line).await?;
+
+        if bytes == 0 {
+            return Err(Report::msg("Connection closed by server"));
+        }
+
+        Ok(String::from_utf8_lossy(&line).trim_end().to_string())
+    }
+}
+
+/// Quotes a string for use as a command argument.
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_escapes() {
+        assert_eq!(quote(r#"pa"ss\word"#), r#""pa\"ss\\word""#);
+    }
+}
@@ -0,0 +1,316 @@
+mod imap;
+
+use self::imap::Connection;
+use crate::channels::AsyncSenderExt;
+use crate::clients::reconnect;
+use crate::config::{CommonConfig, LayoutConfig, Secret};
+use crate::gtk_helpers::IronbarLabelExt;
+use crate::modules::{Module, ModuleInfo, ModuleParts, WidgetContext};
+use crate::panic_guard;
+use crate::script::Script;
+use crate::{module_impl, poll, spawn, spawn_blocking};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Button, Label};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::error;
+
+/// How long to wait in `IDLE` before restarting it.
+/// Servers may drop connections which stay idle for 30 minutes.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(25 * 60);
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct MailModule {
+    /// The IMAP accounts to check for unread mail.
+    /// Unread counts across all accounts are combined into a single total.
+    ///
+    /// **Default**: `[]`
+    accounts: Vec<Account>,
+
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{icon} {unread}`
+    #[serde(skip)]
+    format: String,
+
+    /// The icon to use for the `{icon}` token.
+    ///
+    /// **Default**: `󰇮`
+    icon: String,
+
+    /// Command to run when the widget is clicked,
+    /// such as opening a mail client.
+    ///
+    /// **Default**: `null`
+    command: Option<String>,
+
+    /// Time in milliseconds between checks,
+    /// for servers which do not support `IDLE`.
+    /// Set from the common `interval` option.
+    ///
+    /// **Default**: `60000`
+    #[serde(skip)]
+    interval: u64,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for MailModule {
+    fn default() -> Self {
+        Self {
+            accounts: vec![],
+            format: "{icon} {unread}".to_string(),
+            icon: "󰇮".to_string(),
+            command: None,
+            interval: 60_000,
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct Account {
+    /// The name to show for the account in the tooltip.
+    /// Defaults to the username.
+    ///
+    /// **Default**: `null`
+    #[serde(default)]
+    name: Option<String>,
+
+    /// The IMAP server hostname.
+    /// Only TLS connections are supported.
+    host: String,
+
+    /// The IMAP server port.
+    ///
+    /// **Default**: `993`
+    #[serde(default = "default_port")]
+    port: u16,
+
+    /// The username to log in with.
+    username: String,
+
+    /// The password to log in with.
+    /// See [secrets](configuration-guide#34-secrets).
+    password: Secret,
+
+    /// The mailbox to count unread messages in.
+    ///
+    /// **Default**: `INBOX`
+    #[serde(default = "default_mailbox")]
+    mailbox: String,
+}
+
+const fn default_port() -> u16 {
+    993
+}
+
+fn default_mailbox() -> String {
+    "INBOX".to_string()
+}
+
+impl Account {
+    fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.username)
+    }
+}
+
+/// Logs in and sends the unread count whenever it changes,
+/// until the connection fails.
+async fn watch_account(
+    account: &Account,
+    password: &str,
+    interval: Duration,
+    session: &reconnect::Session,
+    tx: &mpsc::Sender<(usize, usize)>,
+    index: usize,
+) -> Result<()> {
+    let mut connection =
+        Connection::connect(&account.host, account.port, &account.username, password).await?;
+
+    let supports_idle = connection.supports_idle().await?;
+    connection.examine(&account.mailbox).await?;
+
+    session.connected();
+
+    loop {
+        let unread = connection.unread().await?;
+        tx.send_expect((index, unread)).await;
+
+        if supports_idle {
+            connection.idle(IDLE_TIMEOUT).await?;
+        } else {
+            poll::sleep(interval).await;
+            connection.noop().await?;
+        }
+    }
+}
+
+impl Module<Button> for MailModule {
+    /// The unread count of each account, in config order.
+    /// Accounts which have not yet been checked are `None`.
+    type SendMessage = Vec<Option<usize>>;
+    type ReceiveMessage = ();
+
+    module_impl!("mail");
+
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
+    fn set_interval(&mut self, interval: u64) {
+        self.interval = interval;
+    }
+
+    fn coalesce_key(_update: &Self::SendMessage) -> Option<String> {
+        // each update is the full state
+        Some(String::new())
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let interval = Duration::from_millis(self.interval);
+
+        // each account sends its own count here,
+        // and the counts for every account are sent in response
+        let (count_tx, mut count_rx) = mpsc::channel(8);
+
+        for (index, account) in self.accounts.iter().cloned().enumerate() {
+            let count_tx = count_tx.clone();
+
+            spawn(async move {
+                // resolving the password can run a command,
+                // so happens off the main thread
+                let secret = account.password.clone();
+                let password = match spawn_blocking(move || secret.resolve()).await {
+                    Ok(Ok(password)) => password,
+                    Ok(Err(err)) => {
+                        let err =
+                            err.wrap_err(format!("Unable to check mail for {}", account.username));
+                        panic_guard::report_error(&err);
+                        return;
+                    }
+                    Err(err) => {
+                        error!("{err:?}");
+                        return;
+                    }
+                };
+
+                reconnect::run("mail", move |session| {
+                    let account = account.clone();
+                    let password = password.clone();
+                    let count_tx = count_tx.clone();
+
+                    async move {
+                        watch_account(&account, &password, interval, &session, &count_tx, index)
+                            .await
+                    }
+                })
+                .await;
+            });
+        }
+
+        let tx = context.tx.clone();
+        let mut counts = vec![None; self.accounts.len()];
+
+        spawn(async move {
+            while let Some((index, unread)) = count_rx.recv().await {
+                counts[index] = Some(unread);
+                tx.send_update(counts.clone()).await;
+            }
+        });
+
+        let command = self.command.as_deref().map(Script::from);
+
+        spawn(async move {
+            while rx.recv().await.is_some() {
+                if let Some(script) = &command {
+                    script.run_as_oneshot(None);
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let label = Label::builder()
+            .use_markup(true)
+            .justify(self.layout.justify.into())
+            .build();
+
+        let button = Button::new();
+        button.set_child(Some(&label.rotated(self.layout.angle())));
+        button.set_accessible_label("Mail");
+
+        {
+            let tx = context.controller_tx.clone();
+            button.connect_clicked(move |_| tx.send_spawn(()));
+        }
+
+        let names = self
+            .accounts
+            .iter()
+            .map(|account| account.name().to_string())
+            .collect::<Vec<_>>();
+
+        {
+            let button = button.clone();
+            let format = self.format.clone();
+            let icon = self.icon.clone();
+
+            context.subscribe().recv_glib((), move |(), counts| {
+                let unread = counts.iter().flatten().sum::<usize>();
+
+                if unread > 0 {
+                    button.add_css_class("unread");
+                } else {
+                    button.remove_css_class("unread");
+                }
+
+                let text = format
+                    .replace("{icon}", &icon)
+                    .replace("{unread}", &unread.to_string());
+
+                label.set_label_escaped(&text);
+
+                let tooltip = names
+                    .iter()
+                    .zip(&counts)
+                    .map(|(name, count)| match count {
+                        Some(count) => format!("{name}: {count}"),
+                        None => format!("{name}: -"),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                button.set_tooltip_text(Some(&tooltip));
+                button.set_accessible_description(&format!("{unread} unread"));
+            });
+        }
+
+        Ok(ModuleParts::new(button, None))
+    }
+}
@@ -114,7 +114,7 @@ impl Module<Button> for MenuModule {
         button.set_child(Some(&button_contents));
 
         if let Some(ref label_icon) = self.label_icon {
-            let image_provider = context.ironbar.image_provider();
+            let image_provider = context.image_provider();
 
             let gtk_image = gtk::Picture::builder()
                 .content_fit(ContentFit::ScaleDown)
@@ -168,7 +168,7 @@ impl Module<Button> for MenuModule {
         context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
         info: &ModuleInfo,
     ) -> Option<gtk::Box> {
-        let image_provider = context.ironbar.image_provider();
+        let image_provider = context.image_provider();
 
         let alignment = {
             match info.bar_position {
@@ -1,20 +1,26 @@
+use std::cell::{Cell, RefCell};
 use std::fmt::{Debug, Formatter};
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::Ironbar;
+use crate::animation;
 use crate::bar::Bar;
-use crate::channels::{MpscReceiverExt, SyncSenderExt};
+use crate::channels::{self, AsyncSenderExt, BroadcastReceiverExt, MpscReceiverExt, SyncSenderExt};
 use crate::clients::{ClientResult, ProvidesClient, ProvidesFallibleClient};
 use crate::config::{BarPosition, CommonConfig, TransitionType};
-use crate::gtk_helpers::IronbarGlibExt;
+use crate::gtk_helpers::{IronbarGlibExt, IronbarGtkExt};
+use crate::image;
+use crate::panic_guard::{self, Failure, PanicGuard};
 use crate::popup::{ButtonFinder, Popup};
 use color_eyre::Result;
+use glib::SourceId;
 use gtk::gdk::Monitor;
 use gtk::prelude::*;
-use gtk::{Application, Button, Orientation, Revealer, Widget};
+use gtk::{Application, Button, Label, Orientation, Revealer, RevealerTransitionType, Widget};
 use tokio::sync::{broadcast, mpsc};
-use tracing::{debug, trace};
+use tracing::{debug, error, trace, warn};
 
 #[cfg(feature = "battery")]
 pub mod battery;
@@ -22,8 +28,12 @@ pub mod battery;
 pub mod bindmode;
 #[cfg(feature = "bluetooth")]
 pub mod bluetooth;
+#[cfg(feature = "brightness")]
+pub mod brightness;
 #[cfg(feature = "cairo")]
 pub mod cairo;
+#[cfg(feature = "capture")]
+pub mod capture;
 #[cfg(feature = "clipboard")]
 pub mod clipboard;
 /// Displays the current date and time.
@@ -34,34 +44,72 @@ pub mod clipboard;
 /// with second-level precision and a calendar.
 #[cfg(feature = "clock")]
 pub mod clock;
+#[cfg(feature = "containers")]
+pub mod containers;
 #[cfg(feature = "custom")]
 pub mod custom;
+#[cfg(feature = "feed")]
+pub mod feed;
 #[cfg(feature = "focused")]
 pub mod focused;
+#[cfg(feature = "git")]
+pub mod git;
+#[cfg(feature = "home_assistant")]
+pub mod home_assistant;
 #[cfg(feature = "inhibit")]
 pub mod inhibit;
+#[cfg(feature = "kdeconnect")]
+pub mod kdeconnect;
 #[cfg(feature = "keyboard")]
 pub mod keyboard;
 #[cfg(feature = "label")]
 pub mod label;
 #[cfg(feature = "launcher")]
 pub mod launcher;
+#[cfg(feature = "mail")]
+pub mod mail;
 #[cfg(feature = "menu")]
 pub mod menu;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
 #[cfg(feature = "music")]
 pub mod music;
 #[cfg(feature = "network_manager")]
 pub mod networkmanager;
 #[cfg(feature = "notifications")]
 pub mod notifications;
+#[cfg(feature = "power_menu")]
+pub mod power_menu;
+#[cfg(feature = "privacy")]
+pub mod privacy;
+#[cfg(feature = "screencast")]
+pub mod screencast;
 #[cfg(feature = "script")]
 pub mod script;
+#[cfg(feature = "separator")]
+pub mod separator;
+#[cfg(feature = "syncthing")]
+pub mod syncthing;
 #[cfg(feature = "sys_info")]
 pub mod sysinfo;
+#[cfg(feature = "systemd")]
+pub mod systemd;
+#[cfg(feature = "tablet")]
+pub mod tablet;
+#[cfg(feature = "taskbar")]
+pub mod taskbar;
+#[cfg(feature = "timer")]
+pub mod timer;
 #[cfg(feature = "tray")]
 pub mod tray;
+#[cfg(feature = "updates")]
+pub mod updates;
 #[cfg(feature = "volume")]
 pub mod volume;
+#[cfg(feature = "vpn")]
+pub mod vpn;
+#[cfg(feature = "wallpaper")]
+pub mod wallpaper;
 #[cfg(feature = "workspaces")]
 pub mod workspaces;
 
@@ -112,6 +160,10 @@ where
     pub update_tx: broadcast::Sender<TSend>,
     pub controller_tx: mpsc::Sender<TReceive>,
 
+    /// The image provider for this module,
+    /// using its icon theme override where set.
+    image_provider: image::Provider,
+
     // TODO: Don't like this - need some serious refactoring to deal with it
     //  This is a hack to be able to pass data from module -> popup creation
     //  for custom widget only.
@@ -142,6 +194,14 @@ where
         ProvidesFallibleClient::try_provide(self)
     }
 
+    /// Gets the image provider for this module.
+    ///
+    /// This should be used over `context.image_provider()`
+    /// so that the module's `icon_theme` is respected.
+    pub fn image_provider(&self) -> image::Provider {
+        self.image_provider.clone()
+    }
+
     /// Subscribes to events sent from this widget.
     pub fn subscribe(&self) -> broadcast::Receiver<TSend> {
         self.update_tx.subscribe()
@@ -154,6 +214,9 @@ pub struct ModuleRef {
     pub name: String,
     pub root_widget: Widget,
     pub popup: Option<ModulePopupParts>,
+    /// Tracks the module's tasks,
+    /// so they can be stopped when the module is removed.
+    pub guard: PanicGuard,
 }
 
 pub struct ModuleParts<W: IsA<Widget>> {
@@ -315,6 +378,34 @@ where
     where
         <Self as Module<W>>::SendMessage: Clone;
 
+    /// Creates the module's widget for a bar running vertically,
+    /// on the left or right edge of the screen.
+    ///
+    /// This is called in place of `into_widget` on vertical bars.
+    /// Modules which need a different layout to fit a narrow bar should override this,
+    /// otherwise the usual widget is used.
+    fn into_vertical_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<W>>
+    where
+        Self: Sized,
+        <Self as Module<W>>::SendMessage: Clone,
+    {
+        self.into_widget(context, info)
+    }
+
+    /// Gets the key used to coalesce updates when the `debounce` option is set.
+    /// Of the updates sent within the debounce window, only the latest for each key is applied.
+    ///
+    /// Updates without a key are always applied.
+    /// Modules should only return a key for updates which replace all previous state
+    /// for that key, such as a full snapshot or the latest value of a single item.
+    fn coalesce_key(_update: &Self::SendMessage) -> Option<String> {
+        None
+    }
+
     fn into_popup(
         self,
         _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
@@ -327,6 +418,34 @@ where
         None
     }
 
+    /// Sets the number of milliseconds between each refresh,
+    /// from the common `interval` option.
+    ///
+    /// Modules which poll for their data should override this.
+    fn set_interval(&mut self, _interval: u64)
+    where
+        Self: Sized,
+    {
+        warn!(
+            "{} module does not poll for data, ignoring `interval` option",
+            Self::name()
+        );
+    }
+
+    /// Sets the format string for the text shown on the bar,
+    /// from the common `format` option.
+    ///
+    /// Modules which show formatted text should override this.
+    fn set_format(&mut self, _format: String)
+    where
+        Self: Sized,
+    {
+        warn!(
+            "{} module does not support formatting, ignoring `format` option",
+            Self::name()
+        );
+    }
+
     fn take_common(&mut self) -> CommonConfig;
 }
 
@@ -338,69 +457,194 @@ pub trait ModuleFactory {
         info: &ModuleInfo,
     ) -> Result<ModuleRef>
     where
-        TModule: Module<TWidget, SendMessage = TSend, ReceiveMessage = TRev>,
+        TModule: Module<TWidget, SendMessage = TSend, ReceiveMessage = TRev> + Clone + 'static,
         TWidget: IsA<Widget>,
         TSend: Debug + Clone + Send + 'static,
+        TRev: 'static,
     {
         module.on_create();
 
         let id = Ironbar::unique_id();
-        let common = module.take_common();
+        let mut common = module.take_common();
+
+        if let Some(interval) = common.interval {
+            module.set_interval(interval);
+        }
+
+        if let Some(format) = common.format.take() {
+            module.set_format(format);
+        }
 
         debug!("adding module {} (id: {})", TModule::name(), id);
 
-        let (ui_tx, ui_rx) = mpsc::channel::<ModuleUpdateEvent<TSend>>(64);
-        let (controller_tx, controller_rx) = mpsc::channel::<TRev>(64);
+        let capacity = self
+            .ironbar()
+            .config
+            .borrow()
+            .channel_capacity
+            .unwrap_or(channels::DEFAULT_CAPACITY);
+
+        let (ui_tx, ui_rx) = mpsc::channel::<ModuleUpdateEvent<TSend>>(capacity);
+        let (controller_tx, controller_rx) = mpsc::channel::<TRev>(capacity);
 
-        let (tx, rx) = broadcast::channel(64);
+        let (tx, _) = broadcast::channel(capacity);
 
-        let context = WidgetContext {
+        let image_provider = self
+            .ironbar()
+            .image_provider()
+            .with_icon_theme(common.icon_theme.as_deref());
+
+        let make_context = || WidgetContext {
             ironbar: self.ironbar().clone(),
             bar: self.bar().clone(),
             popup: self.popup().clone(),
-            tx: ui_tx,
+            tx: ui_tx.clone(),
             update_tx: tx.clone(),
-            controller_tx,
-            _update_rx: rx,
+            controller_tx: controller_tx.clone(),
+            image_provider: image_provider.clone(),
+            _update_rx: tx.subscribe(),
             button_id: usize::MAX, // hack :(
         };
 
-        module.spawn_controller(info, &context, controller_rx)?;
+        let context = make_context();
 
+        // tasks and callbacks created by the module report panics here,
+        // so the module can be replaced with an error widget.
         let module_name = TModule::name();
+        let (guard, panic_rx) = PanicGuard::new(module_name);
+
+        // modules hidden behind `show_if` do not start their controller,
+        // and so do not connect to any clients, until they are first shown.
+        let deferred_controller = if common.show_if.is_some() {
+            Some(defer_controller(
+                module.clone(),
+                make_context(),
+                controller_rx,
+                info,
+            ))
+        } else {
+            guard.scope(|| module.spawn_controller(info, &context, controller_rx))?;
+            None
+        };
+
         let instance_name = common
             .name
             .clone()
             .unwrap_or_else(|| module_name.to_string());
 
-        let module_parts = module.into_widget(context, info)?;
+        let module_parts = guard.scope(|| {
+            if info.bar_position.orientation() == Orientation::Vertical {
+                module.into_vertical_widget(context, info)
+            } else {
+                module.into_widget(context, info)
+            }
+        })?;
         module_parts.widget.add_css_class("widget");
         module_parts.widget.add_css_class(module_name);
 
         module_parts.setup_identifiers(&common);
 
+        if common.disable_animations {
+            module_parts.widget.add_css_class(animation::DISABLED_CLASS);
+        }
+
+        // long-pressing a touchscreen opens the popup,
+        // unless a right-click script is set in its place
+        if common.on_click_right.is_none()
+            && !common.disable_popup
+            && let Some(button) = module_parts
+                .popup
+                .as_ref()
+                .and_then(|popup| popup.buttons.first())
+        {
+            let tx = ui_tx.clone();
+            let button_id = button.ensure_popup_id();
+
+            module_parts.widget.connect_long_press(move || {
+                tx.send_spawn(ModuleUpdateEvent::TogglePopup(button_id));
+            });
+        }
+
         if let Some(popup_content) = module_parts.popup.clone() {
             popup_content
                 .container
                 .add_css_class(&format!("popup-{module_name}"));
+            popup_content
+                .container
+                .set_accessible_label(&format!("{instance_name} popup"));
+
+            if common.disable_animations {
+                popup_content
+                    .container
+                    .add_css_class(animation::DISABLED_CLASS);
+            }
+
+            self.popup()
+                .register_content(id, popup_content, common.popup_layout());
+        }
 
-            self.popup().register_content(id, popup_content);
+        if common.transition_on_update {
+            let duration = common.transition_duration.unwrap_or(250);
+            install_update_transition(&module_parts.widget, tx.subscribe(), duration);
         }
 
+        let ui_rx =
+            match common.debounce {
+                Some(debounce) => channels::coalesce(
+                    ui_rx,
+                    Duration::from_millis(debounce),
+                    |event| match event {
+                        ModuleUpdateEvent::Update(update) => TModule::coalesce_key(update),
+                        _ => None,
+                    },
+                ),
+                None => ui_rx,
+            };
+
         self.setup_receiver(tx, ui_rx, module_name, id, common.disable_popup);
 
         let revealer = add_events(
             &module_parts.widget,
             common,
             info.bar_position.orientation(),
+            self.ironbar(),
+            info.output_name,
         );
         container.append(&revealer);
 
+        if let Some(spawn_controller) = deferred_controller {
+            let spawn_controller = Cell::new(Some(spawn_controller));
+            let guard = guard.clone();
+            revealer.connect_reveal_child_notify(move |revealer| {
+                if revealer.reveals_child()
+                    && let Some(spawn_controller) = spawn_controller.take()
+                {
+                    debug!("spawning deferred controller for {module_name} (id: {id})");
+                    panic_guard::catch(Some(&guard), || guard.scope(spawn_controller));
+                }
+            });
+        }
+
+        let name = instance_name.clone();
+        panic_rx.recv_glib((&revealer, self.bar()), move |(revealer, bar), failure| {
+            let reason = match failure {
+                Failure::Panic(message) => format!("Module {name} panicked: {message}"),
+                Failure::Error(message) if bar.strict() => {
+                    bar.set_degraded();
+                    format!("Module {name} failed: {message}")
+                }
+                Failure::Error(_) => return,
+            };
+
+            revealer.set_child(Some(&create_error_widget(&name, &reason)));
+        });
+
         Ok(ModuleRef {
             id,
             name: instance_name,
             root_widget: module_parts.widget.upcast(),
             popup: module_parts.popup,
+            guard,
         })
     }
 
@@ -464,10 +708,10 @@ impl ModuleFactory for BarModuleFactory {
             }
             ModuleUpdateEvent::OpenPopup(button_id) if !disable_popup => {
                 debug!("Opening popup for {name} [#{id}] (button id: {button_id})");
-                popup.hide();
-                popup.show(id, button_id);
+                popup.show_transient(id, button_id);
             }
-            ModuleUpdateEvent::ClosePopup if !disable_popup => {
+            // pinned popups stay open until explicitly hidden
+            ModuleUpdateEvent::ClosePopup if !disable_popup && !popup.pinned() => {
                 debug!("Closing popup for {name} [#{id}]");
                 popup.hide();
             }
@@ -544,10 +788,9 @@ impl ModuleFactory for PopupModuleFactory {
                     "Opening popup for {} [#{}] (button id: {button_id})",
                     name, id
                 );
-                popup.hide();
-                popup.show(id, button_id);
+                popup.show_transient(id, button_id);
             }
-            ModuleUpdateEvent::ClosePopup if !disable_popup => {
+            ModuleUpdateEvent::ClosePopup if !disable_popup && !popup.pinned() => {
                 debug!("Closing popup for {} [#{}]", name, id);
                 popup.hide();
             }
@@ -627,16 +870,70 @@ impl From<PopupModuleFactory> for AnyModuleFactory {
 
 /// Takes a widget and adds event listeners and the revealer.
 /// Returns the revealer.
+/// Prepares a module's controller to be spawned later,
+/// returning a function which spawns it when called.
+fn defer_controller<TModule, TWidget, TSend, TRev>(
+    module: TModule,
+    context: WidgetContext<TSend, TRev>,
+    rx: mpsc::Receiver<TRev>,
+    info: &ModuleInfo,
+) -> impl FnOnce() + 'static
+where
+    TModule: Module<TWidget, SendMessage = TSend, ReceiveMessage = TRev> + 'static,
+    TWidget: IsA<Widget>,
+    TSend: Clone + 'static,
+    TRev: 'static,
+{
+    let app = info.app.clone();
+    let location = info.location.clone();
+    let bar_position = info.bar_position;
+    let monitor = info.monitor.clone();
+    let output_name = info.output_name.to_string();
+
+    move || {
+        let info = ModuleInfo {
+            app: &app,
+            location,
+            bar_position,
+            monitor: &monitor,
+            output_name: &output_name,
+        };
+
+        if let Err(err) = module.spawn_controller(&info, &context, rx) {
+            error!("{err:?}");
+        }
+    }
+}
+
+/// Creates a widget to show in place of a module which failed,
+/// with the reason in its tooltip.
+pub fn create_error_widget(name: &str, reason: &str) -> Label {
+    let label = Label::builder()
+        .label(format!("\u{26a0} {name}"))
+        .tooltip_text(reason)
+        .build();
+
+    label.add_css_class("widget");
+    label.add_css_class("error");
+    label
+}
+
 pub fn add_events<W: IsA<Widget>>(
     widget: &W,
     common: CommonConfig,
     orientation: Orientation,
+    ironbar: &Rc<Ironbar>,
+    monitor_name: &str,
 ) -> Revealer {
-    let transition_type = common
-        .transition_type
-        .as_ref()
-        .unwrap_or(&TransitionType::SlideStart)
-        .to_revealer_transition_type(orientation);
+    let transition_type = if common.disable_animations {
+        RevealerTransitionType::None
+    } else {
+        common
+            .transition_type
+            .as_ref()
+            .unwrap_or(&TransitionType::SlideStart)
+            .to_revealer_transition_type(orientation)
+    };
 
     let revealer = Revealer::builder()
         .transition_type(transition_type)
@@ -644,8 +941,59 @@ pub fn add_events<W: IsA<Widget>>(
         .build();
 
     revealer.set_child(Some(widget));
-    revealer.set_reveal_child(true);
 
-    common.install_events(widget, &revealer);
+    if common.show_if.is_some() || common.visible_when.is_some() {
+        // revealed once the condition is first met
+        revealer.set_reveal_child(false);
+    } else if common.transition_on_create {
+        revealer.set_reveal_child(false);
+        revealer.connect_map(|revealer| revealer.set_reveal_child(true));
+    } else {
+        revealer.set_reveal_child(true);
+    }
+
+    common.install_events(widget, &revealer, ironbar, monitor_name);
     revealer
 }
+
+/// Applies the `updating` class to the widget for `duration` milliseconds
+/// each time the module sends an update.
+///
+/// The class is removed and re-applied on each update,
+/// so that CSS animations restart.
+fn install_update_transition<W, T>(widget: &W, rx: broadcast::Receiver<T>, duration: u32)
+where
+    W: IsA<Widget>,
+    T: Debug + Clone + 'static,
+{
+    const CLASS: &str = "updating";
+
+    let timeout_id = Rc::new(RefCell::new(None::<SourceId>));
+    let duration = Duration::from_millis(u64::from(duration));
+
+    rx.recv_glib(widget.upcast_ref::<Widget>(), move |widget, _| {
+        if let Some(timeout_id) = timeout_id.take() {
+            timeout_id.remove();
+        }
+
+        widget.remove_css_class(CLASS);
+
+        // re-adding the class in the same frame does not restart animations
+        let widget = widget.clone();
+        let timeout_id = timeout_id.clone();
+        glib::idle_add_local_once(move || {
+            widget.add_css_class(CLASS);
+
+            let source_id = {
+                let timeout_id = timeout_id.clone();
+                glib::timeout_add_local_once(duration, move || {
+                    // source is removed once complete, so must not be removed again
+                    timeout_id.take();
+                    widget.remove_css_class(CLASS);
+                })
+            };
+
+            *timeout_id.borrow_mut() = Some(source_id);
+        });
+    });
+}
@@ -0,0 +1,363 @@
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::clients::mqtt;
+use crate::config::{CommonConfig, LayoutConfig, Secret};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt, MouseButton};
+use crate::modules::{Module, ModuleInfo, ModuleParts, ModuleUpdateEvent, WidgetContext};
+use crate::panic_guard::{self, PanicGuard};
+use crate::{module_impl, spawn, spawn_blocking};
+use color_eyre::{Report, Result};
+use gtk::Label;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tracing::error;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct MqttModule {
+    /// The hostname or IP address of the MQTT broker.
+    ///
+    /// **Default**: `localhost`
+    #[serde(default = "default_host")]
+    host: String,
+
+    /// The port of the MQTT broker.
+    ///
+    /// **Default**: `1883`
+    #[serde(default = "default_port")]
+    port: u16,
+
+    /// The username to authenticate with, if the broker requires it.
+    ///
+    /// **Default**: `null`
+    username: Option<String>,
+
+    /// The password to authenticate with, if the broker requires it.
+    ///
+    /// See [secrets](configuration-guide#34-secrets) for other ways to provide this.
+    ///
+    /// **Default**: `null`
+    password: Option<Secret>,
+
+    /// The topics to subscribe to.
+    /// See [below](#topics) for options.
+    ///
+    /// **Default**: `[]`
+    #[serde(default)]
+    topics: Vec<TopicConfig>,
+
+    /// The format string to use for the label.
+    /// Each `{name}` token is replaced with the latest value
+    /// received for the topic with that name.
+    ///
+    /// **Required**
+    #[serde(skip)]
+    format: String,
+
+    /// The message to publish when the widget is left-clicked.
+    /// See [below](#publishing) for options.
+    ///
+    /// **Default**: `null`
+    publish_on_click_left: Option<PublishConfig>,
+
+    /// The message to publish when the widget is middle-clicked.
+    /// See [below](#publishing) for options.
+    ///
+    /// **Default**: `null`
+    publish_on_click_middle: Option<PublishConfig>,
+
+    /// The message to publish when the widget is right-clicked.
+    /// See [below](#publishing) for options.
+    ///
+    /// **Default**: `null`
+    publish_on_click_right: Option<PublishConfig>,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(default, flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct TopicConfig {
+    /// The name of the token to use in the format string.
+    ///
+    /// **Required**
+    name: String,
+
+    /// The topic to subscribe to.
+    /// This may contain `+` and `#` wildcards.
+    ///
+    /// **Required**
+    topic: String,
+
+    /// A JSON path to extract the value from the payload,
+    /// such as `$.sensor.temperature` or `$.readings[0]`.
+    /// If not set, the whole payload is used.
+    ///
+    /// **Default**: `null`
+    path: Option<String>,
+
+    /// The name of an [ironvar](ironvars) to set to each received value,
+    /// allowing it to be used in other modules.
+    ///
+    /// **Default**: `null`
+    #[cfg(feature = "ipc")]
+    variable: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct PublishConfig {
+    /// The topic to publish to.
+    ///
+    /// **Required**
+    topic: String,
+
+    /// The payload to publish.
+    ///
+    /// **Default**: `""`
+    #[serde(default)]
+    payload: String,
+
+    /// Whether the broker should retain the message
+    /// for future subscribers.
+    ///
+    /// **Default**: `false`
+    #[serde(default)]
+    retain: bool,
+}
+
+fn default_host() -> String {
+    "localhost".to_string()
+}
+
+const fn default_port() -> u16 {
+    1883
+}
+
+impl TopicConfig {
+    /// Gets the value to show for the payload,
+    /// or `None` if the JSON path does not match.
+    fn value(&self, payload: &str) -> Option<String> {
+        match &self.path {
+            Some(path) => extract(payload, path),
+            None => Some(payload.to_string()),
+        }
+    }
+}
+
+/// Extracts the value at the JSON path from the payload.
+///
+/// Strings are returned without quotes,
+/// and any other value is returned as JSON.
+fn extract(payload: &str, path: &str) -> Option<String> {
+    let json: Value = serde_json::from_str(payload).ok()?;
+
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let mut value = &json;
+
+    for segment in path.split(['.', '[', ']']).filter(|s| !s.is_empty()) {
+        value = match value {
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            _ => value.get(segment)?,
+        };
+    }
+
+    Some(match value {
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    })
+}
+
+/// Sends the value of each topic matching the message.
+async fn handle_message(
+    topics: &[TopicConfig],
+    message: &mqtt::Message,
+    tx: &mpsc::Sender<ModuleUpdateEvent<(String, String)>>,
+) {
+    for topic in topics {
+        if !mqtt::matches(&message.topic, &topic.topic) {
+            continue;
+        }
+
+        let Some(value) = topic.value(&message.payload) else {
+            continue;
+        };
+
+        #[cfg(feature = "ipc")]
+        if let Some(variable) = &topic.variable {
+            use crate::ironvar::WritableNamespace;
+
+            if let Err(err) = crate::Ironbar::variable_manager().set(variable, value.clone()) {
+                error!("Failed to set ironvar '{variable}': {err:?}");
+            }
+        }
+
+        tx.send_update((topic.name.clone(), value)).await;
+    }
+}
+
+impl Module<gtk::Widget> for MqttModule {
+    /// The topic name and its latest value.
+    type SendMessage = (String, String);
+    type ReceiveMessage = PublishConfig;
+
+    module_impl!("mqtt");
+
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
+    fn coalesce_key((name, _): &Self::SendMessage) -> Option<String> {
+        Some(name.clone())
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        if self.format.is_empty() {
+            return Err(Report::msg("No `format` set"));
+        }
+
+        let password = self.password.clone();
+        let username = self.username.clone();
+        let host = self.host.clone();
+        let port = self.port;
+        let ironbar = context.ironbar.clone();
+
+        let tx = context.tx.clone();
+        let topics = self.topics.clone();
+
+        // resolving the password can run a command,
+        // so happens off the main thread
+        let guard = PanicGuard::current();
+        glib::spawn_future_local(panic_guard::scope_future(guard, async move {
+            let resolve = move || password.as_ref().map(Secret::resolve).transpose();
+            let password = match spawn_blocking(resolve).await {
+                Ok(res) => res,
+                Err(err) => Err(err.into()),
+            };
+
+            let password = match password {
+                Ok(password) => password,
+                Err(err) => {
+                    panic_guard::report_error(&err.wrap_err("Unable to connect to MQTT broker"));
+                    return;
+                }
+            };
+
+            let credentials = username.zip(password);
+            let client = ironbar.clients.borrow_mut().mqtt(&host, port, credentials);
+
+            spawn(async move {
+                let mut messages = client.subscribe();
+                for topic in &topics {
+                    client.add_topic(&topic.topic);
+                }
+
+                loop {
+                    tokio::select! {
+                        res = messages.recv_tracked("mqtt") => match res {
+                            Ok(message) => handle_message(&topics, &message, &tx).await,
+                            // messages are not re-sent,
+                            // so values stay stale until the next message on the topic.
+                            Err(RecvError::Lagged(_)) => {}
+                            Err(RecvError::Closed) => break,
+                        },
+                        Some(publish) = rx.recv() => {
+                            if let Err(err) = client
+                                .publish(&publish.topic, &publish.payload, publish.retain)
+                                .await
+                            {
+                                error!("Failed to publish to {}: {err:?}", publish.topic);
+                            }
+                        }
+                    }
+                }
+            });
+        }));
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<gtk::Widget>> {
+        let label = Label::builder()
+            .use_markup(true)
+            .justify(self.layout.justify.into())
+            .build();
+
+        for (button, publish) in [
+            (MouseButton::Primary, self.publish_on_click_left),
+            (MouseButton::Middle, self.publish_on_click_middle),
+            (MouseButton::Secondary, self.publish_on_click_right),
+        ] {
+            let Some(publish) = publish else {
+                continue;
+            };
+
+            let tx = context.controller_tx.clone();
+            label.connect_pressed(button, move || tx.send_spawn(publish.clone()));
+        }
+
+        let format = self.format;
+        let mut values = HashMap::new();
+
+        label.set_label_escaped(&render(&format, &self.topics, &values));
+
+        let topics = self.topics;
+        context
+            .subscribe()
+            .recv_glib(&label, move |label, (name, value)| {
+                values.insert(name, value);
+                label.set_label_escaped(&render(&format, &topics, &values));
+            });
+
+        Ok(ModuleParts {
+            widget: label.rotated(self.layout.angle()),
+            popup: None,
+        })
+    }
+}
+
+/// Replaces each topic's token in the format string with its latest value,
+/// or an empty string if no value has been received yet.
+fn render(format: &str, topics: &[TopicConfig], values: &HashMap<String, String>) -> String {
+    topics.iter().fold(format.to_string(), |text, topic| {
+        let value = values.get(&topic.name).map_or("", String::as_str);
+        text.replace(&format!("{{{}}}", topic.name), value)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract() {
+        let payload = r#"{"sensor": {"temperature": 21.5, "name": "lounge"}, "readings": [1, 2]}"#;
+
+        assert_eq!(
+            extract(payload, "$.sensor.temperature").as_deref(),
+            Some("21.5")
+        );
+        assert_eq!(extract(payload, "sensor.name").as_deref(), Some("lounge"));
+        assert_eq!(extract(payload, "$.readings[1]").as_deref(), Some("2"));
+        assert_eq!(extract(payload, "$.missing"), None);
+        assert_eq!(extract("not json", "$.sensor"), None);
+    }
+}
@@ -1,3 +1,4 @@
+use super::visualizer::VisualizerConfig;
 use crate::config::{CommonConfig, LayoutConfig, MarqueeMode, TruncateMode, default};
 use dirs::{audio_dir, home_dir};
 use serde::Deserialize;
@@ -41,6 +42,7 @@ pub struct MusicModule {
     /// Info on formatting tokens [below](#formatting-tokens).
     ///
     /// **Default**: `{title} / {artist}`
+    #[serde(skip)]
     pub(crate) format: String,
 
     /// Player state icons.
@@ -64,6 +66,27 @@ pub struct MusicModule {
     /// **Default**: `128`
     pub(crate) cover_image_size: i32,
 
+    /// Shows a frequency visualizer for the system audio output while playing,
+    /// using [cava](https://github.com/karlstav/cava).
+    ///
+    /// See [visualizer](#visualizer).
+    ///
+    /// **Default**: `null`
+    pub(crate) visualizer: Option<VisualizerConfig>,
+
+    /// Whether to show the player's queue in the popup.
+    /// Tracks can be clicked to play them,
+    /// and removed or dragged to reorder them where the player supports it.
+    ///
+    /// **Default**: `true`
+    pub(crate) show_queue: bool,
+
+    /// The maximum height of the queue in the popup, in pixels.
+    /// The queue scrolls once it is taller than this.
+    ///
+    /// **Default**: `200`
+    pub(crate) queue_height: i32,
+
     // -- MPD --
     /// *[MPD Only]*
     /// TCP or Unix socket address of the MPD server.
@@ -79,6 +102,25 @@ pub struct MusicModule {
     /// **Default**: `$HOME/Music`
     pub(crate) music_dir: PathBuf,
 
+    // -- MPRIS --
+    /// *[MPRIS Only]*
+    /// Player names to prefer, in order, when choosing which player to show.
+    /// Names are matched against the player's identity (eg `Spotify`)
+    /// or D-Bus name (eg `spotify`), ignoring case.
+    /// Use `*` to place any other players.
+    ///
+    /// A player which starts playing is shown
+    /// unless the shown player is also playing and has a higher priority.
+    ///
+    /// **Default**: `[]`
+    pub(crate) player_priority: Vec<String>,
+
+    /// *[MPRIS Only]*
+    /// Player names to never show, matched the same way as `player_priority`.
+    ///
+    /// **Default**: `[]`
+    pub(crate) ignored_players: Vec<String>,
+
     // -- Common --
     /// See [truncate options](module-level-options#truncate-mode).
     ///
@@ -134,8 +176,13 @@ impl Default for MusicModule {
             show_status_icon: true,
             icon_size: default::IconSize::Normal as i32,
             cover_image_size: 128,
+            visualizer: None,
+            show_queue: true,
+            queue_height: 200,
             host: "localhost:6600".to_string(),
             music_dir: default_music_dir(),
+            player_priority: vec![],
+            ignored_players: vec![],
             truncate: None,
             marquee: MarqueeMode::default(),
             truncate_popup_artist: None,
@@ -193,6 +240,11 @@ pub struct Icons {
     ///
     /// **Default**: `󰠃`
     pub(crate) artist: String,
+
+    /// Icon to display for the button to remove a track from the queue.
+    ///
+    /// **Default**: `󰅖`
+    pub(crate) remove: String,
 }
 
 impl Default for Icons {
@@ -206,6 +258,7 @@ impl Default for Icons {
             track: "󰎈".to_string(),
             album: "󰀥".to_string(),
             artist: "󰠃".to_string(),
+            remove: "󰅖".to_string(),
         }
     }
 }
@@ -1,6 +1,5 @@
 use std::borrow::Cow;
 use std::cell::{Cell, RefMut};
-use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
 use std::time::Duration;
@@ -9,16 +8,18 @@ use color_eyre::Result;
 use glib::Propagation;
 use gtk::gdk::Paintable;
 use gtk::prelude::*;
-use gtk::{Button, ContentFit, Label, Orientation, Scale};
+use gtk::{Button, ContentFit, Label, Orientation, Overlay, Scale};
 use tokio::sync::mpsc;
 use tracing::{error, warn};
 
 pub use self::config::MusicModule;
 use self::config::PlayerType;
+use self::queue::QueueView;
+use self::visualizer::VisualizerPosition;
 use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
 use crate::clients::Clients;
 use crate::clients::music::{
-    self, MusicClient, PlayerState, PlayerUpdate, ProgressTick, Status, Track,
+    self, MusicClient, PlayerState, PlayerUpdate, Players, ProgressTick, Queue, Status, Track,
 };
 use crate::gtk_helpers::{IronbarLabelExt, OverflowLabel};
 use crate::image::{IconButton, IconLabel, IconPrefixedLabel};
@@ -29,6 +30,8 @@ use crate::modules::{
 use crate::{module_impl, spawn};
 
 mod config;
+mod queue;
+mod visualizer;
 
 #[derive(Debug)]
 pub enum PlayerCommand {
@@ -38,6 +41,13 @@ pub enum PlayerCommand {
     Next,
     Volume(u8),
     Seek(Duration),
+    /// Plays the track in the queue with the ID.
+    PlayTrack(String),
+    RemoveTrack(String),
+    /// Moves the track with the ID to the index in the queue.
+    MoveTrack(String, usize),
+    /// Switches to the player with the name.
+    SelectPlayer(String),
 }
 
 /// Formats a duration given in seconds
@@ -59,6 +69,11 @@ fn format_time(duration: Duration) -> String {
 pub enum ControllerEvent {
     Update(Option<SongUpdate>),
     UpdateProgress(ProgressTick),
+    Queue(Queue),
+    Players(Players),
+    /// Visualizer bar heights, from `0.0` to `1.0`.
+    /// Empty once playback stops.
+    Visualizer(Vec<f64>),
 }
 
 #[derive(Clone, Debug)]
@@ -68,17 +83,18 @@ pub struct SongUpdate {
     display_string: String,
 }
 
-fn get_client(
-    mut clients: RefMut<'_, Clients>,
-    player_type: PlayerType,
-    host: String,
-    music_dir: PathBuf,
-) -> Arc<dyn MusicClient> {
-    let client_type = match player_type {
+fn get_client(mut clients: RefMut<'_, Clients>, config: &MusicModule) -> Arc<dyn MusicClient> {
+    let client_type = match config.player_type {
         #[cfg(feature = "music+mpd")]
-        PlayerType::Mpd => music::ClientType::Mpd { host, music_dir },
+        PlayerType::Mpd => music::ClientType::Mpd {
+            host: config.host.clone(),
+            music_dir: config.music_dir.clone(),
+        },
         #[cfg(feature = "music+mpris")]
-        PlayerType::Mpris => music::ClientType::Mpris,
+        PlayerType::Mpris => music::ClientType::Mpris {
+            player_priority: config.player_priority.clone(),
+            ignored_players: config.ignored_players.clone(),
+        },
     };
 
     clients.music(client_type)
@@ -90,6 +106,10 @@ impl Module<Button> for MusicModule {
 
     module_impl!("music");
 
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
     fn spawn_controller(
         &self,
         _info: &ModuleInfo,
@@ -98,12 +118,7 @@ impl Module<Button> for MusicModule {
     ) -> Result<()> {
         let format = self.format.clone();
 
-        let client = get_client(
-            context.ironbar.clients.borrow_mut(),
-            self.player_type,
-            self.host.clone(),
-            self.music_dir.clone(),
-        );
+        let client = get_client(context.ironbar.clients.borrow_mut(), self);
 
         // receive player updates
         {
@@ -134,12 +149,22 @@ impl Module<Button> for MusicModule {
                                 tx.send_update(ControllerEvent::UpdateProgress(progress_tick))
                                     .await;
                             }
+                            PlayerUpdate::Queue(queue) => {
+                                tx.send_update(ControllerEvent::Queue(queue)).await;
+                            }
+                            PlayerUpdate::Players(players) => {
+                                tx.send_update(ControllerEvent::Players(players)).await;
+                            }
                         }
                     }
                 }
             });
         }
 
+        if let Some(config) = self.visualizer.clone() {
+            visualizer::spawn_controller(config, client.clone(), context.tx.clone());
+        }
+
         // listen to ui events
         {
             spawn(async move {
@@ -151,6 +176,10 @@ impl Module<Button> for MusicModule {
                         PlayerCommand::Next => client.next(),
                         PlayerCommand::Volume(vol) => client.set_volume_percent(vol),
                         PlayerCommand::Seek(duration) => client.seek(duration),
+                        PlayerCommand::PlayTrack(id) => client.play_track(&id),
+                        PlayerCommand::RemoveTrack(id) => client.remove_track(&id),
+                        PlayerCommand::MoveTrack(id, index) => client.move_track(&id, index),
+                        PlayerCommand::SelectPlayer(name) => client.set_player(&name),
                     };
 
                     if let Err(err) = res {
@@ -174,7 +203,7 @@ impl Module<Button> for MusicModule {
 
         button.set_child(Some(&button_contents));
 
-        let image_provider = context.ironbar.image_provider();
+        let image_provider = context.image_provider();
 
         let icon_play = IconLabel::new(&self.icons.play, self.icon_size, &image_provider);
         let icon_pause = IconLabel::new(&self.icons.pause, self.icon_size, &image_provider);
@@ -194,6 +223,30 @@ impl Module<Button> for MusicModule {
         button_contents.append(&*icon_play);
         button_contents.append(label.widget());
 
+        if let Some(config) = &self.visualizer {
+            let (visualizer, bars) = visualizer::build(config);
+
+            match config.position {
+                VisualizerPosition::Beside => button_contents.append(&visualizer),
+                VisualizerPosition::Behind => {
+                    // the track info determines the size,
+                    // which the bars then fill
+                    let overlay = Overlay::new();
+                    overlay.set_child(Some(&visualizer));
+                    overlay.add_overlay(&button_contents);
+                    overlay.set_measure_overlay(&button_contents, true);
+
+                    button.set_child(Some(&overlay));
+                }
+            }
+
+            context.subscribe().recv_glib((), move |(), event| {
+                if let ControllerEvent::Visualizer(frame) = event {
+                    visualizer::update(&bars, &frame);
+                }
+            });
+        }
+
         {
             let tx = context.tx.clone();
 
@@ -254,11 +307,16 @@ impl Module<Button> for MusicModule {
         context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
         _info: &ModuleInfo,
     ) -> Option<gtk::Box> {
-        let image_provider = context.ironbar.image_provider();
+        let image_provider = context.image_provider();
 
         let container = gtk::Box::new(Orientation::Vertical, 10);
         let main_container = gtk::Box::new(Orientation::Horizontal, 10);
 
+        let players_box = gtk::Box::new(Orientation::Horizontal, 5);
+        players_box.add_css_class("players");
+        players_box.set_visible(false);
+        container.append(&players_box);
+
         let album_image = gtk::Picture::builder()
             .content_fit(ContentFit::ScaleDown)
             .width_request(128)
@@ -390,6 +448,19 @@ impl Module<Button> for MusicModule {
         progress_box.append(&progress_label);
         container.append(&progress_box);
 
+        let queue_view = self.show_queue.then(|| {
+            let queue_view = QueueView::new(
+                self.queue_height,
+                self.format,
+                icons.remove.clone(),
+                self.icon_size,
+                image_provider.clone(),
+                context.controller_tx.clone(),
+            );
+            container.append(&queue_view.container);
+            queue_view
+        });
+
         {
             let tx = context.controller_tx.clone();
             let was_dragging = Rc::new(Cell::new(false));
@@ -423,6 +494,7 @@ impl Module<Button> for MusicModule {
         }
 
         let image_size = self.cover_image_size;
+        let tx = context.controller_tx.clone();
 
         let mut prev_cover = None;
         context.subscribe().recv_glib((), move |(), event| {
@@ -526,6 +598,14 @@ impl Module<Button> for MusicModule {
                         progress_box.set_visible(false);
                     }
                 }
+                ControllerEvent::Queue(queue) => {
+                    if let Some(queue_view) = &queue_view {
+                        queue_view.update(&queue);
+                    }
+                }
+                ControllerEvent::Players(players) => {
+                    update_players(&players_box, &players, &tx);
+                }
                 _ => {}
             }
         });
@@ -534,6 +614,33 @@ impl Module<Button> for MusicModule {
     }
 }
 
+/// Replaces the buttons in the player switcher,
+/// which is only shown while there is more than one player.
+fn update_players(container: &gtk::Box, players: &Players, tx: &mpsc::Sender<PlayerCommand>) {
+    while let Some(child) = container.first_child() {
+        container.remove(&child);
+    }
+
+    container.set_visible(players.names.len() > 1);
+
+    for name in &players.names {
+        let button = Button::with_label(name);
+        button.add_css_class("player");
+
+        if players.current.as_ref() == Some(name) {
+            button.add_css_class("active");
+        }
+
+        let tx = tx.clone();
+        let name = name.clone();
+        button.connect_clicked(move |_| {
+            tx.send_spawn(PlayerCommand::SelectPlayer(name.clone()));
+        });
+
+        container.append(&button);
+    }
+}
+
 fn update_popup_metadata_label(text: Option<String>, label: &IconPrefixedLabel) {
     match text {
         Some(value) => {
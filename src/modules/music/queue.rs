@@ -0,0 +1,166 @@
+use super::{PlayerCommand, replace_tokens};
+use crate::channels::AsyncSenderExt;
+use crate::clients::music::Queue;
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::image::{self, IconButton};
+use gtk::gdk::{ContentProvider, DragAction};
+use gtk::prelude::*;
+use gtk::{
+    Button, DragSource, DropTarget, Label, Orientation, PolicyType, ScrolledWindow, Viewport,
+    WidgetPaintable, pango,
+};
+use tokio::sync::mpsc;
+
+/// The scrollable list of tracks in the queue.
+pub struct QueueView {
+    pub container: ScrolledWindow,
+    viewport: Viewport,
+    list: gtk::Box,
+
+    format: String,
+    remove_icon: String,
+    icon_size: i32,
+    image_provider: image::Provider,
+    tx: mpsc::Sender<PlayerCommand>,
+}
+
+impl QueueView {
+    /// Creates the view, with a maximum `height` before it scrolls.
+    ///
+    /// Tracks are shown using the module's `format`.
+    pub fn new(
+        height: i32,
+        format: String,
+        remove_icon: String,
+        icon_size: i32,
+        image_provider: image::Provider,
+        tx: mpsc::Sender<PlayerCommand>,
+    ) -> Self {
+        let list = gtk::Box::new(Orientation::Vertical, 0);
+        list.add_css_class("tracks");
+
+        let viewport = Viewport::builder().child(&list).build();
+
+        let container = ScrolledWindow::builder()
+            .hscrollbar_policy(PolicyType::Never)
+            .max_content_height(height)
+            .propagate_natural_height(true)
+            .child(&viewport)
+            .build();
+        container.add_css_class("queue");
+        container.set_visible(false);
+
+        Self {
+            container,
+            viewport,
+            list,
+            format,
+            remove_icon,
+            icon_size,
+            image_provider,
+            tx,
+        }
+    }
+
+    /// Replaces the tracks in the list with those in the queue.
+    ///
+    /// Clicking a track plays it.
+    /// Where the player supports it, tracks can be removed
+    /// or dragged onto another track to move them to its position.
+    pub fn update(&self, queue: &Queue) {
+        while let Some(child) = self.list.first_child() {
+            self.list.remove(&child);
+        }
+
+        self.container.set_visible(!queue.tracks.is_empty());
+
+        for (index, queue_track) in queue.tracks.iter().enumerate() {
+            let row = gtk::Box::new(Orientation::Horizontal, 5);
+            row.add_css_class("track");
+
+            if queue.current == Some(index) {
+                row.add_css_class("current");
+            }
+
+            let label = Label::builder()
+                .label(replace_tokens(&self.format, &queue_track.track))
+                .ellipsize(pango::EllipsizeMode::End)
+                .xalign(0.0)
+                .build();
+
+            let button = Button::builder().child(&label).hexpand(true).build();
+            button.add_css_class("btn-track");
+
+            {
+                let tx = self.tx.clone();
+                let id = queue_track.id.clone();
+                button.connect_clicked(move |_| {
+                    tx.send_spawn(PlayerCommand::PlayTrack(id.clone()));
+                });
+            }
+
+            row.append(&button);
+
+            if queue.can_remove {
+                let remove_button = IconButton::new(
+                    &self.remove_icon,
+                    self.icon_size,
+                    self.image_provider.clone(),
+                );
+                remove_button.add_css_class("btn-remove");
+
+                let tx = self.tx.clone();
+                let id = queue_track.id.clone();
+                remove_button.connect_clicked(move |_| {
+                    tx.send_spawn(PlayerCommand::RemoveTrack(id.clone()));
+                });
+
+                row.append(&*remove_button);
+            }
+
+            if queue.can_reorder {
+                connect_reorder(&row, &queue_track.id, index, self.tx.clone());
+            }
+
+            self.list.append(&row);
+        }
+
+        // keep the playing track in view
+        if let Some(row) = queue
+            .current
+            .and_then(|index| self.list.children().nth(index))
+        {
+            self.viewport.scroll_to(&row, None);
+        }
+    }
+}
+
+/// Allows the row to be dragged onto another row,
+/// moving its track to that position in the queue.
+fn connect_reorder(row: &gtk::Box, id: &str, index: usize, tx: mpsc::Sender<PlayerCommand>) {
+    let drag_source = DragSource::builder().actions(DragAction::MOVE).build();
+
+    let id = id.to_string();
+    drag_source.connect_prepare(move |_, _, _| Some(ContentProvider::for_value(&id.to_value())));
+
+    drag_source.connect_drag_begin(|source, _| {
+        if let Some(widget) = source.widget() {
+            source.set_icon(Some(&WidgetPaintable::new(Some(&widget))), 0, 0);
+        }
+    });
+
+    row.add_controller(drag_source);
+
+    let drop_target = DropTarget::new(String::static_type(), DragAction::MOVE);
+
+    drop_target.connect_drop(move |_, value, _, _| {
+        let Ok(id) = value.get::<String>() else {
+            return false;
+        };
+
+        tx.send_spawn(PlayerCommand::MoveTrack(id, index));
+        true
+    });
+
+    row.add_controller(drop_target);
+}
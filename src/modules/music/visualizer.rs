@@ -0,0 +1,276 @@
+use super::ControllerEvent;
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::clients::music::{MusicClient, PlayerState, PlayerUpdate};
+use crate::modules::ModuleUpdateEvent;
+use crate::{Ironbar, spawn};
+use color_eyre::{Report, Result};
+use gtk::prelude::*;
+use gtk::{LevelBar, Orientation};
+use serde::Deserialize;
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, error};
+
+/// The value cava reports for a full bar.
+const MAX_RANGE: u32 = 1000;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct VisualizerConfig {
+    /// The number of frequency bars to show.
+    ///
+    /// **Default**: `16`
+    bars: u32,
+
+    /// The number of times to update the bars per second.
+    ///
+    /// **Default**: `60`
+    framerate: u32,
+
+    /// Where to show the bars, relative to the track info.
+    ///
+    /// **Valid options**: `beside`, `behind`
+    /// <br>
+    /// **Default**: `beside`
+    pub(crate) position: VisualizerPosition,
+
+    /// The audio server to capture from.
+    ///
+    /// **Valid options**: `pipewire`, `pulse`
+    /// <br>
+    /// **Default**: `pipewire`
+    method: AudioMethod,
+
+    /// The name of the monitor source to capture,
+    /// or `auto` to use the default output's monitor.
+    ///
+    /// **Default**: `auto`
+    source: String,
+}
+
+impl Default for VisualizerConfig {
+    fn default() -> Self {
+        Self {
+            bars: 16,
+            framerate: 60,
+            position: VisualizerPosition::default(),
+            method: AudioMethod::default(),
+            source: "auto".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum VisualizerPosition {
+    /// After the track info.
+    #[default]
+    Beside,
+    /// Underneath the track info, filling the widget.
+    Behind,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+enum AudioMethod {
+    #[default]
+    Pipewire,
+    Pulse,
+}
+
+impl AudioMethod {
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Pipewire => "pipewire",
+            Self::Pulse => "pulse",
+        }
+    }
+}
+
+impl VisualizerConfig {
+    /// Creates the cava config, which writes each frame to stdout
+    /// as a line of values separated by semicolons.
+    fn cava_config(&self) -> String {
+        format!(
+            "[general]
+bars = {bars}
+framerate = {framerate}
+
+[input]
+method = {method}
+source = {source}
+
+[output]
+method = raw
+raw_target = /dev/stdout
+data_format = ascii
+ascii_max_range = {MAX_RANGE}
+bar_delimiter = 59
+frame_delimiter = 10
+",
+            bars = self.bars,
+            framerate = self.framerate,
+            method = self.method.as_str(),
+            source = self.source,
+        )
+    }
+}
+
+/// Runs cava while the player is playing,
+/// sending each frame of bar heights to the widget.
+///
+/// Once playback stops, cava is stopped and the bars are cleared.
+pub fn spawn_controller(
+    config: VisualizerConfig,
+    client: Arc<dyn MusicClient>,
+    tx: mpsc::Sender<ModuleUpdateEvent<ControllerEvent>>,
+) {
+    spawn(async move {
+        let mut rx = client.subscribe_change();
+        let mut cava: Option<JoinHandle<()>> = None;
+
+        loop {
+            let playing = match rx.recv_tracked("music visualizer").await {
+                Ok(PlayerUpdate::Update(_, status)) => {
+                    matches!(status.state, PlayerState::Playing)
+                }
+                Ok(
+                    PlayerUpdate::ProgressTick(_)
+                    | PlayerUpdate::Queue(_)
+                    | PlayerUpdate::Players(_),
+                )
+                | Err(broadcast::error::RecvError::Lagged(_)) => {
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+
+            match (playing, &cava) {
+                (true, None) => {
+                    debug!("Starting visualizer");
+
+                    let config = config.clone();
+                    let tx = tx.clone();
+
+                    cava = Some(spawn(async move {
+                        if let Err(err) = run_cava(&config, &tx).await {
+                            error!("Failed to run visualizer: {err:?}");
+                        }
+                    }));
+                }
+                (false, Some(handle)) => {
+                    debug!("Stopping visualizer");
+
+                    // cava is killed once its task is dropped
+                    handle.abort();
+                    cava = None;
+
+                    tx.send_update(ControllerEvent::Visualizer(vec![])).await;
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+async fn run_cava(
+    config: &VisualizerConfig,
+    tx: &mpsc::Sender<ModuleUpdateEvent<ControllerEvent>>,
+) -> Result<()> {
+    let path = std::env::temp_dir().join(format!("ironbar-cava-{}", Ironbar::unique_id()));
+    tokio::fs::write(&path, config.cava_config()).await?;
+
+    let child = Command::new("cava")
+        .arg("-p")
+        .arg(&path)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn();
+
+    let mut child = match child {
+        Ok(child) => child,
+        Err(err) => {
+            tokio::fs::remove_file(&path).await.ok();
+            return Err(err.into());
+        }
+    };
+
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| Report::msg("Failed to capture cava output"))?;
+
+    let mut lines = BufReader::new(stdout).lines();
+    let mut first = true;
+
+    while let Some(line) = lines.next_line().await? {
+        // cava has read the config once it starts writing frames
+        if first {
+            tokio::fs::remove_file(&path).await.ok();
+            first = false;
+        }
+
+        let frame = line
+            .split(';')
+            .filter_map(|value| value.parse::<u32>().ok())
+            .map(|value| f64::from(value) / f64::from(MAX_RANGE))
+            .collect();
+
+        tx.send_update(ControllerEvent::Visualizer(frame)).await;
+    }
+
+    let status = child.wait().await?;
+    Err(Report::msg(format!("cava exited ({status})")))
+}
+
+/// Creates the container of bars,
+/// which fill from the bottom with each frame's values.
+///
+/// Bars can be styled with `levelbar block.filled`,
+/// for example using a `linear-gradient` background.
+pub fn build(config: &VisualizerConfig) -> (gtk::Box, Vec<LevelBar>) {
+    let container = gtk::Box::new(Orientation::Horizontal, 1);
+    container.add_css_class("visualizer");
+    container.set_can_target(false);
+
+    let bars = (0..config.bars)
+        .map(|_| {
+            let bar = LevelBar::new();
+            bar.set_orientation(Orientation::Vertical);
+            bar.set_inverted(true);
+            bar.set_hexpand(config.position == VisualizerPosition::Behind);
+            bar.add_css_class("bar");
+
+            // the default offsets would recolour bars by height
+            for offset in [
+                gtk::LEVEL_BAR_OFFSET_LOW,
+                gtk::LEVEL_BAR_OFFSET_HIGH,
+                gtk::LEVEL_BAR_OFFSET_FULL,
+            ] {
+                bar.remove_offset_value(Some(offset));
+            }
+
+            container.append(&bar);
+            bar
+        })
+        .collect();
+
+    (container, bars)
+}
+
+/// Sets the bar heights to the frame's values,
+/// or clears them if the frame is empty.
+pub fn update(bars: &[LevelBar], frame: &[f64]) {
+    for (index, bar) in bars.iter().enumerate() {
+        bar.set_value(frame.get(index).copied().unwrap_or_default());
+    }
+}
@@ -1,15 +1,20 @@
 use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
-use crate::clients::networkmanager::{Client, ClientState};
+use crate::clients::networkmanager::{AccessPoint, Client, ClientState, VpnConnection, VpnState};
 use crate::config::{CommonConfig, default};
-use crate::modules::{Module, ModuleInfo, ModuleParts, WidgetContext};
+use crate::image;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
 use crate::{module_impl, spawn};
 use color_eyre::Result;
 use futures_lite::StreamExt;
 use futures_signals::signal::SignalExt;
 use gtk::prelude::*;
-use gtk::{Box as GtkBox, ContentFit, Picture};
+use gtk::{Box as GtkBox, Button, ContentFit, Label, Orientation, PasswordEntry, Picture, Switch};
+use gtk_layer_shell::{KeyboardMode, LayerShell};
 use serde::Deserialize;
 use tokio::sync::mpsc::Receiver;
+use tracing::error;
 
 #[derive(Debug, Deserialize, Clone)]
 #[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
@@ -17,6 +22,11 @@ use tokio::sync::mpsc::Receiver;
 pub struct NetworkManagerModule {
     icon_size: i32,
 
+    /// Size to render the signal strength icons in the popup at.
+    ///
+    /// **Default**: `16`
+    popup_icon_size: i32,
+
     #[serde(flatten)]
     pub common: Option<CommonConfig>,
 }
@@ -25,30 +35,118 @@ impl Default for NetworkManagerModule {
     fn default() -> Self {
         Self {
             icon_size: default::IconSize::Small as i32,
+            popup_icon_size: default::IconSize::Tiny as i32,
             common: Some(CommonConfig::default()),
         }
     }
 }
 
-impl Module<GtkBox> for NetworkManagerModule {
-    type SendMessage = ClientState;
-    type ReceiveMessage = ();
+#[derive(Debug, Clone)]
+pub enum NetworkManagerUpdate {
+    State(ClientState),
+    /// The Wi-Fi networks visible to the wireless device.
+    AccessPoints(Vec<AccessPoint>),
+    /// Whether a requested scan is running.
+    Scanning(bool),
+    /// The saved VPN and WireGuard connections.
+    Vpn(Vec<VpnConnection>),
+}
+
+#[derive(Debug, Clone)]
+pub enum UiEvent {
+    /// Requests the latest list of networks.
+    Refresh,
+    /// Scans for networks, then refreshes the list.
+    Scan,
+    /// Connects to the network,
+    /// with the password if it is new and secured.
+    Connect(AccessPoint, Option<String>),
+    /// Brings the VPN connection up or down.
+    SetVpn(VpnConnection, bool),
+}
+
+impl Module<Button> for NetworkManagerModule {
+    type SendMessage = NetworkManagerUpdate;
+    type ReceiveMessage = UiEvent;
 
     module_impl!("network_manager");
 
+    fn coalesce_key(update: &Self::SendMessage) -> Option<String> {
+        // each update is the full state of its kind
+        let key = match update {
+            NetworkManagerUpdate::State(_) => "state",
+            NetworkManagerUpdate::AccessPoints(_) => "access_points",
+            NetworkManagerUpdate::Scanning(_) => "scanning",
+            NetworkManagerUpdate::Vpn(_) => "vpn",
+        };
+
+        Some(key.to_string())
+    }
+
     fn spawn_controller(
         &self,
         _: &ModuleInfo,
-        context: &WidgetContext<ClientState, ()>,
-        _: Receiver<()>,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
         let client = context.try_client::<Client>()?;
         let mut client_signal = client.subscribe().to_stream();
+        let mut vpn_signal = client.subscribe_vpn().to_stream();
         let tx = context.tx.clone();
 
+        {
+            let tx = tx.clone();
+            let client = client.clone();
+
+            spawn(async move {
+                while let Some(state) = client_signal.next().await {
+                    tx.send_update(NetworkManagerUpdate::State(state)).await;
+
+                    // the connected network may have changed
+                    send_access_points(&client, &tx).await;
+                }
+            });
+        }
+
+        {
+            let tx = tx.clone();
+
+            spawn(async move {
+                while let Some(connections) = vpn_signal.next().await {
+                    tx.send_update(NetworkManagerUpdate::Vpn(connections)).await;
+                }
+            });
+        }
+
         spawn(async move {
-            while let Some(state) = client_signal.next().await {
-                tx.send_update(state).await;
+            while let Some(event) = rx.recv().await {
+                match event {
+                    UiEvent::Refresh => {}
+                    UiEvent::Scan => {
+                        tx.send_update(NetworkManagerUpdate::Scanning(true)).await;
+
+                        if let Err(err) = client.scan().await {
+                            error!("Failed to scan for Wi-Fi networks: {err:?}");
+                        }
+
+                        tx.send_update(NetworkManagerUpdate::Scanning(false)).await;
+                    }
+                    UiEvent::Connect(access_point, password) => {
+                        if let Err(err) = client.connect(&access_point, password.as_deref()).await {
+                            error!("Failed to connect to '{}': {err:?}", access_point.ssid);
+                        }
+                    }
+                    UiEvent::SetVpn(connection, active) => {
+                        if let Err(err) = client.set_vpn_active(&connection, active).await {
+                            error!("Failed to toggle VPN '{}': {err:?}", connection.id);
+                        }
+
+                        // VPN changes are picked up by the client
+                        continue;
+                    }
+                }
+
+                send_access_points(&client, &tx).await;
             }
         });
 
@@ -57,11 +155,13 @@ impl Module<GtkBox> for NetworkManagerModule {
 
     fn into_widget(
         self,
-        context: WidgetContext<ClientState, ()>,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
         info: &ModuleInfo,
-    ) -> Result<ModuleParts<GtkBox>> {
+    ) -> Result<ModuleParts<Button>> {
         const INITIAL_ICON_NAME: &str = "content-loading-symbolic";
 
+        let button = Button::new();
+
         let container = GtkBox::new(info.bar_position.orientation(), 0);
         let icon = Picture::builder()
             .content_fit(ContentFit::ScaleDown)
@@ -69,40 +169,375 @@ impl Module<GtkBox> for NetworkManagerModule {
         icon.add_css_class("icon");
         container.append(&icon);
 
-        let image_provider = context.ironbar.image_provider();
+        // shown alongside the connection icon while any VPN is up
+        let vpn_icon = Picture::builder()
+            .content_fit(ContentFit::ScaleDown)
+            .visible(false)
+            .build();
+        vpn_icon.add_css_class("vpn-icon");
+        container.append(&vpn_icon);
+
+        button.set_child(Some(&container));
+
+        {
+            let tx = context.tx.clone();
+            let controller_tx = context.controller_tx.clone();
+
+            button.connect_clicked(move |button| {
+                controller_tx.send_spawn(UiEvent::Refresh);
+                tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        let image_provider = context.image_provider();
 
         glib::spawn_future_local({
             let image_provider = image_provider.clone();
             let icon = icon.clone();
+            let vpn_icon = vpn_icon.clone();
 
             async move {
                 image_provider
                     .load_into_picture_silent(INITIAL_ICON_NAME, self.icon_size, false, &icon)
                     .await;
+                image_provider
+                    .load_into_picture_silent(
+                        "network-vpn-symbolic",
+                        self.icon_size,
+                        false,
+                        &vpn_icon,
+                    )
+                    .await;
             }
         });
 
-        context.subscribe().recv_glib_async((), move |(), state| {
-            let image_provider = image_provider.clone();
-            let icon = icon.clone();
+        let icon_size = self.icon_size;
 
-            let icon_name = match state {
-                ClientState::WiredConnected => "network-wired-symbolic",
-                ClientState::WifiConnected => "network-wireless-symbolic",
-                ClientState::CellularConnected => "network-cellular-symbolic",
-                ClientState::VpnConnected => "network-vpn-symbolic",
-                ClientState::WifiDisconnected => "network-wireless-acquiring-symbolic",
-                ClientState::Offline => "network-wireless-disabled-symbolic",
-                ClientState::Unknown => "dialog-question-symbolic",
-            };
+        context
+            .subscribe()
+            .recv_glib_async(&button, move |button, update| {
+                let image_provider = image_provider.clone();
+                let icon = icon.clone();
 
-            async move {
-                image_provider
-                    .load_into_picture_silent(icon_name, self.icon_size, false, &icon)
-                    .await;
+                if let NetworkManagerUpdate::Vpn(connections) = &update {
+                    let active = connections
+                        .iter()
+                        .any(|connection| connection.state == VpnState::Active);
+
+                    vpn_icon.set_visible(active);
+                    if active {
+                        button.add_css_class("vpn-active");
+                    } else {
+                        button.remove_css_class("vpn-active");
+                    }
+                }
+
+                let icon_name = match update {
+                    NetworkManagerUpdate::State(state) => Some(match state {
+                        ClientState::WiredConnected => "network-wired-symbolic",
+                        ClientState::WifiConnected => "network-wireless-symbolic",
+                        ClientState::CellularConnected => "network-cellular-symbolic",
+                        ClientState::VpnConnected => "network-vpn-symbolic",
+                        ClientState::WifiDisconnected => "network-wireless-acquiring-symbolic",
+                        ClientState::Offline => "network-wireless-disabled-symbolic",
+                        ClientState::Unknown => "dialog-question-symbolic",
+                    }),
+                    _ => None,
+                };
+
+                async move {
+                    if let Some(icon_name) = icon_name {
+                        image_provider
+                            .load_into_picture_silent(icon_name, icon_size, false, &icon)
+                            .await;
+                    }
+                }
+            });
+
+        let popup = self
+            .into_popup(context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<GtkBox>
+    where
+        Self: Sized,
+    {
+        let container = GtkBox::new(Orientation::Vertical, 5);
+        container.add_css_class("wifi");
+
+        let header = GtkBox::new(Orientation::Horizontal, 5);
+        header.add_css_class("header");
+
+        let title = Label::new(Some("Wi-Fi"));
+        title.add_css_class("title");
+        title.set_hexpand(true);
+        title.set_xalign(0.0);
+        header.append(&title);
+
+        let rescan = Button::with_label("Rescan");
+        rescan.add_css_class("rescan");
+        header.append(&rescan);
+
+        {
+            let tx = context.controller_tx.clone();
+            rescan.connect_clicked(move |_| tx.send_spawn(UiEvent::Scan));
+        }
+
+        container.append(&header);
+
+        let networks = GtkBox::new(Orientation::Vertical, 0);
+        networks.add_css_class("networks");
+        container.append(&networks);
+
+        let vpn = GtkBox::new(Orientation::Vertical, 5);
+        vpn.add_css_class("vpn");
+        vpn.set_visible(false);
+
+        let vpn_title = Label::new(Some("VPN"));
+        vpn_title.add_css_class("title");
+        vpn_title.set_xalign(0.0);
+        vpn.append(&vpn_title);
+
+        let vpn_connections = GtkBox::new(Orientation::Vertical, 0);
+        vpn_connections.add_css_class("connections");
+        vpn.append(&vpn_connections);
+
+        let popup = GtkBox::new(Orientation::Vertical, 5);
+        popup.append(&container);
+        popup.append(&vpn);
+
+        let image_provider = context.image_provider();
+        let controller_tx = context.controller_tx.clone();
+        let icon_size = self.popup_icon_size;
+
+        context
+            .subscribe()
+            .recv_glib((), move |(), update| match update {
+                NetworkManagerUpdate::AccessPoints(access_points) => {
+                    while let Some(child) = networks.first_child() {
+                        networks.remove(&child);
+                    }
+
+                    for access_point in access_points {
+                        let row =
+                            network_row(access_point, icon_size, &image_provider, &controller_tx);
+                        networks.append(&row);
+                    }
+                }
+                NetworkManagerUpdate::Scanning(scanning) => {
+                    rescan.set_sensitive(!scanning);
+                    rescan.set_label(if scanning { "Scanning…" } else { "Rescan" });
+                }
+                NetworkManagerUpdate::Vpn(connections) => {
+                    while let Some(child) = vpn_connections.first_child() {
+                        vpn_connections.remove(&child);
+                    }
+
+                    vpn.set_visible(!connections.is_empty());
+
+                    for connection in connections {
+                        vpn_connections.append(&vpn_row(connection, &controller_tx));
+                    }
+                }
+                NetworkManagerUpdate::State(_) => {}
+            });
+
+        Some(popup)
+    }
+}
+
+async fn send_access_points(
+    client: &Client,
+    tx: &tokio::sync::mpsc::Sender<ModuleUpdateEvent<NetworkManagerUpdate>>,
+) {
+    match client.access_points().await {
+        Ok(access_points) => {
+            tx.send_update(NetworkManagerUpdate::AccessPoints(access_points))
+                .await;
+        }
+        Err(err) => error!("Failed to get Wi-Fi networks: {err:?}"),
+    }
+}
+
+/// Gets the icon for the signal strength, from `0` to `100`.
+fn strength_icon(strength: u8) -> &'static str {
+    match strength {
+        76.. => "network-wireless-signal-excellent-symbolic",
+        51..=75 => "network-wireless-signal-good-symbolic",
+        26..=50 => "network-wireless-signal-ok-symbolic",
+        1..=25 => "network-wireless-signal-weak-symbolic",
+        0 => "network-wireless-signal-none-symbolic",
+    }
+}
+
+/// Creates the popup row for a VPN connection,
+/// with a switch to bring it up or down.
+fn vpn_row(
+    connection: VpnConnection,
+    controller_tx: &tokio::sync::mpsc::Sender<UiEvent>,
+) -> GtkBox {
+    let row = GtkBox::new(Orientation::Horizontal, 5);
+    row.add_css_class("connection");
+    row.add_css_class(&connection.kind);
+    row.add_css_class(connection.state.css_class());
+    row.add_css_class(&format!("connection-{}", css_name(&connection.id)));
+
+    let label = Label::new(Some(&connection.id));
+    label.add_css_class("name");
+    label.set_hexpand(true);
+    label.set_xalign(0.0);
+    row.append(&label);
+
+    let switch = Switch::new();
+    switch.set_active(connection.state != VpnState::Inactive);
+    row.append(&switch);
+
+    let controller_tx = controller_tx.clone();
+    switch.connect_state_set(move |_, active| {
+        controller_tx.send_spawn(UiEvent::SetVpn(connection.clone(), active));
+        glib::Propagation::Proceed
+    });
+
+    row
+}
+
+/// Converts the connection name for use in a CSS class,
+/// such as `work-vpn` for `Work VPN`.
+fn css_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect()
+}
+
+/// Creates the popup row for a network.
+///
+/// Clicking the row connects to the network,
+/// first showing a password entry if the network is new and secured.
+fn network_row(
+    access_point: AccessPoint,
+    icon_size: i32,
+    image_provider: &image::Provider,
+    controller_tx: &tokio::sync::mpsc::Sender<UiEvent>,
+) -> GtkBox {
+    let row = GtkBox::new(Orientation::Vertical, 5);
+    row.add_css_class("network");
+
+    if access_point.active {
+        row.add_css_class("active");
+    }
+    if access_point.secured {
+        row.add_css_class("secured");
+    }
+    if access_point.known {
+        row.add_css_class("known");
+    }
+
+    let button = Button::new();
+    let content = GtkBox::new(Orientation::Horizontal, 5);
+
+    let icon = Picture::builder()
+        .content_fit(ContentFit::ScaleDown)
+        .build();
+    icon.add_css_class("icon");
+    content.append(&icon);
+
+    {
+        let image_provider = image_provider.clone();
+        let icon = icon.clone();
+        let icon_name = strength_icon(access_point.strength);
+
+        glib::spawn_future_local(async move {
+            image_provider
+                .load_into_picture_silent(icon_name, icon_size, false, &icon)
+                .await;
+        });
+    }
+
+    let ssid = Label::new(Some(&access_point.ssid));
+    ssid.add_css_class("ssid");
+    ssid.set_hexpand(true);
+    ssid.set_xalign(0.0);
+    content.append(&ssid);
+
+    button.set_child(Some(&content));
+    button.set_tooltip_text(Some(&format!("{}%", access_point.strength)));
+    row.append(&button);
+
+    let password_box = GtkBox::new(Orientation::Horizontal, 5);
+    password_box.add_css_class("password");
+    password_box.set_visible(false);
+
+    let entry = PasswordEntry::builder()
+        .show_peek_icon(true)
+        .hexpand(true)
+        .build();
+    password_box.append(&entry);
+
+    let connect = Button::with_label("Connect");
+    connect.add_css_class("connect");
+    password_box.append(&connect);
+
+    row.append(&password_box);
+
+    let needs_password = access_point.secured && !access_point.known;
+
+    {
+        let controller_tx = controller_tx.clone();
+        let access_point = access_point.clone();
+        let password_box = password_box.clone();
+        let entry = entry.clone();
+
+        button.connect_clicked(move |button| {
+            if access_point.active {
+                return;
+            }
+
+            if needs_password {
+                // the password entry needs keyboard focus,
+                // which is returned once the popup closes
+                if let Some(window) = button.root().and_downcast::<gtk::Window>() {
+                    window.set_keyboard_mode(KeyboardMode::OnDemand);
+                }
+
+                password_box.set_visible(!password_box.is_visible());
+                entry.grab_focus();
+            } else {
+                controller_tx.send_spawn(UiEvent::Connect(access_point.clone(), None));
             }
         });
+    }
 
-        Ok(ModuleParts::new(container, None))
+    let submit = {
+        let controller_tx = controller_tx.clone();
+        let entry = entry.clone();
+
+        move || {
+            let password = entry.text().to_string();
+            if !password.is_empty() {
+                controller_tx.send_spawn(UiEvent::Connect(access_point.clone(), Some(password)));
+            }
+        }
+    };
+
+    {
+        let submit = submit.clone();
+        entry.connect_activate(move |_| submit());
     }
+
+    connect.connect_clicked(move |_| submit());
+
+    row
 }
@@ -0,0 +1,140 @@
+use super::UiEvent;
+use crate::channels::AsyncSenderExt;
+use crate::clients::notifications::Notification;
+use crate::gtk_helpers::{IronbarGtkExt, MouseButton};
+use crate::image;
+use gtk::prelude::*;
+use gtk::{Align, Button, ContentFit, Entry, Label, Orientation, Picture};
+use tokio::sync::mpsc::Sender;
+
+/// The action invoked by clicking on the notification itself.
+const DEFAULT_ACTION: &str = "default";
+
+/// Creates a widget for a single notification,
+/// with buttons for its actions and an entry for inline replies.
+///
+/// Used for both toasts and the popup history.
+pub fn build(
+    notification: &Notification,
+    icon_size: i32,
+    image_provider: &image::Provider,
+    tx: &Sender<UiEvent>,
+) -> gtk::Box {
+    let id = notification.id;
+
+    let container = gtk::Box::new(Orientation::Vertical, 5);
+    container.add_css_class("notification");
+    container.add_css_class(notification.urgency.class());
+
+    let header = gtk::Box::new(Orientation::Horizontal, 10);
+    container.append(&header);
+
+    let picture = Picture::builder()
+        .content_fit(ContentFit::ScaleDown)
+        .valign(Align::Start)
+        .build();
+    picture.add_css_class("icon");
+    header.append(&picture);
+
+    let input = if notification.app_icon.is_empty() {
+        notification.app_name.clone()
+    } else {
+        notification.app_icon.clone()
+    };
+
+    {
+        let image_provider = image_provider.clone();
+        let picture = picture.clone();
+        glib::spawn_future_local(async move {
+            image_provider
+                .load_into_picture_silent(&input, icon_size, true, &picture)
+                .await;
+        });
+    }
+
+    let text = gtk::Box::new(Orientation::Vertical, 2);
+    text.set_hexpand(true);
+    header.append(&text);
+
+    let app_name = Label::new(Some(&notification.app_name));
+    app_name.add_css_class("app-name");
+    app_name.set_halign(Align::Start);
+    text.append(&app_name);
+
+    let summary = Label::new(Some(&notification.summary));
+    summary.add_css_class("summary");
+    summary.set_halign(Align::Start);
+    summary.set_wrap(true);
+    text.append(&summary);
+
+    if !notification.body.is_empty() {
+        let body = Label::new(Some(&notification.body));
+        body.add_css_class("body");
+        body.set_halign(Align::Start);
+        body.set_wrap(true);
+        text.append(&body);
+    }
+
+    let close = Button::from_icon_name("window-close-symbolic");
+    close.add_css_class("close");
+    close.set_valign(Align::Start);
+    header.append(&close);
+
+    {
+        let tx = tx.clone();
+        close.connect_clicked(move |_| tx.send_spawn(UiEvent::Close(id)));
+    }
+
+    if notification
+        .actions
+        .iter()
+        .any(|action| action.key == DEFAULT_ACTION)
+    {
+        let tx = tx.clone();
+        header.connect_pressed(MouseButton::Primary, move || {
+            tx.send_spawn(UiEvent::Action(id, DEFAULT_ACTION.to_string()));
+        });
+    }
+
+    let actions = notification
+        .actions
+        .iter()
+        .filter(|action| action.key != DEFAULT_ACTION)
+        .collect::<Vec<_>>();
+
+    if !actions.is_empty() {
+        let container_actions = gtk::Box::new(Orientation::Horizontal, 5);
+        container_actions.add_css_class("actions");
+        container_actions.set_homogeneous(true);
+
+        for action in actions {
+            let button = Button::with_label(&action.label);
+            button.add_css_class("action");
+
+            let tx = tx.clone();
+            let key = action.key.clone();
+            button.connect_clicked(move |_| tx.send_spawn(UiEvent::Action(id, key.clone())));
+
+            container_actions.append(&button);
+        }
+
+        container.append(&container_actions);
+    }
+
+    if let Some(placeholder) = &notification.reply_placeholder {
+        let entry = Entry::builder().placeholder_text(placeholder).build();
+        entry.add_css_class("reply");
+
+        let tx = tx.clone();
+        entry.connect_activate(move |entry| {
+            let text = entry.text();
+            if !text.is_empty() {
+                tx.send_spawn(UiEvent::Reply(id, text.to_string()));
+            }
+        });
+
+        container.append(&entry);
+    }
+
+    container
+}
@@ -0,0 +1,193 @@
+use super::{NotificationsUpdate, UiEvent};
+use crate::channels::AsyncSenderExt;
+#[cfg(any(feature = "ipc", feature = "toplevel"))]
+use crate::channels::BroadcastReceiverExt;
+#[cfg(feature = "toplevel")]
+use crate::clients::wayland::{self, ToplevelEvent, ToplevelInfo};
+use crate::modules::WidgetContext;
+use crate::spawn;
+use chrono::{Local, NaiveTime};
+use serde::{Deserialize, Deserializer};
+#[cfg(feature = "toplevel")]
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(any(feature = "ipc", feature = "toplevel"))]
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+#[cfg(feature = "ipc")]
+use tracing::error;
+#[cfg(not(feature = "toplevel"))]
+use tracing::warn;
+
+/// How often to check whether the schedule has started or ended.
+const SCHEDULE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A daily period to enable do not disturb for.
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct DndSchedule {
+    /// The time to enable do not disturb, in 24-hour `HH:MM` format.
+    ///
+    /// **Required**
+    #[serde(deserialize_with = "deserialize_time")]
+    #[cfg_attr(feature = "extras", schemars(with = "String"))]
+    start: NaiveTime,
+
+    /// The time to disable do not disturb, in 24-hour `HH:MM` format.
+    /// This can be earlier than `start` for periods which pass midnight.
+    ///
+    /// **Required**
+    #[serde(deserialize_with = "deserialize_time")]
+    #[cfg_attr(feature = "extras", schemars(with = "String"))]
+    end: NaiveTime,
+}
+
+impl DndSchedule {
+    fn contains(self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+fn deserialize_time<'de, D>(deserializer: D) -> Result<NaiveTime, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let time = String::deserialize(deserializer)?;
+    NaiveTime::parse_from_str(&time, "%H:%M")
+        .map_err(|_| serde::de::Error::custom(format!("invalid time: {time}")))
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Trigger {
+    Schedule(bool),
+    Fullscreen(bool),
+}
+
+/// Enables do not disturb during the schedule,
+/// or while a fullscreen window is focused,
+/// and disables it again afterwards.
+///
+/// Changes are only sent when entering or leaving these periods,
+/// so do not disturb can still be toggled manually in between.
+pub fn spawn_auto_dnd(
+    schedule: Option<DndSchedule>,
+    fullscreen: bool,
+    context: &WidgetContext<NotificationsUpdate, UiEvent>,
+) {
+    if schedule.is_none() && !fullscreen {
+        return;
+    }
+
+    let (trigger_tx, mut trigger_rx) = mpsc::channel(8);
+
+    if let Some(schedule) = schedule {
+        let tx = trigger_tx.clone();
+
+        spawn(async move {
+            loop {
+                let active = schedule.contains(Local::now().time());
+                tx.send_expect(Trigger::Schedule(active)).await;
+                sleep(SCHEDULE_INTERVAL).await;
+            }
+        });
+    }
+
+    if fullscreen {
+        #[cfg(feature = "toplevel")]
+        watch_fullscreen(context.client::<wayland::Client>(), trigger_tx);
+
+        #[cfg(not(feature = "toplevel"))]
+        warn!("`dnd_fullscreen` requires the `toplevel` feature");
+    }
+
+    let tx = context.controller_tx.clone();
+
+    spawn(async move {
+        let mut scheduled = false;
+        let mut fullscreen = false;
+        let mut active = false;
+
+        while let Some(trigger) = trigger_rx.recv().await {
+            match trigger {
+                Trigger::Schedule(value) => scheduled = value,
+                Trigger::Fullscreen(value) => fullscreen = value,
+            }
+
+            if active != (scheduled || fullscreen) {
+                active = !active;
+                tx.send_expect(UiEvent::SetDnd(active)).await;
+            }
+        }
+    });
+}
+
+/// Sends whether the focused window on any output is fullscreen
+/// each time the windows change.
+#[cfg(feature = "toplevel")]
+fn watch_fullscreen(wl: Arc<wayland::Client>, tx: mpsc::Sender<Trigger>) {
+    let focused_fullscreen = |windows: Vec<ToplevelInfo>| {
+        windows
+            .into_iter()
+            .find(|info| info.focused && info.fullscreen)
+            .map(|info| info.id)
+    };
+
+    let mut toplevels = wl.subscribe_toplevels();
+    let mut current = focused_fullscreen(wl.toplevel_info_all());
+
+    spawn(async move {
+        tx.send_expect(Trigger::Fullscreen(current.is_some())).await;
+
+        loop {
+            match toplevels.recv_tracked("notifications fullscreen").await {
+                Ok(ToplevelEvent::New(info) | ToplevelEvent::Update(info)) if info.focused => {
+                    current = info.fullscreen.then_some(info.id);
+                }
+                Ok(ToplevelEvent::Update(info) | ToplevelEvent::Remove(info))
+                    if current == Some(info.id) =>
+                {
+                    current = None;
+                }
+                Ok(_) => continue,
+                Err(RecvError::Lagged(_)) => current = focused_fullscreen(wl.toplevel_info_all()),
+                Err(RecvError::Closed) => break,
+            }
+
+            tx.send_expect(Trigger::Fullscreen(current.is_some())).await;
+        }
+    });
+}
+
+/// Sets the ironvar with the name to the do not disturb state
+/// each time it changes.
+#[cfg(feature = "ipc")]
+pub fn expose_variable(name: String, context: &WidgetContext<NotificationsUpdate, UiEvent>) {
+    use crate::ironvar::WritableNamespace;
+
+    let mut rx = context.subscribe();
+
+    spawn(async move {
+        let mut last = None;
+
+        loop {
+            match rx.recv_tracked("notifications variable").await {
+                Ok(NotificationsUpdate::State(state)) if last != Some(state.dnd) => {
+                    last = Some(state.dnd);
+
+                    if let Err(err) =
+                        crate::Ironbar::variable_manager().set(&name, state.dnd.to_string())
+                    {
+                        error!("Failed to set ironvar '{name}': {err:?}");
+                    }
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
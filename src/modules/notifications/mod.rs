@@ -0,0 +1,635 @@
+mod card;
+mod dnd;
+mod toast;
+
+use self::dnd::DndSchedule;
+use self::toast::{ToastOptions, Toasts};
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::clients::notifications::{self, Notification, Urgency, daemon};
+use crate::config::{CommonConfig, default};
+use crate::gtk_helpers::IronbarGtkExt;
+use crate::image::IconButton;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::{module_impl, spawn};
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation, Overlay, PolicyType, ScrolledWindow};
+use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::{Receiver, Sender};
+use tracing::warn;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct NotificationsModule {
+    /// Whether to show the current notification count.
+    ///
+    /// **Default**: `true`
+    show_count: bool,
+
+    /// Whether to run the built-in notification daemon,
+    /// rather than connecting to an external daemon.
+    ///
+    /// Only one notification daemon can run at a time,
+    /// so any other daemon must be stopped first.
+    ///
+    /// **Default**: `false`
+    daemon: bool,
+
+    /// The external notification daemon to connect to
+    /// when not running the built-in daemon.
+    ///
+    /// **Valid options**: `swaync`, `mako`
+    /// <br>
+    /// **Default**: `swaync`
+    backend: notifications::ClientType,
+
+    /// The maximum number of notifications to keep in the history.
+    /// The oldest notifications are dismissed once this is exceeded.
+    ///
+    /// This only applies in daemon mode,
+    /// and the value from the first module to start the daemon is used.
+    ///
+    /// **Default**: `50`
+    history_size: usize,
+
+    /// Whether to show new notifications as toasts,
+    /// which pop up on the bar's monitor.
+    /// Toasts are not shown while do not disturb is enabled,
+    /// except for critical notifications.
+    ///
+    /// This only applies in daemon mode.
+    ///
+    /// **Default**: `true`
+    toasts: bool,
+
+    /// The screen position to show toasts at.
+    ///
+    /// **Valid options**: `top`, `top_left`, `top_right`, `bottom`, `bottom_left`, `bottom_right`
+    /// <br>
+    /// **Default**: `top_right`
+    toast_position: ToastPosition,
+
+    /// The gap between toasts and the screen edges, in pixels.
+    ///
+    /// **Default**: `10`
+    toast_margin: i32,
+
+    /// The number of milliseconds to show toasts for,
+    /// unless the notification sets its own timeout.
+    /// Critical notifications stay open until dismissed.
+    ///
+    /// **Default**: `5000`
+    toast_timeout: u64,
+
+    /// The maximum number of toasts to show at once.
+    /// The oldest toasts are hidden once this is exceeded.
+    ///
+    /// **Default**: `3`
+    max_toasts: usize,
+
+    /// The size of application icons in toasts and the popup.
+    ///
+    /// **Default**: `32`
+    icon_size: i32,
+
+    /// Automatically enables do not disturb between two times each day,
+    /// and disables it again afterwards.
+    /// Do not disturb can still be toggled manually in between.
+    ///
+    /// See [dnd schedule](#dnd-schedule).
+    ///
+    /// **Default**: `null`
+    dnd_schedule: Option<DndSchedule>,
+
+    /// Whether to automatically enable do not disturb
+    /// while a fullscreen window is focused,
+    /// and disable it again afterwards.
+    ///
+    /// This requires the `toplevel` feature.
+    ///
+    /// **Default**: `false`
+    dnd_fullscreen: bool,
+
+    /// The name of an [ironvar](ironvars) to set to `true` or `false`
+    /// with the current do not disturb state.
+    ///
+    /// **Default**: `null`
+    #[cfg(feature = "ipc")]
+    dnd_variable: Option<String>,
+
+    /// Per-application rules for notifications.
+    ///
+    /// See [filters](#filters).
+    filters: Filters,
+
+    /// Notification state icons.
+    ///
+    /// See [icons](#icons).
+    icons: Icons,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for NotificationsModule {
+    fn default() -> Self {
+        Self {
+            show_count: true,
+            daemon: false,
+            backend: notifications::ClientType::default(),
+            history_size: 50,
+            toasts: true,
+            toast_position: ToastPosition::default(),
+            toast_margin: 10,
+            toast_timeout: 5000,
+            max_toasts: 3,
+            icon_size: default::IconSize::Normal as i32,
+            dnd_schedule: None,
+            dnd_fullscreen: false,
+            #[cfg(feature = "ipc")]
+            dnd_variable: None,
+            filters: Filters::default(),
+            icons: Icons::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum ToastPosition {
+    Top,
+    TopLeft,
+    #[default]
+    TopRight,
+    Bottom,
+    BottomLeft,
+    BottomRight,
+}
+
+impl ToastPosition {
+    const fn is_bottom(self) -> bool {
+        matches!(self, Self::Bottom | Self::BottomLeft | Self::BottomRight)
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+struct Filters {
+    /// Names of applications whose notifications
+    /// are shown as toasts even while do not disturb is enabled.
+    ///
+    /// This only applies in daemon mode.
+    ///
+    /// **Default**: `[]`
+    allow: Vec<String>,
+
+    /// Names of applications whose notifications are ignored entirely.
+    ///
+    /// This only applies in daemon mode,
+    /// and the value from the first module to start the daemon is used.
+    ///
+    /// **Default**: `[]`
+    deny: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+struct Icons {
+    /// Icon to show when the panel is closed, with no notifications.
+    ///
+    /// **Default**: `󰍥`
+    closed_none: String,
+
+    /// Icon to show when the panel is closed, with notifications.
+    ///
+    /// **Default**: `󱥂`
+    closed_some: String,
+
+    /// Icon to show when the panel is closed, with DnD enabled.
+    /// Takes higher priority than count-based icons.
+    ///
+    /// **Default**: `󱅯`
+    closed_dnd: String,
+
+    /// Icon to show when the panel is open, with no notifications.
+    ///
+    /// **Default**: `󰍡`
+    open_none: String,
+
+    /// Icon to show when the panel is open, with notifications.
+    ///
+    /// **Default**: `󱥁`
+    open_some: String,
+
+    /// Icon to show when the panel is open, with DnD enabled.
+    /// Takes higher priority than count-based icons.
+    ///
+    /// **Default**: `󱅮`
+    open_dnd: String,
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self {
+            closed_none: "󰍥".to_string(),
+            closed_some: "󱥂".to_string(),
+            closed_dnd: "󱅯".to_string(),
+            open_none: "󰍡".to_string(),
+            open_some: "󱥁".to_string(),
+            open_dnd: "󱅮".to_string(),
+        }
+    }
+}
+
+impl Icons {
+    fn icon(&self, value: notifications::State) -> &str {
+        match (value.panel_open, value.count > 0, value.dnd) {
+            (true, _, true) => &self.open_dnd,
+            (true, true, false) => &self.open_some,
+            (true, false, false) => &self.open_none,
+            (false, _, true) => &self.closed_dnd,
+            (false, true, false) => &self.closed_some,
+            (false, false, false) => &self.closed_none,
+        }
+        .as_str()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum NotificationsUpdate {
+    /// The current state, used for the bar icon and count.
+    State(notifications::State),
+    /// All open notifications, newest first.
+    /// Only sent in daemon mode.
+    History(Vec<Notification>),
+    /// A new notification to show as a toast.
+    Toast(Notification),
+    /// The notification with the ID was dismissed.
+    Close(u32),
+}
+
+#[derive(Debug, Clone)]
+pub enum UiEvent {
+    ToggleVisibility,
+    Close(u32),
+    /// Invokes the action with the key on the notification with the ID.
+    Action(u32, String),
+    /// Sends an inline reply to the notification with the ID.
+    Reply(u32, String),
+    ClearAll,
+    ToggleDnd,
+    SetDnd(bool),
+}
+
+impl Module<Overlay> for NotificationsModule {
+    type SendMessage = NotificationsUpdate;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("notifications");
+
+    fn coalesce_key(update: &Self::SendMessage) -> Option<String> {
+        match update {
+            NotificationsUpdate::State(_) => Some("state".to_string()),
+            NotificationsUpdate::History(_) => Some("history".to_string()),
+            NotificationsUpdate::Toast(_) | NotificationsUpdate::Close(_) => None,
+        }
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: Receiver<Self::ReceiveMessage>,
+    ) -> color_eyre::Result<()>
+    where
+        <Self as Module<Overlay>>::SendMessage: Clone,
+    {
+        dnd::spawn_auto_dnd(self.dnd_schedule, self.dnd_fullscreen, context);
+
+        #[cfg(feature = "ipc")]
+        if let Some(variable) = self.dnd_variable.clone() {
+            dnd::expose_variable(variable, context);
+        }
+
+        if self.daemon {
+            let client = context
+                .ironbar
+                .clients
+                .borrow_mut()
+                .notification_daemon(self.history_size, &self.filters.deny)?;
+
+            spawn_daemon_controller(client, self.filters.allow.clone(), context.tx.clone(), rx);
+            return Ok(());
+        }
+
+        if !self.filters.allow.is_empty() || !self.filters.deny.is_empty() {
+            warn!("Notification filters are only supported in daemon mode");
+        }
+
+        let client = context
+            .ironbar
+            .clients
+            .borrow_mut()
+            .notifications(self.backend)?;
+
+        {
+            let mut rx = client.subscribe();
+            let initial_state = client.state();
+            let tx = context.tx.clone();
+
+            spawn(async move {
+                if let Some(state) = initial_state {
+                    tx.send_update(NotificationsUpdate::State(state)).await;
+                }
+
+                while let Ok(state) = rx.recv().await {
+                    tx.send_update(NotificationsUpdate::State(state)).await;
+                }
+            });
+        }
+
+        let tx = context.tx.clone();
+        spawn(async move {
+            while let Some(event) = rx.recv().await {
+                match event {
+                    UiEvent::ToggleVisibility => {
+                        client.toggle_visibility();
+                        tx.send_expect(ModuleUpdateEvent::ClosePopup).await;
+                    }
+                    UiEvent::SetDnd(dnd) => client.set_dnd(dnd),
+                    // toasts and the popup are only shown in daemon mode
+                    UiEvent::Close(_)
+                    | UiEvent::Action(..)
+                    | UiEvent::Reply(..)
+                    | UiEvent::ClearAll
+                    | UiEvent::ToggleDnd => {}
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> color_eyre::Result<ModuleParts<Overlay>>
+    where
+        <Self as Module<Overlay>>::SendMessage: Clone,
+    {
+        let overlay = Overlay::new();
+        let button = IconButton::new(&self.icons.closed_none, 16, context.image_provider());
+        button.add_css_class("button");
+        overlay.set_child(Some(&*button));
+
+        // TODO: make halign/valign configurable
+        let label = Label::builder()
+            .label("0")
+            .halign(Align::End)
+            .valign(Align::Start)
+            .build();
+
+        if self.show_count {
+            label.add_css_class("count");
+            label.set_can_target(false);
+            overlay.add_overlay(&label);
+        }
+
+        // the daemon has no panel of its own, so uses the popup instead
+        if self.daemon {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        } else {
+            let ctx = context.controller_tx.clone();
+            button.connect_clicked(move |_| {
+                ctx.send_spawn(UiEvent::ToggleVisibility);
+            });
+        }
+
+        let toasts = (self.daemon && self.toasts).then(|| {
+            let options = ToastOptions {
+                position: self.toast_position,
+                margin: self.toast_margin,
+                timeout: self.toast_timeout,
+                max_toasts: self.max_toasts,
+                icon_size: self.icon_size,
+            };
+
+            Toasts::new(
+                info.monitor,
+                options,
+                context.image_provider(),
+                &context.controller_tx,
+            )
+        });
+
+        if let Some(toasts) = &toasts {
+            let toasts = toasts.clone();
+            overlay.connect_destroy(move |_| toasts.destroy());
+        }
+
+        let icons = self.icons.clone();
+        let show_count = self.show_count;
+
+        context
+            .subscribe()
+            .recv_glib(&button, move |button, update| match update {
+                NotificationsUpdate::State(ev) => {
+                    let icon = icons.icon(ev);
+                    button.set_label(icon);
+
+                    label.set_label(&ev.count.to_string());
+                    label.set_visible(show_count && ev.count > 0);
+                }
+                NotificationsUpdate::Toast(notification) => {
+                    if let Some(toasts) = &toasts {
+                        toasts.show(&notification);
+                    }
+                }
+                NotificationsUpdate::Close(id) => {
+                    if let Some(toasts) = &toasts {
+                        toasts.close(id);
+                    }
+                }
+                NotificationsUpdate::History(_) => {}
+            });
+
+        let popup = if self.daemon {
+            self.into_popup(context, info)
+                .into_popup_parts(vec![&*button])
+        } else {
+            None
+        };
+
+        Ok(ModuleParts {
+            widget: overlay,
+            popup,
+        })
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let header = Label::new(None);
+        header.add_css_class("header");
+        header.set_halign(Align::Start);
+        container.append(&header);
+
+        let list = gtk::Box::new(Orientation::Vertical, 5);
+        list.add_css_class("list");
+
+        let scrolled = ScrolledWindow::builder()
+            .hscrollbar_policy(PolicyType::Never)
+            .min_content_height(200)
+            .child(&list)
+            .build();
+        container.append(&scrolled);
+
+        let actions = gtk::Box::new(Orientation::Horizontal, 5);
+        actions.set_halign(Align::End);
+        container.append(&actions);
+
+        let dnd = Button::with_label(&format!("{} Do not disturb", self.icons.closed_dnd));
+        dnd.add_css_class("btn-dnd");
+        actions.append(&dnd);
+
+        let tx = context.controller_tx.clone();
+        dnd.connect_clicked(move |_| tx.send_spawn(UiEvent::ToggleDnd));
+
+        let clear = Button::with_label(&format!("{} Clear", self.icons.closed_none));
+        clear.add_css_class("btn-clear");
+        actions.append(&clear);
+
+        let tx = context.controller_tx.clone();
+        clear.connect_clicked(move |_| tx.send_spawn(UiEvent::ClearAll));
+
+        let icon_size = self.icon_size;
+        let image_provider = context.image_provider();
+        let tx = context.controller_tx.clone();
+
+        context.subscribe().recv_glib(
+            (&header, &list, &clear, &dnd),
+            move |(header, list, clear, dnd), update| match update {
+                NotificationsUpdate::State(ev) => {
+                    if ev.dnd {
+                        dnd.add_css_class("active");
+                    } else {
+                        dnd.remove_css_class("active");
+                    }
+                }
+                NotificationsUpdate::History(notifications) => {
+                    header.set_label(&match notifications.len() {
+                        0 => "No notifications".to_string(),
+                        1 => "1 notification".to_string(),
+                        count => format!("{count} notifications"),
+                    });
+                    clear.set_sensitive(!notifications.is_empty());
+
+                    for child in list.children() {
+                        list.remove(&child);
+                    }
+
+                    for notification in &notifications {
+                        list.append(&card::build(notification, icon_size, &image_provider, &tx));
+                    }
+                }
+                NotificationsUpdate::Toast(_) | NotificationsUpdate::Close(_) => {}
+            },
+        );
+
+        Some(container)
+    }
+}
+
+/// Forwards events from the built-in daemon to the UI,
+/// and handles interactions with notifications.
+fn spawn_daemon_controller(
+    client: Arc<daemon::Client>,
+    allowed_apps: Vec<String>,
+    tx: Sender<ModuleUpdateEvent<NotificationsUpdate>>,
+    mut rx: Receiver<UiEvent>,
+) {
+    {
+        let client = client.clone();
+        let tx = tx.clone();
+
+        spawn(async move {
+            let mut events = client.subscribe();
+            send_daemon_state(&client, &tx).await;
+
+            loop {
+                match events.recv_tracked("notifications").await {
+                    Ok(daemon::Event::Notify(notification)) => {
+                        let allowed = notification.urgency == Urgency::Critical
+                            || notification.is_from(&allowed_apps);
+
+                        if !client.dnd() || allowed {
+                            tx.send_update(NotificationsUpdate::Toast(notification))
+                                .await;
+                        }
+                    }
+                    Ok(daemon::Event::Close(id)) => {
+                        tx.send_update(NotificationsUpdate::Close(id)).await;
+                    }
+                    Ok(daemon::Event::Dnd(_)) | Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
+
+                send_daemon_state(&client, &tx).await;
+            }
+        });
+    }
+
+    spawn(async move {
+        while let Some(event) = rx.recv().await {
+            match event {
+                // the daemon has no panel to toggle
+                UiEvent::ToggleVisibility => {}
+                UiEvent::Close(id) => client.close(id).await,
+                UiEvent::Action(id, key) => client.invoke_action(id, &key).await,
+                UiEvent::Reply(id, text) => client.reply(id, &text).await,
+                UiEvent::ClearAll => client.clear().await,
+                UiEvent::ToggleDnd => client.toggle_dnd(),
+                UiEvent::SetDnd(dnd) => client.set_dnd(dnd),
+            }
+        }
+    });
+}
+
+/// Sends the current state and history of the daemon to the UI.
+async fn send_daemon_state(
+    client: &daemon::Client,
+    tx: &Sender<ModuleUpdateEvent<NotificationsUpdate>>,
+) {
+    let notifications = client.notifications();
+
+    // the built-in daemon has no panel of its own
+    let state = notifications::State {
+        count: u32::try_from(notifications.len()).unwrap_or(u32::MAX),
+        dnd: client.dnd(),
+        panel_open: false,
+    };
+
+    tx.send_update(NotificationsUpdate::State(state)).await;
+    tx.send_update(NotificationsUpdate::History(notifications))
+        .await;
+}
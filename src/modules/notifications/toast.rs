@@ -0,0 +1,168 @@
+use super::{ToastPosition, UiEvent, card};
+use crate::clients::notifications::{Notification, Urgency};
+use crate::image;
+use crate::rc_mut;
+use glib::SourceId;
+use gtk::gdk::Monitor;
+use gtk::prelude::*;
+use gtk::{Orientation, Window};
+use gtk_layer_shell::{Edge, KeyboardMode, LayerShell};
+use indexmap::IndexMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::sync::mpsc::Sender;
+
+/// An overlay surface showing new notifications
+/// for a short time after they are received.
+#[derive(Clone)]
+pub struct Toasts {
+    window: Window,
+    container: gtk::Box,
+    /// The open toasts, oldest first, and their timeouts.
+    toasts: Rc<RefCell<IndexMap<u32, (gtk::Box, Option<SourceId>)>>>,
+
+    position: ToastPosition,
+    timeout: u64,
+    max_toasts: usize,
+    icon_size: i32,
+    image_provider: image::Provider,
+    tx: Sender<UiEvent>,
+}
+
+/// Appearance options for toasts.
+#[derive(Debug, Clone, Copy)]
+pub struct ToastOptions {
+    pub position: ToastPosition,
+    pub margin: i32,
+    pub timeout: u64,
+    pub max_toasts: usize,
+    pub icon_size: i32,
+}
+
+impl Toasts {
+    pub fn new(
+        monitor: &Monitor,
+        options: ToastOptions,
+        image_provider: image::Provider,
+        tx: &Sender<UiEvent>,
+    ) -> Self {
+        let window = Window::new();
+        window.add_css_class("notification-toasts");
+        setup_layer_shell(&window, monitor, options.position, options.margin);
+
+        let container = gtk::Box::new(Orientation::Vertical, 10);
+        container.add_css_class("toasts");
+        window.set_child(Some(&container));
+
+        Self {
+            window,
+            container,
+            toasts: rc_mut!(IndexMap::new()),
+            position: options.position,
+            timeout: options.timeout,
+            max_toasts: options.max_toasts,
+            icon_size: options.icon_size,
+            image_provider,
+            tx: tx.clone(),
+        }
+    }
+
+    /// Shows a toast for the notification,
+    /// replacing any existing toast with the same ID.
+    pub fn show(&self, notification: &Notification) {
+        let id = notification.id;
+        self.close(id);
+
+        let widget = card::build(notification, self.icon_size, &self.image_provider, &self.tx);
+        widget.add_css_class("toast");
+
+        // the newest toast is always closest to the screen edge
+        if self.position.is_bottom() {
+            self.container.append(&widget);
+        } else {
+            self.container.prepend(&widget);
+        }
+
+        let timeout = match notification.expire_timeout {
+            0 => None,
+            timeout if timeout > 0 => Some(u64::from(timeout.unsigned_abs())),
+            _ if notification.urgency == Urgency::Critical => None,
+            _ => Some(self.timeout),
+        };
+
+        let timeout_id = timeout.map(|timeout| {
+            let toasts = self.clone();
+            glib::timeout_add_local_once(Duration::from_millis(timeout), move || {
+                // source is removed once complete, so must not be removed again
+                if let Some((_, timeout_id)) = toasts.toasts.borrow_mut().get_mut(&id) {
+                    timeout_id.take();
+                }
+                toasts.close(id);
+            })
+        });
+
+        let mut toasts = self.toasts.borrow_mut();
+        toasts.insert(id, (widget, timeout_id));
+
+        while toasts.len() > self.max_toasts {
+            if let Some((_, (widget, timeout_id))) = toasts.shift_remove_index(0) {
+                if let Some(id) = timeout_id {
+                    id.remove();
+                }
+                self.container.remove(&widget);
+            }
+        }
+
+        drop(toasts);
+        self.window.set_visible(true);
+    }
+
+    /// Hides the toast for the notification with the ID, if shown.
+    pub fn close(&self, id: u32) {
+        let mut toasts = self.toasts.borrow_mut();
+
+        if let Some((widget, timeout_id)) = toasts.shift_remove(&id) {
+            if let Some(id) = timeout_id {
+                id.remove();
+            }
+            self.container.remove(&widget);
+        }
+
+        if toasts.is_empty() {
+            self.window.set_visible(false);
+        }
+    }
+
+    /// Closes the toast window.
+    pub fn destroy(&self) {
+        self.window.close();
+        self.window.destroy();
+    }
+}
+
+/// Sets up GTK layer shell for the toast window,
+/// placing it above all other surfaces without reserving space.
+fn setup_layer_shell(window: &Window, monitor: &Monitor, position: ToastPosition, margin: i32) {
+    window.init_layer_shell();
+    window.set_monitor(Some(monitor));
+    window.set_layer(gtk_layer_shell::Layer::Overlay);
+    window.set_namespace(Some(concat!(env!("CARGO_PKG_NAME"), "-notifications")));
+
+    // allows typing inline replies
+    window.set_keyboard_mode(KeyboardMode::OnDemand);
+
+    let (vertical, horizontal) = match position {
+        ToastPosition::Top => (Edge::Top, None),
+        ToastPosition::TopLeft => (Edge::Top, Some(Edge::Left)),
+        ToastPosition::TopRight => (Edge::Top, Some(Edge::Right)),
+        ToastPosition::Bottom => (Edge::Bottom, None),
+        ToastPosition::BottomLeft => (Edge::Bottom, Some(Edge::Left)),
+        ToastPosition::BottomRight => (Edge::Bottom, Some(Edge::Right)),
+    };
+
+    for edge in std::iter::once(vertical).chain(horizontal) {
+        window.set_anchor(edge, true);
+        window.set_margin(edge, margin);
+    }
+}
@@ -0,0 +1,32 @@
+use zbus::proxy;
+
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+pub trait Manager {
+    /// Powers off the system.
+    /// If `interactive` is set, polkit may ask the user to authenticate.
+    fn power_off(&self, interactive: bool) -> zbus::Result<()>;
+
+    fn reboot(&self, interactive: bool) -> zbus::Result<()>;
+
+    fn suspend(&self, interactive: bool) -> zbus::Result<()>;
+
+    fn hibernate(&self, interactive: bool) -> zbus::Result<()>;
+}
+
+/// The session ironbar is running in.
+#[proxy(
+    interface = "org.freedesktop.login1.Session",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1/session/auto"
+)]
+pub trait Session {
+    /// Asks the session's screen locker to lock the screen.
+    fn lock(&self) -> zbus::Result<()>;
+
+    /// Ends the session, closing all of its processes.
+    fn terminate(&self) -> zbus::Result<()>;
+}
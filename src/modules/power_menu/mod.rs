@@ -0,0 +1,357 @@
+mod logind;
+
+use self::logind::{ManagerProxy, SessionProxy};
+use crate::channels::AsyncSenderExt;
+use crate::config::{CommonConfig, LayoutConfig};
+use crate::gtk_helpers::IronbarLabelExt;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::script::Script;
+use crate::{module_impl, spawn};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use tokio::sync::mpsc;
+use tracing::error;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct PowerMenuModule {
+    /// The label to show on the widget button.
+    /// Pango markup is supported.
+    ///
+    /// **Default**: `󰐥`
+    icon: String,
+
+    /// Whether to ask for confirmation before running an action.
+    /// Locking the screen never asks for confirmation.
+    ///
+    /// **Default**: `true`
+    confirm: bool,
+
+    /// The buttons to show in the popup, in order.
+    ///
+    /// **Default**: A button for each action.
+    buttons: Vec<PowerButton>,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for PowerMenuModule {
+    fn default() -> Self {
+        Self {
+            icon: "󰐥".to_string(),
+            confirm: true,
+            buttons: PowerAction::ALL
+                .into_iter()
+                .map(|action| PowerButton {
+                    action,
+                    label: None,
+                    command: None,
+                })
+                .collect(),
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct PowerButton {
+    /// The action to run when the button is clicked.
+    ///
+    /// **Valid options**: `lock`, `logout`, `suspend`, `hibernate`, `reboot`, `shutdown`
+    action: PowerAction,
+
+    /// The label to show on the button.
+    /// Defaults to an icon and the action's name.
+    ///
+    /// **Default**: `null`
+    #[serde(default)]
+    label: Option<String>,
+
+    /// A command to run instead of asking logind to perform the action,
+    /// for systems which do not use systemd-logind or elogind.
+    ///
+    /// **Default**: `null`
+    #[serde(default)]
+    command: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum PowerAction {
+    Lock,
+    Logout,
+    Suspend,
+    Hibernate,
+    Reboot,
+    Shutdown,
+}
+
+impl PowerAction {
+    const ALL: [Self; 6] = [
+        Self::Lock,
+        Self::Logout,
+        Self::Suspend,
+        Self::Hibernate,
+        Self::Reboot,
+        Self::Shutdown,
+    ];
+
+    /// The action's CSS class.
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Lock => "lock",
+            Self::Logout => "logout",
+            Self::Suspend => "suspend",
+            Self::Hibernate => "hibernate",
+            Self::Reboot => "reboot",
+            Self::Shutdown => "shutdown",
+        }
+    }
+
+    const fn default_label(self) -> &'static str {
+        match self {
+            Self::Lock => "󰌾 Lock",
+            Self::Logout => "󰍃 Log out",
+            Self::Suspend => "󰤄 Suspend",
+            Self::Hibernate => "󰋊 Hibernate",
+            Self::Reboot => "󰜉 Reboot",
+            Self::Shutdown => "󰐥 Shut down",
+        }
+    }
+
+    /// The question to ask before running the action.
+    const fn confirmation(self) -> &'static str {
+        match self {
+            Self::Lock => "Lock the screen?",
+            Self::Logout => "Log out?",
+            Self::Suspend => "Suspend?",
+            Self::Hibernate => "Hibernate?",
+            Self::Reboot => "Reboot?",
+            Self::Shutdown => "Shut down?",
+        }
+    }
+
+    /// Asks logind to run the action.
+    async fn run(self, connection: &zbus::Connection) -> zbus::Result<()> {
+        match self {
+            Self::Lock => SessionProxy::new(connection).await?.lock().await,
+            Self::Logout => SessionProxy::new(connection).await?.terminate().await,
+            Self::Suspend => ManagerProxy::new(connection).await?.suspend(true).await,
+            Self::Hibernate => ManagerProxy::new(connection).await?.hibernate(true).await,
+            Self::Reboot => ManagerProxy::new(connection).await?.reboot(true).await,
+            Self::Shutdown => ManagerProxy::new(connection).await?.power_off(true).await,
+        }
+    }
+}
+
+impl Module<Button> for PowerMenuModule {
+    type SendMessage = ();
+    type ReceiveMessage = PowerAction;
+
+    module_impl!("power_menu");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        _context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let commands = self
+            .buttons
+            .iter()
+            .filter_map(|button| {
+                let command = button.command.as_deref()?;
+                Some((button.action, Script::from(command)))
+            })
+            .collect::<HashMap<_, _>>();
+
+        spawn(async move {
+            while let Some(action) = rx.recv().await {
+                if let Some(script) = commands.get(&action) {
+                    script.run_as_oneshot(None);
+                    continue;
+                }
+
+                // only connected once an action needs logind
+                let connection = match crate::clients::dbus::system().await {
+                    Ok(connection) => connection,
+                    Err(err) => {
+                        error!("Failed to connect to system bus: {err:?}");
+                        continue;
+                    }
+                };
+
+                if let Err(err) = action.run(&connection).await {
+                    error!("Failed to {}: {err:?}", action.name());
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let label = Label::builder()
+            .use_markup(true)
+            .justify(self.layout.justify.into())
+            .build();
+        label.set_label(&self.icon);
+
+        let button = Button::new();
+        button.set_child(Some(&label.rotated(self.layout.angle())));
+        button.set_accessible_label("Power menu");
+
+        let tx = context.tx.clone();
+        button.connect_clicked(move |button| {
+            tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
+        });
+
+        let popup = self
+            .into_popup(context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::new(Orientation::Vertical, 0);
+
+        let buttons = gtk::Box::new(Orientation::Vertical, 5);
+        buttons.add_css_class("buttons");
+        container.append(&buttons);
+
+        let confirmation = gtk::Box::new(Orientation::Vertical, 5);
+        confirmation.add_css_class("confirmation");
+        confirmation.set_visible(false);
+        container.append(&confirmation);
+
+        let message = Label::new(None);
+        message.add_css_class("message");
+        confirmation.append(&message);
+
+        let answers = gtk::Box::new(Orientation::Horizontal, 5);
+        answers.set_halign(Align::End);
+        confirmation.append(&answers);
+
+        let cancel = Button::with_label("Cancel");
+        cancel.add_css_class("btn-cancel");
+        answers.append(&cancel);
+
+        let confirm = Button::with_label("Confirm");
+        confirm.add_css_class("btn-confirm");
+        answers.append(&confirm);
+
+        // the action waiting for confirmation
+        let pending = Rc::new(Cell::new(None::<PowerAction>));
+
+        // runs the action and closes the popup
+        let run = {
+            let controller_tx = context.controller_tx.clone();
+            let tx = context.tx.clone();
+
+            move |action: PowerAction| {
+                controller_tx.send_spawn(action);
+                tx.send_spawn(ModuleUpdateEvent::ClosePopup);
+            }
+        };
+
+        for power_button in &self.buttons {
+            let action = power_button.action;
+
+            let button = Button::new();
+            button.add_css_class("action");
+            button.add_css_class(action.name());
+
+            let label = Label::new(None);
+            label.set_label_escaped(
+                power_button
+                    .label
+                    .as_deref()
+                    .unwrap_or(action.default_label()),
+            );
+            label.set_halign(Align::Start);
+            button.set_child(Some(&label));
+
+            buttons.append(&button);
+
+            let buttons = buttons.clone();
+            let confirmation = confirmation.clone();
+            let message = message.clone();
+            let pending = pending.clone();
+            let run = run.clone();
+            let needs_confirmation = self.confirm && action != PowerAction::Lock;
+
+            button.connect_clicked(move |_| {
+                if needs_confirmation {
+                    pending.set(Some(action));
+                    message.set_label(action.confirmation());
+                    buttons.set_visible(false);
+                    confirmation.set_visible(true);
+                } else {
+                    run(action);
+                }
+            });
+        }
+
+        let show_buttons = {
+            let buttons = buttons.clone();
+            let confirmation = confirmation.clone();
+            let pending = pending.clone();
+
+            move || {
+                pending.set(None);
+                confirmation.set_visible(false);
+                buttons.set_visible(true);
+            }
+        };
+
+        {
+            let show_buttons = show_buttons.clone();
+            cancel.connect_clicked(move |_| show_buttons());
+        }
+
+        {
+            let show_buttons = show_buttons.clone();
+            confirm.connect_clicked(move |_| {
+                if let Some(action) = pending.get() {
+                    run(action);
+                }
+                show_buttons();
+            });
+        }
+
+        // always start on the buttons, rather than a confirmation left open
+        context.popup.popover.connect_show(move |_| show_buttons());
+
+        Some(container)
+    }
+}
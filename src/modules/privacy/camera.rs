@@ -0,0 +1,35 @@
+use std::fs;
+use std::path::Path;
+
+/// Gets the names of processes with a video device open,
+/// sorted and without duplicates.
+///
+/// Only processes owned by the current user can be inspected.
+pub fn users() -> Vec<String> {
+    let Ok(processes) = fs::read_dir("/proc") else {
+        return vec![];
+    };
+
+    let mut users = processes
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().parse::<u32>().is_ok())
+        .filter(|entry| has_video_device(&entry.path()))
+        .filter_map(|entry| fs::read_to_string(entry.path().join("comm")).ok())
+        .map(|name| name.trim().to_string())
+        .collect::<Vec<_>>();
+
+    users.sort();
+    users.dedup();
+    users
+}
+
+/// Checks whether any of the process's open files are video devices.
+fn has_video_device(process: &Path) -> bool {
+    let Ok(fds) = fs::read_dir(process.join("fd")) else {
+        return false;
+    };
+
+    fds.flatten()
+        .filter_map(|fd| fs::read_link(fd.path()).ok())
+        .any(|target| target.to_string_lossy().starts_with("/dev/video"))
+}
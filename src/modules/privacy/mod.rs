@@ -0,0 +1,407 @@
+mod camera;
+
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::clients::screencast::Screencast;
+use crate::clients::volume::{self, Event, SourceOutput};
+use crate::config::{CommonConfig, LayoutConfig};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::{module_impl, poll, spawn, spawn_blocking};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation, ToggleButton};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tracing::{error, warn};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct PrivacyModule {
+    /// The audio server API to use for detecting recording streams.
+    /// Either `pulse` or `pipewire`.
+    ///
+    /// **Default**: `pulse`
+    backend: volume::ClientType,
+
+    /// The icon to show while any app is recording audio.
+    ///
+    /// **Default**: `󰍬`
+    icon_mic: String,
+
+    /// The icon to show while any app is using a camera.
+    ///
+    /// **Default**: `󰄀`
+    icon_camera: String,
+
+    /// The icon to show while any app is sharing the screen.
+    ///
+    /// **Default**: `󰹑`
+    icon_screenshare: String,
+
+    /// The number of milliseconds between checking for camera use,
+    /// set from the common `interval` option.
+    #[serde(skip)]
+    interval: u64,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(default, flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for PrivacyModule {
+    fn default() -> Self {
+        Self {
+            backend: volume::ClientType::default(),
+            icon_mic: "󰍬".to_string(),
+            icon_camera: "󰄀".to_string(),
+            icon_screenshare: "󰹑".to_string(),
+            interval: 2000,
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PrivacyUpdate {
+    /// All streams recording from an input device.
+    Recording(Vec<SourceOutput>),
+    /// The names of all processes using a camera.
+    Camera(Vec<String>),
+    /// All active screencast sessions.
+    Screencast(Vec<Screencast>),
+}
+
+#[derive(Debug, Clone)]
+pub enum UiEvent {
+    /// Sets whether the recording stream with the index is muted.
+    MuteRecording(u32, bool),
+    /// Stops the screencast session with the handle.
+    StopScreencast(String),
+}
+
+impl Module<gtk::Box> for PrivacyModule {
+    type SendMessage = PrivacyUpdate;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("privacy");
+
+    fn coalesce_key(update: &Self::SendMessage) -> Option<String> {
+        // each update is the full state of its kind
+        let key = match update {
+            PrivacyUpdate::Recording(_) => "recording",
+            PrivacyUpdate::Camera(_) => "camera",
+            PrivacyUpdate::Screencast(_) => "screencast",
+        };
+
+        Some(key.to_string())
+    }
+
+    fn set_interval(&mut self, interval: u64) {
+        self.interval = interval;
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let volume = context.ironbar.clients.borrow_mut().volume(self.backend);
+
+        let screencast = match context.ironbar.clients.borrow_mut().screencast() {
+            Ok(client) => Some(client),
+            Err(err) => {
+                warn!("Failed to start screencast client, screen sharing is hidden: {err:?}");
+                None
+            }
+        };
+
+        {
+            let volume = volume.clone();
+            let tx = context.tx.clone();
+
+            spawn(async move {
+                let mut events = volume.subscribe();
+                tx.send_update(PrivacyUpdate::Recording(volume.source_outputs()))
+                    .await;
+
+                loop {
+                    match events.recv_tracked("volume").await {
+                        Ok(
+                            Event::AddOutput(_) | Event::UpdateOutput(_) | Event::RemoveOutput(_),
+                        )
+                        | Err(RecvError::Lagged(_)) => {
+                            tx.send_update(PrivacyUpdate::Recording(volume.source_outputs()))
+                                .await;
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+
+        {
+            let tx = context.tx.clone();
+            let interval = Duration::from_millis(self.interval);
+
+            spawn(async move {
+                let mut users = None;
+
+                loop {
+                    let new_users = spawn_blocking(camera::users).await.unwrap_or_default();
+
+                    if users.as_ref() != Some(&new_users) {
+                        users = Some(new_users.clone());
+                        tx.send_update(PrivacyUpdate::Camera(new_users)).await;
+                    }
+
+                    poll::sleep(interval).await;
+                }
+            });
+        }
+
+        if let Some(screencast) = screencast.clone() {
+            let tx = context.tx.clone();
+
+            spawn(async move {
+                let mut updates = screencast.subscribe();
+                tx.send_update(PrivacyUpdate::Screencast(screencast.screencasts()))
+                    .await;
+
+                loop {
+                    match updates.recv_tracked("screencast").await {
+                        Ok(screencasts) => {
+                            tx.send_update(PrivacyUpdate::Screencast(screencasts)).await;
+                        }
+                        Err(RecvError::Lagged(_)) => {
+                            tx.send_update(PrivacyUpdate::Screencast(screencast.screencasts()))
+                                .await;
+                        }
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+
+        spawn(async move {
+            while let Some(event) = rx.recv().await {
+                match event {
+                    UiEvent::MuteRecording(index, muted) => volume.set_output_muted(index, muted),
+                    UiEvent::StopScreencast(handle) => {
+                        if let Some(screencast) = &screencast
+                            && let Err(err) = screencast.stop(&handle).await
+                        {
+                            error!("Failed to stop screencast: {err:?}");
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<gtk::Box>> {
+        let container = gtk::Box::new(self.layout.orientation(info), 0);
+
+        let create_button = |icon: &str, class: &str, accessible_label: &str| {
+            let label = Label::builder().use_markup(true).build();
+            label.set_justify(self.layout.justify.into());
+            label.set_label_escaped(icon);
+
+            let button = Button::new();
+            button.set_child(Some(&label));
+            button.add_css_class(class);
+            button.set_accessible_label(accessible_label);
+            button.set_visible(false);
+
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+
+            container.append(&button);
+            button
+        };
+
+        let mic_button = create_button(&self.icon_mic, "mic", "Microphone in use");
+        let camera_button = create_button(&self.icon_camera, "camera", "Camera in use");
+        let screenshare_button =
+            create_button(&self.icon_screenshare, "screenshare", "Screen shared");
+
+        {
+            let mic_button = mic_button.clone();
+            let camera_button = camera_button.clone();
+            let screenshare_button = screenshare_button.clone();
+
+            context
+                .subscribe()
+                .recv_glib(&context.tx, move |tx, update| {
+                    let (button, active) = match update {
+                        PrivacyUpdate::Recording(outputs) => (&mic_button, !outputs.is_empty()),
+                        PrivacyUpdate::Camera(users) => (&camera_button, !users.is_empty()),
+                        PrivacyUpdate::Screencast(screencasts) => {
+                            (&screenshare_button, !screencasts.is_empty())
+                        }
+                    };
+
+                    button.set_visible(active);
+
+                    // close the popup once nothing is in use
+                    if !active
+                        && !mic_button.is_visible()
+                        && !camera_button.is_visible()
+                        && !screenshare_button.is_visible()
+                    {
+                        tx.send_spawn(ModuleUpdateEvent::ClosePopup);
+                    }
+                });
+        }
+
+        let popup = self.into_popup(context, info).into_popup_parts(vec![
+            &mic_button,
+            &camera_button,
+            &screenshare_button,
+        ]);
+
+        Ok(ModuleParts::new(container, popup))
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::new(Orientation::Vertical, 10);
+
+        let create_section = |class: &str, title: &str| {
+            let section = gtk::Box::new(Orientation::Vertical, 5);
+            section.add_css_class(class);
+            section.set_visible(false);
+
+            let title = Label::new(Some(title));
+            title.add_css_class("title");
+            title.set_halign(Align::Start);
+            section.append(&title);
+
+            let rows = gtk::Box::new(Orientation::Vertical, 5);
+            section.append(&rows);
+
+            container.append(&section);
+            (section, rows)
+        };
+
+        let mic_section = create_section("mic-box", "Microphone");
+        let camera_section = create_section("camera-box", "Camera");
+        let screenshare_section = create_section("screenshare-box", "Screen sharing");
+
+        let tx = context.controller_tx.clone();
+
+        context.subscribe().recv_glib((), move |(), update| {
+            let ((section, rows), active) = match &update {
+                PrivacyUpdate::Recording(outputs) => (&mic_section, !outputs.is_empty()),
+                PrivacyUpdate::Camera(users) => (&camera_section, !users.is_empty()),
+                PrivacyUpdate::Screencast(screencasts) => {
+                    (&screenshare_section, !screencasts.is_empty())
+                }
+            };
+
+            section.set_visible(active);
+
+            for child in rows.children() {
+                rows.remove(&child);
+            }
+
+            match update {
+                PrivacyUpdate::Recording(outputs) => {
+                    for output in &outputs {
+                        rows.append(&recording_row(output, &tx));
+                    }
+                }
+                PrivacyUpdate::Camera(users) => {
+                    for user in &users {
+                        rows.append(&app_row(user));
+                    }
+                }
+                PrivacyUpdate::Screencast(screencasts) => {
+                    for screencast in &screencasts {
+                        rows.append(&screencast_row(screencast, &tx));
+                    }
+                }
+            }
+        });
+
+        Some(container)
+    }
+}
+
+/// Creates a popup row containing the app name.
+fn app_row(name: &str) -> gtk::Box {
+    let row = gtk::Box::new(Orientation::Horizontal, 10);
+    row.add_css_class("app-box");
+
+    let app = Label::new(Some(name));
+    app.add_css_class("app");
+    app.set_halign(Align::Start);
+    app.set_hexpand(true);
+    row.append(&app);
+
+    row
+}
+
+/// Creates a popup row for the recording stream,
+/// with a button to mute it.
+fn recording_row(output: &SourceOutput, tx: &mpsc::Sender<UiEvent>) -> gtk::Box {
+    let row = app_row(&output.name);
+
+    let mute = ToggleButton::with_label("Mute");
+    mute.add_css_class("btn-mute");
+    mute.set_active(output.muted);
+    row.append(&mute);
+
+    let tx = tx.clone();
+    let index = output.index;
+    mute.connect_toggled(move |button| {
+        tx.send_spawn(UiEvent::MuteRecording(index, button.is_active()));
+    });
+
+    row
+}
+
+/// Creates a popup row for the screencast session,
+/// with a button to stop it.
+fn screencast_row(screencast: &Screencast, tx: &mpsc::Sender<UiEvent>) -> gtk::Box {
+    let row = app_row(&screencast.app);
+
+    let stop = Button::with_label("Stop");
+    stop.add_css_class("btn-stop");
+    row.append(&stop);
+
+    let tx = tx.clone();
+    let handle = screencast.handle.clone();
+    stop.connect_clicked(move |button| {
+        button.set_sensitive(false);
+        tx.send_spawn(UiEvent::StopScreencast(handle.clone()));
+    });
+
+    row
+}
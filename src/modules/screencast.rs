@@ -0,0 +1,207 @@
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::clients::screencast::Screencast;
+use crate::config::{CommonConfig, LayoutConfig};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::{module_impl, spawn};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation};
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tracing::error;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct ScreencastModule {
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰹑`
+    #[serde(skip)]
+    format: String,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(default, flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for ScreencastModule {
+    fn default() -> Self {
+        Self {
+            format: "󰹑".to_string(),
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+impl ScreencastModule {
+    fn format(&self, screencasts: &[Screencast]) -> String {
+        let apps = screencasts
+            .iter()
+            .map(|screencast| screencast.app.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        self.format
+            .replace("{count}", &screencasts.len().to_string())
+            .replace("{apps}", &apps)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum UiEvent {
+    /// Stops the session with the handle.
+    Stop(String),
+}
+
+impl Module<Button> for ScreencastModule {
+    type SendMessage = Vec<Screencast>;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("screencast");
+
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let client = context.ironbar.clients.borrow_mut().screencast()?;
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            let mut updates = client.subscribe();
+            tx.send_update(client.screencasts()).await;
+
+            loop {
+                tokio::select! {
+                    res = updates.recv_tracked("screencast") => match res {
+                        Ok(screencasts) => tx.send_update(screencasts).await,
+                        Err(RecvError::Lagged(_)) => tx.send_update(client.screencasts()).await,
+                        Err(RecvError::Closed) => break,
+                    },
+                    Some(UiEvent::Stop(handle)) = rx.recv() => {
+                        if let Err(err) = client.stop(&handle).await {
+                            error!("Failed to stop screencast: {err:?}");
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        button.add_css_class("btn");
+        button.set_visible(false);
+
+        let label = Label::builder().use_markup(true).build();
+        label.set_justify(self.layout.justify.into());
+        button.set_child(Some(&label));
+
+        let tx = context.tx.clone();
+        button.connect_clicked(move |button| {
+            tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
+        });
+
+        {
+            let module = self.clone();
+            context.subscribe().recv_glib(
+                (&button, &label, &context.tx),
+                move |(button, label, tx), screencasts| {
+                    label.set_label_escaped(&module.format(&screencasts));
+
+                    let sharing = !screencasts.is_empty();
+                    button.set_visible(sharing);
+
+                    if sharing {
+                        button.add_css_class("sharing");
+                    } else {
+                        button.remove_css_class("sharing");
+                        tx.send_spawn(ModuleUpdateEvent::ClosePopup);
+                    }
+                },
+            );
+        }
+
+        let popup = self
+            .into_popup(context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let tx = context.controller_tx.clone();
+
+        context
+            .subscribe()
+            .recv_glib(&container, move |container, screencasts| {
+                for child in container.children() {
+                    container.remove(&child);
+                }
+
+                for screencast in &screencasts {
+                    container.append(&session_row(screencast, &tx));
+                }
+            });
+
+        Some(container)
+    }
+}
+
+/// Creates a popup row for the session,
+/// with a button to stop it.
+fn session_row(screencast: &Screencast, tx: &mpsc::Sender<UiEvent>) -> gtk::Box {
+    let row = gtk::Box::new(Orientation::Horizontal, 10);
+    row.add_css_class("session");
+
+    let app = Label::new(Some(&screencast.app));
+    app.add_css_class("app");
+    app.set_halign(Align::Start);
+    app.set_hexpand(true);
+    row.append(&app);
+
+    let stop = Button::with_label("Stop");
+    stop.add_css_class("btn-stop");
+    row.append(&stop);
+
+    let tx = tx.clone();
+    let handle = screencast.handle.clone();
+    stop.connect_clicked(move |button| {
+        button.set_sensitive(false);
+        tx.send_spawn(UiEvent::Stop(handle.clone()));
+    });
+
+    row
+}
@@ -6,7 +6,9 @@ use crate::script::{OutputStream, Script, ScriptMode};
 use crate::{module_impl, spawn};
 use color_eyre::{Help, Report, Result};
 use gtk::Label;
+use gtk::prelude::*;
 use serde::Deserialize;
+use serde_json::{Map, Value};
 use tokio::sync::mpsc;
 use tracing::error;
 
@@ -30,11 +32,28 @@ pub struct ScriptModule {
     /// **Default**: `poll`
     mode: ScriptMode,
 
-    /// Time in milliseconds between executions.
+    /// Time in milliseconds between executions,
+    /// set from the common `interval` option.
     ///
     /// **Default**: `5000`
+    #[serde(skip)]
     interval: u64,
 
+    /// How to read the script output.
+    /// See [JSON output](#json-output) for more info.
+    ///
+    /// **Valid options**: `text`, `json`
+    /// <br />
+    /// **Default**: `text`
+    output: ScriptOutput,
+
+    /// The format string to use for the label in `json` output mode.
+    /// Each `{field}` token is replaced with that field from the JSON object.
+    ///
+    /// **Default**: `{text}`
+    #[serde(skip)]
+    format: String,
+
     // -- Common --
     /// See [layout options](module-level-options#layout)
     #[serde(flatten)]
@@ -51,12 +70,82 @@ impl Default for ScriptModule {
             cmd: String::new(),
             mode: ScriptMode::Poll,
             interval: 5000,
+            output: ScriptOutput::default(),
+            format: "{text}".to_string(),
             layout: LayoutConfig::default(),
             common: Some(CommonConfig::default()),
         }
     }
 }
 
+#[derive(Debug, Default, Deserialize, Clone, Copy, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum ScriptOutput {
+    /// Output is shown as-is.
+    #[default]
+    Text,
+    /// Each line of output is a JSON object.
+    Json,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ScriptUpdate {
+    text: String,
+    tooltip: Option<String>,
+    classes: Vec<String>,
+}
+
+impl ScriptUpdate {
+    /// Parses a line of JSON output,
+    /// following the i3blocks/waybar custom script convention.
+    ///
+    /// `text` (or `full_text`) is used for the label,
+    /// `tooltip` for the tooltip,
+    /// and `class` (a string or array of strings) for CSS classes.
+    /// All fields can be used as tokens in `format`.
+    fn from_json(line: &str, format: &str) -> Result<Self> {
+        let fields = serde_json::from_str::<Map<String, Value>>(line)?;
+
+        let field = |key: &str| {
+            fields.get(key).and_then(|value| match value {
+                Value::Null => None,
+                Value::String(string) => Some(string.clone()),
+                value => Some(value.to_string()),
+            })
+        };
+
+        let mut text = format.to_string();
+        for key in fields.keys() {
+            let token = format!("{{{key}}}");
+            if text.contains(&token) {
+                text = text.replace(&token, &field(key).unwrap_or_default());
+            }
+        }
+
+        // i3blocks uses `full_text`
+        if !fields.contains_key("text") {
+            text = text.replace("{text}", &field("full_text").unwrap_or_default());
+        }
+
+        let classes = match fields.get("class") {
+            Some(Value::String(class)) => vec![class.clone()],
+            Some(Value::Array(classes)) => classes
+                .iter()
+                .filter_map(Value::as_str)
+                .map(ToString::to_string)
+                .collect(),
+            _ => vec![],
+        };
+
+        Ok(Self {
+            text,
+            tooltip: field("tooltip"),
+            classes,
+        })
+    }
+}
+
 impl From<&ScriptModule> for Script {
     fn from(module: &ScriptModule) -> Self {
         Self {
@@ -68,11 +157,24 @@ impl From<&ScriptModule> for Script {
 }
 
 impl Module<Label> for ScriptModule {
-    type SendMessage = String;
+    type SendMessage = ScriptUpdate;
     type ReceiveMessage = ();
 
     module_impl!("script");
 
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
+    fn set_interval(&mut self, interval: u64) {
+        self.interval = interval;
+    }
+
+    fn coalesce_key(_update: &Self::SendMessage) -> Option<String> {
+        // each update is the full state
+        Some(String::new())
+    }
+
     fn spawn_controller(
         &self,
         _info: &ModuleInfo,
@@ -80,21 +182,43 @@ impl Module<Label> for ScriptModule {
         _rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
         let script: Script = self.into();
+        let output = self.output;
+        let format = self.format.clone();
 
         let tx = context.tx.clone();
         spawn(async move {
-            script.run(None, move |out, _| match out {
-               OutputStream::Stdout(stdout) => {
-                   tx.send_update_spawn(stdout);
-               },
-               OutputStream::Stderr(stderr) => {
-                   error!("{:?}", Report::msg(stderr)
-                                    .wrap_err("Watched script error:")
-                                    .suggestion("Check the path to your script")
-                                    .suggestion("Check the script for errors")
-                                    .suggestion("If you expect the script to write to stderr, consider redirecting its output to /dev/null to suppress these messages"));
-               }
-           }).await;
+            script
+                .run(None, move |out, _| match out {
+                    OutputStream::Stdout(stdout) => match output {
+                        ScriptOutput::Text => tx.send_update_spawn(ScriptUpdate {
+                            text: stdout,
+                            ..ScriptUpdate::default()
+                        }),
+                        ScriptOutput::Json => {
+                            // polled scripts may write several lines, the last of which is used
+                            let Some(line) = stdout.lines().rfind(|line| !line.trim().is_empty())
+                            else {
+                                return;
+                            };
+
+                            match ScriptUpdate::from_json(line, &format) {
+                                Ok(update) => tx.send_update_spawn(update),
+                                Err(err) => error!(
+                                    "{:?}",
+                                    err.wrap_err("Failed to parse script output as JSON")
+                                ),
+                            }
+                        }
+                    },
+                    OutputStream::Stderr(stderr) => {
+                        error!("{:?}", Report::msg(stderr)
+                            .wrap_err("Watched script error:")
+                            .suggestion("Check the path to your script")
+                            .suggestion("Check the script for errors")
+                            .suggestion("If you expect the script to write to stderr, consider redirecting its output to /dev/null to suppress these messages"));
+                    }
+                })
+                .await;
         });
 
         Ok(())
@@ -110,9 +234,25 @@ impl Module<Label> for ScriptModule {
             .justify(self.layout.justify.into())
             .build();
 
-        context
-            .subscribe()
-            .recv_glib(&label, |label, s| label.set_label_escaped(&s));
+        // classes added by the previous update, to remove on the next
+        let mut classes = vec![];
+
+        context.subscribe().recv_glib(&label, move |label, update| {
+            label.set_label_escaped(&update.text);
+
+            if let Some(tooltip) = update.tooltip {
+                label.set_tooltip_markup(Some(&tooltip));
+            }
+
+            for class in classes.drain(..) {
+                label.remove_css_class(&class);
+            }
+
+            for class in update.classes {
+                label.add_css_class(&class);
+                classes.push(class);
+            }
+        });
 
         Ok(ModuleParts {
             widget: label,
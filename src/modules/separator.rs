@@ -0,0 +1,64 @@
+use crate::config::CommonConfig;
+use crate::gtk_helpers::IronbarLabelExt;
+use crate::module_impl;
+use crate::modules::{Module, ModuleInfo, ModuleParts, WidgetContext};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Label, Orientation, Separator};
+use serde::Deserialize;
+use tokio::sync::mpsc;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct SeparatorModule {
+    /// Text to show in place of a line, such as `|`.
+    /// Leave unset to draw a line across the bar.
+    ///
+    /// **Default**: `null`
+    label: Option<String>,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Module<gtk::Widget> for SeparatorModule {
+    type SendMessage = ();
+    type ReceiveMessage = ();
+
+    module_impl!("separator");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        _context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        _context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<gtk::Widget>> {
+        let widget = if let Some(text) = self.label {
+            let label = Label::builder().use_markup(true).build();
+            label.set_label_escaped(&text);
+            label.upcast()
+        } else {
+            // the line runs across the bar
+            let orientation = match info.bar_position.orientation() {
+                Orientation::Horizontal => Orientation::Vertical,
+                _ => Orientation::Horizontal,
+            };
+
+            Separator::new(orientation).upcast()
+        };
+
+        Ok(ModuleParts {
+            widget,
+            popup: None,
+        })
+    }
+}
@@ -0,0 +1,370 @@
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::clients::syncthing::{self, Folder, Update};
+use crate::config::{CommonConfig, LayoutConfig, Secret};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::panic_guard::{self, PanicGuard};
+use crate::{module_impl, spawn, spawn_blocking};
+use color_eyre::{Report, Result};
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation, ProgressBar};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::rc::Rc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tracing::error;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct SyncthingModule {
+    /// The URL of the Syncthing GUI and REST API.
+    ///
+    /// **Default**: `http://127.0.0.1:8384`
+    url: String,
+
+    /// The API key to authenticate with.
+    /// If not set, it is read from Syncthing's `config.xml`.
+    ///
+    /// See [secrets](configuration-guide#34-secrets) for other ways to provide this.
+    ///
+    /// **Default**: `null`
+    api_key: Option<Secret>,
+
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰓦 {state}`
+    #[serde(skip)]
+    format: String,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(default, flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for SyncthingModule {
+    fn default() -> Self {
+        Self {
+            url: "http://127.0.0.1:8384".to_string(),
+            api_key: None,
+            format: "󰓦 {state}".to_string(),
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+/// The overall state of every folder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Idle,
+    Syncing,
+    Error,
+    Paused,
+    Disconnected,
+}
+
+impl State {
+    const ALL: [Self; 5] = [
+        Self::Idle,
+        Self::Syncing,
+        Self::Error,
+        Self::Paused,
+        Self::Disconnected,
+    ];
+
+    fn of(folders: Option<&[Folder]>) -> Self {
+        let Some(folders) = folders else {
+            return Self::Disconnected;
+        };
+
+        if folders.iter().any(Folder::is_error) {
+            Self::Error
+        } else if folders.iter().any(Folder::is_syncing) {
+            Self::Syncing
+        } else if !folders.is_empty() && folders.iter().all(|folder| folder.paused) {
+            Self::Paused
+        } else {
+            Self::Idle
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Idle => "idle",
+            Self::Syncing => "syncing",
+            Self::Error => "error",
+            Self::Paused => "paused",
+            Self::Disconnected => "disconnected",
+        }
+    }
+}
+
+/// Gets the percentage of the folders which is up to date.
+fn progress<'a>(folders: impl IntoIterator<Item = &'a Folder>) -> f64 {
+    let (in_sync, global) = folders
+        .into_iter()
+        .filter(|folder| !folder.paused)
+        .fold((0, 0), |(in_sync, global), folder| {
+            (in_sync + folder.in_sync_bytes, global + folder.global_bytes)
+        });
+
+    if global == 0 {
+        100.0
+    } else {
+        in_sync as f64 / global as f64 * 100.0
+    }
+}
+
+impl SyncthingModule {
+    fn format(&self, folders: Option<&[Folder]>) -> String {
+        let state = State::of(folders);
+        let folders = folders.unwrap_or_default();
+
+        self.format
+            .replace("{state}", state.name())
+            .replace("{progress}", &format!("{:.0}", progress(folders)))
+            .replace("{folders}", &folders.len().to_string())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum UiEvent {
+    SetPaused(bool),
+}
+
+/// Gets the API key from the config,
+/// falling back to the key in Syncthing's own config.
+fn resolve_api_key(api_key: Option<&Secret>) -> Result<String> {
+    match api_key {
+        Some(api_key) => api_key.resolve(),
+        None => syncthing::find_api_key()
+            .ok_or_else(|| Report::msg("No Syncthing API key configured or found")),
+    }
+}
+
+impl Module<Button> for SyncthingModule {
+    /// The state of every folder, or `None` while disconnected.
+    type SendMessage = Option<Vec<Folder>>;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("syncthing");
+
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let api_key = self.api_key.clone();
+        let url = self.url.clone();
+        let ironbar = context.ironbar.clone();
+
+        let tx = context.tx.clone();
+
+        // resolving the key can run a command or read Syncthing's config,
+        // so happens off the main thread
+        let guard = PanicGuard::current();
+        glib::spawn_future_local(panic_guard::scope_future(guard, async move {
+            let client = match spawn_blocking(move || resolve_api_key(api_key.as_ref())).await {
+                Ok(Ok(api_key)) => ironbar.clients.borrow_mut().syncthing(&url, &api_key),
+                Ok(Err(err)) => Err(err),
+                Err(err) => Err(err.into()),
+            };
+
+            let client = match client {
+                Ok(client) => client,
+                Err(err) => {
+                    panic_guard::report_error(&err.wrap_err("Unable to connect to Syncthing"));
+                    return;
+                }
+            };
+
+            spawn(async move {
+                let mut updates = client.subscribe();
+                tx.send_update(client.folders()).await;
+
+                loop {
+                    tokio::select! {
+                        res = updates.recv_tracked("syncthing") => match res {
+                            Ok(Update::Folders(folders)) => tx.send_update(Some(folders)).await,
+                            Ok(Update::Disconnected) => tx.send_update(None).await,
+                            Err(RecvError::Lagged(_)) => tx.send_update(client.folders()).await,
+                            Err(RecvError::Closed) => break,
+                        },
+                        Some(UiEvent::SetPaused(paused)) = rx.recv() => {
+                            if let Err(err) = client.set_paused(paused).await {
+                                error!("Failed to pause Syncthing folders: {err:?}");
+                            }
+                        }
+                    }
+                }
+            });
+        }));
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        button.add_css_class("btn");
+
+        let label = Label::builder().use_markup(true).build();
+        label.set_justify(self.layout.justify.into());
+        button.set_child(Some(&label));
+
+        let tx = context.tx.clone();
+        button.connect_clicked(move |button| {
+            tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
+        });
+
+        {
+            let module = self.clone();
+            context
+                .subscribe()
+                .recv_glib((&button, &label), move |(button, label), folders| {
+                    label.set_label_escaped(&module.format(folders.as_deref()));
+
+                    for state in State::ALL {
+                        button.remove_css_class(state.name());
+                    }
+                    button.add_css_class(State::of(folders.as_deref()).name());
+                });
+        }
+
+        let popup = self
+            .into_popup(context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let header = gtk::Box::new(Orientation::Horizontal, 10);
+        header.add_css_class("header");
+        container.append(&header);
+
+        let state = Label::new(None);
+        state.add_css_class("state");
+        state.set_halign(Align::Start);
+        state.set_hexpand(true);
+        header.append(&state);
+
+        let pause = Button::new();
+        pause.add_css_class("btn-pause");
+        header.append(&pause);
+
+        let folders_box = gtk::Box::new(Orientation::Vertical, 5);
+        folders_box.add_css_class("folders");
+        container.append(&folders_box);
+
+        let paused = Rc::new(Cell::new(false));
+
+        {
+            let tx = context.controller_tx.clone();
+            let paused = paused.clone();
+            pause.connect_clicked(move |_| tx.send_spawn(UiEvent::SetPaused(!paused.get())));
+        }
+
+        context.subscribe().recv_glib(
+            (&state, &pause, &folders_box),
+            move |(state, pause, folders_box), folders| {
+                let overall = State::of(folders.as_deref());
+                state.set_label(&match overall {
+                    State::Syncing => {
+                        let folders = folders.as_deref().unwrap_or_default();
+                        format!("Syncing ({:.0}%)", progress(folders))
+                    }
+                    State::Idle => "Up to date".to_string(),
+                    State::Error => "Error".to_string(),
+                    State::Paused => "Paused".to_string(),
+                    State::Disconnected => "Disconnected".to_string(),
+                });
+
+                paused.set(overall == State::Paused);
+                pause.set_visible(folders.is_some());
+                pause.set_label(if paused.get() { "Resume" } else { "Pause" });
+
+                for child in folders_box.children() {
+                    folders_box.remove(&child);
+                }
+
+                for folder in folders.iter().flatten() {
+                    folders_box.append(&folder_row(folder));
+                }
+            },
+        );
+
+        Some(container)
+    }
+}
+
+fn folder_row(folder: &Folder) -> gtk::Box {
+    let row = gtk::Box::new(Orientation::Vertical, 2);
+    row.add_css_class("folder");
+
+    let class = if folder.paused {
+        "paused"
+    } else if folder.is_error() {
+        "error"
+    } else if folder.is_syncing() {
+        "syncing"
+    } else {
+        "idle"
+    };
+    row.add_css_class(class);
+
+    let details = gtk::Box::new(Orientation::Horizontal, 10);
+    row.append(&details);
+
+    let name = Label::new(Some(folder.name()));
+    name.add_css_class("name");
+    name.set_halign(Align::Start);
+    name.set_hexpand(true);
+    details.append(&name);
+
+    let status = if folder.paused {
+        "paused".to_string()
+    } else if folder.errors > 0 {
+        format!("{} failed", folder.errors)
+    } else {
+        folder.state.clone()
+    };
+
+    let status = Label::new(Some(&status));
+    status.add_css_class("status");
+    details.append(&status);
+
+    let progress_bar = ProgressBar::new();
+    progress_bar.add_css_class("progress");
+    progress_bar.set_fraction(progress([folder]) / 100.0);
+    row.append(&progress_bar);
+
+    row
+}
@@ -1,39 +1,53 @@
 mod parser;
+mod popup;
 mod renderer;
 mod token;
 
 use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
-use crate::clients::sysinfo::TokenType;
-use crate::config::{CommonConfig, LayoutConfig, ModuleOrientation};
-use crate::gtk_helpers::IronbarLabelExt;
-use crate::modules::sysinfo::token::Part;
-use crate::modules::{Module, ModuleInfo, ModuleParts, WidgetContext};
+use crate::clients::sysinfo::{RefreshType, TokenType};
+use crate::config::{
+    CommonConfig, GraphConfig, LayoutConfig, ModuleOrientation, ThresholdLevel, Thresholds,
+};
+use crate::gtk_helpers::{Graph, IronbarLabelExt};
+use crate::modules::sysinfo::popup::{Popup, PopupConfig, PopupData, Sampler};
+use crate::modules::sysinfo::renderer::TokenValue;
+use crate::modules::sysinfo::token::{NameFilter, Part};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
 use crate::{clients, module_impl, spawn};
-use color_eyre::Result;
-use gtk::Label;
+use color_eyre::{Report, Result};
 use gtk::prelude::*;
+use gtk::{Button, Label};
+use regex::Regex;
 use serde::Deserialize;
 use std::time::Duration;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use tokio::time::sleep;
 
 #[derive(Debug, Deserialize, Clone)]
 #[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct SysInfoModule {
-    /// List of strings including formatting tokens.
+    /// List of strings including formatting tokens,
+    /// or graphs of a single token.
     /// For available tokens, see [below](#formatting-tokens).
     ///
     /// **Required**
-    format: Vec<String>,
+    format: Vec<FormatItem>,
 
-    /// Number of seconds between refresh.
+    /// The number of milliseconds between each refresh,
+    /// set from the common `interval` option.
     ///
-    /// This can be set as a global interval,
-    /// or passed as an object to customize the interval per-system.
+    /// **Default**: `5000`
+    #[serde(skip)]
+    interval: u64,
+
+    /// The number of milliseconds between refreshing each type of data,
+    /// overriding `interval`.
     ///
-    /// **Default**: `5`
-    interval: Interval,
+    /// **Default**: `{}`
+    intervals: Intervals,
 
     /// The orientation by which the labels are laid out.
     ///
@@ -42,6 +56,33 @@ pub struct SysInfoModule {
     /// **Default** : `horizontal`
     direction: Option<ModuleOrientation>,
 
+    /// The values at or above which the `.low`, `.warning` and `.critical` classes
+    /// are applied to each label, set per-system.
+    ///
+    /// Each label is checked against the first token in its format
+    /// which has thresholds set, using the value as displayed.
+    ///
+    /// **Default**: `{}`
+    thresholds: SysInfoThresholds,
+
+    /// Regular expressions limiting which network adapters and disk mounts
+    /// are included when a token combines their values,
+    /// such as to leave out loopback and virtual adapters.
+    ///
+    /// Values chosen by name are always available.
+    ///
+    /// **Default**: `{}`
+    filters: SysInfoFilters,
+
+    /// Shows a popup on click with the usage of each CPU core,
+    /// load averages and the processes using the most resources.
+    ///
+    /// The popup is only refreshed while it is open,
+    /// so it has no cost while closed.
+    ///
+    /// **Default**: `null`
+    popup: Option<PopupConfig>,
+
     // -- common --
     /// See [layout options](module-level-options#layout)
     #[serde(flatten)]
@@ -56,131 +97,263 @@ impl Default for SysInfoModule {
     fn default() -> Self {
         Self {
             format: vec![],
-            interval: Interval::default(),
+            interval: 5000,
+            intervals: Intervals::default(),
             direction: None,
+            thresholds: SysInfoThresholds::default(),
+            filters: SysInfoFilters::default(),
+            popup: None,
             layout: LayoutConfig::default(),
             common: Some(CommonConfig::default()),
         }
     }
 }
 
-#[derive(Debug, Deserialize, Copy, Clone)]
+#[derive(Debug, Deserialize, Clone)]
+#[serde(untagged)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum FormatItem {
+    /// A label, rendered from a string of formatting tokens.
+    Label(String),
+    /// A graph of a single token's recent history.
+    Graph(GraphItem),
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub struct GraphItem {
+    /// The token to graph, such as `{cpu_percent}`.
+    /// This must be a single number token.
+    ///
+    /// **Required**
+    graph: String,
+
+    /// See [graph options](custom#graph).
+    #[serde(flatten)]
+    config: GraphConfig,
+}
+
+impl FormatItem {
+    fn parse(&self, filters: &Filters) -> Result<Vec<Part>> {
+        let mut parts = match self {
+            Self::Label(format) => parser::parse_input(format)?,
+            Self::Graph(item) => {
+                let parts = parser::parse_input(&item.graph)?;
+                match parts.as_slice() {
+                    [Part::Token(token)] if token.token != TokenType::Uptime => parts,
+                    _ => {
+                        return Err(Report::msg(format!(
+                            "graph '{}' must be a single number token",
+                            item.graph
+                        )));
+                    }
+                }
+            }
+        };
+
+        for part in &mut parts {
+            if let Part::Token(token) = part {
+                token.filter = token.token.filter(filters);
+            }
+        }
+
+        Ok(parts)
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Copy, Clone)]
 #[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct Intervals {
-    /// The number of seconds between refreshing memory data.
+    /// The number of milliseconds between refreshing memory data.
     ///
-    /// **Default**: `5`
-    memory: u64,
+    /// **Default**: `null`
+    memory: Option<u64>,
 
-    /// The number of seconds between refreshing CPU data.
+    /// The number of milliseconds between refreshing CPU data.
     ///
-    /// **Default**: `5`
-    cpu: u64,
+    /// **Default**: `null`
+    cpu: Option<u64>,
 
-    /// The number of seconds between refreshing temperature data.
+    /// The number of milliseconds between refreshing temperature data.
     ///
-    /// **Default**: `5`
-    temps: u64,
+    /// **Default**: `null`
+    temps: Option<u64>,
 
-    /// The number of seconds between refreshing disk data.
+    /// The number of milliseconds between refreshing disk data.
     ///
-    /// **Default**: `5`
-    disks: u64,
+    /// **Default**: `null`
+    disks: Option<u64>,
 
-    /// The number of seconds between refreshing network data.
+    /// The number of milliseconds between refreshing network data.
     ///
-    /// **Default**: `5`
-    networks: u64,
+    /// **Default**: `null`
+    networks: Option<u64>,
 
-    /// The number of seconds between refreshing system data.
+    /// The number of milliseconds between refreshing system data.
     ///
-    /// **Default**: `5`
-    system: u64,
+    /// **Default**: `null`
+    system: Option<u64>,
+
+    /// The number of milliseconds between refreshing GPU data.
+    ///
+    /// **Default**: `null`
+    gpu: Option<u64>,
 }
 
-impl Default for Intervals {
-    fn default() -> Self {
-        Self {
-            memory: 5,
-            cpu: 5,
-            temps: 5,
-            disks: 5,
-            networks: 5,
-            system: 5,
-        }
+impl Intervals {
+    /// Gets the interval for `refresh_type`,
+    /// falling back to `default` milliseconds if it is not set.
+    fn get(self, refresh_type: RefreshType, default: u64) -> Duration {
+        let millis = match refresh_type {
+            RefreshType::Memory => self.memory,
+            RefreshType::Cpu => self.cpu,
+            RefreshType::Temps => self.temps,
+            RefreshType::Disks => self.disks,
+            RefreshType::Network => self.networks,
+            RefreshType::System => self.system,
+            RefreshType::Gpu => self.gpu,
+        };
+
+        Duration::from_millis(millis.unwrap_or(default))
     }
 }
 
-#[derive(Debug, Deserialize, Copy, Clone)]
-#[serde(untagged)]
+#[derive(Debug, Default, Deserialize, Copy, Clone)]
 #[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
-pub enum Interval {
-    All(u64),
-    Individual(Intervals),
+#[serde(default)]
+pub struct SysInfoThresholds {
+    /// Thresholds for CPU tokens.
+    ///
+    /// **Default**: `{}`
+    cpu: Thresholds,
+
+    /// Thresholds for memory tokens.
+    ///
+    /// **Default**: `{}`
+    memory: Thresholds,
+
+    /// Thresholds for swap tokens.
+    ///
+    /// **Default**: `{}`
+    swap: Thresholds,
+
+    /// Thresholds for temperature tokens.
+    ///
+    /// **Default**: `{}`
+    temps: Thresholds,
+
+    /// Thresholds for disk tokens.
+    ///
+    /// **Default**: `{}`
+    disks: Thresholds,
+
+    /// Thresholds for network tokens.
+    ///
+    /// **Default**: `{}`
+    networks: Thresholds,
+
+    /// Thresholds for load average tokens.
+    ///
+    /// **Default**: `{}`
+    load: Thresholds,
+
+    /// Thresholds for GPU tokens.
+    ///
+    /// **Default**: `{}`
+    gpu: Thresholds,
 }
 
-impl Default for Interval {
-    fn default() -> Self {
-        Self::All(5)
-    }
+#[derive(Debug, Default, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct SysInfoFilters {
+    /// Filters network adapters by name, such as `enp39s0`.
+    ///
+    /// **Default**: `{}`
+    networks: Filter,
+
+    /// Filters disks by mount point, such as `/home`.
+    ///
+    /// **Default**: `{}`
+    disks: Filter,
 }
 
-impl Interval {
-    const fn memory(self) -> u64 {
-        match self {
-            Self::All(n) => n,
-            Self::Individual(intervals) => intervals.memory,
-        }
-    }
+#[derive(Debug, Default, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct Filter {
+    /// If set, only names matching this regular expression are included.
+    ///
+    /// **Default**: `null`
+    include: Option<String>,
 
-    const fn cpu(self) -> u64 {
-        match self {
-            Self::All(n) => n,
-            Self::Individual(intervals) => intervals.cpu,
-        }
-    }
+    /// Names matching this regular expression are left out.
+    ///
+    /// **Default**: `null`
+    exclude: Option<String>,
+}
 
-    const fn temps(self) -> u64 {
-        match self {
-            Self::All(n) => n,
-            Self::Individual(intervals) => intervals.temps,
+impl Filter {
+    fn compile(&self) -> Result<Option<NameFilter>> {
+        if self.include.is_none() && self.exclude.is_none() {
+            return Ok(None);
         }
+
+        let compile = |pattern: Option<&str>| pattern.map(Regex::new).transpose();
+
+        Ok(Some(NameFilter {
+            include: compile(self.include.as_deref())?,
+            exclude: compile(self.exclude.as_deref())?,
+        }))
     }
+}
 
-    pub const fn disks(self) -> u64 {
-        match self {
-            Self::All(n) => n,
-            Self::Individual(intervals) => intervals.disks,
-        }
+/// Compiled filters, ready to be attached to tokens.
+struct Filters {
+    networks: Option<NameFilter>,
+    disks: Option<NameFilter>,
+}
+
+impl TryFrom<&SysInfoFilters> for Filters {
+    type Error = Report;
+
+    fn try_from(filters: &SysInfoFilters) -> Result<Self> {
+        Ok(Self {
+            networks: filters.networks.compile()?,
+            disks: filters.disks.compile()?,
+        })
     }
+}
 
-    pub const fn networks(self) -> u64 {
-        match self {
-            Self::All(n) => n,
-            Self::Individual(intervals) => intervals.networks,
-        }
+impl TokenType {
+    /// Whether the token is shown with a unit chosen to suit its size,
+    /// such as `1.2 MiB/s`.
+    const fn is_auto_scaled(self) -> bool {
+        matches!(
+            self,
+            Self::DiskReadAuto | Self::DiskWriteAuto | Self::NetDownAuto | Self::NetUpAuto
+        )
     }
 
-    const fn system(self) -> u64 {
+    /// Gets the filter for the set of values the token reads from, if any.
+    fn filter(self, filters: &Filters) -> Option<NameFilter> {
         match self {
-            Self::All(n) => n,
-            Self::Individual(intervals) => intervals.system,
+            Self::DiskFree
+            | Self::DiskTotal
+            | Self::DiskUsed
+            | Self::DiskPercent
+            | Self::DiskRead
+            | Self::DiskWrite
+            | Self::DiskReadAuto
+            | Self::DiskWriteAuto => filters.disks.clone(),
+            Self::NetDown | Self::NetUp | Self::NetDownAuto | Self::NetUpAuto => {
+                filters.networks.clone()
+            }
+            _ => None,
         }
     }
-}
-
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
-enum RefreshType {
-    Memory,
-    Cpu,
-    Temps,
-    Disks,
-    Network,
-    System,
-}
 
-impl TokenType {
     fn is_affected_by(self, refresh_type: RefreshType) -> bool {
         match self {
             Self::CpuFrequency | Self::CpuPercent => refresh_type == RefreshType::Cpu,
@@ -199,77 +372,184 @@ impl TokenType {
             | Self::DiskUsed
             | Self::DiskPercent
             | Self::DiskRead
-            | Self::DiskWrite => refresh_type == RefreshType::Disks,
-            Self::NetDown | Self::NetUp => refresh_type == RefreshType::Network,
+            | Self::DiskWrite
+            | Self::DiskReadAuto
+            | Self::DiskWriteAuto => refresh_type == RefreshType::Disks,
+            Self::NetDown | Self::NetUp | Self::NetDownAuto | Self::NetUpAuto => {
+                refresh_type == RefreshType::Network
+            }
+            Self::GpuPercent
+            | Self::GpuMemoryUsed
+            | Self::GpuMemoryTotal
+            | Self::GpuMemoryPercent
+            | Self::GpuTempC
+            | Self::GpuTempF => refresh_type == RefreshType::Gpu,
             Self::LoadAverage1 | Self::LoadAverage5 | Self::LoadAverage15 => {
                 refresh_type == RefreshType::System
             }
             Self::Uptime => refresh_type == RefreshType::System,
         }
     }
+
+    fn thresholds(self, thresholds: SysInfoThresholds) -> Option<Thresholds> {
+        match self {
+            Self::CpuFrequency | Self::CpuPercent => Some(thresholds.cpu),
+            Self::MemoryFree
+            | Self::MemoryAvailable
+            | Self::MemoryTotal
+            | Self::MemoryUsed
+            | Self::MemoryPercent => Some(thresholds.memory),
+            Self::SwapFree | Self::SwapTotal | Self::SwapUsed | Self::SwapPercent => {
+                Some(thresholds.swap)
+            }
+            Self::TempC | Self::TempF => Some(thresholds.temps),
+            Self::DiskFree
+            | Self::DiskTotal
+            | Self::DiskUsed
+            | Self::DiskPercent
+            | Self::DiskRead
+            | Self::DiskWrite
+            | Self::DiskReadAuto
+            | Self::DiskWriteAuto => Some(thresholds.disks),
+            Self::NetDown | Self::NetUp | Self::NetDownAuto | Self::NetUpAuto => {
+                Some(thresholds.networks)
+            }
+            Self::GpuPercent
+            | Self::GpuMemoryUsed
+            | Self::GpuMemoryTotal
+            | Self::GpuMemoryPercent
+            | Self::GpuTempC
+            | Self::GpuTempF => Some(thresholds.gpu),
+            Self::LoadAverage1 | Self::LoadAverage5 | Self::LoadAverage15 => Some(thresholds.load),
+            Self::Uptime => None,
+        }
+        .filter(|thresholds| !thresholds.is_empty())
+    }
+}
+
+impl Part {
+    /// Gets the warning state for a label,
+    /// using the first token with thresholds set.
+    fn threshold_level(
+        tokens: &[Self],
+        client: &clients::sysinfo::Client,
+        thresholds: SysInfoThresholds,
+    ) -> Option<ThresholdLevel> {
+        tokens.iter().find_map(|part| {
+            let Part::Token(token) = part else {
+                return None;
+            };
+
+            let token_thresholds = token.token.thresholds(thresholds)?;
+            match token.get(client) {
+                TokenValue::Number(value) => token_thresholds.level(value, true),
+                TokenValue::String(_) => None,
+            }
+        })
+    }
+}
+
+impl Part {
+    /// Gets the value for a format item.
+    /// Graphs are made of a single token, so use its raw value.
+    fn value_all(tokens: &[Self], client: &clients::sysinfo::Client, is_graph: bool) -> TokenValue {
+        match tokens {
+            [Part::Token(token)] if is_graph => token.get(client),
+            _ => TokenValue::String(Part::render_all(tokens, client)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ControllerEvent {
+    /// The new value for the format item at the index.
+    Item(usize, TokenValue, Option<ThresholdLevel>),
+    Popup(Box<PopupData>),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum UiEvent {
+    PopupOpened,
+    PopupClosed,
 }
 
 impl Module<gtk::Box> for SysInfoModule {
-    type SendMessage = (usize, String);
-    type ReceiveMessage = ();
+    type SendMessage = ControllerEvent;
+    type ReceiveMessage = UiEvent;
 
     module_impl!("sysinfo");
 
+    fn set_interval(&mut self, interval: u64) {
+        self.interval = interval;
+    }
+
+    fn coalesce_key(update: &Self::SendMessage) -> Option<String> {
+        match update {
+            ControllerEvent::Item(index, ..) => Some(index.to_string()),
+            ControllerEvent::Popup(_) => Some("popup".to_string()),
+        }
+    }
+
     fn spawn_controller(
         &self,
         _info: &ModuleInfo,
         context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
-        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
     ) -> Result<()> {
         let interval = self.interval;
+        let type_intervals = self.intervals;
+        let thresholds = self.thresholds;
 
         let client = context.client::<clients::sysinfo::Client>();
+        let filters = Filters::try_from(&self.filters)?;
 
         let format_tokens = self
             .format
             .iter()
-            .map(|format| parser::parse_input(format.as_str()))
+            .map(|format| {
+                Ok((
+                    format.parse(&filters)?,
+                    matches!(format, FormatItem::Graph(_)),
+                ))
+            })
             .collect::<Result<Vec<_>>>()?;
 
-        for (i, token_set) in format_tokens.iter().enumerate() {
-            let rendered = Part::render_all(token_set, &client, interval);
-            context.tx.send_update_spawn((i, rendered));
+        for (i, (token_set, is_graph)) in format_tokens.iter().enumerate() {
+            let value = Part::value_all(token_set, &client, *is_graph);
+            let level = Part::threshold_level(token_set, &client, thresholds);
+            context
+                .tx
+                .send_update_spawn(ControllerEvent::Item(i, value, level));
         }
 
-        let (refresh_tx, mut refresh_rx) = mpsc::channel(16);
-
-        macro_rules! spawn_refresh {
-            ($refresh_type:expr, $func:ident) => {{
-                let tx = refresh_tx.clone();
-                spawn(async move {
-                    loop {
-                        tx.send_expect($refresh_type).await;
-                        sleep(Duration::from_secs(interval.$func())).await;
-                    }
-                });
-            }};
-        }
-
-        spawn_refresh!(RefreshType::Memory, memory);
-        spawn_refresh!(RefreshType::Cpu, cpu);
-        spawn_refresh!(RefreshType::Temps, temps);
-        spawn_refresh!(RefreshType::Disks, disks);
-        spawn_refresh!(RefreshType::Network, networks);
-        spawn_refresh!(RefreshType::System, system);
+        // only sample data which is used by this module's tokens
+        let intervals = RefreshType::ALL
+            .into_iter()
+            .filter(|&refresh_type| {
+                format_tokens
+                    .iter()
+                    .flat_map(|(token_set, _)| token_set)
+                    .any(|part| match part {
+                        Part::Token(token) => token.token.is_affected_by(refresh_type),
+                        Part::Static(_) => false,
+                    })
+            })
+            .map(|refresh_type| (refresh_type, type_intervals.get(refresh_type, interval)))
+            .collect::<Vec<_>>();
+
+        let mut refresh_rx = client.subscribe(&intervals);
 
         let tx = context.tx.clone();
         spawn(async move {
-            while let Some(refresh) = refresh_rx.recv().await {
-                match refresh {
-                    RefreshType::Memory => client.refresh_memory(),
-                    RefreshType::Cpu => client.refresh_cpu(),
-                    RefreshType::Temps => client.refresh_temps(),
-                    RefreshType::Disks => client.refresh_disks(),
-                    RefreshType::Network => client.refresh_network(),
-                    RefreshType::System => client.refresh_load_average(),
-                }
-
-                for (i, token_set) in format_tokens.iter().enumerate() {
+            loop {
+                let refresh = match refresh_rx.recv_tracked("sysinfo refresh").await {
+                    Ok(refresh) => refresh,
+                    // every refresh re-renders the full state, so missed ones can be skipped
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+
+                for (i, (token_set, is_graph)) in format_tokens.iter().enumerate() {
                     let is_affected = token_set
                         .iter()
                         .filter_map(|part| {
@@ -282,13 +562,49 @@ impl Module<gtk::Box> for SysInfoModule {
                         .any(|t| t.token.is_affected_by(refresh));
 
                     if is_affected {
-                        let rendered = Part::render_all(token_set, &client, interval);
-                        tx.send_update((i, rendered)).await;
+                        let value = Part::value_all(token_set, &client, *is_graph);
+                        let level = Part::threshold_level(token_set, &client, thresholds);
+                        tx.send_update(ControllerEvent::Item(i, value, level)).await;
                     }
                 }
             }
         });
 
+        if let Some(config) = self.popup.clone() {
+            let tx = context.tx.clone();
+            let interval = Duration::from_millis(config.interval.max(100));
+
+            spawn(async move {
+                loop {
+                    // wait for the popup to open
+                    loop {
+                        match rx.recv().await {
+                            Some(UiEvent::PopupOpened) => break,
+                            Some(UiEvent::PopupClosed) => {}
+                            None => return,
+                        }
+                    }
+
+                    let mut sampler = Sampler::new();
+                    sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL).await;
+
+                    loop {
+                        let data = sampler.sample(&config);
+                        tx.send_update(ControllerEvent::Popup(Box::new(data))).await;
+
+                        tokio::select! {
+                            () = sleep(interval) => {}
+                            event = rx.recv() => match event {
+                                Some(UiEvent::PopupClosed) => break,
+                                Some(UiEvent::PopupOpened) => {}
+                                None => return,
+                            }
+                        }
+                    }
+                }
+            });
+        }
+
         Ok(())
     }
 
@@ -304,28 +620,122 @@ impl Module<gtk::Box> for SysInfoModule {
 
         let container = gtk::Box::new(layout, 10);
 
-        let mut labels = Vec::new();
+        // items are placed inside a button when there is a popup to open,
+        // leaving the module unchanged otherwise
+        let (items_container, button) = if self.popup.is_some() {
+            let items_container = gtk::Box::new(layout, 10);
+
+            let button = Button::new();
+            button.set_child(Some(&items_container));
+            button.set_accessible_label("System information");
+            container.append(&button);
+
+            {
+                let tx = context.tx.clone();
+                button.connect_clicked(move |button| {
+                    tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
+                });
+            }
+
+            (items_container, Some(button))
+        } else {
+            (container.clone(), None)
+        };
+
+        let mut items = Vec::new();
+
+        for format in &self.format {
+            let item = match format {
+                FormatItem::Label(_) => {
+                    let label = Label::builder()
+                        .use_markup(true)
+                        .justify(self.layout.justify.into())
+                        .build();
+
+                    label.add_css_class("item");
 
-        for _ in &self.format {
-            let label = Label::builder()
-                .use_markup(true)
-                .justify(self.layout.justify.into())
-                .build();
+                    items_container.append(&label.rotated(self.layout.angle()));
+                    Item::Label(label)
+                }
+                FormatItem::Graph(item) => {
+                    let graph = Graph::new(&item.config);
+                    graph.widget().add_css_class("item");
 
-            label.add_css_class("item");
+                    items_container.append(graph.widget());
+                    Item::Graph(graph)
+                }
+            };
 
-            container.append(&label);
-            labels.push(label);
+            items.push(item);
         }
 
-        context.subscribe().recv_glib((), move |(), data| {
-            let label = &labels[data.0];
-            label.set_label_escaped(&data.1);
+        context.subscribe().recv_glib((), move |(), event| {
+            let ControllerEvent::Item(index, value, level) = event else {
+                return;
+            };
+
+            match (&items[index], value) {
+                (Item::Label(label), TokenValue::String(value)) => {
+                    label.set_label_escaped(&value);
+                    ThresholdLevel::apply(label, level);
+                }
+                (Item::Graph(graph), TokenValue::Number(value)) => {
+                    graph.push(value);
+                    ThresholdLevel::apply(graph.widget(), level);
+                }
+                _ => {}
+            }
         });
 
+        let popup = match &button {
+            Some(button) => self
+                .into_popup(context, info)
+                .into_popup_parts(vec![button]),
+            None => None,
+        };
+
         Ok(ModuleParts {
             widget: container,
-            popup: None,
+            popup,
         })
     }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        self.popup.as_ref()?;
+
+        let mut popup = Popup::new(self.thresholds.cpu);
+        let container = popup.container.clone();
+
+        // data is only sampled while the popup is visible
+        {
+            let tx = context.controller_tx.clone();
+            container.connect_map(move |_| tx.send_spawn(UiEvent::PopupOpened));
+        }
+
+        {
+            let tx = context.controller_tx.clone();
+            container.connect_unmap(move |_| tx.send_spawn(UiEvent::PopupClosed));
+        }
+
+        context.subscribe().recv_glib((), move |(), event| {
+            if let ControllerEvent::Popup(data) = event {
+                popup.update(&data);
+            }
+        });
+
+        Some(container)
+    }
+}
+
+/// A widget showing a format item.
+enum Item {
+    Label(Label),
+    Graph(Graph),
 }
@@ -16,8 +16,18 @@ impl Function {
             | TokenType::DiskUsed
             | TokenType::DiskRead
             | TokenType::DiskWrite
+            | TokenType::DiskReadAuto
+            | TokenType::DiskWriteAuto
             | TokenType::NetDown
-            | TokenType::NetUp => Self::Sum,
+            | TokenType::NetUp
+            | TokenType::NetDownAuto
+            | TokenType::NetUpAuto
+            | TokenType::GpuMemoryUsed
+            | TokenType::GpuMemoryTotal => Self::Sum,
+            TokenType::GpuPercent
+            | TokenType::GpuMemoryPercent
+            | TokenType::GpuTempC
+            | TokenType::GpuTempF => Self::Max,
             _ => Self::None,
         }
     }
@@ -36,7 +46,9 @@ impl Prefix {
             | TokenType::SwapUsed
             | TokenType::DiskFree
             | TokenType::DiskTotal
-            | TokenType::DiskUsed => Self::Giga,
+            | TokenType::DiskUsed
+            | TokenType::GpuMemoryUsed
+            | TokenType::GpuMemoryTotal => Self::Giga,
             TokenType::DiskRead | TokenType::DiskWrite => Self::Mega,
             TokenType::NetDown | TokenType::NetUp => Self::MegaBit,
             _ => Self::None,
@@ -95,6 +107,15 @@ impl Formatting {
                 align: Alignment::default(),
                 precision: 2,
             },
+            TokenType::DiskReadAuto
+            | TokenType::DiskWriteAuto
+            | TokenType::NetDownAuto
+            | TokenType::NetUpAuto => Self {
+                width: 0,
+                fill: ' ',
+                align: Alignment::default(),
+                precision: 1,
+            },
             TokenType::CpuPercent => Self {
                 width: 2,
                 fill: '0',
@@ -109,7 +130,9 @@ impl Formatting {
             | TokenType::SwapFree
             | TokenType::SwapTotal
             | TokenType::SwapUsed
-            | TokenType::SwapPercent => Self {
+            | TokenType::SwapPercent
+            | TokenType::GpuMemoryUsed
+            | TokenType::GpuMemoryTotal => Self {
                 width: 4,
                 fill: '0',
                 align: Alignment::default(),
@@ -205,10 +228,16 @@ fn parse_dynamic(chars: &mut Peekable<Chars>) -> Result<Part> {
         function: func_str
             .parse()
             .unwrap_or_else(|()| Function::default_for(token_type)),
-        prefix: prefix_str
-            .parse()
-            .unwrap_or_else(|_| Prefix::default_for(token_type)),
+        // automatically scaled values choose their own unit
+        prefix: if token_type.is_auto_scaled() {
+            Prefix::None
+        } else {
+            prefix_str
+                .parse()
+                .unwrap_or_else(|_| Prefix::default_for(token_type))
+        },
         formatting,
+        filter: None,
     };
 
     Ok(Part::Token(token))
@@ -415,4 +444,22 @@ mod tests {
         assert!(matches!(&tokens[1], Part::Static(str) if str == " GHz"));
         Ok(())
     }
+
+    #[test]
+    fn auto_scaled() -> Result {
+        let tokens = parse_input("{net_down_auto@eth0#Mi:8}")?;
+
+        assert_eq!(tokens.len(), 1);
+
+        let Part::Token(token) = tokens.first().expect("should exist") else {
+            return Err(Box::new(NotTokenErr));
+        };
+
+        assert_eq!(token.token, TokenType::NetDownAuto);
+        assert!(matches!(&token.function, Function::Name(n) if n == "eth0"));
+        assert_eq!(token.prefix, Prefix::None);
+        assert_eq!(token.formatting.width, 8);
+        assert_eq!(token.formatting.fill, ' ');
+        Ok(())
+    }
 }
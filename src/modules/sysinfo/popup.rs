@@ -0,0 +1,270 @@
+use crate::config::Thresholds;
+use gtk::prelude::*;
+use gtk::{Align, Label, Orientation, ProgressBar};
+use serde::Deserialize;
+use std::cmp::Ordering;
+use sysinfo::{
+    CpuRefreshKind, MemoryRefreshKind, ProcessRefreshKind, ProcessesToUpdate, RefreshKind, System,
+};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct PopupConfig {
+    /// The number of processes to list.
+    /// Set to `0` to hide the process list.
+    ///
+    /// **Default**: `5`
+    pub processes: usize,
+
+    /// How to choose and order the listed processes.
+    ///
+    /// **Valid options**: `cpu`, `memory`
+    /// <br>
+    /// **Default**: `cpu`
+    pub sort: ProcessSort,
+
+    /// The number of milliseconds between refreshing the popup while it is open.
+    ///
+    /// **Default**: `2000`
+    pub interval: u64,
+}
+
+impl Default for PopupConfig {
+    fn default() -> Self {
+        Self {
+            processes: 5,
+            sort: ProcessSort::default(),
+            interval: 2000,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessSort {
+    /// Processes using the most CPU first.
+    #[default]
+    Cpu,
+    /// Processes using the most memory first.
+    Memory,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    pub name: String,
+    /// Usage as a percentage of a single core.
+    pub cpu_percent: f64,
+    pub memory_percent: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PopupData {
+    /// Usage of each core, as a percentage.
+    pub cores: Vec<f64>,
+    pub load_average: [f64; 3],
+    pub processes: Vec<ProcessInfo>,
+}
+
+/// Samples the data shown in the popup.
+///
+/// This is kept apart from the shared client
+/// so that processes are only read from procfs while the popup is open.
+pub struct Sampler {
+    system: System,
+}
+
+impl Sampler {
+    /// Takes an initial sample.
+    /// Usage is calculated between two samples,
+    /// so at least [`sysinfo::MINIMUM_CPU_UPDATE_INTERVAL`] should pass
+    /// before calling [`Sampler::sample`].
+    pub fn new() -> Self {
+        let mut system = System::new_with_specifics(
+            RefreshKind::nothing()
+                .with_cpu(CpuRefreshKind::nothing().with_cpu_usage())
+                .with_memory(MemoryRefreshKind::nothing().with_ram()),
+        );
+
+        Self::refresh_processes(&mut system);
+
+        Self { system }
+    }
+
+    fn refresh_processes(system: &mut System) {
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing().with_cpu().with_memory(),
+        );
+    }
+
+    pub fn sample(&mut self, config: &PopupConfig) -> PopupData {
+        self.system.refresh_cpu_usage();
+        Self::refresh_processes(&mut self.system);
+
+        let cores = self
+            .system
+            .cpus()
+            .iter()
+            .map(|cpu| f64::from(cpu.cpu_usage()))
+            .collect();
+
+        let load = System::load_average();
+
+        let total_memory = self.system.total_memory() as f64;
+        let mut processes = self
+            .system
+            .processes()
+            .values()
+            // threads are listed alongside their process
+            .filter(|process| process.thread_kind().is_none())
+            .map(|process| ProcessInfo {
+                pid: process.pid().as_u32(),
+                name: process.name().to_string_lossy().into_owned(),
+                cpu_percent: f64::from(process.cpu_usage()),
+                memory_percent: if total_memory > 0.0 {
+                    process.memory() as f64 / total_memory * 100.0
+                } else {
+                    0.0
+                },
+            })
+            .collect::<Vec<_>>();
+
+        let key = |process: &ProcessInfo| match config.sort {
+            ProcessSort::Cpu => process.cpu_percent,
+            ProcessSort::Memory => process.memory_percent,
+        };
+
+        processes.sort_by(|a, b| key(b).partial_cmp(&key(a)).unwrap_or(Ordering::Equal));
+        processes.truncate(config.processes);
+
+        PopupData {
+            cores,
+            load_average: [load.one, load.five, load.fifteen],
+            processes,
+        }
+    }
+}
+
+/// The popup contents, updated in place as new data arrives.
+pub struct Popup {
+    pub container: gtk::Box,
+    cores: gtk::Box,
+    core_rows: Vec<(ProgressBar, Label)>,
+    load_average: Label,
+    processes: gtk::Box,
+    thresholds: Thresholds,
+}
+
+impl Popup {
+    pub fn new(thresholds: Thresholds) -> Self {
+        let container = gtk::Box::new(Orientation::Vertical, 10);
+
+        let cores = gtk::Box::new(Orientation::Vertical, 2);
+        cores.add_css_class("cores");
+        container.append(&cores);
+
+        let load_average = Label::new(None);
+        load_average.add_css_class("load-average");
+        load_average.set_halign(Align::Start);
+        container.append(&load_average);
+
+        let processes = gtk::Box::new(Orientation::Vertical, 2);
+        processes.add_css_class("processes");
+        container.append(&processes);
+
+        Self {
+            container,
+            cores,
+            core_rows: vec![],
+            load_average,
+            processes,
+            thresholds,
+        }
+    }
+
+    pub fn update(&mut self, data: &PopupData) {
+        // the number of cores does not change,
+        // so rows are only created for the first update
+        if self.core_rows.len() != data.cores.len() {
+            while let Some(child) = self.cores.first_child() {
+                self.cores.remove(&child);
+            }
+
+            let rows = (0..data.cores.len())
+                .map(|index| self.add_core_row(index))
+                .collect();
+            self.core_rows = rows;
+        }
+
+        for ((bar, label), &percent) in self.core_rows.iter().zip(&data.cores) {
+            bar.set_fraction((percent / 100.0).clamp(0.0, 1.0));
+            label.set_label(&format!("{percent:.0}%"));
+
+            self.thresholds.apply(bar, Some(percent), true);
+        }
+
+        let [one, five, fifteen] = data.load_average;
+        self.load_average
+            .set_label(&format!("Load: {one:.2} {five:.2} {fifteen:.2}"));
+
+        while let Some(child) = self.processes.first_child() {
+            self.processes.remove(&child);
+        }
+
+        for process in &data.processes {
+            self.processes.append(&process_row(process));
+        }
+    }
+
+    fn add_core_row(&self, index: usize) -> (ProgressBar, Label) {
+        let row = gtk::Box::new(Orientation::Horizontal, 10);
+        row.add_css_class("core");
+
+        let name = Label::new(Some(&format!("cpu{index}")));
+        name.add_css_class("name");
+        row.append(&name);
+
+        let bar = ProgressBar::new();
+        bar.add_css_class("progress");
+        bar.set_hexpand(true);
+        bar.set_valign(Align::Center);
+        row.append(&bar);
+
+        let percent = Label::new(None);
+        percent.add_css_class("percent");
+        percent.set_width_chars(4);
+        percent.set_xalign(1.0);
+        row.append(&percent);
+
+        self.cores.append(&row);
+
+        (bar, percent)
+    }
+}
+
+fn process_row(process: &ProcessInfo) -> gtk::Box {
+    let row = gtk::Box::new(Orientation::Horizontal, 10);
+    row.add_css_class("process");
+    row.set_tooltip_text(Some(&format!("PID {}", process.pid)));
+
+    let name = Label::new(Some(&process.name));
+    name.add_css_class("name");
+    name.set_halign(Align::Start);
+    name.set_hexpand(true);
+    name.set_ellipsize(gtk::pango::EllipsizeMode::End);
+    row.append(&name);
+
+    let cpu = Label::new(Some(&format!("{:.1}%", process.cpu_percent)));
+    cpu.add_css_class("cpu");
+    row.append(&cpu);
+
+    let memory = Label::new(Some(&format!("{:.1}%", process.memory_percent)));
+    memory.add_css_class("memory");
+    row.append(&memory);
+
+    row
+}
@@ -1,61 +1,85 @@
-use super::Interval;
 use super::token::{Alignment, Part, Token};
 use crate::clients;
-use crate::clients::sysinfo::{TokenType, Value, ValueSet};
+use crate::clients::sysinfo::{Function, TokenType, Value, ValueSet};
 
+#[derive(Debug, Clone)]
 pub enum TokenValue {
     Number(f64),
     String(String),
 }
 
 impl Part {
-    pub fn render_all(
-        tokens: &[Self],
-        client: &clients::sysinfo::Client,
-        interval: Interval,
-    ) -> String {
-        tokens
-            .iter()
-            .map(|part| part.render(client, interval))
-            .collect()
+    pub fn render_all(tokens: &[Self], client: &clients::sysinfo::Client) -> String {
+        tokens.iter().map(|part| part.render(client)).collect()
     }
 
-    fn render(&self, client: &clients::sysinfo::Client, interval: Interval) -> String {
+    fn render(&self, client: &clients::sysinfo::Client) -> String {
         match self {
             Part::Static(str) => str.clone(),
             Part::Token(token) => {
-                match token.get(client, interval) {
+                let fmt = token.formatting;
+                let mut str = match token.get(client) {
+                    TokenValue::Number(value) if token.token.is_auto_scaled() => {
+                        format_rate(value, fmt.precision)
+                    }
                     TokenValue::Number(value) => {
-                        let fmt = token.formatting;
-                        let mut str = format!("{value:.precision$}", precision = fmt.precision);
-
-                        // fill/align doesn't support parameterization so we need our own impl
-                        let mut add_to_end = fmt.align == Alignment::Right;
-                        while str.len() < fmt.width {
-                            if add_to_end {
-                                str.push(fmt.fill);
-                            } else {
-                                str.insert(0, fmt.fill);
-                            }
+                        format!("{value:.precision$}", precision = fmt.precision)
+                    }
+                    TokenValue::String(value) => value,
+                };
 
-                            if fmt.align == Alignment::Center {
-                                add_to_end = !add_to_end;
-                            }
-                        }
+                // fill/align doesn't support parameterization so we need our own impl
+                let mut add_to_end = fmt.align == Alignment::Right;
+                while str.chars().count() < fmt.width {
+                    if add_to_end {
+                        str.push(fmt.fill);
+                    } else {
+                        str.insert(0, fmt.fill);
+                    }
 
-                        str
+                    if fmt.align == Alignment::Center {
+                        add_to_end = !add_to_end;
                     }
-                    TokenValue::String(value) => value,
                 }
+
+                str
             }
         }
     }
 }
 
+/// Formats a number of bytes per second
+/// using the largest binary unit which keeps it above 1.
+fn format_rate(bytes: f64, precision: usize) -> String {
+    const UNITS: [&str; 5] = ["B/s", "KiB/s", "MiB/s", "GiB/s", "TiB/s"];
+
+    let mut value = bytes;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    // bytes cannot be fractional
+    let precision = if unit == 0 { 0 } else { precision };
+    format!("{value:.precision$} {}", UNITS[unit])
+}
+
 impl Token {
-    pub fn get(&self, client: &clients::sysinfo::Client, interval: Interval) -> TokenValue {
+    pub fn get(&self, client: &clients::sysinfo::Client) -> TokenValue {
         let get = |value: Value| TokenValue::Number(value.get(self.prefix));
-        let apply = |set: ValueSet| TokenValue::Number(set.apply(&self.function, self.prefix));
+        let apply = |set: ValueSet| {
+            // values chosen by name are always available
+            let set = match &self.filter {
+                Some(filter) if !matches!(self.function, Function::Name(_)) => {
+                    set.filter(|name| filter.matches(name))
+                }
+                _ => set,
+            };
+
+            TokenValue::Number(set.apply(&self.function, self.prefix))
+        };
 
         match self.token {
             // Number tokens
@@ -76,10 +100,20 @@ impl Token {
             TokenType::DiskTotal => apply(client.disk_total()),
             TokenType::DiskUsed => apply(client.disk_used()),
             TokenType::DiskPercent => apply(client.disk_percent()),
-            TokenType::DiskRead => apply(client.disk_read(interval)),
-            TokenType::DiskWrite => apply(client.disk_write(interval)),
-            TokenType::NetDown => apply(client.net_down(interval)),
-            TokenType::NetUp => apply(client.net_up(interval)),
+            TokenType::DiskRead => apply(client.disk_read()),
+            TokenType::DiskWrite => apply(client.disk_write()),
+            TokenType::DiskReadAuto => apply(client.disk_read()),
+            TokenType::DiskWriteAuto => apply(client.disk_write()),
+            TokenType::NetDown => apply(client.net_down()),
+            TokenType::NetUp => apply(client.net_up()),
+            TokenType::NetDownAuto => apply(client.net_down()),
+            TokenType::NetUpAuto => apply(client.net_up()),
+            TokenType::GpuPercent => apply(client.gpu_percent()),
+            TokenType::GpuMemoryUsed => apply(client.gpu_memory_used()),
+            TokenType::GpuMemoryTotal => apply(client.gpu_memory_total()),
+            TokenType::GpuMemoryPercent => apply(client.gpu_memory_percent()),
+            TokenType::GpuTempC => apply(client.gpu_temp_c()),
+            TokenType::GpuTempF => apply(client.gpu_temp_f()),
             TokenType::LoadAverage1 => get(client.load_average_1()),
             TokenType::LoadAverage5 => get(client.load_average_5()),
             TokenType::LoadAverage15 => get(client.load_average_15()),
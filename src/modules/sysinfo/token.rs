@@ -1,4 +1,5 @@
 use crate::clients::sysinfo::{Function, Prefix, TokenType};
+use regex::Regex;
 
 #[derive(Debug, Clone)]
 pub struct Token {
@@ -6,6 +7,23 @@ pub struct Token {
     pub function: Function,
     pub prefix: Prefix,
     pub formatting: Formatting,
+    /// Limits which values are combined by the token's function.
+    pub filter: Option<NameFilter>,
+}
+
+/// Matches the names of values in a set,
+/// such as network adapters or disk mounts.
+#[derive(Debug, Clone)]
+pub struct NameFilter {
+    pub include: Option<Regex>,
+    pub exclude: Option<Regex>,
+}
+
+impl NameFilter {
+    pub fn matches(&self, name: &str) -> bool {
+        self.include.as_ref().is_none_or(|re| re.is_match(name))
+            && !self.exclude.as_ref().is_some_and(|re| re.is_match(name))
+    }
 }
 
 #[derive(Debug, Clone)]
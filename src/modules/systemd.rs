@@ -0,0 +1,393 @@
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::clients::systemd::{ActiveState, Bus, Client, Unit, UnitAction};
+use crate::config::{CommonConfig, LayoutConfig};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::{module_impl, spawn};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tracing::error;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct SystemdModule {
+    /// The names of system units to watch, such as `nginx.service`.
+    ///
+    /// **Default**: `[]`
+    system_units: Vec<String>,
+
+    /// The names of the current user's units to watch, such as `syncthing.service`.
+    ///
+    /// **Default**: `[]`
+    user_units: Vec<String>,
+
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{icon} {active}/{total}`
+    #[serde(skip)]
+    format: String,
+
+    /// The icon to use for the `{icon}` token.
+    ///
+    /// **Default**: `󰒓`
+    icon: String,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for SystemdModule {
+    fn default() -> Self {
+        Self {
+            system_units: vec![],
+            user_units: vec![],
+            format: "{icon} {active}/{total}".to_string(),
+            icon: "󰒓".to_string(),
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+impl SystemdModule {
+    /// Gets each configured unit name along with its bus, in display order.
+    fn units(&self) -> impl Iterator<Item = (Bus, &String)> {
+        self.system_units
+            .iter()
+            .map(|name| (Bus::System, name))
+            .chain(self.user_units.iter().map(|name| (Bus::User, name)))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UiEvent {
+    bus: Bus,
+    name: String,
+    action: UnitAction,
+}
+
+impl Module<Button> for SystemdModule {
+    type SendMessage = Vec<Unit>;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("systemd");
+
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
+    fn coalesce_key(_update: &Self::SendMessage) -> Option<String> {
+        // each update is the full state
+        Some(String::new())
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let mut clients = Vec::<(Bus, Arc<Client>, Vec<String>)>::new();
+
+        for (bus, names) in [
+            (Bus::System, &self.system_units),
+            (Bus::User, &self.user_units),
+        ] {
+            if names.is_empty() {
+                continue;
+            }
+
+            let client = context.ironbar.clients.borrow_mut().systemd(bus)?;
+            for name in names {
+                client.watch(name);
+            }
+
+            clients.push((bus, client, names.clone()));
+        }
+
+        let clients = Arc::new(clients);
+
+        // each client signals a change here,
+        // and the full state of every unit is sent in response
+        let (changed_tx, mut changed_rx) = mpsc::channel(8);
+
+        for (_, client, names) in clients.iter() {
+            let client = client.clone();
+            let names = names.clone();
+            let changed_tx = changed_tx.clone();
+
+            spawn(async move {
+                let mut updates = client.subscribe();
+
+                loop {
+                    match updates.recv_tracked("systemd").await {
+                        Ok(unit) if names.contains(&unit.name) => changed_tx.send_expect(()).await,
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(_)) => changed_tx.send_expect(()).await,
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+
+        {
+            let clients = clients.clone();
+            let tx = context.tx.clone();
+
+            spawn(async move {
+                let all_units = || {
+                    clients
+                        .iter()
+                        .flat_map(|(_, client, names)| client.units(names))
+                        .collect::<Vec<_>>()
+                };
+
+                tx.send_update(all_units()).await;
+
+                while changed_rx.recv().await.is_some() {
+                    // several units often change together
+                    while changed_rx.try_recv().is_ok() {}
+
+                    tx.send_update(all_units()).await;
+                }
+            });
+        }
+
+        spawn(async move {
+            while let Some(event) = rx.recv().await {
+                let Some((_, client, _)) = clients.iter().find(|(bus, ..)| *bus == event.bus)
+                else {
+                    continue;
+                };
+
+                if let Err(err) = client.run(&event.name, event.action).await {
+                    error!(
+                        "Failed to {} systemd unit '{}': {err:?}",
+                        event.action.name(),
+                        event.name
+                    );
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let label = Label::builder()
+            .use_markup(true)
+            .justify(self.layout.justify.into())
+            .build();
+
+        let button = Button::new();
+        button.set_child(Some(&label.rotated(self.layout.angle())));
+        button.set_accessible_label("Systemd units");
+
+        {
+            let tx = context.tx.clone();
+            button.connect_clicked(move |button| {
+                tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
+            });
+        }
+
+        {
+            let button = button.clone();
+            let format = self.format.clone();
+            let icon = self.icon.clone();
+            let total = self.units().count();
+
+            context.subscribe().recv_glib((), move |(), units| {
+                let count = |state| {
+                    units
+                        .iter()
+                        .filter(|unit| unit.active_state == state)
+                        .count()
+                };
+
+                let active = count(ActiveState::Active);
+                let failed = count(ActiveState::Failed);
+
+                if failed > 0 {
+                    button.add_css_class("failed");
+                } else {
+                    button.remove_css_class("failed");
+                }
+
+                let text = format
+                    .replace("{icon}", &icon)
+                    .replace("{active}", &active.to_string())
+                    .replace("{failed}", &failed.to_string())
+                    .replace("{total}", &total.to_string());
+
+                label.set_label_escaped(&text);
+                button.set_accessible_description(&format!(
+                    "{active} of {total} active, {failed} failed"
+                ));
+            });
+        }
+
+        let popup = self
+            .into_popup(context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::new(Orientation::Vertical, 10);
+
+        // the configured units never change, so each row is created up front
+        let mut rows = HashMap::new();
+
+        for (bus, name) in self.units() {
+            let row = UnitRow::new(bus, name, &context.controller_tx);
+            container.append(&row.container);
+            rows.insert((bus, name.clone()), row);
+        }
+
+        context.subscribe().recv_glib((), move |(), units| {
+            for unit in units {
+                if let Some(row) = rows.get(&(unit.bus, unit.name.clone())) {
+                    row.update(&unit);
+                }
+            }
+        });
+
+        Some(container)
+    }
+}
+
+/// A popup row showing the state of a unit,
+/// with buttons to start, stop and restart it.
+struct UnitRow {
+    container: gtk::Box,
+    description: Label,
+    state: Label,
+    start: Button,
+    stop: Button,
+    restart: Button,
+}
+
+impl UnitRow {
+    fn new(bus: Bus, name: &str, tx: &mpsc::Sender<UiEvent>) -> Self {
+        let container = gtk::Box::new(Orientation::Horizontal, 10);
+        container.add_css_class("unit");
+
+        let details = gtk::Box::new(Orientation::Vertical, 0);
+        details.set_hexpand(true);
+        container.append(&details);
+
+        let title = Label::new(Some(name));
+        title.add_css_class("name");
+        title.set_halign(Align::Start);
+        details.append(&title);
+
+        let description = Label::new(None);
+        description.add_css_class("description");
+        description.set_halign(Align::Start);
+        details.append(&description);
+
+        let state = Label::new(Some(ActiveState::Unknown.name()));
+        state.add_css_class("state");
+        state.set_halign(Align::Start);
+        details.append(&state);
+
+        let controls = gtk::Box::new(Orientation::Horizontal, 5);
+        controls.set_valign(Align::Center);
+        container.append(&controls);
+
+        let button = |action: UnitAction, icon: &str| {
+            let button = Button::with_label(icon);
+            button.add_css_class(&format!("btn-{}", action.name()));
+            button.set_accessible_label(&format!("{} {name}", action.name()));
+
+            let tx = tx.clone();
+            let name = name.to_string();
+            button.connect_clicked(move |_| {
+                tx.send_spawn(UiEvent {
+                    bus,
+                    name: name.clone(),
+                    action,
+                });
+            });
+
+            controls.append(&button);
+            button
+        };
+
+        let start = button(UnitAction::Start, "󰐊");
+        let stop = button(UnitAction::Stop, "󰓛");
+        let restart = button(UnitAction::Restart, "󰑓");
+
+        Self {
+            container,
+            description,
+            state,
+            start,
+            stop,
+            restart,
+        }
+    }
+
+    fn update(&self, unit: &Unit) {
+        for class in [
+            ActiveState::Active,
+            ActiveState::Reloading,
+            ActiveState::Inactive,
+            ActiveState::Failed,
+            ActiveState::Activating,
+            ActiveState::Deactivating,
+            ActiveState::Maintenance,
+            ActiveState::Refreshing,
+            ActiveState::Unknown,
+        ] {
+            self.container.remove_css_class(class.name());
+        }
+        self.container.add_css_class(unit.active_state.name());
+
+        self.description.set_label(&unit.description);
+
+        let state = if unit.load_state == "loaded" {
+            format!("{} ({})", unit.active_state.name(), unit.sub_state)
+        } else {
+            unit.load_state.clone()
+        };
+        self.state.set_label(&state);
+
+        let running = matches!(
+            unit.active_state,
+            ActiveState::Active | ActiveState::Activating | ActiveState::Reloading
+        );
+
+        self.start.set_sensitive(!running);
+        self.stop.set_sensitive(running);
+        self.restart.set_sensitive(unit.load_state == "loaded");
+    }
+}
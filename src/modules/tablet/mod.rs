@@ -0,0 +1,243 @@
+mod sensor;
+mod switch;
+
+use self::sensor::Orientation;
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::config::{CommonConfig, LayoutConfig};
+use crate::gtk_helpers::IronbarLabelExt;
+use crate::modules::{Module, ModuleInfo, ModuleParts, WidgetContext};
+use crate::panic_guard;
+use crate::script::Script;
+use crate::{module_impl, spawn, spawn_blocking};
+use color_eyre::Result;
+use gtk::Label;
+use gtk::prelude::*;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct TabletModule {
+    /// The format string to use for the label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{icon}`
+    #[serde(skip)]
+    format: String,
+
+    /// The icon to use for the `{icon}` token while in tablet mode.
+    ///
+    /// **Default**: `󰓶`
+    icon_tablet: String,
+
+    /// The icon to use for the `{icon}` token while in laptop mode.
+    ///
+    /// **Default**: `󰌢`
+    icon_laptop: String,
+
+    /// A script to run when switching between tablet and laptop mode.
+    /// The new mode (`tablet` or `laptop`) is passed as the first argument.
+    ///
+    /// **Default**: `null`
+    on_mode_change: Option<String>,
+
+    /// A script to run when the screen orientation changes.
+    /// The new orientation (`normal`, `bottom-up`, `left-up` or `right-up`)
+    /// is passed as the first argument.
+    ///
+    /// **Default**: `null`
+    on_rotate: Option<String>,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for TabletModule {
+    fn default() -> Self {
+        Self {
+            format: "{icon}".to_string(),
+            icon_tablet: "󰓶".to_string(),
+            icon_laptop: "󰌢".to_string(),
+            on_mode_change: None,
+            on_rotate: None,
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+impl TabletModule {
+    fn format(&self, tablet_mode: bool, orientation: Orientation) -> String {
+        let (icon, mode) = if tablet_mode {
+            (&self.icon_tablet, "tablet")
+        } else {
+            (&self.icon_laptop, "laptop")
+        };
+
+        self.format
+            .replace("{icon}", icon)
+            .replace("{mode}", mode)
+            .replace("{orientation}", orientation.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum TabletUpdate {
+    /// Whether the device is in tablet mode.
+    TabletMode(bool),
+    Orientation(Orientation),
+}
+
+impl Module<gtk::Widget> for TabletModule {
+    type SendMessage = TabletUpdate;
+    type ReceiveMessage = ();
+
+    module_impl!("tablet");
+
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
+    fn coalesce_key(update: &Self::SendMessage) -> Option<String> {
+        // each update is the full state of its kind
+        let key = match update {
+            TabletUpdate::TabletMode(_) => "mode",
+            TabletUpdate::Orientation(_) => "orientation",
+        };
+
+        Some(key.to_string())
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let (event_tx, mut event_rx) = mpsc::channel(8);
+
+        {
+            let event_tx = event_tx.clone();
+            spawn_blocking(move || {
+                let device = match switch::find_device() {
+                    Ok(Some(device)) => device,
+                    Ok(None) => {
+                        debug!("No tablet mode switch found");
+                        return;
+                    }
+                    Err(err) => {
+                        panic_guard::report_error(
+                            &err.wrap_err("Failed to find tablet mode switch"),
+                        );
+                        return;
+                    }
+                };
+
+                if let Err(err) = switch::watch(&device, &event_tx) {
+                    panic_guard::report_error(&err.wrap_err("Failed to read tablet mode switch"));
+                }
+            });
+        }
+
+        spawn(async move {
+            match sensor::watch(&event_tx).await {
+                Ok(true) => {}
+                Ok(false) => debug!("No accelerometer found"),
+                Err(err) => warn!("Failed to watch screen orientation: {err:?}"),
+            }
+        });
+
+        let tx = context.tx.clone();
+        let on_mode_change = self.on_mode_change.as_deref().map(Script::from);
+        let on_rotate = self.on_rotate.as_deref().map(Script::from);
+
+        spawn(async move {
+            let mut tablet_mode = None;
+            let mut orientation = None;
+
+            while let Some(update) = event_rx.recv().await {
+                // scripts only run on changes,
+                // not for the state when the bar starts.
+                match update {
+                    TabletUpdate::TabletMode(value) => {
+                        if tablet_mode.replace(value).is_some_and(|prev| prev != value)
+                            && let Some(script) = &on_mode_change
+                        {
+                            let mode = if value { "tablet" } else { "laptop" };
+                            script.run_as_oneshot(Some(&[mode.to_string()]));
+                        }
+                    }
+                    TabletUpdate::Orientation(value) => {
+                        if orientation.replace(value).is_some_and(|prev| prev != value)
+                            && value != Orientation::Undefined
+                            && let Some(script) = &on_rotate
+                        {
+                            script.run_as_oneshot(Some(&[value.name().to_string()]));
+                        }
+                    }
+                }
+
+                tx.send_update(update).await;
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<gtk::Widget>> {
+        let label = Label::builder()
+            .use_markup(true)
+            .justify(self.layout.justify.into())
+            .build();
+
+        let widget = label.rotated(self.layout.angle());
+
+        let mut tablet_mode = false;
+        let mut orientation = Orientation::Undefined;
+
+        label.set_label_escaped(&self.format(tablet_mode, orientation));
+        widget.add_css_class("laptop-mode");
+
+        context
+            .subscribe()
+            .recv_glib((&label, &widget), move |(label, widget), update| {
+                match update {
+                    TabletUpdate::TabletMode(value) => {
+                        tablet_mode = value;
+
+                        if value {
+                            widget.remove_css_class("laptop-mode");
+                            widget.add_css_class("tablet-mode");
+                        } else {
+                            widget.remove_css_class("tablet-mode");
+                            widget.add_css_class("laptop-mode");
+                        }
+                    }
+                    TabletUpdate::Orientation(value) => {
+                        widget.remove_css_class(orientation.name());
+                        orientation = value;
+                        widget.add_css_class(value.name());
+                    }
+                }
+
+                label.set_label_escaped(&self.format(tablet_mode, orientation));
+            });
+
+        Ok(ModuleParts {
+            widget,
+            popup: None,
+        })
+    }
+}
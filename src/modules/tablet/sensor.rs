@@ -0,0 +1,91 @@
+use super::TabletUpdate;
+use color_eyre::{Report, Result};
+use futures_lite::StreamExt;
+use std::str::FromStr;
+use tokio::sync::mpsc;
+use zbus::proxy;
+
+#[proxy(
+    interface = "net.hadess.SensorProxy",
+    default_service = "net.hadess.SensorProxy",
+    default_path = "/net/hadess/SensorProxy"
+)]
+trait SensorProxy {
+    /// Starts receiving accelerometer updates.
+    /// Updates stop when the connection is closed.
+    fn claim_accelerometer(&self) -> zbus::Result<()>;
+
+    #[zbus(property)]
+    fn has_accelerometer(&self) -> zbus::Result<bool>;
+
+    #[zbus(property)]
+    fn accelerometer_orientation(&self) -> zbus::Result<String>;
+}
+
+/// The orientation of the screen, as reported by `iio-sensor-proxy`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    #[default]
+    Undefined,
+    Normal,
+    BottomUp,
+    LeftUp,
+    RightUp,
+}
+
+impl Orientation {
+    pub const ALL: [Self; 5] = [
+        Self::Undefined,
+        Self::Normal,
+        Self::BottomUp,
+        Self::LeftUp,
+        Self::RightUp,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Undefined => "undefined",
+            Self::Normal => "normal",
+            Self::BottomUp => "bottom-up",
+            Self::LeftUp => "left-up",
+            Self::RightUp => "right-up",
+        }
+    }
+}
+
+impl FromStr for Orientation {
+    type Err = Report;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|orientation| orientation.name() == s)
+            .ok_or_else(|| Report::msg(format!("Unknown orientation: {s}")))
+    }
+}
+
+/// Sends the current orientation,
+/// and again each time it changes.
+///
+/// Returns `Ok(false)` if the device has no accelerometer.
+pub async fn watch(tx: &mpsc::Sender<TabletUpdate>) -> Result<bool> {
+    let connection = crate::clients::dbus::system().await?;
+    let proxy = SensorProxyProxy::new(&connection).await?;
+
+    if !proxy.has_accelerometer().await? {
+        return Ok(false);
+    }
+
+    proxy.claim_accelerometer().await?;
+
+    let mut changes = proxy.receive_accelerometer_orientation_changed().await;
+    let orientation = proxy.accelerometer_orientation().await?.parse()?;
+    tx.send(TabletUpdate::Orientation(orientation)).await?;
+
+    while let Some(change) = changes.next().await {
+        let orientation = change.get().await?.parse()?;
+        tx.send(TabletUpdate::Orientation(orientation)).await?;
+    }
+
+    Ok(true)
+}
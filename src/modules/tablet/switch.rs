@@ -0,0 +1,85 @@
+use super::TabletUpdate;
+use color_eyre::Result;
+use evdev_rs::enums::{EV_SW, EventCode};
+use evdev_rs::{Device, DeviceWrapper, ReadFlag, ReadStatus};
+use std::fs::{self, File};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+use tracing::{debug, trace};
+
+const TABLET_MODE: EventCode = EventCode::EV_SW(EV_SW::SW_TABLET_MODE);
+
+/// Finds the input device which reports the tablet mode switch.
+///
+/// This is usually an ACPI or platform device such as `Intel HID switches`.
+/// Reading it requires the user to be in the `input` group.
+pub fn find_device() -> Result<Option<Device>> {
+    let mut paths = fs::read_dir("/dev/input")?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("event"))
+        })
+        .collect::<Vec<PathBuf>>();
+
+    paths.sort();
+
+    for path in paths {
+        // opened without `O_NONBLOCK` so reading events can block
+        let Ok(file) = File::open(&path) else {
+            trace!("Unable to open {}", path.display());
+            continue;
+        };
+
+        let device = Device::new_from_file(file)?;
+        if device.has(TABLET_MODE) {
+            debug!(
+                "Found tablet mode switch: {} ({})",
+                device.name().unwrap_or_default(),
+                path.display()
+            );
+
+            return Ok(Some(device));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Gets whether the device is currently in tablet mode.
+fn is_tablet_mode(device: &Device) -> bool {
+    device
+        .event_value(&TABLET_MODE)
+        .is_some_and(|value| value > 0)
+}
+
+/// Sends the current state of the switch,
+/// then blocks reading events from the device,
+/// sending the state again each time it may have changed.
+pub fn watch(device: &Device, tx: &mpsc::Sender<TabletUpdate>) -> Result<()> {
+    let send = |tablet_mode| tx.blocking_send(TabletUpdate::TabletMode(tablet_mode));
+    send(is_tablet_mode(device))?;
+
+    loop {
+        let (status, event) = device.next_event(ReadFlag::NORMAL | ReadFlag::BLOCKING)?;
+
+        match status {
+            ReadStatus::Success if event.event_code == TABLET_MODE => send(event.value > 0)?,
+            ReadStatus::Success => {}
+            // events were dropped, so the state must be re-synced
+            ReadStatus::Sync => {
+                loop {
+                    match device.next_event(ReadFlag::SYNC) {
+                        Ok(_) => {}
+                        Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                        Err(err) => return Err(err.into()),
+                    }
+                }
+
+                send(is_tablet_mode(device))?;
+            }
+        }
+    }
+}
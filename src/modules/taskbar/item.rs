@@ -0,0 +1,284 @@
+use super::{TaskbarUpdate, UiEvent};
+use crate::channels::AsyncSenderExt;
+use crate::clients::compositor::WindowMatch;
+use crate::clients::wayland::ToplevelInfo;
+use crate::config::{BarPosition, TruncateMode};
+use crate::gtk_helpers::{IronbarGlibExt, IronbarGtkExt, IronbarLabelExt, MouseButton};
+use crate::modules::{ModuleUpdateEvent, PopupButton};
+use crate::{image, rc_mut};
+use gtk::gdk::{ContentProvider, DragAction};
+use gtk::prelude::*;
+use gtk::{
+    Align, Button, ContentFit, DragSource, DropTarget, EventControllerMotion, Justification, Label,
+    Orientation, Picture, Widget, WidgetPaintable,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+use tokio::sync::mpsc::Sender;
+
+#[derive(Clone, Copy)]
+pub struct AppearanceOptions {
+    pub show_names: bool,
+    pub show_icons: bool,
+    pub icon_size: i32,
+    pub truncate: TruncateMode,
+    pub orientation: Orientation,
+    pub justify: Justification,
+    /// Whether to open the popup for items with a single window.
+    pub always_popup: bool,
+}
+
+/// A bar button representing an item,
+/// which is either a single window or a group of windows.
+pub struct TaskbarButton {
+    pub button: Button,
+    label: Label,
+    windows: Rc<RefCell<Vec<ToplevelInfo>>>,
+    show_names: bool,
+}
+
+impl TaskbarButton {
+    pub fn new(
+        key: String,
+        window: &ToplevelInfo,
+        appearance: AppearanceOptions,
+        image_provider: &image::Provider,
+        bar_position: BarPosition,
+        tx: &Sender<ModuleUpdateEvent<TaskbarUpdate>>,
+        controller_tx: &Sender<UiEvent>,
+    ) -> Self {
+        let button = Button::new();
+        button.ensure_popup_id();
+        button.add_css_class("item");
+
+        let container = gtk::Box::new(appearance.orientation, 0);
+        container.set_halign(Align::Center);
+        container.set_valign(Align::Center);
+        button.set_child(Some(&container));
+
+        if appearance.show_icons {
+            let picture = Picture::builder()
+                .content_fit(ContentFit::ScaleDown)
+                .build();
+            container.append(&picture);
+
+            let input = if window.app_id.is_empty() {
+                window.title.clone()
+            } else {
+                window.app_id.clone()
+            };
+
+            let image_provider = image_provider.clone();
+            glib::spawn_future_local(async move {
+                image_provider
+                    .load_into_picture_silent(&input, appearance.icon_size, true, &picture)
+                    .await;
+            });
+        }
+
+        let label = Label::new(None);
+        label.truncate(appearance.truncate);
+        label.set_justify(appearance.justify);
+        label.set_visible(appearance.show_names);
+        container.append(&label);
+
+        let windows = rc_mut!(vec![]);
+
+        {
+            let windows = windows.clone();
+            let tx = controller_tx.clone();
+
+            button.connect_pressed(MouseButton::Primary, move || {
+                if let Some(id) = next_window(&windows.borrow()) {
+                    tx.send_spawn(UiEvent::Focus(id));
+                }
+            });
+        }
+
+        {
+            let windows = windows.clone();
+            let tx = controller_tx.clone();
+
+            button.connect_pressed(MouseButton::Middle, move || {
+                if let Some(id) = current_window(&windows.borrow()) {
+                    tx.send_spawn(UiEvent::Close(id));
+                }
+            });
+        }
+
+        let event_controller = EventControllerMotion::new();
+
+        {
+            let windows = windows.clone();
+            let tx = tx.clone();
+            let button = button.clone();
+
+            event_controller.connect_enter(move |_, _, _| {
+                if appearance.always_popup || windows.borrow().len() > 1 {
+                    tx.send_update_spawn(TaskbarUpdate::Hover(key.clone()));
+                    tx.send_spawn(ModuleUpdateEvent::OpenPopup(button.popup_id()));
+                } else {
+                    tx.send_spawn(ModuleUpdateEvent::ClosePopup);
+                }
+            });
+        }
+
+        {
+            let tx = tx.clone();
+            let button = button.clone();
+
+            event_controller.connect_leave(move |controller| {
+                const THRESHOLD: f64 = 5.0;
+
+                let Some(ev) = controller.current_event() else {
+                    return;
+                };
+
+                let (x, y) = ev.position().unwrap_or_default();
+
+                // the popup stays open when moving towards it
+                let close = match bar_position {
+                    BarPosition::Top => y + THRESHOLD < f64::from(button.height()),
+                    BarPosition::Bottom => y > THRESHOLD,
+                    BarPosition::Left => x + THRESHOLD < f64::from(button.width()),
+                    BarPosition::Right => x > THRESHOLD,
+                };
+
+                if close {
+                    tx.send_spawn(ModuleUpdateEvent::ClosePopup);
+                }
+            });
+        }
+
+        button.add_controller(event_controller);
+
+        connect_reorder(&button, windows.clone());
+
+        Self {
+            button,
+            label,
+            windows,
+            show_names: appearance.show_names,
+        }
+    }
+
+    /// Updates the button to represent the item's current windows.
+    pub fn update(&self, windows: Vec<ToplevelInfo>) {
+        if self.show_names
+            && let Some(window) = windows
+                .iter()
+                .find(|window| window.focused)
+                .or_else(|| windows.first())
+        {
+            self.label.set_label(&window.title);
+        }
+
+        if windows.iter().any(|window| window.focused) {
+            self.button.add_css_class("focused");
+        } else {
+            self.button.remove_css_class("focused");
+        }
+
+        if windows.len() > 1 {
+            self.button.add_css_class("grouped");
+        } else {
+            self.button.remove_css_class("grouped");
+        }
+
+        *self.windows.borrow_mut() = windows;
+    }
+}
+
+/// Gets the focused window, or the first window if none are focused.
+fn current_window(windows: &[ToplevelInfo]) -> Option<usize> {
+    windows
+        .iter()
+        .find(|window| window.focused)
+        .or_else(|| windows.first())
+        .map(|window| window.id)
+}
+
+/// Gets the window to focus when clicked,
+/// cycling through windows of the group on each click.
+fn next_window(windows: &[ToplevelInfo]) -> Option<usize> {
+    let next = windows
+        .iter()
+        .position(|window| window.focused)
+        .map_or(0, |index| (index + 1) % windows.len());
+
+    windows.get(next).map(|window| window.id)
+}
+
+/// Allows the button to be dragged onto another button
+/// in the same container to move it to that position,
+/// or onto a workspace button to move its windows to that workspace.
+fn connect_reorder(button: &Button, windows: Rc<RefCell<Vec<ToplevelInfo>>>) {
+    let drag_source = DragSource::builder().actions(DragAction::MOVE).build();
+
+    {
+        let button = button.clone();
+        drag_source.connect_prepare(move |_, _, _| {
+            let windows = windows.borrow();
+
+            if let Some(window) = windows.first() {
+                // groups move all windows of the application
+                let title = (windows.len() == 1).then(|| window.title.clone());
+
+                button.set_tag(
+                    WindowMatch::TAG,
+                    WindowMatch {
+                        app_id: window.app_id.clone(),
+                        title,
+                    },
+                );
+            }
+
+            Some(ContentProvider::for_value(&button.to_value()))
+        });
+    }
+
+    drag_source.connect_drag_begin(|source, _| {
+        if let Some(widget) = source.widget() {
+            source.set_icon(Some(&WidgetPaintable::new(Some(&widget))), 0, 0);
+        }
+    });
+
+    button.add_controller(drag_source);
+
+    let drop_target = DropTarget::new(Button::static_type(), DragAction::MOVE);
+
+    {
+        let target = button.clone();
+        drop_target.connect_drop(move |_, value, _, _| {
+            let Ok(source) = value.get::<Button>() else {
+                return false;
+            };
+
+            let Some(container) = target.parent().and_downcast::<gtk::Box>() else {
+                return false;
+            };
+
+            if source == target
+                || source.parent().as_ref() != Some(container.upcast_ref::<Widget>())
+            {
+                return false;
+            }
+
+            let target_widget = target.upcast_ref::<Widget>();
+            let forwards =
+                std::iter::successors(source.next_sibling(), |widget| widget.next_sibling())
+                    .any(|sibling| &sibling == target_widget);
+
+            // moving forwards places the item after the target, and backwards before it
+            if forwards {
+                container.reorder_child_after(&source, Some(&target));
+            } else {
+                container.reorder_child_after(&source, target.prev_sibling().as_ref());
+            }
+
+            true
+        });
+    }
+
+    button.add_controller(drop_target);
+}
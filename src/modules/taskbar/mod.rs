@@ -0,0 +1,403 @@
+mod item;
+mod popup;
+
+use self::item::{AppearanceOptions, TaskbarButton};
+use self::popup::Popup;
+use super::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModulePopupParts, ModuleUpdateEvent, PopupButton,
+    WidgetContext,
+};
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::clients::wayland::{self, Thumbnail, ToplevelEvent, ToplevelInfo};
+use crate::config::{CommonConfig, EllipsizeMode, LayoutConfig, TruncateMode, default};
+use crate::{module_impl, rc_mut, spawn};
+use color_eyre::Result;
+use gtk::prelude::*;
+use indexmap::IndexMap;
+use serde::Deserialize;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+use tokio::time::sleep;
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct TaskbarModule {
+    /// Whether to show windows from all monitors,
+    /// rather than only those on the bar's monitor.
+    ///
+    /// **Default**: `false`
+    all_monitors: bool,
+
+    /// Whether to group windows of the same application into a single item.
+    ///
+    /// **Default**: `true`
+    grouping: bool,
+
+    /// Whether to show window titles on the bar.
+    ///
+    /// **Default**: `true`
+    show_names: bool,
+
+    /// Whether to show application icons on the bar.
+    ///
+    /// **Default**: `true`
+    show_icons: bool,
+
+    /// Size in pixels to render icon at (image icons only).
+    ///
+    /// **Default**: `32`
+    icon_size: i32,
+
+    /// Whether to show live thumbnails of windows in the hover popup.
+    ///
+    /// This requires compositor support for the `ext-image-copy-capture` protocol.
+    /// Where unsupported, only window titles are shown.
+    ///
+    /// **Default**: `true`
+    thumbnails: bool,
+
+    /// The maximum width or height of thumbnails, in pixels.
+    ///
+    /// **Default**: `200`
+    thumbnail_size: i32,
+
+    /// The number of milliseconds between refreshing thumbnails
+    /// while the popup is open.
+    ///
+    /// **Default**: `1000`
+    thumbnail_interval: u64,
+
+    // -- common --
+    /// Truncate window titles on the bar if they get too long.
+    /// See [truncate options](module-level-options#truncate-mode).
+    ///
+    /// **Default**: `{ mode = "end" max_length = 25 }`
+    truncate: TruncateMode,
+
+    /// Truncate window titles in the popup if they get too long.
+    /// See [truncate options](module-level-options#truncate-mode).
+    ///
+    /// **Default**: `{ mode = "middle" max_length = 25 }`
+    truncate_popup: TruncateMode,
+
+    /// See [layout options](module-level-options#layout)
+    #[serde(default, flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for TaskbarModule {
+    fn default() -> Self {
+        Self {
+            all_monitors: false,
+            grouping: true,
+            show_names: true,
+            show_icons: true,
+            icon_size: default::IconSize::Normal as i32,
+            thumbnails: true,
+            thumbnail_size: 200,
+            thumbnail_interval: 1000,
+            truncate: TruncateMode::Length {
+                mode: EllipsizeMode::End,
+                length: None,
+                max_length: Some(25),
+            },
+            truncate_popup: TruncateMode::Length {
+                mode: EllipsizeMode::Middle,
+                length: None,
+                max_length: Some(25),
+            },
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum TaskbarUpdate {
+    /// The full list of windows to show, in the order they were opened.
+    Windows(Vec<ToplevelInfo>),
+    /// A new thumbnail for the window with the ID.
+    Thumbnail(usize, Arc<Thumbnail>),
+    /// Declares the item with the key has been hovered over.
+    Hover(String),
+}
+
+#[derive(Debug)]
+pub enum UiEvent {
+    Focus(usize),
+    Close(usize),
+    /// Starts capturing thumbnails of the windows with the IDs,
+    /// replacing any previous set.
+    Preview(Vec<usize>),
+    /// Stops capturing thumbnails.
+    StopPreview,
+}
+
+/// Gets the key of the item a window belongs to.
+fn item_key(window: &ToplevelInfo, grouping: bool) -> String {
+    if grouping {
+        window.app_id.clone()
+    } else {
+        window.id.to_string()
+    }
+}
+
+/// Groups windows into items, keeping the order each item first appears.
+fn group_windows(
+    windows: Vec<ToplevelInfo>,
+    grouping: bool,
+) -> IndexMap<String, Vec<ToplevelInfo>> {
+    let mut items = IndexMap::<String, Vec<ToplevelInfo>>::new();
+
+    for window in windows {
+        items
+            .entry(item_key(&window, grouping))
+            .or_default()
+            .push(window);
+    }
+
+    items
+}
+
+impl Module<gtk::Box> for TaskbarModule {
+    type SendMessage = TaskbarUpdate;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("taskbar");
+
+    fn coalesce_key(update: &Self::SendMessage) -> Option<String> {
+        match update {
+            // each update is the full state
+            TaskbarUpdate::Windows(_) => Some("windows".to_string()),
+            TaskbarUpdate::Thumbnail(id, _) => Some(format!("thumbnail-{id}")),
+            TaskbarUpdate::Hover(_) => None,
+        }
+    }
+
+    fn spawn_controller(
+        &self,
+        info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let wl = context.client::<wayland::Client>();
+
+        {
+            let wl = wl.clone();
+            let tx = context.tx.clone();
+            let all_monitors = self.all_monitors;
+            let output_name = info.output_name.to_string();
+
+            spawn(async move {
+                let visible = |windows: &IndexMap<usize, ToplevelInfo>| {
+                    windows
+                        .values()
+                        .filter(|window| {
+                            all_monitors || window.output.as_deref() == Some(output_name.as_str())
+                        })
+                        .cloned()
+                        .collect::<Vec<_>>()
+                };
+
+                let all_windows = || {
+                    wl.toplevel_info_all()
+                        .into_iter()
+                        .map(|window| (window.id, window))
+                        .collect::<IndexMap<_, _>>()
+                };
+
+                let mut toplevels = wl.subscribe_toplevels();
+                let mut windows = all_windows();
+
+                tx.send_update(TaskbarUpdate::Windows(visible(&windows)))
+                    .await;
+
+                loop {
+                    match toplevels.recv_tracked("taskbar").await {
+                        Ok(ToplevelEvent::New(window) | ToplevelEvent::Update(window)) => {
+                            windows.insert(window.id, window);
+                        }
+                        Ok(ToplevelEvent::Remove(window)) => {
+                            windows.shift_remove(&window.id);
+                        }
+                        Err(RecvError::Lagged(_)) => windows = all_windows(),
+                        Err(RecvError::Closed) => break,
+                    }
+
+                    tx.send_update(TaskbarUpdate::Windows(visible(&windows)))
+                        .await;
+                }
+            });
+        }
+
+        let tx = context.tx.clone();
+        let interval = Duration::from_millis(self.thumbnail_interval);
+
+        spawn(async move {
+            let mut preview = None;
+
+            while let Some(event) = rx.recv().await {
+                match event {
+                    UiEvent::Focus(id) => {
+                        tx.send_expect(ModuleUpdateEvent::ClosePopup).await;
+                        wl.toplevel_focus(id);
+                    }
+                    UiEvent::Close(id) => wl.toplevel_close(id),
+                    UiEvent::Preview(ids) => {
+                        if let Some(task) = preview.take() {
+                            task.abort();
+                        }
+
+                        let wl = wl.clone();
+                        let tx = tx.clone();
+
+                        preview = Some(spawn(async move {
+                            loop {
+                                for &id in &ids {
+                                    if let Some(thumbnail) = wl.toplevel_capture(id).await {
+                                        tx.send_update(TaskbarUpdate::Thumbnail(
+                                            id,
+                                            Arc::new(thumbnail),
+                                        ))
+                                        .await;
+                                    }
+                                }
+
+                                sleep(interval).await;
+                            }
+                        }));
+                    }
+                    UiEvent::StopPreview => {
+                        if let Some(task) = preview.take() {
+                            task.abort();
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<gtk::Box>> {
+        let container = gtk::Box::new(self.layout.orientation(info), 0);
+
+        let image_provider = context.image_provider();
+        let buttons = rc_mut!(IndexMap::<String, TaskbarButton>::new());
+
+        {
+            let appearance = AppearanceOptions {
+                show_names: self.show_names,
+                show_icons: self.show_icons,
+                icon_size: self.icon_size,
+                truncate: self.truncate,
+                orientation: self.layout.orientation(info),
+                justify: self.layout.justify.into(),
+                always_popup: self.thumbnails,
+            };
+
+            let grouping = self.grouping;
+            let bar_position = info.bar_position;
+            let buttons = buttons.clone();
+
+            context.subscribe().recv_glib(
+                (&container, &context.controller_tx, &context.tx),
+                move |(container, controller_tx, tx), update| {
+                    let TaskbarUpdate::Windows(windows) = update else {
+                        return;
+                    };
+
+                    let items = group_windows(windows, grouping);
+                    let mut buttons = buttons.borrow_mut();
+
+                    buttons.retain(|key, button| {
+                        let keep = items.contains_key(key);
+                        if !keep {
+                            container.remove(&button.button);
+                        }
+                        keep
+                    });
+
+                    // existing items keep their position,
+                    // which may have been changed by dragging
+                    for (key, windows) in items {
+                        let button = buttons.entry(key.clone()).or_insert_with(|| {
+                            let button = TaskbarButton::new(
+                                key,
+                                &windows[0],
+                                appearance,
+                                &image_provider,
+                                bar_position,
+                                tx,
+                                controller_tx,
+                            );
+
+                            container.append(&button.button);
+                            button
+                        });
+
+                        button.update(windows);
+                    }
+                },
+            );
+        }
+
+        let popup = self
+            .into_popup(context, info)
+            .into_popup_parts_with_finder(Rc::new(move |id| {
+                buttons
+                    .borrow()
+                    .values()
+                    .find(|button| button.button.popup_id() == id)
+                    .map(|button| button.button.clone())
+            }))
+            .map(ModulePopupParts::disable_autohide);
+
+        Ok(ModuleParts {
+            widget: container,
+            popup,
+        })
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box> {
+        let popup = Popup::new(
+            self.grouping,
+            self.thumbnails.then_some(self.thumbnail_size),
+            self.truncate_popup,
+            &context.controller_tx,
+        );
+
+        {
+            let tx = context.tx.clone();
+            popup.connect_leave(move || tx.send_spawn(ModuleUpdateEvent::ClosePopup));
+        }
+
+        let container = popup.container.clone();
+
+        context
+            .subscribe()
+            .recv_glib((), move |(), update| match update {
+                TaskbarUpdate::Windows(windows) => popup.set_windows(windows),
+                TaskbarUpdate::Thumbnail(id, thumbnail) => popup.set_thumbnail(id, &thumbnail),
+                TaskbarUpdate::Hover(key) => popup.show_item(&key),
+            });
+
+        Some(container)
+    }
+}
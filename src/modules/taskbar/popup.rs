@@ -0,0 +1,254 @@
+use super::{UiEvent, item_key};
+use crate::channels::AsyncSenderExt;
+use crate::clients::wayland::{Thumbnail, ToplevelInfo};
+use crate::config::TruncateMode;
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt, MouseButton};
+use crate::rc_mut;
+use gtk::gdk::{MemoryFormat, MemoryTexture};
+use gtk::prelude::*;
+use gtk::{Align, Button, ContentFit, EventControllerMotion, Label, Orientation, Picture};
+use indexmap::IndexMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tokio::sync::mpsc::Sender;
+
+/// The popup listing the windows of the hovered item.
+#[derive(Clone)]
+pub struct Popup {
+    pub container: gtk::Box,
+    state: Rc<RefCell<State>>,
+
+    grouping: bool,
+    thumbnail_size: Option<i32>,
+    truncate: TruncateMode,
+    tx: Sender<UiEvent>,
+}
+
+#[derive(Default)]
+struct State {
+    windows: Vec<ToplevelInfo>,
+    /// The key of the item currently shown.
+    key: Option<String>,
+    rows: IndexMap<usize, Row>,
+}
+
+impl Popup {
+    pub fn new(
+        grouping: bool,
+        thumbnail_size: Option<i32>,
+        truncate: TruncateMode,
+        tx: &Sender<UiEvent>,
+    ) -> Self {
+        let container = gtk::Box::new(Orientation::Horizontal, 10);
+
+        let popup = Self {
+            container,
+            state: rc_mut!(State::default()),
+            grouping,
+            thumbnail_size,
+            truncate,
+            tx: tx.clone(),
+        };
+
+        // thumbnails are only captured while they can be seen
+        if thumbnail_size.is_some() {
+            {
+                let popup2 = popup.clone();
+                popup.container.connect_map(move |_| popup2.send_preview());
+            }
+
+            let tx = tx.clone();
+            popup
+                .container
+                .connect_unmap(move |_| tx.send_spawn(UiEvent::StopPreview));
+        }
+
+        popup
+    }
+
+    /// Calls `f` when the mouse leaves the popup.
+    pub fn connect_leave<F: Fn() + 'static>(&self, f: F) {
+        let event_controller = EventControllerMotion::new();
+        event_controller.connect_leave(move |_| f());
+        self.container.add_controller(event_controller);
+    }
+
+    pub fn set_windows(&self, windows: Vec<ToplevelInfo>) {
+        self.state.borrow_mut().windows = windows;
+        self.refresh();
+    }
+
+    /// Shows the windows of the item with the key.
+    pub fn show_item(&self, key: &str) {
+        self.state.borrow_mut().key = Some(key.to_string());
+        self.refresh();
+    }
+
+    pub fn set_thumbnail(&self, id: usize, thumbnail: &Thumbnail) {
+        if let Some(row) = self.state.borrow().rows.get(&id) {
+            row.set_thumbnail(thumbnail);
+        }
+    }
+
+    /// Updates the rows to match the windows of the current item.
+    fn refresh(&self) {
+        let mut state = self.state.borrow_mut();
+
+        let Some(key) = state.key.clone() else {
+            return;
+        };
+
+        let windows = state
+            .windows
+            .iter()
+            .filter(|window| item_key(window, self.grouping) == key)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let changed = !state
+            .rows
+            .keys()
+            .eq(windows.iter().map(|window| &window.id));
+
+        if changed {
+            for child in self.container.children() {
+                self.container.remove(&child);
+            }
+
+            // existing rows are kept to avoid losing their thumbnails
+            let mut rows = std::mem::take(&mut state.rows);
+
+            for window in &windows {
+                let row = rows.shift_remove(&window.id).unwrap_or_else(|| {
+                    Row::new(window.id, self.thumbnail_size, self.truncate, &self.tx)
+                });
+
+                self.container.append(&row.button);
+                state.rows.insert(window.id, row);
+            }
+        }
+
+        for window in &windows {
+            if let Some(row) = state.rows.get(&window.id) {
+                row.update(window);
+            }
+        }
+
+        drop(state);
+
+        if changed && self.container.is_mapped() {
+            self.send_preview();
+        }
+    }
+
+    fn send_preview(&self) {
+        if self.thumbnail_size.is_some() {
+            let ids = self.state.borrow().rows.keys().copied().collect();
+            self.tx.send_spawn(UiEvent::Preview(ids));
+        }
+    }
+}
+
+/// A button for a single window,
+/// showing its title and thumbnail.
+struct Row {
+    button: Button,
+    title: Label,
+    thumbnail: Option<Picture>,
+    size: i32,
+}
+
+impl Row {
+    fn new(
+        id: usize,
+        thumbnail_size: Option<i32>,
+        truncate: TruncateMode,
+        tx: &Sender<UiEvent>,
+    ) -> Self {
+        let button = Button::new();
+        button.add_css_class("popup-item");
+
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+        button.set_child(Some(&container));
+
+        let title = Label::new(None);
+        title.add_css_class("title");
+        title.set_halign(Align::Start);
+        title.truncate(truncate);
+        container.append(&title);
+
+        let thumbnail = thumbnail_size.map(|size| {
+            let picture = Picture::builder()
+                .content_fit(ContentFit::Contain)
+                .width_request(size)
+                .height_request(size / 2)
+                .build();
+            picture.add_css_class("thumbnail");
+            container.append(&picture);
+            picture
+        });
+
+        {
+            let tx = tx.clone();
+            button.connect_clicked(move |_| tx.send_spawn(UiEvent::Focus(id)));
+        }
+
+        {
+            let tx = tx.clone();
+            button.connect_pressed(MouseButton::Middle, move || {
+                tx.send_spawn(UiEvent::Close(id));
+            });
+        }
+
+        Self {
+            button,
+            title,
+            thumbnail,
+            size: thumbnail_size.unwrap_or_default(),
+        }
+    }
+
+    fn update(&self, window: &ToplevelInfo) {
+        self.title.set_label(&window.title);
+
+        if window.focused {
+            self.button.add_css_class("focused");
+        } else {
+            self.button.remove_css_class("focused");
+        }
+    }
+
+    fn set_thumbnail(&self, thumbnail: &Thumbnail) {
+        let Some(picture) = &self.thumbnail else {
+            return;
+        };
+
+        let (Ok(width), Ok(height)) = (
+            i32::try_from(thumbnail.width),
+            i32::try_from(thumbnail.height),
+        ) else {
+            return;
+        };
+
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        let texture = MemoryTexture::new(
+            width,
+            height,
+            MemoryFormat::B8g8r8a8Premultiplied,
+            &glib::Bytes::from(&thumbnail.bytes[..]),
+            thumbnail.stride as usize,
+        );
+
+        // scale to fit within the configured size, keeping the aspect ratio
+        let scale = f64::from(self.size) / f64::from(width.max(height));
+        picture.set_size_request(
+            (f64::from(width) * scale) as i32,
+            (f64::from(height) * scale) as i32,
+        );
+
+        picture.set_paintable(Some(&texture));
+    }
+}
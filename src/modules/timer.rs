@@ -0,0 +1,587 @@
+use crate::channels::AsyncSenderExt;
+use crate::config::{CommonConfig, LayoutConfig};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt, MouseButton};
+use crate::modules::{Module, ModuleInfo, ModuleParts, WidgetContext};
+use crate::{module_impl, spawn};
+use color_eyre::Result;
+use glib::Propagation;
+use gtk::prelude::*;
+use gtk::{Button, EventControllerScroll, EventControllerScrollFlags, Label};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::{Instant, MissedTickBehavior};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct TimerModule {
+    /// The length of a pomodoro work session, in minutes.
+    ///
+    /// **Default**: `25`
+    work_duration: u64,
+
+    /// The length of a short break between work sessions, in minutes.
+    ///
+    /// **Default**: `5`
+    break_duration: u64,
+
+    /// The length of a long break, in minutes.
+    ///
+    /// **Default**: `15`
+    long_break_duration: u64,
+
+    /// The number of work sessions before each long break.
+    /// Set to `0` to never take a long break.
+    ///
+    /// **Default**: `4`
+    long_break_interval: u32,
+
+    /// Whether to start the next pomodoro phase as soon as the last one ends.
+    /// If disabled, the timer is paused at the start of the next phase.
+    ///
+    /// **Default**: `true`
+    auto_start: bool,
+
+    /// The number of minutes to change a countdown by
+    /// for each step when scrolling over the widget.
+    ///
+    /// **Default**: `5`
+    scroll_step: u64,
+
+    /// The format string to use for the widget button label while the timer is running.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{icon} {remaining}`
+    #[serde(skip)]
+    format: String,
+
+    /// The format string to use for the widget button label while the timer is stopped.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `{icon}`
+    format_idle: String,
+
+    /// The icons to use for the `{icon}` token.
+    icons: Icons,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for TimerModule {
+    fn default() -> Self {
+        Self {
+            work_duration: 25,
+            break_duration: 5,
+            long_break_duration: 15,
+            long_break_interval: 4,
+            auto_start: true,
+            scroll_step: 5,
+            format: "{icon} {remaining}".to_string(),
+            format_idle: "{icon}".to_string(),
+            icons: Icons::default(),
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+struct Icons {
+    /// The icon to show while the timer is stopped.
+    ///
+    /// **Default**: `󰔛`
+    idle: String,
+
+    /// The icon to show during a work session.
+    ///
+    /// **Default**: `󰔟`
+    work: String,
+
+    /// The icon to show during a short or long break.
+    ///
+    /// **Default**: `󰅶`
+    #[serde(rename = "break")]
+    break_: String,
+
+    /// The icon to show during a countdown.
+    ///
+    /// **Default**: `󱎫`
+    countdown: String,
+}
+
+impl Default for Icons {
+    fn default() -> Self {
+        Self {
+            idle: "󰔛".to_string(),
+            work: "󰔟".to_string(),
+            break_: "󰅶".to_string(),
+            countdown: "󱎫".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Work,
+    Break,
+    LongBreak,
+    Countdown,
+}
+
+impl Phase {
+    /// The name used for the phase in IPC events.
+    #[cfg_attr(not(feature = "ipc"), allow(dead_code))]
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Work => "work",
+            Self::Break => "break",
+            Self::LongBreak => "long_break",
+            Self::Countdown => "countdown",
+        }
+    }
+
+    /// The name shown for the `{phase}` token.
+    const fn label(self) -> &'static str {
+        match self {
+            Self::Work => "Work",
+            Self::Break => "Break",
+            Self::LongBreak => "Long break",
+            Self::Countdown => "Countdown",
+        }
+    }
+
+    /// The widget's CSS class during the phase.
+    const fn class(self) -> &'static str {
+        match self {
+            Self::Work => "working",
+            Self::Break | Self::LongBreak => "break",
+            Self::Countdown => "countdown",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TimerState {
+    /// The running phase, or `None` when stopped.
+    phase: Option<Phase>,
+    /// Seconds left in the phase.
+    /// When stopped, the length of the timer started by clicking.
+    remaining: u64,
+    paused: bool,
+    /// The work session in the current set, starting from 1.
+    session: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum UiEvent {
+    /// Starts, pauses or resumes the timer.
+    Toggle,
+    /// Stops the timer and clears the countdown.
+    Stop,
+    /// Ends the current phase early.
+    Skip,
+    /// Changes the countdown length by a number of scroll steps.
+    Adjust(i32),
+}
+
+/// Pomodoro cycle and countdown state, owned by the controller.
+#[derive(Debug)]
+struct Timer {
+    work: Duration,
+    short_break: Duration,
+    long_break: Duration,
+    long_break_interval: u32,
+    auto_start: bool,
+    step: Duration,
+
+    phase: Option<Phase>,
+    /// When running, the instant the phase ends.
+    deadline: Option<Instant>,
+    /// When paused, the time left in the phase.
+    remaining: Duration,
+    /// Whether the phase has been started,
+    /// as phases are paused at the start when `auto_start` is disabled.
+    started: bool,
+    /// The number of work sessions completed in the current set.
+    sessions: u32,
+    /// The countdown length set by scrolling.
+    countdown: Duration,
+}
+
+impl Timer {
+    fn new(module: &TimerModule) -> Self {
+        let minutes = |minutes| Duration::from_secs(minutes * 60);
+
+        Self {
+            work: minutes(module.work_duration),
+            short_break: minutes(module.break_duration),
+            long_break: minutes(module.long_break_duration),
+            long_break_interval: module.long_break_interval,
+            auto_start: module.auto_start,
+            step: minutes(module.scroll_step),
+            phase: None,
+            deadline: None,
+            remaining: Duration::ZERO,
+            started: false,
+            sessions: 0,
+            countdown: Duration::ZERO,
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.deadline.is_some()
+    }
+
+    fn state(&self) -> TimerState {
+        let remaining = match (self.phase, self.deadline) {
+            (_, Some(deadline)) => deadline.saturating_duration_since(Instant::now()),
+            (Some(_), None) => self.remaining,
+            (None, None) if self.countdown.is_zero() => self.work,
+            (None, None) => self.countdown,
+        };
+
+        TimerState {
+            phase: self.phase,
+            remaining: ceil_secs(remaining),
+            paused: self.phase.is_some() && self.deadline.is_none(),
+            session: self.session(),
+        }
+    }
+
+    fn session(&self) -> u32 {
+        match self.long_break_interval {
+            0 => self.sessions + 1,
+            interval => self.sessions % interval + 1,
+        }
+    }
+
+    const fn duration(&self, phase: Phase) -> Duration {
+        match phase {
+            Phase::Work => self.work,
+            Phase::Break => self.short_break,
+            Phase::LongBreak => self.long_break,
+            Phase::Countdown => self.countdown,
+        }
+    }
+
+    fn handle(&mut self, event: UiEvent) {
+        match event {
+            UiEvent::Toggle => self.toggle(),
+            UiEvent::Stop => self.stop(),
+            UiEvent::Skip => self.advance(),
+            UiEvent::Adjust(steps) => self.adjust(steps),
+        }
+    }
+
+    fn toggle(&mut self) {
+        match (self.phase, self.deadline) {
+            (None, _) => {
+                let phase = if self.countdown.is_zero() {
+                    Phase::Work
+                } else {
+                    Phase::Countdown
+                };
+
+                self.start(phase);
+            }
+            (Some(phase), Some(deadline)) => {
+                // whole seconds keep the display ticking over evenly once resumed
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                self.remaining = Duration::from_secs(ceil_secs(remaining));
+                self.deadline = None;
+
+                self.publish("pause", phase);
+            }
+            (Some(phase), None) => {
+                self.deadline = Some(Instant::now() + self.remaining);
+
+                if self.started {
+                    self.publish("resume", phase);
+                } else {
+                    self.started = true;
+                    self.publish("start", phase);
+                }
+            }
+        }
+    }
+
+    fn start(&mut self, phase: Phase) {
+        let duration = self.duration(phase);
+
+        self.phase = Some(phase);
+        self.remaining = duration;
+        self.deadline = Some(Instant::now() + duration);
+        self.started = true;
+
+        self.publish("start", phase);
+    }
+
+    fn stop(&mut self) {
+        if let Some(phase) = self.phase {
+            self.publish("stop", phase);
+        }
+
+        self.phase = None;
+        self.deadline = None;
+        self.started = false;
+        self.sessions = 0;
+        self.countdown = Duration::ZERO;
+    }
+
+    /// Ends the current phase if its time is up.
+    fn tick(&mut self) {
+        if self
+            .deadline
+            .is_some_and(|deadline| deadline <= Instant::now())
+        {
+            self.advance();
+        }
+    }
+
+    /// Ends the current phase and moves on to the next.
+    fn advance(&mut self) {
+        let Some(phase) = self.phase else {
+            return;
+        };
+
+        if self.started {
+            self.publish("end", phase);
+        }
+
+        match phase {
+            Phase::Work => {
+                self.sessions += 1;
+
+                if self.long_break_interval > 0 && self.sessions % self.long_break_interval == 0 {
+                    self.start_next(Phase::LongBreak);
+                } else {
+                    self.start_next(Phase::Break);
+                }
+            }
+            Phase::Break | Phase::LongBreak => self.start_next(Phase::Work),
+            Phase::Countdown => {
+                // the countdown length is kept, so it can be started again
+                self.phase = None;
+                self.deadline = None;
+            }
+        }
+    }
+
+    /// Starts a pomodoro phase which follows another,
+    /// or waits at its start if `auto_start` is disabled.
+    fn start_next(&mut self, phase: Phase) {
+        if self.auto_start {
+            self.start(phase);
+        } else {
+            self.phase = Some(phase);
+            self.remaining = self.duration(phase);
+            self.deadline = None;
+            self.started = false;
+        }
+    }
+
+    fn adjust(&mut self, steps: i32) {
+        let change = self.step * steps.unsigned_abs();
+
+        let apply = |duration: Duration| {
+            if steps > 0 {
+                duration + change
+            } else {
+                duration.saturating_sub(change)
+            }
+        };
+
+        match self.phase {
+            None => {
+                // round to whole steps, so scrolling always lands on neat values
+                let countdown = apply(self.countdown).as_secs();
+                let step = self.step.as_secs().max(1);
+                self.countdown = Duration::from_secs(countdown / step * step);
+            }
+            Some(Phase::Countdown) => match self.deadline {
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    self.deadline = Some(Instant::now() + apply(remaining));
+                }
+                None => self.remaining = apply(self.remaining),
+            },
+            // pomodoro phases have fixed lengths
+            Some(_) => {}
+        }
+    }
+
+    /// Publishes an event for scripts listening over IPC.
+    #[cfg(feature = "ipc")]
+    fn publish(&self, event: &str, phase: Phase) {
+        crate::ipc::IpcEvent::new("timer", event)
+            .with("phase", phase.name())
+            .with("duration", self.duration(phase).as_secs())
+            .with("session", self.session())
+            .publish();
+    }
+
+    #[cfg(not(feature = "ipc"))]
+    fn publish(&self, _event: &str, _phase: Phase) {}
+}
+
+/// Gets the whole seconds in the duration, rounded up,
+/// so the last second shows as `00:01` rather than `00:00`.
+fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+/// Formats seconds as `MM:SS`, or `HH:MM:SS` when over an hour.
+fn format_remaining(secs: u64) -> String {
+    let (h, m, s) = (secs / 3600, secs % 3600 / 60, secs % 60);
+
+    if h > 0 {
+        format!("{h:02}:{m:02}:{s:02}")
+    } else {
+        format!("{m:02}:{s:02}")
+    }
+}
+
+impl Module<Button> for TimerModule {
+    type SendMessage = TimerState;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("timer");
+
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
+    fn coalesce_key(_update: &Self::SendMessage) -> Option<String> {
+        // each update is the full state
+        Some(String::new())
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let tx = context.tx.clone();
+        let mut timer = Timer::new(self);
+
+        spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(1));
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+            tx.send_update(timer.state()).await;
+
+            loop {
+                tokio::select! {
+                    event = rx.recv() => {
+                        let Some(event) = event else {
+                            break;
+                        };
+
+                        timer.handle(event);
+
+                        // keep ticks in step with the remaining seconds
+                        interval.reset();
+                    }
+                    _ = interval.tick(), if timer.is_running() => timer.tick(),
+                }
+
+                tx.send_update(timer.state()).await;
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let label = Label::builder()
+            .use_markup(true)
+            .justify(self.layout.justify.into())
+            .build();
+
+        let button = Button::new();
+        button.set_child(Some(&label.rotated(self.layout.angle())));
+        button.set_accessible_label("Timer");
+
+        let tx = context.controller_tx.clone();
+
+        for (mouse_button, event) in [
+            (MouseButton::Primary, UiEvent::Toggle),
+            (MouseButton::Secondary, UiEvent::Stop),
+            (MouseButton::Middle, UiEvent::Skip),
+        ] {
+            let tx = tx.clone();
+            button.connect_pressed(mouse_button, move || tx.send_spawn(event));
+        }
+
+        let controller = EventControllerScroll::new(EventControllerScrollFlags::VERTICAL);
+        controller.connect_scroll(move |_, _dx, dy| {
+            let steps = if dy > 0.0 { -1 } else { 1 };
+            tx.send_spawn(UiEvent::Adjust(steps));
+
+            Propagation::Stop
+        });
+        button.add_controller(controller);
+
+        {
+            let button = button.clone();
+
+            context.subscribe().recv_glib((), move |(), state| {
+                for class in ["working", "break", "countdown", "paused"] {
+                    button.remove_css_class(class);
+                }
+
+                let remaining = format_remaining(state.remaining);
+
+                let (format, icon, phase) = match state.phase {
+                    Some(phase) => {
+                        button.add_css_class(phase.class());
+                        if state.paused {
+                            button.add_css_class("paused");
+                        }
+
+                        let icon = match phase {
+                            Phase::Work => &self.icons.work,
+                            Phase::Break | Phase::LongBreak => &self.icons.break_,
+                            Phase::Countdown => &self.icons.countdown,
+                        };
+
+                        (&self.format, icon, phase.label())
+                    }
+                    None => (&self.format_idle, &self.icons.idle, ""),
+                };
+
+                let text = format
+                    .replace("{icon}", icon)
+                    .replace("{remaining}", &remaining)
+                    .replace("{phase}", phase)
+                    .replace("{session}", &state.session.to_string());
+
+                label.set_label_escaped(&text);
+
+                let description = match state.phase {
+                    Some(phase) if state.paused => format!("{} paused, {remaining}", phase.label()),
+                    Some(phase) => format!("{}, {remaining}", phase.label()),
+                    None => "Stopped".to_string(),
+                };
+                button.set_accessible_description(&description);
+            });
+        }
+
+        Ok(ModuleParts::new(button, None))
+    }
+}
@@ -1,29 +1,92 @@
 use crate::gtk_helpers::IronbarPaintableExt;
 use crate::modules::tray::interface::TrayMenu;
 use color_eyre::{Report, Result};
-use gtk::gdk::Texture;
-use gtk::gdk_pixbuf::{Colorspace, Pixbuf};
-use gtk::prelude::WidgetExt;
+use gtk::gdk::{MemoryFormat, MemoryTexture};
 use gtk::{ContentFit, IconLookupFlags, IconTheme, Picture, TextDirection};
+use std::hash::{DefaultHasher, Hash, Hasher};
 use system_tray::item::IconPixmap;
 
+/// Gets a picture for the tray item's icon,
+/// or its attention icon while it needs attention.
+///
+/// `scale` is the scale factor of the surface the icon is shown on,
+/// so that it is loaded at the surface's resolution.
 pub fn get_image(
     item: &TrayMenu,
     size: u32,
+    scale: i32,
+    prefer_icons: bool,
+    icon_theme: &IconTheme,
+) -> Result<Picture> {
+    if item.shows_attention_icon() {
+        get_image_from(
+            item,
+            item.attention_icon_name.as_ref(),
+            item.attention_icon_pixmap.as_ref(),
+            size,
+            scale,
+            prefer_icons,
+            icon_theme,
+        )
+    } else {
+        get_image_from(
+            item,
+            item.icon_name.as_ref(),
+            item.icon_pixmap.as_ref(),
+            size,
+            scale,
+            prefer_icons,
+            icon_theme,
+        )
+    }
+}
+
+/// Gets a picture for the tray item's overlay icon,
+/// which is shown over the corner of its main icon.
+pub fn get_overlay_image(
+    item: &TrayMenu,
+    size: u32,
+    scale: i32,
+    prefer_icons: bool,
+    icon_theme: &IconTheme,
+) -> Result<Picture> {
+    get_image_from(
+        item,
+        item.overlay_icon_name.as_ref(),
+        item.overlay_icon_pixmap.as_ref(),
+        size,
+        scale,
+        prefer_icons,
+        icon_theme,
+    )
+}
+
+fn get_image_from(
+    item: &TrayMenu,
+    icon_name: Option<&String>,
+    icon_pixmap: Option<&Pixmaps>,
+    size: u32,
+    scale: i32,
     prefer_icons: bool,
     icon_theme: &IconTheme,
 ) -> Result<Picture> {
-    if !prefer_icons && item.icon_pixmap.is_some() {
-        get_image_from_pixmap(item.icon_pixmap.as_deref(), size)
+    if !prefer_icons && icon_pixmap.is_some() {
+        get_image_from_pixmap(icon_pixmap, size, scale)
     } else {
-        get_image_from_icon_name(item, size, icon_theme)
-            .or_else(|_| get_image_from_pixmap(item.icon_pixmap.as_deref(), size))
+        get_image_from_icon_name(item, icon_name, size, scale, icon_theme)
+            .or_else(|_| get_image_from_pixmap(icon_pixmap, size, scale))
     }
 }
 
 /// Attempts to get a GTK `Image` component
 /// for the status notifier item's icon.
-fn get_image_from_icon_name(item: &TrayMenu, size: u32, icon_theme: &IconTheme) -> Result<Picture> {
+fn get_image_from_icon_name(
+    item: &TrayMenu,
+    icon_name: Option<&String>,
+    size: u32,
+    scale: i32,
+    icon_theme: &IconTheme,
+) -> Result<Picture> {
     if let Some(path) = item.icon_theme_path.as_ref()
         && !path.as_os_str().is_empty()
         && !icon_theme.search_path().contains(path)
@@ -34,20 +97,16 @@ fn get_image_from_icon_name(item: &TrayMenu, size: u32, icon_theme: &IconTheme)
     let picture = Picture::new();
     picture.set_content_fit(ContentFit::ScaleDown);
 
-    let paintable = item
-        .icon_name
-        .as_ref()
-        .filter(|i| !i.is_empty())
-        .map(|icon_name| {
-            icon_theme.lookup_icon(
-                icon_name,
-                &[],
-                size as i32,
-                picture.scale_factor(),
-                TextDirection::None,
-                IconLookupFlags::empty(),
-            )
-        });
+    let paintable = icon_name.filter(|i| !i.is_empty()).map(|icon_name| {
+        icon_theme.lookup_icon(
+            icon_name,
+            &[],
+            size as i32,
+            scale,
+            TextDirection::None,
+            IconLookupFlags::empty(),
+        )
+    });
 
     if let Some(paintable) = paintable {
         picture.set_paintable(Some(&paintable));
@@ -57,59 +116,96 @@ fn get_image_from_icon_name(item: &TrayMenu, size: u32, icon_theme: &IconTheme)
     }
 }
 
+/// A single size of a tray item's icon pixmap.
+#[derive(Debug, Clone)]
+pub struct Pixmap {
+    width: i32,
+    height: i32,
+    /// Pixel data in ARGB32 format, in network byte order.
+    pixels: glib::Bytes,
+}
+
+/// The icon pixmaps sent by a tray item,
+/// with a hash of their contents so unchanged icons can be skipped.
+#[derive(Debug, Clone)]
+pub struct Pixmaps {
+    pixmaps: Vec<Pixmap>,
+    hash: u64,
+}
+
+impl Pixmaps {
+    /// Takes ownership of the pixmaps' pixel data,
+    /// so it can be handed to GDK without copying.
+    pub fn new(pixmaps: Vec<IconPixmap>) -> Self {
+        let mut hasher = DefaultHasher::new();
+
+        let pixmaps = pixmaps
+            .into_iter()
+            .map(|pixmap| {
+                (pixmap.width, pixmap.height, &pixmap.pixels).hash(&mut hasher);
+
+                Pixmap {
+                    width: pixmap.width,
+                    height: pixmap.height,
+                    pixels: glib::Bytes::from_owned(pixmap.pixels),
+                }
+            })
+            .collect();
+
+        Self {
+            pixmaps,
+            hash: hasher.finish(),
+        }
+    }
+
+    /// Gets the hash of the pixmaps' sizes and pixel data.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+}
+
 /// Attempts to get an image from the item pixmap.
 ///
 /// The pixmap is supplied in ARGB32 format,
 /// which has 8 bits per sample and a bit stride of `4*width`.
-/// The Pixbuf expects RGBA32 format, so some channel shuffling
-/// is required.
-fn get_image_from_pixmap(item: Option<&[IconPixmap]>, size: u32) -> Result<Picture> {
-    const BITS_PER_SAMPLE: i32 = 8;
-
+/// GDK can read this directly, so the pixel data is used as-is.
+fn get_image_from_pixmap(item: Option<&Pixmaps>, size: u32, scale: i32) -> Result<Picture> {
+    // pick the pixmap closest to the size in physical pixels,
+    // which is then scaled down to the logical size.
     let pixmap = item
-        .and_then(|pixmap| find_approx_size(pixmap, size))
+        .and_then(|pixmaps| find_approx_size(&pixmaps.pixmaps, size * scale.max(1) as u32))
         .ok_or_else(|| Report::msg("Failed to get pixmap from tray icon"))?;
 
-    if pixmap.width == 0 || pixmap.height == 0 {
+    if pixmap.width <= 0 || pixmap.height <= 0 {
         return Err(Report::msg("empty pixmap"));
     }
 
-    let mut pixels = pixmap.pixels.clone();
-
-    for i in (0..pixels.len()).step_by(4) {
-        let alpha = pixels[i];
-        pixels[i] = pixels[i + 1];
-        pixels[i + 1] = pixels[i + 2];
-        pixels[i + 2] = pixels[i + 3];
-        pixels[i + 3] = alpha;
+    let row_stride = pixmap.width as usize * 4;
+    if pixmap.pixels.len() < row_stride * pixmap.height as usize {
+        return Err(Report::msg("pixmap data is smaller than its size"));
     }
 
-    let row_stride = pixmap.width * 4;
-    let bytes = glib::Bytes::from(&pixels);
-
-    let pixbuf = Pixbuf::from_bytes(
-        &bytes,
-        Colorspace::Rgb,
-        true,
-        BITS_PER_SAMPLE,
+    let texture = MemoryTexture::new(
         pixmap.width,
         pixmap.height,
+        MemoryFormat::A8r8g8b8,
+        &pixmap.pixels,
         row_stride,
     );
 
-    let texture = Texture::for_pixbuf(&pixbuf).scale(size as f64, size as f64);
-
     let picture = Picture::new();
     picture.set_content_fit(ContentFit::ScaleDown);
+
+    let texture = texture.scale(size as f64, size as f64);
     picture.set_paintable(texture.as_ref());
 
     Ok(picture)
 }
 
-///  Finds the `IconPixmap`
+///  Finds the `Pixmap`
 ///  which is the smallest but bigger than wanted,
 ///  or the biggest of all if no bigger than wanted.
-fn find_approx_size(v: &[IconPixmap], size: u32) -> Option<&IconPixmap> {
+fn find_approx_size(v: &[Pixmap], size: u32) -> Option<&Pixmap> {
     let size = size as i32;
 
     if v.is_empty() {
@@ -135,20 +231,42 @@ fn find_approx_size(v: &[IconPixmap], size: u32) -> Option<&IconPixmap> {
 
 #[cfg(test)]
 mod tests {
+    #[test]
+    fn test_pixmap_hash() {
+        use super::{IconPixmap, Pixmaps};
+
+        let pixmap = |pixels: Vec<u8>| {
+            Pixmaps::new(vec![IconPixmap {
+                width: 1,
+                height: 1,
+                pixels,
+            }])
+        };
+
+        assert_eq!(
+            pixmap(vec![255, 0, 0, 0]).hash(),
+            pixmap(vec![255, 0, 0, 0]).hash()
+        );
+        assert_ne!(
+            pixmap(vec![255, 0, 0, 0]).hash(),
+            pixmap(vec![255, 0, 0, 1]).hash()
+        );
+    }
+
     #[test]
     fn test_find_approx_height() {
-        use super::{IconPixmap, find_approx_size};
+        use super::{Pixmap, find_approx_size};
 
         macro_rules! make_list {
             ($heights:expr) => {
                 $heights
                     .iter()
-                    .map(|width| IconPixmap {
+                    .map(|width| Pixmap {
                         width: *width,
                         height: 0,
-                        pixels: vec![],
+                        pixels: glib::Bytes::from_static(&[]),
                     })
-                    .collect::<Vec<IconPixmap>>()
+                    .collect::<Vec<Pixmap>>()
             };
         }
         macro_rules! assert_correct {
@@ -1,19 +1,25 @@
+use super::overflow::Overflow;
 use crate::channels::AsyncSenderExt;
 use crate::gtk_helpers::{IronbarGtkExt, MouseButton};
+use crate::modules::tray::icon::Pixmaps;
 use crate::modules::tray::{ReservedTrayAction, TrayClickAction, TrayClickHandlers, UiEvent};
 use crate::script::Script;
 use crate::spawn;
 use glib::{Bytes, VariantTy};
 use gtk::gdk::Texture;
 use gtk::gio::{Icon, Menu, MenuModel, SimpleAction, SimpleActionGroup};
+use gtk::{
+    Align, Button, EventControllerScroll, EventControllerScrollFlags, Label, Overlay, PopoverMenu,
+    PropagationPhase,
+};
 use gtk::{
     Box as GtkBox, Orientation, Picture, Shortcut, ShortcutAction, ShortcutController,
     ShortcutTrigger, prelude::*,
 };
-use gtk::{Button, Label, PopoverMenu};
 use std::path::PathBuf;
+use std::rc::Rc;
 use system_tray::client::ActivateRequest;
-use system_tray::item::{IconPixmap, Status, StatusNotifierItem, Tooltip};
+use system_tray::item::{Status, StatusNotifierItem, Tooltip};
 use system_tray::menu::ToggleState;
 use tokio::sync::mpsc;
 use tracing::{debug, error, trace};
@@ -23,16 +29,26 @@ pub(crate) struct TrayMenu {
     pub box_content: GtkBox,
     pub widget: Button,
     pub popover: PopoverMenu,
+    overlay: Overlay,
     image_widget: Option<Picture>,
+    overlay_image_widget: Option<Picture>,
     label_widget: Option<Label>,
+    status: Status,
     tx: mpsc::Sender<UiEvent>,
     path: Option<String>,
     address: String,
+    /// The item's identifier, used to match it against overflow patterns.
+    pub id: String,
+    overflow: Rc<Overflow>,
 
     pub title: Option<String>,
     pub icon_name: Option<String>,
     pub icon_theme_path: Option<PathBuf>,
-    pub icon_pixmap: Option<Vec<IconPixmap>>,
+    pub icon_pixmap: Option<Pixmaps>,
+    pub attention_icon_name: Option<String>,
+    pub attention_icon_pixmap: Option<Pixmaps>,
+    pub overlay_icon_name: Option<String>,
+    pub overlay_icon_pixmap: Option<Pixmaps>,
 }
 
 impl TrayMenu {
@@ -41,6 +57,7 @@ impl TrayMenu {
         item: StatusNotifierItem,
         tx: mpsc::Sender<UiEvent>,
         click_handlers: &TrayClickHandlers,
+        overflow: Rc<Overflow>,
     ) -> Self {
         let popover = PopoverMenu::builder().build(); // no `new` and we do not have a model yet
         let widget = Button::new();
@@ -145,18 +162,33 @@ impl TrayMenu {
             }
         };
 
-        // Set up left-click handler with optional double-click support
-        if click_handlers.on_click_left.is_actionable()
-            || click_handlers.on_click_left_double.is_actionable()
+        // items which only support a menu ask for it on left-click too,
+        // unless another action has been configured
+        let on_click_left = if item.item_is_menu
+            && has_menu
+            && click_handlers.on_click_left
+                == TrayClickAction::Reserved(ReservedTrayAction::Default)
         {
-            let on_single = make_handler(&click_handlers.on_click_left);
+            TrayClickAction::Reserved(ReservedTrayAction::Menu)
+        } else {
+            click_handlers.on_click_left.clone()
+        };
+
+        // Set up left-click handler with optional double-click support
+        if on_click_left.is_actionable() || click_handlers.on_click_left_double.is_actionable() {
+            let on_single = make_handler(&on_click_left);
             let on_double = if click_handlers.on_click_left_double.is_actionable() {
                 Some(make_handler(&click_handlers.on_click_left_double))
             } else {
                 None
             };
 
-            widget.connect_pressed_with_double_click(MouseButton::Primary, on_single, on_double);
+            widget.connect_pressed_with_double_click(
+                MouseButton::Primary,
+                PropagationPhase::Bubble,
+                on_single,
+                on_double,
+            );
         }
 
         // Set up right-click handler with optional double-click support
@@ -170,7 +202,12 @@ impl TrayMenu {
                 None
             };
 
-            widget.connect_pressed_with_double_click(MouseButton::Secondary, on_single, on_double);
+            widget.connect_pressed_with_double_click(
+                MouseButton::Secondary,
+                PropagationPhase::Bubble,
+                on_single,
+                on_double,
+            );
         }
 
         // Set up middle-click handler with optional double-click support
@@ -184,10 +221,39 @@ impl TrayMenu {
                 None
             };
 
-            widget.connect_pressed_with_double_click(MouseButton::Middle, on_single, on_double);
+            widget.connect_pressed_with_double_click(
+                MouseButton::Middle,
+                PropagationPhase::Bubble,
+                on_single,
+                on_double,
+            );
+        }
+
+        {
+            let scroll_controller = EventControllerScroll::new(
+                EventControllerScrollFlags::BOTH_AXES | EventControllerScrollFlags::DISCRETE,
+            );
+
+            let tx = tx.clone();
+            let address = address.to_owned();
+            scroll_controller.connect_scroll(move |_, dx, dy| {
+                for (delta, orientation) in [(dy, "vertical"), (dx, "horizontal")] {
+                    let delta = delta.round() as i32;
+                    if delta != 0 {
+                        tx.send_spawn(UiEvent::Scroll(address.clone(), delta, orientation));
+                    }
+                }
+
+                glib::Propagation::Stop
+            });
+
+            widget.add_controller(scroll_controller);
         }
 
-        widget.set_child(Some(&content));
+        let overlay = Overlay::new();
+        overlay.set_child(Some(&content));
+
+        widget.set_child(Some(&overlay));
         widget.add_css_class("item");
 
         popover.set_parent(&widget);
@@ -207,15 +273,24 @@ impl TrayMenu {
             box_content: content,
             widget,
             popover,
+            overlay,
             image_widget: None,
+            overlay_image_widget: None,
             label_widget: None,
+            status: item.status,
             tx,
             title: item.title,
             icon_name: item.icon_name,
             icon_theme_path: item.icon_theme_path.map(PathBuf::from),
-            icon_pixmap: item.icon_pixmap,
+            icon_pixmap: item.icon_pixmap.map(Pixmaps::new),
+            attention_icon_name: item.attention_icon_name,
+            attention_icon_pixmap: item.attention_icon_pixmap.map(Pixmaps::new),
+            overlay_icon_name: item.overlay_icon_name,
+            overlay_icon_pixmap: item.overlay_icon_pixmap.map(Pixmaps::new),
             path: None,
             address: address.to_owned(),
+            id: item_name,
+            overflow,
         }
     }
 
@@ -257,6 +332,41 @@ impl TrayMenu {
         }
 
         self.box_content.append(image);
+
+        if self.shows_attention_icon() {
+            self.widget.add_css_class("attention");
+        } else {
+            self.widget.remove_css_class("attention");
+        }
+    }
+
+    /// Updates the image shown over the corner of the main image,
+    /// or removes it.
+    pub fn set_overlay_image(&mut self, image: Option<&Picture>) {
+        if let Some(old) = self.overlay_image_widget.take() {
+            self.overlay.remove_overlay(&old);
+        }
+
+        if let Some(image) = image {
+            image.add_css_class("overlay-icon");
+            image.set_halign(Align::End);
+            image.set_valign(Align::End);
+            image.set_can_target(false);
+
+            self.overlay.add_overlay(image);
+            self.overlay_image_widget = Some(image.clone());
+        }
+    }
+
+    /// Whether the item needs attention
+    /// and has an icon to show while it does.
+    pub fn shows_attention_icon(&self) -> bool {
+        self.status == Status::NeedsAttention
+            && (self
+                .attention_icon_name
+                .as_ref()
+                .is_some_and(|name| !name.is_empty())
+                || self.attention_icon_pixmap.is_some())
     }
 
     pub fn label_widget(&self) -> Option<&Label> {
@@ -286,7 +396,8 @@ impl TrayMenu {
         }
     }
 
-    pub fn set_status(&self, status: Status) {
+    pub fn set_status(&mut self, status: Status) {
+        self.status = status;
         self.widget.set_visible(status != Status::Passive);
 
         if status == Status::NeedsAttention {
@@ -307,15 +418,47 @@ impl TrayMenu {
         let action_group = SimpleActionGroup::new();
         let shortcut_controller = ShortcutController::new();
 
-        let model: MenuModel = self
-            .as_menu(&tray_menu.submenus, &action_group, &shortcut_controller)
-            .into();
+        let menu = self.as_menu(&tray_menu.submenus, &action_group, &shortcut_controller);
+        menu.append_section(None, &self.overflow_section(&action_group));
+
+        let model: MenuModel = menu.into();
 
         self.popover.set_menu_model(Some(&model));
         self.widget.insert_action_group("menu", Some(&action_group));
         self.widget.add_controller(shortcut_controller);
     }
 
+    /// Creates the menu section with a toggle
+    /// to move the item between the bar and the overflow popover.
+    fn overflow_section(&self, action_group: &SimpleActionGroup) -> Menu {
+        let hidden = self.overflow.is_hidden(&self.id);
+        let action = SimpleAction::new_stateful("overflow", None, &hidden.to_variant());
+
+        let overflow = self.overflow.clone();
+        let id = self.id.clone();
+        let widget = self.widget.clone();
+
+        action.connect_activate(move |action, _| {
+            let hidden = !action
+                .state()
+                .and_then(|state| state.get::<bool>())
+                .unwrap_or_default();
+            action.set_state(&hidden.to_variant());
+
+            // wait for the menu to close before moving its button
+            let overflow = overflow.clone();
+            let id = id.clone();
+            let widget = widget.clone();
+            glib::idle_add_local_once(move || overflow.set_hidden(&id, &widget, hidden));
+        });
+
+        action_group.add_action(&action);
+
+        let section = Menu::new();
+        section.append(Some("Show in overflow"), Some("menu.overflow"));
+        section
+    }
+
     pub fn connect_item(
         &self,
         sub: &system_tray::menu::MenuItem,
@@ -1,5 +1,6 @@
 mod icon;
 mod interface;
+mod overflow;
 
 use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
 use crate::clients::tray;
@@ -9,13 +10,16 @@ use crate::{lock, module_impl, spawn};
 use color_eyre::{Report, Result};
 use gtk::prelude::*;
 use gtk::{IconTheme, Orientation};
+use icon::Pixmaps;
 use interface::TrayMenu;
+use overflow::Overflow;
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::rc::Rc;
 use system_tray::client::Event;
 use system_tray::client::{ActivateRequest, UpdateEvent};
 use tokio::sync::mpsc;
-use tracing::{debug, error, trace, warn};
+use tracing::{debug, error, trace};
 
 /// Icon configuration for tray items
 struct IconConfig {
@@ -164,6 +168,28 @@ pub struct TrayModule {
     #[serde(flatten)]
     click_handlers: TrayClickHandlers,
 
+    /// Glob patterns matching the IDs of items to always show on the bar,
+    /// even if they also match `hidden`.
+    /// `*` matches any sequence of characters and `?` matches any one character.
+    ///
+    /// **Default**: `[]`
+    pinned: Vec<String>,
+
+    /// Glob patterns matching the IDs of items to collapse
+    /// behind the overflow button at the end of the tray.
+    /// `*` matches any sequence of characters and `?` matches any one character.
+    ///
+    /// Items can also be moved to and from the overflow using their right-click menu.
+    /// This is saved in Ironbar's data directory and takes priority over the patterns.
+    ///
+    /// **Default**: `[]`
+    hidden: Vec<String>,
+
+    /// Icon to show on the overflow button.
+    ///
+    /// **Default**: `󰅀`
+    overflow_icon: String,
+
     /// See [common options](module-level-options#common-options).
     #[serde(flatten)]
     pub common: Option<CommonConfig>,
@@ -176,6 +202,9 @@ impl Default for TrayModule {
             icon_size: default::IconSize::Tiny as u32,
             direction: None,
             click_handlers: TrayClickHandlers::default(),
+            pinned: vec![],
+            hidden: vec![],
+            overflow_icon: "󰅀".to_string(),
             common: Some(CommonConfig::default()),
         }
     }
@@ -184,6 +213,9 @@ impl Default for TrayModule {
 pub enum UiEvent {
     Menu(bool),
     Activate(ActivateRequest),
+    /// Scrolls the item at the address by the delta,
+    /// in the `vertical` or `horizontal` orientation.
+    Scroll(String, i32, &'static str),
 }
 
 impl Module<gtk::Box> for TrayModule {
@@ -257,6 +289,11 @@ impl Module<gtk::Box> for TrayModule {
                         }
                         trace!("end activation");
                     }
+                    UiEvent::Scroll(address, delta, orientation) => {
+                        if let Err(err) = client.scroll(&address, delta, orientation).await {
+                            error!("{err:?}");
+                        }
+                    }
                 }
             }
 
@@ -279,12 +316,20 @@ impl Module<gtk::Box> for TrayModule {
         // as the latter has issues on Sway with menus focus-stealing from the bar.
         let container = gtk::Box::new(orientation, 0);
 
+        let overflow = Rc::new(Overflow::new(
+            &container,
+            orientation,
+            &self,
+            context.image_provider(),
+            context.controller_tx.clone(),
+        ));
+
         {
             let container = container.clone();
             let mut menus = HashMap::new();
             let activated_channel = context.controller_tx.clone();
 
-            let provider = context.ironbar.image_provider();
+            let provider = context.image_provider();
             let icon_config = IconConfig {
                 theme: provider.icon_theme().clone(),
                 size: self.icon_size,
@@ -302,6 +347,7 @@ impl Module<gtk::Box> for TrayModule {
                     &icon_config,
                     &activated_channel,
                     &click_handlers,
+                    &overflow,
                 );
             });
         };
@@ -334,20 +380,26 @@ fn on_update(
     icon_config: &IconConfig,
     activated_channel: &mpsc::Sender<UiEvent>,
     click_handlers: &TrayClickHandlers,
+    overflow: &Rc<Overflow>,
 ) {
     match update {
         Event::Add(address, item) => {
             debug!("Received new tray item at '{address}': {item:?}");
 
-            let mut menu_item =
-                TrayMenu::new(&address, *item, activated_channel.clone(), click_handlers);
+            let mut menu_item = TrayMenu::new(
+                &address,
+                *item,
+                activated_channel.clone(),
+                click_handlers,
+                overflow.clone(),
+            );
 
-            let x: Option<&gtk::Widget> = None;
-            container.insert_child_after(&menu_item.widget, x);
+            overflow.add(&menu_item.id, &menu_item.widget);
 
             if let Ok(image) = icon::get_image(
                 &menu_item,
                 icon_config.size,
+                container.scale_factor(),
                 icon_config.prefer_theme,
                 &icon_config.theme,
             ) {
@@ -357,6 +409,8 @@ fn on_update(
                 menu_item.set_label(&label);
             }
 
+            reload_overlay_image(&mut menu_item, icon_config, container.scale_factor());
+
             menus.insert(address.into(), menu_item);
         }
         Event::Update(address, update) => {
@@ -369,39 +423,43 @@ fn on_update(
             };
 
             match update {
-                UpdateEvent::AttentionIcon(_icon) => {
-                    warn!("received unimplemented NewAttentionIcon event");
+                UpdateEvent::AttentionIcon(icon_name) => {
+                    menu_item.attention_icon_name = icon_name;
+
+                    if menu_item.shows_attention_icon() {
+                        reload_image(menu_item, icon_config, container.scale_factor());
+                    }
                 }
                 UpdateEvent::Icon {
                     icon_name,
                     icon_pixmap,
                 } => {
+                    let icon_pixmap = icon_pixmap.map(Pixmaps::new);
+
+                    // some apps re-send the same icon many times a second,
+                    // so only reload it if the pixels have changed.
                     let name_changed = icon_name.as_ref() != menu_item.icon_name();
-                    let pixmap_changed = icon_pixmap != menu_item.icon_pixmap;
+                    let pixmap_changed = icon_pixmap.as_ref().map(Pixmaps::hash)
+                        != menu_item.icon_pixmap.as_ref().map(Pixmaps::hash);
 
                     if name_changed || pixmap_changed {
                         menu_item.icon_pixmap = icon_pixmap;
                         menu_item.set_icon_name(icon_name);
 
-                        match icon::get_image(
-                            menu_item,
-                            icon_config.size,
-                            icon_config.prefer_theme,
-                            &icon_config.theme,
-                        ) {
-                            Ok(image) => menu_item.set_image(&image),
-                            Err(e) => {
-                                error!("error loading icon: {e}");
-                                menu_item.show_label();
-                            }
-                        }
+                        reload_image(menu_item, icon_config, container.scale_factor());
                     }
                 }
-                UpdateEvent::OverlayIcon(_icon) => {
-                    warn!("received unimplemented NewOverlayIcon event");
+                UpdateEvent::OverlayIcon(icon_name) => {
+                    menu_item.overlay_icon_name = icon_name;
+                    reload_overlay_image(menu_item, icon_config, container.scale_factor());
                 }
                 UpdateEvent::Status(status) => {
+                    let showed_attention_icon = menu_item.shows_attention_icon();
                     menu_item.set_status(status);
+
+                    if menu_item.shows_attention_icon() != showed_attention_icon {
+                        reload_image(menu_item, icon_config, container.scale_factor());
+                    }
                 }
                 UpdateEvent::Title(title) => {
                     if let Some(label_widget) = menu_item.label_widget() {
@@ -426,8 +484,41 @@ fn on_update(
             debug!("Removing tray item at '{address}'");
 
             if let Some(menu) = menus.get(address.as_str()) {
-                container.remove(&menu.widget);
+                overflow.remove(&menu.widget);
             }
         }
     }
 }
+
+/// Reloads the item's image,
+/// showing its label instead if the image cannot be loaded.
+fn reload_image(menu_item: &mut TrayMenu, icon_config: &IconConfig, scale: i32) {
+    match icon::get_image(
+        menu_item,
+        icon_config.size,
+        scale,
+        icon_config.prefer_theme,
+        &icon_config.theme,
+    ) {
+        Ok(image) => menu_item.set_image(&image),
+        Err(e) => {
+            error!("error loading icon: {e}");
+            menu_item.show_label();
+        }
+    }
+}
+
+/// Reloads the item's overlay image at half the icon size,
+/// removing it if the item has none.
+fn reload_overlay_image(menu_item: &mut TrayMenu, icon_config: &IconConfig, scale: i32) {
+    let image = icon::get_overlay_image(
+        menu_item,
+        icon_config.size / 2,
+        scale,
+        icon_config.prefer_theme,
+        &icon_config.theme,
+    )
+    .ok();
+
+    menu_item.set_overlay_image(image.as_ref());
+}
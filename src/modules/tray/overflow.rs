@@ -0,0 +1,281 @@
+use super::{TrayModule, UiEvent};
+use crate::channels::AsyncSenderExt;
+use crate::image::{self, IconButton};
+use crate::spawn;
+use gtk::prelude::*;
+use gtk::{Button, Orientation, Popover, Widget};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use tokio::sync::mpsc;
+use tracing::error;
+
+/// Decides which tray items are collapsed behind the overflow button.
+///
+/// Items set from the bar take priority over the configured patterns,
+/// and are saved in Ironbar's data directory.
+#[derive(Debug, Default)]
+struct Placements {
+    pinned: Vec<String>,
+    hidden: Vec<String>,
+    /// Item IDs set from the bar, and whether they are hidden.
+    overrides: HashMap<String, bool>,
+}
+
+impl Placements {
+    fn is_hidden(&self, id: &str) -> bool {
+        if let Some(&hidden) = self.overrides.get(id) {
+            return hidden;
+        }
+
+        let matches =
+            |patterns: &[String]| patterns.iter().any(|pattern| glob_matches(pattern, id));
+        !matches(&self.pinned) && matches(&self.hidden)
+    }
+}
+
+/// The button at the end of the tray which shows the hidden items in a popover.
+pub struct Overflow {
+    container: gtk::Box,
+    button: Button,
+    items: gtk::Box,
+    placements: RefCell<Placements>,
+}
+
+impl Overflow {
+    /// Creates the overflow button, appending it to the tray container.
+    pub fn new(
+        container: &gtk::Box,
+        orientation: Orientation,
+        config: &TrayModule,
+        image_provider: image::Provider,
+        tx: mpsc::Sender<UiEvent>,
+    ) -> Self {
+        let button = IconButton::new(
+            &config.overflow_icon,
+            config.icon_size as i32,
+            image_provider,
+        );
+        button.add_css_class("overflow");
+        button.set_visible(false);
+
+        let items = gtk::Box::new(orientation, 0);
+        items.add_css_class("overflow-items");
+
+        let popover = Popover::builder().child(&items).build();
+        popover.add_css_class("overflow-popover");
+        popover.set_parent(&*button);
+
+        {
+            let popover = popover.clone();
+            button.connect_clicked(move |_| popover.popup());
+        }
+
+        {
+            let tx = tx.clone();
+            popover.connect_show(move |_| tx.send_spawn(UiEvent::Menu(true)));
+        }
+
+        popover.connect_hide(move |_| tx.send_spawn(UiEvent::Menu(false)));
+
+        container.append(&*button);
+
+        Self {
+            container: container.clone(),
+            button: (*button).clone(),
+            items,
+            placements: RefCell::new(Placements {
+                pinned: config.pinned.clone(),
+                hidden: config.hidden.clone(),
+                overrides: load(),
+            }),
+        }
+    }
+
+    /// Adds the item widget to the start of the bar,
+    /// or of the overflow popover if it is hidden.
+    pub fn add(&self, id: &str, widget: &impl IsA<Widget>) {
+        if self.is_hidden(id) {
+            self.items.prepend(widget);
+        } else {
+            self.container.prepend(widget);
+        }
+
+        self.update_button();
+    }
+
+    /// Removes the item widget from the bar or overflow popover.
+    pub fn remove(&self, widget: &impl IsA<Widget>) {
+        if widget.parent().as_ref() == Some(self.items.upcast_ref()) {
+            self.items.remove(widget);
+        } else {
+            self.container.remove(widget);
+        }
+
+        self.update_button();
+    }
+
+    pub fn is_hidden(&self, id: &str) -> bool {
+        self.placements.borrow().is_hidden(id)
+    }
+
+    /// Moves the item widget to the bar or overflow popover,
+    /// saving the choice.
+    pub fn set_hidden(&self, id: &str, widget: &impl IsA<Widget>, hidden: bool) {
+        self.remove(widget);
+
+        let overrides = {
+            let mut placements = self.placements.borrow_mut();
+            placements.overrides.insert(id.to_string(), hidden);
+            placements.overrides.clone()
+        };
+
+        self.add(id, widget);
+
+        spawn(async move { save(&overrides).await });
+    }
+
+    fn update_button(&self) {
+        self.button.set_visible(self.items.first_child().is_some());
+    }
+}
+
+/// Gets the path of the file storing items moved from the bar,
+/// with one item per line in the format `<id>\t<bar|overflow>`.
+fn path() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("ironbar").join("tray_overflow"))
+}
+
+fn load() -> HashMap<String, bool> {
+    let Some(path) = path() else {
+        return HashMap::new();
+    };
+
+    match std::fs::read_to_string(path) {
+        Ok(contents) => parse(&contents),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => HashMap::new(),
+        Err(err) => {
+            error!("Failed to load tray overflow items: {err:?}");
+            HashMap::new()
+        }
+    }
+}
+
+async fn save(overrides: &HashMap<String, bool>) {
+    let Some(path) = path() else {
+        error!("Missing XDG data dir, tray overflow items will not be saved");
+        return;
+    };
+
+    let res = async {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, serialize(overrides)).await
+    };
+
+    if let Err(err) = res.await {
+        error!("Failed to save tray overflow items: {err:?}");
+    }
+}
+
+fn parse(contents: &str) -> HashMap<String, bool> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (id, placement) = line.split_once('\t')?;
+            let hidden = match placement {
+                "bar" => false,
+                "overflow" => true,
+                _ => return None,
+            };
+
+            Some((id.to_string(), hidden))
+        })
+        .collect()
+}
+
+fn serialize(overrides: &HashMap<String, bool>) -> String {
+    overrides
+        .iter()
+        .map(|(id, &hidden)| format!("{id}\t{}\n", if hidden { "overflow" } else { "bar" }))
+        .collect()
+}
+
+/// Checks whether the value matches the glob pattern,
+/// where `*` matches any sequence of characters and `?` matches any one character.
+/// Matching ignores case.
+fn glob_matches(pattern: &str, value: &str) -> bool {
+    let pattern = pattern.to_lowercase().chars().collect::<Vec<_>>();
+    let value = value.to_lowercase().chars().collect::<Vec<_>>();
+
+    let (mut p, mut v) = (0, 0);
+    // the position after the last `*`, and the value position it was tried from
+    let mut backtrack = None;
+
+    while v < value.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                p += 1;
+                backtrack = Some((p, v));
+            }
+            Some(&c) if c == '?' || c == value[v] => {
+                p += 1;
+                v += 1;
+            }
+            _ => match backtrack {
+                // let the last `*` match one more character
+                Some((star_p, star_v)) => {
+                    p = star_p;
+                    v = star_v + 1;
+                    backtrack = Some((star_p, star_v + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob() {
+        assert!(glob_matches("nm-applet", "nm-applet"));
+        assert!(glob_matches("*", "nm-applet"));
+        assert!(glob_matches("nm-*", "nm-applet"));
+        assert!(glob_matches("*applet", "nm-applet"));
+        assert!(glob_matches("n?-a*t", "NM-Applet"));
+        assert!(!glob_matches("nm-", "nm-applet"));
+        assert!(!glob_matches("*x*", "nm-applet"));
+    }
+
+    #[test]
+    fn pinned_takes_priority() {
+        let mut placements = Placements {
+            pinned: vec!["nm-*".to_string()],
+            hidden: vec!["*".to_string()],
+            overrides: HashMap::new(),
+        };
+
+        assert!(!placements.is_hidden("nm-applet"));
+        assert!(placements.is_hidden("steam"));
+
+        placements.overrides.insert("nm-applet".to_string(), true);
+        assert!(placements.is_hidden("nm-applet"));
+    }
+
+    #[test]
+    fn parse_round_trip() {
+        let overrides = HashMap::from([
+            ("nm-applet".to_string(), true),
+            ("steam".to_string(), false),
+        ]);
+
+        assert_eq!(parse(&serialize(&overrides)), overrides);
+    }
+}
@@ -0,0 +1,186 @@
+use color_eyre::{Report, Result};
+use serde::Deserialize;
+use std::env;
+use std::fmt::{Display, Formatter};
+use tokio::process::Command;
+use tracing::debug;
+
+/// A package manager which can be checked for updates.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum Backend {
+    /// Arch Linux, using `checkupdates` from `pacman-contrib`.
+    Pacman,
+    /// Debian and Ubuntu, using `apt list --upgradable`.
+    ///
+    /// This does not refresh the package lists,
+    /// so relies on them being refreshed separately.
+    Apt,
+    /// Fedora, using `dnf check-update`.
+    Dnf,
+    /// Packages installed with `nix-env`, using `nix-env --upgrade --dry-run`.
+    Nix,
+    /// Flatpak apps and runtimes, using `flatpak remote-ls --updates`.
+    Flatpak,
+}
+
+impl Display for Backend {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Pacman => "pacman",
+                Self::Apt => "apt",
+                Self::Dnf => "dnf",
+                Self::Nix => "nix",
+                Self::Flatpak => "flatpak",
+            }
+        )
+    }
+}
+
+impl Backend {
+    const ALL: [Self; 5] = [Self::Pacman, Self::Apt, Self::Dnf, Self::Nix, Self::Flatpak];
+
+    /// Gets the backends whose programs are installed.
+    pub fn detect() -> Vec<Self> {
+        Self::ALL
+            .into_iter()
+            .filter(|backend| is_installed(backend.program()))
+            .collect()
+    }
+
+    fn program(self) -> &'static str {
+        match self {
+            Self::Pacman => "checkupdates",
+            Self::Apt => "apt",
+            Self::Dnf => "dnf",
+            Self::Nix => "nix-env",
+            Self::Flatpak => "flatpak",
+        }
+    }
+
+    fn args(self) -> &'static [&'static str] {
+        match self {
+            Self::Pacman => &[],
+            Self::Apt => &["list", "--upgradable"],
+            Self::Dnf => &["check-update", "--quiet"],
+            Self::Nix => &["--upgrade", "--dry-run"],
+            Self::Flatpak => &["remote-ls", "--updates", "--columns=application"],
+        }
+    }
+
+    /// Whether the program exited successfully,
+    /// taking into account programs which use other codes to report updates.
+    fn is_success(self, code: Option<i32>) -> bool {
+        match self {
+            // 2 means no updates
+            Self::Pacman => matches!(code, Some(0 | 2)),
+            // 100 means updates are available
+            Self::Dnf => matches!(code, Some(0 | 100)),
+            Self::Apt | Self::Nix | Self::Flatpak => code == Some(0),
+        }
+    }
+
+    /// Gets the names of packages with pending updates.
+    pub async fn check(self) -> Result<Vec<String>> {
+        debug!("Checking for {self} updates");
+
+        let output = Command::new(self.program())
+            .args(self.args())
+            .output()
+            .await?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        if !self.is_success(output.status.code()) {
+            return Err(Report::msg(format!(
+                "{} exited with {}: {}",
+                self.program(),
+                output.status,
+                stderr.trim()
+            )));
+        }
+
+        Ok(self.parse(&stdout, &stderr))
+    }
+
+    /// Parses the package names from the program output.
+    fn parse(self, stdout: &str, stderr: &str) -> Vec<String> {
+        match self {
+            // `name old -> new`
+            Self::Pacman => stdout
+                .lines()
+                .filter_map(|line| line.split_whitespace().next())
+                .map(ToString::to_string)
+                .collect(),
+            // `name/suite version arch [upgradable from: old]`
+            Self::Apt => stdout
+                .lines()
+                .filter_map(|line| line.split_once('/'))
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            // `name.arch version repo`,
+            // followed by an `Obsoleting Packages` section which is skipped.
+            Self::Dnf => stdout
+                .lines()
+                .take_while(|line| !line.starts_with("Obsoleting"))
+                .filter_map(|line| line.split_whitespace().next())
+                .map(|name| name.rsplit_once('.').map_or(name, |(name, _)| name))
+                .map(ToString::to_string)
+                .collect(),
+            // `upgrading 'name-old' to 'name-new'`, written to stderr
+            Self::Nix => stderr
+                .lines()
+                .filter_map(|line| line.strip_prefix("upgrading '"))
+                .filter_map(|line| line.split_once('\''))
+                .map(|(name, _)| name.to_string())
+                .collect(),
+            Self::Flatpak => stdout
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+}
+
+/// Checks whether the program is in a directory on `PATH`.
+fn is_installed(program: &str) -> bool {
+    env::var_os("PATH")
+        .is_some_and(|path| env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pacman() {
+        let stdout = "linux 6.9.1.arch1-1 -> 6.9.2.arch1-1\nmesa 1:24.0.7-1 -> 1:24.1.0-1\n";
+        assert_eq!(Backend::Pacman.parse(stdout, ""), vec!["linux", "mesa"]);
+    }
+
+    #[test]
+    fn test_parse_apt() {
+        let stdout =
+            "Listing...\nfirefox/noble-updates 126.0+build1 amd64 [upgradable from: 125.0]\n";
+        assert_eq!(Backend::Apt.parse(stdout, ""), vec!["firefox"]);
+    }
+
+    #[test]
+    fn test_parse_dnf() {
+        let stdout = "\nkernel.x86_64  6.8.10-300.fc40  updates\n\nObsoleting Packages\ngrub2-tools.x86_64  1:2.06-121  updates\n";
+        assert_eq!(Backend::Dnf.parse(stdout, ""), vec!["kernel"]);
+    }
+
+    #[test]
+    fn test_parse_nix() {
+        let stderr = "upgrading 'hello-2.10' to 'hello-2.12.1'\n";
+        assert_eq!(Backend::Nix.parse("", stderr), vec!["hello-2.10"]);
+    }
+}
@@ -0,0 +1,318 @@
+mod backend;
+
+use self::backend::Backend;
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::config::{CommonConfig, LayoutConfig};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::script::{OutputStream, Script};
+use crate::{module_impl, poll, spawn};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation, PolicyType, ScrolledWindow};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{error, warn};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct UpdatesModule {
+    /// The package managers to check for updates.
+    /// Any of `pacman`, `apt`, `dnf`, `nix` or `flatpak`.
+    ///
+    /// If empty, every package manager which is installed is checked.
+    ///
+    /// **Default**: `[]`
+    backends: Vec<Backend>,
+
+    /// The number of milliseconds between checking for updates,
+    /// set from the common `interval` option.
+    ///
+    /// **Default**: `3600000` (1 hour)
+    #[serde(skip)]
+    interval: u64,
+
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰏗 {count}`
+    #[serde(skip)]
+    format: String,
+
+    /// The command to run when the upgrade button in the popup is clicked.
+    /// Updates are checked again once it exits.
+    ///
+    /// This usually needs to open a terminal to ask for a password,
+    /// for example `kitty -e sudo pacman -Syu`.
+    /// The upgrade button is hidden if not set.
+    ///
+    /// **Default**: `null`
+    upgrade_command: Option<String>,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(default, flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for UpdatesModule {
+    fn default() -> Self {
+        Self {
+            backends: vec![],
+            interval: 3_600_000,
+            format: "󰏗 {count}".to_string(),
+            upgrade_command: None,
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+/// A package with a pending update.
+#[derive(Debug, Clone)]
+pub struct Package {
+    name: String,
+    backend: Backend,
+}
+
+#[derive(Debug, Clone)]
+pub enum ControllerEvent {
+    Checking,
+    Updates(Vec<Package>),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum UiEvent {
+    Check,
+    Upgrade,
+}
+
+/// Checks each backend for updates,
+/// skipping any which fail.
+async fn check(backends: &[Backend]) -> Vec<Package> {
+    let mut packages = vec![];
+
+    for &backend in backends {
+        match backend.check().await {
+            Ok(names) => packages.extend(names.into_iter().map(|name| Package { name, backend })),
+            Err(err) => error!("Failed to check for {backend} updates: {err:?}"),
+        }
+    }
+
+    packages
+}
+
+impl Module<Button> for UpdatesModule {
+    type SendMessage = ControllerEvent;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("updates");
+
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
+    fn set_interval(&mut self, interval: u64) {
+        self.interval = interval;
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let backends = if self.backends.is_empty() {
+            Backend::detect()
+        } else {
+            self.backends.clone()
+        };
+
+        if backends.is_empty() {
+            warn!("No supported package managers found");
+        }
+
+        let upgrade = self.upgrade_command.as_deref().map(Script::from);
+        let interval = Duration::from_millis(self.interval);
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            loop {
+                tx.send_update(ControllerEvent::Checking).await;
+                tx.send_update(ControllerEvent::Updates(check(&backends).await))
+                    .await;
+
+                // check again early after a manual refresh or upgrade
+                tokio::select! {
+                    () = poll::sleep(interval) => {}
+                    Some(event) = rx.recv() => {
+                        if let (UiEvent::Upgrade, Some(script)) = (event, &upgrade) {
+                            run_upgrade(script).await;
+                        }
+                    }
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        button.add_css_class("btn");
+
+        let label = Label::builder().use_markup(true).build();
+        label.set_justify(self.layout.justify.into());
+        button.set_child(Some(&label));
+
+        let tx = context.tx.clone();
+        button.connect_clicked(move |button| {
+            tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
+        });
+
+        {
+            let format = self.format.clone();
+            context
+                .subscribe()
+                .recv_glib(
+                    (&button, &label),
+                    move |(button, label), event| match event {
+                        ControllerEvent::Checking => button.add_css_class("checking"),
+                        ControllerEvent::Updates(packages) => {
+                            button.remove_css_class("checking");
+                            label.set_label_escaped(
+                                &format.replace("{count}", &packages.len().to_string()),
+                            );
+
+                            if packages.is_empty() {
+                                button.add_css_class("up-to-date");
+                            } else {
+                                button.remove_css_class("up-to-date");
+                            }
+                        }
+                    },
+                );
+        }
+
+        let popup = self
+            .into_popup(context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let header = Label::new(None);
+        header.add_css_class("header");
+        header.set_halign(Align::Start);
+        container.append(&header);
+
+        let list = gtk::Box::new(Orientation::Vertical, 0);
+        list.add_css_class("packages");
+
+        let scrolled = ScrolledWindow::builder()
+            .hscrollbar_policy(PolicyType::Never)
+            .min_content_height(200)
+            .child(&list)
+            .build();
+        container.append(&scrolled);
+
+        let actions = gtk::Box::new(Orientation::Horizontal, 5);
+        actions.set_halign(Align::End);
+        container.append(&actions);
+
+        let refresh = Button::with_label("󰑓 Refresh");
+        refresh.add_css_class("btn-refresh");
+        actions.append(&refresh);
+
+        let tx = context.controller_tx.clone();
+        refresh.connect_clicked(move |_| tx.send_spawn(UiEvent::Check));
+
+        let upgrade = Button::with_label("󰚰 Upgrade");
+        upgrade.add_css_class("btn-upgrade");
+        upgrade.set_visible(self.upgrade_command.is_some());
+        actions.append(&upgrade);
+
+        let tx = context.controller_tx.clone();
+        upgrade.connect_clicked(move |_| tx.send_spawn(UiEvent::Upgrade));
+
+        context.subscribe().recv_glib(
+            (&header, &list, &scrolled, &refresh, &upgrade),
+            |(header, list, scrolled, refresh, upgrade), event| match event {
+                ControllerEvent::Checking => {
+                    header.set_label("Checking for updates...");
+                    refresh.set_sensitive(false);
+                    upgrade.set_sensitive(false);
+                }
+                ControllerEvent::Updates(packages) => {
+                    header.set_label(&match packages.len() {
+                        0 => "Up to date".to_string(),
+                        1 => "1 update available".to_string(),
+                        count => format!("{count} updates available"),
+                    });
+
+                    refresh.set_sensitive(true);
+                    upgrade.set_sensitive(!packages.is_empty());
+                    scrolled.set_visible(!packages.is_empty());
+
+                    for child in list.children() {
+                        list.remove(&child);
+                    }
+
+                    for package in packages {
+                        list.append(&package_row(&package));
+                    }
+                }
+            },
+        );
+
+        Some(container)
+    }
+}
+
+/// Runs the upgrade command, waiting for it to exit.
+async fn run_upgrade(script: &Script) {
+    match script.get_output(None).await {
+        Ok((OutputStream::Stderr(stderr), _)) => error!("Upgrade command failed: {stderr}"),
+        Ok(_) => {}
+        Err(err) => error!("Failed to run upgrade command: {err:?}"),
+    }
+}
+
+fn package_row(package: &Package) -> gtk::Box {
+    let row = gtk::Box::new(Orientation::Horizontal, 10);
+    row.add_css_class("package");
+
+    let name = Label::new(Some(&package.name));
+    name.add_css_class("name");
+    name.set_halign(Align::Start);
+    name.set_hexpand(true);
+    row.append(&name);
+
+    let backend = Label::new(Some(&package.backend.to_string()));
+    backend.add_css_class("backend");
+    row.append(&backend);
+
+    row
+}
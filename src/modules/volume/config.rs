@@ -1,3 +1,4 @@
+use crate::clients::volume::ClientType;
 use crate::config::{
     CommonConfig, LayoutConfig, MarqueeMode, ModuleOrientation, Profiles, TruncateMode,
 };
@@ -33,10 +34,20 @@ pub(super) fn default_profiles() -> Profiles<f64, VolumeProfile> {
 #[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct VolumeModule {
+    /// The audio server API to use.
+    /// Either `pulse` or `pipewire`.
+    ///
+    /// The `pipewire` backend requires the `volume+pipewire` feature,
+    /// and does not require `pipewire-pulse`.
+    ///
+    /// **Default**: `pulse`
+    pub(super) backend: ClientType,
+
     /// The format string to use for the widget button label.
     /// For available tokens, see [below](#formatting-tokens).
     ///
     /// **Default**: `{icon} {percentage}%`
+    #[serde(skip)]
     pub(super) format: String,
 
     /// Maximum value to allow volume sliders to reach.
@@ -50,6 +61,24 @@ pub struct VolumeModule {
     /// **Default**: vertical
     pub(super) sink_slider_orientation: ModuleOrientation,
 
+    /// Whether to show controls for input devices,
+    /// such as microphones, and recording streams in the popup.
+    ///
+    /// **Default**: `false`
+    pub(super) show_inputs: bool,
+
+    /// Icons to use for the input device and recording stream mute buttons.
+    pub(super) input_icons: InputIcons,
+
+    /// Whether to show a profile selector for each sound card in the popup.
+    /// This allows switching between profiles
+    /// such as analog stereo duplex or HDMI output.
+    ///
+    /// Profiles are only available using the `pulse` backend.
+    ///
+    /// **Default**: `false`
+    pub(super) show_cards: bool,
+
     /// See [profiles](profiles).
     #[serde(flatten)]
     pub(super) profiles: Profiles<f64, VolumeProfile>,
@@ -76,9 +105,13 @@ pub struct VolumeModule {
 impl Default for VolumeModule {
     fn default() -> Self {
         Self {
+            backend: ClientType::default(),
             format: "{icon} {percentage}%".to_string(),
             max_volume: 100.0,
             sink_slider_orientation: ModuleOrientation::Vertical,
+            show_inputs: false,
+            input_icons: InputIcons::default(),
+            show_cards: false,
             profiles: Profiles::default(),
             truncate: None,
             marquee: MarqueeMode::default(),
@@ -111,3 +144,33 @@ impl Default for Icons {
         }
     }
 }
+
+#[derive(Debug, Clone, Deserialize)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct InputIcons {
+    /// Icon to show for unmuted inputs.
+    ///
+    /// **Default**: `󰍬`
+    pub(super) unmuted: String,
+
+    /// Icon to show for muted inputs.
+    ///
+    /// **Default**: `󰍭`
+    pub(super) muted: String,
+}
+
+impl Default for InputIcons {
+    fn default() -> Self {
+        Self {
+            unmuted: "󰍬".to_string(),
+            muted: "󰍭".to_string(),
+        }
+    }
+}
+
+impl InputIcons {
+    pub(super) fn get(&self, muted: bool) -> &str {
+        if muted { &self.muted } else { &self.unmuted }
+    }
+}
@@ -1,14 +1,15 @@
 mod config;
 
+use crate::animation::{self, AnimatedValue};
 use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
-use crate::clients::volume::{self, Event};
+use crate::clients::volume::{Card, Channel, Event, Sink, SinkInput};
 use crate::config::{ModuleOrientation, ProfileUpdateEvent};
-use crate::gtk_helpers::{IronbarLabelExt, OverflowLabel};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt, OverflowLabel};
 use crate::modules::{
     Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
 };
-use crate::{lock, module_impl, spawn};
-use config::VolumeProfile;
+use crate::{module_impl, spawn};
+use config::{InputIcons, VolumeProfile};
 use glib::subclass::prelude::*;
 use glib::{Object, Properties};
 use gtk::prelude::*;
@@ -16,21 +17,35 @@ use gtk::{
     Button, DropDown, Expression, Label, ListItem, Orientation, Scale, SignalListItemFactory,
     ToggleButton, gio,
 };
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use std::rc::Rc;
 use tokio::sync::mpsc;
 use tracing::trace;
 
 pub use config::VolumeModule;
 
+/// The distance in pixels to swipe across the widget
+/// to change the volume by one percent.
+const SWIPE_PIXELS_PER_PERCENT: f64 = 2.0;
+
 #[derive(Debug, Clone)]
 pub enum Update {
     SinkChange(String),
     SinkVolume(String, f64),
     SinkMute(String, bool),
+    SinkBalance(String, f64),
 
     InputVolume(u32, f64),
     InputMute(u32, bool),
+
+    SourceChange(String),
+    SourceVolume(String, f64),
+    SourceMute(String, bool),
+
+    OutputMute(u32, bool),
+
+    CardProfile(String, String),
 }
 
 struct BarUiUpdate {
@@ -79,6 +94,21 @@ impl Module<Button> for VolumeModule {
 
     module_impl!("volume");
 
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
+    fn coalesce_key(update: &Self::SendMessage) -> Option<String> {
+        match update {
+            Event::UpdateSink(sink) => Some(format!("sink:{}", sink.name)),
+            Event::UpdateInput(input) => Some(format!("input:{}", input.index)),
+            Event::UpdateSource(source) => Some(format!("source:{}", source.name)),
+            Event::UpdateOutput(output) => Some(format!("output:{}", output.index)),
+            Event::UpdateCard(card) => Some(format!("card:{}", card.name)),
+            _ => None,
+        }
+    }
+
     fn on_create(&mut self) {
         self.profiles.setup_defaults(config::default_profiles());
     }
@@ -92,7 +122,7 @@ impl Module<Button> for VolumeModule {
     where
         <Self as Module<Button>>::SendMessage: Clone,
     {
-        let client = context.client::<volume::Client>();
+        let client = context.ironbar.clients.borrow_mut().volume(self.backend);
 
         {
             let client = client.clone();
@@ -101,19 +131,10 @@ impl Module<Button> for VolumeModule {
 
             spawn(async move {
                 // init
-                let sinks = {
-                    let sinks = client.sinks();
-                    let sinks = lock!(sinks);
-                    sinks.iter().cloned().collect::<Vec<_>>()
-                };
-
+                let sinks = client.sinks();
                 trace!("initial syncs: {sinks:?}");
 
-                let inputs = {
-                    let inputs = client.sink_inputs();
-                    let inputs = lock!(inputs);
-                    inputs.iter().cloned().collect::<Vec<_>>()
-                };
+                let inputs = client.sink_inputs();
 
                 trace!("initial inputs: {inputs:?}");
 
@@ -125,6 +146,18 @@ impl Module<Button> for VolumeModule {
                     tx.send_update(Event::AddInput(input)).await;
                 }
 
+                for source in client.sources() {
+                    tx.send_update(Event::AddSource(source)).await;
+                }
+
+                for output in client.source_outputs() {
+                    tx.send_update(Event::AddOutput(output)).await;
+                }
+
+                for card in client.cards() {
+                    tx.send_update(Event::AddCard(card)).await;
+                }
+
                 // recv loop
                 while let Ok(event) = rx.recv().await {
                     trace!("received event: {event:?}");
@@ -140,8 +173,14 @@ impl Module<Button> for VolumeModule {
                     Update::SinkChange(name) => client.set_default_sink(&name),
                     Update::SinkVolume(name, volume) => client.set_sink_volume(&name, volume),
                     Update::SinkMute(name, muted) => client.set_sink_muted(&name, muted),
+                    Update::SinkBalance(name, balance) => client.set_sink_balance(&name, balance),
                     Update::InputVolume(index, volume) => client.set_input_volume(index, volume),
                     Update::InputMute(index, muted) => client.set_input_muted(index, muted),
+                    Update::SourceChange(name) => client.set_default_source(&name),
+                    Update::SourceVolume(name, volume) => client.set_source_volume(&name, volume),
+                    Update::SourceMute(name, muted) => client.set_source_muted(&name, muted),
+                    Update::OutputMute(index, muted) => client.set_output_muted(index, muted),
+                    Update::CardProfile(name, profile) => client.set_card_profile(&name, &profile),
                 }
             }
         });
@@ -164,6 +203,7 @@ impl Module<Button> for VolumeModule {
 
         let button = Button::new();
         button.set_child(Some(&button_label));
+        button.set_accessible_label("Volume");
 
         {
             let tx = context.tx.clone();
@@ -173,6 +213,34 @@ impl Module<Button> for VolumeModule {
             });
         }
 
+        let active_sink = Rc::new(RefCell::new(None::<(String, f64)>));
+
+        // swiping horizontally on a touchscreen adjusts the volume
+        {
+            let swipe_start = Rc::new(Cell::new(0.0));
+            let tx = context.controller_tx.clone();
+            let max_volume = self.max_volume;
+
+            let on_begin = {
+                let active_sink = active_sink.clone();
+                let swipe_start = swipe_start.clone();
+                move || {
+                    if let Some((_, volume)) = &*active_sink.borrow() {
+                        swipe_start.set(*volume);
+                    }
+                }
+            };
+
+            let active_sink = active_sink.clone();
+            button.connect_swipe_horizontal(on_begin, move |offset_x| {
+                if let Some((name, _)) = &*active_sink.borrow() {
+                    let volume = (swipe_start.get() + offset_x / SWIPE_PIXELS_PER_PERCENT)
+                        .clamp(0.0, max_volume);
+                    tx.send_spawn(Update::SinkVolume(name.clone(), volume));
+                }
+            });
+        }
+
         let rx = context.subscribe();
 
         let mut manager = {
@@ -181,7 +249,7 @@ impl Module<Button> for VolumeModule {
             // attach to button as we want class there
             self.profiles.attach(
                 &button,
-                move |_, event: ProfileUpdateEvent<f64, VolumeProfile, BarUiUpdate>| {
+                move |button, event: ProfileUpdateEvent<f64, VolumeProfile, BarUiUpdate>| {
                     let icons = &event.profile.icons;
                     let label = format
                         .replace(
@@ -196,12 +264,21 @@ impl Module<Button> for VolumeModule {
                         .replace("{name}", &event.data.description);
 
                     button_label.set_label_escaped(&label);
+
+                    let description = if event.data.muted {
+                        format!("{}%, muted", event.state)
+                    } else {
+                        format!("{}%", event.state)
+                    };
+                    button.set_accessible_description(&description);
                 },
             )
         };
 
         rx.recv_glib((), move |(), event| match event {
             Event::AddSink(sink) | Event::UpdateSink(sink) if sink.active => {
+                *active_sink.borrow_mut() = Some((sink.name.clone(), sink.volume.percent()));
+
                 manager.update(
                     sink.volume.percent(),
                     BarUiUpdate {
@@ -239,36 +316,20 @@ impl Module<Button> for VolumeModule {
         container.append(&sink_container);
         container.append(&input_container);
 
-        let options = gio::ListStore::new::<DropdownItem>();
-        let factory = SignalListItemFactory::new();
-        factory.connect_setup(move |_, list_item| {
-            let label = Label::new(None);
-            list_item
-                .downcast_ref::<ListItem>()
-                .expect("Needs to be ListItem")
-                .set_child(Some(&label));
-        });
+        if self.show_inputs {
+            let (source_container, output_container) = self.input_popup(&context);
+            container.append(&source_container);
+            container.append(&output_container);
+        }
 
-        factory.connect_bind(move |_, list_item| {
-            let dropdown_item = list_item
-                .downcast_ref::<ListItem>()
-                .expect("should be ListItem")
-                .item()
-                .and_downcast::<DropdownItem>()
-                .expect("should be `DropdownItem`.");
-
-            let label = list_item
-                .downcast_ref::<ListItem>()
-                .expect("should be ListItem")
-                .child()
-                .and_downcast::<Label>()
-                .expect("should be a `Label`.");
-
-            label.set_label(&dropdown_item.value().to_string());
-        });
+        if self.show_cards {
+            container.append(&self.cards_popup(&context));
+        }
+
+        let options = gio::ListStore::new::<DropdownItem>();
 
         let sink_selector = DropDown::new(Some(options.clone()), None::<Expression>);
-        sink_selector.set_factory(Some(&factory));
+        sink_selector.set_factory(Some(&dropdown_factory()));
         sink_selector.add_css_class("device-selector");
 
         {
@@ -283,21 +344,8 @@ impl Module<Button> for VolumeModule {
 
         sink_container.append(&sink_selector);
 
-        let slider = match self.sink_slider_orientation {
-            ModuleOrientation::Horizontal => Scale::builder()
-                .orientation(Orientation::Horizontal)
-                .build(),
-            ModuleOrientation::Vertical => Scale::builder()
-                .orientation(Orientation::Vertical)
-                .height_request(100)
-                .inverted(true)
-                .build(),
-        };
-
-        slider.add_css_class("slider");
-
-        slider.set_range(0.0, self.max_volume);
-        slider.set_value(50.0);
+        let slider = self.device_slider();
+        slider.set_accessible_label("Volume");
         sink_container.append(&slider);
 
         {
@@ -316,8 +364,30 @@ impl Module<Button> for VolumeModule {
             });
         }
 
+        let balance_slider = Scale::with_range(Orientation::Horizontal, -1.0, 1.0, 0.05);
+        balance_slider.add_css_class("balance");
+        balance_slider.set_accessible_label("Balance");
+        balance_slider.add_mark(0.0, gtk::PositionType::Bottom, None);
+        balance_slider.set_visible(false);
+        sink_container.append(&balance_slider);
+
+        {
+            let tx = context.controller_tx.clone();
+            let selector = sink_selector.clone();
+
+            balance_slider.connect_value_changed(move |scale| {
+                if scale.has_css_class("dragging")
+                    && let Some(sink) = selector.selected_item().and_downcast_ref::<DropdownItem>()
+                {
+                    let val = scale.value().clamp(-1.0, 1.0);
+                    tx.send_spawn(Update::SinkBalance(sink.key(), val));
+                }
+            });
+        }
+
         let btn_mute = ToggleButton::new();
         btn_mute.add_css_class("btn-mute");
+        btn_mute.set_accessible_label("Mute");
         sink_container.append(&btn_mute);
 
         let mut manager = self.profiles.attach(
@@ -346,6 +416,8 @@ impl Module<Button> for VolumeModule {
             });
         }
 
+        let volume = animated_slider(&slider);
+
         let mut inputs = HashMap::new();
         let mut sinks = vec![];
 
@@ -358,7 +430,8 @@ impl Module<Button> for VolumeModule {
 
                         if info.active {
                             sink_selector.set_selected(sinks.len() as u32);
-                            slider.set_value(info.volume.percent());
+                            volume.set(info.volume.percent());
+                            update_balance(&balance_slider, &info);
 
                             manager.update(
                                 info.volume.percent(),
@@ -375,9 +448,11 @@ impl Module<Button> for VolumeModule {
                             sink_selector.set_selected(pos as u32);
 
                             if !slider.has_css_class("dragging") {
-                                slider.set_value(info.volume.percent());
+                                volume.set(info.volume.percent());
                             }
 
+                            update_balance(&balance_slider, &info);
+
                             manager.update(
                                 info.volume.percent(),
                                 BtnMuteUiUpdate { muted: info.muted },
@@ -396,6 +471,7 @@ impl Module<Button> for VolumeModule {
 
                         let item_container = gtk::Box::new(Orientation::Vertical, 0);
                         item_container.add_css_class("app-box");
+                        item_container.set_tooltip_text(latency_tooltip(&info).as_deref());
 
                         let title_label = OverflowLabel::new(
                             Label::new(None),
@@ -410,6 +486,7 @@ impl Module<Button> for VolumeModule {
                         slider.set_range(0.0, self.max_volume);
                         slider.set_value(info.volume.percent());
                         slider.add_css_class("slider");
+                        slider.set_accessible_label(&format!("{} volume", info.name));
 
                         {
                             let tx = context.controller_tx.clone();
@@ -424,6 +501,7 @@ impl Module<Button> for VolumeModule {
 
                         let btn_mute = ToggleButton::new();
                         btn_mute.add_css_class("btn-mute");
+                        btn_mute.set_accessible_label(&format!("Mute {}", info.name));
 
                         manager
                             .update(info.volume.percent(), BtnMuteUiUpdate { muted: info.muted });
@@ -446,6 +524,7 @@ impl Module<Button> for VolumeModule {
                             InputUi {
                                 container: item_container,
                                 title_label,
+                                volume: animated_slider(&slider),
                                 slider,
                                 label_raw: info.name.clone(),
                             },
@@ -459,10 +538,12 @@ impl Module<Button> for VolumeModule {
                             }
 
                             if !ui.slider.has_css_class("dragging") {
-                                ui.slider.set_value(info.volume.percent());
+                                ui.volume.set(info.volume.percent());
                             }
 
                             ui.slider.set_sensitive(info.can_set_volume);
+                            ui.container
+                                .set_tooltip_text(latency_tooltip(&info).as_deref());
                             manager.update(
                                 info.volume.percent(),
                                 BtnMuteUiUpdate { muted: info.muted },
@@ -474,6 +555,17 @@ impl Module<Button> for VolumeModule {
                             input_container.remove(&ui.container);
                         }
                     }
+
+                    // handled by the input and card popups
+                    Event::AddSource(_)
+                    | Event::UpdateSource(_)
+                    | Event::RemoveSource(_)
+                    | Event::AddOutput(_)
+                    | Event::UpdateOutput(_)
+                    | Event::RemoveOutput(_)
+                    | Event::AddCard(_)
+                    | Event::UpdateCard(_)
+                    | Event::RemoveCard(_) => {}
                 }
             });
 
@@ -481,10 +573,414 @@ impl Module<Button> for VolumeModule {
     }
 }
 
+impl VolumeModule {
+    /// Creates a slider for a device volume,
+    /// using the configured orientation.
+    fn device_slider(&self) -> Scale {
+        let slider = match self.sink_slider_orientation {
+            ModuleOrientation::Horizontal => Scale::builder()
+                .orientation(Orientation::Horizontal)
+                .build(),
+            ModuleOrientation::Vertical => Scale::builder()
+                .orientation(Orientation::Vertical)
+                .height_request(100)
+                .inverted(true)
+                .build(),
+        };
+
+        slider.add_css_class("slider");
+
+        slider.set_range(0.0, self.max_volume);
+        slider.set_value(50.0);
+
+        slider
+    }
+
+    /// Creates the popup controls for input devices and recording streams,
+    /// returning the source and recording containers.
+    fn input_popup(&self, context: &WidgetContext<Event, Update>) -> (gtk::Box, gtk::Box) {
+        let source_container = gtk::Box::new(Orientation::Vertical, 5);
+        source_container.add_css_class("source-box");
+
+        let output_container = gtk::Box::new(Orientation::Vertical, 5);
+        output_container.add_css_class("recording-box");
+
+        let options = gio::ListStore::new::<DropdownItem>();
+
+        let source_selector = DropDown::new(Some(options.clone()), None::<Expression>);
+        source_selector.set_factory(Some(&dropdown_factory()));
+        source_selector.add_css_class("device-selector");
+
+        {
+            let tx = context.controller_tx.clone();
+
+            source_selector.connect_selected_notify(move |selector| {
+                if let Some(item) = selector.selected_item().and_downcast_ref::<DropdownItem>() {
+                    tx.send_spawn(Update::SourceChange(item.key()));
+                }
+            });
+        }
+
+        source_container.append(&source_selector);
+
+        let slider = self.device_slider();
+        slider.set_accessible_label("Input volume");
+        source_container.append(&slider);
+
+        {
+            let tx = context.controller_tx.clone();
+            let selector = source_selector.clone();
+            let max_volume = self.max_volume;
+
+            slider.connect_value_changed(move |scale| {
+                if scale.has_css_class("dragging")
+                    && let Some(source) =
+                        selector.selected_item().and_downcast_ref::<DropdownItem>()
+                {
+                    // GTK will send values outside min/max range
+                    let val = scale.value().clamp(0.0, max_volume);
+                    tx.send_spawn(Update::SourceVolume(source.key(), val));
+                }
+            });
+        }
+
+        let btn_mute = ToggleButton::with_label(self.input_icons.get(false));
+        btn_mute.add_css_class("btn-mute");
+        btn_mute.set_accessible_label("Mute input");
+        source_container.append(&btn_mute);
+
+        {
+            let tx = context.controller_tx.clone();
+            let selector = source_selector.clone();
+
+            btn_mute.connect_toggled(move |btn| {
+                if let Some(source) = selector.selected_item().and_downcast_ref::<DropdownItem>() {
+                    let muted = btn.is_active();
+                    tx.send_spawn(Update::SourceMute(source.key(), muted));
+                }
+            });
+        }
+
+        let icons = self.input_icons.clone();
+        let controller_tx = context.controller_tx.clone();
+        let truncate = self.truncate;
+        let marquee = self.marquee.clone();
+
+        let volume = animated_slider(&slider);
+
+        let mut outputs = HashMap::new();
+        let mut sources = vec![];
+
+        context.subscribe().recv_glib(
+            &output_container,
+            move |output_container, event| match event {
+                Event::AddSource(info) => {
+                    options.append(&DropdownItem::new(&info.name, &info.description));
+
+                    if info.active {
+                        source_selector.set_selected(sources.len() as u32);
+                        volume.set(info.volume.percent());
+                        set_input_muted(&btn_mute, &icons, info.muted);
+                    }
+
+                    sources.push(info);
+                }
+                Event::UpdateSource(info) => {
+                    if info.active
+                        && let Some(pos) = sources.iter().position(|s| s.name == info.name)
+                    {
+                        source_selector.set_selected(pos as u32);
+
+                        if !slider.has_css_class("dragging") {
+                            volume.set(info.volume.percent());
+                        }
+
+                        set_input_muted(&btn_mute, &icons, info.muted);
+                    }
+                }
+                Event::RemoveSource(name) => {
+                    if let Some(pos) = sources.iter().position(|s| s.name == name) {
+                        options.remove(pos as u32);
+                        sources.remove(pos);
+                    }
+                }
+
+                Event::AddOutput(info) => {
+                    let index = info.index;
+
+                    let item_container = gtk::Box::new(Orientation::Vertical, 0);
+                    item_container.add_css_class("app-box");
+
+                    let title_label =
+                        OverflowLabel::new(Label::new(None), truncate, marquee.clone());
+                    title_label.label().add_css_class("title");
+                    title_label.set_label_escaped(&info.name);
+                    item_container.append(title_label.widget());
+
+                    let btn_mute = ToggleButton::new();
+                    btn_mute.add_css_class("btn-mute");
+                    btn_mute.set_accessible_label(&format!("Mute {}", info.name));
+                    set_input_muted(&btn_mute, &icons, info.muted);
+
+                    {
+                        let tx = controller_tx.clone();
+                        btn_mute.connect_toggled(move |btn| {
+                            let muted = btn.is_active();
+                            tx.send_spawn(Update::OutputMute(index, muted));
+                        });
+                    }
+
+                    item_container.append(&btn_mute);
+                    output_container.append(&item_container);
+
+                    outputs.insert(
+                        info.index,
+                        OutputUi {
+                            container: item_container,
+                            title_label,
+                            btn_mute,
+                            label_raw: info.name.clone(),
+                        },
+                    );
+                }
+                Event::UpdateOutput(info) => {
+                    if let Some(ui) = outputs.get_mut(&info.index) {
+                        if ui.label_raw != info.name {
+                            ui.title_label.set_label_escaped(&info.name);
+                            ui.label_raw = info.name.clone();
+                        }
+
+                        set_input_muted(&ui.btn_mute, &icons, info.muted);
+                    }
+                }
+                Event::RemoveOutput(index) => {
+                    if let Some(ui) = outputs.remove(&index) {
+                        output_container.remove(&ui.container);
+                    }
+                }
+
+                _ => {}
+            },
+        );
+
+        (source_container, output_container)
+    }
+
+    /// Creates the popup controls for switching sound card profiles.
+    fn cards_popup(&self, context: &WidgetContext<Event, Update>) -> gtk::Box {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+        container.add_css_class("cards-box");
+
+        let controller_tx = context.controller_tx.clone();
+        let mut cards = HashMap::new();
+
+        context
+            .subscribe()
+            .recv_glib(&container, move |container, event| match event {
+                Event::AddCard(info) => {
+                    let item_container = gtk::Box::new(Orientation::Vertical, 0);
+                    item_container.add_css_class("card-box");
+
+                    let title_label = Label::new(Some(&info.description));
+                    title_label.add_css_class("title");
+                    item_container.append(&title_label);
+
+                    let options = gio::ListStore::new::<DropdownItem>();
+
+                    let profile_selector = DropDown::new(Some(options.clone()), None::<Expression>);
+                    profile_selector.set_factory(Some(&dropdown_factory()));
+                    profile_selector.add_css_class("profile-selector");
+                    profile_selector.set_accessible_label(&format!("{} profile", info.description));
+
+                    let mut ui = CardUi {
+                        container: item_container,
+                        title_label,
+                        options,
+                        profile_selector,
+                        active_profile: Rc::new(RefCell::new(None)),
+                        profiles: vec![],
+                    };
+
+                    {
+                        let tx = controller_tx.clone();
+                        let name = info.name.clone();
+                        let active_profile = ui.active_profile.clone();
+
+                        ui.profile_selector
+                            .connect_selected_notify(move |selector| {
+                                // the selection also changes when the options are replaced
+                                if let Some(item) =
+                                    selector.selected_item().and_downcast_ref::<DropdownItem>()
+                                    && active_profile.borrow().as_ref() != Some(&item.key())
+                                {
+                                    tx.send_spawn(Update::CardProfile(name.clone(), item.key()));
+                                }
+                            });
+                    }
+
+                    ui.container.append(&ui.profile_selector);
+                    container.append(&ui.container);
+
+                    let name = info.name.clone();
+                    ui.update(info);
+                    cards.insert(name, ui);
+                }
+                Event::UpdateCard(info) => {
+                    if let Some(ui) = cards.get_mut(&info.name) {
+                        ui.update(info);
+                    }
+                }
+                Event::RemoveCard(name) => {
+                    if let Some(ui) = cards.remove(&name) {
+                        container.remove(&ui.container);
+                    }
+                }
+                _ => {}
+            });
+
+        container
+    }
+}
+
+/// Creates a factory for dropdown items,
+/// which shows each item's value as a label.
+fn dropdown_factory() -> SignalListItemFactory {
+    let factory = SignalListItemFactory::new();
+    factory.connect_setup(move |_, list_item| {
+        let label = Label::new(None);
+        list_item
+            .downcast_ref::<ListItem>()
+            .expect("Needs to be ListItem")
+            .set_child(Some(&label));
+    });
+
+    factory.connect_bind(move |_, list_item| {
+        let dropdown_item = list_item
+            .downcast_ref::<ListItem>()
+            .expect("should be ListItem")
+            .item()
+            .and_downcast::<DropdownItem>()
+            .expect("should be `DropdownItem`.");
+
+        let label = list_item
+            .downcast_ref::<ListItem>()
+            .expect("should be ListItem")
+            .child()
+            .and_downcast::<Label>()
+            .expect("should be a `Label`.");
+
+        label.set_label(&dropdown_item.value().to_string());
+    });
+
+    factory
+}
+
+/// Updates the balance slider to match the sink,
+/// hiding it if the sink does not have left and right channels.
+fn update_balance(balance_slider: &Scale, sink: &Sink) {
+    let has_balance = Channel::has_balance(&sink.channels);
+    balance_slider.set_visible(has_balance);
+
+    if has_balance && !balance_slider.has_css_class("dragging") {
+        balance_slider.set_value(sink.volume.balance(&sink.channels));
+    }
+}
+
+/// Updates an input mute button to match the muted state.
+/// Animates a volume slider to each new value,
+/// leaving it alone while the user drags it.
+fn animated_slider(slider: &Scale) -> AnimatedValue {
+    let get = slider.clone();
+    let set = slider.clone();
+
+    AnimatedValue::new(
+        slider,
+        animation::VALUE_DURATION,
+        move || get.value(),
+        move |value| {
+            if !set.has_css_class("dragging") {
+                set.set_value(value);
+            }
+        },
+    )
+}
+
+fn set_input_muted(btn_mute: &ToggleButton, icons: &InputIcons, muted: bool) {
+    btn_mute.set_active(muted);
+    btn_mute.set_label(icons.get(muted));
+}
+
+/// Gets the tooltip showing the stream's latency,
+/// if the backend reports it.
+fn latency_tooltip(info: &SinkInput) -> Option<String> {
+    info.latency
+        .as_ref()
+        .map(|latency| format!("Latency: {latency}"))
+}
+
 struct InputUi {
     container: gtk::Box,
     title_label: OverflowLabel,
     slider: Scale,
+    volume: AnimatedValue,
+    // Store original (unformatted) title to detect change when marquee is enabled
+    label_raw: String,
+}
+
+struct OutputUi {
+    container: gtk::Box,
+    title_label: OverflowLabel,
+    btn_mute: ToggleButton,
     // Store original (unformatted) title to detect change when marquee is enabled
     label_raw: String,
 }
+
+struct CardUi {
+    container: gtk::Box,
+    title_label: Label,
+    options: gio::ListStore,
+    profile_selector: DropDown,
+    active_profile: Rc<RefCell<Option<String>>>,
+    /// The names of the profiles currently in the options.
+    profiles: Vec<String>,
+}
+
+impl CardUi {
+    fn update(&mut self, info: Card) {
+        self.title_label.set_label(&info.description);
+
+        // unavailable profiles are hidden, unless already active
+        let profiles = info
+            .profiles
+            .into_iter()
+            .filter(|profile| {
+                profile.available || info.active_profile.as_ref() == Some(&profile.name)
+            })
+            .collect::<Vec<_>>();
+
+        // set before changing the options so the selection is not sent back
+        self.active_profile.replace(info.active_profile.clone());
+
+        let names = profiles
+            .iter()
+            .map(|profile| profile.name.clone())
+            .collect::<Vec<_>>();
+
+        if names != self.profiles {
+            let items = profiles
+                .iter()
+                .map(|profile| DropdownItem::new(&profile.name, &profile.description))
+                .collect::<Vec<_>>();
+
+            self.options.splice(0, self.options.n_items(), &items);
+            self.profiles = names;
+        }
+
+        if let Some(pos) = info
+            .active_profile
+            .and_then(|active| self.profiles.iter().position(|name| name == &active))
+        {
+            self.profile_selector.set_selected(pos as u32);
+        }
+    }
+}
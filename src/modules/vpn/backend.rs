@@ -0,0 +1,114 @@
+use super::{tailscale, wireguard};
+use color_eyre::{Report, Result};
+use serde::Deserialize;
+use std::path::Path;
+use std::time::Duration;
+
+/// The VPN to show the status of.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum Backend {
+    /// [Tailscale](https://tailscale.com), using the `tailscaled` local API.
+    #[default]
+    Tailscale,
+    /// A WireGuard interface managed by `wg-quick`.
+    ///
+    /// Peers are read using `wg show`, which usually requires root,
+    /// so are only shown if permitted.
+    /// Connecting and disconnecting uses `pkexec wg-quick`.
+    Wireguard,
+}
+
+/// Where to find the VPN's state.
+#[derive(Debug, Clone, Copy)]
+pub struct Target<'a> {
+    /// The Tailscale local API socket.
+    pub socket: &'a Path,
+    /// The WireGuard interface name.
+    pub interface: &'a str,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum State {
+    Connected,
+    Disconnected,
+    NeedsLogin,
+    /// The daemon is not running, or the interface could not be read.
+    #[default]
+    Unavailable,
+}
+
+impl State {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Connected => "connected",
+            Self::Disconnected => "disconnected",
+            Self::NeedsLogin => "needs login",
+            Self::Unavailable => "unavailable",
+        }
+    }
+
+    pub fn class(self) -> &'static str {
+        match self {
+            Self::Connected => "connected",
+            Self::Disconnected => "disconnected",
+            Self::NeedsLogin => "needs-login",
+            Self::Unavailable => "unavailable",
+        }
+    }
+}
+
+/// A device connected to the VPN.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Peer {
+    pub id: String,
+    pub name: String,
+    pub online: bool,
+    /// The round-trip time to the peer,
+    /// if it responded to a ping.
+    pub latency: Option<Duration>,
+    /// Whether traffic is currently routed through the peer.
+    pub exit_node: bool,
+    /// Whether the peer can be used as an exit node.
+    pub exit_node_option: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Status {
+    pub state: State,
+    pub peers: Vec<Peer>,
+}
+
+impl Status {
+    /// Gets the peer currently used as the exit node.
+    pub fn exit_node(&self) -> Option<&Peer> {
+        self.peers.iter().find(|peer| peer.exit_node)
+    }
+}
+
+impl Backend {
+    pub async fn status(self, target: Target<'_>) -> Result<Status> {
+        match self {
+            Self::Tailscale => tailscale::status(target.socket).await,
+            Self::Wireguard => wireguard::status(target.interface).await,
+        }
+    }
+
+    /// Brings the VPN up or down.
+    pub async fn set_connected(self, target: Target<'_>, connected: bool) -> Result<()> {
+        match self {
+            Self::Tailscale => tailscale::set_connected(target.socket, connected).await,
+            Self::Wireguard => wireguard::set_connected(target.interface, connected).await,
+        }
+    }
+
+    /// Routes traffic through the peer with the ID,
+    /// or stops using an exit node if `None`.
+    pub async fn set_exit_node(self, target: Target<'_>, id: Option<&str>) -> Result<()> {
+        match self {
+            Self::Tailscale => tailscale::set_exit_node(target.socket, id).await,
+            Self::Wireguard => Err(Report::msg("WireGuard does not support exit nodes")),
+        }
+    }
+}
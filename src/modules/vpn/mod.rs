@@ -0,0 +1,343 @@
+mod backend;
+mod tailscale;
+mod wireguard;
+
+use self::backend::{Backend, Peer, State, Status, Target};
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::config::{CommonConfig, LayoutConfig};
+use crate::gtk_helpers::{IronbarGtkExt, IronbarLabelExt};
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::{module_impl, poll, spawn};
+use color_eyre::Result;
+use gtk::prelude::*;
+use gtk::{Align, Button, Label, Orientation, Switch, ToggleButton};
+use serde::Deserialize;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, error};
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct VpnModule {
+    /// The VPN to show the status of.
+    /// One of `tailscale` or `wireguard`.
+    ///
+    /// **Default**: `tailscale`
+    backend: Backend,
+
+    /// The path to the `tailscaled` socket.
+    /// Only used by the `tailscale` backend.
+    ///
+    /// **Default**: `/run/tailscale/tailscaled.sock`
+    socket: Option<PathBuf>,
+
+    /// The name of the WireGuard interface.
+    /// Only used by the `wireguard` backend.
+    ///
+    /// **Default**: `wg0`
+    interface: String,
+
+    /// The number of milliseconds between refreshing the status,
+    /// set from the common `interval` option.
+    ///
+    /// **Default**: `10000`
+    #[serde(skip)]
+    interval: u64,
+
+    /// The format string to use for the widget button label.
+    /// For available tokens, see [below](#formatting-tokens).
+    ///
+    /// **Default**: `󰖂`
+    #[serde(skip)]
+    format: String,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(default, flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for VpnModule {
+    fn default() -> Self {
+        Self {
+            backend: Backend::default(),
+            socket: None,
+            interface: "wg0".to_string(),
+            interval: 10_000,
+            format: "󰖂".to_string(),
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+impl VpnModule {
+    fn format(&self, status: &Status) -> String {
+        let exit_node = status.exit_node().map_or("", |peer| peer.name.as_str());
+        let online = status.peers.iter().filter(|peer| peer.online).count();
+
+        self.format
+            .replace("{state}", status.state.label())
+            .replace("{exit_node}", exit_node)
+            .replace("{online}", &online.to_string())
+            .replace("{peers}", &status.peers.len().to_string())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum UiEvent {
+    SetConnected(bool),
+    SetExitNode(Option<String>),
+}
+
+impl Module<Button> for VpnModule {
+    type SendMessage = Status;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("vpn");
+
+    fn set_format(&mut self, format: String) {
+        self.format = format;
+    }
+
+    fn set_interval(&mut self, interval: u64) {
+        self.interval = interval;
+    }
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let backend = self.backend;
+        let socket = self
+            .socket
+            .clone()
+            .unwrap_or_else(tailscale::default_socket);
+        let interface = self.interface.clone();
+        let interval = Duration::from_millis(self.interval);
+        let tx = context.tx.clone();
+
+        spawn(async move {
+            let target = Target {
+                socket: &socket,
+                interface: &interface,
+            };
+
+            let mut status = None;
+
+            loop {
+                let new_status = match backend.status(target).await {
+                    Ok(status) => status,
+                    Err(err) => {
+                        // only log on the first failure, since the daemon may not be running
+                        if status
+                            .as_ref()
+                            .is_none_or(|status| status.state != State::Unavailable)
+                        {
+                            error!("Failed to get {backend:?} status: {err:?}");
+                        } else {
+                            debug!("Failed to get {backend:?} status: {err:?}");
+                        }
+
+                        Status::default()
+                    }
+                };
+
+                if status.as_ref() != Some(&new_status) {
+                    tx.send_update(new_status.clone()).await;
+                    status = Some(new_status);
+                }
+
+                // refresh early after an action to show its result
+                tokio::select! {
+                    () = poll::sleep(interval) => {}
+                    Some(event) = rx.recv() => handle_event(backend, target, event).await,
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = Button::new();
+        button.add_css_class("btn");
+
+        let label = Label::builder().use_markup(true).build();
+        label.set_justify(self.layout.justify.into());
+        button.set_child(Some(&label));
+
+        let tx = context.tx.clone();
+        button.connect_clicked(move |button| {
+            tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
+        });
+
+        {
+            let module = self.clone();
+            context
+                .subscribe()
+                .recv_glib((&button, &label), move |(button, label), status| {
+                    label.set_label_escaped(&module.format(&status));
+
+                    for state in [
+                        State::Connected,
+                        State::Disconnected,
+                        State::NeedsLogin,
+                        State::Unavailable,
+                    ] {
+                        button.remove_css_class(state.class());
+                    }
+                    button.add_css_class(status.state.class());
+                });
+        }
+
+        let popup = self
+            .into_popup(context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button, popup))
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        _info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::new(Orientation::Vertical, 5);
+
+        let header = gtk::Box::new(Orientation::Horizontal, 10);
+        header.add_css_class("header");
+        container.append(&header);
+
+        let state = Label::new(None);
+        state.add_css_class("state");
+        state.set_halign(Align::Start);
+        state.set_hexpand(true);
+        header.append(&state);
+
+        let switch = Switch::new();
+        switch.add_css_class("switch");
+        switch.set_valign(Align::Center);
+        header.append(&switch);
+
+        let tx = context.controller_tx.clone();
+        let handler = switch.connect_active_notify(move |switch| {
+            tx.send_spawn(UiEvent::SetConnected(switch.is_active()));
+        });
+
+        let exit_node = Label::new(None);
+        exit_node.add_css_class("exit-node");
+        exit_node.set_halign(Align::Start);
+        container.append(&exit_node);
+
+        let peers = gtk::Box::new(Orientation::Vertical, 5);
+        peers.add_css_class("peers");
+        container.append(&peers);
+
+        let tx = context.controller_tx.clone();
+
+        context.subscribe().recv_glib(
+            (&state, &switch, &exit_node, &peers),
+            move |(state, switch, exit_node, peers), status| {
+                state.set_label(&capitalize(status.state.label()));
+
+                switch.block_signal(&handler);
+                switch.set_active(status.state == State::Connected);
+                switch.set_sensitive(status.state != State::Unavailable);
+                switch.unblock_signal(&handler);
+
+                let exit_node_name = status.exit_node().map(|peer| peer.name.as_str());
+                exit_node.set_visible(exit_node_name.is_some());
+                exit_node.set_label(&format!(
+                    "Exit node: {}",
+                    exit_node_name.unwrap_or_default()
+                ));
+
+                for child in peers.children() {
+                    peers.remove(&child);
+                }
+
+                for peer in &status.peers {
+                    peers.append(&peer_row(peer, &tx));
+                }
+            },
+        );
+
+        Some(container)
+    }
+}
+
+async fn handle_event(backend: Backend, target: Target<'_>, event: UiEvent) {
+    let res = match &event {
+        UiEvent::SetConnected(connected) => backend.set_connected(target, *connected).await,
+        UiEvent::SetExitNode(id) => backend.set_exit_node(target, id.as_deref()).await,
+    };
+
+    if let Err(err) = res {
+        error!("Failed to handle {event:?}: {err:?}");
+    }
+}
+
+/// Creates a popup row for the peer,
+/// with a button to use it as the exit node if possible.
+fn peer_row(peer: &Peer, tx: &mpsc::Sender<UiEvent>) -> gtk::Box {
+    let row = gtk::Box::new(Orientation::Horizontal, 10);
+    row.add_css_class("peer");
+    row.add_css_class(if peer.online { "online" } else { "offline" });
+
+    let name = Label::new(Some(&peer.name));
+    name.add_css_class("name");
+    name.set_halign(Align::Start);
+    name.set_hexpand(true);
+    row.append(&name);
+
+    let latency = Label::new(
+        peer.latency
+            .map(|latency| format!("{} ms", latency.as_millis()))
+            .as_deref(),
+    );
+    latency.add_css_class("latency");
+    row.append(&latency);
+
+    if peer.exit_node_option {
+        let button = ToggleButton::with_label("Exit node");
+        button.add_css_class("btn-exit-node");
+        button.set_active(peer.exit_node);
+        row.append(&button);
+
+        let tx = tx.clone();
+        let id = peer.id.clone();
+        button.connect_toggled(move |button| {
+            button.set_sensitive(false);
+            tx.send_spawn(UiEvent::SetExitNode(button.is_active().then(|| id.clone())));
+        });
+    }
+
+    row
+}
+
+/// Capitalizes the first letter of the string.
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
@@ -0,0 +1,173 @@
+use super::backend::{Peer, State, Status};
+use crate::clients::unix_http;
+use color_eyre::{Report, Result};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+use tracing::debug;
+
+/// The host `tailscaled` expects local API requests to be sent to.
+const HOST: &str = "local-tailscaled.sock";
+
+/// The maximum time to wait for a peer to respond to a ping.
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TailscaleStatus {
+    backend_state: String,
+    #[serde(default)]
+    peer: Option<HashMap<String, PeerStatus>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PeerStatus {
+    #[serde(rename = "ID")]
+    id: String,
+    host_name: String,
+    #[serde(rename = "DNSName", default)]
+    dns_name: String,
+    #[serde(rename = "TailscaleIPs", default)]
+    tailscale_ips: Option<Vec<String>>,
+    #[serde(default)]
+    online: bool,
+    #[serde(default)]
+    exit_node: bool,
+    #[serde(default)]
+    exit_node_option: bool,
+}
+
+impl PeerStatus {
+    /// Gets the MagicDNS name of the peer,
+    /// falling back to its hostname.
+    fn name(&self) -> &str {
+        self.dns_name
+            .split('.')
+            .next()
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&self.host_name)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct PingResult {
+    #[serde(default)]
+    err: String,
+    latency_seconds: Option<f64>,
+}
+
+/// Gets the path of the `tailscaled` socket.
+pub fn default_socket() -> PathBuf {
+    let socket = Path::new("/run/tailscale/tailscaled.sock");
+    if socket.exists() {
+        socket.to_path_buf()
+    } else {
+        PathBuf::from("/var/run/tailscale/tailscaled.sock")
+    }
+}
+
+pub async fn status(socket: &Path) -> Result<Status> {
+    let body = unix_http::request(socket, HOST, "GET", "/localapi/v0/status", None).await?;
+    let status: TailscaleStatus = serde_json::from_slice(&body)?;
+
+    let state = match status.backend_state.as_str() {
+        "Running" => State::Connected,
+        "NeedsLogin" | "NeedsMachineAuth" => State::NeedsLogin,
+        _ => State::Disconnected,
+    };
+
+    let mut peers = vec![];
+    let mut ips = HashMap::new();
+
+    for peer in status.peer.unwrap_or_default().into_values() {
+        if let Some(ip) = peer.tailscale_ips.iter().flatten().next() {
+            ips.insert(peer.id.clone(), ip.clone());
+        }
+
+        peers.push(Peer {
+            id: peer.id.clone(),
+            name: peer.name().to_string(),
+            online: peer.online,
+            latency: None,
+            exit_node: peer.exit_node,
+            exit_node_option: peer.exit_node_option,
+        });
+    }
+
+    peers.sort_by(|a, b| a.name.cmp(&b.name));
+
+    if state == State::Connected {
+        ping_all(socket, &mut peers, &ips).await;
+    }
+
+    Ok(Status { state, peers })
+}
+
+/// Pings every online peer at once,
+/// setting the latency of each which responds.
+async fn ping_all(socket: &Path, peers: &mut [Peer], ips: &HashMap<String, String>) {
+    let mut pings = JoinSet::new();
+
+    for (index, peer) in peers.iter().enumerate() {
+        let Some(ip) = ips.get(&peer.id).filter(|_| peer.online) else {
+            continue;
+        };
+
+        let socket = socket.to_path_buf();
+        let ip = ip.clone();
+
+        pings.spawn(async move {
+            let latency = timeout(PING_TIMEOUT, ping(&socket, &ip)).await;
+            (index, latency)
+        });
+    }
+
+    while let Some(res) = pings.join_next().await {
+        match res {
+            Ok((index, Ok(Ok(latency)))) => peers[index].latency = Some(latency),
+            Ok((index, Ok(Err(err)))) => debug!("Failed to ping {}: {err:?}", peers[index].name),
+            Ok((index, Err(_))) => debug!("Timed out pinging {}", peers[index].name),
+            Err(err) => debug!("Ping task failed: {err:?}"),
+        }
+    }
+}
+
+async fn ping(socket: &Path, ip: &str) -> Result<Duration> {
+    let path = format!("/localapi/v0/ping?ip={ip}&type=disco");
+    let body = unix_http::request(socket, HOST, "POST", &path, None).await?;
+    let result: PingResult = serde_json::from_slice(&body)?;
+
+    match result.latency_seconds {
+        Some(latency) if result.err.is_empty() => Ok(Duration::from_secs_f64(latency)),
+        _ => Err(Report::msg(result.err)),
+    }
+}
+
+/// Updates the preferences with the JSON object.
+async fn set_prefs(socket: &Path, prefs: &serde_json::Value) -> Result<()> {
+    let body = prefs.to_string();
+    unix_http::request(socket, HOST, "PATCH", "/localapi/v0/prefs", Some(&body)).await?;
+    Ok(())
+}
+
+pub async fn set_connected(socket: &Path, connected: bool) -> Result<()> {
+    set_prefs(
+        socket,
+        &json!({ "WantRunningSet": true, "WantRunning": connected }),
+    )
+    .await
+}
+
+pub async fn set_exit_node(socket: &Path, id: Option<&str>) -> Result<()> {
+    set_prefs(
+        socket,
+        &json!({ "ExitNodeIDSet": true, "ExitNodeID": id.unwrap_or_default() }),
+    )
+    .await
+}
@@ -0,0 +1,120 @@
+use super::backend::{Peer, State, Status};
+use color_eyre::{Report, Result};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::process::Command;
+use tracing::debug;
+
+/// The time since the latest handshake after which a peer is considered offline.
+/// WireGuard re-keys every two minutes while traffic is flowing.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(180);
+
+pub async fn status(interface: &str) -> Result<Status> {
+    if !Path::new("/sys/class/net").join(interface).exists() {
+        return Ok(Status {
+            state: State::Disconnected,
+            peers: vec![],
+        });
+    }
+
+    let output = Command::new("wg")
+        .args(["show", interface, "dump"])
+        .output()
+        .await?;
+
+    let peers = if output.status.success() {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        parse_dump(&String::from_utf8_lossy(&output.stdout), now)
+    } else {
+        // reading peers usually requires root
+        debug!(
+            "Failed to read peers of {interface}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        vec![]
+    };
+
+    Ok(Status {
+        state: State::Connected,
+        peers,
+    })
+}
+
+/// Parses the peers from the output of `wg show <interface> dump`.
+///
+/// The first line describes the interface.
+/// Each following line is a tab-separated peer:
+/// public key, preshared key, endpoint, allowed IPs, latest handshake,
+/// bytes received, bytes sent and keepalive.
+fn parse_dump(output: &str, now: Duration) -> Vec<Peer> {
+    output
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let [public_key, _, endpoint, allowed_ips, handshake, ..] = fields[..] else {
+                return None;
+            };
+
+            let handshake = Duration::from_secs(handshake.parse().unwrap_or_default());
+            let online = !handshake.is_zero() && now.saturating_sub(handshake) < HANDSHAKE_TIMEOUT;
+
+            let name = if endpoint == "(none)" {
+                allowed_ips
+            } else {
+                endpoint
+            };
+
+            Some(Peer {
+                id: public_key.to_string(),
+                name: name.to_string(),
+                online,
+                latency: None,
+                exit_node: false,
+                exit_node_option: false,
+            })
+        })
+        .collect()
+}
+
+/// Brings the interface up or down using `wg-quick`,
+/// asking for authentication with polkit.
+pub async fn set_connected(interface: &str, connected: bool) -> Result<()> {
+    let action = if connected { "up" } else { "down" };
+
+    let output = Command::new("pkexec")
+        .args(["wg-quick", action, interface])
+        .output()
+        .await?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Report::msg(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_dump() {
+        let output = "privkey\tpubkey\t51820\toff\n\
+                      peer1\t(none)\t203.0.113.1:51820\t10.0.0.2/32\t1000\t100\t200\toff\n\
+                      peer2\t(none)\t(none)\t10.0.0.3/32\t0\t0\t0\toff\n";
+
+        let peers = parse_dump(output, Duration::from_secs(1060));
+
+        assert_eq!(peers.len(), 2);
+        assert_eq!(peers[0].name, "203.0.113.1:51820");
+        assert!(peers[0].online);
+        assert_eq!(peers[1].name, "10.0.0.3/32");
+        assert!(!peers[1].online);
+    }
+}
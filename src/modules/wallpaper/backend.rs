@@ -0,0 +1,98 @@
+use color_eyre::{Report, Result};
+use serde::Deserialize;
+use std::ffi::OsString;
+use std::path::Path;
+use tokio::process::Command;
+use tracing::debug;
+
+/// The program used to set the wallpaper.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+pub enum Backend {
+    /// [swww](https://github.com/LGFae/swww), using `swww img`.
+    #[default]
+    Swww,
+    /// [hyprpaper](https://github.com/hyprwm/hyprpaper), using `hyprctl hyprpaper reload`.
+    Hyprpaper,
+    /// [wpaperd](https://github.com/danyspin97/wpaperd), using `wpaperctl`.
+    ///
+    /// wpaperd picks wallpapers from its own configured directory,
+    /// so only supports cycling to the next wallpaper.
+    Wpaperd,
+}
+
+impl Backend {
+    /// Sets the wallpaper to the image at `path`
+    /// on `output`, or on all outputs if `None`.
+    pub async fn set(self, path: &Path, output: Option<&str>) -> Result<()> {
+        match self {
+            Self::Swww => {
+                let mut args = vec![OsString::from("img")];
+                if let Some(output) = output {
+                    args.extend([OsString::from("--outputs"), OsString::from(output)]);
+                }
+                args.push(path.into());
+
+                run("swww", args).await
+            }
+            Self::Hyprpaper => {
+                let mut target = OsString::from(output.unwrap_or_default());
+                target.push(",");
+                target.push(path);
+
+                run(
+                    "hyprctl",
+                    [OsString::from("hyprpaper"), "reload".into(), target],
+                )
+                .await
+            }
+            Self::Wpaperd => Err(Report::msg(
+                "wpaperd does not support setting a specific wallpaper",
+            )),
+        }
+    }
+
+    /// Whether the backend picks the next wallpaper itself,
+    /// rather than the module cycling through its directory.
+    pub fn cycles_itself(self) -> bool {
+        self == Self::Wpaperd
+    }
+
+    /// Switches to the backend's next wallpaper
+    /// on `output`, or on all outputs if `None`.
+    ///
+    /// Only supported where [`Backend::cycles_itself`] is true.
+    pub async fn next(self, output: Option<&str>) -> Result<()> {
+        match self {
+            Self::Wpaperd => {
+                let mut args = vec![OsString::from("next-wallpaper")];
+                args.extend(output.map(OsString::from));
+
+                run("wpaperctl", args).await
+            }
+            Self::Swww | Self::Hyprpaper => Err(Report::msg(format!(
+                "{self:?} does not pick wallpapers itself"
+            ))),
+        }
+    }
+}
+
+/// Runs the program, waiting for it to exit.
+/// Returns an error containing `stderr` if it fails.
+async fn run(program: &str, args: impl IntoIterator<Item = OsString>) -> Result<()> {
+    let args = args.into_iter().collect::<Vec<_>>();
+    debug!("Running {program} with args: {args:?}");
+
+    let output = Command::new(program).args(&args).output().await?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(Report::msg(format!(
+            "{program} exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
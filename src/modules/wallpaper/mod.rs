@@ -0,0 +1,472 @@
+mod backend;
+
+use self::backend::Backend;
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
+use crate::config::{CommonConfig, LayoutConfig};
+use crate::image::IconButton;
+use crate::modules::{
+    Module, ModuleInfo, ModuleParts, ModulePopup, ModuleUpdateEvent, PopupButton, WidgetContext,
+};
+use crate::{module_impl, poll, spawn, spawn_blocking};
+use color_eyre::Result;
+use dirs::{home_dir, picture_dir};
+use gtk::gdk::{Monitor, Texture};
+use gtk::gdk_pixbuf::Pixbuf;
+use gtk::prelude::*;
+use gtk::{
+    Button, ContentFit, FlowBox, Orientation, Picture, PolicyType, ScrolledWindow, SelectionMode,
+    ToggleButton,
+};
+use serde::Deserialize;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, error, warn};
+
+/// File extensions of images which can be used as wallpapers.
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp", "gif", "bmp"];
+
+#[derive(Debug, Deserialize, Clone)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct WallpaperModule {
+    /// The directory containing the wallpapers.
+    /// Images directly inside it are shown in the popup, in name order.
+    ///
+    /// **Default**: `~/Pictures/Wallpapers`
+    directory: PathBuf,
+
+    /// The program used to set the wallpaper.
+    /// One of `swww`, `hyprpaper` or `wpaperd`.
+    ///
+    /// The chosen program's daemon must already be running.
+    /// wpaperd only supports cycling to its next wallpaper,
+    /// not picking one from the popup.
+    ///
+    /// **Default**: `swww`
+    backend: Backend,
+
+    /// The time in seconds between automatically cycling
+    /// to the next wallpaper on every output.
+    /// Set to `0` to disable.
+    ///
+    /// **Default**: `0`
+    cycle_interval: u64,
+
+    /// The icon to show on the bar widget button.
+    /// Supports [image](images) icons.
+    ///
+    /// **Default**: `󰸉`
+    icon: String,
+
+    /// The size to render the icon at.
+    /// Note this only applies to image-type icons.
+    ///
+    /// **Default**: `32`
+    icon_size: i32,
+
+    /// The size to render wallpaper thumbnails at in the popup.
+    ///
+    /// **Default**: `128`
+    thumbnail_size: i32,
+
+    /// The number of thumbnails to show per row in the popup.
+    ///
+    /// **Default**: `3`
+    columns: u32,
+
+    // -- Common --
+    /// See [layout options](module-level-options#layout)
+    #[serde(default, flatten)]
+    layout: LayoutConfig,
+
+    /// See [common options](module-level-options#common-options).
+    #[serde(flatten)]
+    pub common: Option<CommonConfig>,
+}
+
+impl Default for WallpaperModule {
+    fn default() -> Self {
+        Self {
+            directory: default_directory(),
+            backend: Backend::default(),
+            cycle_interval: 0,
+            icon: "󰸉".to_string(),
+            icon_size: 32,
+            thumbnail_size: 128,
+            columns: 3,
+            layout: LayoutConfig::default(),
+            common: Some(CommonConfig::default()),
+        }
+    }
+}
+
+fn default_directory() -> PathBuf {
+    picture_dir()
+        .or_else(|| home_dir().map(|dir| dir.join("Pictures")))
+        .unwrap_or_default()
+        .join("Wallpapers")
+}
+
+#[derive(Debug, Clone)]
+pub enum ControllerEvent {
+    /// The wallpapers found in the directory.
+    Images(Vec<PathBuf>),
+    /// The wallpaper was set on the output, or all outputs if `None`.
+    Current(Option<String>, PathBuf),
+}
+
+#[derive(Debug, Clone)]
+pub enum UiEvent {
+    /// Sets the wallpaper on the output, or all outputs if `None`.
+    Set(PathBuf, Option<String>),
+    /// Cycles to the next wallpaper on the output, or all outputs if `None`.
+    Next(Option<String>),
+}
+
+impl Module<Button> for WallpaperModule {
+    type SendMessage = ControllerEvent;
+    type ReceiveMessage = UiEvent;
+
+    module_impl!("wallpaper");
+
+    fn spawn_controller(
+        &self,
+        _info: &ModuleInfo,
+        context: &WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        mut rx: mpsc::Receiver<Self::ReceiveMessage>,
+    ) -> Result<()> {
+        let tx = context.tx.clone();
+        let directory = expand_home(&self.directory);
+        let backend = self.backend;
+
+        spawn(async move {
+            let images = spawn_blocking(move || list_images(&directory))
+                .await
+                .unwrap_or_default();
+
+            debug!("Found {} wallpapers", images.len());
+            tx.send_update(ControllerEvent::Images(images.clone()))
+                .await;
+
+            // index of the current wallpaper on each output,
+            // with `None` for when set on every output
+            let mut current = HashMap::<Option<String>, usize>::new();
+
+            while let Some(event) = rx.recv().await {
+                let (path, output) = match event {
+                    UiEvent::Set(path, output) => (path, output),
+                    UiEvent::Next(output) if backend.cycles_itself() => {
+                        if let Err(err) = backend.next(output.as_deref()).await {
+                            error!("Failed to cycle wallpaper: {err:?}");
+                        }
+                        continue;
+                    }
+                    UiEvent::Next(output) => {
+                        if images.is_empty() {
+                            warn!("No wallpapers to cycle through");
+                            continue;
+                        }
+
+                        let index = current
+                            .get(&output)
+                            .or_else(|| current.get(&None))
+                            .map_or(0, |index| (index + 1) % images.len());
+
+                        (images[index].clone(), output)
+                    }
+                };
+
+                if let Err(err) = backend.set(&path, output.as_deref()).await {
+                    error!("Failed to set wallpaper: {err:?}");
+                    continue;
+                }
+
+                if let Some(index) = images.iter().position(|image| image == &path) {
+                    // setting every output overrides any per-output wallpapers
+                    if output.is_none() {
+                        current.clear();
+                    }
+
+                    current.insert(output.clone(), index);
+                }
+
+                tx.send_update(ControllerEvent::Current(output, path)).await;
+            }
+        });
+
+        if self.cycle_interval > 0 {
+            let tx = context.controller_tx.clone();
+            let interval = Duration::from_secs(self.cycle_interval);
+
+            spawn(async move {
+                loop {
+                    poll::sleep(interval).await;
+                    tx.send_expect(UiEvent::Next(None)).await;
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    fn into_widget(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Result<ModuleParts<Button>> {
+        let button = IconButton::new(&self.icon, self.icon_size, context.image_provider());
+
+        button.label().set_justify(self.layout.justify.into());
+        button.add_css_class("btn");
+
+        let tx = context.tx.clone();
+        button.connect_clicked(move |button| {
+            tx.send_spawn(ModuleUpdateEvent::TogglePopup(button.popup_id()));
+        });
+
+        context
+            .subscribe()
+            .recv_glib(button.deref(), |button, event| {
+                if let ControllerEvent::Current(_, path) = event {
+                    button.set_tooltip_text(file_name(&path).as_deref());
+                }
+            });
+
+        let popup = self
+            .into_popup(context, info)
+            .into_popup_parts(vec![&button]);
+
+        Ok(ModuleParts::new(button.deref().clone(), popup))
+    }
+
+    fn into_popup(
+        self,
+        context: WidgetContext<Self::SendMessage, Self::ReceiveMessage>,
+        info: &ModuleInfo,
+    ) -> Option<gtk::Box>
+    where
+        Self: Sized,
+    {
+        let container = gtk::Box::new(Orientation::Vertical, 10);
+
+        // the output the wallpaper is set on,
+        // with `None` meaning every output
+        let selected_output = Rc::new(RefCell::new(None::<String>));
+
+        let header = gtk::Box::new(Orientation::Horizontal, 5);
+        header.add_css_class("header");
+        container.append(&header);
+
+        let outputs = gtk::Box::new(Orientation::Horizontal, 0);
+        outputs.add_css_class("outputs");
+        outputs.set_hexpand(true);
+        header.append(&outputs);
+
+        let all_button = ToggleButton::with_label("All");
+        all_button.add_css_class("btn");
+        all_button.set_active(true);
+        outputs.append(&all_button);
+
+        {
+            let selected_output = selected_output.clone();
+            all_button.connect_toggled(move |button| {
+                if button.is_active() {
+                    selected_output.replace(None);
+                }
+            });
+        }
+
+        let monitors = info.monitor.display().monitors();
+        for monitor in monitors.iter::<Monitor>().flatten() {
+            let Some(connector) = monitor.connector() else {
+                continue;
+            };
+
+            let button = ToggleButton::with_label(&connector);
+            button.add_css_class("btn");
+            button.set_group(Some(&all_button));
+            outputs.append(&button);
+
+            let selected_output = selected_output.clone();
+            button.connect_toggled(move |button| {
+                if button.is_active() {
+                    selected_output.replace(Some(connector.to_string()));
+                }
+            });
+        }
+
+        // nothing to pick between with a single output
+        outputs.set_visible(monitors.n_items() > 1);
+
+        let next_button = Button::with_label("Next");
+        next_button.add_css_class("btn-next");
+        header.append(&next_button);
+
+        {
+            let tx = context.controller_tx.clone();
+            let selected_output = selected_output.clone();
+            next_button.connect_clicked(move |_| {
+                tx.send_spawn(UiEvent::Next(selected_output.borrow().clone()));
+            });
+        }
+
+        let grid = FlowBox::builder()
+            .selection_mode(SelectionMode::None)
+            .max_children_per_line(self.columns.max(1))
+            .min_children_per_line(self.columns.max(1))
+            .homogeneous(true)
+            .build();
+        grid.add_css_class("wallpapers");
+
+        let scrolled = ScrolledWindow::builder()
+            .hscrollbar_policy(PolicyType::Never)
+            .propagate_natural_height(true)
+            .max_content_height(self.thumbnail_size * 3)
+            .child(&grid)
+            .build();
+        container.append(&scrolled);
+
+        // thumbnails are loaded the first time the popup opens,
+        // so large wallpapers are not decoded if it is never used
+        let pending = Rc::new(RefCell::new(Vec::<(PathBuf, Picture)>::new()));
+        let thumbnail_size = self.thumbnail_size;
+
+        {
+            let pending = pending.clone();
+            container.connect_map(move |_| {
+                for (path, picture) in pending.take() {
+                    load_thumbnail(path, picture, thumbnail_size);
+                }
+            });
+        }
+
+        let mut buttons = HashMap::<PathBuf, Button>::new();
+        // the wallpaper on each output, with `None` for every output
+        let mut current = HashMap::<Option<String>, PathBuf>::new();
+
+        context.subscribe().recv_glib(&grid, move |grid, event| {
+            match event {
+                ControllerEvent::Images(images) => {
+                    grid.remove_all();
+                    buttons.clear();
+
+                    for path in images {
+                        let picture = Picture::new();
+                        picture.set_content_fit(ContentFit::Cover);
+                        picture.set_size_request(thumbnail_size, thumbnail_size);
+
+                        let button = Button::new();
+                        button.add_css_class("thumbnail");
+                        button.set_child(Some(&picture));
+                        button.set_tooltip_text(file_name(&path).as_deref());
+
+                        {
+                            let tx = context.controller_tx.clone();
+                            let selected_output = selected_output.clone();
+                            let path = path.clone();
+                            button.connect_clicked(move |_| {
+                                tx.send_spawn(UiEvent::Set(
+                                    path.clone(),
+                                    selected_output.borrow().clone(),
+                                ));
+                            });
+                        }
+
+                        grid.append(&button);
+
+                        if grid.is_mapped() {
+                            load_thumbnail(path.clone(), picture, thumbnail_size);
+                        } else {
+                            pending.borrow_mut().push((path.clone(), picture));
+                        }
+
+                        buttons.insert(path, button);
+                    }
+                }
+                ControllerEvent::Current(output, path) => {
+                    if output.is_none() {
+                        current.clear();
+                    }
+
+                    current.insert(output, path);
+                }
+            }
+
+            for (path, button) in &buttons {
+                if current.values().any(|current| current == path) {
+                    button.add_css_class("active");
+                } else {
+                    button.remove_css_class("active");
+                }
+            }
+        });
+
+        Some(container)
+    }
+}
+
+/// Replaces a leading `~` with the user's home directory.
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), home_dir()) {
+        (Ok(rest), Some(home)) => home.join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Gets the images directly inside `directory`, sorted by name.
+fn list_images(directory: &Path) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(err) => {
+            error!(
+                "Failed to read wallpaper directory '{}': {err:?}",
+                directory.display()
+            );
+            return vec![];
+        }
+    };
+
+    let mut images = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        })
+        .collect::<Vec<_>>();
+
+    images.sort();
+    images
+}
+
+/// Decodes a downscaled copy of the image on a blocking thread,
+/// setting it on the picture once ready.
+fn load_thumbnail(path: PathBuf, picture: Picture, size: i32) {
+    let texture = spawn_blocking(move || {
+        Pixbuf::from_file_at_scale(&path, size * 2, size * 2, true)
+            .map(|pixbuf| Texture::for_pixbuf(&pixbuf))
+            .map_err(|err| (path, err))
+    });
+
+    glib::spawn_future_local(async move {
+        match texture.await {
+            Ok(Ok(texture)) => picture.set_paintable(Some(&texture)),
+            Ok(Err((path, err))) => {
+                warn!("Failed to load thumbnail for '{}': {err}", path.display());
+            }
+            Err(err) => error!("{err:?}"),
+        }
+    });
+}
+
+fn file_name(path: &Path) -> Option<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+}
@@ -1,18 +1,29 @@
+use super::UiEvent;
 use super::open_state::OpenState;
 use crate::channels::AsyncSenderExt;
-use crate::image::IconButton;
+use crate::clients::compositor::WindowMatch;
+use crate::gtk_helpers::{IronbarGlibExt, IronbarGtkExt};
+use crate::image::{self, IconButton, IconLabel};
 use crate::modules::workspaces::WorkspaceItemContext;
 use glib::signal::SignalHandlerId;
 use gtk::Button as GtkButton;
+use gtk::gdk::DragAction;
 use gtk::prelude::*;
+use gtk::{ContentFit, DropTarget, Orientation, Picture};
+use std::cell::Cell;
+use std::rc::Rc;
 use tokio::sync::mpsc;
 
 #[derive(Debug)]
 pub struct Button {
-    button: IconButton,
-    workspace_id: i64,
+    button: GtkButton,
+    label: Label,
+    /// Icons of the applications open on the workspace,
+    /// if enabled.
+    app_icons: Option<AppIcons>,
+    workspace_id: Rc<Cell<i64>>,
     conn_id: Option<SignalHandlerId>,
-    tx: mpsc::Sender<i64>,
+    tx: mpsc::Sender<UiEvent>,
 }
 
 impl Button {
@@ -25,19 +36,49 @@ impl Button {
     ) -> Self {
         let label = context.format_label(name, index);
 
-        let button = IconButton::new(&label, context.icon_size, context.image_provider.clone());
+        let (button, label, app_icons) = match context.app_icon_size {
+            Some(app_icon_size) => {
+                let button = GtkButton::new();
+
+                let container = gtk::Box::new(context.orientation, 0);
+                button.set_child(Some(&container));
+
+                let label = IconLabel::new(&label, context.icon_size, &context.image_provider);
+                label.add_css_class("label");
+                container.append(&*label);
+
+                let app_icons =
+                    AppIcons::new(app_icon_size, context.orientation, &context.image_provider);
+                container.append(&app_icons.container);
+
+                (button, Label::Icon(label), Some(app_icons))
+            }
+            None => {
+                let button =
+                    IconButton::new(&label, context.icon_size, context.image_provider.clone());
+
+                ((*button).clone(), Label::Button(button), None)
+            }
+        };
+
         button.set_widget_name(name);
         button.add_css_class("item");
+        button.set_accessible_label(&format!("Workspace {name}"));
 
         let tx = context.tx.clone();
 
         let conn_id = button.connect_clicked(move |_item| {
-            tx.send_spawn(id);
+            tx.send_spawn(UiEvent::Focus(id));
         });
 
+        let workspace_id = Rc::new(Cell::new(id));
+        connect_window_drop(&button, workspace_id.clone(), &context.tx);
+
         let btn = Self {
             button,
-            workspace_id: id,
+            label,
+            app_icons,
+            workspace_id,
             conn_id: Some(conn_id),
             tx: context.tx.clone(),
         };
@@ -51,7 +92,18 @@ impl Button {
     }
 
     pub fn set_label(&self, label: &str) {
-        self.button.set_label(label);
+        match &self.label {
+            Label::Button(button) => button.set_label(label),
+            Label::Icon(icon_label) => icon_label.set_label(Some(label)),
+        }
+    }
+
+    /// Shows icons for the applications with the IDs.
+    /// Does nothing if app icons are disabled.
+    pub fn set_app_ids(&mut self, app_ids: &[String]) {
+        if let Some(app_icons) = &mut self.app_icons {
+            app_icons.set_app_ids(app_ids);
+        }
     }
 
     pub fn set_open_state(&self, open_state: OpenState) {
@@ -83,18 +135,147 @@ impl Button {
     }
 
     pub fn workspace_id(&self) -> i64 {
-        self.workspace_id
+        self.workspace_id.get()
     }
 
     pub fn set_workspace_id(&mut self, id: i64) {
-        self.workspace_id = id;
+        self.workspace_id.set(id);
         if let Some(conn_id) = self.conn_id.take() {
             self.button.disconnect(conn_id);
         }
         let tx = self.tx.clone();
         let conn_id = self.button.connect_clicked(move |_item| {
-            tx.send_spawn(id);
+            tx.send_spawn(UiEvent::Focus(id));
         });
         self.conn_id = Some(conn_id);
     }
 }
+
+/// Allows windows to be moved to the workspace
+/// by dropping buttons tagged with a [`WindowMatch`] onto it,
+/// such as those from the launcher and taskbar.
+fn connect_window_drop(
+    button: &GtkButton,
+    workspace_id: Rc<Cell<i64>>,
+    tx: &mpsc::Sender<UiEvent>,
+) {
+    let drop_target = DropTarget::new(GtkButton::static_type(), DragAction::MOVE);
+
+    {
+        let button = button.clone();
+        drop_target.connect_enter(move |_, _, _| {
+            button.add_css_class("drop-target");
+            DragAction::MOVE
+        });
+    }
+
+    {
+        let button = button.clone();
+        drop_target.connect_leave(move |_| button.remove_css_class("drop-target"));
+    }
+
+    {
+        let button = button.clone();
+        let tx = tx.clone();
+
+        drop_target.connect_drop(move |_, value, _, _| {
+            button.remove_css_class("drop-target");
+
+            let Ok(source) = value.get::<GtkButton>() else {
+                return false;
+            };
+
+            // favourites which are not open have no ID
+            let workspace_id = workspace_id.get();
+            if workspace_id < 0 {
+                return false;
+            }
+
+            let Some(window) = source.get_tag::<WindowMatch>(WindowMatch::TAG).cloned() else {
+                return false;
+            };
+
+            tx.send_spawn(UiEvent::MoveWindow {
+                window,
+                workspace_id,
+            });
+
+            true
+        });
+    }
+
+    button.add_controller(drop_target);
+}
+
+/// The workspace label,
+/// which is either the button itself or a label within it.
+#[derive(Debug)]
+enum Label {
+    Button(IconButton),
+    Icon(IconLabel),
+}
+
+/// A container of application icons,
+/// showing one icon per application.
+#[derive(Debug)]
+struct AppIcons {
+    container: gtk::Box,
+    app_ids: Vec<String>,
+    size: i32,
+    image_provider: image::Provider,
+}
+
+impl AppIcons {
+    fn new(size: i32, orientation: Orientation, image_provider: &image::Provider) -> Self {
+        let container = gtk::Box::new(orientation, 0);
+        container.add_css_class("app-icons");
+        container.set_visible(false);
+
+        Self {
+            container,
+            app_ids: vec![],
+            size,
+            image_provider: image_provider.clone(),
+        }
+    }
+
+    fn set_app_ids(&mut self, app_ids: &[String]) {
+        let mut unique = Vec::<String>::with_capacity(app_ids.len());
+        for app_id in app_ids {
+            if !unique.contains(app_id) {
+                unique.push(app_id.clone());
+            }
+        }
+
+        // icons are only reloaded when the set of applications changes
+        if unique == self.app_ids {
+            return;
+        }
+
+        while let Some(child) = self.container.first_child() {
+            self.container.remove(&child);
+        }
+
+        for app_id in &unique {
+            let picture = Picture::builder()
+                .content_fit(ContentFit::ScaleDown)
+                .build();
+            picture.add_css_class("app-icon");
+            picture.set_tooltip_text(Some(app_id));
+            self.container.append(&picture);
+
+            let image_provider = self.image_provider.clone();
+            let app_id = app_id.clone();
+            let size = self.size;
+
+            glib::spawn_future_local(async move {
+                image_provider
+                    .load_into_picture_silent(&app_id, size, true, &picture)
+                    .await;
+            });
+        }
+
+        self.container.set_visible(!unique.is_empty());
+        self.app_ids = unique;
+    }
+}
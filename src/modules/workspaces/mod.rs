@@ -4,7 +4,9 @@ mod open_state;
 
 use self::button::Button;
 use crate::channels::{AsyncSenderExt, BroadcastReceiverExt};
-use crate::clients::compositor::{Workspace, WorkspaceClient, WorkspaceUpdate};
+use crate::clients::compositor::{
+    WindowMatch, Workspace, WorkspaceClient, WorkspaceUpdate, WorkspaceWindows,
+};
 use crate::config::{CommonConfig, LayoutConfig, default};
 use crate::gtk_helpers::{IronbarGlibExt, IronbarGtkExt};
 use crate::modules::workspaces::button_map::{ButtonMap, Identifier};
@@ -12,10 +14,12 @@ use crate::modules::workspaces::open_state::OpenState;
 use crate::modules::{Module, ModuleInfo, ModuleParts, WidgetContext};
 use crate::{image, module_impl, spawn};
 use color_eyre::{Report, Result};
+use gtk::Orientation;
 use gtk::prelude::*;
 use serde::Deserialize;
 use std::cmp::Ordering;
 use std::collections::HashMap;
+use tokio::sync::broadcast::error::RecvError;
 use tokio::sync::mpsc;
 use tracing::{debug, trace, warn};
 
@@ -158,6 +162,18 @@ pub struct WorkspacesModule {
     /// **Default**: `32`
     icon_size: i32,
 
+    /// Whether to show icons of the applications
+    /// open on each workspace next to its label.
+    /// One icon is shown per application.
+    ///
+    /// **Default**: `false`
+    app_icons: bool,
+
+    /// The size to render application icons at.
+    ///
+    /// **Default**: `16`
+    app_icon_size: i32,
+
     /// The format string for named workspaces.
     ///
     /// The following placeholders are supported:
@@ -192,6 +208,8 @@ impl Default for WorkspacesModule {
             all_monitors: false,
             sort: SortOrder::default(),
             icon_size: default::IconSize::Normal as i32,
+            app_icons: false,
+            app_icon_size: default::IconSize::Tiny as i32,
             format: Format::default(),
             layout: LayoutConfig::default(),
             common: Some(CommonConfig::default()),
@@ -199,12 +217,34 @@ impl Default for WorkspacesModule {
     }
 }
 
+#[derive(Debug, Clone)]
+pub enum UpdateEvent {
+    Workspace(WorkspaceUpdate),
+    /// The windows open on each workspace.
+    /// Only sent when `app_icons` is enabled.
+    Windows(WorkspaceWindows),
+}
+
+#[derive(Debug)]
+pub enum UiEvent {
+    Focus(i64),
+    /// Moves the matching windows to the workspace,
+    /// after being dropped onto its button.
+    MoveWindow {
+        window: WindowMatch,
+        workspace_id: i64,
+    },
+}
+
 #[derive(Debug, Clone)]
 pub struct WorkspaceItemContext {
     name_map: HashMap<String, String>,
     icon_size: i32,
+    /// The size of application icons, if enabled.
+    app_icon_size: Option<i32>,
+    orientation: Orientation,
     image_provider: image::Provider,
-    tx: mpsc::Sender<i64>,
+    tx: mpsc::Sender<UiEvent>,
     format_named: String,
     format_unnamed: String,
 }
@@ -277,11 +317,19 @@ fn reorder_workspaces(container: &gtk::Box, sort_order: SortOrder) {
 }
 
 impl Module<gtk::Box> for WorkspacesModule {
-    type SendMessage = WorkspaceUpdate;
-    type ReceiveMessage = i64;
+    type SendMessage = UpdateEvent;
+    type ReceiveMessage = UiEvent;
 
     module_impl!("workspaces");
 
+    fn coalesce_key(update: &Self::SendMessage) -> Option<String> {
+        match update {
+            // each update is the full state
+            UpdateEvent::Windows(_) => Some("windows".to_string()),
+            UpdateEvent::Workspace(_) => None,
+        }
+    }
+
     fn spawn_controller(
         &self,
         _info: &ModuleInfo,
@@ -290,15 +338,37 @@ impl Module<gtk::Box> for WorkspacesModule {
     ) -> Result<()> {
         let tx = context.tx.clone();
         let client = context.ironbar.clients.borrow_mut().workspaces()?;
+
+        if self.app_icons {
+            let tx = context.tx.clone();
+            let mut srx = client.subscribe_windows()?;
+
+            spawn(async move {
+                loop {
+                    match srx.recv_tracked("workspaces").await {
+                        Ok(windows) => tx.send_update(UpdateEvent::Windows(windows)).await,
+                        Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+
         // Subscribe & send events
         spawn(async move {
             let mut srx = client.subscribe();
 
             trace!("Set up workspace subscription");
 
-            while let Ok(payload) = srx.recv().await {
-                debug!("Received update: {payload:?}");
-                tx.send_update(payload).await;
+            loop {
+                match srx.recv_tracked("workspaces").await {
+                    Ok(payload) => {
+                        debug!("Received update: {payload:?}");
+                        tx.send_update(UpdateEvent::Workspace(payload)).await;
+                    }
+                    Err(RecvError::Lagged(_)) => {}
+                    Err(RecvError::Closed) => break,
+                }
             }
         });
 
@@ -308,8 +378,14 @@ impl Module<gtk::Box> for WorkspacesModule {
         spawn(async move {
             trace!("Setting up UI event handler");
 
-            while let Some(id) = rx.recv().await {
-                client.focus(id);
+            while let Some(event) = rx.recv().await {
+                match event {
+                    UiEvent::Focus(id) => client.focus(id),
+                    UiEvent::MoveWindow {
+                        window,
+                        workspace_id,
+                    } => client.move_window(&window, workspace_id),
+                }
             }
 
             Ok::<(), Report>(())
@@ -332,7 +408,9 @@ impl Module<gtk::Box> for WorkspacesModule {
         let item_context = WorkspaceItemContext {
             name_map: self.name_map.clone(),
             icon_size: self.icon_size,
-            image_provider: context.ironbar.image_provider(),
+            app_icon_size: self.app_icons.then_some(self.app_icon_size),
+            orientation: self.layout.orientation(info),
+            image_provider: context.image_provider(),
             tx: context.controller_tx.clone(),
             format_named,
             format_unnamed,
@@ -426,9 +504,8 @@ impl Module<gtk::Box> for WorkspacesModule {
                 };
             }
 
-            context
-                .subscribe()
-                .recv_glib((), move |(), event| match event {
+            let mut on_update =
+                move |event: WorkspaceUpdate, button_map: &mut ButtonMap| match event {
                     WorkspaceUpdate::Init(workspaces) => {
                         if has_initialized {
                             return;
@@ -533,7 +610,30 @@ impl Module<gtk::Box> for WorkspacesModule {
                     // workspaces.
                     // https://github.com/JakeStanger/ironbar/issues/1196#issuecomment-3407036546
                     _ => warn!("ignoring workspace event received before initialization"),
-                });
+                };
+
+            let app_icons = self.app_icons;
+            let mut windows = WorkspaceWindows::default();
+
+            context.subscribe().recv_glib((), move |(), event| {
+                match event {
+                    UpdateEvent::Workspace(update) => on_update(update, &mut button_map),
+                    UpdateEvent::Windows(new_windows) => windows = new_windows,
+                }
+
+                // buttons may have been added or changed workspace,
+                // so all are updated
+                if app_icons {
+                    for button in button_map.values_mut() {
+                        let app_ids = windows
+                            .0
+                            .get(&button.workspace_id())
+                            .map_or(&[][..], Vec::as_slice);
+
+                        button.set_app_ids(app_ids);
+                    }
+                }
+            });
         }
 
         Ok(ModuleParts {
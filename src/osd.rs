@@ -0,0 +1,291 @@
+use crate::Ironbar;
+use crate::channels::{AsyncSenderExt, BroadcastReceiverExt, MpscReceiverExt};
+#[cfg(feature = "keyboard")]
+use crate::clients::compositor::KeyboardLayoutUpdate;
+#[cfg(feature = "volume")]
+use crate::clients::volume::{self, VolumeClient};
+use crate::config::{OsdConfig, OsdEvent, OsdPosition};
+use crate::gtk_helpers::IronbarLabelExt;
+use crate::spawn;
+use glib::SourceId;
+use gtk::gdk::Monitor;
+use gtk::prelude::*;
+use gtk::{Label, Orientation, ProgressBar, Window};
+use gtk_layer_shell::{Edge, LayerShell};
+use std::cell::RefCell;
+use std::rc::Rc;
+#[cfg(feature = "volume")]
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc;
+#[cfg(feature = "keyboard")]
+use tracing::error;
+#[cfg(any(not(feature = "volume"), not(feature = "keyboard")))]
+use tracing::warn;
+
+#[derive(Debug, Clone)]
+enum OsdUpdate {
+    Volume { percent: f64, muted: bool },
+    Brightness(f64),
+    KeyboardLayout(String),
+}
+
+/// An overlay surface which is briefly shown on the bar's monitor
+/// whenever one of the configured settings changes.
+#[derive(Debug)]
+pub struct Osd {
+    window: Window,
+}
+
+impl Osd {
+    pub fn new(monitor: &Monitor, config: OsdConfig, ironbar: &Ironbar) -> Self {
+        let window = Window::new();
+        window.add_css_class("osd-window");
+        setup_layer_shell(&window, monitor, config.position, config.margin);
+
+        // the osd is for display only, so should never take the pointer
+        window.connect_realize(|window| {
+            if let Some(surface) = window.surface() {
+                surface.set_input_region(&gtk::cairo::Region::create());
+            }
+        });
+
+        let container = gtk::Box::new(Orientation::Horizontal, 10);
+        container.add_css_class("osd");
+
+        let icon = Label::builder().use_markup(true).build();
+        icon.add_css_class("icon");
+        container.append(&icon);
+
+        let level = ProgressBar::new();
+        level.add_css_class("level");
+        level.set_valign(gtk::Align::Center);
+        level.set_hexpand(true);
+        container.append(&level);
+
+        let value = Label::builder().use_markup(true).build();
+        value.add_css_class("value");
+        container.append(&value);
+
+        window.set_child(Some(&container));
+
+        let (tx, rx) = mpsc::channel(16);
+
+        if config.events.contains(&OsdEvent::Volume) {
+            #[cfg(feature = "volume")]
+            watch_volume(
+                ironbar.clients.borrow_mut().volume(config.volume_backend),
+                tx.clone(),
+            );
+
+            #[cfg(not(feature = "volume"))]
+            warn!("Volume changes in the on-screen display require the `volume` feature");
+        }
+
+        if config.events.contains(&OsdEvent::Brightness) {
+            let client = ironbar.clients.borrow_mut().backlight();
+            let tx = tx.clone();
+
+            spawn(async move {
+                let mut updates = client.subscribe();
+                let mut last = client.brightness();
+
+                loop {
+                    match updates.recv_tracked("osd brightness").await {
+                        Ok(_) => {
+                            let percent = client.brightness();
+
+                            // the first reading is not a change
+                            if let (Some(last), Some(percent)) = (last, percent)
+                                && last != percent
+                            {
+                                tx.send_expect(OsdUpdate::Brightness(percent)).await;
+                            }
+                            last = percent;
+                        }
+                        Err(RecvError::Lagged(_)) => last = client.brightness(),
+                        Err(RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+
+        if config.events.contains(&OsdEvent::KeyboardLayout) {
+            #[cfg(feature = "keyboard")]
+            match ironbar.clients.borrow_mut().keyboard_layout() {
+                Ok(client) => watch_keyboard_layout(client.subscribe(), tx),
+                Err(err) => error!("Failed to start keyboard layout client: {err:?}"),
+            }
+
+            #[cfg(not(feature = "keyboard"))]
+            warn!(
+                "Keyboard layout changes in the on-screen display require the `keyboard` feature"
+            );
+        }
+
+        let timeout = Duration::from_millis(config.timeout);
+        let timeout_id = Rc::new(RefCell::new(None::<SourceId>));
+
+        rx.recv_glib(&window, move |window, update| {
+            for class in ["volume", "brightness", "keyboard-layout", "muted"] {
+                container.remove_css_class(class);
+            }
+
+            match update {
+                OsdUpdate::Volume { percent, muted } => {
+                    container.add_css_class("volume");
+                    if muted {
+                        container.add_css_class("muted");
+                    }
+
+                    icon.set_label_escaped(if muted {
+                        &config.icon_muted
+                    } else {
+                        &config.icon_volume
+                    });
+                    set_level(&level, &value, percent);
+                }
+                OsdUpdate::Brightness(percent) => {
+                    container.add_css_class("brightness");
+                    icon.set_label_escaped(&config.icon_brightness);
+                    set_level(&level, &value, percent);
+                }
+                OsdUpdate::KeyboardLayout(layout) => {
+                    container.add_css_class("keyboard-layout");
+                    icon.set_label_escaped(&config.icon_keyboard_layout);
+                    level.set_visible(false);
+                    value.set_label_escaped(&layout);
+                }
+            }
+
+            window.set_visible(true);
+
+            // restart the timeout so the osd stays open while changes continue
+            if let Some(id) = timeout_id.take() {
+                id.remove();
+            }
+
+            let window = window.clone();
+            let timeout_id_inner = timeout_id.clone();
+            timeout_id.replace(Some(glib::timeout_add_local_once(timeout, move || {
+                // source is removed once complete, so must not be removed again
+                timeout_id_inner.take();
+                window.set_visible(false);
+            })));
+        });
+
+        Self { window }
+    }
+
+    /// Closes the on-screen display window.
+    pub fn close(&self) {
+        self.window.close();
+        self.window.destroy();
+    }
+}
+
+/// Sets up GTK layer shell for the on-screen display window,
+/// placing it above all other surfaces without reserving space.
+fn setup_layer_shell(window: &Window, monitor: &Monitor, position: OsdPosition, margin: i32) {
+    window.init_layer_shell();
+    window.set_monitor(Some(monitor));
+    window.set_layer(gtk_layer_shell::Layer::Overlay);
+    window.set_namespace(Some(concat!(env!("CARGO_PKG_NAME"), "-osd")));
+
+    match position {
+        OsdPosition::Top => {
+            window.set_anchor(Edge::Top, true);
+            window.set_margin(Edge::Top, margin);
+        }
+        OsdPosition::Bottom => {
+            window.set_anchor(Edge::Bottom, true);
+            window.set_margin(Edge::Bottom, margin);
+        }
+        OsdPosition::Center => {}
+    }
+}
+
+fn set_level(level: &ProgressBar, value: &Label, percent: f64) {
+    level.set_visible(true);
+    level.set_fraction((percent / 100.0).clamp(0.0, 1.0));
+    value.set_label(&format!("{percent}%"));
+}
+
+/// Sends an update whenever the volume or mute state of the default sink changes.
+/// Switching to a different default sink is not treated as a change.
+#[cfg(feature = "volume")]
+fn watch_volume(client: Arc<dyn VolumeClient>, tx: mpsc::Sender<OsdUpdate>) {
+    let active_state = |client: &dyn VolumeClient| {
+        client
+            .sinks()
+            .into_iter()
+            .find(|sink| sink.active)
+            .map(|sink| sink_state(&sink))
+    };
+
+    spawn(async move {
+        let mut events = client.subscribe();
+        let mut last = active_state(client.as_ref());
+
+        loop {
+            match events.recv_tracked("osd volume").await {
+                Ok(volume::Event::AddSink(sink) | volume::Event::UpdateSink(sink))
+                    if sink.active =>
+                {
+                    let state = sink_state(&sink);
+
+                    let changed = last
+                        .as_ref()
+                        .is_some_and(|last| last.0 == state.0 && *last != state);
+
+                    if changed {
+                        tx.send_expect(OsdUpdate::Volume {
+                            percent: state.1,
+                            muted: state.2,
+                        })
+                        .await;
+                    }
+
+                    last = Some(state);
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(_)) => last = active_state(client.as_ref()),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Gets the name, volume and mute state of the sink.
+#[cfg(feature = "volume")]
+fn sink_state(sink: &volume::Sink) -> (String, f64, bool) {
+    (sink.name.clone(), sink.volume.percent(), sink.muted)
+}
+
+/// Sends an update whenever the active keyboard layout changes.
+/// The first layout received is the current one, so is not sent.
+#[cfg(feature = "keyboard")]
+fn watch_keyboard_layout(
+    mut layouts: tokio::sync::broadcast::Receiver<KeyboardLayoutUpdate>,
+    tx: mpsc::Sender<OsdUpdate>,
+) {
+    spawn(async move {
+        let mut last = None;
+
+        loop {
+            match layouts.recv_tracked("osd keyboard layout").await {
+                Ok(KeyboardLayoutUpdate { name: layout, .. }) => {
+                    if last.as_ref().is_some_and(|last| *last != layout) {
+                        tx.send_expect(OsdUpdate::KeyboardLayout(layout.clone()))
+                            .await;
+                    }
+                    last = Some(layout);
+                }
+                // the next update carries the full layout
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
@@ -0,0 +1,230 @@
+use crate::lock;
+use color_eyre::Report;
+use std::any::Any;
+use std::cell::RefCell;
+use std::future::{Future, poll_fn};
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+use tokio::sync::mpsc;
+use tokio::task::AbortHandle;
+use tracing::error;
+
+thread_local! {
+    /// The guard for the module currently being created.
+    static CURRENT: RefCell<Option<PanicGuard>> = const { RefCell::new(None) };
+}
+
+/// Reports panics and errors in a module's tasks and update callbacks,
+/// so that the module can be disabled without taking down the whole bar.
+///
+/// Tasks and callbacks use the guard which is current when they are created,
+/// set using [`PanicGuard::scope`].
+///
+/// The guard also tracks the module's tasks,
+/// so that they can be stopped if the module is removed.
+#[derive(Debug, Clone)]
+pub struct PanicGuard {
+    name: &'static str,
+    tx: mpsc::Sender<Failure>,
+    /// Handles for the module's running tasks,
+    /// or `None` once they have been aborted.
+    tasks: Arc<Mutex<Option<Vec<AbortHandle>>>>,
+}
+
+impl PanicGuard {
+    /// Creates a new guard for the named module.
+    /// The receiver gets the reason if the module fails.
+    pub fn new(name: &'static str) -> (Self, mpsc::Receiver<Failure>) {
+        let (tx, rx) = mpsc::channel(1);
+        let tasks = Arc::new(Mutex::new(Some(vec![])));
+        (Self { name, tx, tasks }, rx)
+    }
+
+    /// Gets the guard for the module currently being created, if any.
+    pub fn current() -> Option<Self> {
+        CURRENT.with_borrow(Clone::clone)
+    }
+
+    /// Runs `f` with this as the current guard.
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        /// Restores the previous guard, even if `f` panics.
+        struct Restore(Option<PanicGuard>);
+
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                CURRENT.set(self.0.take());
+            }
+        }
+
+        let _restore = Restore(CURRENT.replace(Some(self.clone())));
+        f()
+    }
+
+    /// Records a task as belonging to the module.
+    ///
+    /// If the module's tasks have already been aborted,
+    /// the task is aborted immediately.
+    pub fn track(&self, task: AbortHandle) {
+        let mut tasks = lock!(self.tasks);
+        match tasks.as_mut() {
+            Some(tasks) => {
+                tasks.retain(|task| !task.is_finished());
+                tasks.push(task);
+            }
+            None => task.abort(),
+        }
+    }
+
+    /// Aborts all the module's tasks,
+    /// including any it starts from now on.
+    pub fn abort(&self) {
+        let tasks = lock!(self.tasks).take().unwrap_or_default();
+        for task in tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Polls `fut` with `guard` as the current guard,
+/// so that any tasks it spawns belong to the same module.
+pub async fn scope_future<F: Future>(guard: Option<PanicGuard>, fut: F) -> F::Output {
+    let mut fut = pin!(fut);
+
+    poll_fn(|cx| match &guard {
+        Some(guard) => guard.scope(|| fut.as_mut().poll(cx)),
+        None => fut.as_mut().poll(cx),
+    })
+    .await
+}
+
+/// Why a module stopped working.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Failure {
+    /// The module panicked, with the panic message.
+    Panic(String),
+    /// The module's controller or client returned an error
+    /// after the module was created.
+    Error(String),
+}
+
+/// Gets the message from a panic payload.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(ToString::to_string)
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| String::from("unknown panic"))
+}
+
+/// Logs a caught panic and reports it to the guard, if there is one.
+pub fn report(guard: Option<&PanicGuard>, payload: &(dyn Any + Send)) {
+    let message = panic_message(payload);
+
+    if let Some(guard) = guard {
+        error!("{} module panicked, disabling it: {message}", guard.name);
+        // only the first failure is shown
+        let _ = guard.tx.try_send(Failure::Panic(message));
+    } else {
+        error!("caught panic: {message}");
+    }
+}
+
+/// Logs an error which stops a module from working,
+/// and reports it to the current guard, if there is one.
+///
+/// This is for failures after the module is created,
+/// such as a client failing to connect from the module's controller.
+pub fn report_error(err: &Report) {
+    error!("{err:?}");
+
+    if let Some(guard) = PanicGuard::current() {
+        let _ = guard.tx.try_send(Failure::Error(format!("{err:#}")));
+    }
+}
+
+/// Runs `f`, catching any panic and reporting it to `guard`.
+/// Returns `None` if `f` panicked.
+pub fn catch<R>(guard: Option<&PanicGuard>, f: impl FnOnce() -> R) -> Option<R> {
+    panic::catch_unwind(AssertUnwindSafe(f))
+        .map_err(|payload| report(guard, &*payload))
+        .ok()
+}
+
+/// Like [`catch`], for futures.
+pub async fn catch_future<F: Future>(guard: Option<&PanicGuard>, fut: F) -> Option<F::Output> {
+    catch_unwind(fut)
+        .await
+        .map_err(|payload| report(guard, &*payload))
+        .ok()
+}
+
+/// Polls `fut` to completion, catching any panic.
+pub async fn catch_unwind<F: Future>(fut: F) -> Result<F::Output, Box<dyn Any + Send>> {
+    let mut fut = pin!(fut);
+
+    poll_fn(
+        |cx| match panic::catch_unwind(AssertUnwindSafe(|| fut.as_mut().poll(cx))) {
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => Poll::Ready(Err(payload)),
+        },
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catch_reports_to_guard() {
+        let (guard, mut rx) = PanicGuard::new("test");
+
+        let res: Option<()> = catch(Some(&guard), || panic!("oh no"));
+
+        assert!(res.is_none());
+        assert_eq!(rx.try_recv(), Ok(Failure::Panic("oh no".to_string())));
+    }
+
+    #[test]
+    fn scope_restores_previous() {
+        let (outer, _rx) = PanicGuard::new("outer");
+        let (inner, _rx) = PanicGuard::new("inner");
+
+        outer.scope(|| {
+            let res: Option<()> = catch(None, || inner.scope(|| panic!("oh no")));
+            assert!(res.is_none());
+            assert_eq!(PanicGuard::current().map(|guard| guard.name), Some("outer"));
+        });
+
+        assert!(PanicGuard::current().is_none());
+    }
+
+    #[test]
+    fn report_error_to_current() {
+        let (guard, mut rx) = PanicGuard::new("test");
+
+        guard.scope(|| report_error(&Report::msg("oh no")));
+        report_error(&Report::msg("no guard"));
+
+        assert_eq!(rx.try_recv(), Ok(Failure::Error("oh no".to_string())));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn abort_tracked_tasks() {
+        let (guard, _rx) = PanicGuard::new("test");
+
+        let task = tokio::spawn(std::future::pending::<()>());
+        guard.track(task.abort_handle());
+        guard.abort();
+        assert!(task.await.is_err_and(|err| err.is_cancelled()));
+
+        // tasks started after aborting are stopped straight away
+        let task = tokio::spawn(std::future::pending::<()>());
+        guard.track(task.abort_handle());
+        assert!(task.await.is_err_and(|err| err.is_cancelled()));
+    }
+}
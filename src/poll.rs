@@ -0,0 +1,252 @@
+use crate::{read_lock, write_lock};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{LazyLock, OnceLock, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::select;
+use tokio::sync::watch;
+use tokio::time::{Instant, sleep_until};
+
+/// Options for interval-driven work,
+/// such as polling scripts, refreshing system info and updating the clock.
+///
+/// Wakeups are aligned to shared ticks so that work is batched together,
+/// and intervals are lengthened while the extra precision is not needed.
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct PollingConfig {
+    /// The amount to multiply polling intervals by
+    /// while the system is running on battery.
+    /// Set to `1` to disable.
+    ///
+    /// Requires the `battery` feature.
+    ///
+    /// **Default**: `2`
+    pub battery_factor: f64,
+
+    /// The amount to multiply polling intervals by
+    /// while every bar is hidden.
+    /// Set to `1` to disable.
+    ///
+    /// **Default**: `4`
+    pub hidden_factor: f64,
+}
+
+impl Default for PollingConfig {
+    fn default() -> Self {
+        Self {
+            battery_factor: 2.0,
+            hidden_factor: 4.0,
+        }
+    }
+}
+
+/// The largest amount intervals are multiplied by,
+/// so that a misconfigured factor cannot stop polling entirely.
+const MAX_FACTOR: f64 = 100.0;
+
+/// The system state which polling intervals adapt to.
+#[derive(Debug, Default, Clone, Copy)]
+struct State {
+    on_battery: bool,
+    /// Whether every bar is hidden.
+    hidden: bool,
+}
+
+impl State {
+    /// Gets the amount to multiply intervals by in this state.
+    fn factor(self, config: &PollingConfig) -> f64 {
+        let mut factor = 1.0;
+
+        if self.on_battery {
+            factor *= config.battery_factor;
+        }
+
+        if self.hidden {
+            factor *= config.hidden_factor;
+        }
+
+        factor.max(1.0).min(MAX_FACTOR)
+    }
+}
+
+static CONFIG: LazyLock<RwLock<PollingConfig>> =
+    LazyLock::new(|| RwLock::new(PollingConfig::default()));
+static STATE: LazyLock<watch::Sender<State>> =
+    LazyLock::new(|| watch::Sender::new(State::default()));
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+
+/// The number of bar windows currently shown.
+static SHOWN_BARS: AtomicUsize = AtomicUsize::new(0);
+
+/// Sets the polling options,
+/// and starts watching the power source if required.
+///
+/// This is called again when the config is reloaded,
+/// and waits which are already in progress are re-evaluated with the new options.
+pub fn configure(config: PollingConfig) {
+    *write_lock!(CONFIG) = config;
+
+    // wake each waiting poll so that it picks up the new factors
+    STATE.send_modify(|_| {});
+
+    #[cfg(feature = "battery")]
+    if config.battery_factor > 1.0 {
+        static WATCHING_POWER: std::sync::Once = std::sync::Once::new();
+
+        WATCHING_POWER.call_once(|| {
+            crate::spawn(async {
+                if let Err(err) = crate::clients::upower::watch_on_battery(set_on_battery).await {
+                    tracing::debug!("unable to watch power source: {err:?}");
+                }
+            });
+        });
+    }
+}
+
+fn config() -> PollingConfig {
+    *read_lock!(CONFIG)
+}
+
+/// Gets the point which ticks are measured from.
+///
+/// This is aligned to the wall clock second,
+/// so that ticks of whole seconds land as the second changes.
+fn epoch() -> Instant {
+    *EPOCH.get_or_init(|| {
+        let now = Instant::now();
+        let subsec = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.subsec_nanos())
+            .unwrap_or_default();
+
+        now.checked_sub(Duration::from_nanos(subsec.into()))
+            .unwrap_or(now)
+    })
+}
+
+#[cfg(feature = "battery")]
+fn set_on_battery(on_battery: bool) {
+    STATE.send_if_modified(|state| {
+        let changed = state.on_battery != on_battery;
+        state.on_battery = on_battery;
+        changed
+    });
+
+    tracing::debug!("on battery: {on_battery}");
+}
+
+/// Records that a bar window was shown or hidden.
+/// Polling slows down while every bar is hidden.
+pub fn set_bar_shown(shown: bool) {
+    let count = if shown {
+        SHOWN_BARS.fetch_add(1, Ordering::Relaxed) + 1
+    } else {
+        SHOWN_BARS
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                Some(count.saturating_sub(1))
+            })
+            .unwrap_or_default()
+            .saturating_sub(1)
+    };
+
+    let hidden = count == 0;
+    STATE.send_if_modified(|state| {
+        let changed = state.hidden != hidden;
+        state.hidden = hidden;
+        changed
+    });
+}
+
+/// Waits for roughly `interval` before the next poll.
+///
+/// The wait ends on a tick shared with other polling,
+/// so that work due at similar times runs in the same wakeup.
+/// The interval is lengthened while on battery or while every bar is hidden,
+/// and the wait is re-evaluated as soon as either changes.
+pub async fn sleep(interval: Duration) {
+    let start = Instant::now();
+    let mut state = STATE.subscribe();
+
+    loop {
+        let interval = interval.mul_f64(state.borrow_and_update().factor(&config()));
+        let deadline = align(epoch(), start + interval, interval);
+
+        select! {
+            () = sleep_until(deadline) => return,
+            // sender is static, so is never closed
+            _ = state.changed() => {}
+        }
+    }
+}
+
+/// Rounds `deadline` to the nearest tick after `epoch`.
+///
+/// Intervals of a second or more tick on each whole second.
+/// Shorter intervals tick on multiples of themselves,
+/// which line up with the whole seconds where the interval divides a second.
+fn align(epoch: Instant, deadline: Instant, interval: Duration) -> Instant {
+    let tick = interval
+        .min(Duration::from_secs(1))
+        .max(Duration::from_millis(1))
+        .as_nanos();
+
+    let since = deadline.saturating_duration_since(epoch).as_nanos();
+    let ticks = (since + tick / 2) / tick;
+
+    let offset = u64::try_from(ticks * tick).unwrap_or(u64::MAX);
+    epoch + Duration::from_nanos(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn align_to_seconds() {
+        let epoch = Instant::now();
+        let deadline = epoch + Duration::from_millis(5300);
+
+        assert_eq!(
+            align(epoch, deadline, Duration::from_secs(5)),
+            epoch + Duration::from_secs(5)
+        );
+        assert_eq!(
+            align(
+                epoch,
+                deadline + Duration::from_millis(300),
+                Duration::from_secs(5)
+            ),
+            epoch + Duration::from_secs(6)
+        );
+    }
+
+    #[test]
+    fn align_short_interval() {
+        let epoch = Instant::now();
+        let deadline = epoch + Duration::from_millis(1180);
+
+        assert_eq!(
+            align(epoch, deadline, Duration::from_millis(250)),
+            epoch + Duration::from_millis(1250)
+        );
+    }
+
+    #[test]
+    fn factor_combines_state() {
+        let config = PollingConfig::default();
+
+        let state = State {
+            on_battery: true,
+            hidden: true,
+        };
+        assert!((state.factor(&config) - 8.0).abs() < f64::EPSILON);
+
+        let config = PollingConfig {
+            battery_factor: 0.5,
+            hidden_factor: 1.0,
+        };
+        assert!((state.factor(&config) - 1.0).abs() < f64::EPSILON);
+    }
+}
@@ -1,17 +1,49 @@
-use crate::config::BarPosition;
+use crate::config::{BarPosition, PopupAnchor, PopupKeyboardMode};
 use crate::modules::{ModuleInfo, ModulePopupParts, PopupButton};
 use crate::rc_mut;
+use glib::Propagation;
+use glib::SourceId;
 use gtk::prelude::*;
-use gtk::{Button, Popover, PositionType};
-use std::cell::RefCell;
+use gtk::{
+    Button, DirectionType, EventControllerKey, EventControllerMotion, Orientation, Popover,
+    PositionType, ScrolledWindow, TextDirection, Widget, Window, gdk,
+};
+use gtk_layer_shell::{KeyboardMode, LayerShell};
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
 use std::rc::Rc;
+use std::time::Duration;
 use tracing::{debug, error};
 
+/// Per-module popup placement, sizing and input options.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PopupLayout {
+    /// The side to open the popup on,
+    /// overriding the default of away from the bar.
+    pub position: Option<BarPosition>,
+    /// Additional offset on top of the bar's popup gap.
+    pub offset: (i32, i32),
+    pub max_width: Option<i32>,
+    pub max_height: Option<i32>,
+    pub anchor: PopupAnchor,
+    /// Overrides the bar's keyboard mode for this popup.
+    pub keyboard_mode: Option<PopupKeyboardMode>,
+    /// Time after which a popup opened by hover or scroll closes itself.
+    pub auto_close: Option<Duration>,
+    /// Time to wait after the pointer leaves an auto-closing popup
+    /// before closing it.
+    pub hover_grace: Duration,
+}
+
 #[derive(Debug)]
 pub struct PopupCacheValue {
     pub content: gtk::Box,
+    /// The widget set as the popover child.
+    /// This is the content itself,
+    /// or a scrolled window around it where a max size is set.
+    child: Widget,
+    layout: PopupLayout,
     /// Whether this module disallows the popover widget from using autohide.
     /// Where popups are controlled via hover, autohide can cause issues.
     pub disable_autohide: bool,
@@ -20,6 +52,58 @@ pub struct PopupCacheValue {
 #[derive(Debug, Clone, Copy)]
 struct CurrentWidgetInfo {
     widget_id: usize,
+    /// Whether the popup was opened by hover or scroll,
+    /// rather than by a click.
+    transient: bool,
+}
+
+/// Closes transient popups once their timeout expires,
+/// keeping them open while hovered.
+#[derive(Debug, Default)]
+struct AutoClose {
+    timeout_id: RefCell<Option<SourceId>>,
+    hovered: Cell<bool>,
+    /// The grace period to close the popup after once the pointer leaves it.
+    /// This is only set while the popup is open and closes automatically.
+    grace: Cell<Option<Duration>>,
+}
+
+impl AutoClose {
+    fn schedule(
+        self: &Rc<Self>,
+        delay: Duration,
+        popover: &Popover,
+        current_widget: &Rc<RefCell<Option<CurrentWidgetInfo>>>,
+    ) {
+        self.cancel();
+
+        let auto_close = Rc::downgrade(self);
+        let popover = popover.clone();
+        let current_widget = current_widget.clone();
+
+        let timeout_id = glib::timeout_add_local_once(delay, move || {
+            let Some(auto_close) = auto_close.upgrade() else {
+                return;
+            };
+
+            // source is removed once complete, so must not be removed again
+            auto_close.timeout_id.take();
+
+            if !auto_close.hovered.get() {
+                auto_close.grace.set(None);
+                *current_widget.borrow_mut() = None;
+                popover.popdown();
+            }
+        });
+
+        *self.timeout_id.borrow_mut() = Some(timeout_id);
+    }
+
+    fn cancel(&self) {
+        if let Some(timeout_id) = self.timeout_id.take() {
+            timeout_id.remove();
+        }
+    }
 }
 
 pub type ButtonFinder = dyn Fn(usize) -> Option<Button> + 'static;
@@ -31,8 +115,16 @@ pub struct Popup {
     pub button_finder_cache: Rc<RefCell<HashMap<usize, Rc<ButtonFinder>>>>,
     pub button_cache: Rc<RefCell<Vec<Button>>>,
     pos: BarPosition,
+    position: PositionType,
+    gap: i32,
+    bar: Widget,
     current_widget: Rc<RefCell<Option<CurrentWidgetInfo>>>,
+    auto_close: Rc<AutoClose>,
+    /// Whether the popup is pinned open,
+    /// ignoring focus loss, auto-close timeouts and hover.
+    pinned: Rc<Cell<bool>>,
     autohide: bool,
+    keyboard_mode: PopupKeyboardMode,
 }
 
 impl Debug for Popup {
@@ -44,6 +136,7 @@ impl Debug for Popup {
             .field("button_cache", &self.button_cache)
             .field("pos", &self.pos)
             .field("current_widget", &self.current_widget)
+            .field("pinned", &self.pinned)
             .finish()
     }
 }
@@ -52,7 +145,13 @@ impl Popup {
     /// Creates a new popup window.
     /// This includes setting up gtk-layer-shell
     /// and an empty `gtk::Box` container.
-    pub fn new(module_info: &ModuleInfo, gap: i32, autohide: bool) -> Self {
+    pub fn new(
+        module_info: &ModuleInfo,
+        gap: i32,
+        autohide: bool,
+        keyboard_mode: PopupKeyboardMode,
+        bar: &impl IsA<Widget>,
+    ) -> Self {
         let pos = module_info.bar_position;
 
         let position = match pos {
@@ -62,11 +161,7 @@ impl Popup {
             BarPosition::Right => PositionType::Left,
         };
 
-        let (offset_x, offset_y) = match pos {
-            BarPosition::Top => (0, gap),
-            BarPosition::Bottom => (0, -gap),
-            BarPosition::Left | BarPosition::Right => (gap, 0),
-        };
+        let offset = gap_offset(position, gap);
 
         let popover = Popover::builder()
             .has_arrow(false)
@@ -74,11 +169,86 @@ impl Popup {
             .position(position)
             .build();
 
-        popover.set_offset(offset_x, offset_y);
+        popover.set_offset(offset.0, offset.1);
 
-        popover.connect_closed(|popover| {
-            popover.unparent();
-        });
+        let bar = bar.clone().upcast::<Widget>();
+        let current_widget = rc_mut!(None);
+        let pinned = Rc::new(Cell::new(false));
+
+        {
+            let bar = bar.clone();
+            let pinned = pinned.clone();
+            popover.connect_closed(move |popover| {
+                popover.unparent();
+
+                pinned.set(false);
+                popover.remove_css_class("pinned");
+
+                // give keyboard focus back to the focused window
+                if let Some(window) = bar_window(&bar) {
+                    window.set_keyboard_mode(KeyboardMode::None);
+                }
+            });
+        }
+
+        let key_controller = EventControllerKey::new();
+
+        {
+            let popover = popover.clone();
+            let current_widget = current_widget.clone();
+            key_controller.connect_key_pressed(move |_, key, _, _| {
+                // keys are only received here if not handled by the focused child,
+                // so arrows still work inside entries and sliders
+                let direction = match key {
+                    gdk::Key::Escape => {
+                        *current_widget.borrow_mut() = None;
+                        popover.popdown();
+                        return Propagation::Stop;
+                    }
+                    gdk::Key::Tab => DirectionType::TabForward,
+                    gdk::Key::ISO_Left_Tab => DirectionType::TabBackward,
+                    gdk::Key::Up => DirectionType::Up,
+                    gdk::Key::Down => DirectionType::Down,
+                    gdk::Key::Left => DirectionType::Left,
+                    gdk::Key::Right => DirectionType::Right,
+                    _ => return Propagation::Proceed,
+                };
+
+                if !popover.child_focus(direction) {
+                    popover.error_bell();
+                }
+
+                Propagation::Stop
+            });
+        }
+
+        popover.add_controller(key_controller);
+
+        let auto_close = Rc::new(AutoClose::default());
+        let motion_controller = EventControllerMotion::new();
+
+        {
+            let auto_close = auto_close.clone();
+            motion_controller.connect_enter(move |_, _, _| {
+                auto_close.hovered.set(true);
+                auto_close.cancel();
+            });
+        }
+
+        {
+            let auto_close = auto_close.clone();
+            let popover = popover.clone();
+            let current_widget = current_widget.clone();
+            motion_controller.connect_leave(move |_| {
+                auto_close.hovered.set(false);
+
+                if let Some(grace) = auto_close.grace.get() {
+                    auto_close.schedule(grace, &popover, &current_widget);
+                }
+            });
+        }
+
+        popover.add_controller(motion_controller);
 
         Self {
             popover,
@@ -86,12 +256,18 @@ impl Popup {
             button_cache: rc_mut!(vec![]),
             button_finder_cache: rc_mut!(HashMap::new()),
             pos,
-            current_widget: rc_mut!(None),
+            position,
+            gap,
+            bar,
+            current_widget,
+            auto_close,
+            pinned,
             autohide,
+            keyboard_mode,
         }
     }
 
-    pub fn register_content(&self, key: usize, content: ModulePopupParts) {
+    pub fn register_content(&self, key: usize, content: ModulePopupParts, layout: PopupLayout) {
         debug!("Registered popup content for #{}", key);
 
         for button in &content.buttons {
@@ -102,10 +278,26 @@ impl Popup {
             .borrow_mut()
             .append(&mut content.buttons.clone());
 
+        let child = if layout.max_width.is_some() || layout.max_height.is_some() {
+            let scrolled = ScrolledWindow::builder()
+                .propagate_natural_width(true)
+                .propagate_natural_height(true)
+                .max_content_width(layout.max_width.unwrap_or(-1))
+                .max_content_height(layout.max_height.unwrap_or(-1))
+                .child(&content.container)
+                .build();
+
+            scrolled.upcast()
+        } else {
+            content.container.clone().upcast()
+        };
+
         self.container_cache.borrow_mut().insert(
             key,
             PopupCacheValue {
                 content: content.container.clone(),
+                child,
+                layout,
                 disable_autohide: content.disable_autohide,
             },
         );
@@ -117,6 +309,19 @@ impl Popup {
         }
     }
 
+    /// Removes a widget's popup content,
+    /// hiding the popup if it is showing the content.
+    pub fn unregister_content(&self, key: usize) {
+        debug!("Unregistered popup content for #{}", key);
+
+        if self.current_widget() == Some(key) {
+            self.hide();
+        }
+
+        self.container_cache.borrow_mut().remove(&key);
+        self.button_finder_cache.borrow_mut().remove(&key);
+    }
+
     pub fn register_button(&self, button: Button) {
         button.ensure_popup_id();
         self.button_cache.borrow_mut().push(button);
@@ -130,16 +335,48 @@ impl Popup {
         self.button_cache.borrow_mut().retain(|b| b != button);
     }
 
+    /// Removes all buttons inside `widget`.
+    pub fn unregister_buttons_within(&self, widget: &Widget) {
+        self.button_cache
+            .borrow_mut()
+            .retain(|b| b != widget && !b.is_ancestor(widget));
+    }
+
+    /// Shows the popup for a widget,
+    /// keeping it open until it is dismissed.
     pub fn show(&self, widget_id: usize, button_id: usize) {
+        self.show_inner(widget_id, button_id, false);
+    }
+
+    /// Shows the popup for a widget in response to a hover or scroll,
+    /// closing it automatically if the module has an auto-close timeout.
+    ///
+    /// If the popup is already open for the widget
+    /// until dismissed, it is kept that way.
+    ///
+    /// This is ignored while the popup is pinned.
+    pub fn show_transient(&self, widget_id: usize, button_id: usize) {
+        if self.pinned() {
+            return;
+        }
+
+        let transient = self
+            .current_widget
+            .borrow()
+            .is_none_or(|w| w.widget_id != widget_id || w.transient);
+
+        self.hide();
+        self.show_inner(widget_id, button_id, transient);
+    }
+
+    fn show_inner(&self, widget_id: usize, button_id: usize, transient: bool) {
         self.clear_window();
 
-        if let Some(PopupCacheValue {
-            content,
-            disable_autohide,
-            ..
-        }) = self.container_cache.borrow().get(&widget_id)
-        {
-            *self.current_widget.borrow_mut() = Some(CurrentWidgetInfo { widget_id });
+        if let Some(cache_value) = self.container_cache.borrow().get(&widget_id) {
+            *self.current_widget.borrow_mut() = Some(CurrentWidgetInfo {
+                widget_id,
+                transient,
+            });
 
             let button = if let Some(finder) = self.button_finder_cache.borrow().get(&widget_id) {
                 finder(button_id)
@@ -156,16 +393,7 @@ impl Popup {
                 return;
             };
 
-            content.add_css_class("popup");
-            self.popover.set_child(Some(content));
-            self.popover.unparent();
-            self.popover.set_parent(&button);
-
-            if *disable_autohide {
-                self.popover.set_autohide(false);
-            }
-
-            self.popover.popup();
+            self.open(cache_value, &button, transient);
         }
     }
 
@@ -177,38 +405,102 @@ impl Popup {
     pub fn show_for(&self, widget_id: usize, button: &Button) -> bool {
         self.clear_window();
 
-        if let Some(PopupCacheValue {
-            content,
-            disable_autohide,
-            ..
-        }) = self.container_cache.borrow().get(&widget_id)
-        {
-            *self.current_widget.borrow_mut() = Some(CurrentWidgetInfo { widget_id });
+        if let Some(cache_value) = self.container_cache.borrow().get(&widget_id) {
+            *self.current_widget.borrow_mut() = Some(CurrentWidgetInfo {
+                widget_id,
+                transient: false,
+            });
+            self.open(cache_value, button, false);
 
-            content.add_css_class("popup");
-            self.popover.set_child(Some(content));
-            self.popover.unparent();
-            self.popover.set_parent(button);
+            true
+        } else {
+            false
+        }
+    }
 
-            if *disable_autohide {
-                self.popover.set_autohide(false);
-            }
+    /// Sets the popover content and opens it,
+    /// placed according to the module's popup layout.
+    fn open(&self, cache_value: &PopupCacheValue, button: &Button, transient: bool) {
+        let layout = cache_value.layout;
 
-            self.popover.popup();
+        cache_value.content.add_css_class("popup");
+        self.popover.set_child(Some(&cache_value.child));
+        self.popover.unparent();
 
-            true
+        match layout.anchor {
+            PopupAnchor::Widget => self.popover.set_parent(button),
+            PopupAnchor::Bar => self.popover.set_parent(&self.bar),
+        }
+
+        let position = layout
+            .position
+            .map_or(self.position, |position| match position {
+                BarPosition::Top => PositionType::Top,
+                BarPosition::Bottom => PositionType::Bottom,
+                BarPosition::Left => PositionType::Left,
+                BarPosition::Right => PositionType::Right,
+            });
+
+        self.popover.set_position(position);
+
+        // horizontal offsets are mirrored in right-to-left layouts,
+        // so that popups open the same way relative to their module
+        let offset_x = if self.popover.direction() == TextDirection::Rtl
+            && self.pos.orientation() == Orientation::Horizontal
+        {
+            -layout.offset.0
         } else {
-            false
+            layout.offset.0
+        };
+
+        let offset = gap_offset(position, self.gap);
+        self.popover
+            .set_offset(offset.0 + offset_x, offset.1 + layout.offset.1);
+
+        if cache_value.disable_autohide {
+            self.popover.set_autohide(false);
+        }
+
+        let keyboard_mode = layout.keyboard_mode.unwrap_or(self.keyboard_mode);
+        if keyboard_mode != PopupKeyboardMode::None
+            && let Some(window) = bar_window(&self.bar)
+        {
+            window.set_keyboard_mode(keyboard_mode.to_layer_shell_mode());
+        }
+
+        self.popover.popup();
+
+        if keyboard_mode != PopupKeyboardMode::None {
+            self.popover.child_focus(DirectionType::TabForward);
+        }
+
+        if transient && let Some(delay) = layout.auto_close {
+            self.auto_close.grace.set(Some(layout.hover_grace));
+            self.auto_close
+                .schedule(delay, &self.popover, &self.current_widget);
         }
     }
 
     fn clear_window(&self) {
+        self.auto_close.cancel();
+        self.auto_close.grace.set(None);
+
+        self.pinned.set(false);
+        self.popover.remove_css_class("pinned");
+
         self.popover.set_child(None::<&gtk::Box>);
         self.popover.set_autohide(self.autohide);
+        self.popover.set_position(self.position);
+
+        let offset = gap_offset(self.position, self.gap);
+        self.popover.set_offset(offset.0, offset.1);
     }
 
     /// Hides the popup
     pub fn hide(&self) {
+        self.auto_close.cancel();
+        self.auto_close.grace.set(None);
+
         *self.current_widget.borrow_mut() = None;
         self.popover.popdown();
         self.popover.unparent();
@@ -222,4 +514,97 @@ impl Popup {
     pub fn current_widget(&self) -> Option<usize> {
         self.current_widget.borrow().map(|w| w.widget_id)
     }
+
+    /// Checks if the popup is pinned open.
+    pub fn pinned(&self) -> bool {
+        self.pinned.get()
+    }
+
+    /// Pins the open popup, keeping it open until explicitly hidden,
+    /// or unpins it to restore its usual closing behaviour.
+    ///
+    /// Returns whether the popup is open to be pinned.
+    pub fn set_pinned(&self, pinned: bool) -> bool {
+        let Some(widget_id) = self.current_widget().filter(|_| self.visible()) else {
+            return false;
+        };
+
+        self.pinned.set(pinned);
+
+        if pinned {
+            self.auto_close.cancel();
+            self.auto_close.grace.set(None);
+
+            if let Some(current) = self.current_widget.borrow_mut().as_mut() {
+                current.transient = false;
+            }
+
+            self.popover.set_autohide(false);
+            self.popover.add_css_class("pinned");
+        } else {
+            let disable_autohide = self
+                .container_cache
+                .borrow()
+                .get(&widget_id)
+                .is_some_and(|cache_value| cache_value.disable_autohide);
+
+            self.popover
+                .set_autohide(self.autohide && !disable_autohide);
+            self.popover.remove_css_class("pinned");
+        }
+
+        true
+    }
+
+    /// Gives the open popup keyboard focus,
+    /// focusing its first interactive child.
+    ///
+    /// The module's keyboard mode is used if set,
+    /// otherwise focus is grabbed exclusively until the popup closes.
+    ///
+    /// Returns whether the popup is open to be focused.
+    pub fn focus(&self) -> bool {
+        let Some(widget_id) = self.current_widget().filter(|_| self.visible()) else {
+            return false;
+        };
+
+        let keyboard_mode = self
+            .container_cache
+            .borrow()
+            .get(&widget_id)
+            .and_then(|cache_value| cache_value.layout.keyboard_mode)
+            .unwrap_or(self.keyboard_mode);
+
+        let keyboard_mode = match keyboard_mode {
+            PopupKeyboardMode::None => PopupKeyboardMode::Exclusive,
+            mode => mode,
+        };
+
+        if let Some(window) = bar_window(&self.bar) {
+            window.set_keyboard_mode(keyboard_mode.to_layer_shell_mode());
+        }
+
+        self.popover.child_focus(DirectionType::TabForward);
+
+        true
+    }
+}
+
+/// Gets the offset which moves a popover opening on the `position` side
+/// of its parent `gap` pixels further away from it.
+///
+/// This keeps popups clear of the bar on every edge,
+/// including when a module opens its popup on a different side.
+fn gap_offset(position: PositionType, gap: i32) -> (i32, i32) {
+    match position {
+        PositionType::Top => (0, -gap),
+        PositionType::Right => (gap, 0),
+        PositionType::Left => (-gap, 0),
+        _ => (0, gap),
+    }
+}
+
+/// Gets the layer shell window the bar widget is placed in.
+fn bar_window(bar: &Widget) -> Option<Window> {
+    bar.root().and_then(|root| root.downcast::<Window>().ok())
 }
@@ -1,18 +1,80 @@
 use crate::channels::AsyncSenderExt;
-use crate::spawn;
+use crate::{lock, poll, spawn};
 use color_eyre::eyre::WrapErr;
 use color_eyre::{Report, Result};
 use serde::Deserialize;
 use std::cmp::min;
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
 use std::process::Stdio;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio::select;
-use tokio::sync::mpsc;
-use tokio::time::sleep;
+use tokio::sync::{Semaphore, mpsc};
 use tracing::{debug, error, trace, warn};
 
+/// The default number of scripts which can run at once.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+/// The default minimum time between runs of the same script, in milliseconds.
+pub const DEFAULT_MIN_INTERVAL: u64 = 250;
+
+static LIMITS: OnceLock<Limits> = OnceLock::new();
+
+/// Limits shared by all scripts,
+/// so that a misconfigured script cannot flood the session with processes.
+#[derive(Debug)]
+struct Limits {
+    /// Permits for running scripts.
+    /// Scripts wait for a permit when all are taken.
+    permits: Semaphore,
+    min_interval: Duration,
+    /// When each oneshot script was last started, keyed by command and arguments.
+    last_run: Mutex<HashMap<String, Instant>>,
+}
+
+impl Limits {
+    fn new(concurrency: usize, min_interval: u64) -> Self {
+        Self {
+            permits: Semaphore::new(concurrency.max(1)),
+            min_interval: Duration::from_millis(min_interval),
+            last_run: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn get() -> &'static Self {
+        LIMITS.get_or_init(|| Self::new(DEFAULT_CONCURRENCY, DEFAULT_MIN_INTERVAL))
+    }
+
+    /// Checks whether the oneshot script identified by `key`
+    /// can run now, recording the run if so.
+    ///
+    /// Runs are skipped if the same script started within the minimum interval.
+    fn try_start(&self, key: String) -> bool {
+        let now = Instant::now();
+        let mut last_run = lock!(self.last_run);
+
+        last_run.retain(|_, started| now.duration_since(*started) < self.min_interval);
+
+        if last_run.contains_key(&key) {
+            false
+        } else {
+            last_run.insert(key, now);
+            true
+        }
+    }
+}
+
+/// Sets the limits for all scripts.
+/// This must be called before any scripts are run, otherwise it has no effect.
+pub fn set_limits(concurrency: usize, min_interval: u64) {
+    if LIMITS.set(Limits::new(concurrency, min_interval)).is_err() {
+        warn!("Script limits already set, ignoring");
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 #[serde(untagged)]
 #[cfg_attr(feature = "extras", derive(schemars::JsonSchema))]
@@ -189,10 +251,16 @@ impl Script {
 
     /// Runs the script, passing `args` if provided.
     /// Runs `f`, passing the output stream and whether the command returned 0.
+    ///
+    /// The script is re-run after its interval,
+    /// or after the global minimum interval if that is longer.
+    /// This is adjusted by the polling schedule.
     pub async fn run<F>(&self, args: Option<&[String]>, callback: F)
     where
         F: Fn(OutputStream, bool),
     {
+        let interval = Duration::from_millis(self.interval).max(Limits::get().min_interval);
+
         loop {
             match self.mode {
                 ScriptMode::Poll => match self.get_output(args).await {
@@ -209,7 +277,7 @@ impl Script {
                 },
             }
 
-            sleep(tokio::time::Duration::from_millis(self.interval)).await;
+            poll::sleep(interval).await;
         }
     }
 
@@ -219,7 +287,16 @@ impl Script {
     /// the `stdout` is returned.
     /// Otherwise, an `Err` variant
     /// containing the `stderr` is returned.
+    ///
+    /// If the maximum number of scripts are already running,
+    /// this waits for one to finish first.
     pub async fn get_output(&self, args: Option<&[String]>) -> Result<(OutputStream, bool)> {
+        let _permit = Limits::get()
+            .permits
+            .acquire()
+            .await
+            .wrap_err("Script permits closed")?;
+
         let mut args_list = vec!["-c", &self.cmd];
 
         if let Some(args) = args {
@@ -314,7 +391,19 @@ impl Script {
     /// This has some overhead,
     /// as the script has to be cloned to the thread.
     ///
+    /// Repeat runs of the same script with the same arguments
+    /// within the global minimum interval are skipped.
     pub fn run_as_oneshot(&self, args: Option<&[String]>) {
+        let key = match args {
+            Some(args) => format!("{} {}", self.cmd, args.join(" ")),
+            None => self.cmd.clone(),
+        };
+
+        if !Limits::get().try_start(key) {
+            debug!("Skipping repeat run of '{}'", self.cmd);
+            return;
+        }
+
         let script = self.clone();
         let args = args.map(<[String]>::to_vec);
 
@@ -398,6 +487,15 @@ mod tests {
         assert_eq!(script.mode, ScriptMode::default());
     }
 
+    #[test]
+    fn test_skips_repeat_runs() {
+        let limits = Limits::new(1, 60_000);
+
+        assert!(limits.try_start(String::from("echo a")));
+        assert!(!limits.try_start(String::from("echo a")));
+        assert!(limits.try_start(String::from("echo b")));
+    }
+
     #[test]
     fn test_no_cmd() {
         let mode = ScriptMode::Watch;
@@ -0,0 +1,111 @@
+use crate::lock;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Whether startup timings are being recorded.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// When recording started, at launch.
+static STARTED: OnceLock<Instant> = OnceLock::new();
+
+/// Timings recorded so far, in the order they finished.
+static TIMINGS: Mutex<Vec<Timing>> = Mutex::new(Vec::new());
+
+#[derive(Debug)]
+struct Timing {
+    kind: &'static str,
+    name: String,
+    duration: Duration,
+}
+
+/// Starts recording initialization times,
+/// to be printed with [`report`] once the bars have loaded.
+pub fn enable() {
+    STARTED.get_or_init(Instant::now);
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Runs `f`, recording how long it took under `kind` and `name`
+/// if startup profiling is enabled.
+pub fn time<R>(kind: &'static str, name: &str, f: impl FnOnce() -> R) -> R {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return f();
+    }
+
+    let start = Instant::now();
+    let res = f();
+
+    lock!(TIMINGS).push(Timing {
+        kind,
+        name: name.to_string(),
+        duration: start.elapsed(),
+    });
+
+    res
+}
+
+/// Prints the recorded timings to `stderr`, slowest first,
+/// and stops recording.
+///
+/// This should be called once the initial bars have loaded.
+pub fn report() {
+    if !ENABLED.swap(false, Ordering::Relaxed) {
+        return;
+    }
+
+    let total = STARTED.get().map(Instant::elapsed).unwrap_or_default();
+    let mut timings = std::mem::take(&mut *lock!(TIMINGS));
+    eprintln!("{}", format_report(&mut timings, total));
+}
+
+fn format_report(timings: &mut [Timing], total: Duration) -> String {
+    timings.sort_by(|a, b| b.duration.cmp(&a.duration));
+
+    let name_width = timings
+        .iter()
+        .map(|timing| timing.kind.len() + timing.name.len() + 1)
+        .max()
+        .unwrap_or_default();
+
+    let mut out = format!("Startup took {}ms\n", total.as_millis());
+
+    for timing in timings.iter() {
+        let name = format!("{}:{}", timing.kind, timing.name);
+        let _ = writeln!(
+            out,
+            "  {name:<name_width$}  {:>6}ms",
+            timing.duration.as_millis()
+        );
+    }
+
+    out.push_str("Module times include any clients they started.");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_is_sorted() {
+        let mut timings = vec![
+            Timing {
+                kind: "module",
+                name: "clock".to_string(),
+                duration: Duration::from_millis(2),
+            },
+            Timing {
+                kind: "client",
+                name: "mpd".to_string(),
+                duration: Duration::from_millis(150),
+            },
+        ];
+
+        assert_eq!(
+            format_report(&mut timings, Duration::from_millis(200)),
+            "Startup took 200ms\n  client:mpd       150ms\n  module:clock       2ms\nModule times include any clients they started."
+        );
+    }
+}
@@ -1,14 +1,14 @@
 use crate::channels::{AsyncSenderExt, MpscReceiverExt};
-use crate::spawn;
+use crate::{arc_mut, lock};
 use gtk::ffi::GTK_STYLE_PROVIDER_PRIORITY_USER;
 use gtk::{CssProvider, gio};
 use notify::event::ModifyKind;
 use notify::{Event, EventKind, RecursiveMode, Result, Watcher, recommended_watcher};
-use std::env;
-use std::path::PathBuf;
-use std::time::Duration;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::{env, fs};
 use tokio::sync::mpsc;
-use tokio::time::sleep;
 use tracing::{debug, error, info};
 
 #[derive(Debug)]
@@ -21,7 +21,8 @@ pub enum CssSource {
 /// and attach if to the current GTK application.
 ///
 /// Installs a file watcher and reloads CSS when
-/// write changes are detected on the file.
+/// write changes are detected on the file,
+/// or on any file it imports.
 pub fn load_css(source: &CssSource) {
     let provider = CssProvider::new();
 
@@ -39,7 +40,7 @@ pub fn load_css(source: &CssSource) {
                 env::current_dir().expect("to exist").join(style_path)
             };
 
-            provider.load_from_path(&style_path);
+            load_stylesheet(&provider, &style_path);
             debug!("loaded css from '{}'", style_path.display());
             Some(style_path)
         }
@@ -58,36 +59,218 @@ pub fn load_css(source: &CssSource) {
     if let Some(style_path) = path {
         let (tx, rx) = mpsc::channel(8);
 
-        spawn(async move {
-            let style_path2 = style_path.clone();
-            let mut watcher = recommended_watcher(move |res: Result<Event>| match res {
+        // the main stylesheet and all files it imports
+        let watched = arc_mut!(HashSet::new());
+
+        let mut watcher = {
+            let watched = watched.clone();
+            recommended_watcher(move |res: Result<Event>| match res {
                 Ok(event) if matches!(event.kind, EventKind::Modify(ModifyKind::Data(_))) => {
                     debug!("{event:?}");
-                    if event.paths.first().is_some_and(|p| p == &style_path2) {
-                        tx.send_spawn(style_path2.clone());
+                    if event.paths.iter().any(|p| lock!(watched).contains(p)) {
+                        tx.send_spawn(());
                     }
                 }
                 Err(e) => error!("Error occurred when watching stylesheet: {:?}", e),
                 _ => {}
             })
-            .expect("Failed to create CSS file watcher");
+            .expect("Failed to create CSS file watcher")
+        };
 
-            let dir_path = style_path.parent().expect("to exist");
+        let mut watched_dirs = HashSet::new();
+        watch_stylesheets(&mut watcher, &style_path, &watched, &mut watched_dirs);
 
-            watcher
-                .watch(dir_path, RecursiveMode::NonRecursive)
-                .expect("Failed to start CSS file watcher");
-            debug!("Installed CSS file watcher on '{}'", style_path.display());
+        rx.recv_glib((), move |(), ()| {
+            info!("Reloading CSS");
+            load_stylesheet(&provider, &style_path);
 
-            // avoid watcher from dropping
-            loop {
-                sleep(Duration::from_secs(1)).await;
-            }
+            // imports may have been added or removed
+            watch_stylesheets(&mut watcher, &style_path, &watched, &mut watched_dirs);
         });
+    }
+}
 
-        rx.recv_glib((), move |(), path| {
-            info!("Reloading CSS");
-            provider.load_from_file(&gio::File::for_path(path));
-        });
+/// Loads the stylesheet at `path` into the provider,
+/// compiling it first if it is SCSS.
+///
+/// If compilation fails, the previously loaded styles are kept.
+fn load_stylesheet(provider: &CssProvider, path: &Path) {
+    #[cfg(feature = "scss")]
+    if is_scss(path) {
+        let options = grass::Options::default().load_path(path.parent().unwrap_or(path));
+
+        match grass::from_path(path, &options) {
+            Ok(css) => provider.load_from_string(&css),
+            Err(err) => error!("Failed to compile '{}': {err}", path.display()),
+        }
+
+        return;
+    }
+
+    provider.load_from_file(&gio::File::for_path(path));
+}
+
+fn is_scss(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "scss")
+}
+
+/// Updates the set of watched files to the stylesheet at `style_path`
+/// and every file it imports,
+/// watching any directories containing those files not already watched.
+fn watch_stylesheets(
+    watcher: &mut impl Watcher,
+    style_path: &Path,
+    watched: &Arc<Mutex<HashSet<PathBuf>>>,
+    watched_dirs: &mut HashSet<PathBuf>,
+) {
+    let mut files = HashSet::new();
+    collect_stylesheets(style_path, &mut files);
+
+    for dir_path in files.iter().filter_map(|path| path.parent()) {
+        if watched_dirs.contains(dir_path) {
+            continue;
+        }
+
+        match watcher.watch(dir_path, RecursiveMode::NonRecursive) {
+            Ok(()) => {
+                debug!("Installed CSS file watcher on '{}'", dir_path.display());
+                watched_dirs.insert(dir_path.to_path_buf());
+            }
+            Err(err) => error!(
+                "Failed to start CSS file watcher on '{}': {err:?}",
+                dir_path.display()
+            ),
+        }
+    }
+
+    *lock!(watched) = files;
+}
+
+/// Adds the stylesheet at `path` to `files`,
+/// then recursively adds each local file it imports.
+fn collect_stylesheets(path: &Path, files: &mut HashSet<PathBuf>) {
+    if !files.insert(path.to_path_buf()) {
+        return;
+    }
+
+    let Ok(css) = fs::read_to_string(path) else {
+        return;
+    };
+
+    let dir_path = path.parent().unwrap_or(path);
+    let scss = is_scss(path);
+
+    for import in parse_imports(&css, scss) {
+        let import = import.strip_prefix("file://").unwrap_or(import);
+
+        // remote and resource imports cannot be watched
+        if import.contains("://") {
+            continue;
+        }
+
+        let import_path = dir_path.join(import);
+
+        if scss {
+            if let Some(import_path) = resolve_scss_import(&import_path) {
+                collect_stylesheets(&import_path, files);
+            }
+        } else {
+            collect_stylesheets(&import_path, files);
+        }
+    }
+}
+
+/// Resolves an SCSS import to a file,
+/// following the Sass rules for omitted extensions, partials and index files.
+fn resolve_scss_import(path: &Path) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_string_lossy();
+
+    let candidates = [
+        path.to_path_buf(),
+        path.with_file_name(format!("{file_name}.scss")),
+        path.with_file_name(format!("_{file_name}.scss")),
+        path.with_file_name(format!("{file_name}.css")),
+        path.join("_index.scss"),
+        path.join("index.scss"),
+    ];
+
+    candidates.into_iter().find(|path| path.is_file())
+}
+
+/// Gets the location of each `@import` rule in a stylesheet.
+///
+/// Both `@import "file.css";` and `@import url(file.css);` forms are supported.
+/// For SCSS, `@use` and `@forward` rules are also included.
+fn parse_imports(css: &str, scss: bool) -> Vec<&str> {
+    let keywords: &[&str] = if scss {
+        &["@import", "@use", "@forward"]
+    } else {
+        &["@import"]
+    };
+
+    keywords
+        .iter()
+        .flat_map(|keyword| css.match_indices(keyword))
+        .filter_map(|(index, keyword)| {
+            let rule = css[index + keyword.len()..].trim_start();
+
+            let (rule, is_url) = match rule.strip_prefix("url(") {
+                Some(rule) => (rule.trim_start(), true),
+                None => (rule, false),
+            };
+
+            match rule.chars().next()? {
+                quote @ ('"' | '\'') => {
+                    let rule = &rule[1..];
+                    rule.find(quote).map(|end| &rule[..end])
+                }
+                _ if is_url => rule.find(')').map(|end| rule[..end].trim_end()),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_import_rules() {
+        let css = r#"
+            @import "colors.css";
+            @import url('modules/clock.css');
+            @import url( "bar.css" );
+            .clock { color: red; }
+        "#;
+
+        assert_eq!(
+            parse_imports(css, false),
+            vec!["colors.css", "modules/clock.css", "bar.css"]
+        );
+    }
+
+    #[test]
+    fn parse_unquoted_import() {
+        assert_eq!(
+            parse_imports("@import url(colors.css);", false),
+            vec!["colors.css"]
+        );
+        assert!(parse_imports("@import colors.css;", false).is_empty());
+    }
+
+    #[test]
+    fn parse_scss_imports() {
+        let css = r#"
+            @use "sass:math";
+            @use "colors" as c;
+            @forward 'mixins';
+        "#;
+
+        assert_eq!(
+            parse_imports(css, true),
+            vec!["sass:math", "colors", "mixins"]
+        );
+        assert!(parse_imports(css, false).is_empty());
     }
 }
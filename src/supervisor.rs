@@ -0,0 +1,131 @@
+use crate::error::ExitCode;
+use crate::ironvar::IronvarValue;
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, exit};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+/// Environment variable passing the ironvar state file to the supervised bar.
+const STATE_FILE_ENV: &str = "IRONBAR_STATE_FILE";
+
+/// How often to check whether the Wayland socket has returned.
+const SOCKET_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The supervisor gives up if the bar fails this many times within [`CRASH_WINDOW`]
+/// while the compositor is still running.
+const MAX_CRASHES: usize = 5;
+const CRASH_WINDOW: Duration = Duration::from_secs(60);
+
+/// Runs the bar in a child process,
+/// restarting it whenever it exits because the Wayland connection was lost.
+///
+/// Ironvars are saved to a state file while the bar runs,
+/// and restored when it is restarted.
+pub fn run() -> ! {
+    let exe = env::current_exe().unwrap_or_else(|err| {
+        error!("Failed to get ironbar executable path: {err:?}");
+        exit(ExitCode::CliError as i32)
+    });
+
+    let args = env::args_os()
+        .skip(1)
+        .filter(|arg| arg != "--supervise")
+        .collect::<Vec<_>>();
+
+    let state_file = env::temp_dir().join(format!("ironbar-{}.vars", std::process::id()));
+
+    // the bar handles the signal itself and exits cleanly,
+    // so the supervisor only needs to wait for it.
+    if let Err(err) = ctrlc::set_handler(|| {}) {
+        warn!("Failed to set Ctrl-C handler: {err:?}");
+    }
+
+    let mut crashes = VecDeque::new();
+
+    let code = loop {
+        let status = Command::new(&exe)
+            .args(&args)
+            .env(STATE_FILE_ENV, &state_file)
+            .status();
+
+        let status = match status {
+            Ok(status) if status.success() => break 0,
+            Ok(status) => status,
+            Err(err) => {
+                error!("Failed to start bar: {err:?}");
+                break ExitCode::CliError as i32;
+            }
+        };
+
+        let code = status.code().unwrap_or(1);
+
+        match wayland_socket() {
+            Some(socket) if !socket.exists() => {
+                info!("Wayland connection lost, waiting for the compositor to return");
+                wait_for(&socket);
+            }
+            _ => {
+                let now = Instant::now();
+                crashes.retain(|time| now.duration_since(*time) < CRASH_WINDOW);
+                crashes.push_back(now);
+
+                if crashes.len() >= MAX_CRASHES {
+                    error!("Bar exited with code {code} too many times, giving up");
+                    break code;
+                }
+
+                warn!("Bar exited with code {code}");
+            }
+        }
+
+        info!("Restarting bar");
+    };
+
+    let _ = fs::remove_file(&state_file);
+    exit(code)
+}
+
+/// Gets the path to the Wayland socket the bar connects to.
+fn wayland_socket() -> Option<PathBuf> {
+    let display = env::var_os("WAYLAND_DISPLAY").unwrap_or_else(|| OsString::from("wayland-0"));
+    let display = PathBuf::from(display);
+
+    if display.is_absolute() {
+        Some(display)
+    } else {
+        env::var_os("XDG_RUNTIME_DIR").map(|dir| PathBuf::from(dir).join(display))
+    }
+}
+
+/// Blocks until the path exists.
+fn wait_for(path: &Path) {
+    while !path.exists() {
+        sleep(SOCKET_POLL_INTERVAL);
+    }
+
+    debug!("Wayland socket '{}' is back", path.display());
+}
+
+/// Gets the file ironvars are saved to while the bar runs,
+/// if it is running under the supervisor.
+pub fn state_file() -> Option<PathBuf> {
+    env::var_os(STATE_FILE_ENV).map(PathBuf::from)
+}
+
+/// Loads the ironvars saved by a previous run of the bar,
+/// if it is running under the supervisor.
+pub fn load_vars() -> HashMap<String, IronvarValue> {
+    let Some(path) = state_file() else {
+        return HashMap::new();
+    };
+
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
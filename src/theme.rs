@@ -0,0 +1,448 @@
+use crate::channels::{AsyncSenderExt, MpscReceiverExt};
+use crate::config::{ColorScheme, Palette, ThemeConfig};
+use gtk::CssProvider;
+use gtk::ffi::GTK_STYLE_PROVIDER_PRIORITY_APPLICATION;
+use gtk::gdk::RGBA;
+use gtk::prelude::*;
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher, recommended_watcher};
+use serde::Deserialize;
+use std::cell::{Cell, OnceCell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info};
+
+/// Injects the configured theme tokens into the stylesheet as CSS variables,
+/// switching between the light and dark palettes with the color scheme.
+#[derive(Debug, Clone, Default)]
+pub struct Theme {
+    inner: Rc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    config: RefCell<ThemeConfig>,
+    provider: OnceCell<CssProvider>,
+    /// Whether the system prefers a dark color scheme.
+    prefers_dark: Cell<bool>,
+    watching: Cell<bool>,
+    /// Sends when a palette file changes.
+    palette_tx: OnceCell<mpsc::Sender<()>>,
+    palette_watcher: RefCell<Option<RecommendedWatcher>>,
+}
+
+/// The contents of a palette file.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum PaletteFile {
+    /// A pywal `colors.json` file.
+    Wal {
+        special: HashMap<String, String>,
+        colors: HashMap<String, String>,
+    },
+    /// A matugen `--json hex` output file.
+    Matugen {
+        colors: MatugenColors,
+    },
+    Palette(Palette),
+}
+
+/// The generated colors in a matugen JSON file.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum MatugenColors {
+    /// Colors grouped by scheme, as `colors.<scheme>.<name>`.
+    ByScheme {
+        light: HashMap<String, String>,
+        dark: HashMap<String, String>,
+    },
+    /// Colors grouped by name, as `colors.<name>.<scheme>`.
+    ByName(HashMap<String, MatugenColor>),
+}
+
+#[derive(Debug, Deserialize)]
+struct MatugenColor {
+    light: String,
+    dark: String,
+}
+
+impl From<PaletteFile> for Palette {
+    fn from(file: PaletteFile) -> Self {
+        match file {
+            PaletteFile::Wal { special, colors } => Palette {
+                tokens: special.into_iter().chain(colors).collect(),
+                ..Palette::default()
+            },
+            PaletteFile::Matugen {
+                colors: MatugenColors::ByScheme { light, dark },
+            } => Palette {
+                light,
+                dark,
+                ..Palette::default()
+            },
+            PaletteFile::Matugen {
+                colors: MatugenColors::ByName(colors),
+            } => {
+                let mut palette = Palette::default();
+
+                for (name, color) in colors {
+                    palette.light.insert(name.clone(), color.light);
+                    palette.dark.insert(name, color.dark);
+                }
+
+                palette
+            }
+            PaletteFile::Palette(palette) => palette,
+        }
+    }
+}
+
+impl Theme {
+    /// Applies the theme config, replacing any previous config.
+    ///
+    /// This must be called after GTK is initialized.
+    pub fn apply(&self, config: ThemeConfig) {
+        let watch = config.color_scheme == ColorScheme::Auto;
+        let palette_files = config.palette_files();
+        self.inner.config.replace(config);
+
+        if watch && !self.inner.watching.replace(true) {
+            self.watch_color_scheme();
+        }
+
+        self.watch_palette_files(palette_files);
+
+        self.reload();
+    }
+
+    /// Regenerates the CSS variables from the current config.
+    pub fn reload(&self) {
+        let config = self.inner.config.borrow();
+
+        let mut palette = config.palette.clone();
+        for path in config.palette_files() {
+            match load_palette(&path) {
+                Ok(file_palette) => palette.merge(file_palette),
+                Err(err) => error!("failed to load palette '{}': {err:?}", path.display()),
+            }
+        }
+
+        let dark = match config.color_scheme {
+            ColorScheme::Auto => self.inner.prefers_dark.get(),
+            ColorScheme::Light => false,
+            ColorScheme::Dark => true,
+        };
+
+        debug!(
+            "loading {} theme tokens",
+            if dark { "dark" } else { "light" }
+        );
+
+        let css = to_css(&palette.resolve(dark));
+        self.provider().load_from_string(&css);
+    }
+
+    fn provider(&self) -> &CssProvider {
+        self.inner.provider.get_or_init(|| {
+            let provider = CssProvider::new();
+
+            // Deprecation warning is an error in gtk-rs bindings
+            // <https://github.com/gtk-rs/gtk4-rs/pull/2161>
+            #[allow(deprecated)]
+            gtk::StyleContext::add_provider_for_display(
+                &crate::get_display(),
+                &provider,
+                GTK_STYLE_PROVIDER_PRIORITY_APPLICATION as u32,
+            );
+
+            provider
+        })
+    }
+
+    fn set_prefers_dark(&self, dark: bool) {
+        if self.inner.prefers_dark.replace(dark) != dark {
+            self.reload();
+        }
+    }
+
+    /// Installs a file watcher on each palette file,
+    /// reloading the tokens when any of them change.
+    /// This replaces the watcher for any previous palette files.
+    fn watch_palette_files(&self, paths: Vec<PathBuf>) {
+        self.inner.palette_watcher.take();
+
+        if paths.is_empty() {
+            return;
+        }
+
+        let tx = self
+            .inner
+            .palette_tx
+            .get_or_init(|| {
+                let (tx, rx) = mpsc::channel(8);
+
+                let theme = self.clone();
+                rx.recv_glib((), move |(), ()| {
+                    info!("Reloading theme palette");
+                    theme.reload();
+                });
+
+                tx
+            })
+            .clone();
+
+        // file watcher requires absolute paths
+        let paths = paths
+            .into_iter()
+            .map(|path| {
+                if path.is_absolute() {
+                    path
+                } else {
+                    env::current_dir().expect("to exist").join(path)
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let watched = paths.clone();
+        let watcher = recommended_watcher(move |res: notify::Result<Event>| match res {
+            // palette generators often replace the file rather than writing to it
+            Ok(event) if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) => {
+                if event.paths.iter().any(|path| watched.contains(path)) {
+                    tx.send_spawn(());
+                }
+            }
+            Err(err) => error!("Error occurred when watching palette: {err:?}"),
+            _ => {}
+        });
+
+        let mut watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(err) => {
+                error!("Failed to create palette file watcher: {err:?}");
+                return;
+            }
+        };
+
+        for path in &paths {
+            let Some(dir_path) = path.parent() else {
+                continue;
+            };
+
+            match watcher.watch(dir_path, RecursiveMode::NonRecursive) {
+                Ok(()) => debug!("Installed palette file watcher on '{}'", path.display()),
+                Err(err) => error!(
+                    "Failed to start palette file watcher on '{}': {err:?}",
+                    path.display()
+                ),
+            }
+        }
+
+        self.inner.palette_watcher.replace(Some(watcher));
+    }
+
+    /// Follows the freedesktop color-scheme setting using the settings portal,
+    /// falling back to GTK's dark theme preference where it is unavailable.
+    fn watch_color_scheme(&self) {
+        let settings = gtk::Settings::default();
+
+        if let Some(settings) = &settings {
+            self.inner
+                .prefers_dark
+                .set(settings.is_gtk_application_prefer_dark_theme());
+        }
+
+        cfg_if::cfg_if! {
+            if #[cfg(feature = "color_scheme")] {
+                let (tx, rx) = mpsc::channel(4);
+
+                crate::spawn(async move {
+                    if let Err(err) = portal::watch(tx).await {
+                        debug!("color scheme portal unavailable: {err:?}");
+                    }
+                });
+
+                let theme = self.clone();
+                rx.recv_glib((), move |(), dark| theme.set_prefers_dark(dark));
+            } else {
+                if let Some(settings) = settings {
+                    let theme = self.clone();
+                    settings.connect_gtk_application_prefer_dark_theme_notify(move |settings| {
+                        theme.set_prefers_dark(settings.is_gtk_application_prefer_dark_theme());
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Loads a palette file in any supported config format.
+fn load_palette(path: &Path) -> color_eyre::Result<Palette> {
+    let palette = config::Config::builder()
+        .add_source(config::File::from(path))
+        .build()?
+        .try_deserialize::<PaletteFile>()?;
+
+    Ok(palette.into())
+}
+
+/// Generates a stylesheet declaring each token as a CSS variable.
+///
+/// Tokens which are colors are also declared using `@define-color`,
+/// as CSS variables are only supported from GTK 4.16.
+fn to_css(tokens: &BTreeMap<&str, &str>) -> String {
+    let mut css = String::new();
+
+    for (key, value) in tokens {
+        if RGBA::parse(*value).is_ok() {
+            let _ = writeln!(css, "@define-color {key} {value};");
+        }
+    }
+
+    css.push_str(":root {\n");
+
+    for (key, value) in tokens {
+        let _ = writeln!(css, "  --{key}: {value};");
+    }
+
+    css.push('}');
+    css
+}
+
+#[cfg(feature = "color_scheme")]
+mod portal {
+    use crate::channels::AsyncSenderExt;
+    use tokio::sync::mpsc;
+    use tracing::debug;
+    use zbus::export::ordered_stream::OrderedStreamExt;
+    use zbus::proxy;
+    use zbus::zvariant::OwnedValue;
+
+    const NAMESPACE: &str = "org.freedesktop.appearance";
+    const KEY: &str = "color-scheme";
+
+    /// The `color-scheme` value for a dark preference.
+    /// `0` is no preference, and `2` is a light preference.
+    const PREFER_DARK: u32 = 1;
+
+    #[proxy(
+        interface = "org.freedesktop.portal.Settings",
+        default_service = "org.freedesktop.portal.Desktop",
+        default_path = "/org/freedesktop/portal/desktop"
+    )]
+    trait Settings {
+        fn read_one(&self, namespace: &str, key: &str) -> zbus::Result<OwnedValue>;
+
+        #[zbus(signal)]
+        fn setting_changed(
+            &self,
+            namespace: &str,
+            key: &str,
+            value: OwnedValue,
+        ) -> zbus::Result<()>;
+    }
+
+    /// Sends whether the dark color scheme is preferred,
+    /// initially and each time the setting changes.
+    pub async fn watch(tx: mpsc::Sender<bool>) -> zbus::Result<()> {
+        let dbus = crate::clients::dbus::session().await?;
+        let proxy = SettingsProxy::new(&dbus).await?;
+
+        let mut stream = proxy.receive_setting_changed().await?;
+
+        let value = proxy.read_one(NAMESPACE, KEY).await?;
+        tx.send_expect(u32::try_from(value)? == PREFER_DARK).await;
+
+        while let Some(signal) = stream.next().await {
+            let args = signal.args()?;
+            if args.namespace == NAMESPACE && args.key == KEY {
+                let dark = u32::try_from(args.value)? == PREFER_DARK;
+                debug!("color scheme changed, prefers dark: {dark}");
+                tx.send_expect(dark).await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn resolve_scheme() {
+        let palette = Palette {
+            tokens: tokens(&[("accent", "red"), ("radius", "4px")]),
+            light: tokens(&[("accent", "blue")]),
+            dark: tokens(&[("accent", "green")]),
+        };
+
+        assert_eq!(
+            to_css(&palette.resolve(true)),
+            "@define-color accent green;\n:root {\n  --accent: green;\n  --radius: 4px;\n}"
+        );
+        assert_eq!(
+            to_css(&palette.resolve(false)),
+            "@define-color accent blue;\n:root {\n  --accent: blue;\n  --radius: 4px;\n}"
+        );
+    }
+
+    #[test]
+    fn merge_keeps_existing() {
+        let mut palette = Palette {
+            tokens: tokens(&[("accent", "red")]),
+            ..Palette::default()
+        };
+
+        palette.merge(Palette {
+            tokens: tokens(&[("accent", "blue"), ("radius", "4px")]),
+            ..Palette::default()
+        });
+
+        assert_eq!(
+            palette.tokens,
+            tokens(&[("accent", "red"), ("radius", "4px")])
+        );
+    }
+
+    #[test]
+    fn wal_palette() {
+        let file = PaletteFile::Wal {
+            special: tokens(&[("background", "#000000")]),
+            colors: tokens(&[("color1", "#ff0000")]),
+        };
+
+        let palette = Palette::from(file);
+        assert_eq!(
+            palette.tokens,
+            tokens(&[("background", "#000000"), ("color1", "#ff0000")])
+        );
+    }
+
+    #[test]
+    fn matugen_palette() {
+        let file = PaletteFile::Matugen {
+            colors: MatugenColors::ByName(HashMap::from([(
+                "primary".to_string(),
+                MatugenColor {
+                    light: "#415f91".to_string(),
+                    dark: "#aac7ff".to_string(),
+                },
+            )])),
+        };
+
+        let palette = Palette::from(file);
+        assert_eq!(palette.light, tokens(&[("primary", "#415f91")]));
+        assert_eq!(palette.dark, tokens(&[("primary", "#aac7ff")]));
+    }
+}